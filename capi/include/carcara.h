@@ -0,0 +1,91 @@
+/*
+ * C API for Carcara, a proof checker for the Alethe format.
+ *
+ * All strings passed to these functions must be NUL-terminated and valid UTF-8. The strings in a
+ * `CarcaraResult` are owned by the library, and must be released by calling `carcara_result_free`
+ * once, after which they must no longer be used.
+ */
+
+#ifndef CARCARA_H
+#define CARCARA_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The outcome of a call to `carcara_check`. These values won't change between versions. */
+typedef enum {
+    /* The proof is valid. */
+    CARCARA_VALID = 0,
+    /* The proof is valid, but contains holes, like `hole` steps or `lia_generic` steps. */
+    CARCARA_HOLEY = 1,
+    /* The problem or proof could not be parsed. */
+    CARCARA_PARSER_ERROR = 2,
+    /* The proof was parsed, but a step in it is invalid. */
+    CARCARA_CHECKER_ERROR = 3,
+    /* The proof is invalid for some other reason, e.g. it does not reach the empty clause. */
+    CARCARA_INVALID_PROOF = 4,
+    /* An argument is a null pointer, is not valid UTF-8, or has an unknown value. */
+    CARCARA_INVALID_ARGUMENT = 5,
+    /* The checker panicked. This is always a bug in Carcara. */
+    CARCARA_INTERNAL_ERROR = 6,
+} CarcaraStatus;
+
+/* The values of `CarcaraOptions.int_real_subtyping`. */
+#define CARCARA_INT_REAL_SUBTYPING_STRICT 0
+#define CARCARA_INT_REAL_SUBTYPING_IMPLICIT 1
+#define CARCARA_INT_REAL_SUBTYPING_TO_REAL 2
+
+/*
+ * The options used when parsing and checking a proof. These should be initialized by calling
+ * `carcara_default_options`, and then changing the relevant fields.
+ */
+typedef struct {
+    bool apply_function_defs;
+    bool expand_lets;
+    bool strict;
+    bool check_sorts;
+    uint32_t int_real_subtyping;
+    bool elaborated;
+    bool ignore_unknown_rules;
+} CarcaraOptions;
+
+/*
+ * The result of a call to `carcara_check`. If the proof is valid, all strings are null and the
+ * line and column are zero.
+ */
+typedef struct {
+    CarcaraStatus status;
+    /* The error message, or null if there was no error. */
+    char *message;
+    /* The id of the step where checking failed, or null. */
+    char *step;
+    /* The position in the proof file where the error happened, starting at one, or zero. */
+    size_t line;
+    size_t column;
+} CarcaraResult;
+
+/* Returns the default options. */
+CarcaraOptions carcara_default_options(void);
+
+/*
+ * Checks a proof, given the contents of the problem and proof files. If `options` is null, the
+ * default options are used. The result must be released with `carcara_result_free`.
+ */
+CarcaraResult carcara_check(const char *problem, const char *proof, const CarcaraOptions *options);
+
+/*
+ * Releases the strings in a result, and sets them to null. Calling this again on the same result,
+ * or with a null pointer, does nothing.
+ */
+void carcara_result_free(CarcaraResult *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CARCARA_H */
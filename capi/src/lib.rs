@@ -0,0 +1,329 @@
+//! A C API for Carcara, so the checker can be called in-process, for example by an SMT solver that
+//! checks its own proofs. The declarations for C are in `include/carcara.h`.
+//!
+//! All strings passed to these functions must be NUL-terminated and valid UTF-8. Strings returned
+//! in a [`CarcaraResult`] are owned by the library, and must be released by calling
+//! [`carcara_result_free`] once, after which they must no longer be used.
+
+#![deny(clippy::undocumented_unsafe_blocks)]
+
+use carcara::{checker, parser, Error};
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+/// The outcome of a call to [`carcara_check`]. These values are part of the C ABI, and won't
+/// change between versions.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarcaraStatus {
+    /// The proof is valid.
+    Valid = 0,
+
+    /// The proof is valid, but contains holes, like `hole` steps or `lia_generic` steps.
+    Holey = 1,
+
+    /// The problem or proof could not be parsed.
+    ParserError = 2,
+
+    /// The proof was parsed, but a step in it is invalid.
+    CheckerError = 3,
+
+    /// The proof is invalid for some other reason, for example because it does not reach the empty
+    /// clause.
+    InvalidProof = 4,
+
+    /// One of the arguments is a null pointer, is not valid UTF-8, or has an unknown value.
+    InvalidArgument = 5,
+
+    /// The checker panicked. This is always a bug in Carcara.
+    InternalError = 6,
+}
+
+/// The values of [`CarcaraOptions::int_real_subtyping`].
+pub const CARCARA_INT_REAL_SUBTYPING_STRICT: u32 = 0;
+pub const CARCARA_INT_REAL_SUBTYPING_IMPLICIT: u32 = 1;
+pub const CARCARA_INT_REAL_SUBTYPING_TO_REAL: u32 = 2;
+
+/// The options used when parsing and checking a proof. See the documentation of
+/// [`parser::Config`] and [`checker::Config`] for what each of them means. These should be
+/// initialized by calling [`carcara_default_options`], and then changing the relevant fields.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CarcaraOptions {
+    pub apply_function_defs: bool,
+    pub expand_lets: bool,
+    pub strict: bool,
+    pub check_sorts: bool,
+
+    /// One of the `CARCARA_INT_REAL_SUBTYPING_*` constants.
+    pub int_real_subtyping: u32,
+
+    pub elaborated: bool,
+    pub ignore_unknown_rules: bool,
+}
+
+impl CarcaraOptions {
+    fn to_configs(self) -> Option<(parser::Config, checker::Config)> {
+        let int_real_subtyping = match self.int_real_subtyping {
+            CARCARA_INT_REAL_SUBTYPING_STRICT => parser::IntRealSubtyping::Strict,
+            CARCARA_INT_REAL_SUBTYPING_IMPLICIT => parser::IntRealSubtyping::Implicit,
+            CARCARA_INT_REAL_SUBTYPING_TO_REAL => parser::IntRealSubtyping::ToReal,
+            _ => return None,
+        };
+        let parser_config = parser::Config {
+            apply_function_defs: self.apply_function_defs,
+            expand_lets: self.expand_lets,
+            int_real_subtyping,
+            strict: self.strict,
+            check_sorts: self.check_sorts,
+            track_locations: true,
+            ..parser::Config::new()
+        };
+        let checker_config = checker::Config::new()
+            .elaborated(self.elaborated)
+            .ignore_unknown_rules(self.ignore_unknown_rules);
+        Some((parser_config, checker_config))
+    }
+}
+
+/// The result of a call to [`carcara_check`]. If the proof is valid, all strings are null and the
+/// line and column are zero.
+#[repr(C)]
+#[derive(Debug)]
+pub struct CarcaraResult {
+    pub status: CarcaraStatus,
+
+    /// The error message, or null if there was no error.
+    pub message: *mut c_char,
+
+    /// The id of the step where checking failed, or null if the error didn't happen in a specific
+    /// step.
+    pub step: *mut c_char,
+
+    /// The position in the proof file where the error happened, or zero if it is not known. Lines
+    /// and columns start at one.
+    pub line: usize,
+    pub column: usize,
+}
+
+impl CarcaraResult {
+    fn new(status: CarcaraStatus, message: Option<String>) -> Self {
+        Self {
+            status,
+            message: message.map_or(ptr::null_mut(), into_c_string),
+            step: ptr::null_mut(),
+            line: 0,
+            column: 0,
+        }
+    }
+
+    fn from_error(error: Error) -> Self {
+        let status = match &error {
+            Error::Parser(..) => CarcaraStatus::ParserError,
+            Error::Checker { .. } => CarcaraStatus::CheckerError,
+            _ => CarcaraStatus::InvalidProof,
+        };
+        let (step, position) = match &error {
+            Error::Parser(_, pos) => (None, Some(*pos)),
+            Error::Checker { step, location, .. } => {
+                (Some(step.clone()), location.as_ref().map(|l| l.span.start))
+            }
+            _ => (None, None),
+        };
+        let mut result = Self::new(status, Some(error.to_string()));
+        result.step = step.map_or(ptr::null_mut(), into_c_string);
+        (result.line, result.column) = position.unwrap_or((0, 0));
+        result
+    }
+}
+
+/// Converts a string into a C string owned by the caller, which must be freed with
+/// `CString::from_raw`. Interior NUL bytes, which can only come from the input files, are dropped.
+fn into_c_string(s: String) -> *mut c_char {
+    let mut bytes = s.into_bytes();
+    bytes.retain(|&b| b != 0);
+    CString::new(bytes).unwrap().into_raw()
+}
+
+/// Reads a C string given as an argument, returning `None` if it is null or not valid UTF-8.
+///
+/// # Safety
+///
+/// If `s` is not null, it must point to a NUL-terminated string that is valid for the lifetime
+/// `'a`.
+unsafe fn read_c_string<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    // SAFETY: the pointer is not null, and the caller guarantees that it points to a valid
+    // NUL-terminated string
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+/// Returns the default options.
+#[no_mangle]
+pub extern "C" fn carcara_default_options() -> CarcaraOptions {
+    let parser_config = parser::Config::new();
+    let checker_config = checker::Config::new();
+    CarcaraOptions {
+        apply_function_defs: parser_config.apply_function_defs,
+        expand_lets: parser_config.expand_lets,
+        strict: parser_config.strict,
+        check_sorts: parser_config.check_sorts,
+        int_real_subtyping: CARCARA_INT_REAL_SUBTYPING_STRICT,
+        elaborated: checker_config.elaborated,
+        ignore_unknown_rules: checker_config.ignore_unknown_rules,
+    }
+}
+
+/// Checks a proof, given the contents of the problem and proof files. If `options` is null, the
+/// default options are used. The returned result must be released with [`carcara_result_free`].
+///
+/// # Safety
+///
+/// `problem` and `proof` must point to NUL-terminated strings, and `options` must be null or point
+/// to a valid `CarcaraOptions` struct. These are only read during the call.
+#[no_mangle]
+pub unsafe extern "C" fn carcara_check(
+    problem: *const c_char,
+    proof: *const c_char,
+    options: *const CarcaraOptions,
+) -> CarcaraResult {
+    // SAFETY: the caller guarantees that the strings are valid during this call
+    let (problem, proof) = match unsafe { (read_c_string(problem), read_c_string(proof)) } {
+        (Some(problem), Some(proof)) => (problem, proof),
+        _ => {
+            let message = "problem and proof must be non-null UTF-8 strings".to_owned();
+            return CarcaraResult::new(CarcaraStatus::InvalidArgument, Some(message));
+        }
+    };
+    let options = if options.is_null() {
+        carcara_default_options()
+    } else {
+        // SAFETY: the pointer is not null, and the caller guarantees that it is valid
+        unsafe { *options }
+    };
+    let Some((parser_config, checker_config)) = options.to_configs() else {
+        let message = format!(
+            "unknown `int_real_subtyping` value: {}",
+            options.int_real_subtyping
+        );
+        return CarcaraResult::new(CarcaraStatus::InvalidArgument, Some(message));
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        carcara::check(
+            problem.as_bytes(),
+            proof.as_bytes(),
+            parser_config,
+            checker_config,
+            false,
+        )
+    }));
+    match result {
+        Ok(Ok(false)) => CarcaraResult::new(CarcaraStatus::Valid, None),
+        Ok(Ok(true)) => CarcaraResult::new(CarcaraStatus::Holey, None),
+        Ok(Err(e)) => CarcaraResult::from_error(e),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| (*s).to_owned())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "the checker panicked".to_owned());
+            CarcaraResult::new(CarcaraStatus::InternalError, Some(message))
+        }
+    }
+}
+
+/// Releases the strings in a result returned by [`carcara_check`], and sets them to null. Calling
+/// this again on the same result, or with a null pointer, does nothing.
+///
+/// # Safety
+///
+/// `result` must be null or point to a result returned by `carcara_check`, whose strings were not
+/// modified.
+#[no_mangle]
+pub unsafe extern "C" fn carcara_result_free(result: *mut CarcaraResult) {
+    // SAFETY: the caller guarantees that the pointer is null or valid
+    let Some(result) = (unsafe { result.as_mut() }) else {
+        return;
+    };
+    for s in [&mut result.message, &mut result.step] {
+        if !s.is_null() {
+            // SAFETY: the string was created by `into_c_string`, and was not freed yet, since it
+            // is set to null when freed
+            drop(unsafe { CString::from_raw(*s) });
+            *s = ptr::null_mut();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(problem: &str, proof: &str, options: Option<CarcaraOptions>) -> CarcaraResult {
+        let problem = CString::new(problem).unwrap();
+        let proof = CString::new(proof).unwrap();
+        let options = options.as_ref().map_or(ptr::null(), |o| o as *const _);
+        // SAFETY: the strings and options are valid during the call
+        unsafe { carcara_check(problem.as_ptr(), proof.as_ptr(), options) }
+    }
+
+    fn message(result: &CarcaraResult) -> &str {
+        // SAFETY: the message was created by `into_c_string`, and was not freed
+        unsafe { CStr::from_ptr(result.message) }.to_str().unwrap()
+    }
+
+    #[test]
+    fn test_carcara_check() {
+        let problem = "(declare-fun p () Bool) (assert p) (assert (not p))";
+
+        let proof =
+            "(assume h1 p) (assume h2 (not p)) (step t3 (cl) :rule resolution :premises (h1 h2))";
+        let mut result = check(problem, proof, None);
+        assert_eq!(result.status, CarcaraStatus::Valid);
+        assert!(result.message.is_null() && result.step.is_null());
+        // SAFETY: `result` was returned by `carcara_check`
+        unsafe { carcara_result_free(&mut result) };
+
+        let proof = "(assume h1 p) (step t2 (cl) :rule resolution :premises (h1 h1))";
+        let mut result = check(problem, proof, None);
+        assert_eq!(result.status, CarcaraStatus::CheckerError);
+        assert!(message(&result).contains("t2"));
+        // SAFETY: the step was created by `into_c_string`, and was not freed
+        let step = unsafe { CStr::from_ptr(result.step) };
+        assert_eq!(step.to_str(), Ok("t2"));
+        assert_eq!((result.line, result.column), (1, 15));
+        // SAFETY: `result` was returned by `carcara_check`. Freeing it twice does nothing
+        unsafe {
+            carcara_result_free(&mut result);
+            carcara_result_free(&mut result);
+        }
+        assert!(result.message.is_null() && result.step.is_null());
+
+        let mut result = check(problem, "(assume h1 q)", None);
+        assert_eq!(result.status, CarcaraStatus::ParserError);
+        assert_eq!((result.line, result.column), (1, 12));
+        // SAFETY: `result` was returned by `carcara_check`
+        unsafe { carcara_result_free(&mut result) };
+
+        let options = CarcaraOptions {
+            int_real_subtyping: 3,
+            ..carcara_default_options()
+        };
+        let mut result = check(problem, "", Some(options));
+        assert_eq!(result.status, CarcaraStatus::InvalidArgument);
+        // SAFETY: `result` was returned by `carcara_check`
+        unsafe { carcara_result_free(&mut result) };
+
+        // SAFETY: null strings are rejected before being read
+        let mut result = unsafe { carcara_check(ptr::null(), ptr::null(), ptr::null()) };
+        assert_eq!(result.status, CarcaraStatus::InvalidArgument);
+        // SAFETY: `result` was returned by `carcara_check`
+        unsafe { carcara_result_free(&mut result) };
+    }
+}
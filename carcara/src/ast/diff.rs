@@ -0,0 +1,178 @@
+use super::*;
+use std::{collections::HashMap, fmt};
+
+/// The differences between two proofs, as computed by [`diff_proofs`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofDiff {
+    /// The ids of commands that are the same in both proofs, but that have different ids. Each
+    /// pair contains the id in the first proof followed by the id in the second proof.
+    pub renamed: Vec<(String, String)>,
+
+    /// The ids of commands in the second proof that have no counterpart in the first proof.
+    pub added: Vec<String>,
+
+    /// The ids of commands in the first proof that have no counterpart in the second proof.
+    pub removed: Vec<String>,
+
+    /// The ids of commands that conclude the same clause in both proofs, but with a different rule,
+    /// arguments or premises. Each pair contains the id in the first proof followed by the id in
+    /// the second proof.
+    pub changed: Vec<(String, String)>,
+}
+
+impl ProofDiff {
+    /// Returns `true` if the two proofs are the same, modulo renaming of commands.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for ProofDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for id in &self.removed {
+            writeln!(f, "- {}", id)?;
+        }
+        for id in &self.added {
+            writeln!(f, "+ {}", id)?;
+        }
+        for (a, b) in &self.changed {
+            writeln!(f, "~ {} -> {}", a, b)?;
+        }
+        Ok(())
+    }
+}
+
+/// A proof command, flattened so that its premises are indices into the list of all commands.
+struct Entry<'a> {
+    id: &'a str,
+    depth: usize,
+    rule: &'a str,
+    clause: &'a [Rc<Term>],
+    args: &'a [Rc<Term>],
+    premises: Vec<usize>,
+}
+
+/// Flattens the commands of a proof into a list of entries. Subproofs themselves are not included,
+/// but the commands inside them are. `scopes` holds, for each enclosing scope, the index of the
+/// entry of each of its commands.
+fn flatten<'a>(
+    commands: &'a [ProofCommand],
+    scopes: &mut Vec<Vec<usize>>,
+    entries: &mut Vec<Entry<'a>>,
+) {
+    let depth = scopes.len();
+    scopes.push(Vec::with_capacity(commands.len()));
+    for command in commands {
+        let entry = match command {
+            ProofCommand::Assume { id, term } => Entry {
+                id,
+                depth,
+                rule: "assume",
+                clause: std::slice::from_ref(term),
+                args: &[],
+                premises: Vec::new(),
+            },
+            ProofCommand::Step(s) => Entry {
+                id: &s.id,
+                depth,
+                rule: &s.rule,
+                clause: &s.clause,
+                args: &s.args,
+                premises: (s.premises.iter().chain(&s.discharge))
+                    .map(|&(d, i)| scopes[d][i])
+                    .collect(),
+            },
+            ProofCommand::Subproof(s) => {
+                flatten(&s.commands, scopes, entries);
+
+                // The subproof is represented by the entry of its last step
+                let last = entries.len() - 1;
+                scopes[depth].push(last);
+                continue;
+            }
+        };
+        scopes[depth].push(entries.len());
+        entries.push(entry);
+    }
+    scopes.pop();
+}
+
+fn terms_equiv(a: &[Rc<Term>], b: &[Rc<Term>]) -> bool {
+    let mut comp = Polyeq::new().alpha_equiv(true);
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| comp.eq(a, b))
+}
+
+/// Structurally compares two proofs, modulo renaming of commands and alpha-equivalence of terms.
+///
+/// A command in `b` is considered the same as a command in `a` if they have the same depth, rule,
+/// arguments and conclusion, and if their premises are themselves the same commands. If there is
+/// no such command, but there is a command in `a` that concludes the same clause at the same depth,
+/// the command is considered changed. Each command in `a` is matched to at most one command in
+/// `b`, and commands are matched in order.
+pub fn diff_proofs(a: &Proof, b: &Proof) -> ProofDiff {
+    let mut entries_a = Vec::new();
+    flatten(&a.commands, &mut Vec::new(), &mut entries_a);
+    let mut entries_b = Vec::new();
+    flatten(&b.commands, &mut Vec::new(), &mut entries_b);
+
+    // To avoid comparing every pair of commands, we group the commands of `a` by their depth and
+    // the length of their conclusion
+    let mut candidates: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (i, e) in entries_a.iter().enumerate() {
+        candidates
+            .entry((e.depth, e.clause.len()))
+            .or_default()
+            .push(i);
+    }
+
+    let mut matched_a = vec![false; entries_a.len()];
+    let mut b_to_a: Vec<Option<usize>> = Vec::with_capacity(entries_b.len());
+    let mut diff = ProofDiff::default();
+    for entry in &entries_b {
+        let bucket = candidates
+            .get(&(entry.depth, entry.clause.len()))
+            .map_or(&[][..], Vec::as_slice);
+        let same_clause: Vec<usize> = bucket
+            .iter()
+            .copied()
+            .filter(|&i| !matched_a[i] && terms_equiv(entries_a[i].clause, entry.clause))
+            .collect();
+        let is_same = |i: usize| {
+            let other = &entries_a[i];
+            let same_premises = other.premises.len() == entry.premises.len()
+                && (other.premises.iter().zip(&entry.premises))
+                    .all(|(&p, &q)| b_to_a[q] == Some(p));
+            other.rule == entry.rule && terms_equiv(other.args, entry.args) && same_premises
+        };
+
+        let found = match same_clause.iter().copied().find(|&i| is_same(i)) {
+            Some(i) => {
+                if entries_a[i].id != entry.id {
+                    let pair = (entries_a[i].id.to_owned(), entry.id.to_owned());
+                    diff.renamed.push(pair);
+                }
+                Some(i)
+            }
+            None => {
+                let changed = same_clause.first().copied();
+                if let Some(i) = changed {
+                    let pair = (entries_a[i].id.to_owned(), entry.id.to_owned());
+                    diff.changed.push(pair);
+                } else {
+                    diff.added.push(entry.id.to_owned());
+                }
+                changed
+            }
+        };
+        if let Some(i) = found {
+            matched_a[i] = true;
+        }
+        b_to_a.push(found);
+    }
+
+    diff.removed = (entries_a.iter().zip(matched_a))
+        .filter(|(_, matched)| !matched)
+        .map(|(e, _)| e.id.to_owned())
+        .collect();
+    diff
+}
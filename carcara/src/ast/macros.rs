@@ -172,6 +172,8 @@ macro_rules! match_term {
     (@GET_VARIANT bbterm)   => { $crate::ast::Operator::BvBbTerm };
     (@GET_VARIANT bvult)    => { $crate::ast::Operator::BvULt };
     (@GET_VARIANT bvadd)    => { $crate::ast::Operator::BvAdd };
+    (@GET_VARIANT select)   => { $crate::ast::Operator::Select };
+    (@GET_VARIANT store)    => { $crate::ast::Operator::Store };
 
     (@GET_VARIANT extract)     => { $crate::ast::ParamOperator::BvExtract };
     (@GET_VARIANT bit_of)      => { $crate::ast::ParamOperator::BvBitOf };
@@ -181,6 +183,7 @@ macro_rules! match_term {
     (@GET_VARIANT strconcat) => { $crate::ast::Operator::StrConcat };
     (@GET_VARIANT strsubstr) => { $crate::ast::Operator::Substring };
     (@GET_VARIANT strlen)    => { $crate::ast::Operator::StrLen };
+    (@GET_VARIANT strtocode) => { $crate::ast::Operator::StrToCode };
 
     (@GET_VARIANT strinre)    => { $crate::ast::Operator::StrInRe };
     (@GET_VARIANT reinter)    => { $crate::ast::Operator::ReIntersection };
@@ -277,13 +280,13 @@ macro_rules! build_term {
 /// }
 /// ```
 macro_rules! impl_str_conversion_traits {
-    ($enum_name:ident { $($variant:ident: $str:literal),* $(,)? }) => {
+    ($enum_name:ident { $($variant:ident: $str:literal $(| $alias:literal)*),* $(,)? }) => {
         impl std::str::FromStr for $enum_name {
             type Err = ();
 
             fn from_str(s: &str) -> Result<Self, Self::Err> {
                 match s {
-                    $($str => Ok($enum_name::$variant),)*
+                    $($str $(| $alias)* => Ok($enum_name::$variant),)*
                     _ => Err(()),
                 }
             }
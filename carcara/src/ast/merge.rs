@@ -0,0 +1,175 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+
+/// Merges two proofs of the same problem into a single proof.
+///
+/// This is useful, for example, to combine a propositional skeleton with theory lemmas that were
+/// proved or elaborated separately. The commands of `lemmas` are added first, followed by the
+/// commands of `main`, so the conclusion of the merged proof is the conclusion of `main`. Steps in
+/// `lemmas` that conclude the empty clause are not included.
+///
+/// Both proofs must have been parsed using the same term pool, so identical terms are represented
+/// by the same pointer. At the root level, `assume` commands of the same term are shared, and any
+/// command that concludes a clause that was already derived by a previous command is replaced by
+/// it. In particular, a `hole` step in `main` is replaced by a step in `lemmas` that concludes the
+/// same clause. Steps in `lemmas` whose ids clash with an id in `main` are renamed.
+pub fn merge_proofs(main: &Proof, lemmas: &Proof) -> Proof {
+    let mut main_ids = HashSet::new();
+    collect_ids(&main.commands, &mut main_ids);
+
+    let mut merger = Merger {
+        commands: Vec::new(),
+        assumptions: HashMap::new(),
+        clauses: HashMap::new(),
+        used_ids: main_ids,
+    };
+
+    // All root-level `assume` commands are added before any other command
+    let mut lemmas_map = vec![None; lemmas.commands.len()];
+    let mut main_map = vec![None; main.commands.len()];
+    merger.add_commands(&lemmas.commands, &mut lemmas_map, true, true);
+    merger.add_commands(&main.commands, &mut main_map, false, true);
+    merger.add_commands(&lemmas.commands, &mut lemmas_map, true, false);
+    merger.add_commands(&main.commands, &mut main_map, false, false);
+
+    let mut constant_definitions = main.constant_definitions.clone();
+    for (name, value) in &lemmas.constant_definitions {
+        if !constant_definitions.iter().any(|(n, _)| n == name) {
+            constant_definitions.push((name.clone(), value.clone()));
+        }
+    }
+
+    let mut commands = merger.commands;
+    let mut next_context_id = 0;
+    renumber_contexts(&mut commands, &mut next_context_id);
+
+    Proof { constant_definitions, commands }
+}
+
+fn collect_ids(commands: &[ProofCommand], ids: &mut HashSet<String>) {
+    for c in commands {
+        ids.insert(c.id().to_owned());
+        if let ProofCommand::Subproof(s) = c {
+            collect_ids(&s.commands, ids);
+        }
+    }
+}
+
+/// Assigns a new context id to each subproof, in the order in which they appear in the proof.
+pub fn renumber_contexts(commands: &mut [ProofCommand], next_id: &mut usize) {
+    for c in commands {
+        if let ProofCommand::Subproof(s) = c {
+            s.context_id = *next_id;
+            *next_id += 1;
+            renumber_contexts(&mut s.commands, next_id);
+        }
+    }
+}
+
+struct Merger {
+    commands: Vec<ProofCommand>,
+
+    /// Maps each root-level `assume` term to the index of its command.
+    assumptions: HashMap<Rc<Term>, usize>,
+
+    /// Maps each clause concluded by a root-level command to the index of the command.
+    clauses: HashMap<Vec<Rc<Term>>, usize>,
+
+    /// The ids that are already in use, used to avoid clashes when renaming.
+    used_ids: HashSet<String>,
+}
+
+impl Merger {
+    /// Adds either only the `assume` commands or only the other commands of a proof to the merged
+    /// proof. `index_map` maps each command index in the original proof to its index in the merged
+    /// proof, and is `None` if the command was removed (or was not added yet).
+    fn add_commands(
+        &mut self,
+        commands: &[ProofCommand],
+        index_map: &mut [Option<usize>],
+        is_lemmas: bool,
+        assumes: bool,
+    ) {
+        for (old_index, command) in commands.iter().enumerate() {
+            if command.is_assume() != assumes {
+                continue;
+            }
+            let mut command = command.clone();
+            if is_lemmas && command.clause().is_empty() {
+                continue;
+            }
+            if let ProofCommand::Assume { term, .. } = &command {
+                if let Some(&i) = self.assumptions.get(term) {
+                    index_map[old_index] = Some(i);
+                    continue;
+                }
+            }
+            if let Some(&i) = self.clauses.get(command.clause()) {
+                index_map[old_index] = Some(i);
+                continue;
+            }
+
+            // If any of the root-level premises of this command was removed, the command must
+            // also be removed
+            if !remap_root_premises(&mut command, index_map) {
+                continue;
+            }
+            if is_lemmas {
+                self.rename_clashing_ids(&mut command);
+            }
+
+            let index = self.commands.len();
+            if let ProofCommand::Assume { term, .. } = &command {
+                self.assumptions.insert(term.clone(), index);
+            }
+            self.clauses.insert(command.clause().to_vec(), index);
+            self.commands.push(command);
+            index_map[old_index] = Some(index);
+        }
+    }
+
+    fn rename_clashing_ids(&mut self, command: &mut ProofCommand) {
+        let id = match command {
+            ProofCommand::Assume { id, .. } => id,
+            ProofCommand::Step(s) => &mut s.id,
+            ProofCommand::Subproof(s) => {
+                for c in &mut s.commands {
+                    self.rename_clashing_ids(c);
+                }
+                return;
+            }
+        };
+        if self.used_ids.contains(id.as_str()) {
+            let mut i = 1;
+            while self.used_ids.contains(&format!("{}_{}", id, i)) {
+                i += 1;
+            }
+            *id = format!("{}_{}", id, i);
+        }
+        self.used_ids.insert(id.clone());
+    }
+}
+
+/// Updates all references to root-level commands inside `command` using `index_map`. Returns
+/// `false` if any of the referenced commands was removed.
+pub fn remap_root_premises(command: &mut ProofCommand, index_map: &[Option<usize>]) -> bool {
+    match command {
+        ProofCommand::Assume { .. } => true,
+        ProofCommand::Step(s) => s
+            .premises
+            .iter_mut()
+            .chain(s.discharge.iter_mut())
+            .filter(|(depth, _)| *depth == 0)
+            .all(|(_, i)| match index_map[*i] {
+                Some(new) => {
+                    *i = new;
+                    true
+                }
+                None => false,
+            }),
+        ProofCommand::Subproof(s) => s
+            .commands
+            .iter_mut()
+            .all(|c| remap_root_premises(c, index_map)),
+    }
+}
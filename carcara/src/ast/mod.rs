@@ -5,7 +5,9 @@
 #[macro_use]
 mod macros;
 mod context;
+mod diff;
 mod iter;
+mod merge;
 mod node;
 mod polyeq;
 pub mod pool;
@@ -14,21 +16,31 @@ mod problem;
 mod proof;
 mod rc;
 mod substitution;
+mod template;
 mod term;
 #[cfg(test)]
 mod tests;
+pub mod visit;
 
 pub use context::{Context, ContextStack};
+pub use diff::{diff_proofs, ProofDiff};
 pub use iter::ProofIter;
+pub use merge::merge_proofs;
 pub use node::{ProofNode, StepNode, SubproofNode};
 pub use polyeq::{alpha_equiv, polyeq, Polyeq, PolyeqComparable, PolyeqConfig};
-pub use pool::{PrimitivePool, TermPool};
-pub use printer::{print_proof, USE_SHARING_IN_TERM_DISPLAY};
+pub use pool::{FreshNameScheme, PrimitivePool, TermPool, TermPoolView};
+#[cfg(feature = "smt-printer")]
+pub use printer::write_smt_assertions;
+pub use printer::{
+    print_proof, write_proof, PrintOptions, SharingMode, USE_SHARING_IN_TERM_DISPLAY,
+};
 pub use problem::*;
 pub use proof::*;
 pub use rc::Rc;
 pub use substitution::{Substitution, SubstitutionError};
+pub use template::{apply_step_templates, StepTemplate, TemplateError};
 pub use term::{Binder, BindingList, Constant, Operator, ParamOperator, Sort, SortedVar, Term};
 
+pub(crate) use merge::{remap_root_premises, renumber_contexts};
 #[cfg(test)]
 pub(crate) use node::compare_nodes;
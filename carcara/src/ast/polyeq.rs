@@ -12,8 +12,11 @@ use rug::Rational;
 use super::{
     AnchorArg, BindingList, Constant, Operator, ProofCommand, ProofStep, Rc, Sort, Subproof, Term,
 };
-use crate::utils::HashMapStack;
-use std::time::{Duration, Instant};
+use crate::{
+    term_trace::{self, EqualityKind},
+    utils::{HashMapStack, Instant},
+};
+use std::time::Duration;
 
 /// An helper enum that allow a construction of lists with easy differentiation over the nature of the term
 /// (String constant or other). Therefore, is easy to manipulate, attach and detach terms of lists of
@@ -49,7 +52,9 @@ pub trait PolyeqComparable {
 ///
 /// This function records how long it takes to run, and adds that duration to the `time` argument.
 pub fn polyeq(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> bool {
-    Polyeq::new().mod_reordering(true).eq_with_time(a, b, time)
+    let result = Polyeq::new().mod_reordering(true).eq_with_time(a, b, time);
+    term_trace::equality(EqualityKind::Polyeq, a, b, result);
+    result
 }
 
 /// Similar to `polyeq`, but instead compares terms for alpha equivalence.
@@ -61,10 +66,12 @@ pub fn polyeq(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> bool {
 ///
 /// This function records how long it takes to run, and adds that duration to the `time` argument.
 pub fn alpha_equiv(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> bool {
-    Polyeq::new()
+    let result = Polyeq::new()
         .mod_reordering(true)
         .alpha_equiv(true)
-        .eq_with_time(a, b, time)
+        .eq_with_time(a, b, time);
+    term_trace::equality(EqualityKind::Alpha, a, b, result);
+    result
 }
 
 /// Configuration for a `Polyeq`.
@@ -471,8 +478,15 @@ impl PolyeqComparable for Rc<Term> {
         // even though both instances of `(< x y)` are identical, they are not alpha-equivalent.
         //
         // To account for that, if we are checking for alpha-equivalence and have encountered at
-        // least one binder, we don't apply this optimization
-        let possibly_renamed = comp.de_bruijn_map.as_ref().is_some_and(|m| !m.is_empty());
+        // least one binder that binds a variable to a different name in each term, we don't apply
+        // this optimization. If every pair of enclosing binders uses the same names, identical
+        // terms are still alpha-equivalent. This is the common case when the term pool adds binder
+        // terms modulo alpha-equivalence, since alpha-equivalent subterms then share the same
+        // bound variable names
+        let possibly_renamed = comp
+            .de_bruijn_map
+            .as_ref()
+            .is_some_and(|m| !m.is_identity());
         if !possibly_renamed && a == b {
             return true;
         }
@@ -629,12 +643,16 @@ impl PolyeqComparable for Sort {
             | (Sort::Real, Sort::Real)
             | (Sort::String, Sort::String)
             | (Sort::RegLan, Sort::RegLan)
+            | (Sort::RoundingMode, Sort::RoundingMode)
             | (Sort::RareList, Sort::RareList)
             | (Sort::Type, Sort::Type) => true,
             (Sort::Array(x_a, y_a), Sort::Array(x_b, y_b)) => {
                 comp.eq(x_a, x_b) && comp.eq(y_a, y_b)
             }
             (Sort::BitVec(a), Sort::BitVec(b)) => a == b,
+            (Sort::FloatingPoint(e_a, s_a), Sort::FloatingPoint(e_b, s_b)) => {
+                e_a == e_b && s_a == s_b
+            }
             _ => false,
         }
     }
@@ -736,6 +754,10 @@ struct DeBruijnMap {
 
     // Holds the count of how many variables were bound before each depth
     counter: Vec<usize>,
+
+    // Holds the count of how many variables were bound to different names in each term, up to
+    // each depth
+    renamed: Vec<usize>,
 }
 
 impl DeBruijnMap {
@@ -743,11 +765,13 @@ impl DeBruijnMap {
         Self {
             indices: (HashMapStack::new(), HashMapStack::new()),
             counter: vec![0],
+            renamed: vec![0],
         }
     }
 
-    fn is_empty(&self) -> bool {
-        self.indices.0.is_empty() && self.indices.1.is_empty()
+    /// Returns `true` if every variable in scope is bound to the same name in both terms.
+    fn is_identity(&self) -> bool {
+        *self.renamed.last().unwrap() == 0
     }
 
     fn push(&mut self) {
@@ -755,6 +779,8 @@ impl DeBruijnMap {
         self.indices.1.push_scope();
         let current = *self.counter.last().unwrap();
         self.counter.push(current);
+        let renamed = *self.renamed.last().unwrap();
+        self.renamed.push(renamed);
     }
 
     fn pop(&mut self) {
@@ -762,11 +788,15 @@ impl DeBruijnMap {
         self.indices.1.pop_scope();
 
         // If we successfully popped the scopes from the indices stacks, that means that there was
-        // at least one scope, so we can safely pop from the counter stack as well
+        // at least one scope, so we can safely pop from the counter stacks as well
         self.counter.pop();
+        self.renamed.pop();
     }
 
     fn insert(&mut self, a: String, b: String) {
+        if a != b {
+            *self.renamed.last_mut().unwrap() += 1;
+        }
         let current = self.counter.last_mut().unwrap();
         self.indices.0.insert(a, *current);
         self.indices.1.insert(b, *current);
@@ -886,5 +916,38 @@ fn nary_case(op: Operator) -> Option<NaryCase> {
         | Operator::BvSGe
         | Operator::BvBbTerm
         | Operator::RareList => None,
+
+        // Floating-point
+        Operator::RoundNearestTiesToEven
+        | Operator::RoundNearestTiesToAway
+        | Operator::RoundTowardPositive
+        | Operator::RoundTowardNegative
+        | Operator::RoundTowardZero
+        | Operator::Fp
+        | Operator::FpAbs
+        | Operator::FpNeg
+        | Operator::FpAdd
+        | Operator::FpSub
+        | Operator::FpMul
+        | Operator::FpDiv
+        | Operator::FpFma
+        | Operator::FpSqrt
+        | Operator::FpRem
+        | Operator::FpRoundToIntegral
+        | Operator::FpMin
+        | Operator::FpMax
+        | Operator::FpLeq
+        | Operator::FpLt
+        | Operator::FpGeq
+        | Operator::FpGt
+        | Operator::FpEq
+        | Operator::FpIsNormal
+        | Operator::FpIsSubnormal
+        | Operator::FpIsZero
+        | Operator::FpIsInfinite
+        | Operator::FpIsNaN
+        | Operator::FpIsNegative
+        | Operator::FpIsPositive
+        | Operator::FpToReal => None,
     }
 }
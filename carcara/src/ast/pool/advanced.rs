@@ -1,5 +1,5 @@
 use super::super::{Rc, Term};
-use super::{PrimitivePool, TermPool};
+use super::{PrimitivePool, TermPool, TermPoolView};
 use indexmap::IndexSet;
 use std::sync::{Arc, RwLock};
 
@@ -23,9 +23,12 @@ impl ContextPool {
     }
 
     pub fn from_global(global_pool: &Arc<PrimitivePool>) -> Self {
+        // Names are generated by the context pool, which uses the scheme of the global pool
+        let mut inner = PrimitivePool::new();
+        inner.set_fresh_name_scheme(global_pool.fresh_name_scheme());
         Self {
             global_pool: global_pool.clone(),
-            inner: Arc::new(RwLock::new(PrimitivePool::new())),
+            inner: Arc::new(RwLock::new(inner)),
         }
     }
 
@@ -35,36 +38,62 @@ impl ContextPool {
             inner: ctx_pool.inner.clone(),
         }
     }
-}
 
-impl TermPool for ContextPool {
-    fn add(&mut self, term: Term) -> Rc<Term> {
-        // If the global pool has the term
-        if let Some(entry) = self.global_pool.storage.get(&term) {
-            return entry.clone();
+    /// Adds a term to the pool through a shared reference, so the pool can be used by many threads
+    /// at once. The term is first looked up holding only read locks, and the write lock is only
+    /// taken if the term is not yet in the pool.
+    pub fn add_shared(&self, term: Term) -> Rc<Term> {
+        if let Some(entry) = self.get(&term) {
+            return entry;
         }
         let mut ctx_guard = self.inner.write().unwrap();
-        let term = ctx_guard.storage.add(term);
-        ctx_guard.compute_sort(&term);
+        let (id, term) = ctx_guard.storage.add_with_id(term);
+        ctx_guard.compute_sort_with_id(&term, Some(id));
         term
     }
+}
 
+impl TermPoolView for ContextPool {
     fn sort(&self, term: &Rc<Term>) -> Rc<Term> {
-        if let Some(sort) = self.global_pool.sorts_cache.get(term) {
-            sort.clone()
+        if let Some(sort) = self.global_pool.cached_sort(term) {
+            sort
         }
         // A sort inserted by context
         else {
-            self.inner.read().unwrap().sorts_cache[term].clone()
+            self.inner.read().unwrap().sort(term)
         }
     }
 
+    fn get(&self, term: &Term) -> Option<Rc<Term>> {
+        self.global_pool
+            .get(term)
+            .or_else(|| self.inner.read().unwrap().get(term))
+    }
+}
+
+impl TermPool for ContextPool {
+    fn as_view(&self) -> &dyn TermPoolView {
+        self
+    }
+
+    fn add(&mut self, term: Term) -> Rc<Term> {
+        self.add_shared(term)
+    }
+
     fn free_vars(&mut self, term: &Rc<Term>) -> IndexSet<Rc<Term>> {
         self.inner
             .write()
             .unwrap()
             .free_vars_with_priorities(term, [&self.global_pool])
     }
+
+    fn fresh_name(&mut self, name: &str, is_taken: &dyn Fn(&str) -> bool) -> String {
+        self.inner.write().unwrap().fresh_names.generate(
+            name,
+            is_taken,
+            &self.global_pool.reserved_names,
+        )
+    }
 }
 
 // =========================================================================
@@ -98,29 +127,30 @@ impl LocalPool {
     }
 }
 
-impl TermPool for LocalPool {
-    fn add(&mut self, term: Term) -> Rc<Term> {
-        // If there is a constant pool and has the term
-        if let Some(entry) = self.ctx_pool.global_pool.storage.get(&term) {
-            entry.clone()
-        }
-        // If this term was inserted by the context
-        else if let Some(entry) = self.ctx_pool.inner.read().unwrap().storage.get(&term) {
-            entry.clone()
-        } else {
-            self.inner.add(term)
+impl TermPoolView for LocalPool {
+    fn sort(&self, term: &Rc<Term>) -> Rc<Term> {
+        // Terms added by this thread are looked up first, since that doesn't require any locks
+        match self.inner.cached_sort(term) {
+            Some(sort) => sort,
+            None => self.ctx_pool.sort(term),
         }
     }
 
-    fn sort(&self, term: &Rc<Term>) -> Rc<Term> {
-        if let Some(sort) = self.ctx_pool.global_pool.sorts_cache.get(term) {
-            sort.clone()
-        }
-        // A sort inserted by context
-        else if let Some(entry) = self.ctx_pool.inner.read().unwrap().storage.get(term) {
-            entry.clone()
-        } else {
-            self.inner.sorts_cache[term].clone()
+    fn get(&self, term: &Term) -> Option<Rc<Term>> {
+        self.ctx_pool.get(term).or_else(|| self.inner.get(term))
+    }
+}
+
+impl TermPool for LocalPool {
+    fn as_view(&self) -> &dyn TermPoolView {
+        self
+    }
+
+    fn add(&mut self, term: Term) -> Rc<Term> {
+        // Terms in the global or context pools take priority, so they are shared between threads
+        match self.ctx_pool.get(&term) {
+            Some(entry) => entry,
+            None => self.inner.add(term),
         }
     }
 
@@ -133,4 +163,39 @@ impl TermPool for LocalPool {
             ],
         )
     }
+
+    fn fresh_name(&mut self, name: &str, is_taken: &dyn Fn(&str) -> bool) -> String {
+        self.ctx_pool.fresh_name(name, is_taken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Sort;
+    use std::thread;
+
+    #[test]
+    fn test_shared_context_pool() {
+        let ctx_pool = ContextPool::new();
+        let int_sort = ctx_pool.add_shared(Term::Sort(Sort::Int));
+        let terms: Vec<_> = thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| s.spawn(|| ctx_pool.add_shared(Term::new_var("x", int_sort.clone()))))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert!(terms.windows(2).all(|w| w[0] == w[1]));
+
+        // Terms added to the context are found, and their sorts computed, by the local pools
+        let x = &terms[0];
+        let mut local = LocalPool::from_previous(&ctx_pool);
+        assert_eq!(local.get(x), Some(x.clone()));
+        assert_eq!(local.add(Term::new_var("x", int_sort.clone())), *x);
+        assert_eq!(local.sort(x), int_sort);
+
+        let y = local.add(Term::new_var("y", int_sort.clone()));
+        assert_eq!(ctx_pool.get(&y), None);
+        assert_eq!(local.sort(&y).as_sort(), Some(&Sort::Int));
+    }
 }
@@ -0,0 +1,217 @@
+//! Interning of binder terms modulo alpha-equivalence.
+
+use super::storage::Storage;
+use crate::{
+    ast::{BindingList, Rc, Term},
+    utils::HashMapStack,
+};
+use indexmap::IndexMap;
+
+/// Keeps track of the alpha-equivalence classes of the binder terms in a pool.
+///
+/// Each binder term is associated with a canonical key, in which every bound variable is renamed
+/// according to its De Bruijn level. Since alpha-equivalent terms have the same key, only the first
+/// term added with a given key is stored in the pool, and it is returned for all terms added later
+/// that are alpha-equivalent to it.
+#[derive(Debug, Default)]
+pub(super) struct AlphaClasses {
+    /// The storage for the canonical keys. These are kept apart from the pool terms, so they are
+    /// never returned by the pool.
+    keys: Storage,
+
+    /// Maps each canonical key to the pool term that represents its class.
+    representatives: IndexMap<Rc<Term>, Rc<Term>>,
+
+    /// Maps each representative to its canonical key.
+    key_of: IndexMap<Rc<Term>, Rc<Term>>,
+}
+
+impl AlphaClasses {
+    /// Adds a binder term to `storage`, unless an alpha-equivalent term is already there, in which
+    /// case that term is returned instead.
+    pub fn add(&mut self, storage: &mut Storage, term: Term) -> Rc<Term> {
+        if let Some(existing) = storage.get(&term) {
+            return existing.clone();
+        }
+        let key = Canonicalizer::new(&mut self.keys).term_key(&term);
+        if let Some(representative) = self.representatives.get(&key) {
+            return representative.clone();
+        }
+        let term = storage.add(term);
+        self.insert(key, term.clone());
+        term
+    }
+
+    /// Registers a term that is already in the pool as the representative of its class, unless
+    /// the class already has one.
+    pub fn register(&mut self, term: &Rc<Term>) {
+        let key = Canonicalizer::new(&mut self.keys).term_key(term);
+        if !self.representatives.contains_key(&key) {
+            self.insert(key, term.clone());
+        }
+    }
+
+    fn insert(&mut self, key: Rc<Term>, representative: Rc<Term>) {
+        self.representatives
+            .insert(key.clone(), representative.clone());
+        self.key_of.insert(representative, key);
+    }
+
+    /// Returns the number of references to `term` held by this struct.
+    pub fn references(&self, term: &Rc<Term>) -> usize {
+        if self.key_of.contains_key(term) {
+            2
+        } else {
+            0
+        }
+    }
+
+    /// Removes the class represented by `term`, if there is one.
+    pub fn remove(&mut self, term: &Rc<Term>) {
+        if let Some(key) = self.key_of.swap_remove(term) {
+            self.representatives.swap_remove(&key);
+        }
+    }
+
+    /// Retains only the classes whose representatives satisfy `keep`.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Rc<Term>) -> bool) {
+        let representatives = &mut self.representatives;
+        self.key_of.retain(|representative, key| {
+            let result = keep(representative);
+            if !result {
+                representatives.swap_remove(key);
+            }
+            result
+        });
+        self.collect_garbage();
+    }
+
+    /// Removes the canonical keys that are no longer used by any class.
+    pub fn collect_garbage(&mut self) {
+        loop {
+            let before = self.keys.len();
+            self.keys.retain(|_, key| Rc::strong_count(key) > 1);
+            if self.keys.len() == before {
+                break;
+            }
+        }
+    }
+}
+
+/// Computes the canonical key of a term. In the key, each bound variable is renamed to `|n`, where
+/// `n` is its De Bruijn level, that is, the number of variables bound before it by the enclosing
+/// binders. Since symbols can never contain `|`, these names don't clash with any variable in the
+/// original term.
+///
+/// Subterms that are not changed by the renaming are kept as they are, so the key may share them
+/// with the pool.
+struct Canonicalizer<'a> {
+    keys: &'a mut Storage,
+
+    /// The De Bruijn level of each bound variable in scope. Variables bound by `let` terms are
+    /// mapped to `None`, since they are not renamed.
+    levels: HashMapStack<String, Option<usize>>,
+    next_level: usize,
+
+    /// The keys computed for each subterm, with one cache for each scope. Only the innermost cache
+    /// may be used, since a variable may be renamed differently in an inner scope, if it is shadowed
+    cache: Vec<IndexMap<Rc<Term>, Rc<Term>>>,
+}
+
+impl<'a> Canonicalizer<'a> {
+    fn new(keys: &'a mut Storage) -> Self {
+        Self {
+            keys,
+            levels: HashMapStack::new(),
+            next_level: 0,
+            cache: vec![IndexMap::new()],
+        }
+    }
+
+    fn term_key(&mut self, term: &Term) -> Rc<Term> {
+        let key = self.canonicalize(term);
+        self.keys.add(key)
+    }
+
+    fn canonicalize_rc(&mut self, term: &Rc<Term>) -> Rc<Term> {
+        if let Some(result) = self.cache.last().unwrap().get(term) {
+            return result.clone();
+        }
+        let canonical = self.canonicalize(term);
+        let result = if canonical == **term {
+            term.clone()
+        } else {
+            self.keys.add(canonical)
+        };
+        self.cache
+            .last_mut()
+            .unwrap()
+            .insert(term.clone(), result.clone());
+        result
+    }
+
+    fn canonicalize(&mut self, term: &Term) -> Term {
+        match term {
+            Term::Const(_) | Term::Sort(_) => term.clone(),
+            Term::Var(name, sort) => match self.levels.get(name) {
+                Some(Some(level)) => Term::Var(format!("|{}", level), sort.clone()),
+                _ => term.clone(),
+            },
+            Term::App(f, args) => {
+                let f = self.canonicalize_rc(f);
+                Term::App(f, self.canonicalize_all(args))
+            }
+            Term::Op(op, args) => Term::Op(*op, self.canonicalize_all(args)),
+            Term::ParamOp { op, op_args, args } => Term::ParamOp {
+                op: *op,
+                op_args: op_args.clone(),
+                args: self.canonicalize_all(args),
+            },
+            Term::Binder(binder, bindings, inner) => {
+                self.push_scope();
+                let previous_level = self.next_level;
+                let bindings = bindings
+                    .iter()
+                    .map(|(name, sort)| {
+                        let level = self.next_level;
+                        self.next_level += 1;
+                        self.levels.insert(name.clone(), Some(level));
+                        (format!("|{}", level), sort.clone())
+                    })
+                    .collect();
+                let inner = self.canonicalize_rc(inner);
+                self.next_level = previous_level;
+                self.pop_scope();
+                Term::Binder(*binder, BindingList(bindings), inner)
+            }
+            Term::Let(bindings, inner) => {
+                let values: Vec<_> = bindings
+                    .iter()
+                    .map(|(_, value)| self.canonicalize_rc(value))
+                    .collect();
+                self.push_scope();
+                for (name, _) in bindings {
+                    self.levels.insert(name.clone(), None);
+                }
+                let inner = self.canonicalize_rc(inner);
+                self.pop_scope();
+                let bindings = bindings.iter().map(|(name, _)| name.clone()).zip(values);
+                Term::Let(BindingList(bindings.collect()), inner)
+            }
+        }
+    }
+
+    fn canonicalize_all(&mut self, terms: &[Rc<Term>]) -> Vec<Rc<Term>> {
+        terms.iter().map(|t| self.canonicalize_rc(t)).collect()
+    }
+
+    fn push_scope(&mut self) {
+        self.levels.push_scope();
+        self.cache.push(IndexMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        self.levels.pop_scope();
+        self.cache.pop();
+    }
+}
@@ -1,15 +1,38 @@
 //! This module implements `TermPool`, a structure that stores terms and implements hash consing.
 
 pub mod advanced;
+mod alpha;
+pub mod shared;
 mod storage;
 
-use super::{Binder, Operator, Rc, Sort, Term};
+use super::{
+    visit::{fold_term, FoldCache},
+    Binder, Operator, Rc, Sort, Term,
+};
 use crate::ast::{Constant, ParamOperator};
+use alpha::AlphaClasses;
 use indexmap::{IndexMap, IndexSet};
 use rug::Integer;
-use storage::Storage;
+use storage::{Storage, TermId};
+
+/// The read-only operations of a term pool. Code that only needs to look up sorts or existing terms
+/// should take a `TermPoolView`, so it can be used with a shared pool without requiring exclusive
+/// access to it.
+pub trait TermPoolView {
+    /// Returns the sort of the given term.
+    ///
+    /// This method assumes that the sorts of any subterms have already been checked, and are
+    /// correct. If `term` is itself a sort, this simply returns that sort.
+    fn sort(&self, term: &Rc<Term>) -> Rc<Term>;
+
+    /// Returns the `Rc` that references the given term, if it was already added to the pool.
+    fn get(&self, term: &Term) -> Option<Rc<Term>>;
+}
+
+pub trait TermPool: TermPoolView {
+    /// Returns this pool as a [`TermPoolView`], for code that only reads from it.
+    fn as_view(&self) -> &dyn TermPoolView;
 
-pub trait TermPool {
     /// Returns the term corresponding to the boolean constant `true`.
     fn bool_true(&mut self) -> Rc<Term> {
         self.bool_constant(true)
@@ -35,16 +58,324 @@ pub trait TermPool {
     fn add_all(&mut self, terms: Vec<Term>) -> Vec<Rc<Term>> {
         terms.into_iter().map(|t| self.add(t)).collect()
     }
-    /// Returns the sort of the given term.
-    ///
-    /// This method assumes that the sorts of any subterms have already been checked, and are
-    /// correct. If `term` is itself a sort, this simply returns that sort.
-    fn sort(&self, term: &Rc<Term>) -> Rc<Term>;
     /// Returns an `IndexSet` containing all the free variables in the given term.
     ///
     /// This method uses a cache, so there is no additional cost to computing the free variables of
     /// a term multiple times.
     fn free_vars(&mut self, term: &Rc<Term>) -> IndexSet<Rc<Term>>;
+    /// Returns a fresh name based on `name`, to be used when renaming a bound variable. If `name`
+    /// is neither taken, according to `is_taken`, nor reserved by the pool, it is returned
+    /// unchanged. Otherwise, a new name is built according to the pool's [`FreshNameScheme`]. The
+    /// names of the symbols declared in the problem are reserved, so a renamed variable never
+    /// clashes with them when the proof is printed.
+    fn fresh_name(&mut self, name: &str, is_taken: &dyn Fn(&str) -> bool) -> String;
+}
+
+/// How [`TermPool::fresh_name`] builds a new name for a bound variable that must be renamed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FreshNameScheme {
+    /// Appends `'`s to the name until it is neither taken nor reserved. For example, `x` may be
+    /// renamed to `x'`, then to `x''`.
+    #[default]
+    Primes,
+
+    /// Appends `!n` to the original name of the variable, where `n` is a counter shared by the
+    /// whole pool. For example, `x` may be renamed to `x!0`, and `y` to `y!1`. A variable that was
+    /// already renamed is renamed based on its original name, so `x!0` may be renamed to `x!2`,
+    /// but never to `x!0!2`.
+    Counter,
+}
+
+/// Generates the names returned by [`TermPool::fresh_name`], and remembers the original name of
+/// each variable that was renamed.
+#[derive(Debug, Default)]
+pub(crate) struct FreshNames {
+    pub(crate) scheme: FreshNameScheme,
+    counter: usize,
+
+    /// Maps each generated name to the original name of the variable.
+    originals: IndexMap<String, String>,
+}
+
+impl FreshNames {
+    fn generate(
+        &mut self,
+        name: &str,
+        is_taken: &dyn Fn(&str) -> bool,
+        reserved: &IndexSet<String>,
+    ) -> String {
+        let is_free = |name: &str| !is_taken(name) && !reserved.contains(name);
+        if is_free(name) {
+            return name.to_owned();
+        }
+        let original = self.original_name(name).to_owned();
+        let result = match self.scheme {
+            FreshNameScheme::Primes => {
+                let mut result = name.to_owned();
+                while !is_free(&result) {
+                    result.push('\'');
+                }
+                result
+            }
+            FreshNameScheme::Counter => loop {
+                let candidate = format!("{}!{}", original, self.counter);
+                self.counter += 1;
+                if is_free(&candidate) {
+                    break candidate;
+                }
+            },
+        };
+        self.originals.insert(result.clone(), original);
+        result
+    }
+
+    fn original_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.originals.get(name).map_or(name, String::as_str)
+    }
+}
+
+/// Computes the sort of a term, given a function that returns the sorts of its subterms.
+fn compute_sort(term: &Term, sort_of: &mut dyn FnMut(&Rc<Term>) -> Rc<Term>) -> Sort {
+    match term {
+        Term::Const(c) => match c {
+            Constant::Integer(_) => Sort::Int,
+            Constant::Real(_) => Sort::Real,
+            Constant::String(_) => Sort::String,
+            Constant::BitVec(_, w) => Sort::BitVec(w.clone()),
+        },
+        Term::Var(_, sort) => sort.as_sort().unwrap().clone(),
+        Term::Op(op, args) => match op {
+            Operator::True
+            | Operator::False
+            | Operator::Not
+            | Operator::Implies
+            | Operator::And
+            | Operator::Or
+            | Operator::Xor
+            | Operator::Equals
+            | Operator::Distinct
+            | Operator::LessThan
+            | Operator::GreaterThan
+            | Operator::LessEq
+            | Operator::GreaterEq
+            | Operator::IsInt
+            | Operator::StrLessThan
+            | Operator::StrLessEq
+            | Operator::PrefixOf
+            | Operator::SuffixOf
+            | Operator::Contains
+            | Operator::StrIsDigit
+            | Operator::StrInRe
+            | Operator::BvULt
+            | Operator::BvULe
+            | Operator::BvUGt
+            | Operator::BvUGe
+            | Operator::BvSLt
+            | Operator::BvSLe
+            | Operator::BvSGt
+            | Operator::BvSGe
+            | Operator::BvShl
+            | Operator::BvLShr
+            | Operator::FpLeq
+            | Operator::FpLt
+            | Operator::FpGeq
+            | Operator::FpGt
+            | Operator::FpEq
+            | Operator::FpIsNormal
+            | Operator::FpIsSubnormal
+            | Operator::FpIsZero
+            | Operator::FpIsInfinite
+            | Operator::FpIsNaN
+            | Operator::FpIsNegative
+            | Operator::FpIsPositive => Sort::Bool,
+            Operator::BvAdd
+            | Operator::BvSub
+            | Operator::BvNot
+            | Operator::BvNeg
+            | Operator::BvNAnd
+            | Operator::BvNOr
+            | Operator::BvAnd
+            | Operator::BvOr
+            | Operator::BvUDiv
+            | Operator::BvURem
+            | Operator::BvXor
+            | Operator::BvXNor
+            | Operator::BvMul
+            | Operator::BvSDiv
+            | Operator::BvSRem
+            | Operator::BvSMod
+            | Operator::BvAShr => {
+                let Sort::BitVec(width) = sort_of(&args[0]).as_sort().unwrap().clone() else {
+                    unreachable!()
+                };
+                Sort::BitVec(width)
+            }
+            Operator::BvComp => Sort::BitVec(Integer::ONE.into()),
+            Operator::BvBbTerm => Sort::BitVec(Integer::from(args.len())),
+            Operator::BvConcat => {
+                let mut total_width = Integer::ZERO;
+                for arg in args {
+                    let Sort::BitVec(arg_width) = sort_of(arg).as_sort().unwrap().clone() else {
+                        unreachable!()
+                    };
+                    total_width += arg_width;
+                }
+                Sort::BitVec(total_width)
+            }
+            Operator::Ite => sort_of(&args[1]).as_sort().unwrap().clone(),
+            Operator::Add | Operator::Sub | Operator::Mult => {
+                if args
+                    .iter()
+                    .any(|a| sort_of(a).as_sort().unwrap() == &Sort::Real)
+                {
+                    Sort::Real
+                } else {
+                    Sort::Int
+                }
+            }
+            Operator::RealDiv | Operator::ToReal => Sort::Real,
+            Operator::IntDiv | Operator::Mod | Operator::Abs | Operator::ToInt => Sort::Int,
+            Operator::Select => match sort_of(&args[0]).as_sort().unwrap() {
+                Sort::Array(_, y) => y.as_sort().unwrap().clone(),
+                _ => unreachable!(),
+            },
+            Operator::Store => sort_of(&args[0]).as_sort().unwrap().clone(),
+            Operator::StrLen | Operator::IndexOf | Operator::StrToCode | Operator::StrToInt => {
+                Sort::Int
+            }
+            Operator::StrConcat
+            | Operator::CharAt
+            | Operator::Substring
+            | Operator::Replace
+            | Operator::ReplaceAll
+            | Operator::ReplaceRe
+            | Operator::ReplaceReAll
+            | Operator::StrFromCode
+            | Operator::StrFromInt => Sort::String,
+            Operator::StrToRe
+            | Operator::ReNone
+            | Operator::ReAll
+            | Operator::ReAllChar
+            | Operator::ReConcat
+            | Operator::ReUnion
+            | Operator::ReIntersection
+            | Operator::ReKleeneClosure
+            | Operator::ReComplement
+            | Operator::ReDiff
+            | Operator::ReKleeneCross
+            | Operator::ReOption
+            | Operator::ReRange => Sort::RegLan,
+            Operator::RareList => Sort::RareList,
+            Operator::RoundNearestTiesToEven
+            | Operator::RoundNearestTiesToAway
+            | Operator::RoundTowardPositive
+            | Operator::RoundTowardNegative
+            | Operator::RoundTowardZero => Sort::RoundingMode,
+            Operator::Fp => {
+                let [Sort::BitVec(e), Sort::BitVec(s)] =
+                    [&args[1], &args[2]].map(|a| sort_of(a).as_sort().unwrap().clone())
+                else {
+                    unreachable!()
+                };
+                // The significand width includes the hidden bit, which is not present in the
+                // literal
+                Sort::FloatingPoint(e, s + Integer::ONE)
+            }
+            Operator::FpAbs
+            | Operator::FpNeg
+            | Operator::FpRem
+            | Operator::FpMin
+            | Operator::FpMax => sort_of(&args[0]).as_sort().unwrap().clone(),
+            Operator::FpAdd
+            | Operator::FpSub
+            | Operator::FpMul
+            | Operator::FpDiv
+            | Operator::FpFma
+            | Operator::FpSqrt
+            | Operator::FpRoundToIntegral => sort_of(&args[1]).as_sort().unwrap().clone(),
+            Operator::FpToReal => Sort::Real,
+        },
+        Term::App(f, _) => {
+            match sort_of(f).as_sort().unwrap() {
+                Sort::Function(sorts) => sorts.last().unwrap().as_sort().unwrap().clone(),
+                _ => unreachable!(), // We assume that the function is correctly sorted
+            }
+        }
+        Term::Sort(_) => Sort::Type,
+        Term::Binder(Binder::Forall | Binder::Exists, _, _) => Sort::Bool,
+        Term::Binder(Binder::Choice, v, _) => v[0].1.as_sort().unwrap().clone(),
+        Term::Binder(Binder::Lambda, bindings, body) => {
+            let mut result: Vec<_> = bindings.iter().map(|(_name, sort)| sort.clone()).collect();
+            result.push(sort_of(body));
+            Sort::Function(result)
+        }
+        Term::Let(_, inner) => sort_of(inner).as_sort().unwrap().clone(),
+        Term::ParamOp { op, op_args, args } => {
+            let sort = match op {
+                ParamOperator::BvExtract => {
+                    let i = op_args[0].as_integer().unwrap();
+                    let j = op_args[1].as_integer().unwrap();
+                    Sort::BitVec(i - j + Integer::ONE)
+                }
+                ParamOperator::ZeroExtend | ParamOperator::SignExtend => {
+                    let extension_width = op_args[0].as_integer().unwrap();
+                    let Sort::BitVec(bv_width) = sort_of(&args[0]).as_sort().unwrap().clone()
+                    else {
+                        unreachable!()
+                    };
+                    Sort::BitVec(extension_width + bv_width)
+                }
+                ParamOperator::BvConst => unreachable!(
+                    "bv const should be handled by the parser and transfromed into a constant"
+                ),
+                ParamOperator::BvBitOf | ParamOperator::DtTester => Sort::Bool,
+                ParamOperator::FpPlusZero
+                | ParamOperator::FpMinusZero
+                | ParamOperator::FpPlusInfinity
+                | ParamOperator::FpMinusInfinity
+                | ParamOperator::FpNaN
+                | ParamOperator::FpToFp
+                | ParamOperator::FpToFpUnsigned => {
+                    let e = op_args[0].as_integer().unwrap();
+                    let s = op_args[1].as_integer().unwrap();
+                    Sort::FloatingPoint(e, s)
+                }
+                ParamOperator::FpToUbv | ParamOperator::FpToSbv => {
+                    Sort::BitVec(op_args[0].as_integer().unwrap())
+                }
+                ParamOperator::RePower | ParamOperator::ReLoop => Sort::RegLan,
+                ParamOperator::ArrayConst => op_args[0].as_sort().unwrap().clone(),
+            };
+            sort
+        }
+    }
+}
+
+/// Pushes all the direct subterms of `term` into `stack`, including the sorts in bindings and in
+/// sort terms.
+fn push_children(term: &Term, stack: &mut Vec<Rc<Term>>) {
+    match term {
+        Term::Const(_) => (),
+        Term::Var(_, sort) => stack.push(sort.clone()),
+        Term::App(f, args) => {
+            stack.push(f.clone());
+            stack.extend(args.iter().cloned());
+        }
+        Term::Op(_, args) => stack.extend(args.iter().cloned()),
+        Term::ParamOp { op_args, args, .. } => {
+            stack.extend(op_args.iter().cloned());
+            stack.extend(args.iter().cloned());
+        }
+        Term::Sort(sort) => match sort {
+            Sort::Function(sorts) | Sort::Atom(_, sorts) => stack.extend(sorts.iter().cloned()),
+            Sort::Array(x, y) => stack.extend([x.clone(), y.clone()]),
+            _ => (),
+        },
+        Term::Binder(_, bindings, inner) | Term::Let(bindings, inner) => {
+            stack.extend(bindings.iter().map(|(_, t)| t.clone()));
+            stack.push(inner.clone());
+        }
+    }
 }
 
 /// A structure to store and manage all allocated terms.
@@ -56,11 +387,34 @@ pub trait TermPool {
 ///
 /// This struct also provides other utility methods, like computing the sort of a term (see
 /// [`PrimitivePool::sort`]) or its free variables (see [`PrimitivePool::free_vars`]).
+///
+/// Each term in the pool is identified by a 32-bit id, its position in the pool storage. The sort
+/// of each term is stored by id, which is much more compact than a map between `Rc`s.
 #[derive(Debug, Default)]
 pub struct PrimitivePool {
     pub(crate) storage: Storage,
     pub(crate) free_vars_cache: IndexMap<Rc<Term>, IndexSet<Rc<Term>>>,
-    pub(crate) sorts_cache: IndexMap<Rc<Term>, Rc<Term>>,
+
+    /// The id of the sort of each term, indexed by the term id. This is `None` for terms whose sort
+    /// was never computed, like the sorts added while computing the sorts of other terms.
+    sorts: Vec<Option<TermId>>,
+
+    /// The sorts of terms that are not in this pool, but in a pool that takes priority over it,
+    /// like the global pool of a [`advanced::ContextPool`].
+    foreign_sorts: IndexMap<Rc<Term>, Rc<Term>>,
+
+    /// Names that can't be chosen by [`TermPool::fresh_name`].
+    pub(crate) reserved_names: IndexSet<String>,
+
+    pub(crate) fresh_names: FreshNames,
+
+    /// If this is `Some`, every name generated by [`TermPool::fresh_name`] is recorded here,
+    /// together with the original name.
+    rename_log: Option<Vec<(String, String)>>,
+
+    /// If this is `Some`, binder terms are added modulo alpha-equivalence. See
+    /// [`PrimitivePool::enable_alpha_canonical_binders`].
+    alpha_classes: Option<AlphaClasses>,
 }
 
 impl PrimitivePool {
@@ -70,180 +424,239 @@ impl PrimitivePool {
         Self::default()
     }
 
-    /// Computes the sort of a term and adds it to the sort cache.
-    fn compute_sort(&mut self, term: &Rc<Term>) -> Rc<Term> {
-        if let Some(sort) = self.sorts_cache.get(term) {
-            return sort.clone();
+    /// Returns the number of terms currently stored in the pool.
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Returns `true` if the pool contains no terms.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reserves a name, so it is never chosen when renaming bound variables. This is used for the
+    /// symbols declared in the problem.
+    pub fn reserve_name(&mut self, name: String) {
+        self.reserved_names.insert(name);
+    }
+
+    /// Sets how new names are built when renaming bound variables. See [`FreshNameScheme`].
+    pub fn set_fresh_name_scheme(&mut self, scheme: FreshNameScheme) {
+        self.fresh_names.scheme = scheme;
+    }
+
+    /// Returns how new names are built when renaming bound variables.
+    pub fn fresh_name_scheme(&self) -> FreshNameScheme {
+        self.fresh_names.scheme
+    }
+
+    /// Returns the original name of a variable that was renamed by [`TermPool::fresh_name`], so
+    /// printers can display it. If the variable was never renamed, `name` is returned as is.
+    pub fn original_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.fresh_names.original_name(name)
+    }
+
+    /// Starts recording the names generated when renaming bound variables. Any names recorded
+    /// previously are discarded.
+    pub fn start_rename_log(&mut self) {
+        self.rename_log = Some(Vec::new());
+    }
+
+    /// Stops recording the names generated when renaming bound variables, and returns the
+    /// renamings recorded so far, as pairs of the original and the new name.
+    pub fn take_rename_log(&mut self) -> Vec<(String, String)> {
+        self.rename_log.take().unwrap_or_default()
+    }
+
+    /// Makes the pool add binder terms modulo alpha-equivalence. After this is called, adding a
+    /// quantifier, `choice` or `lambda` term that is alpha-equivalent to a term already in the pool
+    /// returns that term, so all terms that only differ in the names of their bound variables share
+    /// the same allocation. If the pool already has alpha-equivalent terms, the first of them that
+    /// was added is used for all terms added from now on.
+    ///
+    /// Note that the term returned by [`TermPool::add`] may then use different names for its bound
+    /// variables than the given term. This can break rules that refer to bound variables by name,
+    /// like the ones that use the context of a subproof, so this should only be enabled if the
+    /// proof doesn't depend on these names.
+    pub fn enable_alpha_canonical_binders(&mut self) {
+        if self.alpha_classes.is_some() {
+            return;
+        }
+        let mut classes = AlphaClasses::default();
+        for term in self.storage.iter() {
+            if let Term::Binder(..) = term.as_ref() {
+                classes.register(term);
+            }
         }
+        self.alpha_classes = Some(classes);
+    }
 
-        let result: Sort = match term.as_ref() {
-            Term::Const(c) => match c {
-                Constant::Integer(_) => Sort::Int,
-                Constant::Real(_) => Sort::Real,
-                Constant::String(_) => Sort::String,
-                Constant::BitVec(_, w) => Sort::BitVec(w.clone()),
-            },
-            Term::Var(_, sort) => sort.as_sort().unwrap().clone(),
-            Term::Op(op, args) => match op {
-                Operator::True
-                | Operator::False
-                | Operator::Not
-                | Operator::Implies
-                | Operator::And
-                | Operator::Or
-                | Operator::Xor
-                | Operator::Equals
-                | Operator::Distinct
-                | Operator::LessThan
-                | Operator::GreaterThan
-                | Operator::LessEq
-                | Operator::GreaterEq
-                | Operator::IsInt
-                | Operator::StrLessThan
-                | Operator::StrLessEq
-                | Operator::PrefixOf
-                | Operator::SuffixOf
-                | Operator::Contains
-                | Operator::StrIsDigit
-                | Operator::StrInRe
-                | Operator::BvULt
-                | Operator::BvULe
-                | Operator::BvUGt
-                | Operator::BvUGe
-                | Operator::BvSLt
-                | Operator::BvSLe
-                | Operator::BvSGt
-                | Operator::BvSGe
-                | Operator::BvShl
-                | Operator::BvLShr => Sort::Bool,
-                Operator::BvAdd
-                | Operator::BvSub
-                | Operator::BvNot
-                | Operator::BvNeg
-                | Operator::BvNAnd
-                | Operator::BvNOr
-                | Operator::BvAnd
-                | Operator::BvOr
-                | Operator::BvUDiv
-                | Operator::BvURem
-                | Operator::BvXor
-                | Operator::BvXNor
-                | Operator::BvMul
-                | Operator::BvSDiv
-                | Operator::BvSRem
-                | Operator::BvSMod
-                | Operator::BvAShr => {
-                    let Sort::BitVec(width) =
-                        self.compute_sort(&args[0]).as_sort().unwrap().clone()
-                    else {
-                        unreachable!()
-                    };
-                    Sort::BitVec(width)
+    /// Returns `true` if the pool adds binder terms modulo alpha-equivalence.
+    pub fn has_alpha_canonical_binders(&self) -> bool {
+        self.alpha_classes.is_some()
+    }
+
+    /// Removes from the pool all terms that are no longer referenced outside of it, including the
+    /// entries for these terms in the sort and free variables caches.
+    ///
+    /// Since only unreachable terms are removed, this preserves the hash consing invariant: any
+    /// term that is added to the pool after a collection will still be in the same allocation as
+    /// any existing equal term.
+    pub fn collect_garbage(&mut self) {
+        loop {
+            let before = self.storage.len();
+
+            // Besides the storage itself, the caches may hold references to a term, either as a key
+            // or as a value in its own entry (e.g., a variable is one of its own free variables).
+            // Terms that are referenced anywhere else are kept, together with their sorts
+            let mut keep = vec![false; before];
+            for (i, term) in self.storage.iter().enumerate() {
+                let free_vars_refs = self
+                    .free_vars_cache
+                    .get(term)
+                    .map_or(0, |vars| 1 + usize::from(vars.contains(term)));
+                let alpha_refs = self
+                    .alpha_classes
+                    .as_ref()
+                    .map_or(0, |c| c.references(term));
+                keep[i] = Rc::strong_count(term) > 1 + free_vars_refs + alpha_refs;
+            }
+            self.keep_sorts(&mut keep);
+
+            let free_vars_cache = &mut self.free_vars_cache;
+            let alpha_classes = &mut self.alpha_classes;
+            let new_ids = self.storage.retain(|id, term| {
+                if keep[id.index()] {
+                    return true;
                 }
-                Operator::BvComp => Sort::BitVec(Integer::ONE.into()),
-                Operator::BvBbTerm => Sort::BitVec(Integer::from(args.len())),
-                Operator::BvConcat => {
-                    let mut total_width = Integer::ZERO;
-                    for arg in args {
-                        let Sort::BitVec(arg_width) =
-                            self.compute_sort(arg).as_sort().unwrap().clone()
-                        else {
-                            unreachable!()
-                        };
-                        total_width += arg_width;
-                    }
-                    Sort::BitVec(total_width)
+                free_vars_cache.swap_remove(term);
+                if let Some(classes) = alpha_classes.as_mut() {
+                    classes.remove(term);
                 }
-                Operator::Ite => self.compute_sort(&args[1]).as_sort().unwrap().clone(),
-                Operator::Add | Operator::Sub | Operator::Mult => {
-                    if args
-                        .iter()
-                        .any(|a| self.compute_sort(a).as_sort().unwrap() == &Sort::Real)
-                    {
-                        Sort::Real
-                    } else {
-                        Sort::Int
+                false
+            });
+            if let Some(classes) = alpha_classes.as_mut() {
+                // The canonical keys of the removed classes may hold the last references to some
+                // of their subterms
+                classes.collect_garbage();
+            }
+
+            self.remap_sorts(&new_ids);
+
+            // Removing a term may make its subterms unreachable, so we repeat until no term is
+            // removed
+            if self.storage.len() == before {
+                break;
+            }
+        }
+    }
+
+    /// Removes from the pool all terms that are not reachable from `roots`, together with their
+    /// entries in the sort and free variables caches. The sorts of the reachable terms are kept as
+    /// well. This is useful when the same pool is used to check many proofs, to drop all terms that
+    /// were only used by the previous proofs.
+    ///
+    /// Unlike [`PrimitivePool::collect_garbage`], this also removes terms that are still referenced
+    /// outside of the pool, if they are not reachable from the roots. These terms must not be used
+    /// with the pool afterwards: since the pool no longer knows about them, adding an equal term
+    /// creates a new allocation, and the two terms won't be considered equal.
+    pub fn collect_garbage_from_roots<'a>(
+        &mut self,
+        roots: impl IntoIterator<Item = &'a Rc<Term>>,
+    ) {
+        let mut reachable = IndexSet::new();
+        let mut to_visit: Vec<_> = roots.into_iter().cloned().collect();
+        while let Some(term) = to_visit.pop() {
+            if !reachable.insert(term.clone()) {
+                continue;
+            }
+            if let Some(sort) = self.cached_sort(&term) {
+                to_visit.push(sort);
+            }
+            push_children(&term, &mut to_visit);
+        }
+
+        let new_ids = self.storage.retain(|_, term| reachable.contains(term));
+        self.remap_sorts(&new_ids);
+        self.foreign_sorts
+            .retain(|term, _| reachable.contains(term));
+        self.free_vars_cache
+            .retain(|term, _| reachable.contains(term));
+        if let Some(classes) = &mut self.alpha_classes {
+            classes.retain(|term| reachable.contains(term));
+        }
+    }
+
+    /// Marks the sorts of all terms marked in `keep` to be kept as well.
+    fn keep_sorts(&self, keep: &mut [bool]) {
+        for i in 0..keep.len() {
+            let mut current = i;
+            // The sort of a sort is also kept, and so on, until we reach a term that is already kept
+            while keep[current] {
+                match self.sorts.get(current).copied().flatten() {
+                    Some(sort) if !keep[sort.index()] => {
+                        keep[sort.index()] = true;
+                        current = sort.index();
                     }
-                }
-                Operator::RealDiv | Operator::ToReal => Sort::Real,
-                Operator::IntDiv | Operator::Mod | Operator::Abs | Operator::ToInt => Sort::Int,
-                Operator::Select => match self.compute_sort(&args[0]).as_sort().unwrap() {
-                    Sort::Array(_, y) => y.as_sort().unwrap().clone(),
-                    _ => unreachable!(),
-                },
-                Operator::Store => self.compute_sort(&args[0]).as_sort().unwrap().clone(),
-                Operator::StrLen | Operator::IndexOf | Operator::StrToCode | Operator::StrToInt => {
-                    Sort::Int
-                }
-                Operator::StrConcat
-                | Operator::CharAt
-                | Operator::Substring
-                | Operator::Replace
-                | Operator::ReplaceAll
-                | Operator::ReplaceRe
-                | Operator::ReplaceReAll
-                | Operator::StrFromCode
-                | Operator::StrFromInt => Sort::String,
-                Operator::StrToRe
-                | Operator::ReNone
-                | Operator::ReAll
-                | Operator::ReAllChar
-                | Operator::ReConcat
-                | Operator::ReUnion
-                | Operator::ReIntersection
-                | Operator::ReKleeneClosure
-                | Operator::ReComplement
-                | Operator::ReDiff
-                | Operator::ReKleeneCross
-                | Operator::ReOption
-                | Operator::ReRange => Sort::RegLan,
-                Operator::RareList => Sort::RareList,
-            },
-            Term::App(f, _) => {
-                match self.compute_sort(f).as_sort().unwrap() {
-                    Sort::Function(sorts) => sorts.last().unwrap().as_sort().unwrap().clone(),
-                    _ => unreachable!(), // We assume that the function is correctly sorted
+                    _ => break,
                 }
             }
-            Term::Sort(_) => Sort::Type,
-            Term::Binder(Binder::Forall | Binder::Exists, _, _) => Sort::Bool,
-            Term::Binder(Binder::Choice, v, _) => v[0].1.as_sort().unwrap().clone(),
-            Term::Binder(Binder::Lambda, bindings, body) => {
-                let mut result: Vec<_> =
-                    bindings.iter().map(|(_name, sort)| sort.clone()).collect();
-                result.push(self.compute_sort(body));
-                Sort::Function(result)
+        }
+    }
+
+    /// Updates the sorts after terms were removed from the storage, given the new id of each term.
+    fn remap_sorts(&mut self, new_ids: &[Option<TermId>]) {
+        let mut sorts = vec![None; self.storage.len()];
+        for (old, sort) in self.sorts.iter().enumerate() {
+            if let (Some(new), Some(sort)) = (new_ids[old], sort) {
+                // The sort of a kept term is always kept
+                sorts[new.index()] = new_ids[sort.index()];
             }
-            Term::Let(_, inner) => self.compute_sort(inner).as_sort().unwrap().clone(),
-            Term::ParamOp { op, op_args, args } => {
-                let sort = match op {
-                    ParamOperator::BvExtract => {
-                        let i = op_args[0].as_integer().unwrap();
-                        let j = op_args[1].as_integer().unwrap();
-                        Sort::BitVec(i - j + Integer::ONE)
-                    }
-                    ParamOperator::ZeroExtend | ParamOperator::SignExtend => {
-                        let extension_width = op_args[0].as_integer().unwrap();
-                        let Sort::BitVec(bv_width) =
-                            self.compute_sort(&args[0]).as_sort().unwrap().clone()
-                        else {
-                            unreachable!()
-                        };
-                        Sort::BitVec(extension_width + bv_width)
-                    }
-                    ParamOperator::BvConst => unreachable!(
-                        "bv const should be handled by the parser and transfromed into a constant"
-                    ),
-                    ParamOperator::BvBitOf => Sort::Bool,
-                    ParamOperator::RePower | ParamOperator::ReLoop => Sort::RegLan,
-                    ParamOperator::ArrayConst => op_args[0].as_sort().unwrap().clone(),
-                };
-                sort
+        }
+        self.sorts = sorts;
+    }
+
+    /// Returns the sort of the given term, if it was already computed by this pool.
+    pub(crate) fn cached_sort(&self, term: &Rc<Term>) -> Option<Rc<Term>> {
+        self.cached_sort_with_id(term, self.storage.id_of(term))
+    }
+
+    fn cached_sort_with_id(&self, term: &Rc<Term>, id: Option<TermId>) -> Option<Rc<Term>> {
+        match id {
+            Some(id) => {
+                let sort = self.sorts.get(id.index()).copied().flatten()?;
+                Some(self.storage.get_by_id(sort).clone())
             }
-        };
-        let sort = self.storage.add(Term::Sort(result));
-        self.sorts_cache.insert(term.clone(), sort);
-        self.sorts_cache[term].clone()
+            None => self.foreign_sorts.get(term).cloned(),
+        }
+    }
+
+    /// Computes the sort of a term and adds it to the sort cache.
+    fn compute_sort(&mut self, term: &Rc<Term>) -> Rc<Term> {
+        let id = self.storage.id_of(term);
+        self.compute_sort_with_id(term, id)
+    }
+
+    /// Computes the sort of a term, given its id in the storage, if it is in it, and adds it to
+    /// the sort cache.
+    pub(crate) fn compute_sort_with_id(&mut self, term: &Rc<Term>, id: Option<TermId>) -> Rc<Term> {
+        if let Some(sort) = self.cached_sort_with_id(term, id) {
+            return sort;
+        }
+        let result = compute_sort(term, &mut |t| self.compute_sort(t));
+        let (sort_id, sort) = self.storage.add_with_id(Term::Sort(result));
+        match id {
+            Some(id) => {
+                if self.sorts.len() <= id.index() {
+                    self.sorts.resize(self.storage.len(), None);
+                }
+                self.sorts[id.index()] = Some(sort_id);
+            }
+            None => {
+                self.foreign_sorts.insert(term.clone(), sort.clone());
+            }
+        }
+        sort
     }
 
     fn add_with_priorities<const N: usize>(
@@ -266,11 +679,11 @@ impl PrimitivePool {
         prior_pools: [&PrimitivePool; N],
     ) -> Rc<Term> {
         for p in prior_pools {
-            if let Some(sort) = p.sorts_cache.get(term) {
-                return sort.clone();
+            if let Some(sort) = p.cached_sort(term) {
+                return sort;
             }
         }
-        self.sorts_cache[term].clone()
+        self.sort(term)
     }
 
     // TODO: Try to workaround the lifetime specifiers and return a ref
@@ -279,79 +692,109 @@ impl PrimitivePool {
         term: &Rc<Term>,
         prior_pools: [&PrimitivePool; N],
     ) -> IndexSet<Rc<Term>> {
-        for p in prior_pools {
-            if let Some(set) = p.free_vars_cache.get(term) {
-                return set.clone();
-            }
+        /// Looks up the free variables of a term in the prior pools first, and then in the cache
+        /// of this pool.
+        struct FreeVarsCache<'a, const N: usize> {
+            prior_pools: [&'a PrimitivePool; N],
+            own: IndexMap<Rc<Term>, IndexSet<Rc<Term>>>,
         }
 
-        if let Some(set) = self.free_vars_cache.get(term) {
-            return set.clone();
-        }
-
-        let set = match term.as_ref() {
-            Term::App(f, args) => {
-                let mut set = self.free_vars_with_priorities(f, prior_pools);
-                for a in args {
-                    set.extend(self.free_vars_with_priorities(a, prior_pools).into_iter());
-                }
-                set
+        impl<const N: usize> FoldCache<IndexSet<Rc<Term>>> for FreeVarsCache<'_, N> {
+            fn get(&self, term: &Rc<Term>) -> Option<IndexSet<Rc<Term>>> {
+                self.prior_pools
+                    .iter()
+                    .find_map(|p| p.free_vars_cache.get(term))
+                    .or_else(|| self.own.get(term))
+                    .cloned()
             }
-            Term::Op(_, args) => {
-                let mut set = IndexSet::new();
-                for a in args {
-                    set.extend(self.free_vars_with_priorities(a, prior_pools).into_iter());
-                }
-                set
-            }
-            Term::Binder(_, bindings, inner) => {
-                let mut vars = self.free_vars_with_priorities(inner, prior_pools);
-                for bound_var in bindings {
-                    let term = self.add_with_priorities(bound_var.clone().into(), prior_pools);
-                    vars.remove(&term);
-                }
-                vars
+
+            fn insert(&mut self, term: Rc<Term>, value: IndexSet<Rc<Term>>) {
+                self.own.insert(term, value);
             }
-            Term::Let(bindings, inner) => {
-                let mut vars = self.free_vars_with_priorities(inner, prior_pools);
-                for (var, value) in bindings {
-                    let sort = self.sort_with_priorities(value, prior_pools);
-                    let term = self.add_with_priorities((var.clone(), sort).into(), prior_pools);
-                    vars.remove(&term);
+        }
+
+        let mut cache = FreeVarsCache {
+            prior_pools,
+            own: std::mem::take(&mut self.free_vars_cache),
+        };
+        let result = fold_term(term, &mut cache, &mut |term, mut subterm_vars| {
+            match term.as_ref() {
+                Term::Var(..) => IndexSet::from([term.clone()]),
+                Term::Binder(_, bindings, _) => {
+                    let mut vars = subterm_vars.pop().unwrap();
+                    for bound_var in bindings {
+                        let term = self.add_with_priorities(bound_var.clone().into(), prior_pools);
+                        vars.remove(&term);
+                    }
+                    vars
                 }
-                vars
-            }
-            Term::Var(..) => {
-                let mut set = IndexSet::with_capacity(1);
-                set.insert(term.clone());
-                set
-            }
-            Term::Const(_) | Term::Sort(_) => IndexSet::new(),
-            Term::ParamOp { op: _, op_args: _, args } => {
-                let mut set = IndexSet::new();
-                for a in args {
-                    set.extend(self.free_vars_with_priorities(a, prior_pools).into_iter());
+                Term::Let(bindings, _) => {
+                    let mut vars = subterm_vars.pop().unwrap();
+                    for (var, value) in bindings {
+                        let sort = self.sort_with_priorities(value, prior_pools);
+                        let term =
+                            self.add_with_priorities((var.clone(), sort).into(), prior_pools);
+                        vars.remove(&term);
+                    }
+                    // The values are not in the scope of the `let` bindings
+                    for value_vars in subterm_vars {
+                        vars.extend(value_vars);
+                    }
+                    vars
                 }
-                set
+                _ => subterm_vars.into_iter().flatten().collect(),
             }
-        };
-        self.free_vars_cache.insert(term.clone(), set);
-        self.free_vars_cache.get(term).unwrap().clone()
+        });
+        self.free_vars_cache = cache.own;
+        result
+    }
+}
+
+impl TermPoolView for PrimitivePool {
+    fn sort(&self, term: &Rc<Term>) -> Rc<Term> {
+        self.cached_sort(term)
+            .expect("the sort of a term is computed when it is added to the pool")
+    }
+
+    fn get(&self, term: &Term) -> Option<Rc<Term>> {
+        self.storage.get(term).cloned()
     }
 }
 
 impl TermPool for PrimitivePool {
-    fn add(&mut self, term: Term) -> Rc<Term> {
-        let term = self.storage.add(term);
-        self.compute_sort(&term);
-        term
+    fn as_view(&self) -> &dyn TermPoolView {
+        self
     }
 
-    fn sort(&self, term: &Rc<Term>) -> Rc<Term> {
-        self.sorts_cache[term].clone()
+    fn add(&mut self, term: Term) -> Rc<Term> {
+        let (id, term) = match &mut self.alpha_classes {
+            Some(classes) if matches!(term, Term::Binder(..)) => {
+                let term = classes.add(&mut self.storage, term);
+                (self.storage.id_of(&term), term)
+            }
+            _ => {
+                let (id, term) = self.storage.add_with_id(term);
+                (Some(id), term)
+            }
+        };
+        self.compute_sort_with_id(&term, id);
+        crate::term_trace::construction(&term);
+        term
     }
 
     fn free_vars(&mut self, term: &Rc<Term>) -> IndexSet<Rc<Term>> {
         self.free_vars_with_priorities(term, [])
     }
+
+    fn fresh_name(&mut self, name: &str, is_taken: &dyn Fn(&str) -> bool) -> String {
+        let result = self
+            .fresh_names
+            .generate(name, is_taken, &self.reserved_names);
+        if let Some(log) = &mut self.rename_log {
+            if result != name {
+                log.push((name.to_owned(), result.clone()));
+            }
+        }
+        result
+    }
 }
@@ -0,0 +1,242 @@
+//! A term pool that can be shared between threads, so many workers can hash cons terms into the
+//! same pool concurrently.
+
+use super::{compute_sort, storage::Storage, FreshNameScheme, FreshNames, TermPool, TermPoolView};
+use crate::ast::{Rc, Sort, Term};
+use indexmap::{IndexMap, IndexSet};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{Mutex, RwLock},
+};
+
+/// The number of shards the terms are split into. Threads only contend with each other when adding
+/// new terms to the same shard.
+const NUM_SHARDS: usize = 32;
+
+#[derive(Debug, Default)]
+struct Shard {
+    storage: Storage,
+    sorts_cache: IndexMap<Rc<Term>, Rc<Term>>,
+    free_vars_cache: IndexMap<Rc<Term>, IndexSet<Rc<Term>>>,
+}
+
+/// A term pool that is `Sync`, and can therefore be used by many threads at once.
+///
+/// The terms are split into shards according to their hash, and each shard is behind its own
+/// `RwLock`. Looking up a term only takes a read lock, and the write lock of a shard is only taken
+/// when adding a term that is not yet in the pool. Since `TermPool` methods take `&mut self`, the
+/// trait is implemented for `&SharedPool`, and each thread can use its own reference to the pool.
+#[derive(Debug)]
+pub struct SharedPool {
+    shards: Vec<RwLock<Shard>>,
+
+    /// Names that can't be chosen by [`TermPool::fresh_name`].
+    reserved_names: RwLock<IndexSet<String>>,
+
+    fresh_names: Mutex<FreshNames>,
+}
+
+impl Default for SharedPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedPool {
+    pub fn new() -> Self {
+        Self {
+            shards: (0..NUM_SHARDS).map(|_| RwLock::default()).collect(),
+            reserved_names: RwLock::default(),
+            fresh_names: Mutex::default(),
+        }
+    }
+
+    fn shard(&self, term: &Term) -> &RwLock<Shard> {
+        let mut hasher = DefaultHasher::new();
+        term.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % NUM_SHARDS]
+    }
+
+    /// Returns the number of terms currently stored in the pool.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|s| s.read().unwrap().storage.len())
+            .sum()
+    }
+
+    /// Returns `true` if the pool contains no terms.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reserves a name, so it is never chosen when renaming bound variables.
+    pub fn reserve_name(&self, name: String) {
+        self.reserved_names.write().unwrap().insert(name);
+    }
+
+    /// Sets how new names are built when renaming bound variables. See [`FreshNameScheme`].
+    pub fn set_fresh_name_scheme(&self, scheme: FreshNameScheme) {
+        self.fresh_names.lock().unwrap().scheme = scheme;
+    }
+
+    /// Adds a term to the pool, computing its sort. If an equal term is already in the pool, this
+    /// returns the existing allocation.
+    pub fn add_shared(&self, term: Term) -> Rc<Term> {
+        let shard = self.shard(&term);
+        if let Some(entry) = shard.read().unwrap().storage.get(&term) {
+            return entry.clone();
+        }
+
+        // The sort is computed before taking the write lock, since computing it requires looking up
+        // the sorts of the subterms, which may be in the same shard. The sort of `Type` is itself
+        let sort = match &term {
+            Term::Sort(Sort::Type) => None,
+            _ => {
+                let sort = compute_sort(&term, &mut |t| self.sort(t));
+                Some(self.add_shared(Term::Sort(sort)))
+            }
+        };
+
+        // Another thread may have added the term in the meantime, in which case `Storage::add`
+        // returns the existing allocation, and its sort is kept
+        let mut guard = shard.write().unwrap();
+        let entry = guard.storage.add(term);
+        let sort = sort.unwrap_or_else(|| entry.clone());
+        guard.sorts_cache.entry(entry.clone()).or_insert(sort);
+        entry
+    }
+
+    fn free_vars_shared(&self, term: &Rc<Term>) -> IndexSet<Rc<Term>> {
+        let shard = self.shard(term);
+        if let Some(set) = shard.read().unwrap().free_vars_cache.get(term) {
+            return set.clone();
+        }
+        let union = |terms: &mut dyn Iterator<Item = &Rc<Term>>| {
+            terms.flat_map(|t| self.free_vars_shared(t)).collect()
+        };
+        let set = match term.as_ref() {
+            Term::App(f, args) => union(&mut std::iter::once(f).chain(args)),
+            Term::Op(_, args) | Term::ParamOp { args, .. } => union(&mut args.iter()),
+            Term::Binder(_, bindings, inner) => {
+                let mut vars = self.free_vars_shared(inner);
+                for bound_var in bindings {
+                    vars.shift_remove(&self.add_shared(bound_var.clone().into()));
+                }
+                vars
+            }
+            Term::Let(bindings, inner) => {
+                let mut vars = self.free_vars_shared(inner);
+                for (var, value) in bindings {
+                    let sort = self.sort(value);
+                    vars.shift_remove(&self.add_shared((var.clone(), sort).into()));
+                }
+                vars
+            }
+            Term::Var(..) => IndexSet::from([term.clone()]),
+            Term::Const(_) | Term::Sort(_) => IndexSet::new(),
+        };
+        let mut guard = shard.write().unwrap();
+        guard
+            .free_vars_cache
+            .entry(term.clone())
+            .or_insert(set)
+            .clone()
+    }
+}
+
+impl TermPoolView for SharedPool {
+    fn sort(&self, term: &Rc<Term>) -> Rc<Term> {
+        self.shard(term).read().unwrap().sorts_cache[term].clone()
+    }
+
+    fn get(&self, term: &Term) -> Option<Rc<Term>> {
+        self.shard(term).read().unwrap().storage.get(term).cloned()
+    }
+}
+
+impl TermPoolView for &SharedPool {
+    fn sort(&self, term: &Rc<Term>) -> Rc<Term> {
+        (**self).sort(term)
+    }
+
+    fn get(&self, term: &Term) -> Option<Rc<Term>> {
+        (**self).get(term)
+    }
+}
+
+impl TermPool for &SharedPool {
+    fn as_view(&self) -> &dyn TermPoolView {
+        self
+    }
+
+    fn add(&mut self, term: Term) -> Rc<Term> {
+        self.add_shared(term)
+    }
+
+    fn free_vars(&mut self, term: &Rc<Term>) -> IndexSet<Rc<Term>> {
+        self.free_vars_shared(term)
+    }
+
+    fn fresh_name(&mut self, name: &str, is_taken: &dyn Fn(&str) -> bool) -> String {
+        let reserved = self.reserved_names.read().unwrap();
+        let mut fresh_names = self.fresh_names.lock().unwrap();
+        fresh_names.generate(name, is_taken, &reserved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::Operator, checker};
+    use std::thread;
+
+    #[test]
+    fn test_shared_pool() {
+        fn assert_sync<T: Send + Sync>(_: &T) {}
+
+        let pool = SharedPool::new();
+        assert_sync(&pool);
+        let int_sort = pool.add_shared(Term::Sort(Sort::Int));
+
+        // Every thread builds the same terms, which must end up in the same allocations
+        let results: Vec<_> = thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut pool = &pool;
+                        let x = pool.add(Term::new_var("x", int_sort.clone()));
+                        let one = pool.add(Term::new_int(1));
+                        let sum = pool.add(Term::Op(Operator::Add, vec![x.clone(), one]));
+                        let eq = pool.add(Term::Op(Operator::Equals, vec![sum.clone(), x]));
+                        assert_eq!(pool.sort(&sum), int_sort);
+                        assert_eq!(pool.sort(&eq).as_sort(), Some(&Sort::Bool));
+
+                        // Checking a step only needs a `&mut dyn TermPool`
+                        let refl = pool.add(Term::Op(Operator::Equals, vec![sum.clone(), sum]));
+                        checker::check_step(
+                            &mut pool,
+                            &checker::Config::new(),
+                            &[],
+                            "eq_reflexive",
+                            &[],
+                            &[refl],
+                        )
+                        .unwrap();
+                        eq
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert!(results.windows(2).all(|w| w[0] == w[1]));
+
+        let x = pool.get(&Term::new_var("x", int_sort.clone())).unwrap();
+        let free_vars = (&pool).free_vars(&results[0]);
+        assert_eq!(free_vars, IndexSet::from([x]));
+
+        pool.reserve_name("x".to_owned());
+        assert_eq!((&pool).fresh_name("x", &|_| false), "x'");
+    }
+}
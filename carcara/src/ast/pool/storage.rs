@@ -3,10 +3,33 @@
 //* inside the `Rc`. Instead, we store a hash set of `Rc<Term>`s, combining the key and the value
 //* into a single object. We access this hash set using a `&Term`, and if the entry is present, we
 //* clone it; otherwise, we allocate a new `Rc`.
+//*
+//* Each term is also identified by its position in the set, as a 32-bit `TermId`. The pool uses
+//* these ids to store per-term data, like the sort of each term, in plain vectors, instead of in
+//* more hash maps keyed by `Rc<Term>`. Note that this is only an index: each term is still
+//* allocated on its own, in the `Rc` that is handed out by the pool.
 
 use crate::ast::*;
 use indexmap::IndexSet;
-use std::{borrow::Borrow, hash::Hash};
+use std::{borrow::Borrow, hash::Hash, num::NonZeroU32};
+
+/// The position of a term in the storage. Ids are only changed when terms are removed from the
+/// storage, by [`Storage::retain`].
+///
+/// The id is stored plus one, so that `Option<TermId>` has the same size as `TermId`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermId(NonZeroU32);
+
+impl TermId {
+    fn new(index: usize) -> Self {
+        let id = u32::try_from(index + 1).expect("term pool has more than 2^32 - 1 terms");
+        Self(NonZeroU32::new(id).unwrap())
+    }
+
+    pub fn index(self) -> usize {
+        self.0.get() as usize - 1
+    }
+}
 
 /// Since `ast::Rc` intentionally implements hashing and equality by reference (instead of by
 /// value), we cannot safely implement `Borrow<Term>` for `Rc<Term>`, so we cannot access a
@@ -39,14 +62,19 @@ pub struct Storage(IndexSet<ByValue>);
 
 impl Storage {
     pub fn add(&mut self, term: Term) -> Rc<Term> {
+        self.add_with_id(term).1
+    }
+
+    /// Adds a term to the storage, and returns its id together with the `Rc` that references it.
+    pub fn add_with_id(&mut self, term: Term) -> (TermId, Rc<Term>) {
         // If the `hash_set_entry` feature was stable, this would be much simpler to do using
         // `get_or_insert_with` (and would avoid rehashing the term)
-        match self.0.get(&term) {
-            Some(t) => t.0.clone(),
+        match self.0.get_full(&term) {
+            Some((i, t)) => (TermId::new(i), t.0.clone()),
             None => {
                 let result = Rc::new(term);
-                self.0.insert(ByValue(result.clone()));
-                result
+                let (i, _) = self.0.insert_full(ByValue(result.clone()));
+                (TermId::new(i), result)
             }
         }
     }
@@ -55,6 +83,44 @@ impl Storage {
         self.0.get(term).map(|t| &t.0)
     }
 
+    /// Returns the id of the given term, if this exact allocation is in the storage.
+    pub fn id_of(&self, term: &Rc<Term>) -> Option<TermId> {
+        match self.0.get_full(term.as_ref()) {
+            Some((i, t)) if t.0 == *term => Some(TermId::new(i)),
+            _ => None,
+        }
+    }
+
+    pub fn get_by_id(&self, id: TermId) -> &Rc<Term> {
+        &self.0[id.index()].0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rc<Term>> {
+        self.0.iter().map(|t| &t.0)
+    }
+
+    /// Retains only the terms for which `keep` returns `true`. Returns the new id of each term,
+    /// indexed by its old id, or `None` if the term was removed.
+    pub fn retain(
+        &mut self,
+        mut keep: impl FnMut(TermId, &Rc<Term>) -> bool,
+    ) -> Vec<Option<TermId>> {
+        let mut new_ids = Vec::with_capacity(self.0.len());
+        let mut kept = 0;
+        // `IndexSet::retain` visits the terms in order, so we know the id of each term
+        self.0.retain(|ByValue(t)| {
+            let result = keep(TermId::new(new_ids.len()), t);
+            new_ids.push(result.then(|| TermId::new(kept)));
+            kept += usize::from(result);
+            result
+        });
+        new_ids
+    }
+
     // This method is only necessary for the hash consing tests
     #[cfg(test)]
     pub fn into_vec(self) -> Vec<Rc<Term>> {
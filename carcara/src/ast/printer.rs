@@ -1,10 +1,8 @@
 //! A pretty printer for Alethe proofs.
 
-use crate::{
-    ast::*,
-    parser::Token,
-    utils::{is_symbol_character, DedupIterator},
-};
+#[cfg(feature = "smt-printer")]
+use crate::utils::DedupIterator;
+use crate::{ast::*, parser::Token, utils::is_symbol_character};
 use indexmap::IndexMap;
 use std::{
     borrow::Cow,
@@ -15,21 +13,94 @@ use std::{
 
 pub static USE_SHARING_IN_TERM_DISPLAY: AtomicBool = AtomicBool::new(false);
 
-/// Prints a proof to the standard output.
-///
-/// If `use_sharing` is `true`, terms that are used multiple times will make use of sharing. The
-/// first time a novel term appears, it receives a unique name using the `:named` attribute. After
-/// that, any occurrence of that term will simply use this name, instead of printing the whole term.
+/// How terms that occur multiple times in a proof are abbreviated when printing it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SharingMode {
+    /// Terms are always printed in full.
+    #[default]
+    None,
+
+    /// The first time a shared term appears, it receives a unique name using the `:named`
+    /// attribute. After that, any occurrence of that term will simply use this name, instead of
+    /// printing the whole term.
+    Named,
+
+    /// Each shared term is introduced by a `define-fun` command before the first step of the
+    /// proof, and every occurrence of that term uses the defined name.
+    DefineFun,
+}
+
+/// Options that control how proofs are printed.
+#[derive(Debug, Clone)]
+pub struct PrintOptions {
+    /// How terms that occur multiple times are shared.
+    pub sharing: SharingMode,
+
+    /// Only terms that occur more than this number of times in the proof are shared. Occurrences
+    /// inside a term are only counted once, even if that term itself occurs multiple times.
+    pub sharing_threshold: usize,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            sharing: SharingMode::None,
+            sharing_threshold: 1,
+        }
+    }
+}
+
+impl PrintOptions {
+    /// Constructs the default printing options, which don't use sharing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sharing(mut self, value: SharingMode) -> Self {
+        self.sharing = value;
+        self
+    }
+
+    pub fn sharing_threshold(mut self, value: usize) -> Self {
+        self.sharing_threshold = value;
+        self
+    }
+}
+
+/// Prints a proof to the standard output, using the given options.
 pub fn print_proof(
     pool: &mut PrimitivePool,
     prelude: &ProblemPrelude,
     proof: &Proof,
-    use_sharing: bool,
+    options: &PrintOptions,
 ) -> io::Result<()> {
     let mut stdout = io::stdout();
-    AlethePrinter::new(pool, prelude, use_sharing, &mut stdout).write_proof(proof)
+    AlethePrinter::new(pool, prelude, options, &mut stdout).write_proof(proof)
+}
+
+/// Writes a proof to `dest`, using the given options.
+pub fn write_proof(
+    pool: &mut PrimitivePool,
+    prelude: &ProblemPrelude,
+    proof: &Proof,
+    dest: &mut dyn io::Write,
+    options: &PrintOptions,
+) -> io::Result<()> {
+    AlethePrinter::new(pool, prelude, options, dest).write_proof(proof)
+}
+
+/// The printing options used when building SMT-LIB problems that are sent to external solvers.
+#[cfg(feature = "smt-printer")]
+fn smt_print_options(use_sharing: bool) -> PrintOptions {
+    let sharing = if use_sharing {
+        SharingMode::Named
+    } else {
+        SharingMode::None
+    };
+    PrintOptions::new().sharing(sharing)
 }
 
+#[cfg(feature = "smt-printer")]
 /// Given the conclusion clause of a `lia_generic` step, this method will write to `dest` the
 /// corresponding SMT problem instance.
 pub fn write_lia_smt_instance(
@@ -39,7 +110,7 @@ pub fn write_lia_smt_instance(
     clause: &[Rc<Term>],
     use_sharing: bool,
 ) -> io::Result<()> {
-    let mut printer = AlethePrinter::new(pool, prelude, use_sharing, dest);
+    let mut printer = AlethePrinter::new(pool, prelude, &smt_print_options(use_sharing), dest);
     // We have to override the default prefix "@p_" because symbols starting with "@" are reserved
     // in SMT-LIB.
     printer.term_sharing_variable_prefix = "p_";
@@ -50,6 +121,23 @@ pub fn write_lia_smt_instance(
     printer.write_lia_smt_instance(clause)
 }
 
+#[cfg(feature = "smt-printer")]
+/// Writes to `dest` an `assert` command for each of the given terms. Like
+/// `write_lia_smt_instance`, this is meant to be used when building SMT-LIB problems that are
+/// sent to external solvers.
+pub fn write_smt_assertions(
+    pool: &mut PrimitivePool,
+    prelude: &ProblemPrelude,
+    dest: &mut dyn io::Write,
+    terms: &[Rc<Term>],
+    use_sharing: bool,
+) -> io::Result<()> {
+    let mut printer = AlethePrinter::new(pool, prelude, &smt_print_options(use_sharing), dest);
+    printer.term_sharing_variable_prefix = "p_";
+    printer.smt_lib_strict = true;
+    printer.write_smt_assertions(terms)
+}
+
 trait PrintProof {
     fn write_proof(&mut self, proof: &Proof) -> io::Result<()>;
 }
@@ -69,37 +157,17 @@ impl PrintWithSharing for Rc<Term> {
         if let Some(name) = p.defined_constants.get(self) {
             return write!(p.inner, "{}", quote_symbol(name));
         }
-        if let Some(indices) = &mut p.term_indices {
-            // There are a few cases where we don't use sharing when printing a term:
-            let cannot_use_sharing =
-                // - Terminal terms (i.e., constants or variables) could in theory be shared,
-                // but, since they are very small, it's not worth it to give them a name.
-                self.is_const() || self.is_var()
-                // - Sorts are represented as terms, but they are not actually terms in the grammar,
-                // so we can't use the `(! ... :named ...)` syntax to give them a name.
-                || self.is_sort()
-                // - If a term is only used once in the proof, there is no reason to give it a
-                // name. We detect this case by checking if the number of references to it's `Rc` is
-                // no more than 3: one in the pool storage, one in the pool sorts cache, and one in
-                // the proof itself.
-                // TODO: this is a terrible way of checking if it is only used once in the proof,
-                // as it depends on internal implementation details of the term pool.
-                || Rc::strong_count(self) <= 3
-                // - Terms which are not closed, that is, terms which have free variables besides
-                // the global variables, cannot be shared
-                || !self.is_closed(p.pool, &p.global_vars);
-
-            if !cannot_use_sharing {
-                return if let Some(i) = indices.get(self) {
-                    write!(p.inner, "{}{}", p.term_sharing_variable_prefix, i)
-                } else {
-                    let i = indices.len();
-                    indices.insert(self.clone(), i);
-                    write!(p.inner, "(! ")?;
-                    p.write_raw_term(self)?;
-                    write!(p.inner, " :named {}{})", p.term_sharing_variable_prefix, i)
-                };
-            }
+        if p.term_indices.is_some() && p.is_shareable(self) {
+            let indices = p.term_indices.as_mut().unwrap();
+            return if let Some(i) = indices.get(self) {
+                write!(p.inner, "{}{}", p.term_sharing_variable_prefix, i)
+            } else {
+                let i = indices.len();
+                indices.insert(self.clone(), i);
+                write!(p.inner, "(! ")?;
+                p.write_raw_term(self)?;
+                write!(p.inner, " :named {}{})", p.term_sharing_variable_prefix, i)
+            };
         }
         p.write_raw_term(self)
     }
@@ -149,6 +217,65 @@ struct AlethePrinter<'a> {
     global_vars: HashSet<Rc<Term>>,
     defined_constants: HashMap<Rc<Term>, String>,
     smt_lib_strict: bool,
+    sharing: SharingMode,
+    sharing_threshold: usize,
+    occurrences: Occurrences,
+}
+
+/// Counts how many times each term occurs. A term is only traversed the first time it is found,
+/// so the occurrences inside a term are only counted once, even if that term occurs many times.
+#[derive(Default)]
+struct Occurrences(HashMap<Rc<Term>, usize>);
+
+impl Occurrences {
+    fn visit(&mut self, term: &Rc<Term>) {
+        let count = self.0.entry(term.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            for_each_child(term, |child| self.visit(child));
+        }
+    }
+
+    fn get(&self, term: &Rc<Term>) -> usize {
+        self.0.get(term).copied().unwrap_or(0)
+    }
+}
+
+/// Calls `f` on each direct subterm of `term`. Sorts are not considered subterms.
+fn for_each_child(term: &Term, mut f: impl FnMut(&Rc<Term>)) {
+    match term {
+        Term::Const(_) | Term::Var(..) | Term::Sort(_) => (),
+        Term::App(func, args) => {
+            f(func);
+            args.iter().for_each(f);
+        }
+        Term::Op(_, args) => args.iter().for_each(f),
+        Term::Binder(_, _, body) => f(body),
+        Term::Let(bindings, body) => {
+            bindings.iter().for_each(|(_, value)| f(value));
+            f(body);
+        }
+        Term::ParamOp { op_args, args, .. } => op_args.iter().chain(args).for_each(f),
+    }
+}
+
+/// Returns every term in the proof, in the order in which they are printed.
+fn proof_terms(proof: &Proof) -> Vec<&Rc<Term>> {
+    let mut terms: Vec<_> = proof.constant_definitions.iter().map(|(_, t)| t).collect();
+    for command in proof.iter() {
+        match command {
+            ProofCommand::Assume { term, .. } => terms.push(term),
+            ProofCommand::Step(s) => terms.extend(s.clause.iter().chain(&s.args)),
+            ProofCommand::Subproof(s) => {
+                for arg in &s.args {
+                    if let AnchorArg::Assign(_, value) = arg {
+                        terms.push(value);
+                    }
+                }
+            }
+        }
+    }
+    terms
 }
 
 impl<'a> PrintProof for AlethePrinter<'a> {
@@ -166,6 +293,14 @@ impl<'a> PrintProof for AlethePrinter<'a> {
             .cloned()
             .map(|(name, term)| (term, name))
             .collect();
+        if self.sharing != SharingMode::None {
+            for term in proof_terms(proof) {
+                self.occurrences.visit(term);
+            }
+        }
+        if self.sharing == SharingMode::DefineFun {
+            self.write_sharing_definitions(proof)?;
+        }
         let mut iter = proof.iter();
         while let Some(command) = iter.next() {
             match command {
@@ -219,26 +354,83 @@ impl<'a> AlethePrinter<'a> {
     pub fn new(
         pool: &'a mut PrimitivePool,
         prelude: &ProblemPrelude,
-        use_sharing: bool,
+        options: &PrintOptions,
         dest: &'a mut dyn io::Write,
     ) -> Self {
-        let global_variables = if use_sharing {
+        let global_variables = if options.sharing == SharingMode::None {
+            HashSet::new()
+        } else {
             prelude
                 .function_declarations
                 .iter()
                 .map(|var| pool.add(var.clone().into()))
                 .collect()
-        } else {
-            HashSet::new()
         };
         Self {
             pool,
             inner: dest,
-            term_indices: use_sharing.then(IndexMap::new),
+            term_indices: (options.sharing == SharingMode::Named).then(IndexMap::new),
             term_sharing_variable_prefix: "@p_",
             global_vars: global_variables,
             defined_constants: HashMap::new(),
             smt_lib_strict: false,
+            sharing: options.sharing,
+            sharing_threshold: options.sharing_threshold,
+            occurrences: Occurrences::default(),
+        }
+    }
+
+    /// Returns `true` if the term may be shared, that is, given a name that is used instead of
+    /// printing the whole term.
+    fn is_shareable(&mut self, term: &Rc<Term>) -> bool {
+        // There are a few cases where we don't use sharing when printing a term:
+        let cannot_use_sharing =
+            // - Terminal terms (i.e., constants or variables) could in theory be shared, but, since
+            // they are very small, it's not worth it to give them a name.
+            term.is_const() || term.is_var()
+            // - Sorts are represented as terms, but they are not actually terms in the grammar, so
+            // we can't give them a name.
+            || term.is_sort()
+            // - If a term doesn't occur often enough, there is no reason to give it a name.
+            || self.occurrences.get(term) <= self.sharing_threshold
+            // - Terms which are not closed, that is, terms which have free variables besides the
+            // global variables, cannot be shared
+            || !term.is_closed(self.pool, &self.global_vars);
+        !cannot_use_sharing
+    }
+
+    /// Writes a `define-fun` command for each term in the proof that should be shared. The
+    /// definitions are written in an order such that every term is defined after its subterms.
+    fn write_sharing_definitions(&mut self, proof: &Proof) -> io::Result<()> {
+        let mut seen = HashSet::new();
+        let mut to_define = Vec::new();
+        for term in proof_terms(proof) {
+            self.collect_shared_terms(term, &mut seen, &mut to_define);
+        }
+        for (i, term) in to_define.into_iter().enumerate() {
+            let name = format!("{}{}", self.term_sharing_variable_prefix, i);
+            write!(self.inner, "(define-fun {} () ", name)?;
+            self.pool.sort(&term).print_with_sharing(self)?;
+            write!(self.inner, " ")?;
+            term.print_with_sharing(self)?;
+            writeln!(self.inner, ")")?;
+            self.defined_constants.insert(term, name);
+        }
+        Ok(())
+    }
+
+    fn collect_shared_terms(
+        &mut self,
+        term: &Rc<Term>,
+        seen: &mut HashSet<Rc<Term>>,
+        acc: &mut Vec<Rc<Term>>,
+    ) {
+        if !seen.insert(term.clone()) || self.defined_constants.contains_key(term) {
+            return;
+        }
+        for_each_child(term, |child| self.collect_shared_terms(child, seen, acc));
+        if self.is_shareable(term) {
+            acc.push(term.clone());
         }
     }
 
@@ -378,7 +570,14 @@ impl<'a> AlethePrinter<'a> {
         Ok(())
     }
 
+    #[cfg(feature = "smt-printer")]
     fn write_lia_smt_instance(&mut self, clause: &[Rc<Term>]) -> io::Result<()> {
+        if self.sharing != SharingMode::None {
+            clause
+                .iter()
+                .dedup()
+                .for_each(|t| self.occurrences.visit(t));
+        }
         for term in clause.iter().dedup() {
             write!(self.inner, "(assert (not ")?;
             term.print_with_sharing(self)?;
@@ -386,6 +585,19 @@ impl<'a> AlethePrinter<'a> {
         }
         Ok(())
     }
+
+    #[cfg(feature = "smt-printer")]
+    fn write_smt_assertions(&mut self, terms: &[Rc<Term>]) -> io::Result<()> {
+        if self.sharing != SharingMode::None {
+            terms.iter().dedup().for_each(|t| self.occurrences.visit(t));
+        }
+        for term in terms.iter().dedup() {
+            write!(self.inner, "(assert ")?;
+            term.print_with_sharing(self)?;
+            writeln!(self.inner, ")")?;
+        }
+        Ok(())
+    }
 }
 
 fn write_s_expr<H, T>(f: &mut fmt::Formatter, head: H, tail: &[T]) -> fmt::Result
@@ -447,7 +659,13 @@ impl fmt::Display for Term {
             global_vars: HashSet::new(),
             defined_constants: HashMap::new(),
             smt_lib_strict: false,
+            sharing: SharingMode::Named,
+            sharing_threshold: 1,
+            occurrences: Occurrences::default(),
         };
+        if use_sharing {
+            for_each_child(self, |child| printer.occurrences.visit(child));
+        }
         printer.write_raw_term(self).unwrap();
         let result = std::str::from_utf8(&buf).unwrap();
         write!(f, "{}", result)
@@ -522,6 +740,8 @@ impl fmt::Display for Sort {
             Sort::RegLan => write!(f, "RegLan"),
             Sort::Array(x, y) => write_s_expr(f, "Array", &[x, y]),
             Sort::BitVec(w) => write!(f, "(_ BitVec {})", w),
+            Sort::FloatingPoint(e, s) => write!(f, "(_ FloatingPoint {} {})", e, s),
+            Sort::RoundingMode => write!(f, "RoundingMode"),
             Sort::RareList => unreachable!("RARE list sort should never be displayed"),
             Sort::Type => write!(f, "Type"),
         }
@@ -541,6 +761,8 @@ impl fmt::Display for Token {
                 write!(f, "#b{v:0>w$b}", v = value, w = *width as usize)
             }
             Token::String(s) => write!(f, "\"{}\"", escape_string(s)),
+            Token::SharedTermDefinition(n) => write!(f, "#{}:", n),
+            Token::SharedTermReference(n) => write!(f, "#{}", n),
             Token::ReservedWord(r) => write!(f, "{}", r),
             Token::Eof => write!(f, "EOF"),
         }
@@ -555,6 +777,40 @@ impl fmt::Display for ProblemPrelude {
             writeln!(f, "(declare-sort {} {})", quote_symbol(name), arity)?;
         }
 
+        // All datatypes are declared in a single command, since they may be mutually recursive
+        if !self.datatype_declarations.is_empty() {
+            write!(f, "(declare-datatypes (")?;
+            for (i, datatype) in self.datatype_declarations.iter().enumerate() {
+                let sep = if i == 0 { "" } else { " " };
+                write!(f, "{}({} 0)", sep, quote_symbol(&datatype.name))?;
+            }
+            write!(f, ") (")?;
+            for (i, datatype) in self.datatype_declarations.iter().enumerate() {
+                write!(f, "{}(", if i == 0 { "" } else { " " })?;
+                for (j, c) in datatype.constructors.iter().enumerate() {
+                    let sep = if j == 0 { "" } else { " " };
+                    write!(
+                        f,
+                        "{}({}",
+                        sep,
+                        quote_symbol(c.constructor.as_var().unwrap())
+                    )?;
+                    for selector in &c.selectors {
+                        let Term::Var(name, sort) = selector.as_ref() else {
+                            unreachable!()
+                        };
+                        let Some(Sort::Function(sorts)) = sort.as_sort() else {
+                            unreachable!()
+                        };
+                        write!(f, " ({} {})", quote_symbol(name), sorts[1])?;
+                    }
+                    write!(f, ")")?;
+                }
+                write!(f, ")")?;
+            }
+            writeln!(f, "))")?;
+        }
+
         for (name, sort) in &self.function_declarations {
             write!(f, "(declare-fun {} ", quote_symbol(name))?;
             if let Sort::Function(sorts) = sort.as_sort().unwrap() {
@@ -606,7 +862,8 @@ mod tests {
             parser::parse_instance(definitions, proof, parser::Config::new()).unwrap();
 
         let mut buf = Vec::new();
-        AlethePrinter::new(&mut pool, &problem.prelude, true, &mut buf)
+        let options = PrintOptions::new().sharing(SharingMode::Named);
+        AlethePrinter::new(&mut pool, &problem.prelude, &options, &mut buf)
             .write_proof(&proof)
             .unwrap();
 
@@ -615,4 +872,70 @@ mod tests {
 
         assert_eq!(expected, std::str::from_utf8(&buf).unwrap());
     }
+
+    #[test]
+    fn test_define_fun_sharing() {
+        use crate::parser;
+
+        let definitions = "
+            (declare-const a Bool)
+            (declare-const b Bool)
+        ";
+        let proof = "
+            (step t1 (cl (and (or a b) (not (or a b)))) :rule hole)
+            (step t2 (cl (or a b) (and (or a b) (not (or a b)))) :rule hole)
+            (step t3 (cl (and (= 1 2) (= 1 2) (= 1 2))) :rule hole)
+        ";
+        let (problem, proof, mut pool) = parser::parse_instance(
+            definitions.as_bytes(),
+            proof.as_bytes(),
+            parser::Config::new(),
+        )
+        .unwrap();
+
+        let mut print = |options: &PrintOptions, proof: &Proof| {
+            let mut buf = Vec::new();
+            write_proof(&mut pool, &problem.prelude, proof, &mut buf, options).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+
+        let options = PrintOptions::new().sharing(SharingMode::DefineFun);
+        let expected = "\
+            (define-fun @p_0 () Bool (or a b))\n\
+            (define-fun @p_1 () Bool (and @p_0 (not @p_0)))\n\
+            (define-fun @p_2 () Bool (= 1 2))\n\
+            (step t1 (cl @p_1) :rule hole)\n\
+            (step t2 (cl @p_0 @p_1) :rule hole)\n\
+            (step t3 (cl (and @p_2 @p_2 @p_2)) :rule hole)\n\
+        ";
+        let printed = print(&options, &proof);
+        assert_eq!(expected, printed);
+
+        // The `and` term in `t1` occurs only twice, so it is no longer shared
+        let options = options.sharing_threshold(2);
+        let expected = "\
+            (define-fun @p_0 () Bool (or a b))\n\
+            (define-fun @p_1 () Bool (= 1 2))\n\
+            (step t1 (cl (and @p_0 (not @p_0))) :rule hole)\n\
+            (step t2 (cl @p_0 (and @p_0 (not @p_0))) :rule hole)\n\
+            (step t3 (cl (and @p_1 @p_1 @p_1)) :rule hole)\n\
+        ";
+        assert_eq!(expected, print(&options, &proof));
+
+        // The definitions are expanded when the proof is parsed again
+        let (_, reparsed, _) = parser::parse_instance(
+            definitions.as_bytes(),
+            printed.as_bytes(),
+            parser::Config::new(),
+        )
+        .unwrap();
+        let reparsed = Proof {
+            constant_definitions: Vec::new(),
+            commands: reparsed.commands,
+        };
+        assert_eq!(
+            print(&PrintOptions::new(), &proof),
+            print(&PrintOptions::new(), &reparsed)
+        );
+    }
 }
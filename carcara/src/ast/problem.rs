@@ -1,4 +1,4 @@
-use super::{Rc, Term};
+use super::{Rc, Sort, Term};
 use indexmap::IndexSet;
 
 /// An SMT problem in the SMT-LIB format.
@@ -30,6 +30,15 @@ pub struct ProblemPrelude {
     /// The function declarations, each represented by its name and body.
     pub(crate) function_declarations: Vec<(String, Rc<Term>)>,
 
+    /// The datatype declarations, from `declare-datatype` and `declare-datatypes` commands. The
+    /// constructors and selectors of these datatypes are not included in the function
+    /// declarations.
+    pub(crate) datatype_declarations: Vec<DatatypeDef>,
+
+    /// The sort aliases, from `define-sort` commands. These are expanded while parsing, so they are
+    /// kept only for reference, and are not printed with the prelude.
+    pub(crate) sort_definitions: Vec<SortDefinition>,
+
     /// The problem's logic string, if it exists.
     pub(crate) logic: Option<String>,
 }
@@ -38,4 +47,87 @@ impl ProblemPrelude {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Returns the problem's logic string, if it was declared with `set-logic`.
+    pub fn logic(&self) -> Option<&str> {
+        self.logic.as_deref()
+    }
+
+    /// Returns the sorts declared in the problem with `declare-sort`, with their arities.
+    pub fn sort_declarations(&self) -> &[(String, usize)] {
+        &self.sort_declarations
+    }
+
+    /// Returns the sort aliases defined in the problem with `define-sort`.
+    pub fn sort_definitions(&self) -> &[SortDefinition] {
+        &self.sort_definitions
+    }
+
+    /// Returns the datatypes declared in the problem.
+    pub fn datatypes(&self) -> &[DatatypeDef] {
+        &self.datatype_declarations
+    }
+
+    /// Returns the datatype with the given sort, if it was declared in the problem.
+    pub fn datatype(&self, sort: &Sort) -> Option<&DatatypeDef> {
+        match sort {
+            Sort::Atom(name, args) if args.is_empty() => {
+                self.datatype_declarations.iter().find(|d| d.name == *name)
+            }
+            _ => None,
+        }
+    }
+
+    /// If the given term is a datatype constructor, returns its datatype and its definition.
+    pub fn constructor(&self, term: &Rc<Term>) -> Option<(&DatatypeDef, &ConstructorDef)> {
+        self.datatype_declarations.iter().find_map(|d| {
+            let c = d.constructors.iter().find(|c| c.constructor == *term)?;
+            Some((d, c))
+        })
+    }
+
+    /// If the given term is a datatype selector, returns the constructor it belongs to and its
+    /// index among the constructor's selectors.
+    pub fn selector(&self, term: &Rc<Term>) -> Option<(&ConstructorDef, usize)> {
+        self.datatype_declarations
+            .iter()
+            .flat_map(|d| &d.constructors)
+            .find_map(|c| Some((c, c.selectors.iter().position(|s| s == term)?)))
+    }
+}
+
+/// A sort alias defined in the problem with `define-sort`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortDefinition {
+    pub name: String,
+
+    /// The sort parameters. In the body, each parameter is represented by an atom sort with no
+    /// arguments.
+    pub params: Vec<String>,
+
+    /// The sort that the alias stands for.
+    pub body: Rc<Term>,
+}
+
+/// A datatype declared in the problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatatypeDef {
+    pub name: String,
+
+    /// The datatype sort.
+    pub sort: Rc<Term>,
+
+    pub constructors: Vec<ConstructorDef>,
+}
+
+/// A constructor of a datatype.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstructorDef {
+    /// The constructor, as a variable. For constructors with no selectors, its sort is the
+    /// datatype sort. Otherwise, it is a function sort from the selector sorts to the datatype sort.
+    pub constructor: Rc<Term>,
+
+    /// The selectors of the constructor, as variables with function sorts from the datatype sort
+    /// to the sort of the field.
+    pub selectors: Vec<Rc<Term>>,
 }
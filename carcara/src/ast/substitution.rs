@@ -1,11 +1,16 @@
 //! Algorithms for creating and applying capture-avoiding substitutions over terms.
 
-use super::{Binder, BindingList, Rc, Sort, SortedVar, Term, TermPool};
+use super::{visit::map_subterms, Binder, BindingList, Rc, SortedVar, Term, TermPool};
 use indexmap::{IndexMap, IndexSet};
 use thiserror::Error;
 
 /// The error type for errors when constructing or applying substitutions.
 #[derive(Debug, PartialEq, Eq, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", content = "data")
+)]
 pub enum SubstitutionError {
     /// A term in the left-hand side of the substitution was not a variable.
     #[error("term in the left-hand side of substitution is not a variable: '{0}'")]
@@ -34,6 +39,11 @@ type SubstitutionResult<T> = Result<T, SubstitutionError>;
 /// substitutions are also capture-avoiding. This is done by renaming the binder variable when
 /// necessary before applying the substitution. In the earlier example, the resulting term would
 /// actually be `(forall ((y' Int)) (= y y'))`.
+///
+/// Substitutions are applied simultaneously: all mappings are applied at once, and the terms that
+/// the variables are mapped to are not themselves substituted. For example, applying the
+/// substitution `{x -> y, y -> x}` to the term `(= x y)` results in `(= y x)`. To apply one
+/// substitution after another, the two can be combined with [`Substitution::compose`].
 #[derive(Debug, Clone)]
 pub struct Substitution {
     /// The substitution's mappings.
@@ -91,9 +101,25 @@ impl Substitution {
         self.map.is_empty()
     }
 
+    /// Returns the number of mappings in the substitution.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the term that `x` is mapped to, if there is one.
+    pub fn get(&self, x: &Rc<Term>) -> Option<&Rc<Term>> {
+        self.map.get(x)
+    }
+
+    /// Returns an iterator over the mappings of the substitution, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (&Rc<Term>, &Rc<Term>)> {
+        self.map.iter()
+    }
+
     /// Extends the substitution by adding a new mapping from `x` to `t`. This returns an error if
-    /// the sorts of the given terms are not the same, or if `x` is not a variable term.
-    pub(crate) fn insert(
+    /// the sorts of the given terms are not the same, or if `x` is not a variable term. If `x` was
+    /// already mapped to a term, that mapping is replaced.
+    pub fn insert(
         &mut self,
         pool: &mut dyn TermPool,
         x: Rc<Term>,
@@ -128,15 +154,55 @@ impl Substitution {
         Ok(())
     }
 
-    /// Removes a mapping from the substitution.
+    /// Removes the mapping for `x` from the substitution, and returns the term it was mapped to,
+    /// if there was one.
+    ///
+    /// This will clear `self.should_be_renamed` and the cache, such that they might need to be
+    /// recomputed later. Therefore, you should avoid using this method if possible.
+    pub fn remove(&mut self, x: &Rc<Term>) -> Option<Rc<Term>> {
+        let removed = self.map.shift_remove(x);
+        if removed.is_some() {
+            self.invalidate();
+        }
+        removed
+    }
+
+    /// Retains only the mappings for which `keep` returns `true`, removing all others. Like
+    /// [`Substitution::remove`], this clears the cache if any mapping is removed.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Rc<Term>, &Rc<Term>) -> bool) {
+        let len = self.map.len();
+        self.map.retain(|x, t| keep(x, t));
+        if self.map.len() != len {
+            self.invalidate();
+        }
+    }
+
+    /// Composes this substitution with `other`, such that applying the resulting substitution is
+    /// equivalent to applying `self` and then applying `other`, up to the renaming of bound
+    /// variables.
     ///
-    /// This will clear `self.should_be_renamed`, such that it might need to be recomputed later.
-    /// Therefore, you should avoid using this method if possible.
-    pub(super) fn remove(&mut self, x: &Rc<Term>) {
-        let was_present = self.map.remove(x).is_some();
-        if was_present {
-            self.should_be_renamed = None;
+    /// The result maps each variable `x` mapped by `self` to the result of applying `other` to the
+    /// term `x` was mapped to, and each variable only mapped by `other` to the term it is mapped to
+    /// in `other`. Mappings that become reflexive, like `x -> x`, are removed.
+    pub fn compose(&mut self, pool: &mut dyn TermPool, other: &Self) {
+        let mut other = other.clone();
+        let mut map: IndexMap<_, _> = self
+            .map
+            .iter()
+            .map(|(x, t)| (x.clone(), other.apply(pool, t)))
+            .collect();
+        for (y, u) in &other.map {
+            map.entry(y.clone()).or_insert_with(|| u.clone());
         }
+        map.retain(|x, t| x != t);
+        self.map = map;
+        self.invalidate();
+    }
+
+    /// Clears the cache and `self.should_be_renamed`, after mappings were changed or removed.
+    fn invalidate(&mut self) {
+        self.should_be_renamed = None;
+        self.cache.clear();
     }
 
     /// Computes which binder variables will need to be renamed, and stores the result in
@@ -179,17 +245,16 @@ impl Substitution {
         self.should_be_renamed = Some(should_be_renamed);
     }
 
-    /// Applies the substitution to `term`, and returns the result as a new term.
+    /// Applies the substitution to `term`, and returns the result as a new term. All mappings are
+    /// applied simultaneously, and the result is capture-avoiding: bound variables in `term` are
+    /// renamed if they would capture a free variable introduced by the substitution.
     pub fn apply(&mut self, pool: &mut dyn TermPool, term: &Rc<Term>) -> Rc<Term> {
-        macro_rules! apply_to_sequence {
-            ($sequence:expr) => {
-                $sequence
-                    .iter()
-                    .map(|a| self.apply(pool, a))
-                    .collect::<Vec<_>>()
-            };
-        }
+        let result = self.apply_inner(pool, term);
+        crate::term_trace::substitution(self.map.iter(), term, &result);
+        result
+    }
 
+    fn apply_inner(&mut self, pool: &mut dyn TermPool, term: &Rc<Term>) -> Rc<Term> {
         if let Some(t) = self.cache.get(term) {
             return t.clone();
         }
@@ -198,15 +263,6 @@ impl Substitution {
         }
 
         let result = match term.as_ref() {
-            Term::App(func, args) => {
-                let new_args = apply_to_sequence!(args);
-                let new_func = self.apply(pool, func);
-                pool.add(Term::App(new_func, new_args))
-            }
-            Term::Op(op, args) => {
-                let new_args = apply_to_sequence!(args);
-                pool.add(Term::Op(*op, new_args))
-            }
             Term::Binder(binder, binding_list, inner) => {
                 self.apply_to_binder(pool, term, *binder, binding_list.as_ref(), inner)
             }
@@ -214,33 +270,16 @@ impl Substitution {
                 let (new_bindings, mut renaming) =
                     self.rename_binding_list(pool, binding_list, true);
                 let new_term = if renaming.is_empty() {
-                    self.apply(pool, inner)
+                    self.apply_inner(pool, inner)
                 } else {
                     // If there are variables that would be captured by the substitution, we need
                     // to rename them first
-                    let renamed = renaming.apply(pool, inner);
-                    self.apply(pool, &renamed)
+                    let renamed = renaming.apply_inner(pool, inner);
+                    self.apply_inner(pool, &renamed)
                 };
                 pool.add(Term::Let(new_bindings, new_term))
             }
-            Term::Const(_) | Term::Var(..) => term.clone(),
-            Term::ParamOp { op, op_args, args } => {
-                let new_args = apply_to_sequence!(args);
-                pool.add(Term::ParamOp {
-                    op: *op,
-                    op_args: op_args.clone(),
-                    args: new_args,
-                })
-            }
-            Term::Sort(Sort::Atom(sort, args)) => {
-                let new_args = apply_to_sequence!(args);
-                pool.add(Term::Sort(Sort::Atom(sort.clone(), new_args)))
-            }
-            Term::Sort(Sort::Array(x, y)) => {
-                let [x, y] = [x, y].map(|s| self.apply(pool, s));
-                pool.add(Term::Sort(Sort::Array(x, y)))
-            }
-            Term::Sort(_) => term.clone(),
+            _ => map_subterms(pool, term, |pool, t| self.apply_inner(pool, t)),
         };
 
         // Since frequently a term will have more than one identical subterms, we insert the
@@ -300,12 +339,12 @@ impl Substitution {
 
         let (new_bindings, mut renaming) = self.rename_binding_list(pool, binding_list, false);
         let new_term = if renaming.is_empty() {
-            self.apply(pool, inner)
+            self.apply_inner(pool, inner)
         } else {
             // If there are variables that would be captured by the substitution, we need
             // to rename them first
-            let renamed = renaming.apply(pool, inner);
-            self.apply(pool, &renamed)
+            let renamed = renaming.apply_inner(pool, inner);
+            self.apply_inner(pool, &renamed)
         };
         pool.add(Term::Binder(binder, new_bindings, new_term))
     }
@@ -314,8 +353,8 @@ impl Substitution {
     /// captured by this substitution to a new, arbitrary name. Returns that substitution, and the
     /// new binding list, with the bindings renamed. If no variable needs to be renamed, this just
     /// returns a clone of the binding list and an empty substitution. The name chosen when renaming
-    /// a variable is the old name with `'` appended. If the binding list is a "value" list, like in
-    /// a `let` or `lambda` term, `is_value_list` should be true.
+    /// a variable is given by [`TermPool::fresh_name`]. If the binding list is a "value" list, like
+    /// in a `let` or `lambda` term, `is_value_list` should be true.
     fn rename_binding_list(
         &mut self,
         pool: &mut dyn TermPool,
@@ -335,21 +374,16 @@ impl Substitution {
                     value.clone()
                 };
 
-                let mut changed = false;
-                let mut new_var = var.clone();
-
-                // We keep adding `'`s to the variable name as long as it is necessary
-                loop {
-                    if !new_vars.contains(&new_var)
-                        && !self.should_be_renamed.as_ref().unwrap().contains(&new_var)
-                    {
-                        break;
-                    }
-                    new_var.push('\'');
-                    changed = true;
-                }
+                let should_be_renamed = self.should_be_renamed.as_ref().unwrap();
+                let is_taken =
+                    |name: &str| new_vars.contains(name) || should_be_renamed.contains(name);
+                let new_var = if is_taken(var) {
+                    pool.fresh_name(var, &is_taken)
+                } else {
+                    var.clone()
+                };
 
-                if changed {
+                if new_var != *var {
                     // If the variable was renamed, we have to add this renaming to the resulting
                     // substitution
                     let old = pool.add((var.clone(), sort.clone()).into());
@@ -364,7 +398,7 @@ impl Substitution {
                 // If the binding list is a "value" list, we need to apply the current substitution
                 // to each variable's value
                 let new_value = if is_value_list {
-                    new_substitution.apply(pool, value)
+                    new_substitution.apply_inner(pool, value)
                 } else {
                     value.clone()
                 };
@@ -378,7 +412,10 @@ impl Substitution {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{ast::PrimitivePool, parser::*};
+    use crate::{
+        ast::{FreshNameScheme, PrimitivePool},
+        parser::*,
+    };
 
     fn run_test(definitions: &str, original: &str, x: &str, t: &str, result: &str) {
         let mut pool = PrimitivePool::new();
@@ -457,4 +494,126 @@ mod tests {
             // TODO: Add tests for `choice`, `let`, and `lambda` terms
         }
     }
+
+    #[test]
+    fn test_renaming_avoids_declared_symbols() {
+        run_tests! {
+            definitions = "
+                (declare-fun x () Int)
+                (declare-fun y () Int)
+                (declare-fun |y'| () Int)
+            ",
+            "(forall ((y Int)) (> y 0))" [x -> y] => "(forall ((|y''| Int)) (> |y''| 0))",
+            "(forall ((y Int)) (> y |y'|))" [x -> y] =>
+                "(forall ((|y''| Int)) (> |y''| |y'|))",
+        }
+
+        let mut pool = PrimitivePool::new();
+        pool.reserve_name("y'".to_owned());
+        pool.start_rename_log();
+        assert_eq!("x", pool.fresh_name("x", &|_| false));
+        assert_eq!("y''", pool.fresh_name("y", &|name| name == "y"));
+        assert_eq!("z'", pool.fresh_name("z", &|name| name == "z"));
+        let expected = [("y", "y''"), ("z", "z'")].map(|(a, b)| (a.to_owned(), b.to_owned()));
+        assert_eq!(expected.to_vec(), pool.take_rename_log());
+        assert!(pool.take_rename_log().is_empty());
+    }
+
+    #[test]
+    fn test_counter_fresh_names() {
+        let mut pool = PrimitivePool::new();
+        let config = Config {
+            fresh_name_scheme: FreshNameScheme::Counter,
+            ..Config::new()
+        };
+        let definitions = "
+            (declare-fun x () Int)
+            (declare-fun y () Int)
+            (assert (forall ((|y!0| Int)) (> |y!0| 0)))
+        ";
+        let mut parser = Parser::new(&mut pool, config, definitions.as_bytes()).unwrap();
+        parser.parse_problem().unwrap();
+        let [original, x, y] = ["(forall ((y Int)) (> y x))", "x", "y"].map(|s| {
+            parser.reset(s.as_bytes()).unwrap();
+            parser.parse_term().unwrap()
+        });
+
+        // `y!0` is used in the problem, so it is never chosen as a fresh name
+        let mut substitution = Substitution::single(&mut pool, x, y).unwrap();
+        let got = substitution.apply(&mut pool, &original);
+        assert_eq!("(forall ((y!1 Int)) (> y!1 y))", got.to_string());
+
+        // Renaming a renamed variable uses its original name, and the counter is never reset
+        pool.start_rename_log();
+        assert_eq!("y!2", pool.fresh_name("y!1", &|name| name == "y!1"));
+        assert_eq!("z!3", pool.fresh_name("z", &|name| name == "z"));
+        assert_eq!("y", pool.original_name("y!1"));
+        assert_eq!("y", pool.original_name("y!2"));
+        assert_eq!("y!0", pool.original_name("y!0"));
+        let expected = [("y!1", "y!2"), ("z", "z!3")].map(|(a, b)| (a.to_owned(), b.to_owned()));
+        assert_eq!(expected.to_vec(), pool.take_rename_log());
+    }
+
+    #[test]
+    fn test_simultaneous_application_and_composition() {
+        let mut pool = PrimitivePool::new();
+        let definitions = "
+            (declare-fun x () Int)
+            (declare-fun y () Int)
+            (declare-fun z () Int)
+        ";
+        let mut parser = Parser::new(&mut pool, Config::new(), definitions.as_bytes()).unwrap();
+        parser.parse_problem().unwrap();
+        let [x, y, z, one, term, swapped, x_plus_one, binder] = [
+            "x",
+            "y",
+            "z",
+            "1",
+            "(= x y)",
+            "(= y x)",
+            "(+ x 1)",
+            "(forall ((z Int)) (= x y z))",
+        ]
+        .map(|s| {
+            parser.reset(s.as_bytes()).unwrap();
+            parser.parse_term().unwrap()
+        });
+
+        // Mappings are applied simultaneously
+        let map = IndexMap::from([(x.clone(), y.clone()), (y.clone(), x.clone())]);
+        let mut swap = Substitution::new(&mut pool, map).unwrap();
+        assert_eq!(swap.apply(&mut pool, &term), swapped);
+
+        // Composing `{x -> (+ x 1)}` with `{x -> y, y -> x}` gives `{x -> (+ y 1), y -> x}`
+        let mut composed = Substitution::single(&mut pool, x.clone(), x_plus_one).unwrap();
+        composed.compose(&mut pool, &swap);
+        assert_eq!(composed.len(), 2);
+        assert_eq!(composed.get(&x).unwrap().to_string(), "(+ y 1)");
+        assert_eq!(composed.get(&y), Some(&x));
+
+        // Applying the composition is the same as applying one substitution after the other, even
+        // in binder terms
+        let mut other = Substitution::single(&mut pool, z.clone(), one).unwrap();
+        for t in [&term, &binder] {
+            let mut sequential = Substitution::single(&mut pool, y.clone(), z.clone()).unwrap();
+            let expected = sequential.apply(&mut pool, t);
+            let expected = other.apply(&mut pool, &expected);
+
+            sequential.compose(&mut pool, &other);
+            assert_eq!(sequential.apply(&mut pool, t), expected);
+        }
+
+        // Reflexive mappings are removed after composing
+        let mut s = Substitution::single(&mut pool, x.clone(), y.clone()).unwrap();
+        s.compose(&mut pool, &swap);
+        assert!(s.get(&x).is_none());
+        assert_eq!(s.get(&y), Some(&x));
+
+        // Removing a mapping invalidates the results cached for it
+        assert_eq!(swap.remove(&y), Some(x.clone()));
+        assert_eq!(swap.apply(&mut pool, &term).to_string(), "(= y y)");
+        swap.retain(|v, _| *v != x);
+        assert!(swap.is_empty());
+        assert_eq!(swap.apply(&mut pool, &term), term);
+    }
 }
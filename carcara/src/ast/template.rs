@@ -0,0 +1,222 @@
+//! Template-based rewriting of proof steps.
+
+use super::*;
+use indexmap::IndexMap;
+use thiserror::Error;
+
+/// The error type for errors when constructing step templates.
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum TemplateError {
+    /// The name of a pattern variable does not start with `?`.
+    #[error("pattern variable '{0}' must start with '?'")]
+    InvalidPatternVariable(String),
+}
+
+/// A template describing how to rewrite proof steps.
+///
+/// A step matches the template if its rule is `rule` and, if a clause pattern is given, its clause
+/// matches that pattern. The pattern may contain pattern variables, which match any term of the
+/// same sort. If a pattern variable occurs more than once in the pattern, all occurrences must
+/// match the same term. The names of pattern variables must start with `?`, like `?x`, so they are
+/// never confused with the constants and variables used in the proof. A matching step is replaced
+/// by a step with the same id and premises, but with the rule, clause and arguments given by the
+/// template. The new clause and arguments may also refer to the pattern variables, which are
+/// replaced by the terms they matched.
+///
+/// For example, a template that only renames a rule can be created with:
+/// ```
+/// # use carcara::ast::StepTemplate;
+/// let template = StepTemplate::new("la_rw_eq", "la_rewrite_eq");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepTemplate {
+    /// The rule of the steps that should be rewritten.
+    pub rule: String,
+
+    /// The pattern variables that can be used in the clause pattern. Their names must start with
+    /// `?`.
+    pub variables: Vec<SortedVar>,
+
+    /// The clause pattern. If this is `None`, every step with the given rule matches the template.
+    pub clause: Option<Vec<Rc<Term>>>,
+
+    /// The rule of the new step.
+    pub new_rule: String,
+
+    /// The clause of the new step. If this is `None`, the original clause is kept.
+    pub new_clause: Option<Vec<Rc<Term>>>,
+
+    /// The arguments of the new step. If this is `None`, the original arguments are kept.
+    pub new_args: Option<Vec<Rc<Term>>>,
+}
+
+impl StepTemplate {
+    /// Constructs a new template that renames the rule of every step with rule `rule` to
+    /// `new_rule`, keeping the rest of the step unchanged.
+    pub fn new(rule: &str, new_rule: &str) -> Self {
+        Self {
+            rule: rule.to_owned(),
+            variables: Vec::new(),
+            clause: None,
+            new_rule: new_rule.to_owned(),
+            new_clause: None,
+            new_args: None,
+        }
+    }
+
+    /// Sets the clause pattern of the template, and the pattern variables used in it. Returns an
+    /// error if the name of a pattern variable doesn't start with `?`.
+    pub fn clause_pattern(
+        mut self,
+        variables: Vec<SortedVar>,
+        clause: Vec<Rc<Term>>,
+    ) -> Result<Self, TemplateError> {
+        if let Some((name, _)) = variables.iter().find(|(name, _)| !name.starts_with('?')) {
+            return Err(TemplateError::InvalidPatternVariable(name.clone()));
+        }
+        self.variables = variables;
+        self.clause = Some(clause);
+        Ok(self)
+    }
+
+    /// Sets the clause of the new step.
+    pub fn new_clause(mut self, clause: Vec<Rc<Term>>) -> Self {
+        self.new_clause = Some(clause);
+        self
+    }
+
+    /// Sets the arguments of the new step.
+    pub fn new_args(mut self, args: Vec<Rc<Term>>) -> Self {
+        self.new_args = Some(args);
+        self
+    }
+
+    /// Tries to rewrite a step using this template. Returns `None` if the step does not match the
+    /// template.
+    fn apply(&self, pool: &mut dyn TermPool, step: &ProofStep) -> Option<ProofStep> {
+        if step.rule != self.rule {
+            return None;
+        }
+
+        let variables: Vec<_> = self
+            .variables
+            .iter()
+            .map(|(name, sort)| pool.add(Term::new_var(name, sort.clone())))
+            .collect();
+
+        let mut bindings = IndexMap::new();
+        if let Some(pattern) = &self.clause {
+            if pattern.len() != step.clause.len() {
+                return None;
+            }
+            let mut matcher = Matcher {
+                pool,
+                variables: &variables,
+                bindings,
+            };
+            for (p, t) in pattern.iter().zip(&step.clause) {
+                if !matcher.match_term(p, t) {
+                    return None;
+                }
+            }
+            bindings = matcher.bindings;
+        }
+
+        // Since the matcher only binds variables to terms of the same sort, building the
+        // substitution never fails
+        let mut substitution = Substitution::new(pool, bindings).unwrap();
+        let mut instantiate = |terms: &Option<Vec<Rc<Term>>>, original: &[Rc<Term>]| match terms {
+            Some(terms) => terms.iter().map(|t| substitution.apply(pool, t)).collect(),
+            None => original.to_vec(),
+        };
+        let clause = instantiate(&self.new_clause, &step.clause);
+        let args = instantiate(&self.new_args, &step.args);
+
+        Some(ProofStep {
+            id: step.id.clone(),
+            clause,
+            rule: self.new_rule.clone(),
+            premises: step.premises.clone(),
+            args,
+            discharge: step.discharge.clone(),
+        })
+    }
+}
+
+struct Matcher<'a> {
+    pool: &'a mut dyn TermPool,
+    variables: &'a [Rc<Term>],
+    bindings: IndexMap<Rc<Term>, Rc<Term>>,
+}
+
+impl<'a> Matcher<'a> {
+    fn match_term(&mut self, pattern: &Rc<Term>, term: &Rc<Term>) -> bool {
+        if self.variables.contains(pattern) {
+            if let Some(bound) = self.bindings.get(pattern) {
+                return bound == term;
+            }
+            if self.pool.sort(pattern) != self.pool.sort(term) {
+                return false;
+            }
+            self.bindings.insert(pattern.clone(), term.clone());
+            return true;
+        }
+
+        match (pattern.as_ref(), term.as_ref()) {
+            (Term::App(f, a), Term::App(g, b)) => self.match_term(f, g) && self.match_args(a, b),
+            (Term::Op(f, a), Term::Op(g, b)) => f == g && self.match_args(a, b),
+            (Term::Binder(p, a_bindings, a), Term::Binder(q, b_bindings, b)) => {
+                p == q && a_bindings == b_bindings && self.match_term(a, b)
+            }
+            (Term::Let(a_bindings, a), Term::Let(b_bindings, b)) => {
+                a_bindings == b_bindings && self.match_term(a, b)
+            }
+            (
+                Term::ParamOp { op: f, op_args: f_args, args: a },
+                Term::ParamOp { op: g, op_args: g_args, args: b },
+            ) => f == g && f_args == g_args && self.match_args(a, b),
+            _ => pattern == term,
+        }
+    }
+
+    fn match_args(&mut self, patterns: &[Rc<Term>], terms: &[Rc<Term>]) -> bool {
+        patterns.len() == terms.len()
+            && patterns
+                .iter()
+                .zip(terms)
+                .all(|(p, t)| self.match_term(p, t))
+    }
+}
+
+/// Rewrites every step in the proof (including steps inside subproofs) that matches one of the
+/// given templates. If a step matches more than one template, the first one is used.
+pub fn apply_step_templates(
+    pool: &mut dyn TermPool,
+    proof: &Proof,
+    templates: &[StepTemplate],
+) -> Proof {
+    let mut commands = proof.commands.clone();
+    apply_to_commands(pool, &mut commands, templates);
+    Proof {
+        constant_definitions: proof.constant_definitions.clone(),
+        commands,
+    }
+}
+
+fn apply_to_commands(
+    pool: &mut dyn TermPool,
+    commands: &mut [ProofCommand],
+    templates: &[StepTemplate],
+) {
+    for command in commands {
+        match command {
+            ProofCommand::Assume { .. } => (),
+            ProofCommand::Step(step) => {
+                if let Some(new) = templates.iter().find_map(|t| t.apply(pool, step)) {
+                    *step = new;
+                }
+            }
+            ProofCommand::Subproof(s) => apply_to_commands(pool, &mut s.commands, templates),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{PrimitivePool, Rc, TermPool};
+use super::{PrimitivePool, Rc, TermPool, TermPoolView};
 use crate::CheckerError;
 use rug::{Integer, Rational};
 use std::{collections::HashSet, hash::Hash, ops::Deref};
@@ -84,6 +84,15 @@ pub enum Sort {
     /// The associated term is the BV width of this sort.
     BitVec(Integer),
 
+    /// A `FloatingPoint` sort.
+    ///
+    /// The associated integers are the exponent width and the significand width, including the
+    /// hidden bit.
+    FloatingPoint(Integer, Integer),
+
+    /// The `RoundingMode` sort.
+    RoundingMode,
+
     /// The sort of RARE lists.
     RareList,
 
@@ -354,6 +363,52 @@ pub enum Operator {
     BvSGe,
     BvBbTerm,
 
+    // Floating-point
+    /// The `RNE` rounding mode, also written `roundNearestTiesToEven`.
+    RoundNearestTiesToEven,
+
+    /// The `RNA` rounding mode, also written `roundNearestTiesToAway`.
+    RoundNearestTiesToAway,
+
+    /// The `RTP` rounding mode, also written `roundTowardPositive`.
+    RoundTowardPositive,
+
+    /// The `RTN` rounding mode, also written `roundTowardNegative`.
+    RoundTowardNegative,
+
+    /// The `RTZ` rounding mode, also written `roundTowardZero`.
+    RoundTowardZero,
+
+    /// The `fp` operator, that builds a floating-point literal from its sign, exponent and
+    /// significand bit-vectors.
+    Fp,
+
+    FpAbs,
+    FpNeg,
+    FpAdd,
+    FpSub,
+    FpMul,
+    FpDiv,
+    FpFma,
+    FpSqrt,
+    FpRem,
+    FpRoundToIntegral,
+    FpMin,
+    FpMax,
+    FpLeq,
+    FpLt,
+    FpGeq,
+    FpGt,
+    FpEq,
+    FpIsNormal,
+    FpIsSubnormal,
+    FpIsZero,
+    FpIsInfinite,
+    FpIsNaN,
+    FpIsNegative,
+    FpIsPositive,
+    FpToReal,
+
     // Misc.
     /// The `rare-list` operator, used to represent RARE lists.
     RareList,
@@ -368,11 +423,25 @@ pub enum ParamOperator {
     SignExtend,
     BvConst,
 
+    FpPlusZero,
+    FpMinusZero,
+    FpPlusInfinity,
+    FpMinusInfinity,
+    FpNaN,
+    FpToFp,
+    FpToFpUnsigned,
+    FpToUbv,
+    FpToSbv,
+
     RePower,
     ReLoop,
 
     // Qualified operators
     ArrayConst,
+
+    // Datatypes
+    /// The `is` tester, whose single operator argument is the constructor being tested.
+    DtTester,
 }
 
 impl_str_conversion_traits!(Operator {
@@ -473,6 +542,38 @@ impl_str_conversion_traits!(Operator {
     BvSGe: "bvsge",
     BvBbTerm: "bbterm",
 
+    RoundNearestTiesToEven: "RNE" | "roundNearestTiesToEven",
+    RoundNearestTiesToAway: "RNA" | "roundNearestTiesToAway",
+    RoundTowardPositive: "RTP" | "roundTowardPositive",
+    RoundTowardNegative: "RTN" | "roundTowardNegative",
+    RoundTowardZero: "RTZ" | "roundTowardZero",
+    Fp: "fp",
+    FpAbs: "fp.abs",
+    FpNeg: "fp.neg",
+    FpAdd: "fp.add",
+    FpSub: "fp.sub",
+    FpMul: "fp.mul",
+    FpDiv: "fp.div",
+    FpFma: "fp.fma",
+    FpSqrt: "fp.sqrt",
+    FpRem: "fp.rem",
+    FpRoundToIntegral: "fp.roundToIntegral",
+    FpMin: "fp.min",
+    FpMax: "fp.max",
+    FpLeq: "fp.leq",
+    FpLt: "fp.lt",
+    FpGeq: "fp.geq",
+    FpGt: "fp.gt",
+    FpEq: "fp.eq",
+    FpIsNormal: "fp.isNormal",
+    FpIsSubnormal: "fp.isSubnormal",
+    FpIsZero: "fp.isZero",
+    FpIsInfinite: "fp.isInfinite",
+    FpIsNaN: "fp.isNaN",
+    FpIsNegative: "fp.isNegative",
+    FpIsPositive: "fp.isPositive",
+    FpToReal: "fp.to_real",
+
     RareList: "rare-list",
 });
 
@@ -483,10 +584,22 @@ impl_str_conversion_traits!(ParamOperator {
     SignExtend: "sign_extend",
     BvConst: "bv",
 
+    FpPlusZero: "+zero",
+    FpMinusZero: "-zero",
+    FpPlusInfinity: "+oo",
+    FpMinusInfinity: "-oo",
+    FpNaN: "NaN",
+    FpToFp: "to_fp",
+    FpToFpUnsigned: "to_fp_unsigned",
+    FpToUbv: "fp.to_ubv",
+    FpToSbv: "fp.to_sbv",
+
     RePower: "re.^",
     ReLoop: "re.loop",
 
     ArrayConst: "const",
+
+    DtTester: "is",
 });
 
 impl std::ops::Not for Binder {
@@ -570,7 +683,7 @@ impl Term {
     }
 
     /// Returns the sort of this term. This does not make use of a cache --- if possible, prefer to
-    /// use `TermPool::sort`.
+    /// use `TermPoolView::sort`.
     pub fn raw_sort(&self) -> Sort {
         let mut pool = PrimitivePool::new();
         let added = pool.add(self.clone());
@@ -1,5 +1,5 @@
 use crate::{
-    ast::{node::ProofNode, pool::PrimitivePool, Polyeq, TermPool},
+    ast::{node::ProofNode, pool::PrimitivePool, Polyeq, Sort, TermPool, TermPoolView},
     parser::tests::parse_terms,
 };
 use indexmap::IndexSet;
@@ -32,6 +32,8 @@ fn test_free_vars() {
             ("(forall ((a Int)) (forall ((b Int)) (= a b)))", &[]),
             ("(and (forall ((a Int)) (= a 0)) (= a 0))", &["a"]),
             ("(and (= a 0) (forall ((a Int)) (= a 0)))", &["a"]),
+            ("(let ((a b)) (= a 0))", &["b"]),
+            ("(let ((a a)) (= a b))", &["a", "b"]),
         ],
     );
 }
@@ -162,3 +164,377 @@ fn test_node() {
     let got = node.into_commands();
     assert_eq!(expected.commands, got);
 }
+
+#[test]
+fn test_merge() {
+    use crate::{ast::merge_proofs, parser::tests::*};
+
+    let main = "
+        (assume h0 (= 0 0))
+        (assume h1 (= 1 1))
+        (step t2 (cl (= 2 2)) :rule hole)
+        (step t3 (cl (= 3 3)) :rule blah :premises (t2))
+        (step t4 (cl) :rule blah :premises (h0 h1 t3))
+    ";
+    let lemmas = "
+        (assume h0 (= 1 1))
+        (step t2 (cl (= 2 2)) :rule blah :premises (h0))
+        (anchor :step t3)
+            (step t3.t1 (cl true) :rule blah)
+            (step t3 (cl (= 4 4)) :rule blah :premises (t2))
+        (step t4 (cl) :rule blah :premises (t3))
+    ";
+    let expected = "
+        (assume h0_1 (= 1 1))
+        (assume h0 (= 0 0))
+        (step t2_1 (cl (= 2 2)) :rule blah :premises (h0_1))
+        (anchor :step t3_1)
+            (step t3.t1 (cl true) :rule blah)
+            (step t3_1 (cl (= 4 4)) :rule blah :premises (t2_1))
+        (step t3 (cl (= 3 3)) :rule blah :premises (t2_1))
+        (step t4 (cl) :rule blah :premises (h0 h0_1 t3))
+    ";
+    let mut pool = PrimitivePool::new();
+    let main = parse_proof(&mut pool, main);
+    let lemmas = parse_proof(&mut pool, lemmas);
+    let expected = parse_proof(&mut pool, expected);
+
+    let got = merge_proofs(&main, &lemmas);
+    assert_eq!(expected.commands, got.commands);
+}
+
+#[test]
+fn test_diff() {
+    use crate::{ast::diff_proofs, parser::tests::*};
+
+    let a = "
+        (assume h0 (= 0 0))
+        (step t1 (cl (forall ((x Int)) (= x x))) :rule blah)
+        (step t2 (cl (= 1 1)) :rule blah :premises (h0 t1))
+        (step t3 (cl (= 2 2)) :rule blah :premises (t2))
+        (step t4 (cl (= 3 3)) :rule blah)
+        (step t5 (cl) :rule blah :premises (t3))
+    ";
+    let b = "
+        (assume a0 (= 0 0))
+        (step s1 (cl (forall ((y Int)) (= y y))) :rule blah)
+        (step s2 (cl (= 1 1)) :rule other :premises (a0 s1))
+        (step s3 (cl (= 2 2)) :rule blah :premises (s2))
+        (step s4 (cl (= 4 4)) :rule blah)
+        (step s5 (cl) :rule blah :premises (s3))
+    ";
+    let mut pool = PrimitivePool::new();
+    let a = parse_proof(&mut pool, a);
+    let b = parse_proof(&mut pool, b);
+
+    let diff = diff_proofs(&a, &b);
+    let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(a, b)| ((*a).to_owned(), (*b).to_owned()))
+            .collect()
+    };
+    assert_eq!(
+        diff.renamed,
+        pairs(&[("h0", "a0"), ("t1", "s1"), ("t3", "s3"), ("t5", "s5")])
+    );
+    assert_eq!(diff.changed, pairs(&[("t2", "s2")]));
+    assert_eq!(diff.added, ["s4"]);
+    assert_eq!(diff.removed, ["t4"]);
+
+    assert!(diff_proofs(&a, &a).is_empty());
+}
+
+#[test]
+fn test_step_templates() {
+    use crate::{
+        ast::{apply_step_templates, StepTemplate, TemplateError},
+        parser::{self, tests::parse_terms},
+    };
+
+    // The constant `x` is not a pattern variable, so it only matches itself
+    let definitions = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (declare-fun x () Int)
+    ";
+    let original = "
+        (step t1 (cl (= a b)) :rule old_rule)
+        (step t2 (cl (= (+ a 1) (+ 1 a))) :rule comm)
+        (step t3 (cl (= a b) (= b a)) :rule comm :premises (t1))
+        (anchor :step t4)
+            (step t4.t1 (cl (= b b)) :rule old_rule)
+            (step t4 (cl (= b b)) :rule other :premises (t4.t1))
+        (step t5 (cl (= a x)) :rule eq_x)
+        (step t6 (cl (= a b)) :rule eq_x)
+    ";
+    let expected = "
+        (step t1 (cl (= a b)) :rule new_rule)
+        (step t2 (cl (= (+ 1 a) (+ a 1))) :rule symm :args ((+ a 1)))
+        (step t3 (cl (= a b) (= b a)) :rule comm :premises (t1))
+        (anchor :step t4)
+            (step t4.t1 (cl (= b b)) :rule new_rule)
+            (step t4 (cl (= b b)) :rule other :premises (t4.t1))
+        (step t5 (cl (= x a)) :rule x_eq)
+        (step t6 (cl (= a b)) :rule eq_x)
+    ";
+    let mut pool = PrimitivePool::new();
+    let config = parser::Config::new();
+    let (_, original) = parser::parse_instance_with_pool(
+        definitions.as_bytes(),
+        original.as_bytes(),
+        config,
+        &mut pool,
+    )
+    .unwrap();
+    let (_, expected) = parser::parse_instance_with_pool(
+        definitions.as_bytes(),
+        expected.as_bytes(),
+        config,
+        &mut pool,
+    )
+    .unwrap();
+
+    // The pattern variables are only declared to parse the patterns
+    let pattern_definitions = format!(
+        "{} (declare-fun ?x () Int) (declare-fun ?y () Int)",
+        definitions
+    );
+    let [x, pattern, new_clause, x_pattern, x_new_clause] = parse_terms(
+        &mut pool,
+        &pattern_definitions,
+        ["?x", "(= ?x ?y)", "(= ?y ?x)", "(= ?x x)", "(= x ?x)"],
+    );
+    let int_sort = pool.sort(&x);
+    let variables = vec![
+        ("?x".to_owned(), int_sort.clone()),
+        ("?y".to_owned(), int_sort.clone()),
+    ];
+    let templates = [
+        StepTemplate::new("old_rule", "new_rule"),
+        StepTemplate::new("comm", "symm")
+            .clause_pattern(variables, vec![pattern])
+            .unwrap()
+            .new_clause(vec![new_clause])
+            .new_args(vec![x]),
+        StepTemplate::new("eq_x", "x_eq")
+            .clause_pattern(
+                vec![("?x".to_owned(), int_sort.clone())],
+                vec![x_pattern.clone()],
+            )
+            .unwrap()
+            .new_clause(vec![x_new_clause]),
+    ];
+    let got = apply_step_templates(&mut pool, &original, &templates);
+    assert_eq!(expected.commands, got.commands);
+
+    // Pattern variables without the `?` prefix are rejected
+    assert_eq!(
+        StepTemplate::new("eq_x", "x_eq")
+            .clause_pattern(vec![("x".to_owned(), int_sort)], vec![x_pattern]),
+        Err(TemplateError::InvalidPatternVariable("x".to_owned())),
+    );
+}
+
+#[test]
+fn test_garbage_collection() {
+    use crate::parser::tests::parse_term;
+
+    let mut pool = PrimitivePool::new();
+    let kept = parse_term(&mut pool, "(+ 1 2)");
+    let dropped = parse_term(&mut pool, "(* (- 3 4) (+ 1 2))");
+    drop(dropped);
+
+    pool.collect_garbage();
+    // The ids of the remaining terms change, but their sorts are kept
+    assert_eq!(pool.sort(&kept).as_sort(), Some(&Sort::Int));
+    let mut got: Vec<_> = pool
+        .storage
+        .into_vec()
+        .iter()
+        .map(|t| format!("{:#}", t))
+        .collect();
+    got.sort();
+    assert_eq!(got, ["(+ 1 2)", "1", "2", "Int"]);
+    drop(kept);
+}
+
+#[test]
+fn test_alpha_canonical_binders() {
+    let definitions = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+    ";
+    let equivalent = [
+        ("(forall ((x Int)) (> x 0))", "(forall ((y Int)) (> y 0))"),
+        (
+            "(forall ((x Int) (y Int)) (< x y))",
+            "(forall ((y Int) (x Int)) (< y x))",
+        ),
+        (
+            "(forall ((x Int)) (and (> x 0) (exists ((x Int)) (< x 0))))",
+            "(forall ((y Int)) (and (> y 0) (exists ((z Int)) (< z 0))))",
+        ),
+        (
+            "(forall ((x Int)) (let ((x 1)) (> x 0)))",
+            "(forall ((y Int)) (let ((x 1)) (> x 0)))",
+        ),
+        ("(lambda ((x Int)) (+ x a))", "(lambda ((y Int)) (+ y a))"),
+    ];
+    let different = [
+        (
+            "(forall ((x Int) (y Int)) (< x y))",
+            "(forall ((y Int) (x Int)) (< x y))",
+        ),
+        ("(forall ((x Int)) (> x a))", "(forall ((x Int)) (> x b))"),
+        (
+            "(forall ((x Int)) (exists ((y Int)) (< x y)))",
+            "(forall ((x Int)) (exists ((x Int)) (< x x)))",
+        ),
+        (
+            "(forall ((y Int)) (let ((x y)) (> x 0)))",
+            "(forall ((y Int)) (let ((x 1)) (> x 0)))",
+        ),
+        ("(exists ((x Int)) (> x 0))", "(forall ((x Int)) (> x 0))"),
+        (
+            "(forall ((x Int)) (> x 0))",
+            "(forall ((x Real)) (> x 0.0))",
+        ),
+    ];
+
+    for (a, b) in equivalent {
+        let mut pool = PrimitivePool::new();
+        pool.enable_alpha_canonical_binders();
+        let [a_term, b_term] = parse_terms(&mut pool, definitions, [a, b]);
+        assert_eq!(a_term, b_term, "`{}` and `{}` should share a term", a, b);
+        assert_eq!(b_term.to_string(), a);
+    }
+    for (a, b) in different {
+        let mut pool = PrimitivePool::new();
+        pool.enable_alpha_canonical_binders();
+        let [a_term, b_term] = parse_terms(&mut pool, definitions, [a, b]);
+        assert_ne!(
+            a_term, b_term,
+            "`{}` and `{}` should not share a term",
+            a, b
+        );
+        let mut comp = Polyeq::new().alpha_equiv(true);
+        assert!(!comp.eq(&a_term, &b_term));
+    }
+
+    // Enabling it afterwards registers the binder terms already in the pool
+    let mut pool = PrimitivePool::new();
+    let [a] = parse_terms(&mut pool, definitions, ["(forall ((x Int)) (> x a))"]);
+    pool.enable_alpha_canonical_binders();
+    let [b] = parse_terms(&mut pool, definitions, ["(forall ((y Int)) (> y a))"]);
+    assert_eq!(a, b);
+
+    // Once a term is collected, its class is removed as well
+    drop((a, b));
+    pool.collect_garbage();
+    let [b] = parse_terms(&mut pool, definitions, ["(forall ((y Int)) (> y a))"]);
+    assert_eq!(b.to_string(), "(forall ((y Int)) (> y a))");
+}
+
+#[test]
+fn test_garbage_collection_from_roots() {
+    use crate::parser::tests::parse_term;
+
+    let mut pool = PrimitivePool::new();
+    pool.enable_alpha_canonical_binders();
+    let root = parse_term(&mut pool, "(forall ((x Int)) (> x 1))");
+    let unreachable = parse_term(&mut pool, "(* (- 3 4) (+ 1 2))");
+
+    pool.collect_garbage_from_roots([&root]);
+    let mut got: Vec<_> = pool.storage.iter().map(|t| format!("{:#}", t)).collect();
+    got.sort();
+    assert_eq!(
+        got,
+        [
+            "(> x 1)",
+            "(forall ((x Int)) (> x 1))",
+            "1",
+            "Bool",
+            "Int",
+            "Type",
+            "x"
+        ]
+    );
+    drop(unreachable);
+
+    // The removed terms are also removed from the alpha-equivalence classes
+    drop(root);
+    pool.collect_garbage_from_roots([]);
+    assert!(pool.is_empty());
+    let term = parse_term(&mut pool, "(forall ((y Int)) (> y 1))");
+    assert_eq!(term.to_string(), "(forall ((y Int)) (> y 1))");
+}
+
+#[test]
+fn test_visit_term() {
+    use crate::ast::{
+        visit::{visit_term, BoundVars, TermVisitor},
+        Rc, Term,
+    };
+
+    /// Collects the free variables of a term, and the maximum binder depth in it.
+    #[derive(Default)]
+    struct Collector {
+        free_vars: IndexSet<String>,
+        max_depth: usize,
+        post_visits: usize,
+    }
+
+    impl TermVisitor for Collector {
+        fn pre_visit(&mut self, term: &Rc<Term>, bound: &BoundVars) -> bool {
+            self.max_depth = std::cmp::max(self.max_depth, bound.depth());
+            if let Some(name) = term.as_var() {
+                if !bound.contains(name) {
+                    self.free_vars.insert(name.to_owned());
+                }
+            }
+            true
+        }
+
+        fn post_visit(&mut self, _: &Rc<Term>, _: &BoundVars) {
+            self.post_visits += 1;
+        }
+    }
+
+    let definitions = "
+        (declare-fun p () Bool)
+        (declare-fun f (Int) Int)
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+    ";
+    let cases = [
+        ("(and p (= a b))", 0, 5),
+        ("(forall ((a Int)) (= (f a) b))", 1, 6),
+        ("(forall ((a Int)) (exists ((b Int)) (= a b)))", 2, 5),
+        ("(let ((a b)) (forall ((b Int)) (= a b)))", 2, 6),
+        ("(and (forall ((a Int)) (= a 0)) (= a 0))", 1, 8),
+    ];
+    for (term, depth, num_visits) in cases {
+        let mut pool = PrimitivePool::new();
+        let [term] = parse_terms(&mut pool, definitions, [term]);
+        let mut collector = Collector::default();
+        visit_term(&mut collector, &term);
+
+        let expected: IndexSet<_> = pool
+            .free_vars(&term)
+            .iter()
+            .map(|v| v.as_var().unwrap().to_owned())
+            .collect();
+        assert_eq!(
+            expected, collector.free_vars,
+            "wrong free vars in `{}`",
+            term
+        );
+        assert_eq!(depth, collector.max_depth, "wrong depth in `{}`", term);
+        assert_eq!(
+            num_visits, collector.post_visits,
+            "wrong visits in `{}`",
+            term
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Generic traversals over terms.
+//!
+//! This module implements three ways of traversing a term, so code that needs to look at every
+//! subterm of a term doesn't need to match on every `Term` variant:
+//!
+//! - [`visit_term`] calls the hooks of a [`TermVisitor`] on every subterm, keeping track of the
+//!   variables bound by the enclosing binder terms;
+//! - [`fold_term`] computes a value for a term from the values of its subterms, caching the value
+//!   for each subterm, so that it is computed only once;
+//! - [`map_subterms`] rebuilds a term, replacing each of its direct subterms.
+//!
+//! In the first two, the subterms of a term are the function and arguments of an application, the
+//! arguments of an operation, the inner term of a binder, and the values and inner term of a `let`
+//! term. Sorts, including the ones in binding lists, and the indices of indexed operators are not
+//! considered subterms.
+
+use super::{BindingList, Rc, Sort, Term, TermPool};
+use indexmap::IndexMap;
+
+/// The variables bound by the binder and `let` terms that enclose the term being visited.
+#[derive(Debug, Default)]
+pub struct BoundVars {
+    names: Vec<String>,
+    scopes: Vec<usize>,
+}
+
+impl BoundVars {
+    /// Returns `true` if a variable with the given name is bound by an enclosing binder term.
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n == name)
+    }
+
+    /// Returns the number of binder and `let` terms enclosing the current term.
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Returns an iterator over the names of the bound variables, from the outermost to the
+    /// innermost binder. If a variable is shadowed, its name appears more than once.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    fn push(&mut self, bindings: &BindingList) {
+        self.scopes.push(self.names.len());
+        self.names
+            .extend(bindings.iter().map(|(name, _)| name.clone()));
+    }
+
+    fn pop(&mut self) {
+        let len = self.scopes.pop().unwrap();
+        self.names.truncate(len);
+    }
+}
+
+/// A visitor over terms, used with [`visit_term`].
+///
+/// Both hooks receive the variables bound by the binder terms enclosing the current term. For a
+/// binder term, these don't include the variables bound by the term itself.
+pub trait TermVisitor {
+    /// Called before the subterms of `term` are visited. If this returns `false`, the subterms of
+    /// `term` are skipped.
+    fn pre_visit(&mut self, term: &Rc<Term>, bound: &BoundVars) -> bool {
+        let _ = (term, bound);
+        true
+    }
+
+    /// Called after the subterms of `term` are visited, or skipped.
+    fn post_visit(&mut self, term: &Rc<Term>, bound: &BoundVars) {
+        let _ = (term, bound);
+    }
+}
+
+/// Visits `term` and all of its subterms, in depth-first order, calling the hooks of `visitor` on
+/// each of them.
+///
+/// Since the bound variables may be different in each occurrence of a subterm, the term is
+/// traversed as a tree, meaning a subterm is visited once for each of its occurrences. If that is
+/// not needed, [`fold_term`] visits each distinct subterm only once.
+pub fn visit_term<V: TermVisitor + ?Sized>(visitor: &mut V, term: &Rc<Term>) {
+    visit_with_bound_vars(visitor, term, &mut BoundVars::default());
+}
+
+fn visit_with_bound_vars<V: TermVisitor + ?Sized>(
+    visitor: &mut V,
+    term: &Rc<Term>,
+    bound: &mut BoundVars,
+) {
+    if visitor.pre_visit(term, bound) {
+        match term.as_ref() {
+            Term::Binder(_, bindings, inner) => {
+                bound.push(bindings);
+                visit_with_bound_vars(visitor, inner, bound);
+                bound.pop();
+            }
+            Term::Let(bindings, inner) => {
+                for (_, value) in bindings {
+                    visit_with_bound_vars(visitor, value, bound);
+                }
+                bound.push(bindings);
+                visit_with_bound_vars(visitor, inner, bound);
+                bound.pop();
+            }
+            _ => {
+                for subterm in subterms(term) {
+                    visit_with_bound_vars(visitor, subterm, bound);
+                }
+            }
+        }
+    }
+    visitor.post_visit(term, bound);
+}
+
+/// A cache for the values computed by [`fold_term`].
+pub trait FoldCache<T> {
+    /// Returns the value computed for `term`, if it is in the cache.
+    fn get(&self, term: &Rc<Term>) -> Option<T>;
+
+    /// Adds the value computed for `term` to the cache.
+    fn insert(&mut self, term: Rc<Term>, value: T);
+}
+
+impl<T: Clone> FoldCache<T> for IndexMap<Rc<Term>, T> {
+    fn get(&self, term: &Rc<Term>) -> Option<T> {
+        IndexMap::get(self, term).cloned()
+    }
+
+    fn insert(&mut self, term: Rc<Term>, value: T) {
+        IndexMap::insert(self, term, value);
+    }
+}
+
+/// Computes a value for `term` bottom-up. The function `f` receives each subterm, together with
+/// the values computed for its own subterms, in order, and returns the value for that subterm.
+///
+/// The value computed for each subterm is stored in `cache`, so even if a subterm appears many
+/// times, its value is only computed once, and the term is traversed as a DAG. The cache may be
+/// reused across calls. Note that `f` doesn't know which variables are bound in the current
+/// subterm, so it must only depend on the subterm itself. For example, this computes the number of
+/// distinct subterms in a term:
+///
+/// ```
+/// # use carcara::ast::{*, visit::fold_term};
+/// # use indexmap::IndexMap;
+/// # let mut pool = PrimitivePool::new();
+/// # let a = pool.add(Term::new_int(1));
+/// # let term = pool.add(Term::Op(Operator::Add, vec![a.clone(), a]));
+/// let mut cache = IndexMap::new();
+/// let mut seen = 0;
+/// fold_term(&term, &mut cache, &mut |_, _| seen += 1);
+/// assert_eq!(seen, 2);
+/// ```
+pub fn fold_term<T, C, F>(term: &Rc<Term>, cache: &mut C, f: &mut F) -> T
+where
+    T: Clone,
+    C: FoldCache<T> + ?Sized,
+    F: FnMut(&Rc<Term>, Vec<T>) -> T,
+{
+    if let Some(value) = cache.get(term) {
+        return value;
+    }
+    let values = subterms(term).map(|t| fold_term(t, cache, f)).collect();
+    let value = f(term, values);
+    cache.insert(term.clone(), value.clone());
+    value
+}
+
+/// Returns the subterms of a term, in the order they are visited.
+fn subterms(term: &Term) -> impl Iterator<Item = &Rc<Term>> {
+    let (let_values, head, rest): (&[_], _, &[_]) = match term {
+        Term::App(f, args) => (&[], Some(f), args),
+        Term::Op(_, args) | Term::ParamOp { args, .. } => (&[], None, args),
+        Term::Binder(_, _, inner) => (&[], Some(inner), &[]),
+        Term::Let(bindings, inner) => (bindings.as_ref(), Some(inner), &[]),
+        Term::Const(_) | Term::Var(..) | Term::Sort(_) => (&[], None, &[]),
+    };
+    let_values
+        .iter()
+        .map(|(_, value)| value)
+        .chain(head)
+        .chain(rest)
+}
+
+/// Rebuilds `term`, replacing each of its direct subterms by the result of calling `f` on it, and
+/// adds the new term to the pool. Unlike the other functions in this module, this also calls `f` on
+/// the sorts that are the arguments of a sort term. The indices of indexed operators are kept as
+/// they are.
+///
+/// This must not be used on binder or `let` terms, since they bind variables, which `f` would need
+/// to know about. For these terms, this returns `term` unchanged. Constants and variables are also
+/// returned unchanged.
+pub fn map_subterms<P, F>(pool: &mut P, term: &Rc<Term>, mut f: F) -> Rc<Term>
+where
+    P: TermPool + ?Sized,
+    F: FnMut(&mut P, &Rc<Term>) -> Rc<Term>,
+{
+    fn map_all<P: ?Sized>(
+        pool: &mut P,
+        terms: &[Rc<Term>],
+        f: &mut impl FnMut(&mut P, &Rc<Term>) -> Rc<Term>,
+    ) -> Vec<Rc<Term>> {
+        terms.iter().map(|t| f(pool, t)).collect()
+    }
+
+    let new_term = match term.as_ref() {
+        Term::App(func, args) => {
+            let func = f(pool, func);
+            Term::App(func, map_all(pool, args, &mut f))
+        }
+        Term::Op(op, args) => Term::Op(*op, map_all(pool, args, &mut f)),
+        Term::ParamOp { op, op_args, args } => Term::ParamOp {
+            op: *op,
+            op_args: op_args.clone(),
+            args: map_all(pool, args, &mut f),
+        },
+        Term::Sort(Sort::Function(sorts)) => {
+            Term::Sort(Sort::Function(map_all(pool, sorts, &mut f)))
+        }
+        Term::Sort(Sort::Atom(name, args)) => {
+            Term::Sort(Sort::Atom(name.clone(), map_all(pool, args, &mut f)))
+        }
+        Term::Sort(Sort::Array(x, y)) => {
+            let x = f(pool, x);
+            let y = f(pool, y);
+            Term::Sort(Sort::Array(x, y))
+        }
+        Term::Sort(_) | Term::Const(_) | Term::Var(..) | Term::Binder(..) | Term::Let(..) => {
+            return term.clone()
+        }
+    };
+    pool.add(new_term)
+}
@@ -0,0 +1,157 @@
+//! Collecting benchmark results into CSV files, for analysis with external tools.
+
+use super::{CollectResults, RunId, RunMeasurement};
+use indexmap::{IndexMap, IndexSet};
+use std::{fmt, io, sync::Arc, time::Duration};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InternedStepId {
+    pub(crate) file: Arc<str>,
+    pub(crate) step_id: Arc<str>,
+    pub(crate) rule: Arc<str>,
+}
+
+impl fmt::Display for InternedStepId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{} ({})", self.file, self.step_id, self.rule)
+    }
+}
+
+type InternedRunId = (Arc<str>, usize);
+
+#[derive(Default)]
+pub struct CsvBenchmarkResults {
+    strings: IndexSet<Arc<str>>,
+    runs: IndexMap<InternedRunId, RunMeasurement>,
+    steps: Vec<(Arc<str>, Duration)>,
+    is_holey: bool,
+    num_errors: usize,
+}
+
+impl CsvBenchmarkResults {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn is_holey(&self) -> bool {
+        self.is_holey
+    }
+
+    pub fn num_errors(&self) -> usize {
+        self.num_errors
+    }
+
+    fn intern(&mut self, s: &str) -> Arc<str> {
+        match self.strings.get(s) {
+            Some(interned) => interned.clone(),
+            None => {
+                let result: Arc<str> = Arc::from(s);
+                self.strings.insert(result.clone());
+                result
+            }
+        }
+    }
+
+    pub fn write_csv(
+        self,
+        runs_dest: &mut dyn io::Write,
+        steps_dest: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        Self::write_runs_csv(self.runs, runs_dest)?;
+        Self::write_steps_csv(self.steps, steps_dest)
+    }
+
+    fn write_runs_csv(
+        data: IndexMap<InternedRunId, RunMeasurement>,
+        dest: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let pipeline_length = data
+            .iter()
+            .next()
+            .map_or(0, |(_, m)| m.elaboration_pipeline.len());
+        write!(
+            dest,
+            "proof_file,run_id,parsing,checking,elaboration,total_accounted_for,\
+            total,polyeq,polyeq_ratio,assume,assume_ratio,term_nodes"
+        )?;
+        for i in 0..pipeline_length {
+            write!(dest, ",pipeline_step_{}", i)?;
+        }
+        writeln!(dest)?;
+
+        for (id, m) in data {
+            let total_accounted_for = m.parsing + m.checking + m.elaboration;
+            let polyeq_ratio = m.polyeq.as_secs_f64() / m.checking.as_secs_f64();
+            let assume_ratio = m.assume.as_secs_f64() / m.checking.as_secs_f64();
+            write!(
+                dest,
+                "{},{},{},{},{},{},{},{},{},{},{},{}",
+                id.0,
+                id.1,
+                m.parsing.as_nanos(),
+                m.checking.as_nanos(),
+                m.elaboration.as_nanos(),
+                total_accounted_for.as_nanos(),
+                m.total.as_nanos(),
+                m.polyeq.as_nanos(),
+                polyeq_ratio,
+                m.assume.as_nanos(),
+                assume_ratio,
+                m.term_nodes,
+            )?;
+            assert_eq!(m.elaboration_pipeline.len(), pipeline_length);
+            for d in m.elaboration_pipeline {
+                write!(dest, ",{}", d.as_nanos())?;
+            }
+            writeln!(dest)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_steps_csv(
+        data: Vec<(Arc<str>, Duration)>,
+        dest: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        writeln!(dest, "rule,time")?;
+        for (rule, t) in data {
+            writeln!(dest, "{},{}", rule, t.as_nanos())?;
+        }
+        Ok(())
+    }
+}
+
+impl CollectResults for CsvBenchmarkResults {
+    fn add_step_measurement(&mut self, _: &str, _: &str, rule: &str, time: Duration) {
+        let rule = self.intern(rule);
+        self.steps.push((rule, time));
+    }
+
+    fn add_assume_measurement(&mut self, file: &str, id: &str, _: bool, time: Duration) {
+        self.add_step_measurement(file, id, "assume", time);
+    }
+
+    fn add_polyeq_depth(&mut self, _: usize) {}
+
+    fn add_run_measurement(&mut self, (file, i): &RunId, measurement: RunMeasurement) {
+        let id = (self.intern(file), *i);
+        self.runs.insert(id, measurement);
+    }
+
+    fn register_holey(&mut self) {
+        self.is_holey = true;
+    }
+
+    fn register_error(&mut self, _: &crate::Error) {
+        self.num_errors += 1;
+    }
+
+    fn combine(mut a: Self, b: Self) -> Self {
+        // This assumes that the same run never appears in both `a` and `b`. This should be the case
+        // in benchmarks anyway
+        a.runs.extend(b.runs);
+        a.steps.extend(b.steps);
+        a.num_errors += b.num_errors;
+        a
+    }
+}
@@ -0,0 +1,217 @@
+//! Saving benchmark results and comparing them against previous results, to detect performance
+//! regressions.
+
+use super::{Metrics, MetricsUnit, OnlineBenchmarkResults, OnlineMetrics};
+use indexmap::IndexMap;
+use std::fmt;
+#[cfg(feature = "serde")]
+use std::io;
+#[cfg(feature = "serde")]
+use thiserror::Error;
+
+/// The critical value of the one-sided t-test used to decide whether a regression is significant.
+/// This corresponds to a confidence level of 95% for a large number of samples.
+const CRITICAL_VALUE: f64 = 1.645;
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Error)]
+pub enum BenchmarkSummaryError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid benchmark results: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The number of samples, mean and standard deviation of a time measurement, in seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeSummary {
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+impl<K: Clone> From<&OnlineMetrics<K>> for TimeSummary {
+    fn from(metrics: &OnlineMetrics<K>) -> Self {
+        Self {
+            count: metrics.count(),
+            mean: metrics.mean().as_f64(),
+            std_dev: metrics.standard_deviation().as_f64(),
+        }
+    }
+}
+
+/// A summary of the results of a benchmark, containing only what is needed to compare it against
+/// another run of the same benchmark.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchmarkSummary {
+    /// The total time per run.
+    pub total: TimeSummary,
+
+    /// The time per step, for each rule.
+    pub by_rule: IndexMap<String, TimeSummary>,
+}
+
+impl From<&OnlineBenchmarkResults> for BenchmarkSummary {
+    fn from(results: &OnlineBenchmarkResults) -> Self {
+        Self {
+            total: results.total().into(),
+            by_rule: results
+                .step_time_by_rule()
+                .iter()
+                .map(|(rule, metrics)| (rule.clone(), metrics.into()))
+                .collect(),
+        }
+    }
+}
+
+impl BenchmarkSummary {
+    /// Writes the summary as a JSON object, with two fields: `total`, and `by_rule`, which maps
+    /// each rule to its summary. Each summary is an object with the fields `count`, `mean` and
+    /// `std_dev`, where the times are given in seconds.
+    #[cfg(feature = "serde")]
+    pub fn write_json(&self, dest: &mut dyn io::Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *dest, self)?;
+        writeln!(dest)
+    }
+
+    /// Reads a summary from a JSON object, in the format written by
+    /// [`BenchmarkSummary::write_json`]. Unknown fields are ignored.
+    #[cfg(feature = "serde")]
+    pub fn read_json(src: &mut dyn io::Read) -> Result<Self, BenchmarkSummaryError> {
+        Ok(serde_json::from_reader(src)?)
+    }
+
+    /// Compares these results against a previous run of the same benchmark, and returns the
+    /// measurements that regressed. A measurement is considered to have regressed if its mean
+    /// is more than `threshold` (as a fraction, e.g. `0.05` for 5%) higher than the old mean, and
+    /// that difference is statistically significant according to Welch's t-test. Rules that are
+    /// not present in both results are ignored.
+    pub fn find_regressions(&self, old: &BenchmarkSummary, threshold: f64) -> Vec<Regression> {
+        let total = std::iter::once(("total".to_owned(), &old.total, &self.total));
+        let by_rule = self.by_rule.iter().filter_map(|(rule, new)| {
+            let old = old.by_rule.get(rule)?;
+            Some((format!("rule '{}'", rule), old, new))
+        });
+        total
+            .chain(by_rule)
+            .filter(|(_, old, new)| is_regression(old, new, threshold))
+            .map(|(name, old, new)| Regression { name, old: *old, new: *new })
+            .collect()
+    }
+}
+
+/// Returns `true` if the new mean is more than `threshold` higher than the old mean, with
+/// statistical confidence. If neither measurement has enough samples to estimate its variance,
+/// only the threshold is considered.
+fn is_regression(old: &TimeSummary, new: &TimeSummary, threshold: f64) -> bool {
+    if old.count == 0 || new.count == 0 {
+        return false;
+    }
+    let limit = old.mean * (1.0 + threshold);
+    if new.mean <= limit {
+        return false;
+    }
+
+    // The old mean is scaled by the threshold, so its variance must also be scaled accordingly
+    let old_variance = (old.std_dev * (1.0 + threshold)).powi(2) / old.count as f64;
+    let new_variance = new.std_dev.powi(2) / new.count as f64;
+    let standard_error = (old_variance + new_variance).sqrt();
+    standard_error == 0.0 || (new.mean - limit) / standard_error > CRITICAL_VALUE
+}
+
+/// A measurement that regressed between two runs of a benchmark.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub name: String,
+    pub old: TimeSummary,
+    pub new: TimeSummary,
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:.3?} -> {:.3?} (+{:.02}%)",
+            self.name,
+            std::time::Duration::from_secs_f64(self.old.mean),
+            std::time::Duration::from_secs_f64(self.new.mean),
+            100.0 * (self.new.mean / self.old.mean - 1.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(count: usize, mean: f64, std_dev: f64) -> TimeSummary {
+        TimeSummary { count, mean, std_dev }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_json_round_trip() {
+        let original = BenchmarkSummary {
+            total: summary(10, 0.25, 0.01),
+            by_rule: [
+                ("resolution".to_owned(), summary(120, 3.5e-5, 1e-6)),
+                ("la_generic".to_owned(), summary(7, 1.25e-3, 0.0)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let mut buf = Vec::new();
+        original.write_json(&mut buf).unwrap();
+        let read = BenchmarkSummary::read_json(&mut buf.as_slice()).unwrap();
+        assert_eq!(original, read);
+
+        // Unknown fields are ignored, but every field of a summary is required
+        let json = r#"{"total": {"count": 1, "mean": 2.5, "std_dev": 0, "max": 3}, "by_rule": {},
+            "version": [1, "x"]}"#;
+        let read = BenchmarkSummary::read_json(&mut json.as_bytes()).unwrap();
+        assert_eq!(read.total, summary(1, 2.5, 0.0));
+        for invalid in [
+            r#"{"by_rule": {}}"#,
+            r#"{"total": {"count": 1}, "by_rule": {}}"#,
+            "{",
+        ] {
+            assert!(BenchmarkSummary::read_json(&mut invalid.as_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_find_regressions() {
+        let old = BenchmarkSummary {
+            total: summary(100, 1.0, 0.05),
+            by_rule: [
+                ("a".to_owned(), summary(100, 1.0, 0.05)),
+                ("b".to_owned(), summary(100, 1.0, 2.0)),
+                ("c".to_owned(), summary(100, 1.0, 0.05)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let new = BenchmarkSummary {
+            // Within the threshold
+            total: summary(100, 1.04, 0.05),
+            by_rule: [
+                // Beyond the threshold, and significant
+                ("a".to_owned(), summary(100, 1.2, 0.05)),
+                // Beyond the threshold, but too noisy to be significant
+                ("b".to_owned(), summary(100, 1.2, 2.0)),
+                // Faster
+                ("c".to_owned(), summary(100, 0.5, 0.05)),
+                // Not present in the old results
+                ("d".to_owned(), summary(100, 5.0, 0.05)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let regressions = new.find_regressions(&old, 0.05);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].name, "rule 'a'");
+    }
+}
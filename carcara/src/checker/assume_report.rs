@@ -0,0 +1,171 @@
+//! Finds the problem premises that are closest to an `assume` term that could not be matched, so
+//! the error can point out exactly where they differ.
+
+use super::error::{PremiseMismatch, TermDifference};
+use crate::ast::*;
+use indexmap::{IndexMap, IndexSet};
+
+/// The maximum number of premises included in the report.
+const MAX_REPORTED_PREMISES: usize = 3;
+
+/// Returns the premises that are closest to `term`, sorted by their distance to it. The distance
+/// between two terms is the total size of the subterms that differ between them.
+pub(super) fn closest_premises(
+    term: &Rc<Term>,
+    premises: &IndexSet<Rc<Term>>,
+) -> Vec<PremiseMismatch> {
+    let mut sizes = IndexMap::new();
+    let mut result: Vec<_> = premises
+        .iter()
+        .map(|premise| {
+            let mut differences = Vec::new();
+            let distance = diff(&mut sizes, premise, term, &mut Vec::new(), &mut differences);
+            PremiseMismatch {
+                premise: premise.clone(),
+                distance,
+                differences,
+            }
+        })
+        .collect();
+
+    // `sort_by_key` is stable, so premises with the same distance stay in the original order
+    result.sort_by_key(|m| m.distance);
+    result.truncate(MAX_REPORTED_PREMISES);
+    result
+}
+
+fn diff(
+    sizes: &mut IndexMap<Rc<Term>, usize>,
+    expected: &Rc<Term>,
+    got: &Rc<Term>,
+    path: &mut Vec<usize>,
+    differences: &mut Vec<TermDifference>,
+) -> usize {
+    if expected == got {
+        return 0;
+    }
+
+    let children = match (expected.as_ref(), got.as_ref()) {
+        (Term::Op(a, a_args), Term::Op(b, b_args)) if a == b && a_args.len() == b_args.len() => {
+            Some((a_args.as_slice(), b_args.as_slice()))
+        }
+        (Term::App(f, a_args), Term::App(g, b_args)) if f == g && a_args.len() == b_args.len() => {
+            Some((a_args.as_slice(), b_args.as_slice()))
+        }
+        (
+            Term::ParamOp {
+                op: a,
+                op_args: a_op_args,
+                args: a_args,
+            },
+            Term::ParamOp {
+                op: b,
+                op_args: b_op_args,
+                args: b_args,
+            },
+        ) if a == b && a_op_args == b_op_args && a_args.len() == b_args.len() => {
+            Some((a_args.as_slice(), b_args.as_slice()))
+        }
+        (Term::Binder(a, a_bindings, a_inner), Term::Binder(b, b_bindings, b_inner))
+            if a == b && a_bindings == b_bindings =>
+        {
+            Some((std::slice::from_ref(a_inner), std::slice::from_ref(b_inner)))
+        }
+        (Term::Let(a_bindings, a_inner), Term::Let(b_bindings, b_inner))
+            if a_bindings == b_bindings =>
+        {
+            Some((std::slice::from_ref(a_inner), std::slice::from_ref(b_inner)))
+        }
+        _ => None,
+    };
+
+    match children {
+        Some((a_args, b_args)) => {
+            let mut distance = 0;
+            for (i, (a, b)) in a_args.iter().zip(b_args).enumerate() {
+                path.push(i);
+                distance += diff(sizes, a, b, path, differences);
+                path.pop();
+            }
+            distance
+        }
+        None => {
+            differences.push(TermDifference {
+                path: path.clone(),
+                expected: expected.clone(),
+                got: got.clone(),
+            });
+            size(sizes, expected).max(size(sizes, got))
+        }
+    }
+}
+
+/// Computes the number of distinct subterms in a term.
+fn size(cache: &mut IndexMap<Rc<Term>, usize>, term: &Rc<Term>) -> usize {
+    if let Some(&s) = cache.get(term) {
+        return s;
+    }
+    let mut seen = IndexSet::new();
+    let mut stack = vec![term];
+    while let Some(current) = stack.pop() {
+        if seen.insert(current) {
+            stack.extend(children(current));
+        }
+    }
+    cache.insert(term.clone(), seen.len());
+    seen.len()
+}
+
+fn children(term: &Rc<Term>) -> Vec<&Rc<Term>> {
+    match term.as_ref() {
+        Term::Op(_, args) | Term::ParamOp { args, .. } => args.iter().collect(),
+        Term::App(f, args) => std::iter::once(f).chain(args).collect(),
+        Term::Binder(_, _, inner) | Term::Let(_, inner) => vec![inner],
+        Term::Const(_) | Term::Var(..) | Term::Sort(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::tests::parse_terms;
+
+    #[test]
+    fn test_closest_premises() {
+        let definitions = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (declare-fun f (Int) Int)
+            (declare-fun x () Int)
+            (declare-fun y () Int)
+        ";
+        let mut pool = PrimitivePool::new();
+        let [term, close, far, other] = parse_terms(
+            &mut pool,
+            definitions,
+            [
+                "(and p (= (f x) y))",
+                "(and p (= (f y) y))",
+                "(and q (= (f x) x))",
+                "(or p q)",
+            ],
+        );
+        let premises = [other.clone(), far.clone(), close.clone()]
+            .into_iter()
+            .collect();
+
+        let report = closest_premises(&term, &premises);
+        assert_eq!(3, report.len());
+        assert_eq!(close, report[0].premise);
+        assert_eq!(1, report[0].distance);
+        assert_eq!(1, report[0].differences.len());
+        assert_eq!(vec![1, 0, 0], report[0].differences[0].path);
+        assert_eq!("y", report[0].differences[0].expected.to_string());
+        assert_eq!("x", report[0].differences[0].got.to_string());
+        assert_eq!(far, report[1].premise);
+        assert_eq!(2, report[1].distance);
+        assert_eq!(2, report[1].differences.len());
+        assert_eq!(other, report[2].premise);
+        assert!(report[2].differences[0].path.is_empty());
+    }
+}
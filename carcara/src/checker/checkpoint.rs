@@ -0,0 +1,216 @@
+//! Checkpoints of the checking progress, used to resume checking a long proof after the checker
+//! was interrupted.
+//!
+//! Only root-level commands are checkpointed, since the checker state between two root-level
+//! commands consists only of whether the proof is holey and whether it already reached its
+//! conclusion. The term pool is not saved: when resuming, the proof is parsed again, and the
+//! commands that were already checked are skipped.
+
+use super::*;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+const HEADER: &str = "carcara-checkpoint 1";
+
+/// The progress of checking a proof, up to a root-level command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// A fingerprint of the proof, used to ensure that a checkpoint is only used to resume
+    /// checking the same proof.
+    pub fingerprint: u64,
+
+    /// The number of root-level commands that were already checked.
+    pub checked_commands: usize,
+
+    pub is_holey: bool,
+    pub reached_conclusion: bool,
+}
+
+impl Checkpoint {
+    /// Computes the fingerprint of a proof. This depends on the ids, rules and clause lengths of
+    /// all root-level commands, so it can be computed cheaply even for very large proofs.
+    pub fn fingerprint(proof: &Proof) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        proof.commands.len().hash(&mut hasher);
+        for command in &proof.commands {
+            command.id().hash(&mut hasher);
+            command.clause().len().hash(&mut hasher);
+            if let ProofCommand::Step(s) = command {
+                s.rule.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    pub fn write(&self, dest: &mut dyn Write) -> io::Result<()> {
+        writeln!(dest, "{}", HEADER)?;
+        writeln!(dest, "fingerprint {:016x}", self.fingerprint)?;
+        writeln!(dest, "checked {}", self.checked_commands)?;
+        writeln!(dest, "holey {}", self.is_holey)?;
+        writeln!(dest, "reached-conclusion {}", self.reached_conclusion)
+    }
+
+    pub fn read(src: &mut dyn BufRead) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid checkpoint");
+        let mut lines = src.lines();
+        let mut next_field = |name: &str| -> io::Result<String> {
+            let line = lines.next().ok_or_else(invalid)??;
+            match line.split_once(' ') {
+                Some((key, value)) if key == name => Ok(value.to_owned()),
+                _ => Err(invalid()),
+            }
+        };
+        if next_field("carcara-checkpoint")? != "1" {
+            return Err(invalid());
+        }
+        Ok(Self {
+            fingerprint: u64::from_str_radix(&next_field("fingerprint")?, 16)
+                .map_err(|_| invalid())?,
+            checked_commands: next_field("checked")?.parse().map_err(|_| invalid())?,
+            is_holey: next_field("holey")?.parse().map_err(|_| invalid())?,
+            reached_conclusion: next_field("reached-conclusion")?
+                .parse()
+                .map_err(|_| invalid())?,
+        })
+    }
+
+    /// Saves the checkpoint to a file. The checkpoint is first written to a temporary file, which
+    /// then replaces the destination, so an interruption while saving never leaves a partially
+    /// written checkpoint.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let temp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&temp_path)?;
+        self.write(&mut file)?;
+        file.sync_all()?;
+        fs::rename(temp_path, path)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read(&mut io::BufReader::new(fs::File::open(path)?))
+    }
+}
+
+impl<'c> ProofChecker<'c> {
+    /// Checks a proof, calling `save` with the current progress whenever at least `interval` has
+    /// passed since the last checkpoint. If `resume` is given, and it was created for the same
+    /// proof, the root-level commands that it covers are not checked again. Checkpoints created
+    /// for a different proof are ignored.
+    pub fn check_resumable(
+        &mut self,
+        problem: &Problem,
+        proof: &Proof,
+        resume: Option<&Checkpoint>,
+        interval: Duration,
+        mut save: impl FnMut(&Checkpoint) -> io::Result<()>,
+    ) -> CarcaraResult<bool> {
+        let fingerprint = Checkpoint::fingerprint(proof);
+        let mut start = 0;
+        match resume {
+            Some(c) if c.fingerprint == fingerprint => {
+                start = c.checked_commands.min(proof.commands.len());
+                self.is_holey = c.is_holey;
+                self.reached_empty_clause = c.reached_conclusion;
+            }
+            Some(_) => tracing::warn!("checkpoint was created for a different proof, ignoring it"),
+            None => (),
+        }
+
+        self.config.check_rule_filters(proof.iter())?;
+        witnesses::check_witnesses(problem, proof)?;
+        self.logic = Logic::from_prelude(&self.config, &problem.prelude);
+        let mut last_save = Instant::now();
+        for i in start..proof.commands.len() {
+            // Since premises always refer to previous commands, the slice up to the current
+            // command is enough to check it
+            let iter = ProofIter::starting_at(&proof.commands[..=i], i);
+            self.check_commands(
+                problem,
+                iter,
+                None::<&mut CheckerStatistics<OnlineBenchmarkResults>>,
+            )?;
+            if last_save.elapsed() >= interval {
+                save(&Checkpoint {
+                    fingerprint,
+                    checked_commands: i + 1,
+                    is_holey: self.is_holey,
+                    reached_conclusion: self.reached_empty_clause,
+                })?;
+                last_save = Instant::now();
+            }
+        }
+
+        if self.reached_empty_clause {
+            Ok(self.is_holey)
+        } else {
+            Err(self.config.missing_conclusion_error())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_checkpoint() {
+        let problem = "
+            (declare-fun p () Bool)
+            (assert p)
+            (assert (not p))
+        ";
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (step t3 (cl p) :rule hole :premises (h1))
+            (step t4 (cl) :rule resolution :premises (t3 h2))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+
+        // With a zero interval, a checkpoint is saved after every command
+        let mut checkpoints = Vec::new();
+        let result = ProofChecker::new(&mut pool, Config::new()).check_resumable(
+            &problem,
+            &proof,
+            None,
+            Duration::ZERO,
+            |c| {
+                checkpoints.push(*c);
+                Ok(())
+            },
+        );
+        assert!(result.unwrap());
+        assert_eq!(checkpoints.len(), 4);
+        assert!(checkpoints[2].is_holey && !checkpoints[2].reached_conclusion);
+        assert!(checkpoints[3].reached_conclusion);
+
+        let mut written = Vec::new();
+        checkpoints[1].write(&mut written).unwrap();
+        let read = Checkpoint::read(&mut written.as_slice()).unwrap();
+        assert_eq!(read, checkpoints[1]);
+        assert!(Checkpoint::read(&mut "fingerprint 0".as_bytes()).is_err());
+
+        // When resuming, the commands covered by the checkpoint are not checked again, and the
+        // state of the checker is restored
+        let mut resumed = Vec::new();
+        let result = ProofChecker::new(&mut pool, Config::new()).check_resumable(
+            &problem,
+            &proof,
+            Some(&checkpoints[2]),
+            Duration::ZERO,
+            |c| {
+                resumed.push(*c);
+                Ok(())
+            },
+        );
+        assert!(result.unwrap());
+        assert_eq!(resumed, [checkpoints[3]]);
+    }
+}
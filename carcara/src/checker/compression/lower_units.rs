@@ -0,0 +1,575 @@
+//! The Lower Units algorithm.
+//!
+//! A unit clause that is used as a premise by more than one resolution step can instead be resolved
+//! only once, at the end of the proof. This pass removes such units from the resolution steps that
+//! use them, and adds a final resolution step that reintroduces them.
+//!
+//! The proof is compressed in its graph representation (see [`ProofNode`]), where each step refers
+//! directly to its premises, regardless of the scope they are in. Each scope is compressed
+//! independently, and the units lowered in a subproof may come from an enclosing scope. The root of
+//! the compression in each scope is its last step or, if the last step of a subproof is not a
+//! resolution step (like a `subproof` step that discharges its assumptions), the step before it.
+//! Since the graph representation only contains the commands used to derive the conclusion of the
+//! proof, all other commands are removed. In the compressed proof, the root-level `assume` commands
+//! come before all other commands.
+
+use super::{is_resolution_rule, resolves_to, CompressionError, CompressionResult};
+use crate::{
+    ast::*,
+    trace_map::{TraceMap, TraceRecorder},
+};
+use indexmap::IndexSet;
+use std::collections::{HashMap, HashSet};
+
+/// Compresses a proof by lowering its units, and returns the commands of the compressed proof.
+pub(super) fn compress(
+    pool: &mut dyn TermPool,
+    commands: &[ProofCommand],
+    trace: Option<&mut TraceMap>,
+) -> CompressionResult<Vec<ProofCommand>> {
+    let Some(last) = commands.last() else {
+        return Ok(Vec::new());
+    };
+    let root = ProofNode::from_commands_with_root_id(commands.to_vec(), last.id())
+        .ok_or_else(|| CompressionError::MissingRoot(last.id().to_owned()))?;
+    let mut order = Vec::new();
+    root.traverse(|node| order.push(node.clone()));
+
+    let mut compressor = Compressor {
+        pool,
+        consumers: HashMap::new(),
+        positions: HashMap::new(),
+        rebuilt: HashMap::new(),
+    };
+    for (i, node) in order.iter().enumerate() {
+        compressor.positions.insert(node.clone(), i);
+        compressor.consumers.entry(node.clone()).or_default();
+        if let ProofNode::Step(s) = node.as_ref() {
+            for p in &s.premises {
+                compressor
+                    .consumers
+                    .entry(p.clone())
+                    .or_default()
+                    .insert(node.clone());
+            }
+        }
+    }
+
+    let scope_roots = std::iter::once(&root)
+        .chain(order.iter().filter_map(|node| match node.as_ref() {
+            ProofNode::Subproof(s) => Some(&s.last_step),
+            _ => None,
+        }))
+        .filter_map(scope_root);
+    let plans: HashMap<_, _> = scope_roots
+        .filter_map(|root| Some((root.clone(), compressor.plan_lowering(root)?)))
+        .collect();
+
+    // The steps in each region are rebuilt together with the root of their scope
+    let region_steps: HashSet<_> = plans
+        .values()
+        .flat_map(|plan| &plan.region[..plan.region.len() - 1])
+        .collect();
+    for node in &order {
+        if region_steps.contains(node) {
+            continue;
+        }
+        match plans.get(node) {
+            Some(plan) => compressor.rebuild_region(plan),
+            None => {
+                let new_node = compressor.remap(node);
+                compressor.rebuilt.insert(node.clone(), new_node);
+            }
+        }
+    }
+
+    if let Some(trace) = trace {
+        // The nodes are recorded in postorder, so the new steps introduced when lowering the units
+        // of a region are attributed to the root of the region
+        let mut recorder = TraceRecorder::new();
+        for node in &order {
+            recorder.record(node, &compressor.rebuilt[node]);
+        }
+        *trace = recorder.finish().unwrap_or_default();
+    }
+
+    let mut result = move_assumes_first(compressor.rebuilt[&root].into_commands());
+    renumber_contexts(&mut result, &mut 0);
+    Ok(result)
+}
+
+/// Moves the root-level `assume` commands to the start of the proof, keeping the relative order of
+/// all commands, and updates the premises that refer to them.
+fn move_assumes_first(commands: Vec<ProofCommand>) -> Vec<ProofCommand> {
+    let (assumes, others): (Vec<_>, Vec<_>) = commands
+        .into_iter()
+        .enumerate()
+        .partition(|(_, c)| c.is_assume());
+    let mut index_map = vec![None; assumes.len() + others.len()];
+    let mut result = Vec::with_capacity(index_map.len());
+    for (new, (old, command)) in assumes.into_iter().chain(others).enumerate() {
+        index_map[old] = Some(new);
+        result.push(command);
+    }
+    for command in &mut result {
+        // Every command is kept, so all premises can be remapped
+        remap_root_premises(command, &index_map);
+    }
+    result
+}
+
+/// Returns the step if the node is a resolution step that doesn't discharge any assumptions.
+fn as_resolution(node: &ProofNode) -> Option<&StepNode> {
+    node.as_step()
+        .filter(|s| is_resolution_rule(&s.rule) && s.discharge.is_empty())
+}
+
+/// Given the last step of a scope, returns the root of the compression in that scope, if there is
+/// one.
+fn scope_root(last: &Rc<ProofNode>) -> Option<&Rc<ProofNode>> {
+    if as_resolution(last).is_some() {
+        return Some(last);
+    }
+    let previous = last.as_step()?.previous_step.as_ref()?;
+    as_resolution(previous).map(|_| previous)
+}
+
+/// The units to be lowered in a scope, and the resolution steps that must be rebuilt because of
+/// that.
+#[derive(Debug)]
+struct LoweringPlan {
+    /// The resolution steps whose conclusions are only used by other steps in the region, ordered
+    /// so that each step comes after its premises. The last step is the root of the scope.
+    region: Vec<Rc<ProofNode>>,
+    units: IndexSet<Rc<ProofNode>>,
+}
+
+struct Compressor<'a> {
+    pool: &'a mut dyn TermPool,
+
+    /// For each node, the steps that use it as a premise.
+    consumers: HashMap<Rc<ProofNode>, IndexSet<Rc<ProofNode>>>,
+
+    /// The position of each node in a postorder traversal of the proof.
+    positions: HashMap<Rc<ProofNode>, usize>,
+
+    /// The node that replaces each node that was already rebuilt.
+    rebuilt: HashMap<Rc<ProofNode>, Rc<ProofNode>>,
+}
+
+impl<'a> Compressor<'a> {
+    /// Finds the units that can be lowered in the scope of the given root, which must be a
+    /// resolution step.
+    ///
+    /// Starting from the root, we find the region of resolution steps in the same scope whose
+    /// conclusions are only used by other steps in the region. A unit can be lowered if it is
+    /// outside the region and is used only by steps in it. Returns `None` if there are no units to
+    /// lower.
+    fn plan_lowering(&self, root: &Rc<ProofNode>) -> Option<LoweringPlan> {
+        let depth = root.depth();
+        let is_candidate =
+            |node: &Rc<ProofNode>| node.depth() == depth && as_resolution(node).is_some();
+
+        // We visit the candidate steps in postorder, so every step comes after its premises
+        let mut postorder = Vec::new();
+        let mut seen = HashSet::new();
+        let mut todo = vec![(root, false)];
+        while let Some((node, is_done)) = todo.pop() {
+            if is_done {
+                postorder.push(node.clone());
+                continue;
+            }
+            if !seen.insert(node) {
+                continue;
+            }
+            todo.push((node, true));
+            let premises = &node.as_step().unwrap().premises;
+            todo.extend(
+                premises
+                    .iter()
+                    .rev()
+                    .filter(|p| is_candidate(p))
+                    .map(|p| (p, false)),
+            );
+        }
+
+        let mut in_region = HashSet::from([root.clone()]);
+        for node in postorder.iter().rev().skip(1) {
+            let consumers = &self.consumers[node];
+            if !consumers.is_empty() && consumers.iter().all(|c| in_region.contains(c)) {
+                in_region.insert(node.clone());
+            }
+        }
+        postorder.retain(|node| in_region.contains(node));
+
+        let mut units: IndexSet<_> = postorder
+            .iter()
+            .flat_map(|node| &node.as_step().unwrap().premises)
+            .filter(|p| {
+                let consumers = &self.consumers[*p];
+                !in_region.contains(*p)
+                    && p.clause().len() == 1
+                    && consumers.len() >= 2
+                    && consumers.iter().all(|c| in_region.contains(c))
+            })
+            .cloned()
+            .collect();
+        units.sort_by(|a, b| self.positions[a].cmp(&self.positions[b]));
+
+        // Each resolution step must keep at least one premise that is not lowered
+        for node in &postorder {
+            let premises = &node.as_step().unwrap().premises;
+            if premises.iter().all(|p| units.contains(p)) {
+                if let Some(p) = premises.first() {
+                    units.shift_remove(p);
+                }
+            }
+        }
+        if units.is_empty() {
+            return None;
+        }
+        Some(LoweringPlan { region: postorder, units })
+    }
+
+    /// Rebuilds the given node, replacing its premises with their rebuilt versions. If none of them
+    /// changed, the node itself is returned.
+    fn remap(&self, node: &Rc<ProofNode>) -> Rc<ProofNode> {
+        let map = |nodes: &[Rc<ProofNode>]| -> Vec<_> {
+            nodes.iter().map(|n| self.rebuilt[n].clone()).collect()
+        };
+        let new_node = match node.as_ref() {
+            ProofNode::Assume { .. } => return node.clone(),
+            ProofNode::Step(s) => {
+                let premises = map(&s.premises);
+                let discharge = map(&s.discharge);
+                let previous_step = s.previous_step.as_ref().map(|p| self.rebuilt[p].clone());
+                if premises == s.premises
+                    && discharge == s.discharge
+                    && previous_step == s.previous_step
+                {
+                    return node.clone();
+                }
+                ProofNode::Step(StepNode {
+                    premises,
+                    discharge,
+                    previous_step,
+                    ..s.clone()
+                })
+            }
+            ProofNode::Subproof(s) => {
+                let last_step = self.rebuilt[&s.last_step].clone();
+                if last_step == s.last_step {
+                    return node.clone();
+                }
+                let outbound_premises: IndexSet<_> =
+                    map(&s.outbound_premises).into_iter().collect();
+                ProofNode::Subproof(SubproofNode {
+                    last_step,
+                    args: s.args.clone(),
+                    outbound_premises: outbound_premises.into_iter().collect(),
+                })
+            }
+        };
+        Rc::new(new_node)
+    }
+
+    /// Rebuilds the steps in the region of a scope, lowering the units in the plan. If that would
+    /// lead to an invalid resolution step, the steps are only remapped instead.
+    fn rebuild_region(&mut self, plan: &LoweringPlan) {
+        if let Some(new_nodes) = self.lower_units(plan) {
+            self.rebuilt.extend(new_nodes);
+            return;
+        }
+        for node in &plan.region {
+            let new_node = self.remap(node);
+            self.rebuilt.insert(node.clone(), new_node);
+        }
+    }
+
+    /// Removes the units in the plan from the resolution steps in the region, and reintroduces them
+    /// in a final resolution step, which replaces the root of the region. Returns the node that
+    /// replaces each step in the region, or `None` if any of the rebuilt resolution steps is not
+    /// valid.
+    fn lower_units(
+        &mut self,
+        plan: &LoweringPlan,
+    ) -> Option<HashMap<Rc<ProofNode>, Rc<ProofNode>>> {
+        let root = plan.region.last().unwrap();
+        let units = &plan.units;
+
+        // The new node for each step in the region
+        let mut new_nodes: HashMap<Rc<ProofNode>, Rc<ProofNode>> = HashMap::new();
+
+        // For each step in the region, the literals that its new conclusion has, but that were not
+        // in its original conclusion
+        let mut extras: HashMap<Rc<ProofNode>, Vec<Rc<Term>>> = HashMap::new();
+
+        for node in &plan.region {
+            let step = node.as_step().unwrap();
+            let is_root = node == root;
+            let lookup =
+                |p: &Rc<ProofNode>| new_nodes.get(p).unwrap_or_else(|| &self.rebuilt[p]).clone();
+            let (removed, kept): (Vec<_>, Vec<_>) =
+                step.premises.iter().partition(|p| units.contains(*p));
+
+            if kept.len() == 1 && !removed.is_empty() && !is_root {
+                // The step becomes redundant, so it is replaced by its only remaining premise
+                let new_premise = lookup(kept[0]);
+                let extra = new_premise
+                    .clause()
+                    .iter()
+                    .filter(|t| !step.clause.contains(t))
+                    .cloned()
+                    .collect();
+                extras.insert(node.clone(), extra);
+                new_nodes.insert(node.clone(), new_premise);
+                continue;
+            }
+
+            let new_kept: Vec<_> = kept.iter().map(|p| lookup(p)).collect();
+
+            // If the step doesn't use any lowered unit, and none of its premises changed, we keep it
+            // as it is, including its arguments
+            let has_extras = |p: &Rc<ProofNode>| extras.get(p).is_some_and(|e| !e.is_empty());
+            if removed.is_empty() && !is_root && !kept.iter().any(|p| has_extras(p)) {
+                let new_node = ProofNode::Step(StepNode { premises: new_kept, ..step.clone() });
+                new_nodes.insert(node.clone(), Rc::new(new_node));
+                continue;
+            }
+
+            let mut clause = step.clause.clone();
+            for t in kept.iter().filter_map(|p| extras.get(*p)).flatten() {
+                if !clause.contains(t) {
+                    clause.push(t.clone());
+                }
+            }
+            // The complements of the removed units are no longer eliminated
+            for u in &removed {
+                let (n, unit) = u.clause()[0].remove_all_negations();
+                for t in new_kept.iter().flat_map(|p| p.clause()) {
+                    let (m, inner) = t.remove_all_negations();
+                    if inner == unit && m.abs_diff(n) == 1 && !clause.contains(t) {
+                        clause.push(t.clone());
+                    }
+                }
+            }
+            let extra = clause
+                .iter()
+                .filter(|t| !step.clause.contains(t))
+                .cloned()
+                .collect();
+            extras.insert(node.clone(), extra);
+
+            let id = if is_root {
+                format!("{}.lowered", step.id)
+            } else {
+                step.id.clone()
+            };
+            let new_step = StepNode {
+                id,
+                depth: step.depth,
+                clause,
+                rule: step.rule.clone(),
+                premises: new_kept,
+                args: Vec::new(),
+                discharge: Vec::new(),
+                previous_step: None,
+            };
+            if !self.is_valid_resolution(&new_step) {
+                return None;
+            }
+            let new_node = if is_root && new_step.premises.len() == 1 {
+                new_step.premises[0].clone()
+            } else {
+                Rc::new(ProofNode::Step(new_step))
+            };
+            new_nodes.insert(node.clone(), new_node);
+        }
+
+        // Finally, we resolve the new root with all the lowered units, to derive the original
+        // conclusion
+        let step = root.as_step().unwrap();
+        let premises = std::iter::once(new_nodes[root].clone())
+            .chain(units.iter().map(|u| self.rebuilt[u].clone()))
+            .collect();
+        let final_step = StepNode {
+            id: step.id.clone(),
+            depth: step.depth,
+            clause: step.clause.clone(),
+            rule: "resolution".to_owned(),
+            premises,
+            args: Vec::new(),
+            discharge: Vec::new(),
+            previous_step: None,
+        };
+        if !self.is_valid_resolution(&final_step) {
+            return None;
+        }
+        new_nodes.insert(root.clone(), Rc::new(ProofNode::Step(final_step)));
+        Some(new_nodes)
+    }
+
+    fn is_valid_resolution(&mut self, step: &StepNode) -> bool {
+        let premises: Vec<_> = step.premises.iter().map(|p| p.clause()).collect();
+        resolves_to(self.pool, &step.id, &step.clause, &premises)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{checker, checker::compression::compress_proof, parser};
+
+    /// Compresses the given proof, and returns the compressed proof and whether it is valid.
+    fn run(proof: &str) -> (Proof, bool) {
+        let problem = "
+            (declare-const p Bool)
+            (declare-const q Bool)
+            (declare-const r Bool)
+            (assert (or p q))
+            (assert (or p (not q)))
+            (assert (not p))
+            (assert (or p q r))
+            (assert (not r))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let compressed = compress_proof(&proof, &mut pool).unwrap();
+        let valid = checker::ProofChecker::new(&mut pool, checker::Config::new())
+            .check(&problem, &compressed)
+            .is_ok();
+        (compressed, valid)
+    }
+
+    fn ids(commands: &[ProofCommand]) -> Vec<&str> {
+        commands.iter().map(ProofCommand::id).collect()
+    }
+
+    #[test]
+    fn test_lower_units() {
+        let (compressed, valid) = run("
+            (assume h1 (or p q))
+            (assume h2 (or p (not q)))
+            (assume h3 (not p))
+            (step t4 (cl p q) :rule or :premises (h1))
+            (step t5 (cl q) :rule resolution :premises (t4 h3))
+            (step t6 (cl p (not q)) :rule or :premises (h2))
+            (anchor :step t7)
+            (assume t7.h1 (not p))
+            (step t7.t2 (cl (not q)) :rule resolution :premises (t6 t7.h1))
+            (step t7 (cl (not (not p)) (not q)) :rule subproof :discharge (t7.h1))
+            (step t8 (cl (not q)) :rule resolution :premises (t6 h3))
+            (step t9 (cl) :rule resolution :premises (t5 t8))
+        ");
+        assert!(valid);
+
+        // The subproof `t7` is not used to derive the conclusion, so it is removed. The assumptions
+        // come before all other commands
+        assert_eq!(
+            ids(&compressed.commands),
+            ["h1", "h2", "h3", "t4", "t6", "t9.lowered", "t9"]
+        );
+        let ProofCommand::Step(t9) = &compressed.commands[6] else {
+            panic!()
+        };
+        assert_eq!(t9.premises, [(0, 5), (0, 2)]);
+
+        // Scopes where no unit is used more than once are left unchanged
+        let (compressed, valid) = run("
+            (assume h1 (or p q))
+            (assume h2 (or p (not q)))
+            (assume h3 (not p))
+            (step t4 (cl p q) :rule or :premises (h1))
+            (step t5 (cl p (not q)) :rule or :premises (h2))
+            (step t6 (cl p) :rule resolution :premises (t4 t5))
+            (step t7 (cl) :rule resolution :premises (t6 h3))
+        ");
+        assert!(valid);
+        assert_eq!(
+            ids(&compressed.commands),
+            ["h1", "h2", "h3", "t4", "t5", "t6", "t7"]
+        );
+    }
+
+    #[test]
+    fn test_lower_units_in_subproof() {
+        let (compressed, _) = run("
+            (anchor :step t1)
+            (assume t1.h1 (or p q))
+            (assume t1.h2 (or p (not q)))
+            (assume t1.h3 (not p))
+            (step t1.t4 (cl p q) :rule or :premises (t1.h1))
+            (step t1.t5 (cl q) :rule resolution :premises (t1.t4 t1.h3))
+            (step t1.t6 (cl p (not q)) :rule or :premises (t1.h2))
+            (step t1.t7 (cl (not q)) :rule resolution :premises (t1.t6 t1.h3))
+            (step t1 (cl) :rule resolution :premises (t1.t5 t1.t7))
+        ");
+        let ProofCommand::Subproof(s) = &compressed.commands[0] else {
+            panic!()
+        };
+        assert_eq!(
+            ids(&s.commands),
+            [
+                "t1.h1",
+                "t1.t4",
+                "t1.h2",
+                "t1.t6",
+                "t1.lowered",
+                "t1.h3",
+                "t1"
+            ]
+        );
+        let ProofCommand::Step(root) = &s.commands[6] else {
+            panic!()
+        };
+        assert_eq!(root.premises, [(1, 4), (1, 5)]);
+    }
+
+    #[test]
+    fn test_lower_units_across_subproofs() {
+        // The unit `h3` is used twice inside the subproof, which ends with a `subproof` step. It is
+        // lowered to the step before it, and the assumption is still discharged
+        let (compressed, valid) = run("
+            (assume h1 (or p q r))
+            (assume h2 (or p (not q)))
+            (assume h3 (not p))
+            (assume h4 (not p))
+            (assume h5 (not r))
+            (step t6 (cl p q r) :rule or :premises (h1))
+            (step t7 (cl p (not q)) :rule or :premises (h2))
+            (anchor :step t8)
+            (assume t8.a0 (not p))
+            (step t8.t1 (cl q r) :rule resolution :premises (t6 h3))
+            (step t8.t2 (cl (not q)) :rule resolution :premises (t7 h3))
+            (step t8.t3 (cl r) :rule resolution :premises (t8.t1 t8.t2))
+            (step t8 (cl (not (not p)) r) :rule subproof :discharge (t8.a0))
+            (step t9 (cl r) :rule resolution :premises (t8 h4))
+            (step t10 (cl) :rule resolution :premises (t9 h5))
+        ");
+        assert!(valid);
+        assert_eq!(
+            ids(&compressed.commands),
+            ["h2", "h3", "h1", "h4", "h5", "t7", "t6", "t8", "t9", "t10"]
+        );
+        let ProofCommand::Subproof(s) = &compressed.commands[7] else {
+            panic!()
+        };
+        assert_eq!(ids(&s.commands), ["t8.a0", "t8.t3.lowered", "t8.t3", "t8"]);
+
+        // The lowered unit is in the outer scope
+        let ProofCommand::Step(lowered) = &s.commands[1] else {
+            panic!()
+        };
+        assert_eq!(lowered.clause.len(), 2);
+        let ProofCommand::Step(t8_t3) = &s.commands[2] else {
+            panic!()
+        };
+        assert_eq!(t8_t3.premises, [(1, 1), (0, 1)]);
+        assert_eq!(compressed.commands[1].id(), "h3");
+        let ProofCommand::Step(t8) = &s.commands[3] else {
+            panic!()
+        };
+        assert_eq!(t8.discharge, [(1, 0)]);
+    }
+}
@@ -0,0 +1,455 @@
+//! Proof compression algorithms, which reduce the size of the resolution parts of a proof.
+//!
+//! Each algorithm works on one scope at a time, and subproofs are compressed independently of the
+//! scopes that contain them. Algorithms can be chained using `compress_proof_with`. While
+//! compressing, each algorithm can record a [`TraceMap`] from the commands it was given to the
+//! commands that replaced them.
+
+mod lower_units;
+mod prune;
+mod recycle_pivots;
+mod verify;
+
+pub use verify::FailedStep;
+
+use crate::{ast::*, checker, resolution::greedy_resolution, trace_map::TraceMap};
+use thiserror::Error;
+
+fn display_failures(failures: &[FailedStep]) -> String {
+    failures.iter().map(|f| format!("\n{}", f)).collect()
+}
+
+#[derive(Debug, Error)]
+pub enum CompressionError {
+    #[error("premise ({1}, {2}) of step '{0}' does not refer to a preceding command")]
+    InvalidPremise(String, usize, usize),
+
+    #[error("compressed proof has invalid steps:{}", display_failures(.0))]
+    VerificationFailed(Vec<FailedStep>),
+
+    #[error("could not find the last command '{0}' in the graph of the proof")]
+    MissingRoot(String),
+
+    #[error("compressed proof is invalid: {0}")]
+    InvalidProof(crate::Error),
+}
+
+pub type CompressionResult<T> = Result<T, CompressionError>;
+
+/// A proof compression algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    /// Resolves the unit clauses that are used more than once only at the end of each scope.
+    LowerUnits,
+
+    /// Removes resolution steps whose pivot is resolved again later in every path to the end of
+    /// the scope. Steps used as premises more than once are not considered.
+    RecyclePivots,
+
+    /// Like `RecyclePivots`, but also considers steps used as premises more than once, by taking
+    /// the intersection of the pivots resolved later in each of their uses.
+    RecyclePivotsWithIntersection,
+
+    /// Removes the commands that are not used to derive the conclusion of the proof, or the
+    /// conclusion of a subproof that is itself used.
+    Prune,
+}
+
+impl CompressionAlgorithm {
+    fn compress_scope(
+        self,
+        pool: &mut dyn TermPool,
+        commands: &[ProofCommand],
+        maps: &mut Vec<Vec<usize>>,
+        trace: Option<&mut TraceMap>,
+    ) -> CompressionResult<Vec<ProofCommand>> {
+        match self {
+            Self::LowerUnits => lower_units::compress(pool, commands, trace),
+            Self::RecyclePivots => Ok(recycle_pivots::compress_scope(
+                pool, commands, maps, false, trace,
+            )),
+            Self::RecyclePivotsWithIntersection => Ok(recycle_pivots::compress_scope(
+                pool, commands, maps, true, trace,
+            )),
+            Self::Prune => Ok(prune::compress_scope(commands, maps, trace)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The compression algorithms to apply, in order.
+    pub algorithms: Vec<CompressionAlgorithm>,
+
+    /// If `true`, the compressed proof is checked after compression, and an error describing the
+    /// steps that failed is returned if it is not valid.
+    pub verify: bool,
+
+    /// If `true`, a [`TraceMap`] that maps each command in the original proof to the commands
+    /// that replaced it in the compressed proof is returned along with the compressed proof.
+    pub trace: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            algorithms: vec![CompressionAlgorithm::LowerUnits],
+            verify: false,
+            trace: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn algorithms(mut self, value: Vec<CompressionAlgorithm>) -> Self {
+        self.algorithms = value;
+        self
+    }
+
+    pub fn verify(mut self, value: bool) -> Self {
+        self.verify = value;
+        self
+    }
+
+    pub fn trace(mut self, value: bool) -> Self {
+        self.trace = value;
+        self
+    }
+}
+
+/// Compresses a proof by lowering the unit clauses used by more than one resolution step in each
+/// scope. If lowering the units in a scope would lead to an invalid resolution step, that scope is
+/// left unchanged.
+///
+/// Returns an error if a step refers to a premise or discharged assumption that doesn't precede it
+/// in an enclosing scope.
+pub fn compress_proof(proof: &Proof, pool: &mut dyn TermPool) -> CompressionResult<Proof> {
+    compress_proof_with(proof, pool, &[CompressionAlgorithm::LowerUnits])
+}
+
+/// Compresses a proof by applying each of the given algorithms in order. If an algorithm would
+/// lead to an invalid resolution step in a scope, that scope is left unchanged by it.
+pub fn compress_proof_with(
+    proof: &Proof,
+    pool: &mut dyn TermPool,
+    algorithms: &[CompressionAlgorithm],
+) -> CompressionResult<Proof> {
+    compress_and_trace(proof, pool, algorithms, None)
+}
+
+/// Like [`compress_proof_with`], but also composes the trace map of each algorithm into `trace`,
+/// if it is given.
+fn compress_and_trace(
+    proof: &Proof,
+    pool: &mut dyn TermPool,
+    algorithms: &[CompressionAlgorithm],
+    mut trace: Option<&mut TraceMap>,
+) -> CompressionResult<Proof> {
+    validate_premises(&proof.commands, &mut Vec::new())?;
+    let mut commands = proof.commands.clone();
+    for algorithm in algorithms {
+        let _span = tracing::debug_span!("compression", ?algorithm).entered();
+        let mut stage = trace.is_some().then(TraceMap::default);
+        commands = algorithm.compress_scope(pool, &commands, &mut Vec::new(), stage.as_mut())?;
+        if let (Some(trace), Some(stage)) = (trace.as_deref_mut(), &stage) {
+            trace.compose(stage);
+        }
+    }
+    Ok(Proof {
+        constant_definitions: proof.constant_definitions.clone(),
+        commands,
+    })
+}
+
+/// Compresses a proof according to the given configuration. If verification is enabled, the
+/// compressed proof is checked against the problem using `checker_config`. If tracing is enabled,
+/// the trace map of the compression is also returned.
+pub fn compress(
+    pool: &mut PrimitivePool,
+    problem: &Problem,
+    proof: &Proof,
+    config: &Config,
+    checker_config: &checker::Config,
+) -> CompressionResult<(Proof, Option<TraceMap>)> {
+    let mut trace = config
+        .trace
+        .then(|| TraceMap::identity_of_commands(&proof.commands));
+    let compressed = compress_and_trace(proof, pool, &config.algorithms, trace.as_mut())?;
+    if config.verify {
+        verify::verify(pool, problem, proof, &compressed, checker_config)?;
+    }
+    Ok((compressed, trace))
+}
+
+/// Checks that every premise in a scope refers to a command that precedes it. `positions` holds,
+/// for each enclosing scope, the index of the command that is currently being visited.
+fn validate_premises(
+    commands: &[ProofCommand],
+    positions: &mut Vec<usize>,
+) -> CompressionResult<()> {
+    positions.push(0);
+    for (i, command) in commands.iter().enumerate() {
+        *positions.last_mut().unwrap() = i;
+        match command {
+            ProofCommand::Assume { .. } => (),
+            ProofCommand::Step(s) => {
+                for &(depth, index) in s.premises.iter().chain(&s.discharge) {
+                    if positions
+                        .get(depth)
+                        .map_or(true, |&current| index >= current)
+                    {
+                        let id = s.id.clone();
+                        return Err(CompressionError::InvalidPremise(id, depth, index));
+                    }
+                }
+            }
+            ProofCommand::Subproof(s) => validate_premises(&s.commands, positions)?,
+        }
+    }
+    positions.pop();
+    Ok(())
+}
+
+fn is_resolution(step: &ProofStep) -> bool {
+    is_resolution_rule(&step.rule) && step.discharge.is_empty()
+}
+
+fn is_resolution_rule(rule: &str) -> bool {
+    matches!(rule, "resolution" | "th_resolution")
+}
+
+/// Returns `true` if the conclusion of a rebuilt resolution step can be derived from its premises,
+/// which must be in the scope being rebuilt.
+fn is_valid_resolution(
+    pool: &mut dyn TermPool,
+    step: &ProofStep,
+    commands: &[ProofCommand],
+) -> bool {
+    let premises: Vec<_> = step
+        .premises
+        .iter()
+        .map(|&(_, i)| commands[i].clause())
+        .collect();
+    resolves_to(pool, &step.id, &step.clause, &premises)
+}
+
+/// Returns `true` if `clause`, the conclusion of the rebuilt step with the given id, can be derived
+/// from the given premises by resolution.
+fn resolves_to(
+    pool: &mut dyn TermPool,
+    id: &str,
+    clause: &[Rc<Term>],
+    premises: &[&[Rc<Term>]],
+) -> bool {
+    let result = greedy_resolution(clause, premises, pool, false);
+    if let Err(e) = &result {
+        tracing::debug!(step = %id, error = %e, "rebuilt resolution step is invalid");
+    }
+    result.is_ok()
+}
+
+/// Rewrites the premises of a command according to the new indices of the commands in each scope.
+fn remap_premises(command: &ProofCommand, maps: &[Vec<usize>]) -> ProofCommand {
+    let remap = |&(depth, i): &(usize, usize)| (depth, maps[depth][i]);
+    match command {
+        ProofCommand::Step(s) => ProofCommand::Step(ProofStep {
+            premises: s.premises.iter().map(remap).collect(),
+            discharge: s.discharge.iter().map(remap).collect(),
+            ..s.clone()
+        }),
+        _ => command.clone(),
+    }
+}
+
+/// Records in the trace map that each command of a scope was replaced by the command at its new
+/// index in `map`. Commands whose new index is `usize::MAX` were removed. Subproofs are skipped,
+/// since the commands in them are recorded when their own scope is rebuilt.
+fn trace_scope(
+    trace: &mut TraceMap,
+    commands: &[ProofCommand],
+    map: &[usize],
+    new_commands: &[ProofCommand],
+) {
+    for (command, &new_index) in commands.iter().zip(map) {
+        if !command.is_subproof() && new_index != usize::MAX {
+            trace.insert(command.id(), new_commands[new_index].id());
+        }
+    }
+}
+
+/// Calls `f` with the index of every premise of a command (or of the commands inside it, if it is
+/// a subproof) that refers to the scope at the given depth.
+fn collect_premises_at_depth(command: &ProofCommand, depth: usize, f: &mut dyn FnMut(usize)) {
+    match command {
+        ProofCommand::Assume { .. } => (),
+        ProofCommand::Step(s) => {
+            for &(d, i) in s.premises.iter().chain(&s.discharge) {
+                if d == depth {
+                    f(i);
+                }
+            }
+        }
+        ProofCommand::Subproof(s) => {
+            for c in &s.commands {
+                collect_premises_at_depth(c, depth, f);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_invalid_premises() {
+        let mut pool = PrimitivePool::new();
+        let p = pool.bool_true();
+        let step = |id: &str, premises| {
+            ProofCommand::Step(ProofStep {
+                id: id.to_owned(),
+                clause: vec![p.clone()],
+                rule: "resolution".to_owned(),
+                premises,
+                args: Vec::new(),
+                discharge: Vec::new(),
+            })
+        };
+        let assume = ProofCommand::Assume {
+            id: "h1".to_owned(),
+            term: p.clone(),
+        };
+        let proof = |commands| Proof {
+            constant_definitions: Vec::new(),
+            commands,
+        };
+
+        let valid = proof(vec![assume.clone(), step("t2", vec![(0, 0), (0, 0)])]);
+        assert!(compress_proof(&valid, &mut pool).is_ok());
+
+        for premises in [vec![(0, 1)], vec![(0, 5)], vec![(1, 0)]] {
+            let invalid = proof(vec![assume.clone(), step("t2", premises)]);
+            assert!(matches!(
+                compress_proof(&invalid, &mut pool),
+                Err(CompressionError::InvalidPremise(id, _, _)) if id == "t2",
+            ));
+        }
+    }
+
+    #[test]
+    fn test_verify_compression() {
+        let problem = "
+            (declare-const p Bool)
+            (declare-const q Bool)
+            (assert (or p q))
+            (assert (or p (not q)))
+            (assert (not p))
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (or p (not q)))
+            (assume h3 (not p))
+            (step t4 (cl p q) :rule or :premises (h1))
+            (step t5 (cl q) :rule resolution :premises (t4 h3))
+            (step t6 (cl p (not q)) :rule or :premises (h2))
+            (step t7 (cl (not q)) :rule resolution :premises (t6 h3))
+            (step t8 (cl) :rule resolution :premises (t5 t7))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let config = Config::new().verify(true);
+        let checker_config = checker::Config::new();
+        let (compressed, _) =
+            compress(&mut pool, &problem, &proof, &config, &checker_config).unwrap();
+
+        // We break the compressed proof by dropping a premise of the final step
+        let mut broken = compressed.clone();
+        let ProofCommand::Step(last) = broken.commands.last_mut().unwrap() else {
+            panic!()
+        };
+        last.premises.pop();
+        let result = verify::verify(&mut pool, &problem, &compressed, &broken, &checker_config);
+        let Err(CompressionError::VerificationFailed(failures)) = result else {
+            panic!()
+        };
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].id, "t8");
+        assert!(failures[0].original.is_some());
+
+        // If the steps that changed are valid, the whole proof is checked
+        let mut broken = compressed.clone();
+        broken.commands.pop();
+        let result = verify::verify(&mut pool, &problem, &compressed, &broken, &checker_config);
+        assert!(matches!(result, Err(CompressionError::InvalidProof(_))));
+    }
+
+    #[test]
+    fn test_compression_trace() {
+        let problem = "
+            (declare-const p Bool)
+            (declare-const q Bool)
+            (assert (or p q))
+            (assert (or p (not q)))
+            (assert (not p))
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (or p (not q)))
+            (assume h3 (not p))
+            (step t4 (cl p q) :rule or :premises (h1))
+            (step t5 (cl q) :rule resolution :premises (t4 h3))
+            (step t6 (cl p (not q)) :rule or :premises (h2))
+            (step t7 (cl (not q)) :rule resolution :premises (t6 h3))
+            (step t8 (cl p) :rule or :premises (h1))
+            (step t9 (cl) :rule resolution :premises (t5 t7))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let config = Config::new()
+            .algorithms(vec![
+                CompressionAlgorithm::Prune,
+                CompressionAlgorithm::LowerUnits,
+            ])
+            .trace(true);
+        let (compressed, trace) = compress(
+            &mut pool,
+            &problem,
+            &proof,
+            &config,
+            &checker::Config::new(),
+        )
+        .unwrap();
+        let trace = trace.unwrap();
+        let ids: Vec<_> = compressed.commands.iter().map(ProofCommand::id).collect();
+        assert_eq!(ids, ["h1", "h2", "h3", "t4", "t6", "t9.lowered", "t9"]);
+
+        // `t8` is removed by pruning, and the steps that only resolved the lowered unit `h3` are
+        // replaced by their other premise
+        let successors = |id| trace.successors(id).unwrap().iter().collect::<Vec<_>>();
+        assert!(successors("t8").is_empty());
+        assert_eq!(successors("h3"), ["h3"]);
+        assert_eq!(successors("t5"), ["t4"]);
+        assert_eq!(successors("t7"), ["t6"]);
+        assert_eq!(successors("t9"), ["t9", "t9.lowered"]);
+        assert_eq!(trace.predecessors("t4"), ["t4", "t5"]);
+        assert_eq!(trace.predecessors("t9.lowered"), ["t9"]);
+
+        let config = config.trace(false);
+        let (_, trace) = compress(
+            &mut pool,
+            &problem,
+            &proof,
+            &config,
+            &checker::Config::new(),
+        )
+        .unwrap();
+        assert!(trace.is_none());
+    }
+}
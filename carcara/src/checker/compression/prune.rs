@@ -0,0 +1,170 @@
+//! Dead-step elimination.
+//!
+//! Removes every command that is not used, directly or indirectly, to derive the last command of
+//! the proof. Inside a subproof that is kept, the commands that are not used to derive the last step
+//! of the subproof are removed as well. The premises of the remaining commands are renumbered.
+
+use super::{remap_premises, trace_scope};
+use crate::{ast::*, trace_map::TraceMap};
+
+/// Which commands of a scope are used, and the same information for each of its subproofs.
+struct Liveness {
+    is_used: Vec<bool>,
+    subproofs: Vec<Option<Liveness>>,
+}
+
+/// Removes the commands that are not used to derive the last command of each scope. This must be
+/// called with the commands of the whole proof, since a subproof may use commands from the scopes
+/// that contain it.
+pub(super) fn compress_scope(
+    commands: &[ProofCommand],
+    maps: &mut Vec<Vec<usize>>,
+    trace: Option<&mut TraceMap>,
+) -> Vec<ProofCommand> {
+    let liveness = compute_liveness(commands, &mut Vec::new());
+    rebuild_scope(commands, &liveness, maps, trace)
+}
+
+/// Finds the commands of a scope that are used to derive its last command. `enclosing` holds, for
+/// each enclosing scope, which of its commands were found to be used so far.
+///
+/// Premises always refer to commands that precede the step, in the same or in an enclosing scope.
+/// Because of that, visiting the commands in reverse order is enough to find every used command,
+/// including the ones in enclosing scopes that are only used inside a subproof.
+fn compute_liveness(commands: &[ProofCommand], enclosing: &mut Vec<Vec<bool>>) -> Liveness {
+    let depth = enclosing.len();
+    let mut is_used = vec![false; commands.len()];
+    if let Some(last) = is_used.last_mut() {
+        *last = true;
+    }
+    enclosing.push(is_used);
+
+    let mut subproofs: Vec<Option<Liveness>> = (0..commands.len()).map(|_| None).collect();
+    for (i, command) in commands.iter().enumerate().rev() {
+        if !enclosing[depth][i] {
+            continue;
+        }
+        match command {
+            ProofCommand::Assume { .. } => (),
+            ProofCommand::Step(s) => {
+                for &(d, p) in s.premises.iter().chain(&s.discharge) {
+                    enclosing[d][p] = true;
+                }
+            }
+            ProofCommand::Subproof(s) => {
+                subproofs[i] = Some(compute_liveness(&s.commands, enclosing));
+            }
+        }
+    }
+    Liveness {
+        is_used: enclosing.pop().unwrap(),
+        subproofs,
+    }
+}
+
+fn rebuild_scope(
+    commands: &[ProofCommand],
+    liveness: &Liveness,
+    maps: &mut Vec<Vec<usize>>,
+    mut trace: Option<&mut TraceMap>,
+) -> Vec<ProofCommand> {
+    let depth = maps.len();
+    maps.push(Vec::with_capacity(commands.len()));
+    let mut new_commands = Vec::new();
+    for (i, command) in commands.iter().enumerate() {
+        if !liveness.is_used[i] {
+            // Unused commands are never referred to, so their new index is irrelevant
+            maps[depth].push(usize::MAX);
+            continue;
+        }
+        let new_command = match (command, &liveness.subproofs[i]) {
+            (ProofCommand::Subproof(s), Some(inner)) => ProofCommand::Subproof(Subproof {
+                commands: rebuild_scope(&s.commands, inner, maps, trace.as_deref_mut()),
+                args: s.args.clone(),
+                context_id: s.context_id,
+            }),
+            _ => remap_premises(command, maps),
+        };
+        new_commands.push(new_command);
+        maps[depth].push(new_commands.len() - 1);
+    }
+    if let Some(trace) = trace {
+        trace_scope(trace, commands, &maps[depth], &new_commands);
+    }
+    maps.pop();
+    new_commands
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::ProofCommand,
+        checker,
+        checker::compression::{compress_proof_with, CompressionAlgorithm},
+        parser,
+    };
+
+    #[test]
+    fn test_prune() {
+        let problem = "
+            (declare-const p Bool)
+            (declare-const q Bool)
+            (assert p)
+            (assert (not p))
+            (assert q)
+        ";
+        // `t4` is not used at all, and `t5` is only used by `t6.t1`, which is not used to derive
+        // the conclusion of the subproof. The subproof itself is used by `t7`
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (assume h3 q)
+            (step t4 (cl q) :rule reordering :premises (h3))
+            (step t5 (cl p) :rule reordering :premises (h1))
+            (anchor :step t6)
+            (assume t6.a0 q)
+            (step t6.t1 (cl p) :rule reordering :premises (t5))
+            (step t6.t2 (cl q) :rule reordering :premises (t6.a0))
+            (step t6 (cl (not q) q) :rule subproof :discharge (t6.a0))
+            (step t7 (cl (not q) q) :rule reordering :premises (t6))
+            (step t8 (cl) :rule resolution :premises (h1 h2))
+            (step t9 (cl) :rule reordering :premises (t8))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let pruned =
+            compress_proof_with(&proof, &mut pool, &[CompressionAlgorithm::Prune]).unwrap();
+        let ids: Vec<_> = pruned.commands.iter().map(ProofCommand::id).collect();
+        assert_eq!(ids, ["h1", "h2", "t8", "t9"]);
+        assert!(
+            checker::ProofChecker::new(&mut pool, checker::Config::new())
+                .check(&problem, &pruned)
+                .is_ok()
+        );
+
+        // If the subproof is used, its unused steps are removed, and its premises are renumbered
+        let proof = proof.commands[..proof.commands.len() - 2].to_vec();
+        let proof = crate::ast::Proof {
+            constant_definitions: Vec::new(),
+            commands: proof,
+        };
+        let pruned =
+            compress_proof_with(&proof, &mut pool, &[CompressionAlgorithm::Prune]).unwrap();
+        let ids: Vec<_> = pruned.commands.iter().map(ProofCommand::id).collect();
+        assert_eq!(ids, ["t6", "t7"]);
+        let crate::ast::ProofCommand::Subproof(s) = &pruned.commands[0] else {
+            panic!()
+        };
+        let inner: Vec<_> = s.commands.iter().map(ProofCommand::id).collect();
+        assert_eq!(inner, ["t6.a0", "t6"]);
+        let crate::ast::ProofCommand::Step(t6) = &s.commands[1] else {
+            panic!()
+        };
+        assert_eq!(t6.discharge, [(1, 0)]);
+        let crate::ast::ProofCommand::Step(t7) = &pruned.commands[1] else {
+            panic!()
+        };
+        assert_eq!(t7.premises, [(0, 0)]);
+    }
+}
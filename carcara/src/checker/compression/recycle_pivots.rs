@@ -0,0 +1,371 @@
+//! The Recycle Pivots algorithm, and its Recycle Pivots with Intersection variant.
+//!
+//! For each binary resolution step, we find the set of "safe" literals, which are resolved again
+//! in every path from the step to the end of the scope. If one of the pivot literals of the step is
+//! safe, the step is redundant: it can be replaced by the premise that contains that literal, since
+//! the literal will be eliminated later anyway.
+
+use super::{
+    collect_premises_at_depth, is_resolution, is_valid_resolution, remap_premises, trace_scope,
+};
+use crate::{ast::*, trace_map::TraceMap};
+use indexmap::IndexSet;
+
+/// A binary resolution step whose pivot could be determined. `left_literal` is the pivot literal
+/// that appears in the left premise, and `right_literal` is its negation, that appears in the
+/// right premise.
+#[derive(Debug)]
+struct BinaryStep<'a> {
+    left: usize,
+    right: usize,
+    left_literal: &'a Rc<Term>,
+    right_literal: &'a Rc<Term>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    /// The step is kept, but may have to be rebuilt if its premises change.
+    Keep,
+
+    /// The step is replaced by one of its premises.
+    Replace(usize),
+
+    /// The step is no longer used by any other step.
+    Dead,
+}
+
+/// Finds the pivot of a step, if it is a binary resolution step whose premises are in the scope at
+/// the given depth, and there is exactly one pair of complementary literals between them.
+fn binary_step<'a>(
+    step: &ProofStep,
+    commands: &'a [ProofCommand],
+    depth: usize,
+) -> Option<BinaryStep<'a>> {
+    if !is_resolution(step) {
+        return None;
+    }
+    let [(d1, left), (d2, right)] = step.premises[..] else {
+        return None;
+    };
+    if d1 != depth || d2 != depth || left == right {
+        return None;
+    }
+    let mut pairs = commands[left].clause().iter().flat_map(|l| {
+        commands[right].clause().iter().filter_map(move |r| {
+            let complementary = l.remove_negation() == Some(r) || r.remove_negation() == Some(l);
+            complementary.then_some((l, r))
+        })
+    });
+    let (left_literal, right_literal) = pairs.next()?;
+    if pairs.next().is_some() {
+        return None;
+    }
+    Some(BinaryStep {
+        left,
+        right,
+        left_literal,
+        right_literal,
+    })
+}
+
+/// Compresses the commands of a scope using Recycle Pivots. If `intersection` is `true`, steps that
+/// are used more than once are also considered, which corresponds to the Recycle Pivots with
+/// Intersection variant. `maps` holds, for each enclosing scope, the new index of each of its
+/// commands.
+pub(super) fn compress_scope(
+    pool: &mut dyn TermPool,
+    commands: &[ProofCommand],
+    maps: &mut Vec<Vec<usize>>,
+    intersection: bool,
+    mut trace: Option<&mut TraceMap>,
+) -> Vec<ProofCommand> {
+    let depth = maps.len();
+    let decisions = decide(commands, depth, intersection);
+    maps.push(Vec::with_capacity(commands.len()));
+    let result = decisions
+        .as_ref()
+        .and_then(|d| rebuild_scope(pool, commands, maps, d, intersection, trace.as_deref_mut()));
+    let result = result.unwrap_or_else(|| {
+        maps[depth].clear();
+        rebuild_scope(
+            pool,
+            commands,
+            maps,
+            &[],
+            intersection,
+            trace.as_deref_mut(),
+        )
+        .unwrap()
+    });
+    if let Some(trace) = trace {
+        trace_scope(trace, commands, &maps[depth], &result);
+    }
+    maps.pop();
+    result
+}
+
+/// Decides which steps in a scope should be kept or replaced. Returns `None` if the last command of
+/// the scope is not a binary resolution step.
+///
+/// Only the region of binary resolution steps whose conclusions are used solely by other steps in
+/// the region is considered, so the conclusions of the commands outside it never change.
+fn decide(commands: &[ProofCommand], depth: usize, intersection: bool) -> Option<Vec<Decision>> {
+    let steps: Vec<_> = commands
+        .iter()
+        .map(|c| match c {
+            ProofCommand::Step(s) => binary_step(s, commands, depth),
+            _ => None,
+        })
+        .collect();
+    let root = commands.len().checked_sub(1)?;
+    steps[root].as_ref()?;
+
+    let mut consumers: Vec<IndexSet<usize>> = vec![IndexSet::new(); commands.len()];
+    for (i, command) in commands.iter().enumerate() {
+        collect_premises_at_depth(command, depth, &mut |p| {
+            consumers[p].insert(i);
+        });
+    }
+    let mut in_region = vec![false; commands.len()];
+    in_region[root] = true;
+    for i in (0..root).rev() {
+        in_region[i] = steps[i].is_some()
+            && !consumers[i].is_empty()
+            && consumers[i].iter().all(|&c| in_region[c]);
+    }
+
+    // For each step in the region, the sets of safe literals given by each consumer that still
+    // uses it
+    let mut incoming: Vec<Vec<IndexSet<Rc<Term>>>> = vec![Vec::new(); commands.len()];
+    let mut decisions = vec![Decision::Keep; commands.len()];
+    for i in (0..=root).rev() {
+        if !in_region[i] {
+            continue;
+        }
+        let step = steps[i].as_ref().unwrap();
+        let safe = if i == root {
+            IndexSet::new()
+        } else if incoming[i].is_empty() {
+            decisions[i] = Decision::Dead;
+            continue;
+        } else if intersection {
+            let mut sets = std::mem::take(&mut incoming[i]).into_iter();
+            let first = sets.next().unwrap();
+            sets.fold(first, |acc, s| acc.intersection(&s).cloned().collect())
+        } else if incoming[i].len() == 1 {
+            incoming[i].pop().unwrap()
+        } else {
+            IndexSet::new()
+        };
+
+        if safe.contains(step.left_literal) {
+            decisions[i] = Decision::Replace(step.left);
+            incoming[step.left].push(safe);
+        } else if safe.contains(step.right_literal) {
+            decisions[i] = Decision::Replace(step.right);
+            incoming[step.right].push(safe);
+        } else {
+            let mut left_safe = safe.clone();
+            left_safe.insert(step.left_literal.clone());
+            let mut right_safe = safe;
+            right_safe.insert(step.right_literal.clone());
+            incoming[step.left].push(left_safe);
+            incoming[step.right].push(right_safe);
+        }
+    }
+    Some(decisions)
+}
+
+/// Rebuilds the commands of a scope according to the decisions made for each step. Returns `None`
+/// if any rebuilt step is not valid, or if the conclusion of the scope would change. If
+/// `decisions` is empty, the scope is only rebuilt to compress its subproofs.
+fn rebuild_scope(
+    pool: &mut dyn TermPool,
+    commands: &[ProofCommand],
+    maps: &mut Vec<Vec<usize>>,
+    decisions: &[Decision],
+    intersection: bool,
+    mut trace: Option<&mut TraceMap>,
+) -> Option<Vec<ProofCommand>> {
+    let depth = maps.len() - 1;
+    let mut new_commands: Vec<ProofCommand> = Vec::with_capacity(commands.len());
+    for (i, command) in commands.iter().enumerate() {
+        let decision = decisions.get(i).copied().unwrap_or(Decision::Keep);
+        let step = match (command, decision) {
+            (_, Decision::Dead) => {
+                // Dead steps are never referenced, so their new index doesn't matter
+                maps[depth].push(usize::MAX);
+                continue;
+            }
+            (_, Decision::Replace(premise)) => {
+                let new_index = maps[depth][premise];
+                maps[depth].push(new_index);
+                continue;
+            }
+            (ProofCommand::Subproof(s), _) => {
+                let inner =
+                    compress_scope(pool, &s.commands, maps, intersection, trace.as_deref_mut());
+                new_commands.push(ProofCommand::Subproof(Subproof {
+                    commands: inner,
+                    args: s.args.clone(),
+                    context_id: s.context_id,
+                }));
+                maps[depth].push(new_commands.len() - 1);
+                continue;
+            }
+            (ProofCommand::Step(s), _) if !decisions.is_empty() => {
+                binary_step(s, commands, depth).map(|b| (s, b))
+            }
+            _ => None,
+        };
+        let Some((step, binary)) = step else {
+            new_commands.push(remap_premises(command, maps));
+            maps[depth].push(new_commands.len() - 1);
+            continue;
+        };
+
+        let (new_left, new_right) = (maps[depth][binary.left], maps[depth][binary.right]);
+        let left_clause = new_commands[new_left].clause();
+        let right_clause = new_commands[new_right].clause();
+        if left_clause == commands[binary.left].clause()
+            && right_clause == commands[binary.right].clause()
+        {
+            new_commands.push(remap_premises(command, maps));
+            maps[depth].push(new_commands.len() - 1);
+            continue;
+        }
+
+        // If one of the premises no longer contains its pivot literal, the step is not needed
+        if !left_clause.contains(binary.left_literal) {
+            maps[depth].push(new_left);
+            continue;
+        }
+        if !right_clause.contains(binary.right_literal) {
+            maps[depth].push(new_right);
+            continue;
+        }
+
+        let mut clause: Vec<Rc<Term>> = Vec::new();
+        let left_rest = left_clause.iter().filter(|&t| t != binary.left_literal);
+        let right_rest = right_clause.iter().filter(|&t| t != binary.right_literal);
+        for t in left_rest.chain(right_rest) {
+            if !clause.contains(t) {
+                clause.push(t.clone());
+            }
+        }
+        let new_step = ProofStep {
+            id: step.id.clone(),
+            clause,
+            rule: step.rule.clone(),
+            premises: vec![(depth, new_left), (depth, new_right)],
+            args: Vec::new(),
+            discharge: Vec::new(),
+        };
+        if !is_valid_resolution(pool, &new_step, &new_commands) {
+            return None;
+        }
+        new_commands.push(ProofCommand::Step(new_step));
+        maps[depth].push(new_commands.len() - 1);
+    }
+
+    // The conclusion of the scope must not change. In a subproof, the last command must also still
+    // be the step that concludes it
+    if let Some(root) = commands.last() {
+        let new_root = &new_commands[*maps[depth].last().unwrap()];
+        let original: IndexSet<_> = root.clause().iter().collect();
+        let new: IndexSet<_> = new_root.clause().iter().collect();
+        if original != new || (depth > 0 && new_root.id() != root.id()) {
+            return None;
+        }
+    }
+    Some(new_commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        checker,
+        checker::compression::{compress_proof_with, CompressionAlgorithm},
+        parser,
+    };
+
+    /// Compresses the given proof, and returns the ids of the commands in the compressed proof,
+    /// and whether it is valid.
+    fn run(proof: &str, algorithm: CompressionAlgorithm) -> (Vec<String>, bool) {
+        let problem = "
+            (declare-const p Bool)
+            (declare-const q Bool)
+            (declare-const r Bool)
+            (assert (or p q))
+            (assert (or p (not q)))
+            (assert (not p))
+            (assert (or p (not q) r))
+            (assert (or p (not q) (not r)))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let compressed = compress_proof_with(&proof, &mut pool, &[algorithm]).unwrap();
+        let valid = checker::ProofChecker::new(&mut pool, checker::Config::new())
+            .check(&problem, &compressed)
+            .is_ok();
+        let ids = compressed
+            .commands
+            .iter()
+            .map(|c| c.id().to_owned())
+            .collect();
+        (ids, valid)
+    }
+
+    #[test]
+    fn test_recycle_pivots() {
+        // In `t6`, `p` is resolved with `h3`, but it is resolved again in `t8`, so `t6` can be
+        // replaced by `t4`
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (or p (not q)))
+            (assume h3 (not p))
+            (step t4 (cl p q) :rule or :premises (h1))
+            (step t5 (cl p (not q)) :rule or :premises (h2))
+            (step t6 (cl q) :rule resolution :premises (t4 h3))
+            (step t7 (cl p) :rule resolution :premises (t6 t5))
+            (step t8 (cl) :rule resolution :premises (t7 h3))
+        ";
+        for algorithm in [
+            CompressionAlgorithm::RecyclePivots,
+            CompressionAlgorithm::RecyclePivotsWithIntersection,
+        ] {
+            let (ids, valid) = run(proof, algorithm);
+            assert!(valid);
+            assert_eq!(ids, ["h1", "h2", "h3", "t4", "t5", "t7", "t8"]);
+        }
+    }
+
+    #[test]
+    fn test_recycle_pivots_with_intersection() {
+        // `t6` is used twice, and `p` is resolved again in both paths from it to the end of the
+        // proof. Only the variant with intersection can remove it
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (or p (not q) r))
+            (assume h3 (or p (not q) (not r)))
+            (assume h4 (not p))
+            (step t5 (cl p q) :rule or :premises (h1))
+            (step t6 (cl q) :rule resolution :premises (t5 h4))
+            (step t7 (cl p (not q) r) :rule or :premises (h2))
+            (step t8 (cl p (not q) (not r)) :rule or :premises (h3))
+            (step t9 (cl p r) :rule resolution :premises (t6 t7))
+            (step t10 (cl p (not r)) :rule resolution :premises (t6 t8))
+            (step t11 (cl p) :rule resolution :premises (t9 t10))
+            (step t12 (cl) :rule resolution :premises (t11 h4))
+        ";
+        let (ids, valid) = run(proof, CompressionAlgorithm::RecyclePivots);
+        assert!(valid);
+        assert!(ids.iter().any(|id| id == "t6"));
+
+        let (ids, valid) = run(proof, CompressionAlgorithm::RecyclePivotsWithIntersection);
+        assert!(valid);
+        assert!(!ids.iter().any(|id| id == "t6"));
+        assert_eq!(ids.len(), 11);
+    }
+}
@@ -0,0 +1,117 @@
+//! Verification of compressed proofs, used to catch compression bugs before the compressed proof is
+//! used.
+
+use super::{is_resolution, CompressionError, CompressionResult};
+use crate::{
+    ast::*,
+    checker::{self, ProofChecker},
+};
+use std::{collections::HashMap, fmt};
+
+/// A step of the compressed proof that failed to check, together with the step with the same id in
+/// the original proof, if there is one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedStep {
+    pub id: String,
+    pub message: String,
+    pub original: Option<String>,
+    pub compressed: String,
+}
+
+impl fmt::Display for FailedStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "step '{}': {}", self.id, self.message)?;
+        if let Some(original) = &self.original {
+            writeln!(f, "  - {}", original)?;
+        }
+        write!(f, "  + {}", self.compressed)
+    }
+}
+
+/// Checks a compressed proof. Every resolution step that was changed by compression is first
+/// checked in isolation, so all of the invalid ones can be reported at once. If they are all valid,
+/// the whole proof is checked.
+pub(super) fn verify(
+    pool: &mut PrimitivePool,
+    problem: &Problem,
+    original: &Proof,
+    compressed: &Proof,
+    checker_config: &checker::Config,
+) -> CompressionResult<()> {
+    let original_steps = render_steps(original);
+    let compressed_steps = render_steps(compressed);
+    let failed_step = |id: &str, message: String| FailedStep {
+        id: id.to_owned(),
+        message,
+        original: original_steps.get(id).cloned(),
+        compressed: compressed_steps[id].clone(),
+    };
+
+    let mut failures = Vec::new();
+    let mut iter = compressed.iter();
+    while let Some(command) = iter.next() {
+        let ProofCommand::Step(step) = command else {
+            continue;
+        };
+        if !is_resolution(step) || original_steps.get(&step.id) == compressed_steps.get(&step.id) {
+            continue;
+        }
+        let premises: Vec<_> = step
+            .premises
+            .iter()
+            .map(|&p| iter.get_premise(p).clause())
+            .collect();
+        let result = checker::check_step(
+            pool,
+            checker_config,
+            &premises,
+            &step.rule,
+            &step.args,
+            &step.clause,
+        );
+        if let Err(e) = result {
+            failures.push(failed_step(&step.id, e.to_string()));
+        }
+    }
+    if !failures.is_empty() {
+        return Err(CompressionError::VerificationFailed(failures));
+    }
+
+    let mut checker = ProofChecker::new(pool, checker_config.clone());
+    match checker.check(problem, compressed) {
+        Ok(_) => Ok(()),
+        Err(crate::Error::Checker { inner, step, .. }) => {
+            let failure = failed_step(&step, inner.to_string());
+            Err(CompressionError::VerificationFailed(vec![failure]))
+        }
+        Err(e) => Err(CompressionError::InvalidProof(e)),
+    }
+}
+
+/// Renders every step in a proof in a single line, indexed by its id.
+fn render_steps(proof: &Proof) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    let mut iter = proof.iter();
+    while let Some(command) = iter.next() {
+        let ProofCommand::Step(step) = command else {
+            continue;
+        };
+        let clause: String = step.clause.iter().map(|t| format!(" {}", t)).collect();
+        let mut rendered = format!("(step {} (cl{}) :rule {}", step.id, clause, step.rule);
+        if !step.premises.is_empty() {
+            let premises: Vec<_> = step
+                .premises
+                .iter()
+                .map(|&p| iter.get_premise(p).id())
+                .collect();
+            rendered += &format!(" :premises ({})", premises.join(" "));
+        }
+        if !step.args.is_empty() {
+            let args: Vec<_> = step.args.iter().map(ToString::to_string).collect();
+            rendered += &format!(" :args ({})", args.join(" "));
+        }
+        rendered.push(')');
+        result.insert(step.id.clone(), rendered);
+    }
+    result
+}
@@ -0,0 +1,374 @@
+//! Differential testing of the rule implementations against examples from the Alethe
+//! specification.
+//!
+//! The examples are read from a plain text file, in which each example starts with a line of the
+//! form `# <name>`, followed by the fields `rule:`, `expect:` (either `valid` or `invalid`),
+//! `definitions:` and `proof:`. The definitions and the proof span all lines until the next field
+//! or example. For example:
+//!
+//! ```text
+//! # and
+//! rule: and
+//! expect: valid
+//! definitions:
+//! (declare-const p Bool)
+//! (declare-const q Bool)
+//! proof:
+//! (assume h1 (and p q))
+//! (step t2 (cl q) :rule and :premises (h1) :args (1))
+//! ```
+//!
+//! Like in the rule tests, every `assume`d term is added as a problem premise, and the proof does
+//! not need to reach the empty clause.
+
+use super::{error::CheckerError, Config, ProofChecker};
+use crate::{
+    ast::{ProofCommand, ProofStep},
+    parser, CarcaraResult,
+};
+use indexmap::IndexMap;
+use std::{fmt, io::Cursor};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConformanceError {
+    #[error("line {0}: expected example header of the form '# <name>'")]
+    ExpectedHeader(usize),
+
+    #[error("line {0}: unknown field '{1}'")]
+    UnknownField(usize, String),
+
+    #[error("line {0}: invalid expected result '{1}', must be 'valid' or 'invalid'")]
+    InvalidExpectation(usize, String),
+
+    #[error("example '{0}' is missing the '{1}' field")]
+    MissingField(String, &'static str),
+}
+
+/// An example step from the Alethe specification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecExample {
+    pub name: String,
+    pub rule: String,
+    pub expected: bool,
+    pub definitions: String,
+    pub proof: String,
+}
+
+/// The outcome of checking a single example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExampleOutcome {
+    /// The checker agrees with the specification.
+    Passed,
+
+    /// The checker rejected a step that the specification considers valid. Holds the checker
+    /// error.
+    Rejected(String),
+
+    /// The checker accepted a step that the specification considers invalid.
+    Accepted,
+
+    /// The checker does not implement the example's rule.
+    UnknownRule,
+
+    /// The example could not be parsed.
+    ParserError(String),
+}
+
+impl ExampleOutcome {
+    pub fn is_passed(&self) -> bool {
+        matches!(self, ExampleOutcome::Passed)
+    }
+}
+
+/// The results of checking a series of examples.
+#[derive(Debug, Default)]
+pub struct ConformanceReport {
+    pub results: Vec<(SpecExample, ExampleOutcome)>,
+}
+
+impl ConformanceReport {
+    /// Returns `true` if the checker agrees with the specification on every example.
+    pub fn is_conformant(&self) -> bool {
+        self.results.iter().all(|(_, outcome)| outcome.is_passed())
+    }
+
+    /// Returns the examples in which the checker disagrees with the specification.
+    pub fn gaps(&self) -> impl Iterator<Item = &(SpecExample, ExampleOutcome)> {
+        self.results.iter().filter(|(_, o)| !o.is_passed())
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut by_rule: IndexMap<&str, (usize, usize)> = IndexMap::new();
+        for (example, outcome) in &self.results {
+            let (passed, total) = by_rule.entry(example.rule.as_str()).or_default();
+            *total += 1;
+            if outcome.is_passed() {
+                *passed += 1;
+            }
+        }
+        by_rule.sort_keys();
+
+        let passed = self.results.iter().filter(|(_, o)| o.is_passed()).count();
+        writeln!(
+            f,
+            "{}/{} examples passed, covering {} rules",
+            passed,
+            self.results.len(),
+            by_rule.len()
+        )?;
+        for (rule, (passed, total)) in &by_rule {
+            let status = if passed == total { "ok" } else { "GAP" };
+            writeln!(f, "  {:<24} {:>3}/{:<3} {}", rule, passed, total, status)?;
+        }
+
+        let mut gaps = self.gaps().peekable();
+        if gaps.peek().is_some() {
+            writeln!(f, "conformance gaps:")?;
+        }
+        for (example, outcome) in gaps {
+            let reason = match outcome {
+                ExampleOutcome::Passed => unreachable!(),
+                ExampleOutcome::Rejected(e) => format!("valid step was rejected: {}", e),
+                ExampleOutcome::Accepted => "invalid step was accepted".to_owned(),
+                ExampleOutcome::UnknownRule => "rule is not implemented".to_owned(),
+                ExampleOutcome::ParserError(e) => format!("example could not be parsed: {}", e),
+            };
+            writeln!(f, "  {} ({}): {}", example.name, example.rule, reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a file of specification examples.
+pub fn parse_examples(input: &str) -> Result<Vec<SpecExample>, ConformanceError> {
+    #[derive(Clone, Copy)]
+    enum Section {
+        None,
+        Definitions,
+        Proof,
+    }
+
+    struct Partial {
+        name: String,
+        rule: Option<String>,
+        expected: Option<bool>,
+        definitions: String,
+        proof: String,
+    }
+
+    fn finish(partial: Partial) -> Result<SpecExample, ConformanceError> {
+        let missing = |field| ConformanceError::MissingField(partial.name.clone(), field);
+        Ok(SpecExample {
+            rule: partial.rule.clone().ok_or_else(|| missing("rule"))?,
+            expected: partial.expected.ok_or_else(|| missing("expect"))?,
+            name: partial.name,
+            definitions: partial.definitions,
+            proof: partial.proof,
+        })
+    }
+
+    let mut result = Vec::new();
+    let mut current: Option<Partial> = None;
+    let mut section = Section::None;
+    for (i, line) in input.lines().enumerate() {
+        let line_number = i + 1;
+        if let Some(name) = line.strip_prefix("# ") {
+            if let Some(partial) = current.take() {
+                result.push(finish(partial)?);
+            }
+            current = Some(Partial {
+                name: name.trim().to_owned(),
+                rule: None,
+                expected: None,
+                definitions: String::new(),
+                proof: String::new(),
+            });
+            section = Section::None;
+            continue;
+        }
+
+        let Some(partial) = current.as_mut() else {
+            // Anything before the first example must be blank or a comment
+            if line.trim().is_empty() || line.starts_with(';') {
+                continue;
+            }
+            return Err(ConformanceError::ExpectedHeader(line_number));
+        };
+
+        if let Some((field, value)) = line.split_once(':') {
+            let value = value.trim();
+            match field {
+                "rule" => {
+                    partial.rule = Some(value.to_owned());
+                    section = Section::None;
+                    continue;
+                }
+                "expect" => {
+                    partial.expected = Some(match value {
+                        "valid" => true,
+                        "invalid" => false,
+                        other => {
+                            return Err(ConformanceError::InvalidExpectation(
+                                line_number,
+                                other.to_owned(),
+                            ))
+                        }
+                    });
+                    section = Section::None;
+                    continue;
+                }
+                "definitions" => {
+                    section = Section::Definitions;
+                    continue;
+                }
+                "proof" => {
+                    section = Section::Proof;
+                    continue;
+                }
+                _ => (),
+            }
+        }
+
+        let target = match section {
+            Section::Definitions => &mut partial.definitions,
+            Section::Proof => &mut partial.proof,
+            Section::None if line.trim().is_empty() => continue,
+            Section::None => {
+                let field = line.split(':').next().unwrap_or_default().to_owned();
+                return Err(ConformanceError::UnknownField(line_number, field));
+            }
+        };
+        target.push_str(line);
+        target.push('\n');
+    }
+    if let Some(partial) = current {
+        result.push(finish(partial)?);
+    }
+    Ok(result)
+}
+
+/// Checks a proof fragment that exercises a single rule. Every `assume`d term is considered a
+/// premise of the problem, and the proof is not required to reach the empty clause.
+pub fn check_rule_example(definitions: &str, proof: &str, config: Config) -> CarcaraResult<()> {
+    let (mut problem, mut proof, mut pool) = parser::parse_instance(
+        Cursor::new(definitions),
+        Cursor::new(proof),
+        parser::Config::new(),
+    )?;
+
+    problem.premises = proof
+        .commands
+        .iter()
+        .filter_map(|c| match c {
+            ProofCommand::Assume { term, .. } => Some(term.clone()),
+            _ => None,
+        })
+        .collect();
+
+    // All proofs must eventually reach the empty clause, so we add a dummy step that concludes it
+    proof.commands.push(ProofCommand::Step(ProofStep {
+        id: "end".into(),
+        clause: Vec::new(),
+        rule: "hole".into(),
+        premises: Vec::new(),
+        args: Vec::new(),
+        discharge: Vec::new(),
+    }));
+
+    ProofChecker::new(&mut pool, config).check(&problem, &proof)?;
+    Ok(())
+}
+
+/// Checks a single example against the rule implementations.
+pub fn run_example(example: &SpecExample) -> ExampleOutcome {
+    match check_rule_example(&example.definitions, &example.proof, Config::new()) {
+        Ok(()) if example.expected => ExampleOutcome::Passed,
+        Ok(()) => ExampleOutcome::Accepted,
+        Err(e @ (crate::Error::Parser(..) | crate::Error::Io(_))) => {
+            ExampleOutcome::ParserError(e.to_string())
+        }
+        Err(crate::Error::Checker {
+            inner: CheckerError::UnknownRule, ..
+        }) => ExampleOutcome::UnknownRule,
+        Err(_) if !example.expected => ExampleOutcome::Passed,
+        Err(e) => ExampleOutcome::Rejected(e.to_string()),
+    }
+}
+
+/// Checks every example, and returns a report of the results.
+pub fn run_examples(examples: Vec<SpecExample>) -> ConformanceReport {
+    let results = examples
+        .into_iter()
+        .map(|example| {
+            let outcome = run_example(&example);
+            (example, outcome)
+        })
+        .collect();
+    ConformanceReport { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_examples() {
+        let input = "
+; A comment
+# and
+rule: and
+expect: valid
+definitions:
+(declare-const p Bool)
+(declare-const q Bool)
+proof:
+(assume h1 (and p q))
+(step t2 (cl q) :rule and :premises (h1) :args (1))
+
+# and-wrong-index
+rule: and
+expect: invalid
+definitions:
+(declare-const p Bool)
+(declare-const q Bool)
+proof:
+(assume h1 (and p q))
+(step t2 (cl q) :rule and :premises (h1) :args (0))
+
+# unknown
+rule: not_a_rule
+expect: valid
+definitions:
+proof:
+(step t1 (cl true) :rule not_a_rule)
+";
+        let examples = parse_examples(input).unwrap();
+        assert_eq!(3, examples.len());
+        assert_eq!("and", examples[0].rule);
+        assert!(!examples[1].expected);
+
+        let report = run_examples(examples);
+        let outcomes: Vec<_> = report.results.iter().map(|(_, o)| o.clone()).collect();
+        assert_eq!(
+            vec![
+                ExampleOutcome::Passed,
+                ExampleOutcome::Passed,
+                ExampleOutcome::UnknownRule
+            ],
+            outcomes
+        );
+        assert!(!report.is_conformant());
+
+        for invalid in [
+            "rule: and",
+            "# a\nrule: and\nexpect: maybe",
+            "# a\nrule: and",
+            "# a\nrule: and\nexpect: valid\nfoo: bar",
+        ] {
+            assert!(parse_examples(invalid).is_err());
+        }
+    }
+}
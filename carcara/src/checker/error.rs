@@ -1,6 +1,6 @@
 use crate::{
     ast::*,
-    checker::rules::linear_arithmetic::LinearComb,
+    checker::{metadata::Theory, rules::linear_arithmetic::LinearComb},
     utils::{Range, TypeName},
 };
 use rug::{Integer, Rational};
@@ -8,15 +8,26 @@ use std::fmt;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", content = "data")
+)]
 pub enum CheckerError {
     #[error("unspecified error")]
     Unspecified,
 
+    #[error("this rule uses {theory}, which is not part of the logic '{logic}'")]
+    RuleOutsideLogic { theory: Theory, logic: String },
+
     #[error(transparent)]
     Substitution(#[from] SubstitutionError),
 
-    #[error("could not match term to any of the original problem premises: {0}")]
-    Assume(Rc<Term>),
+    #[error(
+        "could not match term to any of the original problem premises: {0}{}",
+        DisplayClosestPremises(.1)
+    )]
+    Assume(Rc<Term>, Vec<PremiseMismatch>),
 
     // Rule specific errors
     #[error(transparent)]
@@ -71,6 +82,18 @@ pub enum CheckerError {
     #[error("cannot evaluate the fixed length of the term '{0}'")]
     LengthCannotBeEvaluated(Rc<Term>),
 
+    #[error("term '{0}' is not of a datatype sort")]
+    NotOfDatatypeSort(Rc<Term>),
+
+    #[error("'{0}' is not a datatype constructor")]
+    NotADatatypeConstructor(Rc<Term>),
+
+    #[error("'{0}' is not a datatype selector")]
+    NotADatatypeSelector(Rc<Term>),
+
+    #[error("expected testers for distinct constructors, got '{0}' twice")]
+    ExpectedDistinctConstructors(Rc<Term>),
+
     #[error("No {0}-th child in term {1}")]
     NoIthChildInTerm(usize, Rc<Term>),
 
@@ -96,6 +119,9 @@ pub enum CheckerError {
     #[error("term '{1}' is of the wrong form, expected '{0}'")]
     TermOfWrongForm(&'static str, Rc<Term>),
 
+    #[error("term '{0}' is not of a bit-vector sort")]
+    NotOfBitVecSort(Rc<Term>),
+
     #[error("expected term '{0}' to be boolean constant '{1}'")]
     ExpectedBoolConstant(bool, Rc<Term>),
 
@@ -109,10 +135,24 @@ pub enum CheckerError {
     ExpectedDifferentConstantPrefixes(Rc<Term>, Rc<Term>),
 
     #[error("expected term '{1}' to be numerical constant {:?}", .0.to_f64())]
-    ExpectedNumber(Rational, Rc<Term>),
+    ExpectedNumber(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::serialize::with_display")
+        )]
+        Rational,
+        Rc<Term>,
+    ),
 
     #[error("expected term '{1}' to be integer constant {:?}", .0.to_i32())]
-    ExpectedInteger(Integer, Rc<Term>),
+    ExpectedInteger(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::serialize::with_display")
+        )]
+        Integer,
+        Rc<Term>,
+    ),
 
     #[error("expected term '{0}' to be a numerical constant")]
     ExpectedAnyNumber(Rc<Term>),
@@ -150,6 +190,40 @@ pub enum CheckerError {
     #[error("division or modulo by zero")]
     DivOrModByZero,
 
+    // Errors for the `evaluate` rule
+    #[error("expected application of an interpreted function, got '{0}'")]
+    ExpectedInterpretedApplication(Rc<Term>),
+
+    #[error("function '{0}' has no registered interpretation")]
+    NoInterpretation(String),
+
+    #[error("term '{0}' is not a value")]
+    IsNotValue(Rc<Term>),
+
+    #[error("interpretation of '{0}' failed to evaluate '{1}'")]
+    EvaluationFailed(String, Rc<Term>),
+
+    // Errors for the `rare_rewrite` rule
+    #[error("expected name of RARE rule as first argument, got '{0}'")]
+    ExpectedRareRuleName(Rc<Term>),
+
+    #[error("RARE rule '{0}' is not in the rule database")]
+    UnknownRareRule(String),
+
+    #[error("expected 'rare-list' term as value of list parameter, got '{0}'")]
+    ExpectedRareList(Rc<Term>),
+
+    #[error(
+        "list parameter '{0}' is not an argument of an operation, so it must have one element"
+    )]
+    RareListOutsideOperation(String),
+
+    #[error("operator '{0}' has no neutral element, so it can't be applied to an empty list")]
+    RareEmptyList(Operator),
+
+    #[error("condition '{0}' of RARE rule is not given as a premise")]
+    RareConditionNotMet(Rc<Term>),
+
     // Equality errors
     #[error(transparent)]
     TermEquality(#[from] EqualityError<Rc<Term>>),
@@ -162,10 +236,122 @@ pub enum CheckerError {
 
     #[error("unknown rule")]
     UnknownRule,
+
+    /// An error, together with an explanation of why the rule could not be applied. Rules attach
+    /// explanations to their errors using [`Explain::explain`].
+    #[error("{0}")]
+    Explained(Box<CheckerError>, Explanation),
+}
+
+impl CheckerError {
+    /// Returns the underlying error, without any explanation attached to it.
+    pub fn cause(&self) -> &CheckerError {
+        match self {
+            CheckerError::Explained(inner, _) => inner,
+            other => other,
+        }
+    }
+
+    /// Returns the explanation attached to this error, if the rule provided one.
+    pub fn explanation(&self) -> Option<&Explanation> {
+        match self {
+            CheckerError::Explained(_, explanation) => Some(explanation),
+            _ => None,
+        }
+    }
+}
+
+/// A structured explanation of why a rule application failed. This complements the error returned
+/// by the rule with the reasons that led to it, and with the sets of terms that were considered,
+/// like the candidate pivots in a resolution step.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Explanation {
+    /// The reasons for the failure, from the most general to the most specific.
+    pub reasons: Vec<String>,
+
+    /// Named sets of terms that are relevant to the failure.
+    pub terms: Vec<ExplanationTerms>,
+}
+
+/// A named set of terms in an [`Explanation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ExplanationTerms {
+    pub label: String,
+    pub terms: Vec<Rc<Term>>,
+}
+
+impl Explanation {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reasons: vec![reason.into()],
+            terms: Vec::new(),
+        }
+    }
+
+    /// Adds a named set of terms to the explanation.
+    pub fn with_terms<'a>(
+        mut self,
+        label: impl Into<String>,
+        terms: impl IntoIterator<Item = &'a Rc<Term>>,
+    ) -> Self {
+        self.terms.push(ExplanationTerms {
+            label: label.into(),
+            terms: terms.into_iter().cloned().collect(),
+        });
+        self
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for reason in &self.reasons {
+            writeln!(f, "{}", reason)?;
+        }
+        for ExplanationTerms { label, terms } in &self.terms {
+            write!(f, "{}:", label)?;
+            if terms.is_empty() {
+                write!(f, " (none)")?;
+            }
+            for t in terms {
+                write!(f, "\n  {}", t)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Allows rules to attach an [`Explanation`] to the error in a `Result`. The explanation is only
+/// built if the result is an error.
+pub(crate) trait Explain<T> {
+    fn explain(self, f: impl FnOnce() -> Explanation) -> Result<T, CheckerError>;
+}
+
+impl<T, E: Into<CheckerError>> Explain<T> for Result<T, E> {
+    fn explain(self, f: impl FnOnce() -> Explanation) -> Result<T, CheckerError> {
+        self.map_err(|e| match e.into() {
+            // If the error already has an explanation, the new reasons are more general, so they
+            // come first
+            CheckerError::Explained(inner, inner_explanation) => {
+                let mut explanation = f();
+                explanation.reasons.extend(inner_explanation.reasons);
+                explanation.terms.extend(inner_explanation.terms);
+                CheckerError::Explained(inner, explanation)
+            }
+            e => CheckerError::Explained(Box::new(e), f()),
+        })
+    }
 }
 
 /// Errors in which we expected two things to be equal but they weren't.
 #[derive(Debug, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", content = "data")
+)]
 pub enum EqualityError<T: TypeName> {
     #[error("expected {}s to be equal: '{0}' and '{1}'", T::NAME)]
     ExpectedEqual(T, T),
@@ -174,6 +360,73 @@ pub enum EqualityError<T: TypeName> {
     ExpectedToBe { expected: T, got: T },
 }
 
+/// A problem premise that is close to an `assume` term that could not be matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PremiseMismatch {
+    pub premise: Rc<Term>,
+
+    /// The total size of the subterms that differ between the premise and the `assume` term.
+    pub distance: usize,
+
+    pub differences: Vec<TermDifference>,
+}
+
+/// A position in which a premise differs from an `assume` term.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TermDifference {
+    /// The path from the root of the term to the differing subterm, given as a sequence of argument
+    /// indices.
+    pub path: Vec<usize>,
+
+    /// The subterm in the premise.
+    pub expected: Rc<Term>,
+
+    /// The subterm in the `assume` term.
+    pub got: Rc<Term>,
+}
+
+impl fmt::Display for TermDifference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "at the root")?;
+        } else {
+            let path: Vec<_> = self.path.iter().map(ToString::to_string).collect();
+            write!(f, "at position {}", path.join("."))?;
+        }
+        write!(f, ", expected '{}' but got '{}'", self.expected, self.got)
+    }
+}
+
+/// The maximum number of differences shown for each premise in an `assume` error.
+const MAX_DISPLAYED_DIFFERENCES: usize = 3;
+
+struct DisplayClosestPremises<'a>(&'a [PremiseMismatch]);
+
+impl<'a> fmt::Display for DisplayClosestPremises<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for mismatch in self.0 {
+            write!(
+                f,
+                "\n  closest premise (distance {}): {}",
+                mismatch.distance, mismatch.premise
+            )?;
+            for d in mismatch.differences.iter().take(MAX_DISPLAYED_DIFFERENCES) {
+                write!(f, "\n    {}", d)?;
+            }
+            let remaining = mismatch
+                .differences
+                .len()
+                .saturating_sub(MAX_DISPLAYED_DIFFERENCES);
+            if remaining > 0 {
+                write!(f, "\n    and {} more differences", remaining)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 struct DisplayIndexedOp<'a>(&'a ParamOperator, &'a Vec<Rc<Term>>);
 
 impl<'a> fmt::Display for DisplayIndexedOp<'a> {
@@ -187,6 +440,11 @@ impl<'a> fmt::Display for DisplayIndexedOp<'a> {
 }
 
 #[derive(Debug, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", content = "data")
+)]
 pub enum CongruenceError {
     #[error("too many premises")]
     TooManyPremises,
@@ -227,6 +485,11 @@ pub enum CongruenceError {
 
 /// Errors relevant to the rules dealing with quantifiers.
 #[derive(Debug, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", content = "data")
+)]
 pub enum QuantifierError {
     #[error("argument doesn't match any binding: '{0}'")]
     NoBindingMatchesArg(String),
@@ -234,6 +497,22 @@ pub enum QuantifierError {
     #[error("no argument was given for binding '{0}'")]
     NoArgGivenForBinding(String),
 
+    #[error(
+        "argument '{arg}' for binding '{binding}' has sort '{got}', expected sort '{expected}'"
+    )]
+    ForallInstArgWrongSort {
+        binding: String,
+        expected: Rc<Term>,
+        arg: Rc<Term>,
+        got: Rc<Term>,
+    },
+
+    #[error("witness '{0}' is not a constant")]
+    WitnessIsNotConstant(Rc<Term>),
+
+    #[error("witness '{0}' is not fresh")]
+    WitnessIsNotFresh(Rc<Term>),
+
     #[error("union of bindings '{left_outer}' and '{left_inner}' does not equal '{right}'")]
     JoinFailed {
         left_outer: BindingList,
@@ -253,6 +532,11 @@ pub enum QuantifierError {
 
 /// Errors relevant to the linear arithmetic rules.
 #[derive(Debug, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", content = "data")
+)]
 pub enum LinearArithmeticError {
     #[error("term '{0}' doesn't match any tautology case")]
     NotValidTautologyCase(Rc<Term>),
@@ -278,6 +562,11 @@ pub enum LinearArithmeticError {
 
 /// Errors relevant to all rules that end subproofs (not just the `subproof` rule).
 #[derive(Debug, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", content = "data")
+)]
 pub enum SubproofError {
     #[error("discharge must be 'assume' command: '{0}'")]
     DischargeMustBeAssume(String),
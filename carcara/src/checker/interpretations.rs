@@ -0,0 +1,59 @@
+//! Interpretations for uninterpreted function symbols, given as Rust functions. These are used by
+//! the `evaluate` rule, which concludes the value of an application of an interpreted function to
+//! values, like `(= (hash 42) 1337)`.
+//!
+//! Since the interpretations are not part of the Alethe semantics, proofs that use them are only
+//! valid if the interpretations are trusted to agree with the intended meaning of the functions.
+//! Steps checked with an interpretation are reported as relying on the
+//! [`TrustedExtension`](super::leniency::Leniency::TrustedExtension) leniency.
+
+use crate::ast::*;
+use std::{collections::HashMap, fmt, sync::Arc};
+
+/// An interpretation of a function symbol. It receives the arguments of an application, which are
+/// all values, and returns the value of the application, or `None` if it can't be computed.
+pub type Interpretation =
+    Arc<dyn Fn(&mut dyn TermPool, &[Rc<Term>]) -> Option<Rc<Term>> + Send + Sync>;
+
+/// A set of interpretations, indexed by the name of the function they interpret.
+#[derive(Clone, Default)]
+pub struct Interpretations(HashMap<String, Interpretation>);
+
+impl Interpretations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the interpretation of the function with the given name, replacing any previous
+    /// interpretation of that function.
+    pub fn register<F>(mut self, name: impl Into<String>, interpretation: F) -> Self
+    where
+        F: Fn(&mut dyn TermPool, &[Rc<Term>]) -> Option<Rc<Term>> + Send + Sync + 'static,
+    {
+        self.0.insert(name.into(), Arc::new(interpretation));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Interpretation> {
+        self.0.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for Interpretations {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// Returns `true` if the term is a value, that is, a constant, a negative number or a boolean
+/// literal.
+pub(super) fn is_value(term: &Rc<Term>) -> bool {
+    term.is_const()
+        || term.as_signed_number().is_some()
+        || term.is_bool_true()
+        || term.is_bool_false()
+}
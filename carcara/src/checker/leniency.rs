@@ -0,0 +1,206 @@
+//! Reports of the places where a proof is only valid because of the checker's leniency, that is,
+//! where the proof deviates from strict Alethe. Producers can use these reports to find out what
+//! must be changed for their proofs to be accepted by a strict checker.
+
+use super::{interpretations::Interpretations, rare::RareRules, rules::reflexivity, *};
+use std::collections::BTreeMap;
+
+/// A leniency of the checker that a proof relies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Leniency {
+    /// An `assume` or `refl` step that is only valid if equalities are implicitly reordered.
+    ImplicitReordering,
+
+    /// A `refl` step that is only valid modulo the renaming of bound variables.
+    AlphaRenaming,
+
+    /// A `resolution` or `th_resolution` step that does not give its pivots as arguments.
+    ElidedArgs,
+
+    /// A step that uses a rule that is not known to the checker.
+    UnknownRule,
+
+    /// An `evaluate` step, which is checked using user-provided interpretations of functions.
+    TrustedExtension,
+}
+
+impl fmt::Display for Leniency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            Leniency::ImplicitReordering => "implicit reordering of equalities",
+            Leniency::AlphaRenaming => "alpha-renaming of bound variables",
+            Leniency::ElidedArgs => "elided pivots in resolution steps",
+            Leniency::UnknownRule => "unknown rules",
+            Leniency::TrustedExtension => "trusted extensions",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// A step that relies on a leniency of the checker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LenientStep {
+    pub id: String,
+    pub rule: String,
+    pub leniency: Leniency,
+}
+
+/// All steps in a proof that rely on a leniency of the checker, in the order they were checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LeniencyReport {
+    pub steps: Vec<LenientStep>,
+}
+
+impl LeniencyReport {
+    /// Returns `true` if no step relies on a leniency of the checker.
+    pub fn is_strict(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Returns the number of steps that rely on the given leniency.
+    pub fn count(&self, leniency: Leniency) -> usize {
+        self.steps.iter().filter(|s| s.leniency == leniency).count()
+    }
+}
+
+impl fmt::Display for LeniencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_strict() {
+            return writeln!(f, "no leniencies needed");
+        }
+        let mut categories: BTreeMap<Leniency, BTreeMap<&str, Vec<&str>>> = BTreeMap::new();
+        for step in &self.steps {
+            categories
+                .entry(step.leniency)
+                .or_default()
+                .entry(&step.rule)
+                .or_default()
+                .push(&step.id);
+        }
+        for (leniency, rules) in categories {
+            writeln!(f, "{}: {} steps", leniency, self.count(leniency))?;
+            for (rule, ids) in rules {
+                writeln!(f, "    {}: {}", rule, ids.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'c> ProofChecker<'c> {
+    /// Checks a proof in the most permissive mode, recording every step that relies on a leniency
+    /// of the checker. Unknown rules are always ignored, and the `elaborated` option is disabled.
+    pub fn check_leniencies(
+        &mut self,
+        problem: &Problem,
+        proof: &Proof,
+    ) -> CarcaraResult<(bool, LeniencyReport)> {
+        self.config.elaborated = false;
+        self.config.ignore_unknown_rules = true;
+        self.leniencies = Some(Vec::new());
+        let result = self.check(problem, proof);
+        let steps = self.leniencies.take().unwrap();
+        Ok((result?, LeniencyReport { steps }))
+    }
+
+    pub(super) fn record_leniency(&mut self, step_id: &str, rule: &str, leniency: Leniency) {
+        if let Some(leniencies) = &mut self.leniencies {
+            leniencies.push(LenientStep {
+                id: step_id.to_owned(),
+                rule: rule.to_owned(),
+                leniency,
+            });
+        }
+    }
+
+    /// Given a valid step, finds which leniency it relies on, if any. Only the rules that are
+    /// stricter when checking elaborated proofs can rely on a leniency.
+    pub(super) fn find_leniency(
+        &mut self,
+        step: &ProofStep,
+        premises: &[Premise],
+        previous_command: Option<Premise>,
+        discharge: &[&ProofCommand],
+        prelude: &ProblemPrelude,
+    ) -> Option<Leniency> {
+        let strict_rule = match step.rule.as_str() {
+            "refl" | "resolution" | "th_resolution" => {
+                Self::get_rule(&step.rule, &Config::new().elaborated(true))?
+            }
+            _ => return None,
+        };
+        let mut polyeq_time = Duration::ZERO;
+        let mut run = |rule: Rule| {
+            rule.check(RuleArgs {
+                conclusion: &step.clause,
+                premises,
+                args: &step.args,
+                pool: self.pool,
+                context: &mut self.context,
+                prelude,
+                interpretations: &Interpretations::new(),
+                rare_rules: &RareRules::new(),
+                previous_command,
+                discharge,
+                polyeq_time: &mut polyeq_time,
+            })
+        };
+        if run(strict_rule).is_ok() {
+            return None;
+        }
+        Some(match step.rule.as_str() {
+            "refl" if run(Rule::Mutating(reflexivity::reordering_refl)).is_ok() => {
+                Leniency::ImplicitReordering
+            }
+            "refl" => Leniency::AlphaRenaming,
+            _ => Leniency::ElidedArgs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_leniency_report() {
+        let problem = "
+            (declare-fun p () Bool)
+            (declare-fun a () Int)
+            (declare-fun b () Int)
+            (assert (= a b))
+            (assert (not p))
+        ";
+        let proof = "
+            (assume h1 (= b a))
+            (assume h2 (not p))
+            (step t1 (cl (= (= a b) (= b a))) :rule refl)
+            (step t2 (cl (= (forall ((x Int)) (= x a)) (forall ((y Int)) (= y a)))) :rule refl)
+            (step t3 (cl p) :rule trust_me)
+            (step t4 (cl) :rule resolution :premises (t3 h2))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let (is_holey, report) = ProofChecker::new(&mut pool, Config::new())
+            .check_leniencies(&problem, &proof)
+            .unwrap();
+        assert!(is_holey);
+
+        let expected = [
+            ("h1", Leniency::ImplicitReordering),
+            ("t1", Leniency::ImplicitReordering),
+            ("t2", Leniency::AlphaRenaming),
+            ("t3", Leniency::UnknownRule),
+            ("t4", Leniency::ElidedArgs),
+        ];
+        let got: Vec<_> = report
+            .steps
+            .iter()
+            .map(|s| (s.id.as_str(), s.leniency))
+            .collect();
+        assert_eq!(got, expected);
+        assert_eq!(report.count(Leniency::ImplicitReordering), 2);
+    }
+}
@@ -0,0 +1,155 @@
+//! Parsing of SMT-LIB logic names, used to reject steps whose rules reason about theories that are
+//! not part of the problem's logic.
+
+use super::{
+    error::CheckerError,
+    metadata::{rule_metadata, Theory},
+    Config,
+};
+use crate::ast::ProblemPrelude;
+
+/// The theories included in an SMT-LIB logic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Logic {
+    pub name: String,
+    pub quantifiers: bool,
+    pub arithmetic: bool,
+    pub bit_vectors: bool,
+    pub strings: bool,
+    pub arrays: bool,
+    pub datatypes: bool,
+}
+
+impl Logic {
+    /// Parses an SMT-LIB logic name, such as `QF_UFLIA` or `AUFBV`. The logic `ALL` includes every
+    /// theory. Returns `None` if the name is not a combination of known theories.
+    pub fn parse(name: &str) -> Option<Self> {
+        // The longer components must come first, so they are not mistaken for a shorter one
+        const COMPONENTS: &[&str] = &[
+            "LIRA", "NIRA", "LIA", "NIA", "LRA", "NRA", "IDL", "RDL", "AX", "UF", "BV", "FP", "DT",
+            "A", "S",
+        ];
+
+        if name == "ALL" {
+            return Some(Self {
+                name: name.to_owned(),
+                quantifiers: true,
+                arithmetic: true,
+                bit_vectors: true,
+                strings: true,
+                arrays: true,
+                datatypes: true,
+            });
+        }
+
+        let (quantifiers, mut rest) = match name.strip_prefix("QF_") {
+            Some(rest) => (false, rest),
+            None => (true, name),
+        };
+        let mut logic = Self {
+            name: name.to_owned(),
+            quantifiers,
+            arithmetic: false,
+            bit_vectors: false,
+            strings: false,
+            arrays: false,
+            datatypes: false,
+        };
+        if rest.is_empty() {
+            return None;
+        }
+        while !rest.is_empty() {
+            let component = COMPONENTS.iter().find(|c| rest.starts_with(*c))?;
+            match *component {
+                "BV" => logic.bit_vectors = true,
+                "S" => logic.strings = true,
+                "A" | "AX" => logic.arrays = true,
+                "DT" => logic.datatypes = true,
+                "LIRA" | "NIRA" | "LIA" | "NIA" | "LRA" | "NRA" | "IDL" | "RDL" => {
+                    logic.arithmetic = true;
+                }
+                _ => (),
+            }
+            rest = &rest[component.len()..];
+        }
+        Some(logic)
+    }
+
+    /// Returns `true` if the logic includes the given theory.
+    pub fn includes(&self, theory: Theory) -> bool {
+        match theory {
+            Theory::Arithmetic => self.arithmetic,
+            Theory::Quantifiers => self.quantifiers,
+            Theory::BitVectors => self.bit_vectors,
+            Theory::Strings => self.strings,
+            Theory::Arrays => self.arrays,
+            Theory::Datatypes => self.datatypes,
+        }
+    }
+
+    /// Returns the logic against which the steps should be checked, if the `enforce_logic` option
+    /// is enabled and the problem declares a known logic.
+    pub(super) fn from_prelude(config: &Config, prelude: &ProblemPrelude) -> Option<Self> {
+        if !config.enforce_logic {
+            return None;
+        }
+        let name = prelude.logic()?;
+        let logic = Self::parse(name);
+        if logic.is_none() {
+            tracing::warn!(
+                "unknown logic '{}', rules will not be restricted by it",
+                name
+            );
+        }
+        logic
+    }
+
+    /// Checks that a rule does not reason about a theory outside of the logic. Rules that are not
+    /// known to the checker are not restricted.
+    pub(super) fn check_rule(&self, rule: &str) -> Result<(), CheckerError> {
+        match rule_metadata(rule).and_then(|r| r.theory) {
+            Some(theory) if !self.includes(theory) => {
+                Err(CheckerError::RuleOutsideLogic { theory, logic: self.name.clone() })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_logic() {
+        let logic = Logic::parse("QF_UF").unwrap();
+        assert!(!logic.quantifiers && !logic.arithmetic && !logic.bit_vectors && !logic.strings);
+        assert!(logic.check_rule("eq_congruent").is_ok());
+        assert!(logic.check_rule("la_generic").is_err());
+        assert!(logic.check_rule("forall_inst").is_err());
+        assert!(logic.check_rule("not_a_rule").is_ok());
+        assert!(logic.check_rule("dt_clash").is_err());
+        assert!(logic.check_rule("ext").is_err());
+
+        let logic = Logic::parse("AUFLIRA").unwrap();
+        assert!(logic.quantifiers && logic.arithmetic && !logic.bit_vectors);
+        assert!(logic.check_rule("la_generic").is_ok());
+        assert!(logic.check_rule("sko_ex").is_ok());
+
+        let logic = Logic::parse("QF_SLIA").unwrap();
+        assert!(logic.strings && logic.arithmetic && !logic.quantifiers);
+
+        let logic = Logic::parse("QF_ABV").unwrap();
+        assert!(logic.bit_vectors && logic.arrays && !logic.arithmetic);
+        assert!(logic.check_rule("row2").is_ok());
+        assert!(logic.check_rule("lia_generic").is_err());
+
+        let logic = Logic::parse("QF_UFDTLIA").unwrap();
+        assert!(logic.datatypes && logic.arithmetic && !logic.strings);
+        assert!(logic.check_rule("dt_split").is_ok());
+
+        assert!(Logic::parse("ALL").unwrap().includes(Theory::Strings));
+        assert_eq!(None, Logic::parse("HORN"));
+        assert_eq!(None, Logic::parse("QF_"));
+    }
+}
@@ -0,0 +1,570 @@
+//! Metadata about the rules implemented by the checker, such as how many premises and arguments
+//! they expect, and which section of the Alethe specification describes them.
+
+use std::fmt;
+
+/// The section of the Alethe specification in which a rule is described.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuleSection {
+    Tautologies,
+    Resolution,
+    Equality,
+    Clausification,
+    Simplification,
+    LinearArithmetic,
+    Quantifiers,
+    Subproofs,
+    BitVectors,
+    Strings,
+
+    /// Rules that are not part of the specification, but are accepted by Carcara.
+    Extensions,
+
+    /// Rules that are always considered valid, and indicate holes in the proof.
+    Holes,
+}
+
+impl fmt::Display for RuleSection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            RuleSection::Tautologies => "tautologies and simple deductions",
+            RuleSection::Resolution => "resolution and related rules",
+            RuleSection::Equality => "equality and congruence",
+            RuleSection::Clausification => "clausification",
+            RuleSection::Simplification => "simplification",
+            RuleSection::LinearArithmetic => "linear arithmetic",
+            RuleSection::Quantifiers => "quantifier reasoning",
+            RuleSection::Subproofs => "subproofs and contexts",
+            RuleSection::BitVectors => "bit-vectors",
+            RuleSection::Strings => "strings",
+            RuleSection::Extensions => "extensions (not in the specification)",
+            RuleSection::Holes => "holes",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A theory that a rule reasons about, beyond the core theory of booleans and uninterpreted
+/// functions. A rule is only expected to appear in proofs for problems whose logic includes its
+/// theory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Theory {
+    Arithmetic,
+    Quantifiers,
+    BitVectors,
+    Strings,
+    Arrays,
+    Datatypes,
+}
+
+impl fmt::Display for Theory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Theory::Arithmetic => "arithmetic",
+            Theory::Quantifiers => "quantifiers",
+            Theory::BitVectors => "bit-vectors",
+            Theory::Strings => "strings",
+            Theory::Arrays => "arrays",
+            Theory::Datatypes => "datatypes",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The number of premises a rule expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PremiseCount {
+    Exactly(usize),
+    AtLeast(usize),
+
+    /// The number of premises depends on the conclusion or on the context.
+    Variable,
+}
+
+impl fmt::Display for PremiseCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PremiseCount::Exactly(n) => write!(f, "{}", n),
+            PremiseCount::AtLeast(n) => write!(f, "{} or more", n),
+            PremiseCount::Variable => write!(f, "depends on the conclusion"),
+        }
+    }
+}
+
+/// The kind of a rule argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// A non-negative integer, used as an index into a term or clause.
+    Index,
+
+    /// A boolean constant.
+    Bool,
+
+    /// A rational coefficient.
+    Coefficient,
+
+    /// An arbitrary term.
+    Term,
+
+    /// A resolution pivot, given as a term followed by a boolean constant indicating its
+    /// polarity in the first clause.
+    Pivot,
+}
+
+impl fmt::Display for ArgKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ArgKind::Index => "index",
+            ArgKind::Bool => "bool",
+            ArgKind::Coefficient => "coefficient",
+            ArgKind::Term => "term",
+            ArgKind::Pivot => "pivot",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The arguments a rule expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleArguments {
+    None,
+
+    /// A fixed sequence of arguments.
+    Fixed(&'static [ArgKind]),
+
+    /// Any number of arguments of the same kind. How many are needed depends on the step.
+    Repeated(ArgKind),
+
+    /// The arguments are optional, and can be either omitted or given as many arguments of the
+    /// same kind.
+    Optional(ArgKind),
+}
+
+impl fmt::Display for RuleArguments {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleArguments::None => write!(f, "none"),
+            RuleArguments::Fixed(kinds) => {
+                let kinds: Vec<_> = kinds.iter().map(ToString::to_string).collect();
+                write!(f, "{}", kinds.join(", "))
+            }
+            RuleArguments::Repeated(kind) => write!(f, "one or more of kind {}", kind),
+            RuleArguments::Optional(kind) => write!(f, "optional, any number of kind {}", kind),
+        }
+    }
+}
+
+/// How a checker configuration option changes the behavior of a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigEffect {
+    /// The name of the option in the checker `Config`.
+    pub option: &'static str,
+    pub effect: &'static str,
+}
+
+/// Metadata about a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleMetadata {
+    pub name: &'static str,
+
+    /// Other names under which the rule is accepted.
+    pub aliases: &'static [&'static str],
+
+    pub section: RuleSection,
+
+    /// The theory the rule reasons about, or `None` if the rule only uses the core theory.
+    pub theory: Option<Theory>,
+
+    pub premises: PremiseCount,
+    pub args: RuleArguments,
+
+    /// If `true`, the rule can only be used in the last step of a subproof.
+    pub ends_subproof: bool,
+
+    pub config_effects: &'static [ConfigEffect],
+    pub description: &'static str,
+}
+
+impl RuleMetadata {
+    const fn new(
+        name: &'static str,
+        section: RuleSection,
+        premises: PremiseCount,
+        args: RuleArguments,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            section,
+            theory: match section {
+                LinearArithmetic => Some(Theory::Arithmetic),
+                Quantifiers => Some(Theory::Quantifiers),
+                BitVectors => Some(Theory::BitVectors),
+                Strings => Some(Theory::Strings),
+                _ => None,
+            },
+            premises,
+            args,
+            ends_subproof: false,
+            config_effects: &[],
+            description,
+        }
+    }
+
+    const fn aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    const fn theory(mut self, theory: Theory) -> Self {
+        self.theory = Some(theory);
+        self
+    }
+
+    const fn ends_subproof(mut self) -> Self {
+        self.ends_subproof = true;
+        self
+    }
+
+    const fn config_effects(mut self, effects: &'static [ConfigEffect]) -> Self {
+        self.config_effects = effects;
+        self
+    }
+}
+
+impl fmt::Display for RuleMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        writeln!(f, "  {}", self.description)?;
+        if !self.aliases.is_empty() {
+            writeln!(f, "  aliases: {}", self.aliases.join(", "))?;
+        }
+        writeln!(f, "  section: {}", self.section)?;
+        if let Some(theory) = self.theory {
+            writeln!(f, "  theory: {}", theory)?;
+        }
+        writeln!(f, "  premises: {}", self.premises)?;
+        writeln!(f, "  arguments: {}", self.args)?;
+        if self.ends_subproof {
+            writeln!(f, "  must be the last step of a subproof")?;
+        }
+        for ConfigEffect { option, effect } in self.config_effects {
+            writeln!(f, "  with `{}`: {}", option, effect)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the metadata for the rule with the given name, or `None` if the rule is not
+/// implemented. Aliases are also accepted.
+pub fn rule_metadata(name: &str) -> Option<&'static RuleMetadata> {
+    RULES
+        .iter()
+        .find(|r| r.name == name || r.aliases.contains(&name))
+}
+
+/// Returns the metadata for every rule implemented by the checker.
+pub fn all_rules() -> &'static [RuleMetadata] {
+    RULES
+}
+
+use ArgKind::*;
+use PremiseCount::*;
+use RuleArguments::{Fixed, Optional, Repeated};
+use RuleSection::*;
+
+const NO_ARGS: RuleArguments = RuleArguments::None;
+
+const ELABORATED_NO_REORDERING: &[ConfigEffect] = &[ConfigEffect {
+    option: "elaborated",
+    effect: "implicit reordering of equalities is not allowed",
+}];
+
+#[rustfmt::skip]
+static RULES: &[RuleMetadata] = &[
+    // Tautologies and simple deductions
+    RuleMetadata::new("true", Tautologies, Exactly(0), NO_ARGS, "Concludes `true`."),
+    RuleMetadata::new("false", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not false)`."),
+    RuleMetadata::new("not_not", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not (not (not phi))), phi`."),
+    RuleMetadata::new("and_pos", Tautologies, Exactly(0), Fixed(&[Index]), "Concludes `(not (and phi_1 ... phi_n)), phi_i`."),
+    RuleMetadata::new("and_neg", Tautologies, Exactly(0), NO_ARGS, "Concludes `(and phi_1 ... phi_n), (not phi_1), ..., (not phi_n)`."),
+    RuleMetadata::new("or_pos", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not (or phi_1 ... phi_n)), phi_1, ..., phi_n`."),
+    RuleMetadata::new("or_neg", Tautologies, Exactly(0), Fixed(&[Index]), "Concludes `(or phi_1 ... phi_n), (not phi_i)`."),
+    RuleMetadata::new("xor_pos1", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not (xor phi_1 phi_2)), phi_1, phi_2`."),
+    RuleMetadata::new("xor_pos2", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not (xor phi_1 phi_2)), (not phi_1), (not phi_2)`."),
+    RuleMetadata::new("xor_neg1", Tautologies, Exactly(0), NO_ARGS, "Concludes `(xor phi_1 phi_2), phi_1, (not phi_2)`."),
+    RuleMetadata::new("xor_neg2", Tautologies, Exactly(0), NO_ARGS, "Concludes `(xor phi_1 phi_2), (not phi_1), phi_2`."),
+    RuleMetadata::new("implies_pos", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not (=> phi_1 phi_2)), (not phi_1), phi_2`."),
+    RuleMetadata::new("implies_neg1", Tautologies, Exactly(0), NO_ARGS, "Concludes `(=> phi_1 phi_2), phi_1`."),
+    RuleMetadata::new("implies_neg2", Tautologies, Exactly(0), NO_ARGS, "Concludes `(=> phi_1 phi_2), (not phi_2)`."),
+    RuleMetadata::new("equiv_pos1", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not (= phi_1 phi_2)), phi_1, (not phi_2)`."),
+    RuleMetadata::new("equiv_pos2", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not (= phi_1 phi_2)), (not phi_1), phi_2`."),
+    RuleMetadata::new("equiv_neg1", Tautologies, Exactly(0), NO_ARGS, "Concludes `(= phi_1 phi_2), (not phi_1), (not phi_2)`."),
+    RuleMetadata::new("equiv_neg2", Tautologies, Exactly(0), NO_ARGS, "Concludes `(= phi_1 phi_2), phi_1, phi_2`."),
+    RuleMetadata::new("ite_pos1", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not (ite phi_1 phi_2 phi_3)), phi_1, phi_3`."),
+    RuleMetadata::new("ite_pos2", Tautologies, Exactly(0), NO_ARGS, "Concludes `(not (ite phi_1 phi_2 phi_3)), (not phi_1), phi_2`."),
+    RuleMetadata::new("ite_neg1", Tautologies, Exactly(0), NO_ARGS, "Concludes `(ite phi_1 phi_2 phi_3), phi_1, (not phi_3)`."),
+    RuleMetadata::new("ite_neg2", Tautologies, Exactly(0), NO_ARGS, "Concludes `(ite phi_1 phi_2 phi_3), (not phi_1), (not phi_2)`."),
+    RuleMetadata::new("equiv1", Tautologies, Exactly(1), NO_ARGS, "From `(= phi_1 phi_2)`, concludes `(not phi_1), phi_2`."),
+    RuleMetadata::new("equiv2", Tautologies, Exactly(1), NO_ARGS, "From `(= phi_1 phi_2)`, concludes `phi_1, (not phi_2)`."),
+    RuleMetadata::new("not_equiv1", Tautologies, Exactly(1), NO_ARGS, "From `(not (= phi_1 phi_2))`, concludes `phi_1, phi_2`."),
+    RuleMetadata::new("not_equiv2", Tautologies, Exactly(1), NO_ARGS, "From `(not (= phi_1 phi_2))`, concludes `(not phi_1), (not phi_2)`."),
+    RuleMetadata::new("ite1", Tautologies, Exactly(1), NO_ARGS, "From `(ite phi_1 phi_2 phi_3)`, concludes `phi_1, phi_3`."),
+    RuleMetadata::new("ite2", Tautologies, Exactly(1), NO_ARGS, "From `(ite phi_1 phi_2 phi_3)`, concludes `(not phi_1), phi_2`."),
+    RuleMetadata::new("not_ite1", Tautologies, Exactly(1), NO_ARGS, "From `(not (ite phi_1 phi_2 phi_3))`, concludes `phi_1, (not phi_3)`."),
+    RuleMetadata::new("not_ite2", Tautologies, Exactly(1), NO_ARGS, "From `(not (ite phi_1 phi_2 phi_3))`, concludes `(not phi_1), (not phi_2)`."),
+    RuleMetadata::new("ite_intro", Tautologies, Exactly(0), NO_ARGS, "Introduces the conditions of the `ite` terms that appear in a term."),
+    RuleMetadata::new("connective_def", Tautologies, Exactly(0), NO_ARGS, "Unfolds the definition of `xor`, `=>`, `=` on booleans, `ite` or a quantifier."),
+
+    // Resolution and related rules
+    RuleMetadata::new("resolution", Resolution, AtLeast(1), Optional(Pivot), "Applies chain resolution to the premises.")
+        .aliases(&["th_resolution"])
+        .config_effects(&[ConfigEffect {
+            option: "elaborated",
+            effect: "the pivots must be provided as arguments",
+        }]),
+    RuleMetadata::new("tautology", Resolution, Exactly(1), NO_ARGS, "From a clause containing both a literal and its negation, concludes `true`."),
+    RuleMetadata::new("contraction", Resolution, Exactly(1), NO_ARGS, "Removes duplicate literals from a clause."),
+
+    // Equality and congruence
+    RuleMetadata::new("eq_reflexive", Equality, Exactly(0), NO_ARGS, "Concludes `(= t t)`."),
+    RuleMetadata::new("eq_transitive", Equality, Exactly(0), NO_ARGS, "Concludes a clause stating that a chain of equalities implies an equality between its ends."),
+    RuleMetadata::new("eq_congruent", Equality, Exactly(0), NO_ARGS, "Concludes a clause stating that equal arguments imply equal function applications."),
+    RuleMetadata::new("eq_congruent_pred", Equality, Exactly(0), NO_ARGS, "Concludes a clause stating that equal arguments imply equivalent predicate applications."),
+    RuleMetadata::new("refl", Equality, Exactly(0), NO_ARGS, "Concludes `(= t u)`, where `t` and `u` are equal after applying the context substitution.")
+        .config_effects(ELABORATED_NO_REORDERING),
+    RuleMetadata::new("trans", Equality, AtLeast(1), NO_ARGS, "Concludes `(= t_1 t_n)` from a chain of equalities `(= t_i t_i+1)`."),
+    RuleMetadata::new("cong", Equality, AtLeast(1), NO_ARGS, "Concludes `(= (f t_1 ... t_n) (f u_1 ... u_n))` from the equalities between the arguments."),
+    RuleMetadata::new("ho_cong", Equality, AtLeast(1), NO_ARGS, "Higher-order variant of `cong`, that also allows the applied functions to be different."),
+    RuleMetadata::new("rewrite", Equality, Exactly(1), Repeated(Index), "Rewrites the subterm at the given position using the premise equality."),
+
+    // Clausification
+    RuleMetadata::new("distinct_elim", Clausification, Exactly(0), NO_ARGS, "Expands a `distinct` term into a conjunction of disequalities."),
+    RuleMetadata::new("and", Clausification, Exactly(1), Fixed(&[Index]), "From `(and phi_1 ... phi_n)`, concludes `phi_i`."),
+    RuleMetadata::new("not_or", Clausification, Exactly(1), Fixed(&[Index]), "From `(not (or phi_1 ... phi_n))`, concludes `(not phi_i)`."),
+    RuleMetadata::new("or", Clausification, Exactly(1), NO_ARGS, "From `(or phi_1 ... phi_n)`, concludes the clause `phi_1, ..., phi_n`."),
+    RuleMetadata::new("not_and", Clausification, Exactly(1), NO_ARGS, "From `(not (and phi_1 ... phi_n))`, concludes `(not phi_1), ..., (not phi_n)`."),
+    RuleMetadata::new("xor1", Clausification, Exactly(1), NO_ARGS, "From `(xor phi_1 phi_2)`, concludes `phi_1, phi_2`."),
+    RuleMetadata::new("xor2", Clausification, Exactly(1), NO_ARGS, "From `(xor phi_1 phi_2)`, concludes `(not phi_1), (not phi_2)`."),
+    RuleMetadata::new("not_xor1", Clausification, Exactly(1), NO_ARGS, "From `(not (xor phi_1 phi_2))`, concludes `phi_1, (not phi_2)`."),
+    RuleMetadata::new("not_xor2", Clausification, Exactly(1), NO_ARGS, "From `(not (xor phi_1 phi_2))`, concludes `(not phi_1), phi_2`."),
+    RuleMetadata::new("implies", Clausification, Exactly(1), NO_ARGS, "From `(=> phi_1 phi_2)`, concludes `(not phi_1), phi_2`."),
+    RuleMetadata::new("not_implies1", Clausification, Exactly(1), NO_ARGS, "From `(not (=> phi_1 phi_2))`, concludes `phi_1`."),
+    RuleMetadata::new("not_implies2", Clausification, Exactly(1), NO_ARGS, "From `(not (=> phi_1 phi_2))`, concludes `(not phi_2)`."),
+    RuleMetadata::new("nary_elim", Clausification, Exactly(0), NO_ARGS, "Replaces an n-ary application of a chainable or associative operator by binary applications."),
+    RuleMetadata::new("nary_intro", Clausification, Exactly(0), NO_ARGS, "Inverse of `nary_elim`."),
+    RuleMetadata::new("bfun_elim", Clausification, Exactly(1), NO_ARGS, "Eliminates boolean arguments of functions and quantified boolean variables by case splitting."),
+
+    // Simplification
+    RuleMetadata::new("ite_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies `ite` terms."),
+    RuleMetadata::new("eq_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies equalities between equal terms or distinct constants."),
+    RuleMetadata::new("and_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies `and` terms."),
+    RuleMetadata::new("or_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies `or` terms."),
+    RuleMetadata::new("not_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies `not` terms."),
+    RuleMetadata::new("implies_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies `=>` terms."),
+    RuleMetadata::new("equiv_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies equivalences between booleans."),
+    RuleMetadata::new("bool_simplify", Simplification, Exactly(0), NO_ARGS, "Applies simple boolean simplifications, such as De Morgan's laws."),
+    RuleMetadata::new("qnt_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies quantifiers over a constant body.")
+        .theory(Theory::Quantifiers),
+    RuleMetadata::new("div_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies `div`, `mod` and `/` terms, evaluating them on ground arguments.")
+        .theory(Theory::Arithmetic),
+    RuleMetadata::new("prod_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies products by multiplying their constant factors.")
+        .theory(Theory::Arithmetic),
+    RuleMetadata::new("minus_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies subtractions and unary negations.")
+        .theory(Theory::Arithmetic)
+        .aliases(&["unary_minus_simplify"]),
+    RuleMetadata::new("sum_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies sums by adding their constant terms.")
+        .theory(Theory::Arithmetic),
+    RuleMetadata::new("comp_simplify", Simplification, Exactly(0), NO_ARGS, "Simplifies arithmetic comparisons.")
+        .theory(Theory::Arithmetic),
+    RuleMetadata::new("ac_simp", Simplification, Exactly(0), NO_ARGS, "Flattens nested `and` and `or` terms and removes duplicate arguments."),
+
+    // Linear arithmetic
+    RuleMetadata::new("la_rw_eq", LinearArithmetic, Exactly(0), NO_ARGS, "Concludes `(= (= t u) (and (<= t u) (<= u t)))`."),
+    RuleMetadata::new("la_generic", LinearArithmetic, Exactly(0), Repeated(Coefficient), "Concludes a tautological clause of linear inequalities, given one coefficient per literal."),
+    RuleMetadata::new("la_disequality", LinearArithmetic, Exactly(0), NO_ARGS, "Concludes `(or (= t u) (not (<= t u)) (not (<= u t)))`."),
+    RuleMetadata::new("la_totality", LinearArithmetic, Exactly(0), NO_ARGS, "Concludes `(or (<= t u) (<= u t))`."),
+    RuleMetadata::new("la_antisymmetry", LinearArithmetic, Exactly(2), NO_ARGS, "Concludes `(= t u)` from `(<= t u)` and `(<= u t)`."),
+    RuleMetadata::new("la_tautology", LinearArithmetic, Exactly(0), NO_ARGS, "Concludes simple linear arithmetic tautologies."),
+
+    // Quantifier reasoning
+    RuleMetadata::new("forall_inst", Quantifiers, Exactly(0), Repeated(Term), "Instantiates a universal quantifier, given one term per bound variable.")
+        .config_effects(&[ConfigEffect {
+            option: "allow_int_real_subtyping",
+            effect: "arguments of sort `Int` may instantiate variables of sort `Real`",
+        }]),
+    RuleMetadata::new("qnt_join", Quantifiers, Exactly(0), NO_ARGS, "Joins nested quantifiers of the same kind."),
+    RuleMetadata::new("qnt_rm_unused", Quantifiers, Exactly(0), NO_ARGS, "Removes bound variables that do not appear in the quantifier body."),
+    RuleMetadata::new("qnt_cnf", Quantifiers, Exactly(0), NO_ARGS, "Concludes that a quantified formula implies one clause of its conjunctive normal form."),
+
+    // Subproofs and contexts
+    RuleMetadata::new("subproof", Subproofs, Variable, NO_ARGS, "Concludes the implication between the local assumptions of a subproof and its last step.")
+        .ends_subproof(),
+    RuleMetadata::new("bind", Subproofs, Exactly(0), NO_ARGS, "Renames the bound variables of a binder term, using the context of the subproof.")
+        .ends_subproof(),
+    RuleMetadata::new("let", Subproofs, Variable, NO_ARGS, "Eliminates a `let` term, using the context of the subproof. Expects one premise per binding that is not syntactically equal to its value.")
+        .ends_subproof(),
+    RuleMetadata::new("onepoint", Subproofs, Exactly(0), NO_ARGS, "Eliminates bound variables that are equal to a term, using the context of the subproof.")
+        .theory(Theory::Quantifiers)
+        .ends_subproof(),
+    RuleMetadata::new("sko_ex", Subproofs, Exactly(0), NO_ARGS, "Skolemizes an existential quantifier, using the context of the subproof.")
+        .theory(Theory::Quantifiers)
+        .ends_subproof(),
+    RuleMetadata::new("sko_forall", Subproofs, Exactly(0), NO_ARGS, "Skolemizes a universal quantifier, using the context of the subproof.")
+        .theory(Theory::Quantifiers)
+        .ends_subproof(),
+
+    // Bit-vectors
+    RuleMetadata::new("bitblast_extract", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bit-vector `extract` term."),
+    RuleMetadata::new("bitblast_bvadd", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bit-vector addition."),
+    RuleMetadata::new("bitblast_ult", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bit-vector unsigned less-than comparison."),
+    RuleMetadata::new("bitblast_var", BitVectors, Exactly(0), NO_ARGS, "Concludes that a bit-vector is equal to the `bbterm` of its bits."),
+    RuleMetadata::new("bitblast_const", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bit-vector constant."),
+    RuleMetadata::new("bitblast_not", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bitwise `bvnot`."),
+    RuleMetadata::new("bitblast_and", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bitwise `bvand`."),
+    RuleMetadata::new("bitblast_or", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bitwise `bvor`."),
+    RuleMetadata::new("bitblast_xor", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bitwise `bvxor`."),
+    RuleMetadata::new("bitblast_xnor", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bitwise `bvxnor`."),
+    RuleMetadata::new("bitblast_equal", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts an equality between bit-vectors into a conjunction of equalities between their bits."),
+    RuleMetadata::new("bitblast_concat", BitVectors, Exactly(0), NO_ARGS, "Bit-blasts a bit-vector `concat` term."),
+
+    // Strings
+    RuleMetadata::new("concat_eq", Strings, Exactly(1), Fixed(&[Bool]), "Removes the common prefix, or suffix, of two equal string concatenations."),
+    RuleMetadata::new("concat_unify", Strings, Exactly(2), Fixed(&[Bool]), "Concludes that the first, or last, components of two equal concatenations with the same length are equal."),
+    RuleMetadata::new("concat_conflict", Strings, Exactly(1), Fixed(&[Bool]), "Concludes `false` from an equality between concatenations that start, or end, with different constants."),
+    RuleMetadata::new("concat_csplit_prefix", Strings, Exactly(2), NO_ARGS, "Splits a concatenation whose prefix is compared to a constant."),
+    RuleMetadata::new("concat_csplit_suffix", Strings, Exactly(2), NO_ARGS, "Splits a concatenation whose suffix is compared to a constant."),
+    RuleMetadata::new("concat_split_prefix", Strings, Exactly(2), NO_ARGS, "Splits two equal concatenations whose first components have different lengths."),
+    RuleMetadata::new("concat_split_suffix", Strings, Exactly(2), NO_ARGS, "Splits two equal concatenations whose last components have different lengths."),
+    RuleMetadata::new("concat_lprop_prefix", Strings, Exactly(2), NO_ARGS, "Length propagation on the prefixes of two equal concatenations."),
+    RuleMetadata::new("concat_lprop_suffix", Strings, Exactly(2), NO_ARGS, "Length propagation on the suffixes of two equal concatenations."),
+    RuleMetadata::new("concat_cprop_prefix", Strings, Exactly(2), NO_ARGS, "Constant propagation on the prefixes of two equal concatenations."),
+    RuleMetadata::new("concat_cprop_suffix", Strings, Exactly(2), NO_ARGS, "Constant propagation on the suffixes of two equal concatenations."),
+    RuleMetadata::new("string_decompose", Strings, Exactly(1), Fixed(&[Bool]), "Decomposes a string into a prefix, or suffix, of a given length and the rest."),
+    RuleMetadata::new("string_length_pos", Strings, Exactly(0), Fixed(&[Term]), "Concludes that the length of a string is either zero or positive."),
+    RuleMetadata::new("string_length_non_empty", Strings, Exactly(1), NO_ARGS, "Concludes that a string that is not empty has a non-zero length."),
+    RuleMetadata::new("string_code_inj", Strings, Exactly(0), NO_ARGS, "Concludes that two strings with the same valid code are equal."),
+    RuleMetadata::new("string_eager_reduction", Strings, Exactly(0), Fixed(&[Term]), "Concludes the bounds of a `str.to_code` or `str.indexof` term, given as argument."),
+    RuleMetadata::new("re_inter", Strings, Exactly(2), NO_ARGS, "Concludes that a string is in the intersection of two regular expressions it belongs to."),
+    RuleMetadata::new("re_unfold_neg", Strings, Exactly(1), NO_ARGS, "Unfolds the negated membership of a string in a regular expression."),
+    RuleMetadata::new("re_unfold_neg_concat_fixed_prefix", Strings, Exactly(1), NO_ARGS, "Unfolds the negated membership of a string in a concatenation of regular expressions with a fixed-length prefix."),
+    RuleMetadata::new("re_unfold_neg_concat_fixed_suffix", Strings, Exactly(1), NO_ARGS, "Unfolds the negated membership of a string in a concatenation of regular expressions with a fixed-length suffix."),
+
+    // Extensions
+    RuleMetadata::new("reordering", Extensions, Exactly(1), NO_ARGS, "Concludes a permutation of the premise clause."),
+    RuleMetadata::new("symm", Extensions, Exactly(1), NO_ARGS, "From `(= t u)`, concludes `(= u t)`."),
+    RuleMetadata::new("not_symm", Extensions, Exactly(1), NO_ARGS, "From `(not (= t u))`, concludes `(not (= u t))`."),
+    RuleMetadata::new("eq_symmetric", Extensions, Exactly(0), NO_ARGS, "Concludes `(= (= t u) (= u t))`."),
+    RuleMetadata::new("weakening", Extensions, Exactly(1), NO_ARGS, "Concludes a clause that extends the premise clause with extra literals."),
+    RuleMetadata::new("bind_let", Extensions, Exactly(0), NO_ARGS, "Renames the variables of a `let` term, using the context of the subproof.")
+        .ends_subproof(),
+    RuleMetadata::new("la_mult_pos", Extensions, Exactly(0), NO_ARGS, "Concludes that multiplying both sides of a comparison by a positive number preserves it.")
+        .theory(Theory::Arithmetic),
+    RuleMetadata::new("la_mult_neg", Extensions, Exactly(0), NO_ARGS, "Concludes that multiplying both sides of a comparison by a negative number flips it.")
+        .theory(Theory::Arithmetic),
+    RuleMetadata::new("exists_elim", Extensions, Exactly(1), Repeated(Term), "Eliminates an existential quantifier, given one fresh constant per bound variable as witnesses.")
+        .theory(Theory::Quantifiers),
+    RuleMetadata::new("mod_simplify", Extensions, Exactly(0), NO_ARGS, "Evaluates a `mod` term on integer constants.")
+        .theory(Theory::Arithmetic),
+    RuleMetadata::new("row1", Extensions, Exactly(0), NO_ARGS, "Concludes that reading an array at the index it was just written to gives the written value.")
+        .theory(Theory::Arrays),
+    RuleMetadata::new("row2", Extensions, Exactly(0), NO_ARGS, "Concludes that writing to an array does not change the values at other indices.")
+        .theory(Theory::Arrays),
+    RuleMetadata::new("row_contra", Extensions, Exactly(1), NO_ARGS, "Concludes that two indices are equal, if writing to one changes the value at the other.")
+        .theory(Theory::Arrays),
+    RuleMetadata::new("ext", Extensions, Exactly(0), NO_ARGS, "Concludes that two arrays are equal, or differ at the index given by a choice term.")
+        .theory(Theory::Arrays),
+    RuleMetadata::new("dt_split", Extensions, Exactly(0), NO_ARGS, "Concludes that a datatype term is built by one of the datatype's constructors.")
+        .theory(Theory::Datatypes),
+    RuleMetadata::new("dt_inst", Extensions, Exactly(0), NO_ARGS, "Concludes that a term satisfies a tester iff it is equal to the constructor applied to its selectors.")
+        .theory(Theory::Datatypes),
+    RuleMetadata::new("dt_collapse", Extensions, Exactly(0), NO_ARGS, "Concludes that a selector applied to a constructor term is equal to the corresponding field.")
+        .theory(Theory::Datatypes),
+    RuleMetadata::new("dt_clash", Extensions, Exactly(0), NO_ARGS, "Concludes that a term does not satisfy the testers of two distinct constructors.")
+        .theory(Theory::Datatypes),
+    RuleMetadata::new("evaluate", Extensions, Exactly(0), NO_ARGS, "Evaluates an application of a function to values, using an interpretation registered in the checker configuration."),
+    RuleMetadata::new("strict_resolution", Extensions, AtLeast(2), Repeated(Pivot), "Applies chain resolution, removing only the pivots and keeping duplicate literals."),
+    RuleMetadata::new("rare_rewrite", Extensions, Variable, Repeated(Term), "Concludes an equality that is an instance of a RARE rewrite rule, given the rule name followed by the values of its parameters. Each condition of the rule must be `true` or the conclusion of a premise.")
+        .config_effects(&[
+            ConfigEffect {
+                option: "rare_rules",
+                effect: "the rules that can be used; if there are none, the rule is not accepted",
+            },
+            ConfigEffect {
+                option: "allowed_rules",
+                effect: "if there are no RARE rules but the rule is allowed, as in the cvc5 dialect, steps are considered holes",
+            },
+        ]),
+
+    // Holes
+    RuleMetadata::new("hole", Holes, Variable, Optional(Term), "Always considered valid. Marks a step that is not justified by the proof."),
+    RuleMetadata::new("lia_generic", Holes, Exactly(0), Optional(Coefficient), "A tautological clause of linear integer arithmetic. If Farkas coefficients are given, checked like `la_generic`. Otherwise, checked as a hole, but can be elaborated using an external solver.")
+        .theory(Theory::Arithmetic),
+    RuleMetadata::new("nla_generic", Holes, Exactly(0), NO_ARGS, "A tautological clause of nonlinear arithmetic. Checked as a hole, but can be elaborated using an external solver.")
+        .theory(Theory::Arithmetic),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        checker::{Config, ProofChecker},
+        parser,
+    };
+    use std::collections::HashSet;
+
+    /// Returns the names matched by `ProofChecker::get_rule`, read from its source. Each arm of the
+    /// match is expected to start in a new line.
+    fn implemented_rules() -> Vec<&'static str> {
+        let source = include_str!("mod.rs");
+        let start = source.find("pub fn get_rule(").unwrap();
+        let end = start + source[start..].find("_ => return None").unwrap();
+        let mut names = Vec::new();
+        for line in source[start..end].lines().map(str::trim) {
+            if line.starts_with('"') {
+                let pattern = line.split("=>").next().unwrap();
+                let pattern = pattern.split(" if ").next().unwrap();
+                names.extend(pattern.split('|').map(|name| name.trim().trim_matches('"')));
+            }
+        }
+        names
+    }
+
+    #[test]
+    fn test_rule_metadata() {
+        // `rare_rewrite` is only accepted if there are RARE rules
+        let rare_rules = "(define-rule bool-double-not-elim ((t Bool)) (not (not t)) t)";
+        let rare_rules = parser::parse_rare_rules(rare_rules.as_bytes(), parser::Config::new());
+        let config = Config::new().rare_rules(rare_rules.unwrap());
+
+        let mut names = HashSet::new();
+        for rule in all_rules() {
+            for name in std::iter::once(&rule.name).chain(rule.aliases) {
+                assert!(names.insert(*name), "rule '{}' is listed twice", name);
+                assert!(
+                    ProofChecker::get_rule(name, &config).is_some(),
+                    "rule '{}' is not implemented",
+                    name
+                );
+            }
+        }
+
+        let implemented = implemented_rules();
+        assert!(implemented.len() > 100);
+        for name in implemented {
+            assert!(
+                rule_metadata(name).is_some(),
+                "rule '{}' has no metadata",
+                name
+            );
+        }
+
+        let resolution = rule_metadata("th_resolution").unwrap();
+        assert_eq!("resolution", resolution.name);
+        assert_eq!(AtLeast(1), resolution.premises);
+        assert_eq!("elaborated", resolution.config_effects[0].option);
+        assert!(rule_metadata("not_a_rule").is_none());
+    }
+}
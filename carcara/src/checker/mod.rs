@@ -1,21 +1,36 @@
+mod assume_report;
+pub mod checkpoint;
+pub mod compression;
+pub mod conformance;
 pub mod error;
+pub mod interpretations;
+pub mod leniency;
+pub mod logic;
+pub mod metadata;
+pub mod obligations;
 mod parallel;
+pub mod rare;
+pub mod roots;
 mod rules;
+mod streaming;
+mod witnesses;
 
 use crate::{
     ast::*,
     benchmarking::{CollectResults, OnlineBenchmarkResults},
+    utils::Instant,
     CarcaraResult, Error,
 };
 use error::{CheckerError, SubproofError};
 use indexmap::IndexSet;
-pub use parallel::{scheduler::Scheduler, ParallelProofChecker};
+use leniency::{Leniency, LenientStep};
+use logic::Logic;
+pub use parallel::{scheduler::Scheduler, CostModel, CostModelError, ParallelProofChecker};
+#[cfg(feature = "elaborator")]
+pub(crate) use rules::simplification::apply_ac_simp;
 use rules::{Premise, Rule, RuleArgs, RuleResult};
-use std::{
-    collections::HashSet,
-    fmt,
-    time::{Duration, Instant},
-};
+use std::{collections::HashSet, fmt, time::Duration};
+pub use streaming::{LastUses, StreamingChecker};
 
 #[derive(Clone)]
 pub struct CheckerStatistics<'s, CR: CollectResults + Send + Default> {
@@ -58,6 +73,45 @@ pub struct Config {
 
     /// A set of rule names that the checker will allow, considering them holes in the proof.
     pub allowed_rules: HashSet<String>,
+
+    /// If this is `Some`, the checker will only accept proofs whose steps use one of the given
+    /// rules. Proofs with steps that use any other rule are rejected before being checked.
+    pub accepted_rules: Option<HashSet<String>>,
+
+    /// A set of rule names that the checker will reject, like `hole` or `trust`. Proofs with steps
+    /// that use any of these rules are rejected before being checked.
+    pub denied_rules: HashSet<String>,
+
+    /// If `true`, the checker will allow `Int`/`Real` subtyping in the instantiation arguments of
+    /// `forall_inst` steps. In that case, arguments of sort `Int` given to bindings of sort `Real`
+    /// are coerced to `Real`.
+    pub allow_int_real_subtyping: bool,
+
+    /// If this is `Some`, the checker will only check the steps with the given ids. All other
+    /// steps are trusted, and considered holes in the proof.
+    pub only_steps: Option<HashSet<String>>,
+
+    /// If this is `Some`, the checker will only check the steps that use one of the given rules.
+    /// All other steps are trusted, and considered holes in the proof. If both this and
+    /// `only_steps` are given, only steps that satisfy both filters are checked.
+    pub only_rules: Option<HashSet<String>>,
+
+    /// The clause that the proof is expected to conclude. If this is `None`, the proof must reach
+    /// the empty clause. The order of the terms in the clause is not relevant.
+    pub expected_conclusion: Option<Vec<Rc<Term>>>,
+
+    /// If `true`, the checker will reject steps whose rules reason about a theory that is not part
+    /// of the problem's logic, like a `la_generic` step in a `QF_UF` problem. If the problem does
+    /// not declare a logic, or declares a logic that is not known, no steps are rejected.
+    pub enforce_logic: bool,
+
+    /// The interpretations of uninterpreted functions used by the `evaluate` rule. Steps checked
+    /// with these interpretations are trusted extensions of the checker.
+    pub interpretations: interpretations::Interpretations,
+
+    /// The database of RARE rules used to check `rare_rewrite` steps. If this is empty,
+    /// `rare_rewrite` is considered an unknown rule.
+    pub rare_rules: rare::RareRules,
 }
 
 impl Config {
@@ -74,14 +128,135 @@ impl Config {
         self.ignore_unknown_rules = value;
         self
     }
+
+    pub fn allow_int_real_subtyping(mut self, value: bool) -> Self {
+        self.allow_int_real_subtyping = value;
+        self
+    }
+
+    pub fn accepted_rules(mut self, value: Option<HashSet<String>>) -> Self {
+        self.accepted_rules = value;
+        self
+    }
+
+    pub fn denied_rules(mut self, value: HashSet<String>) -> Self {
+        self.denied_rules = value;
+        self
+    }
+
+    pub fn only_steps(mut self, value: Option<HashSet<String>>) -> Self {
+        self.only_steps = value;
+        self
+    }
+
+    pub fn only_rules(mut self, value: Option<HashSet<String>>) -> Self {
+        self.only_rules = value;
+        self
+    }
+
+    pub fn expected_conclusion(mut self, value: Option<Vec<Rc<Term>>>) -> Self {
+        self.expected_conclusion = value;
+        self
+    }
+
+    pub fn enforce_logic(mut self, value: bool) -> Self {
+        self.enforce_logic = value;
+        self
+    }
+
+    pub fn interpretations(mut self, value: interpretations::Interpretations) -> Self {
+        self.interpretations = value;
+        self
+    }
+
+    pub fn rare_rules(mut self, value: rare::RareRules) -> Self {
+        self.rare_rules = value;
+        self
+    }
+
+    /// Adjusts the configuration to proofs in the given dialect, by allowing the rules that the
+    /// dialect may use even if they can't be checked. See [`Dialect::allowed_rules`].
+    ///
+    /// [`Dialect::allowed_rules`]: crate::parser::Dialect::allowed_rules
+    pub fn dialect(mut self, dialect: crate::parser::Dialect) -> Self {
+        let allowed = dialect.allowed_rules().iter().map(|&r| r.to_owned());
+        self.allowed_rules.extend(allowed);
+        self
+    }
+
+    /// Returns `true` if the given clause is the conclusion that the proof is expected to reach.
+    fn is_expected_conclusion(&self, clause: &[Rc<Term>]) -> bool {
+        match &self.expected_conclusion {
+            None => clause.is_empty(),
+            Some(expected) => {
+                let expected: IndexSet<_> = expected.iter().collect();
+                expected == clause.iter().collect::<IndexSet<_>>()
+            }
+        }
+    }
+
+    /// Returns the error for a proof that does not reach its expected conclusion.
+    fn missing_conclusion_error(&self) -> Error {
+        match &self.expected_conclusion {
+            None => Error::DoesNotReachEmptyClause,
+            Some(_) => Error::DoesNotReachExpectedConclusion,
+        }
+    }
+
+    /// Returns an error listing every step whose rule is rejected by the `accepted_rules` and
+    /// `denied_rules` filters, if there are any.
+    fn check_rule_filters(&self, commands: ProofIter) -> CarcaraResult<()> {
+        if self.accepted_rules.is_none() && self.denied_rules.is_empty() {
+            return Ok(());
+        }
+        let is_rejected = |rule: &String| {
+            self.denied_rules.contains(rule)
+                || self
+                    .accepted_rules
+                    .as_ref()
+                    .is_some_and(|r| !r.contains(rule))
+        };
+        let rejected: Vec<_> = commands
+            .filter_map(|command| match command {
+                ProofCommand::Step(s) if is_rejected(&s.rule) => {
+                    Some((s.id.clone(), s.rule.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        if rejected.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::RejectedRules(rejected))
+        }
+    }
+
+    /// Returns `true` if the given step should be checked, according to the `only_steps` and
+    /// `only_rules` filters.
+    fn should_check_step(&self, step: &ProofStep) -> bool {
+        self.only_steps
+            .as_ref()
+            .map_or(true, |s| s.contains(&step.id))
+            && self
+                .only_rules
+                .as_ref()
+                .map_or(true, |r| r.contains(&step.rule))
+    }
 }
 
 pub struct ProofChecker<'c> {
     pool: &'c mut PrimitivePool,
     config: Config,
     context: ContextStack,
+    logic: Option<Logic>,
     reached_empty_clause: bool,
     is_holey: bool,
+
+    /// If this is `Some`, the steps that rely on a leniency of the checker are recorded here.
+    leniencies: Option<Vec<LenientStep>>,
+
+    /// If this is `Some`, the steps that are not checked are recorded here as proof obligations.
+    obligations: Option<Vec<obligations::ProofObligation>>,
 }
 
 impl<'c> ProofChecker<'c> {
@@ -90,8 +265,11 @@ impl<'c> ProofChecker<'c> {
             pool,
             config,
             context: ContextStack::new(),
+            logic: None,
             reached_empty_clause: false,
             is_holey: false,
+            leniencies: None,
+            obligations: None,
         }
     }
 
@@ -116,11 +294,27 @@ impl<'c> ProofChecker<'c> {
         &mut self,
         problem: &Problem,
         proof: &Proof,
-        mut stats: Option<&mut CheckerStatistics<CR>>,
+        stats: Option<&mut CheckerStatistics<CR>>,
     ) -> CarcaraResult<bool> {
+        self.config.check_rule_filters(proof.iter())?;
+        witnesses::check_witnesses(problem, proof)?;
+        self.logic = Logic::from_prelude(&self.config, &problem.prelude);
+        self.check_commands(problem, proof.iter(), stats)?;
+        if self.reached_empty_clause {
+            Ok(self.is_holey)
+        } else {
+            Err(self.config.missing_conclusion_error())
+        }
+    }
+
+    fn check_commands<CR: CollectResults + Send + Default>(
+        &mut self,
+        problem: &Problem,
+        mut iter: ProofIter,
+        mut stats: Option<&mut CheckerStatistics<CR>>,
+    ) -> CarcaraResult<()> {
         // Similarly to the parser, to avoid stack overflows in proofs with many nested subproofs,
         // we check the subproofs iteratively, instead of recursively
-        let mut iter = proof.iter();
         while let Some(command) = iter.next() {
             match command {
                 ProofCommand::Step(step) => {
@@ -137,11 +331,12 @@ impl<'c> ProofChecker<'c> {
                     } else {
                         None
                     };
-                    self.check_step(step, previous_command, &iter, &mut stats)
+                    self.check_step(step, previous_command, &problem.prelude, &iter, &mut stats)
                         .map_err(|e| Error::Checker {
                             inner: e,
                             rule: step.rule.clone(),
                             step: step.id.clone(),
+                            location: None,
                         })?;
 
                     // If this is the last command of a subproof, we have to pop the subproof
@@ -151,7 +346,7 @@ impl<'c> ProofChecker<'c> {
                         self.context.pop();
                     }
 
-                    if step.clause.is_empty() {
+                    if self.config.is_expected_conclusion(&step.clause) {
                         self.reached_empty_clause = true;
                     }
                 }
@@ -177,19 +372,19 @@ impl<'c> ProofChecker<'c> {
                 ProofCommand::Assume { id, term } => {
                     if !self.check_assume(id, term, &problem.premises, &iter, &mut stats) {
                         return Err(Error::Checker {
-                            inner: CheckerError::Assume(term.clone()),
+                            inner: CheckerError::Assume(
+                                term.clone(),
+                                assume_report::closest_premises(term, &problem.premises),
+                            ),
                             rule: "assume".into(),
                             step: id.clone(),
+                            location: None,
                         });
                     }
                 }
             }
         }
-        if self.reached_empty_clause {
-            Ok(self.is_holey)
-        } else {
-            Err(Error::DoesNotReachEmptyClause)
-        }
+        Ok(())
     }
 
     fn check_assume<'i, CR: CollectResults + Send + Default>(
@@ -247,8 +442,11 @@ impl<'c> ProofChecker<'c> {
             }
         }
         if !found {
+            tracing::trace!(id, "no premise matches assumption");
             return false;
         };
+        tracing::trace!(id, "assumption matches a premise up to reordering");
+        self.record_leniency(id, "assume", Leniency::ImplicitReordering);
 
         if let Some(s) = &mut stats {
             let time = time.elapsed();
@@ -267,9 +465,11 @@ impl<'c> ProofChecker<'c> {
         &mut self,
         step: &ProofStep,
         previous_command: Option<Premise>,
+        prelude: &ProblemPrelude,
         iter: &'i ProofIter<'i>,
         stats: &mut Option<&mut CheckerStatistics<CR>>,
     ) -> RuleResult {
+        let _span = tracing::trace_span!("step", id = %step.id, rule = %step.rule).entered();
         let time = Instant::now();
         let mut polyeq_time = Duration::ZERO;
 
@@ -277,19 +477,31 @@ impl<'c> ProofChecker<'c> {
             return Err(CheckerError::Subproof(SubproofError::DischargeInWrongRule));
         }
 
-        let rule = match Self::get_rule(&step.rule, self.config.elaborated) {
+        if !self.config.should_check_step(step) {
+            self.is_holey = true;
+            self.record_obligation(step, iter);
+            return Ok(());
+        }
+        if let Some(logic) = &self.logic {
+            logic.check_rule(&step.rule)?;
+        }
+
+        let rule = match Self::get_rule(&step.rule, &self.config) {
             Some(r) => r,
             None if self.config.ignore_unknown_rules
                 || self.config.allowed_rules.contains(&step.rule) =>
             {
                 self.is_holey = true;
+                self.record_leniency(&step.id, &step.rule, Leniency::UnknownRule);
+                self.record_obligation(step, iter);
                 return Ok(());
             }
             None => return Err(CheckerError::UnknownRule),
         };
 
-        if step.rule == "hole" || step.rule == "lia_generic" {
+        if is_hole(&step.rule, &step.args) {
             self.is_holey = true;
+            self.record_obligation(step, iter);
         }
 
         let premises: Vec<_> = step
@@ -306,18 +518,36 @@ impl<'c> ProofChecker<'c> {
             .map(|&i| iter.get_premise(i))
             .collect();
 
-        let rule_args = RuleArgs {
+        let rule_args: RuleArgs = RuleArgs {
             conclusion: &step.clause,
             premises: &premises,
             args: &step.args,
             pool: self.pool,
             context: &mut self.context,
+            prelude,
+            interpretations: &self.config.interpretations,
+            rare_rules: &self.config.rare_rules,
             previous_command,
             discharge: &discharge,
             polyeq_time: &mut polyeq_time,
         };
 
-        rule(rule_args)?;
+        if let Err(e) = rule.check(rule_args) {
+            tracing::trace!(error = %e, "step failed");
+            return Err(e);
+        }
+        tracing::trace!(elapsed = ?time.elapsed(), "step checked");
+
+        if step.rule == "evaluate" {
+            self.record_leniency(&step.id, &step.rule, Leniency::TrustedExtension);
+        }
+        if self.leniencies.is_some() {
+            if let Some(leniency) =
+                self.find_leniency(step, &premises, previous_command, &discharge, prelude)
+            {
+                self.record_leniency(&step.id, &step.rule, leniency);
+            }
+        }
 
         if iter.is_end_step() {
             let subproof = iter.current_subproof().unwrap();
@@ -353,150 +583,474 @@ impl<'c> ProofChecker<'c> {
         }
     }
 
-    pub fn get_rule(rule_name: &str, elaborated: bool) -> Option<Rule> {
+    // When adding a new rule here, remember to also add its metadata to `metadata::RULES`
+    pub fn get_rule(rule_name: &str, config: &Config) -> Option<Rule> {
         use rules::*;
 
+        let elaborated = config.elaborated;
+
         Some(match rule_name {
-            "true" => tautology::r#true,
-            "false" => tautology::r#false,
-            "not_not" => tautology::not_not,
-            "and_pos" => tautology::and_pos,
-            "and_neg" => tautology::and_neg,
-            "or_pos" => tautology::or_pos,
-            "or_neg" => tautology::or_neg,
-            "xor_pos1" => tautology::xor_pos1,
-            "xor_pos2" => tautology::xor_pos2,
-            "xor_neg1" => tautology::xor_neg1,
-            "xor_neg2" => tautology::xor_neg2,
-            "implies_pos" => tautology::implies_pos,
-            "implies_neg1" => tautology::implies_neg1,
-            "implies_neg2" => tautology::implies_neg2,
-            "equiv_pos1" => tautology::equiv_pos1,
-            "equiv_pos2" => tautology::equiv_pos2,
-            "equiv_neg1" => tautology::equiv_neg1,
-            "equiv_neg2" => tautology::equiv_neg2,
-            "ite_pos1" => tautology::ite_pos1,
-            "ite_pos2" => tautology::ite_pos2,
-            "ite_neg1" => tautology::ite_neg1,
-            "ite_neg2" => tautology::ite_neg2,
-            "eq_reflexive" => reflexivity::eq_reflexive,
-            "eq_transitive" => transitivity::eq_transitive,
-            "eq_congruent" => congruence::eq_congruent,
-            "eq_congruent_pred" => congruence::eq_congruent_pred,
-            "distinct_elim" => clausification::distinct_elim,
-            "la_rw_eq" => linear_arithmetic::la_rw_eq,
-            "la_generic" => linear_arithmetic::la_generic,
-            "la_disequality" => linear_arithmetic::la_disequality,
-            "la_totality" => linear_arithmetic::la_totality,
-            "la_tautology" => linear_arithmetic::la_tautology,
-            "forall_inst" => quantifier::forall_inst,
-            "qnt_join" => quantifier::qnt_join,
-            "qnt_rm_unused" => quantifier::qnt_rm_unused,
-            "resolution" | "th_resolution" if elaborated => resolution::resolution_with_args,
-            "resolution" | "th_resolution" => resolution::resolution,
-            "refl" if elaborated => reflexivity::strict_refl,
-            "refl" => reflexivity::refl,
-            "trans" => transitivity::trans,
-            "cong" => congruence::cong,
-            "ho_cong" => congruence::ho_cong,
-            "and" => clausification::and,
-            "tautology" => resolution::tautology,
-            "not_or" => clausification::not_or,
-            "or" => clausification::or,
-            "not_and" => clausification::not_and,
-            "xor1" => clausification::xor1,
-            "xor2" => clausification::xor2,
-            "not_xor1" => clausification::not_xor1,
-            "not_xor2" => clausification::not_xor2,
-            "implies" => clausification::implies,
-            "not_implies1" => clausification::not_implies1,
-            "not_implies2" => clausification::not_implies2,
-            "equiv1" => tautology::equiv1,
-            "equiv2" => tautology::equiv2,
-            "not_equiv1" => tautology::not_equiv1,
-            "not_equiv2" => tautology::not_equiv2,
-            "ite1" => tautology::ite1,
-            "ite2" => tautology::ite2,
-            "not_ite1" => tautology::not_ite1,
-            "not_ite2" => tautology::not_ite2,
-            "ite_intro" => tautology::ite_intro,
-            "contraction" => resolution::contraction,
-            "connective_def" => tautology::connective_def,
-            "ite_simplify" => simplification::ite_simplify,
-            "eq_simplify" => simplification::eq_simplify,
-            "and_simplify" => simplification::and_simplify,
-            "or_simplify" => simplification::or_simplify,
-            "not_simplify" => simplification::not_simplify,
-            "implies_simplify" => simplification::implies_simplify,
-            "equiv_simplify" => simplification::equiv_simplify,
-            "bool_simplify" => simplification::bool_simplify,
-            "qnt_simplify" => simplification::qnt_simplify,
-            "div_simplify" => simplification::div_simplify,
-            "prod_simplify" => simplification::prod_simplify,
+            "true" => Rule::ReadOnly(tautology::r#true),
+            "false" => Rule::ReadOnly(tautology::r#false),
+            "not_not" => Rule::ReadOnly(tautology::not_not),
+            "and_pos" => Rule::ReadOnly(tautology::and_pos),
+            "and_neg" => Rule::ReadOnly(tautology::and_neg),
+            "or_pos" => Rule::ReadOnly(tautology::or_pos),
+            "or_neg" => Rule::ReadOnly(tautology::or_neg),
+            "xor_pos1" => Rule::ReadOnly(tautology::xor_pos1),
+            "xor_pos2" => Rule::ReadOnly(tautology::xor_pos2),
+            "xor_neg1" => Rule::ReadOnly(tautology::xor_neg1),
+            "xor_neg2" => Rule::ReadOnly(tautology::xor_neg2),
+            "implies_pos" => Rule::ReadOnly(tautology::implies_pos),
+            "implies_neg1" => Rule::ReadOnly(tautology::implies_neg1),
+            "implies_neg2" => Rule::ReadOnly(tautology::implies_neg2),
+            "equiv_pos1" => Rule::ReadOnly(tautology::equiv_pos1),
+            "equiv_pos2" => Rule::ReadOnly(tautology::equiv_pos2),
+            "equiv_neg1" => Rule::ReadOnly(tautology::equiv_neg1),
+            "equiv_neg2" => Rule::ReadOnly(tautology::equiv_neg2),
+            "ite_pos1" => Rule::ReadOnly(tautology::ite_pos1),
+            "ite_pos2" => Rule::ReadOnly(tautology::ite_pos2),
+            "ite_neg1" => Rule::ReadOnly(tautology::ite_neg1),
+            "ite_neg2" => Rule::ReadOnly(tautology::ite_neg2),
+            "eq_reflexive" => Rule::ReadOnly(reflexivity::eq_reflexive),
+            "eq_transitive" => Rule::ReadOnly(transitivity::eq_transitive),
+            "eq_congruent" => Rule::ReadOnly(congruence::eq_congruent),
+            "eq_congruent_pred" => Rule::ReadOnly(congruence::eq_congruent_pred),
+            "distinct_elim" => Rule::Mutating(clausification::distinct_elim),
+            "la_rw_eq" => Rule::ReadOnly(linear_arithmetic::la_rw_eq),
+            "la_generic" => Rule::ReadOnly(linear_arithmetic::la_generic),
+            "lia_generic" => Rule::ReadOnly(linear_arithmetic::lia_generic),
+            "la_disequality" => Rule::ReadOnly(linear_arithmetic::la_disequality),
+            "la_totality" => Rule::ReadOnly(linear_arithmetic::la_totality),
+            "la_antisymmetry" => Rule::ReadOnly(linear_arithmetic::la_antisymmetry),
+            "la_tautology" => Rule::ReadOnly(linear_arithmetic::la_tautology),
+            "forall_inst" if config.allow_int_real_subtyping => {
+                Rule::Mutating(quantifier::forall_inst_with_subtyping)
+            }
+            "forall_inst" => Rule::Mutating(quantifier::forall_inst),
+            "exists_elim" => Rule::Mutating(quantifier::exists_elim),
+            "qnt_join" => Rule::ReadOnly(quantifier::qnt_join),
+            "qnt_rm_unused" => Rule::Mutating(quantifier::qnt_rm_unused),
+            "resolution" | "th_resolution" if elaborated => {
+                Rule::Mutating(resolution::resolution_with_args)
+            }
+            "resolution" | "th_resolution" => Rule::Mutating(resolution::resolution),
+            "refl" if elaborated => Rule::Mutating(reflexivity::strict_refl),
+            "refl" => Rule::Mutating(reflexivity::refl),
+            "trans" => Rule::ReadOnly(transitivity::trans),
+            "cong" => Rule::ReadOnly(congruence::cong),
+            "ho_cong" => Rule::ReadOnly(congruence::ho_cong),
+            "rewrite" => Rule::ReadOnly(congruence::rewrite),
+            "and" => Rule::ReadOnly(clausification::and),
+            "tautology" => Rule::ReadOnly(resolution::tautology),
+            "not_or" => Rule::ReadOnly(clausification::not_or),
+            "or" => Rule::ReadOnly(clausification::or),
+            "not_and" => Rule::ReadOnly(clausification::not_and),
+            "xor1" => Rule::ReadOnly(clausification::xor1),
+            "xor2" => Rule::ReadOnly(clausification::xor2),
+            "not_xor1" => Rule::ReadOnly(clausification::not_xor1),
+            "not_xor2" => Rule::ReadOnly(clausification::not_xor2),
+            "implies" => Rule::ReadOnly(clausification::implies),
+            "not_implies1" => Rule::ReadOnly(clausification::not_implies1),
+            "not_implies2" => Rule::ReadOnly(clausification::not_implies2),
+            "equiv1" => Rule::ReadOnly(tautology::equiv1),
+            "equiv2" => Rule::ReadOnly(tautology::equiv2),
+            "not_equiv1" => Rule::ReadOnly(tautology::not_equiv1),
+            "not_equiv2" => Rule::ReadOnly(tautology::not_equiv2),
+            "ite1" => Rule::ReadOnly(tautology::ite1),
+            "ite2" => Rule::ReadOnly(tautology::ite2),
+            "not_ite1" => Rule::ReadOnly(tautology::not_ite1),
+            "not_ite2" => Rule::ReadOnly(tautology::not_ite2),
+            "ite_intro" => Rule::ReadOnly(tautology::ite_intro),
+            "contraction" => Rule::ReadOnly(resolution::contraction),
+            "connective_def" => Rule::ReadOnly(tautology::connective_def),
+            "ite_simplify" => Rule::Mutating(simplification::ite_simplify),
+            "eq_simplify" => Rule::Mutating(simplification::eq_simplify),
+            "and_simplify" => Rule::Mutating(simplification::and_simplify),
+            "or_simplify" => Rule::Mutating(simplification::or_simplify),
+            "not_simplify" => Rule::Mutating(simplification::not_simplify),
+            "implies_simplify" => Rule::Mutating(simplification::implies_simplify),
+            "equiv_simplify" => Rule::Mutating(simplification::equiv_simplify),
+            "bool_simplify" => Rule::Mutating(simplification::bool_simplify),
+            "qnt_simplify" => Rule::ReadOnly(simplification::qnt_simplify),
+            "div_simplify" => Rule::ReadOnly(simplification::div_simplify),
+            "prod_simplify" => Rule::Mutating(simplification::prod_simplify),
             // Despite being separate rules in the specification, proofs generated by veriT don't
             // differentiate between `unary_minus_simplify` and `minus_simplify`. To account for
             // that, `simplification::minus_simplify` implements both rules in the same function.
-            "unary_minus_simplify" | "minus_simplify" => simplification::minus_simplify,
-            "sum_simplify" => simplification::sum_simplify,
-            "comp_simplify" => simplification::comp_simplify,
-            "nary_elim" => clausification::nary_elim,
-            "ac_simp" => simplification::ac_simp,
-            "bfun_elim" => clausification::bfun_elim,
-            "bind" => subproof::bind,
-            "qnt_cnf" => quantifier::qnt_cnf,
-            "subproof" => subproof::subproof,
-            "let" => subproof::r#let,
-            "onepoint" => subproof::onepoint,
-            "sko_ex" => subproof::sko_ex,
-            "sko_forall" => subproof::sko_forall,
-            "reordering" => extras::reordering,
-            "symm" => extras::symm,
-            "not_symm" => extras::not_symm,
-            "eq_symmetric" => extras::eq_symmetric,
-            "weakening" => extras::weakening,
-            "bind_let" => extras::bind_let,
-            "la_mult_pos" => extras::la_mult_pos,
-            "la_mult_neg" => extras::la_mult_neg,
-            "mod_simplify" => extras::mod_simplify,
-            "bitblast_extract" => bitvectors::extract,
-            "bitblast_bvadd" => bitvectors::add,
-            "bitblast_ult" => bitvectors::ult,
-
-            "concat_eq" => strings::concat_eq,
-            "concat_unify" => strings::concat_unify,
-            "concat_conflict" => strings::concat_conflict,
-            "concat_csplit_prefix" => strings::concat_csplit_prefix,
-            "concat_csplit_suffix" => strings::concat_csplit_suffix,
-            "concat_split_prefix" => strings::concat_split_prefix,
-            "concat_split_suffix" => strings::concat_split_suffix,
-            "concat_lprop_prefix" => strings::concat_lprop_prefix,
-            "concat_lprop_suffix" => strings::concat_lprop_suffix,
-            "concat_cprop_prefix" => strings::concat_cprop_prefix,
-            "concat_cprop_suffix" => strings::concat_cprop_suffix,
-
-            "string_decompose" => strings::string_decompose,
-            "string_length_pos" => strings::string_length_pos,
-            "string_length_non_empty" => strings::string_length_non_empty,
-
-            "re_inter" => strings::re_inter,
-            "re_unfold_neg" => strings::re_unfold_neg,
-            "re_unfold_neg_concat_fixed_prefix" => strings::re_unfold_neg_concat_fixed_prefix,
-            "re_unfold_neg_concat_fixed_suffix" => strings::re_unfold_neg_concat_fixed_suffix,
+            "unary_minus_simplify" | "minus_simplify" => {
+                Rule::ReadOnly(simplification::minus_simplify)
+            }
+            "sum_simplify" => Rule::Mutating(simplification::sum_simplify),
+            "comp_simplify" => Rule::Mutating(simplification::comp_simplify),
+            "nary_elim" => Rule::Mutating(clausification::nary_elim),
+            "nary_intro" => Rule::Mutating(clausification::nary_intro),
+            "ac_simp" => Rule::Mutating(simplification::ac_simp),
+            "bfun_elim" => Rule::Mutating(clausification::bfun_elim),
+            "bind" => Rule::Mutating(subproof::bind),
+            "qnt_cnf" => Rule::Mutating(quantifier::qnt_cnf),
+            "subproof" => Rule::Mutating(subproof::subproof),
+            "let" => Rule::Mutating(subproof::r#let),
+            "onepoint" => Rule::Mutating(subproof::onepoint),
+            "sko_ex" => Rule::Mutating(subproof::sko_ex),
+            "sko_forall" => Rule::Mutating(subproof::sko_forall),
+            "reordering" => Rule::ReadOnly(extras::reordering),
+            "symm" => Rule::ReadOnly(extras::symm),
+            "not_symm" => Rule::ReadOnly(extras::not_symm),
+            "eq_symmetric" => Rule::ReadOnly(extras::eq_symmetric),
+            "weakening" => Rule::ReadOnly(extras::weakening),
+            "bind_let" => Rule::ReadOnly(extras::bind_let),
+            "la_mult_pos" => Rule::ReadOnly(extras::la_mult_pos),
+            "la_mult_neg" => Rule::ReadOnly(extras::la_mult_neg),
+            "mod_simplify" => Rule::ReadOnly(extras::mod_simplify),
+            "evaluate" => Rule::Mutating(extras::evaluate),
+            "rare_rewrite" if !config.rare_rules.is_empty() => Rule::Mutating(extras::rare_rewrite),
+            "bitblast_extract" => Rule::Mutating(bitvectors::extract),
+            "bitblast_bvadd" => Rule::Mutating(bitvectors::add),
+            "bitblast_ult" => Rule::Mutating(bitvectors::ult),
+            "bitblast_var" => Rule::Mutating(bitvectors::var),
+            "bitblast_const" => Rule::Mutating(bitvectors::constant),
+            "bitblast_not" => Rule::Mutating(bitvectors::not),
+            "bitblast_and" => Rule::Mutating(bitvectors::and),
+            "bitblast_or" => Rule::Mutating(bitvectors::or),
+            "bitblast_xor" => Rule::Mutating(bitvectors::xor),
+            "bitblast_xnor" => Rule::Mutating(bitvectors::xnor),
+            "bitblast_equal" => Rule::Mutating(bitvectors::equal),
+            "bitblast_concat" => Rule::Mutating(bitvectors::concat),
+
+            "concat_eq" => Rule::Mutating(strings::concat_eq),
+            "concat_unify" => Rule::Mutating(strings::concat_unify),
+            "concat_conflict" => Rule::Mutating(strings::concat_conflict),
+            "concat_csplit_prefix" => Rule::Mutating(strings::concat_csplit_prefix),
+            "concat_csplit_suffix" => Rule::Mutating(strings::concat_csplit_suffix),
+            "concat_split_prefix" => Rule::Mutating(strings::concat_split_prefix),
+            "concat_split_suffix" => Rule::Mutating(strings::concat_split_suffix),
+            "concat_lprop_prefix" => Rule::Mutating(strings::concat_lprop_prefix),
+            "concat_lprop_suffix" => Rule::Mutating(strings::concat_lprop_suffix),
+            "concat_cprop_prefix" => Rule::Mutating(strings::concat_cprop_prefix),
+            "concat_cprop_suffix" => Rule::Mutating(strings::concat_cprop_suffix),
+
+            "string_decompose" => Rule::Mutating(strings::string_decompose),
+            "string_length_pos" => Rule::ReadOnly(strings::string_length_pos),
+            "string_length_non_empty" => Rule::ReadOnly(strings::string_length_non_empty),
+            "string_code_inj" => Rule::ReadOnly(strings::string_code_inj),
+            "string_eager_reduction" => Rule::ReadOnly(strings::string_eager_reduction),
+
+            "row1" => Rule::ReadOnly(arrays::row1),
+            "row2" => Rule::ReadOnly(arrays::row2),
+            "row_contra" => Rule::ReadOnly(arrays::row_contra),
+            "ext" => Rule::Mutating(arrays::ext),
+
+            "dt_split" => Rule::ReadOnly(datatypes::dt_split),
+            "dt_inst" => Rule::Mutating(datatypes::dt_inst),
+            "dt_collapse" => Rule::ReadOnly(datatypes::dt_collapse),
+            "dt_clash" => Rule::ReadOnly(datatypes::dt_clash),
+
+            "re_inter" => Rule::ReadOnly(strings::re_inter),
+            "re_unfold_neg" => Rule::Mutating(strings::re_unfold_neg),
+            "re_unfold_neg_concat_fixed_prefix" => {
+                Rule::Mutating(strings::re_unfold_neg_concat_fixed_prefix)
+            }
+            "re_unfold_neg_concat_fixed_suffix" => {
+                Rule::Mutating(strings::re_unfold_neg_concat_fixed_suffix)
+            }
 
             // Special rules that always check as valid, and are used to indicate holes in the
             // proof.
-            "hole" => |_| Ok(()),
-            "lia_generic" => |_| {
-                log::warn!("encountered \"lia_generic\" rule, ignoring");
+            "hole" => Rule::ReadOnly(|_| Ok(())),
+            "nla_generic" => Rule::ReadOnly(|_| {
+                tracing::warn!("encountered \"nla_generic\" rule, ignoring");
                 Ok(())
-            },
+            }),
 
             // The Alethe specification does not yet describe how this more strict version of the
             // resolution rule will be called. Until that is decided and added to the specification,
             // we define a new specialized rule that calls it
-            "strict_resolution" => resolution::strict_resolution,
+            "strict_resolution" => Rule::Mutating(resolution::strict_resolution),
 
             _ => return None,
         })
     }
 }
+
+/// Returns `true` if a step with the given rule and arguments is a hole, that is, if it is
+/// accepted without being checked.
+fn is_hole(rule: &str, args: &[Rc<Term>]) -> bool {
+    match rule {
+        "hole" | "nla_generic" => true,
+        "lia_generic" => args.is_empty(),
+        _ => false,
+    }
+}
+
+/// Checks a single inference in isolation, without the context of a whole proof. The premises are
+/// given by their clauses, and the terms must have been added to `pool`.
+///
+/// Since there is no enclosing proof, rules that close a subproof, or that depend on the context
+/// introduced by an `anchor`, can't be checked with this function. Similarly, the witnesses of an
+/// `exists_elim` step are only checked against its premise, since their freshness depends on the
+/// rest of the proof. If the rule is not known, this returns `CheckerError::UnknownRule`.
+///
+/// Like [`ProofChecker::check`], this returns `true` if the step is a hole, that is, if it uses a
+/// rule like `hole` or `lia_generic` (without arguments) that is accepted without being checked.
+/// Callers that must ensure the step was actually checked should reject that result.
+pub fn check_step(
+    pool: &mut dyn TermPool,
+    config: &Config,
+    premises: &[&[Rc<Term>]],
+    rule_name: &str,
+    args: &[Rc<Term>],
+    conclusion: &[Rc<Term>],
+) -> Result<bool, CheckerError> {
+    let rule = ProofChecker::get_rule(rule_name, config).ok_or(CheckerError::UnknownRule)?;
+    let ids: Vec<_> = (0..premises.len()).map(|i| format!("p{}", i)).collect();
+    let premises: Vec<_> = premises
+        .iter()
+        .enumerate()
+        .map(|(i, &clause)| Premise { id: &ids[i], clause, index: (0, i) })
+        .collect();
+    let mut polyeq_time = Duration::ZERO;
+    let rule_args: RuleArgs = RuleArgs {
+        conclusion,
+        premises: &premises,
+        args,
+        pool,
+        context: &mut ContextStack::new(),
+        prelude: &ProblemPrelude::new(),
+        interpretations: &config.interpretations,
+        rare_rules: &config.rare_rules,
+        previous_command: None,
+        discharge: &[],
+        polyeq_time: &mut polyeq_time,
+    };
+    rule.check(rule_args)?;
+    Ok(is_hole(rule_name, args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_step_filters() {
+        let problem: &[u8] = b"
+            (declare-const a Bool)
+            (assert a)
+            (assert (not a))
+        ";
+        let proof: &[u8] = b"
+            (assume h1 a)
+            (assume h2 (not a))
+            (step t1 (cl (= a a)) :rule refl)
+            (step t2 (cl a) :rule not_not :premises (h1))
+            (step t3 (cl) :rule resolution :premises (h1 h2))
+        ";
+        let run = |config: Config| {
+            let (problem, proof, mut pool) =
+                parser::parse_instance(problem, proof, parser::Config::new()).unwrap();
+            ProofChecker::new(&mut pool, config).check(&problem, &proof)
+        };
+        let set = |items: &[&str]| Some(items.iter().map(|&s| s.to_owned()).collect());
+
+        assert!(run(Config::new()).is_err());
+        assert!(run(Config::new().only_steps(set(&["t1", "t3"]))).unwrap());
+        assert!(run(Config::new().only_steps(set(&["t2"]))).is_err());
+        assert!(run(Config::new().only_rules(set(&["refl", "resolution"]))).unwrap());
+        assert!(run(Config::new().only_rules(set(&["not_not"]))).is_err());
+
+        let config = Config::new()
+            .only_steps(set(&["t1", "t2"]))
+            .only_rules(set(&["refl"]));
+        assert!(run(config).unwrap());
+    }
+
+    #[test]
+    fn test_rule_filters() {
+        let problem: &[u8] = b"
+            (declare-const a Bool)
+            (assert a)
+            (assert (not a))
+        ";
+        let proof: &[u8] = b"
+            (assume h1 a)
+            (assume h2 (not a))
+            (step t3 (cl a) :rule hole)
+            (anchor :step t4)
+            (step t4.t1 (cl (= a a)) :rule trust)
+            (step t4 (cl (= a a)) :rule bind)
+            (step t5 (cl) :rule resolution :premises (h1 h2))
+        ";
+        let run = |config: Config| {
+            let (problem, proof, mut pool) =
+                parser::parse_instance(problem, proof, parser::Config::new()).unwrap();
+            ProofChecker::new(&mut pool, config).check(&problem, &proof)
+        };
+        let set = |items: &[&str]| items.iter().map(|&s| s.to_owned()).collect();
+        let rejected = |result: CarcaraResult<bool>| match result {
+            Err(Error::RejectedRules(steps)) => steps,
+            other => panic!("expected rejected rules, got {:?}", other),
+        };
+        let pair = |step: &str, rule: &str| (step.to_owned(), rule.to_owned());
+
+        assert_eq!(
+            rejected(run(Config::new().denied_rules(set(&["hole", "trust"])))),
+            [pair("t3", "hole"), pair("t4.t1", "trust")]
+        );
+        assert_eq!(
+            rejected(run(
+                Config::new().accepted_rules(Some(set(&["hole", "trust"])))
+            )),
+            [pair("t4", "bind"), pair("t5", "resolution")]
+        );
+        let config = Config::new()
+            .accepted_rules(Some(set(&["hole", "resolution", "bind", "trust"])))
+            .denied_rules(set(&["trust"]));
+        assert_eq!(rejected(run(config)), [pair("t4.t1", "trust")]);
+
+        // Steps are only rejected because of these filters if some filter is given
+        assert!(!matches!(run(Config::new()), Err(Error::RejectedRules(_))));
+    }
+
+    #[test]
+    fn test_interpretations() {
+        use interpretations::Interpretations;
+
+        let problem: &[u8] = b"
+            (declare-fun hash (Int) Int)
+            (declare-fun g (Int) Int)
+            (declare-const x Int)
+            (assert (not (= (hash 21) 42)))
+        ";
+        let run = |proof: &str, interpretations: Interpretations| {
+            let (problem, proof, mut pool) =
+                parser::parse_instance(problem, proof.as_bytes(), parser::Config::new()).unwrap();
+            let config = Config::new().interpretations(interpretations);
+            ProofChecker::new(&mut pool, config).check_leniencies(&problem, &proof)
+        };
+        let interpretations = Interpretations::new()
+            .register("hash", |pool, args| {
+                let n = args[0].as_integer()?;
+                Some(pool.add(Term::new_int(n * 2)))
+            })
+            .register("g", |_, _| panic!("not implemented"));
+
+        let proof = "
+            (assume h1 (not (= (hash 21) 42)))
+            (step t2 (cl (= (hash 21) 42)) :rule evaluate)
+            (step t3 (cl) :rule resolution :premises (h1 t2))
+        ";
+        let (is_holey, report) = run(proof, interpretations.clone()).unwrap();
+        assert!(!is_holey);
+        assert_eq!(report.count(Leniency::TrustedExtension), 1);
+        assert!(run(proof, Interpretations::new()).is_err());
+
+        for conclusion in [
+            "(= (hash 21) 41)",
+            "(= (hash x) 42)",
+            "(= (g 1) 2)",
+            "(= (+ 21 21) 42)",
+        ] {
+            let proof = format!("(step t1 (cl {}) :rule evaluate)", conclusion);
+            assert!(run(&proof, interpretations.clone()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_check_step() {
+        let mut pool = PrimitivePool::new();
+        let [p, not_p, q] = parser::tests::parse_terms(
+            &mut pool,
+            "(declare-const p Bool) (declare-const q Bool)",
+            ["p", "(not p)", "q"],
+        );
+        let config = Config::new();
+        let premises: [&[_]; 2] = [&[p.clone(), q.clone()], std::slice::from_ref(&not_p)];
+        assert!(matches!(
+            check_step(
+                &mut pool,
+                &config,
+                &premises,
+                "resolution",
+                &[],
+                std::slice::from_ref(&q)
+            ),
+            Ok(false),
+        ));
+        assert!(check_step(
+            &mut pool,
+            &config,
+            &premises,
+            "resolution",
+            &[],
+            std::slice::from_ref(&p)
+        )
+        .is_err());
+        assert!(check_step(&mut pool, &config, &[], "not_not", &[], &[p]).is_err());
+        assert!(matches!(
+            check_step(
+                &mut pool,
+                &config,
+                &[],
+                "foo",
+                &[],
+                std::slice::from_ref(&q)
+            ),
+            Err(CheckerError::UnknownRule),
+        ));
+
+        // Holes are accepted, but reported as such
+        assert!(matches!(
+            check_step(
+                &mut pool,
+                &config,
+                &[],
+                "hole",
+                &[],
+                std::slice::from_ref(&q)
+            ),
+            Ok(true),
+        ));
+        assert!(matches!(
+            check_step(&mut pool, &config, &[], "lia_generic", &[], &[q]),
+            Ok(true),
+        ));
+    }
+
+    #[test]
+    fn test_explanations() {
+        let mut pool = PrimitivePool::new();
+        let [p, q, r, true_] = parser::tests::parse_terms(
+            &mut pool,
+            "(declare-const p Bool) (declare-const q Bool) (declare-const r Bool)",
+            ["p", "q", "r", "true"],
+        );
+        let config = Config::new();
+        let premises: [&[_]; 2] = [std::slice::from_ref(&p), &[q.clone(), r.clone()]];
+        let args = [p.clone(), true_];
+        let err = check_step(
+            &mut pool,
+            &config,
+            &premises,
+            "resolution",
+            &args,
+            std::slice::from_ref(&r),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.cause(),
+            CheckerError::Resolution(crate::resolution::ResolutionError::PivotNotFound(_)),
+        ));
+        let explanation = err.explanation().unwrap();
+        assert_eq!(
+            explanation.reasons,
+            ["pivot '(not p)' not found in right premise 'p1'"]
+        );
+        assert_eq!(explanation.terms.len(), 1);
+        assert_eq!(explanation.terms[0].terms, [q, r]);
+
+        // Errors that don't come with an explanation are left unchanged
+        let err = check_step(&mut pool, &config, &[], "refl", &[], &[p]).unwrap_err();
+        assert!(err.explanation().is_none());
+    }
+}
@@ -0,0 +1,227 @@
+//! Reports of the proof obligations left by a proof, that is, the steps that were not checked and
+//! were instead assumed to hold. These include `hole` steps, steps that use unknown rules, and
+//! steps skipped because of the `only_steps` and `only_rules` filters. Each obligation can be
+//! discharged externally, for example by giving its SMT-LIB instance to a solver.
+
+use super::*;
+
+/// A step that was not checked, and whose clause must be shown to hold by other means.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofObligation {
+    pub id: String,
+    pub rule: String,
+    pub clause: Vec<Rc<Term>>,
+
+    /// The clauses of the step's premises, which may be assumed when discharging the obligation.
+    pub premises: Vec<Vec<Rc<Term>>>,
+
+    /// The subproof depth of the step. Obligations inside subproofs may refer to variables
+    /// introduced by their anchors, so they can't always be stated on their own.
+    pub depth: usize,
+}
+
+impl ProofObligation {
+    /// Returns the goal of this obligation as a single term, that is, the disjunction of the
+    /// literals in its clause.
+    pub fn goal(&self, pool: &mut dyn TermPool) -> Rc<Term> {
+        clause_to_term(pool, &self.clause)
+    }
+
+    /// Returns an SMT-LIB instance that is unsatisfiable if and only if this obligation holds, or
+    /// returns `None` if the obligation is inside a subproof, since its clause may then
+    /// refer to variables that are not declared in the problem. The instance asserts each of the
+    /// step's premises, and the negation of each literal in its clause.
+    #[cfg(feature = "smt-printer")]
+    pub fn smt_instance(
+        &self,
+        pool: &mut PrimitivePool,
+        prelude: &ProblemPrelude,
+        use_sharing: bool,
+    ) -> Option<String> {
+        if self.depth > 0 {
+            return None;
+        }
+        let mut assertions: Vec<_> = self
+            .premises
+            .iter()
+            .map(|clause| clause_to_term(pool, clause))
+            .collect();
+        assertions.extend(
+            self.clause
+                .iter()
+                .map(|literal| pool.add(Term::Op(Operator::Not, vec![literal.clone()]))),
+        );
+        let mut bytes = prelude.to_string().into_bytes();
+        printer::write_smt_assertions(pool, prelude, &mut bytes, &assertions, use_sharing).unwrap();
+        bytes.extend_from_slice(b"(check-sat)\n(exit)\n");
+        Some(String::from_utf8(bytes).unwrap())
+    }
+}
+
+/// Returns the disjunction of the literals in a clause, as a single term.
+fn clause_to_term(pool: &mut dyn TermPool, clause: &[Rc<Term>]) -> Rc<Term> {
+    match clause {
+        [] => pool.bool_false(),
+        [literal] => literal.clone(),
+        literals => pool.add(Term::Op(Operator::Or, literals.to_vec())),
+    }
+}
+
+/// All proof obligations left by a proof, in the order they were checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObligationReport {
+    pub obligations: Vec<ProofObligation>,
+}
+
+impl ObligationReport {
+    /// Returns `true` if every step in the proof was checked.
+    pub fn is_empty(&self) -> bool {
+        self.obligations.is_empty()
+    }
+}
+
+impl fmt::Display for ObligationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no proof obligations");
+        }
+        writeln!(f, "{} proof obligations:", self.obligations.len())?;
+        // The goals are built in a fresh pool, since they are only used for printing
+        let mut pool = PrimitivePool::new();
+        for o in &self.obligations {
+            writeln!(f, "    {} ({}): {}", o.id, o.rule, o.goal(&mut pool))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'c> ProofChecker<'c> {
+    /// Checks a proof, treating every step that is not checked as an assumption, and records these
+    /// steps as proof obligations. Unknown rules are always ignored in this mode.
+    pub fn check_obligations(
+        &mut self,
+        problem: &Problem,
+        proof: &Proof,
+    ) -> CarcaraResult<(bool, ObligationReport)> {
+        self.config.ignore_unknown_rules = true;
+        self.obligations = Some(Vec::new());
+        let result = self.check(problem, proof);
+        let obligations = self.obligations.take().unwrap();
+        Ok((result?, ObligationReport { obligations }))
+    }
+
+    pub(super) fn record_obligation(&mut self, step: &ProofStep, iter: &ProofIter) {
+        if let Some(obligations) = &mut self.obligations {
+            // The step that ends a subproof is part of the enclosing scope
+            let depth = iter.depth() - usize::from(iter.is_end_step());
+            obligations.push(ProofObligation {
+                id: step.id.clone(),
+                rule: step.rule.clone(),
+                clause: step.clause.clone(),
+                premises: step
+                    .premises
+                    .iter()
+                    .map(|&p| iter.get_premise(p).clause().to_vec())
+                    .collect(),
+                depth,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_obligation_report() {
+        let problem = "
+            (declare-const a Bool)
+            (declare-const b Bool)
+            (assert (not a))
+            (assert (not b))
+        ";
+        let proof = "
+            (assume h1 (not a))
+            (assume h2 (not b))
+            (step t3 (cl a b) :rule hole)
+            (step t4 (cl a) :rule resolution :premises (t3 h2))
+            (anchor :step t5 :args ((x Bool)))
+            (step t5.t1 (cl (= x x)) :rule trust_me)
+            (step t5 (cl (= (forall ((x Bool)) x) (forall ((x Bool)) x))) :rule bind)
+            (step t6 (cl) :rule resolution :premises (t4 h1))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let (is_holey, report) = ProofChecker::new(&mut pool, Config::new())
+            .check_obligations(&problem, &proof)
+            .unwrap();
+        assert!(is_holey);
+
+        let got: Vec<_> = report
+            .obligations
+            .iter()
+            .map(|o| (o.id.as_str(), o.rule.as_str(), o.depth))
+            .collect();
+        assert_eq!(got, [("t3", "hole", 0), ("t5.t1", "trust_me", 1)]);
+        assert_eq!(
+            report.to_string(),
+            "2 proof obligations:\n    t3 (hole): (or a b)\n    t5.t1 (trust_me): (= x x)\n"
+        );
+
+        #[cfg(feature = "smt-printer")]
+        {
+            let instance = report.obligations[0]
+                .smt_instance(&mut pool, &problem.prelude, false)
+                .unwrap();
+            assert!(instance.contains("(assert (not a))\n(assert (not b))\n(check-sat)"));
+            assert_eq!(
+                report.obligations[1].smt_instance(&mut pool, &problem.prelude, false),
+                None
+            );
+        }
+    }
+    #[test]
+    fn test_obligation_premises() {
+        let problem = "
+            (declare-const a Bool)
+            (declare-const b Bool)
+            (assert (or a b))
+            (assert (not a))
+            (assert (not b))
+        ";
+        let proof = "
+            (assume h1 (or a b))
+            (assume h2 (not a))
+            (assume h3 (not b))
+            (step t4 (cl b) :rule hole :premises (h1 h2))
+            (step t5 (cl) :rule resolution :premises (t4 h3))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let (_, report) = ProofChecker::new(&mut pool, Config::new())
+            .check_obligations(&problem, &proof)
+            .unwrap();
+
+        let [obligation] = report.obligations.as_slice() else {
+            panic!("expected exactly one obligation");
+        };
+        let premises: Vec<Vec<_>> = obligation
+            .premises
+            .iter()
+            .map(|c| c.iter().map(ToString::to_string).collect())
+            .collect();
+        assert_eq!(premises, [["(or a b)"], ["(not a)"]]);
+
+        #[cfg(feature = "smt-printer")]
+        {
+            let instance = obligation
+                .smt_instance(&mut pool, &problem.prelude, false)
+                .unwrap();
+            assert!(instance.contains("(assert (or a b))\n(assert (not a))\n(assert (not b))\n"));
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::ast::ProofCommand;
+use std::{collections::HashMap, io};
+use thiserror::Error;
+
+/// The default cost model, serialized from the benchmark data used in [Carcara's
+/// paper](https://hanielbarbosa.com/papers/tacas2023.pdf).
+const DEFAULT_COST_MODEL: &str = include_str!("default_cost_model.txt");
+
+#[derive(Debug, Error)]
+pub enum CostModelError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid cost model entry at line {0}: '{1}'")]
+    InvalidEntry(usize, String),
+}
+
+/// A cost model, used by the parallel checker to estimate the cost of checking each step, and
+/// balance the work between threads accordingly.
+///
+/// A cost model is read from a text file, where each line has the form `<rule> <weight>`. The
+/// weight of `assume` commands is given by the `assume` entry, and the weight of every rule that
+/// is not listed is given by the `*` entry, or zero if there is no such entry. Empty lines and
+/// lines starting with `#` are ignored. Subproofs themselves always have a weight of zero, since
+/// their cost is already accounted for by their steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostModel {
+    weights: HashMap<String, u64>,
+    default_weight: u64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self::from_str(DEFAULT_COST_MODEL).expect("default cost model is invalid")
+    }
+}
+
+impl CostModel {
+    /// Returns the default cost model.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a cost model from a reader.
+    pub fn from_reader<R: io::Read>(mut reader: R) -> Result<Self, CostModelError> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        Self::from_str(&contents)
+    }
+
+    fn from_str(contents: &str) -> Result<Self, CostModelError> {
+        let mut weights = HashMap::new();
+        let mut default_weight = 0;
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || CostModelError::InvalidEntry(i + 1, line.to_owned());
+            let mut parts = line.split_whitespace();
+            let (Some(rule), Some(weight), None) = (parts.next(), parts.next(), parts.next())
+            else {
+                return Err(invalid());
+            };
+            let weight: u64 = weight.parse().map_err(|_| invalid())?;
+            if rule == "*" {
+                default_weight = weight;
+            } else {
+                weights.insert(rule.to_owned(), weight);
+            }
+        }
+        Ok(Self { weights, default_weight })
+    }
+
+    /// Returns the weight of a rule in this cost model.
+    pub fn rule_weight(&self, rule: &str) -> u64 {
+        self.weights
+            .get(rule)
+            .copied()
+            .unwrap_or(self.default_weight)
+    }
+
+    /// Returns the estimated cost of checking a command.
+    pub fn step_weight(&self, command: &ProofCommand) -> u64 {
+        match command {
+            ProofCommand::Assume { .. } => self.rule_weight("assume"),
+            ProofCommand::Subproof(_) => 0,
+            ProofCommand::Step(s) => self.rule_weight(&s.rule),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_model() {
+        let default = CostModel::new();
+        assert_eq!(230, default.rule_weight("assume"));
+        assert_eq!(0, default.rule_weight("not_a_rule"));
+
+        let custom = "
+            # a comment
+            resolution 100
+            *          7
+        ";
+        let custom = CostModel::from_reader(custom.as_bytes()).unwrap();
+        assert_eq!(100, custom.rule_weight("resolution"));
+        assert_eq!(7, custom.rule_weight("assume"));
+        assert_eq!(7, custom.rule_weight("la_generic"));
+
+        for invalid in ["resolution", "resolution 1 2", "resolution -1"] {
+            assert!(CostModel::from_reader(invalid.as_bytes()).is_err());
+        }
+    }
+}
@@ -1,23 +1,29 @@
+mod cost_model;
 pub mod scheduler;
 
 use super::{
+    assume_report,
     error::{CheckerError, SubproofError},
-    rules::{Premise, RuleArgs, RuleResult},
-    Config, ProofChecker,
+    is_hole,
+    logic::Logic,
+    rules::{Premise, Rule, RuleArgs, RuleResult},
+    witnesses, Config, ProofChecker,
 };
 use crate::benchmarking::{CollectResults, OnlineBenchmarkResults};
 use crate::checker::CheckerStatistics;
 use crate::{
     ast::{pool::advanced::*, *},
+    utils::Instant,
     CarcaraResult, Error,
 };
+pub use cost_model::{CostModel, CostModelError};
 use indexmap::IndexSet;
 pub use scheduler::{Schedule, ScheduleIter, Scheduler};
 use std::{
     ops::ControlFlow,
     sync::{atomic::AtomicBool, Arc},
     thread,
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 pub struct ParallelProofChecker<'c> {
@@ -25,6 +31,7 @@ pub struct ParallelProofChecker<'c> {
     config: Config,
     prelude: &'c ProblemPrelude,
     context: ContextStack,
+    logic: Option<Logic>,
     reached_empty_clause: bool,
     is_holey: bool,
     stack_size: usize,
@@ -39,6 +46,7 @@ impl<'c> ParallelProofChecker<'c> {
         stack_size: usize,
     ) -> Self {
         ParallelProofChecker {
+            logic: Logic::from_prelude(&config, prelude),
             pool,
             config,
             prelude,
@@ -56,6 +64,7 @@ impl<'c> ParallelProofChecker<'c> {
             config: self.config.clone(),
             prelude: self.prelude,
             context: ContextStack::from_previous(&self.context),
+            logic: self.logic.clone(),
             reached_empty_clause: false,
             is_holey: false,
             stack_size: self.stack_size,
@@ -68,6 +77,9 @@ impl<'c> ParallelProofChecker<'c> {
         proof: &Proof,
         scheduler: &Scheduler,
     ) -> CarcaraResult<bool> {
+        self.config.check_rule_filters(proof.iter())?;
+        witnesses::check_witnesses(problem, proof)?;
+
         // Used to estimulate threads to abort prematurely (only happens when a
         // thread already found out an invalid step)
         let premature_abort = Arc::new(AtomicBool::new(false));
@@ -129,7 +141,7 @@ impl<'c> ParallelProofChecker<'c> {
             if reached {
                 Ok(holey)
             } else {
-                Err(Error::DoesNotReachEmptyClause)
+                Err(self.config.missing_conclusion_error())
             }
         })
     }
@@ -141,6 +153,9 @@ impl<'c> ParallelProofChecker<'c> {
         scheduler: &Scheduler,
         stats: &mut CheckerStatistics<CR>,
     ) -> CarcaraResult<bool> {
+        self.config.check_rule_filters(proof.iter())?;
+        witnesses::check_witnesses(problem, proof)?;
+
         // Used to estimulate threads to abort prematurely (only happens when a
         // thread already found out an invalid step)
         let premature_abort = Arc::new(AtomicBool::new(false));
@@ -227,7 +242,7 @@ impl<'c> ParallelProofChecker<'c> {
             if reached {
                 Ok(holey)
             } else {
-                Err(Error::DoesNotReachEmptyClause)
+                Err(self.config.missing_conclusion_error())
             }
         })
     }
@@ -274,18 +289,26 @@ impl<'c> ParallelProofChecker<'c> {
                         None
                     };
 
-                    self.check_step(step, previous_command, &iter, &mut pool, &mut stats)
-                        .map_err(|e| {
-                            // Signalize to other threads to stop the proof checking
-                            should_abort.store(true, Ordering::Release);
-                            Error::Checker {
-                                inner: e,
-                                rule: step.rule.clone(),
-                                step: step.id.clone(),
-                            }
-                        })?;
+                    self.check_step(
+                        step,
+                        previous_command,
+                        &problem.prelude,
+                        &iter,
+                        &mut pool,
+                        &mut stats,
+                    )
+                    .map_err(|e| {
+                        // Signalize to other threads to stop the proof checking
+                        should_abort.store(true, Ordering::Release);
+                        Error::Checker {
+                            inner: e,
+                            rule: step.rule.clone(),
+                            step: step.id.clone(),
+                            location: None,
+                        }
+                    })?;
 
-                    if step.clause.is_empty() {
+                    if self.config.is_expected_conclusion(&step.clause) {
                         self.reached_empty_clause = true;
                     }
                 }
@@ -316,9 +339,13 @@ impl<'c> ParallelProofChecker<'c> {
                         // Signalize to other threads to stop the proof checking
                         should_abort.store(true, Ordering::Release);
                         return Err(Error::Checker {
-                            inner: CheckerError::Assume(term.clone()),
+                            inner: CheckerError::Assume(
+                                term.clone(),
+                                assume_report::closest_premises(term, &problem.premises),
+                            ),
                             rule: "assume".into(),
                             step: id.clone(),
+                            location: None,
                         });
                     }
                 }
@@ -410,10 +437,12 @@ impl<'c> ParallelProofChecker<'c> {
         &mut self,
         step: &ProofStep,
         previous_command: Option<Premise>,
+        prelude: &ProblemPrelude,
         iter: &ScheduleIter,
         pool: &mut LocalPool,
         stats: &mut Option<&mut CheckerStatistics<CR>>,
     ) -> RuleResult {
+        let _span = tracing::trace_span!("step", id = %step.id, rule = %step.rule).entered();
         let time = Instant::now();
         let mut polyeq_time = Duration::ZERO;
 
@@ -421,7 +450,15 @@ impl<'c> ParallelProofChecker<'c> {
             return Err(CheckerError::Subproof(SubproofError::DischargeInWrongRule));
         }
 
-        let rule = match ProofChecker::get_rule(&step.rule, self.config.elaborated) {
+        if !self.config.should_check_step(step) {
+            self.is_holey = true;
+            return Ok(());
+        }
+        if let Some(logic) = &self.logic {
+            logic.check_rule(&step.rule)?;
+        }
+
+        let rule = match ProofChecker::get_rule(&step.rule, &self.config) {
             Some(r) => r,
             None if self.config.ignore_unknown_rules => {
                 self.is_holey = true;
@@ -430,7 +467,7 @@ impl<'c> ParallelProofChecker<'c> {
             None => return Err(CheckerError::UnknownRule),
         };
 
-        if step.rule == "hole" || step.rule == "lia_generic" {
+        if is_hole(&step.rule, &step.args) {
             self.is_holey = true;
         }
 
@@ -452,14 +489,27 @@ impl<'c> ParallelProofChecker<'c> {
             conclusion: &step.clause,
             premises: &premises,
             args: &step.args,
-            pool,
+            pool: (),
             context: &mut self.context,
+            prelude,
+            interpretations: &self.config.interpretations,
+            rare_rules: &self.config.rare_rules,
             previous_command,
             discharge: &discharge,
             polyeq_time: &mut polyeq_time,
         };
 
-        rule(rule_args)?;
+        // Read-only rules only get a shared reference to the local pool, so they can never take
+        // the write lock of the context pool
+        let result = match rule {
+            Rule::ReadOnly(rule) => rule(rule_args.map_pool(|()| &*pool as &dyn TermPoolView)),
+            Rule::Mutating(rule) => rule(rule_args.map_pool(|()| pool as &mut dyn TermPool)),
+        };
+        if let Err(e) = result {
+            tracing::trace!(error = %e, "step failed");
+            return Err(e);
+        }
+        tracing::trace!(elapsed = ?time.elapsed(), "step checked");
 
         if iter.is_end_step() {
             let subproof = iter.current_subproof().unwrap();
@@ -0,0 +1,341 @@
+//! Rewrite rules written in RARE, the rule language used by cvc5. cvc5 justifies many rewrite steps
+//! with `rare_rewrite` steps, whose first argument is the name of a RARE rule, and whose remaining
+//! arguments are the terms that instantiate the rule's parameters. Given a database of these rules,
+//! the checker verifies each such step by instantiating the rule and comparing it with the step's
+//! conclusion. The rule database is parsed from a file of rule definitions by
+//! [`parse_rare_rules`](crate::parser::parse_rare_rules).
+
+use super::error::CheckerError;
+use crate::ast::*;
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+/// A parameter of a RARE rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RareParam {
+    pub name: String,
+
+    /// If `true`, this parameter is instantiated with a list of terms, given as a `rare-list` term,
+    /// which is spliced into the arguments of the operation where the parameter occurs.
+    pub is_list: bool,
+}
+
+/// A RARE rule, stating that `lhs` can be rewritten to `rhs`, if all the conditions hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RareRule {
+    pub name: String,
+    pub params: Vec<RareParam>,
+    pub conditions: Vec<Rc<Term>>,
+    pub lhs: Rc<Term>,
+    pub rhs: Rc<Term>,
+}
+
+/// An instance of a RARE rule, where every parameter was replaced by its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RareInstance {
+    pub conditions: Vec<Rc<Term>>,
+    pub lhs: Rc<Term>,
+    pub rhs: Rc<Term>,
+}
+
+/// The value of a parameter in an instantiation of a rule.
+enum Binding<'a> {
+    Term(&'a Rc<Term>),
+    List(&'a [Rc<Term>]),
+}
+
+impl RareRule {
+    /// Instantiates the rule with the given values for its parameters, in order. Since the terms
+    /// in the rule were parsed separately, the whole instance is rebuilt in the given pool.
+    pub fn instantiate(
+        &self,
+        pool: &mut dyn TermPool,
+        values: &[Rc<Term>],
+    ) -> Result<RareInstance, CheckerError> {
+        if values.len() != self.params.len() {
+            return Err(CheckerError::WrongNumberOfArgs(
+                (self.params.len() + 1).into(),
+                values.len() + 1,
+            ));
+        }
+        let mut bindings = IndexMap::new();
+        for (param, value) in self.params.iter().zip(values) {
+            let binding = match value.as_ref() {
+                Term::Op(Operator::RareList, list) if param.is_list => Binding::List(list),
+                _ if param.is_list => return Err(CheckerError::ExpectedRareList(value.clone())),
+                _ => Binding::Term(value),
+            };
+            bindings.insert(param.name.as_str(), binding);
+        }
+        Ok(RareInstance {
+            conditions: instantiate_all(pool, &self.conditions, &bindings)?,
+            lhs: instantiate(pool, &self.lhs, &bindings)?,
+            rhs: instantiate(pool, &self.rhs, &bindings)?,
+        })
+    }
+}
+
+/// Returns `true` if the operator is associative, so an application of it to a single argument
+/// can be replaced by that argument.
+fn is_associative(op: Operator) -> bool {
+    matches!(
+        op,
+        Operator::And
+            | Operator::Or
+            | Operator::Xor
+            | Operator::Add
+            | Operator::Mult
+            | Operator::StrConcat
+            | Operator::ReConcat
+            | Operator::ReUnion
+            | Operator::ReIntersection
+            | Operator::BvAnd
+            | Operator::BvOr
+            | Operator::BvXor
+            | Operator::BvAdd
+            | Operator::BvMul
+    )
+}
+
+/// Returns the neutral element of an operator, which is the result of applying it to an empty list.
+fn neutral_element(pool: &mut dyn TermPool, op: Operator) -> Option<Rc<Term>> {
+    Some(match op {
+        Operator::And => pool.bool_true(),
+        Operator::Or | Operator::Xor => pool.bool_false(),
+        Operator::StrConcat => pool.add(Term::new_string("")),
+        _ => return None,
+    })
+}
+
+/// Instantiates the arguments of an application. Parameters bound to lists are spliced into the
+/// arguments. Returns the new arguments, and whether any list was spliced.
+fn instantiate_args(
+    pool: &mut dyn TermPool,
+    args: &[Rc<Term>],
+    bindings: &IndexMap<&str, Binding>,
+) -> Result<(Vec<Rc<Term>>, bool), CheckerError> {
+    let mut result = Vec::with_capacity(args.len());
+    let mut has_list = false;
+    for arg in args {
+        let list = match arg.as_ref() {
+            Term::Var(name, _) => match bindings.get(name.as_str()) {
+                Some(Binding::List(list)) => Some(list),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(list) = list {
+            result.extend(list.iter().cloned());
+            has_list = true;
+        } else {
+            result.push(instantiate(pool, arg, bindings)?);
+        }
+    }
+    Ok((result, has_list))
+}
+
+fn instantiate_all(
+    pool: &mut dyn TermPool,
+    terms: &[Rc<Term>],
+    bindings: &IndexMap<&str, Binding>,
+) -> Result<Vec<Rc<Term>>, CheckerError> {
+    terms
+        .iter()
+        .map(|t| instantiate(pool, t, bindings))
+        .collect()
+}
+
+fn instantiate(
+    pool: &mut dyn TermPool,
+    term: &Rc<Term>,
+    bindings: &IndexMap<&str, Binding>,
+) -> Result<Rc<Term>, CheckerError> {
+    let result = match term.as_ref() {
+        Term::Var(name, sort) => match bindings.get(name.as_str()) {
+            Some(Binding::Term(value)) => return Ok((*value).clone()),
+            // A list parameter outside of an operation is only allowed if it has a single element
+            Some(Binding::List([value])) => return Ok(value.clone()),
+            Some(Binding::List(_)) => {
+                return Err(CheckerError::RareListOutsideOperation(name.clone()))
+            }
+            None => Term::Var(name.clone(), instantiate(pool, sort, bindings)?),
+        },
+        Term::Const(_) => term.as_ref().clone(),
+        Term::Sort(sort) => Term::Sort(match sort {
+            Sort::Function(sorts) => Sort::Function(instantiate_all(pool, sorts, bindings)?),
+            Sort::Atom(name, args) => {
+                Sort::Atom(name.clone(), instantiate_all(pool, args, bindings)?)
+            }
+            Sort::Array(x, y) => Sort::Array(
+                instantiate(pool, x, bindings)?,
+                instantiate(pool, y, bindings)?,
+            ),
+            other => other.clone(),
+        }),
+        Term::App(func, args) => {
+            let func = instantiate(pool, func, bindings)?;
+            let (args, _) = instantiate_args(pool, args, bindings)?;
+            Term::App(func, args)
+        }
+        Term::Op(op, args) => {
+            let (args, has_list) = instantiate_args(pool, args, bindings)?;
+            // Like in cvc5, an n-ary operation whose arguments came from lists is replaced by its
+            // only argument, or by its neutral element if it has no arguments
+            match args.as_slice() {
+                [] if has_list => {
+                    return neutral_element(pool, *op).ok_or(CheckerError::RareEmptyList(*op));
+                }
+                [arg] if has_list && is_associative(*op) => return Ok(arg.clone()),
+                _ => Term::Op(*op, args),
+            }
+        }
+        Term::ParamOp { op, op_args, args } => {
+            let op_args = instantiate_all(pool, op_args, bindings)?;
+            let (args, _) = instantiate_args(pool, args, bindings)?;
+            Term::ParamOp { op: *op, op_args, args }
+        }
+        Term::Binder(binder, binding_list, inner) => {
+            let binding_list = instantiate_binding_list(pool, binding_list, bindings)?;
+            Term::Binder(*binder, binding_list, instantiate(pool, inner, bindings)?)
+        }
+        Term::Let(binding_list, inner) => {
+            let binding_list = instantiate_binding_list(pool, binding_list, bindings)?;
+            Term::Let(binding_list, instantiate(pool, inner, bindings)?)
+        }
+    };
+    Ok(pool.add(result))
+}
+
+fn instantiate_binding_list(
+    pool: &mut dyn TermPool,
+    binding_list: &BindingList,
+    bindings: &IndexMap<&str, Binding>,
+) -> Result<BindingList, CheckerError> {
+    let list = binding_list
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), instantiate(pool, value, bindings)?)))
+        .collect::<Result<_, CheckerError>>()?;
+    Ok(BindingList(list))
+}
+
+/// A database of RARE rules, indexed by name.
+#[derive(Debug, Clone, Default)]
+pub struct RareRules(HashMap<String, RareRule>);
+
+impl RareRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule to the database, replacing any previous rule with the same name.
+    pub fn insert(&mut self, rule: RareRule) {
+        self.0.insert(rule.name.clone(), rule);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RareRule> {
+        self.0.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        checker::{conformance::check_rule_example, Config},
+        parser,
+    };
+
+    #[test]
+    fn test_rare_rewrite() {
+        let rules = "
+            (define-rule bool-double-not-elim ((t Bool)) (not (not t)) t)
+            (define-rule bool-and-true ((xs Bool :list) (ys Bool :list))
+                (and xs true ys)
+                (and xs ys))
+            (define-cond-rule ite-same-branches ((c Bool) (x ?) (y ?))
+                (= x y)
+                (ite c x y)
+                x)
+        ";
+        let rules = parser::parse_rare_rules(rules.as_bytes(), parser::Config::new()).unwrap();
+        let definitions = "
+            (declare-fun a () Bool)
+            (declare-fun b () Bool)
+            (declare-fun c () Bool)
+            (declare-fun x () Int)
+            (declare-fun y () Int)
+        ";
+        let cases = [
+            (
+                r#"(step t1 (cl (= (not (not a)) a))
+                    :rule rare_rewrite :args ("bool-double-not-elim" a))"#,
+                true,
+            ),
+            (
+                r#"(step t1 (cl (= (not (not a)) b))
+                    :rule rare_rewrite :args ("bool-double-not-elim" a))"#,
+                false,
+            ),
+            (
+                r#"(step t1 (cl (= (and a true b c) (and a b c)))
+                    :rule rare_rewrite :args ("bool-and-true" (rare-list a) (rare-list b c)))"#,
+                true,
+            ),
+            // An empty list is spliced out, and a single remaining argument replaces the operation
+            (
+                r#"(step t1 (cl (= (and true b) b))
+                    :rule rare_rewrite :args ("bool-and-true" (rare-list) (rare-list b)))"#,
+                true,
+            ),
+            (
+                r#"(step t1 (cl (= true true))
+                    :rule rare_rewrite :args ("bool-and-true" (rare-list) (rare-list)))"#,
+                true,
+            ),
+            (
+                r#"(step t1 (cl (= (and a true b) (and a b)))
+                    :rule rare_rewrite :args ("bool-and-true" a (rare-list b)))"#,
+                false,
+            ),
+            (
+                r#"(step t1 (cl (= (not (not a)) a))
+                    :rule rare_rewrite :args ("bool-double-not-elim" a b))"#,
+                false,
+            ),
+            (
+                r#"(step t1 (cl (= (not (not a)) a)) :rule rare_rewrite :args ("not-a-rule" a))"#,
+                false,
+            ),
+            // The condition of a conditional rule must be given as a premise
+            (
+                r#"(assume h1 (= x y))
+                (step t2 (cl (= (ite c x y) x))
+                    :rule rare_rewrite :premises (h1) :args ("ite-same-branches" c x y))"#,
+                true,
+            ),
+            (
+                r#"(step t1 (cl (= (ite c x y) x))
+                    :rule rare_rewrite :args ("ite-same-branches" c x y))"#,
+                false,
+            ),
+        ];
+        for (i, (proof, expected)) in cases.into_iter().enumerate() {
+            let config = Config::new().rare_rules(rules.clone());
+            let result = check_rule_example(definitions, proof, config);
+            assert_eq!(result.is_ok(), expected, "case {}: {:?}", i, result);
+        }
+
+        // Without a rule database, `rare_rewrite` is an unknown rule
+        let result = check_rule_example(definitions, cases[0].0, Config::new());
+        assert!(result.is_err());
+        let config = Config::new().ignore_unknown_rules(true);
+        assert!(check_rule_example(definitions, cases[0].0, config).is_ok());
+    }
+}
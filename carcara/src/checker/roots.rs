@@ -0,0 +1,139 @@
+//! Structural analysis of which commands of a proof are used to reach its conclusion.
+//!
+//! Some proof producers emit proofs whose last command is not the empty clause, or with several
+//! components that are not connected to each other. This analysis finds the command that actually
+//! concludes the proof, and the commands that are not needed to derive it.
+
+use crate::{ast::*, slicer};
+use indexmap::IndexSet;
+
+/// The result of analyzing the roots of a proof. All indices refer to outermost commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootAnalysis {
+    /// The index of the first command that concludes the goal, or `None` if there is no such
+    /// command.
+    pub root: Option<usize>,
+
+    /// The commands that are not needed to derive the root. If there is no root, this is empty.
+    pub disconnected: Vec<usize>,
+
+    /// The disconnected commands that are not used as premises by any other command. Each one of
+    /// them is the root of a component that is disconnected from the conclusion of the proof.
+    pub disconnected_roots: Vec<usize>,
+}
+
+impl RootAnalysis {
+    /// Returns `true` if every command in the proof is needed to derive the root.
+    pub fn is_connected(&self) -> bool {
+        self.disconnected.is_empty()
+    }
+}
+
+/// Analyzes the roots of a proof. The goal is the clause that the proof is expected to conclude,
+/// which is usually the empty clause. The order of the terms in the clause is not relevant.
+pub fn analyze_roots(proof: &Proof, goal: &[Rc<Term>]) -> RootAnalysis {
+    let commands = &proof.commands;
+    let goal: IndexSet<_> = goal.iter().collect();
+    let root = commands
+        .iter()
+        .position(|c| !c.is_assume() && c.clause().iter().collect::<IndexSet<_>>() == goal);
+    let Some(root) = root else {
+        return RootAnalysis {
+            root: None,
+            disconnected: Vec::new(),
+            disconnected_roots: Vec::new(),
+        };
+    };
+
+    let mut is_used = vec![false; commands.len()];
+    let mut is_reachable = vec![false; commands.len()];
+    let mut premises = Vec::new();
+    for command in commands {
+        slicer::outermost_premises(command, &mut premises);
+    }
+    for &p in &premises {
+        is_used[p] = true;
+    }
+
+    is_reachable[root] = true;
+    let mut stack = vec![root];
+    while let Some(i) = stack.pop() {
+        premises.clear();
+        slicer::outermost_premises(&commands[i], &mut premises);
+        for &p in &premises {
+            if !is_reachable[p] {
+                is_reachable[p] = true;
+                stack.push(p);
+            }
+        }
+    }
+
+    let disconnected: Vec<_> = (0..commands.len()).filter(|&i| !is_reachable[i]).collect();
+    let disconnected_roots = disconnected
+        .iter()
+        .copied()
+        .filter(|&i| !is_used[i])
+        .collect();
+    RootAnalysis {
+        root: Some(root),
+        disconnected,
+        disconnected_roots,
+    }
+}
+
+/// Removes from a proof all the commands that are not needed to derive the root found by the
+/// analysis. Returns `None` if the analysis found no root.
+pub fn prune_disconnected(
+    pool: &mut dyn TermPool,
+    proof: &Proof,
+    analysis: &RootAnalysis,
+) -> Option<Proof> {
+    let root_id = proof.commands[analysis.root?].id();
+    slicer::slice(pool, proof, root_id, None).map(|s| s.proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_analyze_roots() {
+        let definitions = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+        ";
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (assume h3 q)
+            (step t4 (cl q q) :rule hole :premises (h3))
+            (step t5 (cl q) :rule contraction :premises (t4))
+            (step t6 (cl) :rule resolution :premises (h1 h2))
+            (step t7 (cl p) :rule hole :premises (h1))
+        ";
+        let (_, proof, mut pool) = parser::parse_instance(
+            definitions.as_bytes(),
+            proof.as_bytes(),
+            parser::Config::new(),
+        )
+        .unwrap();
+
+        let analysis = analyze_roots(&proof, &[]);
+        assert_eq!(analysis.root, Some(5));
+        assert_eq!(analysis.disconnected, [2, 3, 4, 6]);
+        assert_eq!(analysis.disconnected_roots, [4, 6]);
+
+        let pruned = prune_disconnected(&mut pool, &proof, &analysis).unwrap();
+        let ids: Vec<_> = pruned.iter().map(ProofCommand::id).collect();
+        assert_eq!(ids, ["h1", "h2", "t6"]);
+
+        // The goal may also be a non-empty clause. Since clauses are compared as sets, `t4` already
+        // concludes it
+        let bool_sort = pool.add(Term::Sort(Sort::Bool));
+        let q = pool.add(Term::new_var("q", bool_sort));
+        let analysis = analyze_roots(&proof, &[q]);
+        assert_eq!(analysis.root, Some(3));
+        assert_eq!(analysis.disconnected_roots, [4, 5, 6]);
+    }
+}
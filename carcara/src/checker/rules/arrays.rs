@@ -0,0 +1,157 @@
+//! Rules for the theory of arrays, reasoning about the `select` and `store` operators.
+
+use super::{
+    assert_alpha_equiv_expected, assert_clause_len, assert_eq, assert_num_premises,
+    get_premise_term, ReadOnlyRuleArgs, RuleArgs, RuleResult,
+};
+use crate::{ast::*, checker::error::CheckerError};
+
+pub fn row1(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
+    assert_num_premises(premises, 0)?;
+    assert_clause_len(conclusion, 1)?;
+
+    let (((_, i, e), j), v) = match_term_err!((= (select (store a i e) j) v) = &conclusion[0])?;
+    assert_eq(i, j)?;
+    assert_eq(e, v)
+}
+
+/// Checks that `i` and `j` are the indices in a term of the form
+/// `(= (select (store a i e) j) (select a j))`.
+fn check_read_over_write(i: &Rc<Term>, j: &Rc<Term>, term: &Rc<Term>) -> RuleResult {
+    let (((a, i_1, _), j_1), (a_2, j_2)) =
+        match_term_err!((= (select (store a i e) j) (select a j)) = term)?;
+    assert_eq(a, a_2)?;
+    assert_eq(i, i_1)?;
+    assert_eq(j, j_1)?;
+    assert_eq(j, j_2)
+}
+
+pub fn row2(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
+    assert_num_premises(premises, 0)?;
+    assert_clause_len(conclusion, 2)?;
+
+    let (i, j) = match_term_err!((= i j) = &conclusion[0])?;
+    check_read_over_write(i, j, &conclusion[1])
+}
+
+pub fn row_contra(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
+    assert_num_premises(premises, 1)?;
+    assert_clause_len(conclusion, 1)?;
+
+    let premise = match_term_err!((not t) = get_premise_term(&premises[0])?)?;
+    let (i, j) = match_term_err!((= i j) = &conclusion[0])?;
+    check_read_over_write(i, j, premise)
+}
+
+pub fn ext(
+    RuleArgs {
+        conclusion,
+        premises,
+        pool,
+        polyeq_time,
+        ..
+    }: RuleArgs,
+) -> RuleResult {
+    assert_num_premises(premises, 0)?;
+    assert_clause_len(conclusion, 2)?;
+
+    let (a, b) = match_term_err!((= a b) = &conclusion[0])?;
+    let ((a_1, k), (b_1, k_1)) =
+        match_term_err!((not (= (select a k) (select b k))) = &conclusion[1])?;
+    assert_eq(a, a_1)?;
+    assert_eq(b, b_1)?;
+    assert_eq(k, k_1)?;
+
+    // The index `k` must be the witness of the arrays' difference, given as a choice term
+    let bindings = match k.as_ref() {
+        Term::Binder(Binder::Choice, bindings, _) if bindings.len() == 1 => bindings,
+        _ => {
+            return Err(CheckerError::TermOfWrongForm(
+                "(choice ((x I)) ...)",
+                k.clone(),
+            ))
+        }
+    };
+    let x = pool.add(Term::from(bindings[0].clone()));
+    let inner = build_term!(
+        pool,
+        (not (= (select {a.clone()} {x.clone()}) (select {b.clone()} {x})))
+    );
+    let expected = pool.add(Term::Binder(Binder::Choice, bindings.clone(), inner));
+    assert_alpha_equiv_expected(k, expected, polyeq_time)
+}
+
+#[cfg(test)]
+mod tests {
+    const DEFINITIONS: &str = "
+        (declare-fun a () (Array Int Int))
+        (declare-fun b () (Array Int Int))
+        (declare-fun i () Int)
+        (declare-fun j () Int)
+        (declare-fun e () Int)
+    ";
+
+    #[test]
+    fn row1() {
+        test_cases! {
+            definitions = DEFINITIONS,
+            "Simple working examples" {
+                "(step t1 (cl (= (select (store a i e) i) e)) :rule row1)": true,
+                "(step t1 (cl (= (select (store a (+ i 1) 0) (+ i 1)) 0)) :rule row1)": true,
+            }
+            "Wrong index or value" {
+                "(step t1 (cl (= (select (store a i e) j) e)) :rule row1)": false,
+                "(step t1 (cl (= (select (store a i e) i) j)) :rule row1)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn row2() {
+        test_cases! {
+            definitions = DEFINITIONS,
+            "Simple working examples" {
+                "(step t1 (cl (= i j) (= (select (store a i e) j) (select a j))) :rule row2)": true,
+            }
+            "Wrong indices or arrays" {
+                "(step t1 (cl (= j i) (= (select (store a i e) j) (select a j))) :rule row2)": false,
+                "(step t1 (cl (= i j) (= (select (store a i e) j) (select b j))) :rule row2)": false,
+                "(step t1 (cl (= i j) (= (select (store a i e) j) (select a i))) :rule row2)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn row_contra() {
+        test_cases! {
+            definitions = DEFINITIONS,
+            "Simple working examples" {
+                "(assume h1 (not (= (select (store a i e) j) (select a j))))
+                (step t1 (cl (= i j)) :rule row_contra :premises (h1))": true,
+            }
+            "Wrong indices" {
+                "(assume h1 (not (= (select (store a i e) j) (select a j))))
+                (step t1 (cl (= j i)) :rule row_contra :premises (h1))": false,
+            }
+        }
+    }
+
+    #[test]
+    fn ext() {
+        test_cases! {
+            definitions = DEFINITIONS,
+            "Simple working examples" {
+                "(step t1 (cl (= a b) (not (= (select a (choice ((x Int)) (not (= (select a x) (select b x)))))
+                    (select b (choice ((x Int)) (not (= (select a x) (select b x)))))))) :rule ext)": true,
+
+                "(step t1 (cl (= a b) (not (= (select a (choice ((y Int)) (not (= (select a y) (select b y)))))
+                    (select b (choice ((y Int)) (not (= (select a y) (select b y)))))))) :rule ext)": true,
+            }
+            "Index is not the witness" {
+                "(step t1 (cl (= a b) (not (= (select a i) (select b i)))) :rule ext)": false,
+                "(step t1 (cl (= a b) (not (= (select a (choice ((x Int)) (= (select a x) (select b x))))
+                    (select b (choice ((x Int)) (= (select a x) (select b x))))))) :rule ext)": false,
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use crate::{
-    ast::{pool::TermPool, Operator, ParamOperator, Rc, Sort, Term},
-    checker::rules::assert_clause_len,
+    ast::{
+        pool::{TermPool, TermPoolView},
+        Constant, Operator, ParamOperator, Rc, Sort, Term,
+    },
+    checker::{error::CheckerError, rules::assert_clause_len},
 };
 
 use super::{assert_eq, RuleArgs, RuleResult};
@@ -23,15 +26,138 @@ fn build_term_vec(term: &Rc<Term>, size: usize, pool: &mut dyn TermPool) -> Vec<
     term
 }
 
-pub fn ult(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
+/// Returns the width of a bit-vector term, or an error if the term is not of a bit-vector sort.
+fn bv_size(term: &Rc<Term>, pool: &(impl TermPoolView + ?Sized)) -> Result<usize, CheckerError> {
+    match pool.sort(term).as_sort() {
+        Some(Sort::BitVec(size)) => Ok(size.to_usize().unwrap()),
+        _ => Err(CheckerError::NotOfBitVecSort(term.clone())),
+    }
+}
+
+fn bbterm(bits: Vec<Rc<Term>>, pool: &mut dyn TermPool) -> Rc<Term> {
+    pool.add(Term::Op(Operator::BvBbTerm, bits))
+}
+
+pub fn var(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
-    let ((x, y), res) = match_term_err!((= (bvult x y) res) = &conclusion[0])?;
+    let (x, res) = match_term_err!((= x res) = &conclusion[0])?;
+
+    let size = bv_size(x, pool)?;
+    let bits = build_term_vec(x, size, pool);
+    let expected_res = bbterm(bits, pool);
+
+    assert_eq(&expected_res, res)
+}
+
+pub fn constant(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    let (c, res) = match_term_err!((= c res) = &conclusion[0])?;
+
+    let Term::Const(Constant::BitVec(value, width)) = c.as_ref() else {
+        return Err(CheckerError::TermOfWrongForm("#b...", c.clone()));
+    };
+    let bits = (0..width.to_u32().unwrap())
+        .map(|i| pool.bool_constant(value.get_bit(i)))
+        .collect();
+    let expected_res = bbterm(bits, pool);
+
+    assert_eq(&expected_res, res)
+}
+
+/// Checks a bit-blasting step of a bitwise operation, in which each bit of the result is the
+/// `bit_op` application on the corresponding bits of the arguments.
+fn bitwise(
+    RuleArgs { conclusion, pool, .. }: RuleArgs,
+    bv_op: Operator,
+    bit_op: Operator,
+) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    let (app, res) = match_term_err!((= app res) = &conclusion[0])?;
 
-    let Sort::BitVec(size) = pool.sort(x).as_sort().cloned().unwrap() else {
-        unreachable!();
+    let args = match app.as_op() {
+        Some((op, args)) if op == bv_op && !args.is_empty() => args,
+        _ => return Err(CheckerError::TermOfWrongForm("(bv... ...)", app.clone())),
     };
 
-    let size = size.to_usize().unwrap();
+    let size = bv_size(&args[0], pool)?;
+    let args: Vec<_> = args.iter().map(|a| build_term_vec(a, size, pool)).collect();
+    let bits = (0..size)
+        .map(|i| {
+            let bit_args = args.iter().map(|a| a[i].clone()).collect();
+            pool.add(Term::Op(bit_op, bit_args))
+        })
+        .collect();
+    let expected_res = bbterm(bits, pool);
+
+    assert_eq(&expected_res, res)
+}
+
+pub fn not(args: RuleArgs) -> RuleResult {
+    bitwise(args, Operator::BvNot, Operator::Not)
+}
+
+pub fn and(args: RuleArgs) -> RuleResult {
+    bitwise(args, Operator::BvAnd, Operator::And)
+}
+
+pub fn or(args: RuleArgs) -> RuleResult {
+    bitwise(args, Operator::BvOr, Operator::Or)
+}
+
+pub fn xor(args: RuleArgs) -> RuleResult {
+    bitwise(args, Operator::BvXor, Operator::Xor)
+}
+
+pub fn xnor(args: RuleArgs) -> RuleResult {
+    bitwise(args, Operator::BvXNor, Operator::Equals)
+}
+
+pub fn equal(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    let ((x, y), res) = match_term_err!((= (= x y) res) = &conclusion[0])?;
+
+    let size = bv_size(x, pool)?;
+    let x = build_term_vec(x, size, pool);
+    let y = build_term_vec(y, size, pool);
+
+    let mut bits: Vec<_> = (0..size)
+        .map(|i| build_term!(pool, (= {x[i].clone()} {y[i].clone()})))
+        .collect();
+    let expected_res = if bits.len() == 1 {
+        bits.pop().unwrap()
+    } else {
+        pool.add(Term::Op(Operator::And, bits))
+    };
+
+    assert_eq(&expected_res, res)
+}
+
+pub fn concat(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    let (app, res) = match_term_err!((= app res) = &conclusion[0])?;
+
+    let args = match app.as_op() {
+        Some((Operator::BvConcat, args)) => args,
+        _ => return Err(CheckerError::TermOfWrongForm("(concat ...)", app.clone())),
+    };
+
+    // The first argument of `concat` holds the most significant bits, so the bits of the last
+    // argument come first
+    let mut bits = Vec::new();
+    for a in args.iter().rev() {
+        let size = bv_size(a, pool)?;
+        bits.extend(build_term_vec(a, size, pool));
+    }
+    let expected_res = bbterm(bits, pool);
+
+    assert_eq(&expected_res, res)
+}
+
+pub fn ult(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    let ((x, y), res) = match_term_err!((= (bvult x y) res) = &conclusion[0])?;
+
+    let size = bv_size(x, pool)?;
 
     let x = build_term_vec(x, size, pool);
     let y = build_term_vec(y, size, pool);
@@ -54,11 +180,7 @@ pub fn add(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let ((x, y), res) = match_term_err!((= (bvadd x y) res) = &conclusion[0])?;
 
-    let Sort::BitVec(size) = pool.sort(x).as_sort().cloned().unwrap() else {
-        unreachable!();
-    };
-
-    let size = size.to_usize().unwrap();
+    let size = bv_size(x, pool)?;
 
     let x = build_term_vec(x, size, pool);
     let y = build_term_vec(y, size, pool);
@@ -171,4 +293,106 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn var() {
+        test_cases! {
+            definitions = "
+                (declare-fun x2 () (_ BitVec 2))
+                (declare-fun y2 () (_ BitVec 2))
+                (declare-fun a () Int)
+                (declare-fun b () Int)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (= x2 (bbterm ((_ bit_of 0) x2) ((_ bit_of 1) x2)))) :rule bitblast_var)": true,
+            }
+            "Wrong bits" {
+                "(step t1 (cl (= x2 (bbterm ((_ bit_of 1) x2) ((_ bit_of 0) x2)))) :rule bitblast_var)": false,
+                "(step t1 (cl (= x2 (bbterm ((_ bit_of 0) x2) ((_ bit_of 1) y2)))) :rule bitblast_var)": false,
+            }
+            "Term is not a bit-vector" {
+                "(step t1 (cl (= a b)) :rule bitblast_var)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn constant() {
+        test_cases! {
+            definitions = "",
+            "Simple working examples" {
+                "(step t1 (cl (= #b110 (bbterm false true true))) :rule bitblast_const)": true,
+                "(step t1 (cl (= #b0 (bbterm false))) :rule bitblast_const)": true,
+            }
+            "Wrong bits" {
+                "(step t1 (cl (= #b110 (bbterm true true false))) :rule bitblast_const)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn bitwise() {
+        test_cases! {
+            definitions = "
+                (declare-fun x2 () (_ BitVec 2))
+                (declare-fun y2 () (_ BitVec 2))
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (= (bvnot x2) (bbterm (not ((_ bit_of 0) x2)) (not ((_ bit_of 1) x2))))) :rule bitblast_not)": true,
+                "(step t1 (cl (= (bvand x2 y2) (bbterm (and ((_ bit_of 0) x2) ((_ bit_of 0) y2)) (and ((_ bit_of 1) x2) ((_ bit_of 1) y2))))) :rule bitblast_and)": true,
+                "(step t1 (cl (= (bvor x2 y2) (bbterm (or ((_ bit_of 0) x2) ((_ bit_of 0) y2)) (or ((_ bit_of 1) x2) ((_ bit_of 1) y2))))) :rule bitblast_or)": true,
+                "(step t1 (cl (= (bvxor x2 y2) (bbterm (xor ((_ bit_of 0) x2) ((_ bit_of 0) y2)) (xor ((_ bit_of 1) x2) ((_ bit_of 1) y2))))) :rule bitblast_xor)": true,
+                "(step t1 (cl (= (bvxnor x2 y2) (bbterm (= ((_ bit_of 0) x2) ((_ bit_of 0) y2)) (= ((_ bit_of 1) x2) ((_ bit_of 1) y2))))) :rule bitblast_xnor)": true,
+            }
+            "Arguments given as bbterms" {
+                "(step t1 (cl (= (bvand (bbterm true false) y2) (bbterm (and true ((_ bit_of 0) y2)) (and false ((_ bit_of 1) y2))))) :rule bitblast_and)": true,
+            }
+            "Wrong operator" {
+                "(step t1 (cl (= (bvor x2 y2) (bbterm (and ((_ bit_of 0) x2) ((_ bit_of 0) y2)) (and ((_ bit_of 1) x2) ((_ bit_of 1) y2))))) :rule bitblast_and)": false,
+                "(step t1 (cl (= (bvand x2 y2) (bbterm (or ((_ bit_of 0) x2) ((_ bit_of 0) y2)) (or ((_ bit_of 1) x2) ((_ bit_of 1) y2))))) :rule bitblast_and)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn equal() {
+        test_cases! {
+            definitions = "
+                (declare-fun x1 () (_ BitVec 1))
+                (declare-fun y1 () (_ BitVec 1))
+                (declare-fun x2 () (_ BitVec 2))
+                (declare-fun y2 () (_ BitVec 2))
+                (declare-fun a () Int)
+                (declare-fun b () Int)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (= (= x2 y2) (and (= ((_ bit_of 0) x2) ((_ bit_of 0) y2)) (= ((_ bit_of 1) x2) ((_ bit_of 1) y2))))) :rule bitblast_equal)": true,
+                "(step t1 (cl (= (= x1 y1) (= ((_ bit_of 0) x1) ((_ bit_of 0) y1)))) :rule bitblast_equal)": true,
+            }
+            "Wrong bits" {
+                "(step t1 (cl (= (= x2 y2) (and (= ((_ bit_of 0) x2) ((_ bit_of 1) y2)) (= ((_ bit_of 1) x2) ((_ bit_of 0) y2))))) :rule bitblast_equal)": false,
+                "(step t1 (cl (= (= x2 y2) (= ((_ bit_of 0) x2) ((_ bit_of 0) y2)))) :rule bitblast_equal)": false,
+            }
+            "Terms are not bit-vectors" {
+                "(step t1 (cl (= (= a b) true)) :rule bitblast_equal)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn concat() {
+        test_cases! {
+            definitions = "
+                (declare-fun x1 () (_ BitVec 1))
+                (declare-fun y2 () (_ BitVec 2))
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (= (concat x1 y2) (bbterm ((_ bit_of 0) y2) ((_ bit_of 1) y2) ((_ bit_of 0) x1)))) :rule bitblast_concat)": true,
+                "(step t1 (cl (= (concat (bbterm true) y2) (bbterm ((_ bit_of 0) y2) ((_ bit_of 1) y2) true))) :rule bitblast_concat)": true,
+            }
+            "Wrong order" {
+                "(step t1 (cl (= (concat x1 y2) (bbterm ((_ bit_of 0) x1) ((_ bit_of 0) y2) ((_ bit_of 1) y2)))) :rule bitblast_concat)": false,
+            }
+        }
+    }
 }
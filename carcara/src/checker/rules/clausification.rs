@@ -1,10 +1,10 @@
 use super::{
     assert_clause_len, assert_eq, assert_is_expected, assert_num_args, assert_num_premises,
     assert_operation_len, assert_polyeq_expected, get_premise_term, CheckerError, EqualityError,
-    RuleArgs, RuleResult,
+    ReadOnlyRuleArgs, RuleArgs, RuleResult,
 };
 use crate::ast::*;
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 
 pub fn distinct_elim(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
@@ -60,7 +60,7 @@ pub fn distinct_elim(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult
     }
 }
 
-pub fn and(RuleArgs { conclusion, premises, args, .. }: RuleArgs) -> RuleResult {
+pub fn and(RuleArgs { conclusion, premises, args, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_num_args(args, 1)?;
     assert_clause_len(conclusion, 1)?;
@@ -76,7 +76,7 @@ pub fn and(RuleArgs { conclusion, premises, args, .. }: RuleArgs) -> RuleResult
     assert_eq(&conclusion[0], &and_contents[i])
 }
 
-pub fn not_or(RuleArgs { conclusion, premises, args, .. }: RuleArgs) -> RuleResult {
+pub fn not_or(RuleArgs { conclusion, premises, args, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_num_args(args, 1)?;
     assert_clause_len(conclusion, 1)?;
@@ -93,7 +93,7 @@ pub fn not_or(RuleArgs { conclusion, premises, args, .. }: RuleArgs) -> RuleResu
     assert_eq(conclusion, &or_contents[i])
 }
 
-pub fn or(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn or(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
 
     let or_term = get_premise_term(&premises[0])?;
@@ -106,7 +106,7 @@ pub fn or(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn not_and(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_and(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
 
     let and_term = get_premise_term(&premises[0])?;
@@ -120,7 +120,7 @@ pub fn not_and(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn xor1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn xor1(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
 
@@ -131,7 +131,7 @@ pub fn xor1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, &conclusion[1])
 }
 
-pub fn xor2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn xor2(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
 
@@ -142,7 +142,7 @@ pub fn xor2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, conclusion[1].remove_negation_err()?)
 }
 
-pub fn not_xor1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_xor1(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
 
@@ -153,7 +153,7 @@ pub fn not_xor1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, conclusion[1].remove_negation_err()?)
 }
 
-pub fn not_xor2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_xor2(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
 
@@ -164,7 +164,7 @@ pub fn not_xor2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, &conclusion[1])
 }
 
-pub fn implies(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn implies(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
 
@@ -175,7 +175,7 @@ pub fn implies(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, &conclusion[1])
 }
 
-pub fn not_implies1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_implies1(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 1)?;
 
@@ -185,7 +185,7 @@ pub fn not_implies1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResu
     assert_eq(phi_1, &conclusion[0])
 }
 
-pub fn not_implies2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_implies2(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 1)?;
 
@@ -195,72 +195,102 @@ pub fn not_implies2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResu
     assert_eq(phi_2, conclusion[0].remove_negation_err()?)
 }
 
-pub fn nary_elim(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
-    /// The three possible cases for n-ary operators: chainable, right associative and left
-    /// associative
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    enum Case {
-        Chainable,
-        RightAssoc,
-        LeftAssoc,
-    }
-
-    /// A function to expand terms that fall in the right or left associative cases. For example,
-    /// the term `(=> p q r s)` will be expanded into the term `(=> p (=> q (=> r s)))`.
-    fn expand_assoc(
-        pool: &mut dyn TermPool,
-        op: Operator,
-        args: &[Rc<Term>],
-        case: Case,
-    ) -> Rc<Term> {
-        let (head, tail) = match args {
-            [] => unreachable!(),
-            [t] => return t.clone(),
-
-            // The "head" term will be the first or last term in `args`, depending on if the
-            // operator is right or left associative
-            [first, rest @ ..] if case == Case::RightAssoc => (first, rest),
-            [rest @ .., last] => (last, rest),
-        };
-
-        // Note: if the argument list if very long, this may overflow the stack
-        let nested = expand_assoc(pool, op, tail, case);
+/// The three possible cases for n-ary operators: chainable, right associative and left
+/// associative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NaryCase {
+    Chainable,
+    RightAssoc,
+    LeftAssoc,
+}
 
-        let new_args = match case {
-            Case::RightAssoc => vec![head.clone(), nested],
-            Case::LeftAssoc => vec![nested, head.clone()],
-            Case::Chainable => unreachable!(),
-        };
-        pool.add(Term::Op(op, new_args))
-    }
+/// A function to expand terms that fall in the right or left associative cases. For example, the
+/// term `(=> p q r s)` will be expanded into the term `(=> p (=> q (=> r s)))`.
+fn expand_assoc(
+    pool: &mut dyn TermPool,
+    op: Operator,
+    args: &[Rc<Term>],
+    case: NaryCase,
+) -> Rc<Term> {
+    let (head, tail) = match args {
+        [] => unreachable!(),
+        [t] => return t.clone(),
+
+        // The "head" term will be the first or last term in `args`, depending on if the operator
+        // is right or left associative
+        [first, rest @ ..] if case == NaryCase::RightAssoc => (first, rest),
+        [rest @ .., last] => (last, rest),
+    };
 
-    assert_clause_len(conclusion, 1)?;
+    // Note: if the argument list if very long, this may overflow the stack
+    let nested = expand_assoc(pool, op, tail, case);
 
-    let (original, result) = match_term_err!((= o r) = &conclusion[0])?;
+    let new_args = match case {
+        NaryCase::RightAssoc => vec![head.clone(), nested],
+        NaryCase::LeftAssoc => vec![nested, head.clone()],
+        NaryCase::Chainable => unreachable!(),
+    };
+    pool.add(Term::Op(op, new_args))
+}
 
+/// Checks that `expanded` is the expansion of the n-ary term `original` into binary applications.
+/// Chainable operators (like `=` and `<=`) are expanded into a conjunction of binary applications,
+/// and associative operators are expanded into nested binary applications, according to their
+/// associativity. Since `+` and `*` are associative, they may be nested in either direction.
+fn check_nary_expansion(
+    pool: &mut dyn TermPool,
+    original: &Rc<Term>,
+    expanded: &Rc<Term>,
+) -> RuleResult {
     let (op, args) = match original.as_ref() {
-        Term::Op(op, args) if args.len() >= 2 => (op, args),
+        Term::Op(op, args) if args.len() >= 2 => (*op, args),
         _ => return Err(CheckerError::NotValidNaryTerm(original.clone())),
     };
 
     let case = match op {
-        Operator::Equals => Case::Chainable,
-        Operator::Add | Operator::Sub | Operator::Mult => Case::LeftAssoc,
-        Operator::Implies => Case::RightAssoc,
+        Operator::Equals
+        | Operator::LessThan
+        | Operator::GreaterThan
+        | Operator::LessEq
+        | Operator::GreaterEq => NaryCase::Chainable,
+        Operator::Add | Operator::Sub | Operator::Mult | Operator::IntDiv | Operator::RealDiv => {
+            NaryCase::LeftAssoc
+        }
+        Operator::Implies => NaryCase::RightAssoc,
         _ => return Err(CheckerError::NotValidNaryTerm(original.clone())),
     };
 
     let expected = match case {
-        Case::Chainable => {
+        NaryCase::Chainable => {
             let and_args: Vec<_> = args
                 .windows(2)
-                .map(|args| pool.add(Term::Op(*op, args.to_vec())))
+                .map(|args| pool.add(Term::Op(op, args.to_vec())))
                 .collect();
             pool.add(Term::Op(Operator::And, and_args))
         }
-        assoc_case => expand_assoc(pool, *op, args, assoc_case),
+        assoc_case => expand_assoc(pool, op, args, assoc_case),
     };
-    assert_is_expected(result, expected)
+    if matches!(op, Operator::Add | Operator::Mult) && *expanded != expected {
+        let right_assoc = expand_assoc(pool, op, args, NaryCase::RightAssoc);
+        if *expanded == right_assoc {
+            return Ok(());
+        }
+    }
+    assert_is_expected(expanded, expected)
+}
+
+pub fn nary_elim(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+
+    let (original, result) = match_term_err!((= o r) = &conclusion[0])?;
+    check_nary_expansion(pool, original, result)
+}
+
+pub fn nary_intro(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+
+    let (original, result) = match_term_err!((= o r) = &conclusion[0])?;
+    check_nary_expansion(pool, result, original)
 }
 
 /// The first simplification step for `bfun_elim`, that expands quantifiers over boolean variables.
@@ -385,6 +415,110 @@ fn apply_bfun_elim(
     Ok(result)
 }
 
+/// The state used to check a `bfun_elim` step directly against its conclusion.
+struct BfunElimChecker<'a> {
+    pool: &'a mut dyn TermPool,
+
+    /// The results of `apply_bfun_elim` for the terms that could not be checked directly.
+    cache: IndexMap<Rc<Term>, Rc<Term>>,
+
+    /// The pairs of original and conclusion terms that were already checked.
+    checked: IndexSet<(Rc<Term>, Rc<Term>)>,
+}
+
+impl BfunElimChecker<'_> {
+    /// Returns `true` if `conclusion` is the result of applying the `bfun_elim` simplifications to
+    /// `original`. Function applications are checked by walking their `ite` expansion in the
+    /// conclusion, so the expected expansion, which is exponential in the number of boolean
+    /// arguments, is never constructed. Other terms fall back to `apply_bfun_elim`.
+    fn check(
+        &mut self,
+        original: &Rc<Term>,
+        conclusion: &Rc<Term>,
+    ) -> Result<bool, SubstitutionError> {
+        let key = (original.clone(), conclusion.clone());
+        if self.checked.contains(&key) {
+            return Ok(true);
+        }
+        let result = match (original.as_ref(), conclusion.as_ref()) {
+            (Term::App(f, args), _) => {
+                let mut assignment = vec![None; args.len()];
+                self.check_app(f, args, &mut assignment, 0, conclusion)?
+            }
+            (Term::Op(op, args), Term::Op(other_op, other_args)) => {
+                if op != other_op || args.len() != other_args.len() {
+                    return Ok(false);
+                }
+                for (a, b) in args.iter().zip(other_args) {
+                    if !self.check(a, b)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            (Term::Op(..), _) => false,
+            _ => apply_bfun_elim(self.pool, original, &mut self.cache)? == *conclusion,
+        };
+        if result {
+            self.checked.insert(key);
+        }
+        Ok(result)
+    }
+
+    /// Checks the expansion of the application of `func` to `args`, where the boolean arguments
+    /// before index `processed` were already replaced by the values in `assignment`.
+    fn check_app(
+        &mut self,
+        func: &Rc<Term>,
+        args: &[Rc<Term>],
+        assignment: &mut [Option<bool>],
+        processed: usize,
+        conclusion: &Rc<Term>,
+    ) -> Result<bool, SubstitutionError> {
+        for i in processed..args.len() {
+            if self.pool.sort(&args[i]).as_sort().unwrap() == &Sort::Bool
+                && !args[i].is_bool_false()
+                && !args[i].is_bool_true()
+            {
+                let Some((condition, if_true, if_false)) = match_term!((ite c t e) = conclusion)
+                else {
+                    return Ok(false);
+                };
+                if !self.check(&args[i], condition)? {
+                    return Ok(false);
+                }
+                for (value, branch) in [(true, if_true), (false, if_false)] {
+                    assignment[i] = Some(value);
+                    let result = self.check_app(func, args, assignment, i + 1, branch)?;
+                    assignment[i] = None;
+                    if !result {
+                        return Ok(false);
+                    }
+                }
+                return Ok(true);
+            }
+        }
+
+        let Term::App(other_func, other_args) = conclusion.as_ref() else {
+            return Ok(false);
+        };
+        if func != other_func || args.len() != other_args.len() {
+            return Ok(false);
+        }
+        for ((arg, other), value) in args.iter().zip(other_args).zip(assignment.iter()) {
+            let matches = match value {
+                Some(true) => other.is_bool_true(),
+                Some(false) => other.is_bool_false(),
+                None => self.check(arg, other)?,
+            };
+            if !matches {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
 pub fn bfun_elim(
     RuleArgs {
         conclusion,
@@ -399,7 +533,20 @@ pub fn bfun_elim(
 
     let psi = get_premise_term(&premises[0])?;
 
-    let expected = apply_bfun_elim(pool, psi, &mut IndexMap::new())?;
+    // We first try to check the conclusion directly, which avoids constructing the full expansion
+    // of applications with many boolean arguments. If that fails, which can happen if the
+    // conclusion is only equal to the expected term modulo reordering of equalities, we construct
+    // the expected term and compare them with `polyeq`
+    let mut checker = BfunElimChecker {
+        pool,
+        cache: IndexMap::new(),
+        checked: IndexSet::new(),
+    };
+    if checker.check(psi, &conclusion[0])? {
+        return Ok(());
+    }
+    let BfunElimChecker { pool, mut cache, .. } = checker;
+    let expected = apply_bfun_elim(pool, psi, &mut cache)?;
     assert_polyeq_expected(&conclusion[0], expected, polyeq_time)
 }
 
@@ -863,6 +1010,44 @@ mod tests {
                 "(step t1 (cl (= (- a) (- a))) :rule nary_elim)": false,
                 "(step t1 (cl (= (=> p (=> q (=> r s))) (=> p q r s))) :rule nary_elim)": false,
             }
+            "Chainable comparison operators" {
+                "(step t1 (cl (= (< a b c) (and (< a b) (< b c)))) :rule nary_elim)": true,
+                "(step t1 (cl (= (<= a b c) (and (<= a b) (<= b c)))) :rule nary_elim)": true,
+                "(step t1 (cl (= (>= a b c) (and (>= a b) (> b c)))) :rule nary_elim)": false,
+            }
+            "Associative operators may be nested in either direction" {
+                "(step t1 (cl (= (+ a b c d) (+ a (+ b (+ c d))))) :rule nary_elim)": true,
+                "(step t1 (cl (= (* a b c) (* a (* b c)))) :rule nary_elim)": true,
+                "(step t1 (cl (= (div a b c) (div a (div b c)))) :rule nary_elim)": false,
+                "(step t1 (cl (= (div a b c) (div (div a b) c))) :rule nary_elim)": true,
+            }
+        }
+    }
+
+    #[test]
+    fn nary_intro() {
+        test_cases! {
+            definitions = "
+                (declare-fun p () Bool)
+                (declare-fun q () Bool)
+                (declare-fun r () Bool)
+                (declare-fun a () Int)
+                (declare-fun b () Int)
+                (declare-fun c () Int)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (= (and (= a b) (= b c)) (= a b c))) :rule nary_intro)": true,
+                "(step t1 (cl (= (- (- a b) c) (- a b c))) :rule nary_intro)": true,
+                "(step t1 (cl (= (+ a (+ b c)) (+ a b c))) :rule nary_intro)": true,
+                "(step t1 (cl (= (=> p (=> q r)) (=> p q r))) :rule nary_intro)": true,
+            }
+            "Wrong associativity" {
+                "(step t1 (cl (= (- a (- b c)) (- a b c))) :rule nary_intro)": false,
+                "(step t1 (cl (= (=> (=> p q) r) (=> p q r))) :rule nary_intro)": false,
+            }
+            "Sides are flipped" {
+                "(step t1 (cl (= (= a b c) (and (= a b) (= b c)))) :rule nary_intro)": false,
+            }
         }
     }
 
@@ -873,7 +1058,9 @@ mod tests {
                 (declare-fun f (Bool) Bool)
                 (declare-fun g (Bool Bool Bool) Bool)
                 (declare-fun h (Int Bool Real) Bool)
+                (declare-fun k (Bool Bool Bool Bool) Bool)
                 (declare-fun a () Bool)
+                (declare-fun d () Bool)
                 (declare-fun b () Bool)
                 (declare-fun c () Bool)
             ",
@@ -914,6 +1101,64 @@ mod tests {
                         (ite c (g false true true) (g false true false))
                         (ite c (g false false true) (g false false false)))
                 )) :rule bfun_elim :premises (h1))": true,
+
+                "(assume h1 (g a true c))
+                (step t1 (cl (ite a
+                    (ite c (g true true true) (g true true false))
+                    (ite c (g false true true) (g false true false))
+                )) :rule bfun_elim :premises (h1))": true,
+
+                "(assume h1 (k a b c d))
+                (step t1 (cl (ite a
+                    (ite b
+                        (ite c
+                            (ite d (k true true true true) (k true true true false))
+                            (ite d (k true true false true) (k true true false false)))
+                        (ite c
+                            (ite d (k true false true true) (k true false true false))
+                            (ite d (k true false false true) (k true false false false))))
+                    (ite b
+                        (ite c
+                            (ite d (k false true true true) (k false true true false))
+                            (ite d (k false true false true) (k false true false false)))
+                        (ite c
+                            (ite d (k false false true true) (k false false true false))
+                            (ite d (k false false false true) (k false false false false))))
+                )) :rule bfun_elim :premises (h1))": true,
+
+                "(assume h1 (f (g a b c)))
+                (step t1 (cl (ite
+                    (ite a
+                        (ite b
+                            (ite c (g true true true) (g true true false))
+                            (ite c (g true false true) (g true false false)))
+                        (ite b
+                            (ite c (g false true true) (g false true false))
+                            (ite c (g false false true) (g false false false))))
+                    (f true)
+                    (f false)
+                )) :rule bfun_elim :premises (h1))": true,
+            }
+            "Wrong expansion" {
+                "(assume h1 (g a b c))
+                (step t1 (cl (ite a
+                    (ite b
+                        (ite c (g true true true) (g true true false))
+                        (ite c (g true false true) (g true false false)))
+                    (ite b
+                        (ite c (g false true true) (g false true false))
+                        (ite c (g false false false) (g false false false)))
+                )) :rule bfun_elim :premises (h1))": false,
+
+                "(assume h1 (g a b c))
+                (step t1 (cl (ite b
+                    (ite a
+                        (ite c (g true true true) (g true true false))
+                        (ite c (g false true true) (g false true false)))
+                    (ite a
+                        (ite c (g true false true) (g true false false))
+                        (ite c (g false false true) (g false false false)))
+                )) :rule bfun_elim :premises (h1))": false,
             }
             "Both steps" {
                 "(assume h1 (exists ((x Bool)) (and x (f a))))
@@ -1,9 +1,10 @@
 use super::{
-    assert_clause_len, assert_num_premises, get_premise_term, CheckerError, RuleArgs, RuleResult,
+    assert_clause_len, assert_num_premises, get_premise_term, CheckerError, ReadOnlyRuleArgs,
+    RuleArgs, RuleResult,
 };
 use crate::{ast::*, checker::error::CongruenceError};
 
-pub fn eq_congruent(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn eq_congruent(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 2..)?;
 
     let premises = conclusion[..conclusion.len() - 1]
@@ -14,7 +15,7 @@ pub fn eq_congruent(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     generic_congruent_rule(premises, conclusion)
 }
 
-pub fn eq_congruent_pred(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn eq_congruent_pred(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3..)?;
 
     let premises = conclusion[..conclusion.len() - 2]
@@ -130,39 +131,14 @@ where
     }
 }
 
-pub fn cong(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
-    assert_clause_len(conclusion, 1)?;
-    assert_num_premises(premises, 1..)?;
+type CongArgs<'a> = (&'a [Rc<Term>], &'a [Rc<Term>]);
 
-    let premises: Vec<_> = premises
-        .iter()
-        .map(|premise| match_term_err!((= t u) = get_premise_term(premise)?))
-        .collect::<Result<_, _>>()?;
+type TermPair<'a> = (&'a Rc<Term>, &'a Rc<Term>);
 
-    let (f, g) = match_term_err!((= f g) = &conclusion[0])?;
+/// Returns the arguments of two terms that may be related by congruence: two applications of the
+/// same function, or two operations with the same (possibly indexed) operator.
+fn get_cong_args<'a>(f: &'a Rc<Term>, g: &'a Rc<Term>) -> Result<CongArgs<'a>, CheckerError> {
     let (f_args, g_args) = match (f.as_ref(), g.as_ref()) {
-        // Because of the way veriT handles equality terms, when the `cong` rule is called with two
-        // equalities of two terms, the order of their arguments may be flipped. Because of that,
-        // we have to treat this special case separately
-        (Term::Op(Operator::Equals, f_args), Term::Op(Operator::Equals, g_args))
-            if f_args.len() == 2 && g_args.len() == 2 =>
-        {
-            // We have to test all four possibilities: neither f nor g are flipped, only f is
-            // flipped, only g is flipped, or both f and g are flipped
-            let f_args_flipped: &[_] = &[f_args[1].clone(), f_args[0].clone()];
-            let g_args_flipped: &[_] = &[g_args[1].clone(), g_args[0].clone()];
-
-            // We store the result of the first possibility (when neither arguments are flipped),
-            // because, if the checking fails in the end, we use it to get more sensible error
-            // messages
-            let original_result = check_cong(&premises, f_args, g_args);
-            let any_valid = original_result.is_ok()
-                || check_cong(&premises, f_args_flipped, g_args.as_slice()).is_ok()
-                || check_cong(&premises, f_args.as_slice(), g_args_flipped).is_ok()
-                || check_cong(&premises, f_args_flipped, g_args_flipped).is_ok();
-            return if any_valid { Ok(()) } else { original_result };
-        }
-
         (Term::App(f, f_args), Term::App(g, g_args)) => match f == g {
             true => Ok((f_args, g_args)),
             false => Err(CongruenceError::DifferentFunctions(f.clone(), g.clone())),
@@ -203,10 +179,48 @@ pub fn cong(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
         f_args.len() == g_args.len(),
         CongruenceError::DifferentNumberOfArguments(f_args.len(), g_args.len())
     );
+    Ok((f_args, g_args))
+}
+
+pub fn cong(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    assert_num_premises(premises, 1..)?;
+
+    let premises: Vec<_> = premises
+        .iter()
+        .map(|premise| match_term_err!((= t u) = get_premise_term(premise)?))
+        .collect::<Result<_, _>>()?;
+
+    let (f, g) = match_term_err!((= f g) = &conclusion[0])?;
+    let (f_args, g_args) = match (f.as_ref(), g.as_ref()) {
+        // Because of the way veriT handles equality terms, when the `cong` rule is called with two
+        // equalities of two terms, the order of their arguments may be flipped. Because of that,
+        // we have to treat this special case separately
+        (Term::Op(Operator::Equals, f_args), Term::Op(Operator::Equals, g_args))
+            if f_args.len() == 2 && g_args.len() == 2 =>
+        {
+            // We have to test all four possibilities: neither f nor g are flipped, only f is
+            // flipped, only g is flipped, or both f and g are flipped
+            let f_args_flipped: &[_] = &[f_args[1].clone(), f_args[0].clone()];
+            let g_args_flipped: &[_] = &[g_args[1].clone(), g_args[0].clone()];
+
+            // We store the result of the first possibility (when neither arguments are flipped),
+            // because, if the checking fails in the end, we use it to get more sensible error
+            // messages
+            let original_result = check_cong(&premises, f_args, g_args);
+            let any_valid = original_result.is_ok()
+                || check_cong(&premises, f_args_flipped, g_args.as_slice()).is_ok()
+                || check_cong(&premises, f_args.as_slice(), g_args_flipped).is_ok()
+                || check_cong(&premises, f_args_flipped, g_args_flipped).is_ok();
+            return if any_valid { Ok(()) } else { original_result };
+        }
+
+        _ => get_cong_args(f, g)?,
+    };
     check_cong(&premises, f_args, g_args)
 }
 
-pub fn ho_cong(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn ho_cong(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     use std::iter::once;
 
     assert_clause_len(conclusion, 1)?;
@@ -233,6 +247,54 @@ pub fn ho_cong(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     check_cong(&premises, f_args, g_args)
 }
 
+/// Returns the subterms of `t` and `u` at the given position. The position is a list of indices,
+/// each of which selects an argument of an application or operation. The two terms must have the
+/// same structure along the path, and all the arguments that are not in the path must be equal.
+fn get_subterms_at<'a>(
+    t: &'a Rc<Term>,
+    u: &'a Rc<Term>,
+    path: &[usize],
+) -> Result<Vec<TermPair<'a>>, CheckerError> {
+    let mut result = vec![(t, u)];
+    for &i in path {
+        let (t, u) = *result.last().unwrap();
+        let (t_args, u_args) = get_cong_args(t, u)?;
+        if i >= t_args.len() {
+            return Err(CheckerError::NoIthChildInTerm(i, t.clone()));
+        }
+        for (j, (a, b)) in t_args.iter().zip(u_args).enumerate() {
+            rassert!(
+                j == i || a == b,
+                CongruenceError::MissingPremise(a.clone(), b.clone())
+            );
+        }
+        result.push((&t_args[i], &u_args[i]));
+    }
+    Ok(result)
+}
+
+pub fn rewrite(RuleArgs { conclusion, premises, args, .. }: ReadOnlyRuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    assert_num_premises(premises, 1)?;
+
+    let (s, s_prime) = match_term_err!((= s t) = get_premise_term(&premises[0])?)?;
+    let (t, t_prime) = match_term_err!((= t u) = &conclusion[0])?;
+    let path = args
+        .iter()
+        .map(Rc::as_usize_err)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (a, b) = *get_subterms_at(t, t_prime, &path)?.last().unwrap();
+    rassert!(
+        (a, b) == (s, s_prime) || (a, b) == (s_prime, s),
+        CongruenceError::PremiseDoesntJustifyArgs {
+            args: (a.clone(), b.clone()),
+            premise: (s.clone(), s_prime.clone()),
+        }
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -381,6 +443,8 @@ mod tests {
                 (declare-fun s () Bool)
                 (declare-fun x () Real)
                 (declare-fun y () Real)
+                (declare-fun u () Float32)
+                (declare-fun v () Float32)
             ",
             "Simple working examples" {
                 "(assume h1 (= a b))
@@ -391,6 +455,9 @@ mod tests {
                 (assume h2 (= r s))
                 (step t3 (cl (= (and p false s) (and q false r)))
                     :rule cong :premises (h1 h2))": true,
+
+                "(assume h1 (= u v))
+                (step t2 (cl (= (fp.add RNE u u) (fp.add RNE v u))) :rule cong :premises (h1))": true,
             }
             "Functions or operators don't match" {
                 "(assume h1 (= a b))
@@ -401,6 +468,9 @@ mod tests {
                 (assume h2 (= r s))
                 (step t3 (cl (= (and p false s) (or q false r)))
                     :rule cong :premises (h1 h2))": false,
+
+                "(assume h1 (= u v))
+                (step t2 (cl (= (fp.add RNE u u) (fp.add RTZ v u))) :rule cong :premises (h1))": false,
             }
             "No premises were given" {
                 "(step t1 (cl (= (f a true c) (f a true c))) :rule cong)": false,
@@ -483,4 +553,52 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn rewrite() {
+        test_cases! {
+            definitions = "
+                (declare-fun a () Int)
+                (declare-fun b () Int)
+                (declare-fun c () Int)
+                (declare-fun f (Int Int) Int)
+                (declare-fun p () Bool)
+            ",
+            "Simple working examples" {
+                "(assume h1 (= a b))
+                (step t2 (cl (= (f c a) (f c b))) :rule rewrite :premises (h1) :args (1))": true,
+
+                "(assume h1 (= a b))
+                (step t2 (cl (= (and p (< (+ c a) 0)) (and p (< (+ c b) 0))))
+                    :rule rewrite :premises (h1) :args (1 0 1))": true,
+
+                "(assume h1 (= (+ c a) c))
+                (step t2 (cl (= (< (+ c a) 0) (< c 0))) :rule rewrite :premises (h1) :args (0))": true,
+
+                "(assume h1 (= a b))
+                (step t2 (cl (= a b)) :rule rewrite :premises (h1))": true,
+            }
+            "Premise may be flipped" {
+                "(assume h1 (= b a))
+                (step t2 (cl (= (f c a) (f c b))) :rule rewrite :premises (h1) :args (1))": true,
+            }
+            "Wrong position" {
+                "(assume h1 (= a b))
+                (step t2 (cl (= (f c a) (f c b))) :rule rewrite :premises (h1) :args (0))": false,
+
+                "(assume h1 (= a b))
+                (step t2 (cl (= (f c a) (f c b))) :rule rewrite :premises (h1) :args (2))": false,
+
+                "(assume h1 (= a b))
+                (step t2 (cl (= (f c a) (f c b))) :rule rewrite :premises (h1) :args (1 0))": false,
+            }
+            "Terms differ outside of the position" {
+                "(assume h1 (= a b))
+                (step t2 (cl (= (f c a) (f a b))) :rule rewrite :premises (h1) :args (1))": false,
+
+                "(assume h1 (= a b))
+                (step t2 (cl (= (f c a) (+ c b))) :rule rewrite :premises (h1) :args (1))": false,
+            }
+        }
+    }
 }
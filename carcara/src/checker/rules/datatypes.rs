@@ -0,0 +1,194 @@
+//! Rules for the theory of algebraic datatypes. These are not yet in the specification for the
+//! Alethe format, and follow the datatype reasoning steps produced by cvc5.
+
+use super::{
+    assert_clause_len, assert_eq, assert_num_premises, ReadOnlyRuleArgs, RuleArgs, RuleResult,
+};
+use crate::{ast::*, checker::error::CheckerError};
+
+/// If the term is a datatype tester application `((_ is C) t)`, returns the constructor `C` and the
+/// term `t`.
+fn match_tester(term: &Rc<Term>) -> Result<(&Rc<Term>, &Rc<Term>), CheckerError> {
+    match term.as_ref() {
+        Term::ParamOp {
+            op: ParamOperator::DtTester,
+            op_args,
+            args,
+        } => Ok((&op_args[0], &args[0])),
+        _ => Err(CheckerError::TermOfWrongForm("((_ is C) t)", term.clone())),
+    }
+}
+
+/// Returns the constructor of a term of the form `(C t1 ... tn)`, or of a nullary constructor `C`,
+/// together with its arguments.
+fn match_constructor_app<'a>(
+    prelude: &'a ProblemPrelude,
+    term: &'a Rc<Term>,
+) -> Result<(&'a ConstructorDef, &'a [Rc<Term>]), CheckerError> {
+    let (head, args) = match term.as_ref() {
+        Term::App(f, args) => (f, args.as_slice()),
+        _ => (term, [].as_slice()),
+    };
+    match prelude.constructor(head) {
+        Some((_, c)) if c.selectors.len() == args.len() => Ok((c, args)),
+        _ => Err(CheckerError::NotADatatypeConstructor(head.clone())),
+    }
+}
+
+pub fn dt_split(
+    RuleArgs {
+        conclusion, premises, pool, prelude, ..
+    }: ReadOnlyRuleArgs,
+) -> RuleResult {
+    assert_num_premises(premises, 0)?;
+
+    assert_clause_len(conclusion, 1..)?;
+
+    let (_, t) = match_tester(&conclusion[0])?;
+    let sort = pool.sort(t);
+    let datatype = prelude
+        .datatype(sort.as_sort().unwrap())
+        .ok_or_else(|| CheckerError::NotOfDatatypeSort(t.clone()))?;
+    assert_clause_len(conclusion, datatype.constructors.len())?;
+
+    for (literal, def) in conclusion.iter().zip(&datatype.constructors) {
+        let (constructor, u) = match_tester(literal)?;
+        assert_eq(t, u)?;
+        assert_eq(&def.constructor, constructor)?;
+    }
+    Ok(())
+}
+
+pub fn dt_inst(
+    RuleArgs {
+        conclusion, premises, pool, prelude, ..
+    }: RuleArgs,
+) -> RuleResult {
+    assert_num_premises(premises, 0)?;
+    assert_clause_len(conclusion, 1)?;
+
+    let (tester, (t, instance)) = match_term_err!((= tester (= t instance)) = &conclusion[0])?;
+    let (constructor, u) = match_tester(tester)?;
+    assert_eq(t, u)?;
+
+    let (def, args) = match_constructor_app(prelude, instance)?;
+    assert_eq(&def.constructor, constructor)?;
+    for (arg, selector) in args.iter().zip(&def.selectors) {
+        let expected = pool.add(Term::App(selector.clone(), vec![t.clone()]));
+        assert_eq(arg, &expected)?;
+    }
+    Ok(())
+}
+
+pub fn dt_collapse(RuleArgs { conclusion, premises, prelude, .. }: ReadOnlyRuleArgs) -> RuleResult {
+    assert_num_premises(premises, 0)?;
+    assert_clause_len(conclusion, 1)?;
+
+    let (left, right) = match_term_err!((= l r) = &conclusion[0])?;
+    let (selector, inner) = match left.as_ref() {
+        Term::App(f, args) if args.len() == 1 => (f, &args[0]),
+        _ => {
+            return Err(CheckerError::TermOfWrongForm(
+                "(s (C t1 ... tn))",
+                left.clone(),
+            ))
+        }
+    };
+    let (selector_def, i) = prelude
+        .selector(selector)
+        .ok_or_else(|| CheckerError::NotADatatypeSelector(selector.clone()))?;
+    let (def, args) = match_constructor_app(prelude, inner)?;
+    assert_eq(&selector_def.constructor, &def.constructor)?;
+    assert_eq(&args[i], right)
+}
+
+pub fn dt_clash(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
+    assert_num_premises(premises, 0)?;
+    assert_clause_len(conclusion, 2)?;
+
+    let first = match_term_err!((not t) = &conclusion[0])?;
+    let second = match_term_err!((not t) = &conclusion[1])?;
+    let (c_1, t_1) = match_tester(first)?;
+    let (c_2, t_2) = match_tester(second)?;
+    assert_eq(t_1, t_2)?;
+    if c_1 == c_2 {
+        return Err(CheckerError::ExpectedDistinctConstructors(c_1.clone()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    const DEFINITIONS: &str = "
+        (declare-datatypes ((List 0) (Tree 0)) (
+            ((nil) (cons (head Int) (tail List)))
+            ((leaf) (node (children List)))
+        ))
+        (declare-fun xs () List)
+        (declare-fun t () Tree)
+        (declare-fun a () Int)
+    ";
+
+    #[test]
+    fn dt_split() {
+        test_cases! {
+            definitions = DEFINITIONS,
+            "Simple working examples" {
+                "(step t1 (cl ((_ is nil) xs) ((_ is cons) xs)) :rule dt_split)": true,
+                "(step t1 (cl ((_ is leaf) t) ((_ is node) t)) :rule dt_split)": true,
+            }
+            "Missing or wrong constructors" {
+                "(step t1 (cl ((_ is nil) xs)) :rule dt_split)": false,
+                "(step t1 (cl ((_ is cons) xs) ((_ is nil) xs)) :rule dt_split)": false,
+                "(step t1 (cl ((_ is nil) xs) ((_ is cons) (tail xs))) :rule dt_split)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn dt_inst() {
+        test_cases! {
+            definitions = DEFINITIONS,
+            "Simple working examples" {
+                "(step t1 (cl (= ((_ is cons) xs) (= xs (cons (head xs) (tail xs))))) :rule dt_inst)": true,
+                "(step t1 (cl (= ((_ is nil) xs) (= xs nil))) :rule dt_inst)": true,
+            }
+            "Wrong instantiation" {
+                "(step t1 (cl (= ((_ is cons) xs) (= xs nil))) :rule dt_inst)": false,
+                "(step t1 (cl (= ((_ is cons) xs) (= xs (cons a (tail xs))))) :rule dt_inst)": false,
+                "(step t1 (cl (= ((_ is node) t) (= t (node (tail xs))))) :rule dt_inst)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn dt_collapse() {
+        test_cases! {
+            definitions = DEFINITIONS,
+            "Simple working examples" {
+                "(step t1 (cl (= (head (cons a xs)) a)) :rule dt_collapse)": true,
+                "(step t1 (cl (= (tail (cons a xs)) xs)) :rule dt_collapse)": true,
+                "(step t1 (cl (= (children (node xs)) xs)) :rule dt_collapse)": true,
+            }
+            "Wrong selector or field" {
+                "(step t1 (cl (= (tail (cons a xs)) nil)) :rule dt_collapse)": false,
+                "(step t1 (cl (= (tail nil) nil)) :rule dt_collapse)": false,
+                "(step t1 (cl (= (head xs) a)) :rule dt_collapse)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn dt_clash() {
+        test_cases! {
+            definitions = DEFINITIONS,
+            "Simple working examples" {
+                "(step t1 (cl (not ((_ is nil) xs)) (not ((_ is cons) xs))) :rule dt_clash)": true,
+            }
+            "Same constructor or different terms" {
+                "(step t1 (cl (not ((_ is nil) xs)) (not ((_ is nil) xs))) :rule dt_clash)": false,
+                "(step t1 (cl (not ((_ is nil) xs)) (not ((_ is cons) (tail xs)))) :rule dt_clash)": false,
+            }
+        }
+    }
+}
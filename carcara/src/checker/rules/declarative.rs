@@ -0,0 +1,252 @@
+//! Transformations of "simplify" rules that are given declaratively, as rewrite rules in the
+//! `simplify.rules` file, instead of being implemented by hand. The file is embedded in the binary,
+//! and parsed the first time one of its rules is used.
+
+use crate::{
+    ast::*,
+    parser::{Lexer, Token},
+    utils::SExpr,
+};
+use indexmap::IndexMap;
+use std::{collections::HashMap, sync::OnceLock};
+
+const SPEC: &str = include_str!("simplify.rules");
+
+/// A pattern or result in a rewrite rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pattern {
+    Var(String),
+    Bool(bool),
+    Op(Operator, Vec<Pattern>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RewriteRule {
+    pattern: Pattern,
+    result: Pattern,
+}
+
+/// The rewrite rules of each "simplify" rule, in the order they appear in the spec.
+type Spec = HashMap<String, Vec<RewriteRule>>;
+
+impl Pattern {
+    fn from_sexpr(sexpr: &SExpr) -> Result<Self, String> {
+        match sexpr {
+            SExpr::Atom(s) if s == "true" => Ok(Pattern::Bool(true)),
+            SExpr::Atom(s) if s == "false" => Ok(Pattern::Bool(false)),
+            SExpr::Atom(s) if s.parse::<Operator>().is_ok() => {
+                Err(format!("operator '{}' is not applied", s))
+            }
+            SExpr::Atom(s) => Ok(Pattern::Var(s.clone())),
+            SExpr::List(list) => {
+                let (head, args) = match list.as_slice() {
+                    [SExpr::Atom(head), args @ ..] if !args.is_empty() => (head, args),
+                    _ => return Err("expected operator application".to_owned()),
+                };
+                let op = head
+                    .parse()
+                    .map_err(|()| format!("unknown operator '{}'", head))?;
+                let args = args
+                    .iter()
+                    .map(Self::from_sexpr)
+                    .collect::<Result<_, _>>()?;
+                Ok(Pattern::Op(op, args))
+            }
+        }
+    }
+
+    fn vars<'a>(&'a self, acc: &mut Vec<&'a str>) {
+        match self {
+            Pattern::Var(v) => acc.push(v),
+            Pattern::Bool(_) => (),
+            Pattern::Op(_, args) => args.iter().for_each(|a| a.vars(acc)),
+        }
+    }
+}
+
+/// Reads the s-expressions in the spec, using the same lexer as the Alethe parser, so comments,
+/// quoted symbols and string literals are handled as in SMT-LIB. Since patterns only contain
+/// symbols, any other token is an error.
+fn read_spec(src: &str) -> Result<Vec<SExpr>, String> {
+    let mut lexer = Lexer::new(src.as_bytes()).map_err(|e| e.to_string())?;
+    let mut stack = vec![Vec::new()];
+    loop {
+        let (token, (line, column)) = lexer.next_token().map_err(|e| e.to_string())?;
+        match token {
+            Token::OpenParen => stack.push(Vec::new()),
+            Token::CloseParen => {
+                let list = stack.pop().filter(|_| !stack.is_empty());
+                let list = list.ok_or_else(|| format!("unexpected ')' at {}:{}", line, column))?;
+                stack.last_mut().unwrap().push(SExpr::List(list));
+            }
+            Token::Symbol(s) => stack.last_mut().unwrap().push(SExpr::Atom(s)),
+            Token::Eof => break,
+            other => {
+                return Err(format!(
+                    "unexpected token {:?} at {}:{}",
+                    other, line, column
+                ))
+            }
+        }
+    }
+    match stack.len() {
+        1 => Ok(stack.pop().unwrap()),
+        _ => Err("unclosed '('".to_owned()),
+    }
+}
+
+fn parse_spec(src: &str) -> Result<Spec, String> {
+    let mut spec = Spec::new();
+    for entry in read_spec(src)? {
+        let entry = match entry {
+            SExpr::List(list) => <[SExpr; 3]>::try_from(list).ok(),
+            SExpr::Atom(_) => None,
+        };
+        let Some([SExpr::Atom(rule), pattern, result]) = entry else {
+            return Err("expected entry of the form (<rule> <pattern> <result>)".to_owned());
+        };
+        let pattern = Pattern::from_sexpr(&pattern)?;
+        let result = Pattern::from_sexpr(&result)?;
+        if !matches!(pattern, Pattern::Op(..)) {
+            return Err(format!(
+                "pattern in '{}' must be an operator application",
+                rule
+            ));
+        }
+        let (mut pattern_vars, mut result_vars) = (Vec::new(), Vec::new());
+        pattern.vars(&mut pattern_vars);
+        result.vars(&mut result_vars);
+        if let Some(v) = result_vars.iter().find(|v| !pattern_vars.contains(v)) {
+            return Err(format!(
+                "variable '{}' in '{}' is not bound by pattern",
+                v, rule
+            ));
+        }
+        spec.entry(rule)
+            .or_default()
+            .push(RewriteRule { pattern, result });
+    }
+    Ok(spec)
+}
+
+fn spec() -> &'static Spec {
+    static SPEC_RULES: OnceLock<Spec> = OnceLock::new();
+    SPEC_RULES.get_or_init(|| parse_spec(SPEC).unwrap_or_else(|e| panic!("invalid spec: {}", e)))
+}
+
+/// Returns `true` if the term has the same structure as the pattern's arguments. The bindings of
+/// the pattern variables are added to `bindings`.
+fn match_args(
+    patterns: &[Pattern],
+    terms: &[Rc<Term>],
+    bindings: &mut IndexMap<String, Rc<Term>>,
+) -> bool {
+    patterns.len() == terms.len()
+        && patterns
+            .iter()
+            .zip(terms)
+            .all(|(p, t)| match_pattern(p, t, bindings))
+}
+
+fn match_pattern(
+    pattern: &Pattern,
+    term: &Rc<Term>,
+    bindings: &mut IndexMap<String, Rc<Term>>,
+) -> bool {
+    match pattern {
+        Pattern::Var(v) => match bindings.get(v) {
+            Some(bound) => bound == term,
+            None => {
+                bindings.insert(v.clone(), term.clone());
+                true
+            }
+        },
+        Pattern::Bool(b) => term.is_bool_constant(*b),
+        Pattern::Op(op, args) => match term.as_ref() {
+            Term::Op(term_op, term_args) if term_op == op => match_args(args, term_args, bindings),
+            _ => false,
+        },
+    }
+}
+
+fn instantiate(
+    pool: &mut dyn TermPool,
+    pattern: &Pattern,
+    bindings: &IndexMap<String, Rc<Term>>,
+) -> Rc<Term> {
+    match pattern {
+        Pattern::Var(v) => bindings[v].clone(),
+        Pattern::Bool(b) => pool.bool_constant(*b),
+        Pattern::Op(op, args) => {
+            let args = args
+                .iter()
+                .map(|a| instantiate(pool, a, bindings))
+                .collect();
+            pool.add(Term::Op(*op, args))
+        }
+    }
+}
+
+/// Applies the first rewrite rule of the given "simplify" rule whose pattern matches the term.
+/// Returns `None` if no pattern matches, or if the rule has no rewrite rules in the spec.
+pub fn rewrite(rule: &str, term: &Term, pool: &mut dyn TermPool) -> Option<Rc<Term>> {
+    let Term::Op(op, args) = term else {
+        return None;
+    };
+    spec().get(rule)?.iter().find_map(|r| {
+        let Pattern::Op(pattern_op, pattern_args) = &r.pattern else {
+            unreachable!()
+        };
+        let mut bindings = IndexMap::new();
+        (pattern_op == op && match_args(pattern_args, args, &mut bindings))
+            .then(|| instantiate(pool, &r.result, &bindings))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::pool::PrimitivePool, parser::tests::parse_terms};
+
+    #[test]
+    fn test_spec() {
+        let spec = parse_spec(SPEC).unwrap();
+        assert_eq!(spec["bool_simplify"].len(), 7);
+
+        let mut p = PrimitivePool::new();
+        let definitions = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+        ";
+        let [a, b, c] = parse_terms(
+            &mut p,
+            definitions,
+            ["(=> (=> p q) q)", "(=> (=> p q) p)", "(or p q)"],
+        );
+        assert_eq!(rewrite("bool_simplify", &a, &mut p), Some(c.clone()));
+        assert_eq!(rewrite("bool_simplify", &b, &mut p), None);
+        assert_eq!(rewrite("bool_simplify", &c, &mut p), None);
+        assert_eq!(rewrite("not_a_rule", &a, &mut p), None);
+
+        for invalid in [
+            "(r (not p) q",
+            "(r (not p))",
+            "(r p q)",
+            "(r (not p) (and p q))",
+            "(r (foo p) p)",
+            "(r (not and) p)",
+            r#"(r (not ")") p)"#,
+        ] {
+            assert!(parse_spec(invalid).is_err(), "{}", invalid);
+        }
+
+        // Quoted symbols may contain parentheses and `;`, which don't start a comment
+        let spec = parse_spec("(r (not |a;(|) |a;(|) ; comment (").unwrap();
+        let var = Pattern::Var("a;(".to_owned());
+        let expected = RewriteRule {
+            pattern: Pattern::Op(Operator::Not, vec![var.clone()]),
+            result: var,
+        };
+        assert_eq!(spec["r"], [expected]);
+    }
+}
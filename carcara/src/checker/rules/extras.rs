@@ -1,13 +1,17 @@
 //! This module contains rules that are not yet in the specification for the Alethe format.
 
 use super::{
-    assert_clause_len, assert_eq, assert_num_premises, get_premise_term, CheckerError,
-    EqualityError, RuleArgs, RuleResult,
+    assert_clause_len, assert_eq, assert_num_args, assert_num_premises, get_premise_term,
+    CheckerError, EqualityError, ReadOnlyRuleArgs, RuleArgs, RuleResult,
+};
+use crate::{
+    ast::*,
+    checker::{interpretations::is_value, rules::assert_operation_len},
 };
-use crate::{ast::*, checker::rules::assert_operation_len};
 use indexmap::IndexSet;
+use std::panic::{self, AssertUnwindSafe};
 
-pub fn reordering(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn reordering(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
 
     let premise = premises[0].clause;
@@ -24,7 +28,7 @@ pub fn reordering(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult
     }
 }
 
-pub fn symm(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn symm(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 1)?;
 
@@ -35,7 +39,7 @@ pub fn symm(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(q_1, q_2)
 }
 
-pub fn not_symm(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_symm(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 1)?;
 
@@ -46,14 +50,14 @@ pub fn not_symm(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(q_1, q_2)
 }
 
-pub fn eq_symmetric(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn eq_symmetric(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let ((t_1, u_1), (u_2, t_2)) = match_term_err!((= (= t u) (= u t)) = &conclusion[0])?;
     assert_eq(t_1, t_2)?;
     assert_eq(u_1, u_2)
 }
 
-pub fn weakening(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn weakening(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     let premise = premises[0].clause;
     assert_clause_len(conclusion, premise.len()..)?;
@@ -69,7 +73,7 @@ pub fn bind_let(
         premises,
         previous_command,
         ..
-    }: RuleArgs,
+    }: ReadOnlyRuleArgs,
 ) -> RuleResult {
     let previous_command = previous_command.ok_or(CheckerError::MustBeLastStepInSubproof)?;
 
@@ -109,11 +113,11 @@ pub fn bind_let(
     assert_eq(right, phi_prime)
 }
 
-pub fn la_mult_pos(args: RuleArgs) -> RuleResult {
+pub fn la_mult_pos(args: ReadOnlyRuleArgs) -> RuleResult {
     la_mult_generic(args.conclusion, true)
 }
 
-pub fn la_mult_neg(args: RuleArgs) -> RuleResult {
+pub fn la_mult_neg(args: ReadOnlyRuleArgs) -> RuleResult {
     la_mult_generic(args.conclusion, false)
 }
 
@@ -176,7 +180,7 @@ fn la_mult_generic(conclusion: &[Rc<Term>], is_pos: bool) -> RuleResult {
     assert_eq(r, r_2)
 }
 
-pub fn mod_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn mod_simplify(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let (left, right) = match_term_err!((= l r) = &conclusion[0])?;
     let (t1, t2) = match_term_err!((mod t1 t2) = left)?;
@@ -206,6 +210,77 @@ pub fn mod_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
+/// Evaluates an application of a function to values, using the interpretation of the function that
+/// was registered in the checker configuration.
+pub fn evaluate(
+    RuleArgs {
+        conclusion, pool, interpretations, ..
+    }: RuleArgs,
+) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    let (application, value) = match_term_err!((= a v) = &conclusion[0])?;
+
+    let not_interpreted = || CheckerError::ExpectedInterpretedApplication(application.clone());
+    let Term::App(function, args) = application.as_ref() else {
+        return Err(not_interpreted());
+    };
+    let name = function.as_var().ok_or_else(not_interpreted)?;
+    let interpretation = interpretations
+        .get(name)
+        .ok_or_else(|| CheckerError::NoInterpretation(name.to_owned()))?;
+    if let Some(arg) = args.iter().find(|a| !is_value(a)) {
+        return Err(CheckerError::IsNotValue(arg.clone()));
+    }
+
+    // Interpretations are user code, so a panic in one of them is reported as a failed evaluation
+    // instead of aborting the checker
+    let result = panic::catch_unwind(AssertUnwindSafe(|| interpretation(pool, args)))
+        .ok()
+        .flatten()
+        .ok_or_else(|| CheckerError::EvaluationFailed(name.to_owned(), application.clone()))?;
+    if !is_value(&result) {
+        return Err(CheckerError::IsNotValue(result));
+    }
+    assert_eq(value, &result)
+}
+
+/// Checks a rewrite justified by a RARE rule. The first argument is the name of the rule, and the
+/// remaining arguments are the values of its parameters. Each condition of the rule must be either
+/// `true` or the conclusion of one of the premises, once the rule is instantiated.
+pub fn rare_rewrite(
+    RuleArgs {
+        conclusion,
+        premises,
+        args,
+        pool,
+        rare_rules,
+        ..
+    }: RuleArgs,
+) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    let (lhs, rhs) = match_term_err!((= l r) = &conclusion[0])?;
+
+    assert_num_args(args, 1..)?;
+    let (name, values) = args.split_first().unwrap();
+    let Term::Const(Constant::String(name)) = name.as_ref() else {
+        return Err(CheckerError::ExpectedRareRuleName(name.clone()));
+    };
+    let rule = rare_rules
+        .get(name)
+        .ok_or_else(|| CheckerError::UnknownRareRule(name.clone()))?;
+    let instance = rule.instantiate(pool, values)?;
+
+    for condition in &instance.conditions {
+        let is_premise = premises.iter().any(|p| p.clause == [condition.clone()]);
+        rassert!(
+            condition.is_bool_true() || is_premise,
+            CheckerError::RareConditionNotMet(condition.clone())
+        );
+    }
+    assert_eq(lhs, &instance.lhs)?;
+    assert_eq(rhs, &instance.rhs)
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -1,4 +1,7 @@
-use super::{assert_clause_len, assert_eq, assert_num_args, RuleArgs, RuleResult};
+use super::{
+    assert_clause_len, assert_eq, assert_num_args, assert_num_premises, get_premise_term,
+    ReadOnlyRuleArgs, RuleArgs, RuleResult,
+};
 use crate::{
     ast::*,
     checker::error::{CheckerError, LinearArithmeticError},
@@ -6,7 +9,7 @@ use crate::{
 use indexmap::{map::Entry, IndexMap};
 use rug::{ops::NegAssign, Integer, Rational};
 
-pub fn la_rw_eq(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn la_rw_eq(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let ((t_1, u_1), ((t_2, u_2), (u_3, t_3))) = match_term_err!(
@@ -64,6 +67,25 @@ fn negate_disequality(term: &Rc<Term>) -> Result<(Operator, LinearComb, LinearCo
 #[derive(Debug)]
 pub struct LinearComb(pub(crate) IndexMap<Rc<Term>, Rational>, pub(crate) Rational);
 
+/// A linear combination is serialized as a pair, whose first element is the list of variables and
+/// their coefficients, and whose second element is the constant term.
+#[cfg(feature = "serde")]
+impl serde::Serialize for LinearComb {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeTuple;
+
+        let vars: Vec<_> = self
+            .0
+            .iter()
+            .map(|(var, coeff)| (var.to_string(), coeff.to_string()))
+            .collect();
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&vars)?;
+        tuple.serialize_element(&self.1.to_string())?;
+        tuple.end()
+    }
+}
+
 impl LinearComb {
     fn new() -> Self {
         Self(IndexMap::new(), Rational::new())
@@ -198,9 +220,57 @@ impl LinearComb {
         // have to return one instead
         std::cmp::max(Integer::from(1), result)
     }
+
+    /// Returns `true` if the linear combination has at least one variable, all of its variables
+    /// are of sort `Int`, and all of their coefficients are integers. In that case, the value of
+    /// the left side of a disequality over this linear combination is always an integer multiple
+    /// of the GCD of the coefficients.
+    fn is_over_integers(&self, pool: &(impl TermPoolView + ?Sized)) -> bool {
+        !self.0.is_empty()
+            && self.0.iter().all(|(var, coeff)| {
+                coeff.is_integer() && pool.sort(var).as_sort() == Some(&Sort::Int)
+            })
+    }
+
+    /// Finds the greatest common divisor of the coefficients of the variables in the linear
+    /// combination, ignoring the constant term. This assumes that all coefficients are integers.
+    fn variable_coefficients_gcd(&self) -> Integer {
+        let mut result = Integer::new();
+        for coeff in self.0.values() {
+            result.gcd_mut(coeff.numer());
+        }
+        std::cmp::max(Integer::from(1), result)
+    }
+}
+
+/// Applies integer tightening to a disequality of the form `s > d` or `s >= d`, where `s` is a
+/// linear combination over integer variables. Since `s` is always an integer multiple of the GCD
+/// `g` of its coefficients, a strict disequality `s > d` is equivalent to `s >= floor(d) + 1`, and
+/// a non-strict disequality `s >= d` is equivalent to `s >= g * ceil(d / g)`. This allows
+/// disequalities with non-integer bounds, or that mix strict and non-strict bounds, to be
+/// tightened as much as possible.
+fn tighten(op: Operator, disequality: &mut LinearComb) -> Operator {
+    if op == Operator::GreaterThan {
+        disequality.1.floor_mut();
+        disequality.1 += 1;
+    }
+    let gcd = disequality.variable_coefficients_gcd();
+    disequality.1 /= &gcd;
+    disequality.1.ceil_mut();
+    disequality.1 *= gcd;
+    Operator::GreaterEq
 }
 
-fn strengthen(op: Operator, disequality: &mut LinearComb, a: &Rational) -> Operator {
+fn strengthen(
+    op: Operator,
+    disequality: &mut LinearComb,
+    a: &Rational,
+    is_over_integers: bool,
+) -> Operator {
+    if is_over_integers && matches!(op, Operator::GreaterThan | Operator::GreaterEq) {
+        return tighten(op, disequality);
+    }
+
     // Multiplications are expensive, so we avoid them if we can
     let is_integer = if *a == 0 {
         true
@@ -257,7 +327,7 @@ fn strengthen(op: Operator, disequality: &mut LinearComb, a: &Rational) -> Opera
     }
 }
 
-pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
+pub fn la_generic(RuleArgs { conclusion, args, pool, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_args(args, conclusion.len())?;
 
     let args: Vec<_> = args
@@ -290,7 +360,8 @@ pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
             }
 
             // Step 4: Apply strengthening rules
-            let op = strengthen(op, &mut disequality, &a);
+            let is_over_integers = disequality.is_over_integers(pool);
+            let op = strengthen(op, &mut disequality, &a, is_over_integers);
 
             // Step 5: Multiply disequality by a
             let a = match op {
@@ -339,7 +410,19 @@ pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn la_disequality(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+/// A `lia_generic` step is usually a hole in the proof, that can only be filled by calling an
+/// external solver. However, if the step includes Farkas coefficients in its arguments, it can be
+/// checked just like a `la_generic` step, since a certificate that the negated clause is
+/// unsatisfiable over the rationals also shows that it is unsatisfiable over the integers.
+pub fn lia_generic(rule_args: ReadOnlyRuleArgs) -> RuleResult {
+    if rule_args.args.is_empty() {
+        tracing::warn!("encountered \"lia_generic\" rule, ignoring");
+        return Ok(());
+    }
+    la_generic(rule_args)
+}
+
+pub fn la_disequality(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let ((t1_1, t2_1), (t1_2, t2_2), (t2_3, t1_3)) = match_term_err!(
@@ -351,7 +434,7 @@ pub fn la_disequality(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_eq(t2_2, t2_3)
 }
 
-pub fn la_totality(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn la_totality(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let ((t1_1, t2_1), (t2_2, t1_2)) =
@@ -361,6 +444,31 @@ pub fn la_totality(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_eq(t2_1, t2_2)
 }
 
+/// If the term is an inequality stating that `t <= u`, written as `(<= t u)` or `(>= u t)`, returns
+/// `t` and `u`.
+fn match_less_eq(term: &Rc<Term>) -> Result<(&Rc<Term>, &Rc<Term>), CheckerError> {
+    if let Some((u, t)) = match_term!((>= u t) = term) {
+        return Ok((t, u));
+    }
+    match_term_err!((<= t u) = term)
+}
+
+pub fn la_antisymmetry(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
+    assert_num_premises(premises, 2)?;
+    assert_clause_len(conclusion, 1)?;
+
+    let (t, u) = match_term_err!((= t u) = &conclusion[0])?;
+    let (a_1, b_1) = match_less_eq(get_premise_term(&premises[0])?)?;
+    let (a_2, b_2) = match_less_eq(get_premise_term(&premises[1])?)?;
+
+    // The premises must state that `t <= u` and `u <= t`, in any order
+    let (t, u) = if a_1 == t { (t, u) } else { (u, t) };
+    assert_eq(a_1, t)?;
+    assert_eq(b_1, u)?;
+    assert_eq(a_2, u)?;
+    assert_eq(b_2, t)
+}
+
 fn assert_less_than(a: &Rc<Term>, b: &Rc<Term>) -> RuleResult {
     rassert!(
         a.as_signed_number_err()? < b.as_signed_number_err()?,
@@ -377,7 +485,7 @@ fn assert_less_eq(a: &Rc<Term>, b: &Rc<Term>) -> RuleResult {
     Ok(())
 }
 
-pub fn la_tautology(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn la_tautology(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     if let Some((first, second)) = match_term!((or phi_1 phi_2) = conclusion[0]) {
@@ -469,6 +577,14 @@ mod tests {
                 "(step t1 (cl (= (= b a) (and (<= a b) (<= b a)))) :rule la_rw_eq)": false,
                 "(step t1 (cl (= (= x y) (and (<= x y) (<= x y)))) :rule la_rw_eq)": false,
             }
+            "Integer division, modulo and absolute value" {
+                "(step t1 (cl (= (= (div a 2) (mod b 3))
+                    (and (<= (div a 2) (mod b 3)) (<= (mod b 3) (div a 2))))) :rule la_rw_eq)": true,
+                "(step t1 (cl (= (= (abs a) b) (and (<= (abs a) b) (<= b (abs a)))))
+                    :rule la_rw_eq)": true,
+                "(step t1 (cl (= (= (div a 2) b) (and (<= (div a 2) b) (<= b (div a 3)))))
+                    :rule la_rw_eq)": false,
+            }
         }
     }
 
@@ -522,6 +638,47 @@ mod tests {
                     (not (<= m 1))
                 ) :rule la_generic :args (1 1 1 1))": true,
             }
+            "Integer tightening" {
+                "(step t1 (cl (< (* 2 n) 1) (> (* 2 n) 1)) :rule la_generic :args (1 1))": true,
+                "(step t1 (cl (< (* 2 n) 1) (>= (* 2 n) 2)) :rule la_generic :args (1 1))": true,
+                "(step t1 (cl (<= (* 3 n) 1) (> (+ (* 3 n) (* 6 m)) 2))
+                    :rule la_generic :args (1 1))": false,
+                "(step t1 (cl (< (* 2.0 a) 1.0) (> (* 2.0 a) 1.0))
+                    :rule la_generic :args (1.0 1.0))": false,
+            }
+        }
+    }
+
+    #[test]
+    fn lia_generic() {
+        test_cases! {
+            definitions = "
+                (declare-fun m () Int)
+                (declare-fun n () Int)
+            ",
+            "Steps without arguments are holes" {
+                "(step t1 (cl (> m 0) (> n 0)) :rule lia_generic)": true,
+            }
+            "Steps with Farkas coefficients" {
+                "(step t1 (cl (> m 0) (<= m 0)) :rule lia_generic :args (1 1))": true,
+                "(step t1 (cl (not (< m n)) (>= (- n m) 1)) :rule lia_generic :args (1 1))": true,
+                "(step t1 (cl (> m 0) (< m 0)) :rule lia_generic :args (1 1))": false,
+                "(step t1 (cl (> m 0) (<= m 0)) :rule lia_generic :args (1))": false,
+            }
+        }
+    }
+
+    #[test]
+    fn nla_generic() {
+        test_cases! {
+            definitions = "
+                (declare-fun x () Real)
+                (declare-fun y () Real)
+            ",
+            "Steps are holes" {
+                "(step t1 (cl (>= (* x x) 0.0)) :rule nla_generic)": true,
+                "(step t1 (cl (not (= (* x y) 1.0)) (not (= x 0.0))) :rule nla_generic)": true,
+            }
         }
     }
 
@@ -570,6 +727,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn la_antisymmetry() {
+        test_cases! {
+            definitions = "
+                (declare-fun a () Int)
+                (declare-fun b () Int)
+                (declare-fun x () Real)
+                (declare-fun y () Real)
+            ",
+            "Simple working examples" {
+                "(assume h1 (<= a b)) (assume h2 (<= b a))
+                (step t1 (cl (= a b)) :rule la_antisymmetry :premises (h1 h2))": true,
+
+                "(assume h1 (<= x y)) (assume h2 (<= y x))
+                (step t1 (cl (= y x)) :rule la_antisymmetry :premises (h1 h2))": true,
+            }
+            "Inequalities written with `>=`" {
+                "(assume h1 (>= b a)) (assume h2 (<= b a))
+                (step t1 (cl (= a b)) :rule la_antisymmetry :premises (h1 h2))": true,
+
+                "(assume h1 (>= y x)) (assume h2 (>= x y))
+                (step t1 (cl (= x y)) :rule la_antisymmetry :premises (h2 h1))": true,
+            }
+            "Premises don't imply the equality" {
+                "(assume h1 (<= a b)) (assume h2 (<= a b))
+                (step t1 (cl (= a b)) :rule la_antisymmetry :premises (h1 h2))": false,
+
+                "(assume h1 (<= a b)) (assume h2 (< b a))
+                (step t1 (cl (= a b)) :rule la_antisymmetry :premises (h1 h2))": false,
+
+                "(assume h1 (<= x y)) (assume h2 (<= y x))
+                (step t1 (cl (= x x)) :rule la_antisymmetry :premises (h1 h2))": false,
+            }
+        }
+    }
+
     #[test]
     fn la_tautology() {
         test_cases! {
@@ -1,5 +1,7 @@
 use super::{
     error::{CheckerError, EqualityError},
+    interpretations::Interpretations,
+    rare::RareRules,
     ContextStack,
 };
 use crate::{
@@ -10,15 +12,42 @@ use std::time::Duration;
 
 pub type RuleResult = Result<(), CheckerError>;
 
-pub type Rule = fn(RuleArgs) -> RuleResult;
+/// The function that checks a rule. Rules that only read from the term pool take a
+/// [`ReadOnlyRuleArgs`], so they can be checked holding only a shared reference to the pool.
+#[derive(Clone, Copy)]
+pub enum Rule {
+    ReadOnly(fn(ReadOnlyRuleArgs) -> RuleResult),
+    Mutating(fn(RuleArgs) -> RuleResult),
+}
+
+impl Rule {
+    /// Checks the rule, giving read-only rules a view of the pool in `args`.
+    pub(super) fn check(self, args: RuleArgs) -> RuleResult {
+        match self {
+            Rule::ReadOnly(rule) => rule(args.read_only()),
+            Rule::Mutating(rule) => rule(args),
+        }
+    }
+}
 
-pub struct RuleArgs<'a> {
+/// The arguments given to a rule. By default, the rule can add terms to the pool, but read-only
+/// rules only get a [`TermPoolView`] (see [`ReadOnlyRuleArgs`]).
+pub struct RuleArgs<'a, P = &'a mut dyn TermPool> {
     pub(super) conclusion: &'a [Rc<Term>],
     pub(super) premises: &'a [Premise<'a>],
     pub(super) args: &'a [Rc<Term>],
-    pub(super) pool: &'a mut dyn TermPool,
+    pub(super) pool: P,
     pub(super) context: &'a mut ContextStack,
 
+    // The problem prelude, for rules that depend on the declared datatypes
+    pub(super) prelude: &'a ProblemPrelude,
+
+    // The interpretations of uninterpreted functions, for the `evaluate` rule
+    pub(super) interpretations: &'a Interpretations,
+
+    // The database of RARE rules, for the `rare_rewrite` rule
+    pub(super) rare_rules: &'a RareRules,
+
     // For rules that end a subproof, we need to pass the previous command in the subproof that it
     // is closing, because it may be implicitly referenced, and it is not given as premises. If a
     // rule is not ending a subproof, this should be `None`.
@@ -28,6 +57,35 @@ pub struct RuleArgs<'a> {
     pub(super) polyeq_time: &'a mut Duration,
 }
 
+/// The arguments given to a rule that only reads from the term pool.
+pub type ReadOnlyRuleArgs<'a> = RuleArgs<'a, &'a dyn TermPoolView>;
+
+impl<'a, P> RuleArgs<'a, P> {
+    /// Replaces the pool given to the rule by the result of `f`.
+    pub(super) fn map_pool<Q>(self, f: impl FnOnce(P) -> Q) -> RuleArgs<'a, Q> {
+        RuleArgs {
+            conclusion: self.conclusion,
+            premises: self.premises,
+            args: self.args,
+            pool: f(self.pool),
+            context: self.context,
+            prelude: self.prelude,
+            interpretations: self.interpretations,
+            rare_rules: self.rare_rules,
+            previous_command: self.previous_command,
+            discharge: self.discharge,
+            polyeq_time: self.polyeq_time,
+        }
+    }
+}
+
+impl<'a> RuleArgs<'a> {
+    /// Turns these into the arguments of a read-only rule, which only get a view of the pool.
+    pub(super) fn read_only(self) -> ReadOnlyRuleArgs<'a> {
+        self.map_pool(|pool| pool.as_view())
+    }
+}
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct Premise<'a> {
     pub id: &'a str,
@@ -157,47 +215,21 @@ fn assert_is_bool_constant(got: &Rc<Term>, expected: bool) -> RuleResult {
 
 #[cfg(test)]
 fn run_tests(test_name: &str, definitions: &str, cases: &[(&str, bool)]) {
-    use crate::{checker, parser};
-    use std::io::Cursor;
+    use crate::{checker, Error};
 
     for (i, (proof, expected)) in cases.iter().enumerate() {
         // This parses the definitions again for every case, which is not ideal
-        let (mut problem, mut proof, mut pool) = parser::parse_instance(
-            Cursor::new(definitions),
-            Cursor::new(proof),
-            parser::Config::new(),
-        )
-        .unwrap_or_else(|e| panic!("parser error during test \"{}\": {}", test_name, e));
-
-        // Since rule tests often use `assume` commands to introduce premises, we search the proof
-        // for all `assume`d terms and retroactively add them as the problem premises, to avoid
-        // checker errors on the `assume`s
-        problem.premises = proof
-            .commands
-            .iter()
-            .filter_map(|c| match c {
-                ProofCommand::Assume { term, .. } => Some(term.clone()),
-                _ => None,
-            })
-            .collect();
-
-        // All proofs must eventually reach the empty clause, so to avoid having to add a dummy
-        // `(step end (cl) :rule hole)` to every rule test, we add this dummy step here
-        proof.commands.push(ProofCommand::Step(ProofStep {
-            id: "end".into(),
-            clause: Vec::new(),
-            rule: "hole".into(),
-            premises: Vec::new(),
-            args: Vec::new(),
-            discharge: Vec::new(),
-        }));
-
-        let mut checker = checker::ProofChecker::new(&mut pool, checker::Config::new());
-        let got = checker.check(&problem, &proof).is_ok();
+        let result =
+            checker::conformance::check_rule_example(definitions, proof, checker::Config::new());
+        if let Err(e @ Error::Parser(..)) = &result {
+            panic!("parser error during test \"{}\": {}", test_name, e);
+        }
         assert_eq!(
-            *expected, got,
+            *expected,
+            result.is_ok(),
             "test case \"{}\" index {} failed",
-            test_name, i
+            test_name,
+            i
         );
     }
 }
@@ -219,9 +251,12 @@ macro_rules! test_cases {
 
 // Since the rule submodules use the `test_cases` macro, we have to declare them here, after the
 // macro is declared
+pub(super) mod arrays;
 pub(super) mod bitvectors;
 pub(super) mod clausification;
 pub(super) mod congruence;
+pub(super) mod datatypes;
+mod declarative;
 pub(super) mod extras;
 pub(super) mod linear_arithmetic;
 pub(super) mod quantifier;
@@ -1,14 +1,27 @@
 use super::{
     assert_alpha_equiv_expected, assert_clause_len, assert_eq, assert_is_expected, assert_num_args,
-    CheckerError, RuleArgs, RuleResult,
+    assert_num_premises, get_premise_term, CheckerError, ReadOnlyRuleArgs, RuleArgs, RuleResult,
 };
 use crate::{ast::*, checker::error::QuantifierError, utils::DedupIterator};
 use indexmap::{IndexMap, IndexSet};
 
-pub fn forall_inst(
+pub fn forall_inst(args: RuleArgs) -> RuleResult {
+    check_forall_inst(args, false)
+}
+
+/// A variant of `forall_inst` that allows `Int`/`Real` subtyping in the instantiation arguments.
+/// If a binding of sort `Real` is instantiated with a term of sort `Int`, the term is coerced to
+/// `Real` before being substituted. Integer constants are converted to the equivalent real
+/// constant, and other terms are wrapped in a `to_real` application.
+pub fn forall_inst_with_subtyping(args: RuleArgs) -> RuleResult {
+    check_forall_inst(args, true)
+}
+
+fn check_forall_inst(
     RuleArgs {
         conclusion, args, pool, polyeq_time, ..
     }: RuleArgs,
+    allow_int_real_subtyping: bool,
 ) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
@@ -17,25 +30,103 @@ pub fn forall_inst(
 
     assert_num_args(args, bindings.len())?;
 
+    let substitution = build_instantiation(pool, bindings, args, allow_int_real_subtyping)?;
+    let mut substitution = Substitution::new(pool, substitution)?;
+
+    // Equalities may be reordered, and the application of the substitution might rename bound
+    // variables, so we need to compare for alpha-equivalence here
+    let expected = substitution.apply(pool, original);
+    assert_alpha_equiv_expected(substituted, expected, polyeq_time)
+}
+
+/// Builds the substitution that maps each variable in `bindings` to the corresponding argument,
+/// checking that their sorts match.
+fn build_instantiation(
+    pool: &mut dyn TermPool,
+    bindings: &BindingList,
+    args: &[Rc<Term>],
+    allow_int_real_subtyping: bool,
+) -> Result<IndexMap<Rc<Term>, Rc<Term>>, CheckerError> {
     // iterate over the bindings and arguments simultaneously, building the substitution
-    let substitution: IndexMap<_, _> = bindings
+    bindings
         .iter()
         .zip(args)
         .map(|((var_name, sort), value)| {
-            assert_eq(sort, &pool.sort(value))?;
+            let value_sort = pool.sort(value);
+            let value = if value_sort == *sort {
+                value.clone()
+            } else if allow_int_real_subtyping
+                && sort.as_sort() == Some(&Sort::Real)
+                && value_sort.as_sort() == Some(&Sort::Int)
+            {
+                coerce_to_real(pool, value)
+            } else {
+                return Err(QuantifierError::ForallInstArgWrongSort {
+                    binding: var_name.clone(),
+                    expected: sort.clone(),
+                    arg: value.clone(),
+                    got: value_sort,
+                }
+                .into());
+            };
             let var = pool.add(Term::new_var(var_name, sort.clone()));
-            Ok((var.clone(), value.clone()))
+            Ok((var, value))
         })
-        .collect::<Result<_, CheckerError>>()?;
-    let mut substitution = Substitution::new(pool, substitution)?;
+        .collect()
+}
 
-    // Equalities may be reordered, and the application of the substitution might rename bound
-    // variables, so we need to compare for alpha-equivalence here
+/// Eliminates an existential quantifier by instantiating its bound variables with explicit
+/// witnesses, given as arguments. This is an alternative to `sko_ex` for proof producers that
+/// don't use `choice` terms to represent Skolem constants.
+///
+/// Each witness must be a constant that does not appear in the quantified formula, and the
+/// witnesses must be pairwise distinct. Additionally, each witness must not appear in the problem
+/// premises or in any command before this step. Since that depends on the whole proof, it is not
+/// checked here, but in the `witnesses` module.
+pub fn exists_elim(
+    RuleArgs {
+        conclusion,
+        premises,
+        args,
+        pool,
+        polyeq_time,
+        ..
+    }: RuleArgs,
+) -> RuleResult {
+    assert_num_premises(premises, 1)?;
+    assert_clause_len(conclusion, 1)?;
+
+    let premise = get_premise_term(&premises[0])?;
+    let (bindings, original) = match_term_err!((exists ... original) = premise)?;
+    assert_num_args(args, bindings.len())?;
+
+    let free_vars = pool.free_vars(premise);
+    let mut seen = IndexSet::new();
+    for witness in args {
+        rassert!(
+            witness.is_var(),
+            QuantifierError::WitnessIsNotConstant(witness.clone())
+        );
+        rassert!(
+            !free_vars.contains(witness) && seen.insert(witness),
+            QuantifierError::WitnessIsNotFresh(witness.clone())
+        );
+    }
+
+    let substitution = build_instantiation(pool, bindings, args, false)?;
+    let mut substitution = Substitution::new(pool, substitution)?;
     let expected = substitution.apply(pool, original);
-    assert_alpha_equiv_expected(substituted, expected, polyeq_time)
+    assert_alpha_equiv_expected(&conclusion[0], expected, polyeq_time)
+}
+
+fn coerce_to_real(pool: &mut dyn TermPool, term: &Rc<Term>) -> Rc<Term> {
+    match term.as_integer() {
+        Some(i) => pool.add(Term::new_real(i)),
+        None => pool.add(Term::Op(Operator::ToReal, vec![term.clone()])),
+    }
 }
 
-pub fn qnt_join(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn qnt_join(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let (left, right) = match_term_err!((= l r) = &conclusion[0])?;
@@ -309,6 +400,7 @@ mod tests {
                 (declare-fun a () Real)
                 (declare-fun b () Real)
                 (declare-fun x () Real)
+                (declare-fun n () Int)
             ",
             "Simple working examples" {
                 "(step t1 (cl (or (not (forall ((p Bool)) p)) q))
@@ -354,6 +446,109 @@ mod tests {
                 "(step t1 (cl (or (not (forall ((x Real) (y Real)) (= x y))) (= a b)))
                     :rule forall_inst :args ((= x a) b))": false,
             }
+            "Argument has the wrong sort" {
+                "(step t1 (cl (or (not (forall ((x Real)) (> x 0.0))) (> (to_real n) 0.0)))
+                    :rule forall_inst :args (n))": false,
+            }
+        }
+    }
+
+    #[test]
+    fn forall_inst_with_subtyping() {
+        use crate::{checker, parser};
+
+        let definitions = "
+            (declare-fun a () Real)
+            (declare-fun n () Int)
+        ";
+        let cases = [
+            ("(> (to_real n) 0.0)", "n", true),
+            ("(> 1.0 0.0)", "1", true),
+            ("(> a 0.0)", "a", true),
+            ("(> n 0.0)", "n", false),
+            ("(> (to_real n) 0.0)", "true", false),
+        ];
+        for (result, arg, expected) in cases {
+            let proof = format!(
+                "(step t1 (cl (or (not (forall ((x Real)) (> x 0.0))) {}))
+                    :rule forall_inst :args ({}))
+                (step end (cl) :rule hole)",
+                result, arg
+            );
+            let parser_config = parser::Config {
+                int_real_subtyping: parser::IntRealSubtyping::Implicit,
+                ..parser::Config::new()
+            };
+            let (problem, proof, mut pool) =
+                parser::parse_instance(definitions.as_bytes(), proof.as_bytes(), parser_config)
+                    .unwrap();
+            let config = checker::Config::new().allow_int_real_subtyping(true);
+            let got = checker::ProofChecker::new(&mut pool, config)
+                .check(&problem, &proof)
+                .is_ok();
+            assert_eq!(expected, got, "case with argument '{}' failed", arg);
+        }
+    }
+
+    #[test]
+    fn exists_elim() {
+        test_cases! {
+            definitions = "
+                (declare-fun p (Int) Bool)
+                (declare-fun q (Int Int) Bool)
+                (declare-fun a () Int)
+                (declare-fun b () Int)
+                (declare-fun c () Int)
+                (declare-fun x () Real)
+            ",
+            "Simple working examples" {
+                "(assume h1 (exists ((y Int)) (p y)))
+                (step t2 (cl (p a)) :rule exists_elim :premises (h1) :args (a))": true,
+
+                "(assume h1 (exists ((y Int) (z Int)) (and (q y z) (p c))))
+                (step t2 (cl (and (q a b) (p c))) :rule exists_elim :premises (h1) :args (a b))": true,
+
+                "(assume h1 (exists ((y Int) (z Int)) (= y z)))
+                (step t2 (cl (= b a)) :rule exists_elim :premises (h1) :args (a b))": true,
+            }
+            "Wrong conclusion" {
+                "(assume h1 (exists ((y Int)) (p y)))
+                (step t2 (cl (p b)) :rule exists_elim :premises (h1) :args (a))": false,
+
+                "(assume h1 (exists ((y Int) (z Int)) (q y z)))
+                (step t2 (cl (q b a)) :rule exists_elim :premises (h1) :args (a b))": false,
+            }
+            "Witness is not fresh" {
+                "(assume h1 (exists ((y Int)) (q y c)))
+                (step t2 (cl (q c c)) :rule exists_elim :premises (h1) :args (c))": false,
+
+                "(assume h1 (exists ((y Int) (z Int)) (q y z)))
+                (step t2 (cl (q a a)) :rule exists_elim :premises (h1) :args (a a))": false,
+
+                "(assume h1 (exists ((y Int)) (p y)))
+                (assume h2 (not (p c)))
+                (step t1 (cl (p c)) :rule exists_elim :premises (h1) :args (c))
+                (step t2 (cl) :rule resolution :premises (t1 h2))": false,
+
+                "(assume h1 (exists ((y Int)) (p y)))
+                (step t1 (cl (p a)) :rule exists_elim :premises (h1) :args (a))
+                (step t2 (cl (p a)) :rule exists_elim :premises (h1) :args (a))": false,
+            }
+            "Witness is not a constant" {
+                "(assume h1 (exists ((y Int)) (p y)))
+                (step t2 (cl (p (+ a 1))) :rule exists_elim :premises (h1) :args ((+ a 1)))": false,
+
+                "(assume h1 (exists ((y Int)) (p y)))
+                (step t2 (cl (p 1)) :rule exists_elim :premises (h1) :args (1))": false,
+            }
+            "Witness has wrong sort" {
+                "(assume h1 (exists ((y Int)) (p y)))
+                (step t2 (cl (p a)) :rule exists_elim :premises (h1) :args (x))": false,
+            }
+            "Wrong number of arguments" {
+                "(assume h1 (exists ((y Int) (z Int)) (q y z)))
+                (step t2 (cl (q a b)) :rule exists_elim :premises (h1) :args (a))": false,
+            }
         }
     }
 
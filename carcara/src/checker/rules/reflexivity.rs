@@ -1,13 +1,23 @@
-use super::{assert_clause_len, assert_eq, CheckerError, RuleArgs, RuleResult};
+use super::{assert_clause_len, assert_eq, CheckerError, ReadOnlyRuleArgs, RuleArgs, RuleResult};
 use crate::ast::*;
 
-pub fn eq_reflexive(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn eq_reflexive(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let (a, b) = match_term_err!((= a b) = &conclusion[0])?;
     assert_eq(a, b)
 }
 
-pub fn refl(
+pub fn refl(args: RuleArgs) -> RuleResult {
+    refl_modulo(args, alpha_equiv)
+}
+
+/// A version of `refl` that allows implicit reordering of equalities, but not the renaming of bound
+/// variables. This is only used to find out which leniency a valid `refl` step relies on.
+pub fn reordering_refl(args: RuleArgs) -> RuleResult {
+    refl_modulo(args, polyeq)
+}
+
+fn refl_modulo(
     RuleArgs {
         conclusion,
         pool,
@@ -15,6 +25,7 @@ pub fn refl(
         polyeq_time,
         ..
     }: RuleArgs,
+    equiv: fn(&Rc<Term>, &Rc<Term>, &mut std::time::Duration) -> bool,
 ) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
@@ -23,7 +34,7 @@ pub fn refl(
     // If the two terms are directly identical, we don't need to do any more work. We make sure to
     // do this check before we try to get the context substitution, because `refl` can be used
     // outside of any subproof
-    if alpha_equiv(left, right, polyeq_time) {
+    if equiv(left, right, polyeq_time) {
         return Ok(());
     }
 
@@ -36,10 +47,9 @@ pub fn refl(
     // don't compute the new left and right terms until they are needed, to avoid doing unnecessary
     // work
     let new_left = context.apply(pool, left);
-    let result = alpha_equiv(&new_left, right, polyeq_time) || {
+    let result = equiv(&new_left, right, polyeq_time) || {
         let new_right = context.apply(pool, right);
-        alpha_equiv(left, &new_right, polyeq_time)
-            || alpha_equiv(&new_left, &new_right, polyeq_time)
+        equiv(left, &new_right, polyeq_time) || equiv(&new_left, &new_right, polyeq_time)
     };
     rassert!(
         result,
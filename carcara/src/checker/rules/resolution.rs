@@ -1,7 +1,8 @@
 use super::{
     assert_clause_len, assert_eq, assert_is_bool_constant, assert_num_args, assert_num_premises,
-    CheckerError, Premise, RuleArgs, RuleResult,
+    CheckerError, Premise, ReadOnlyRuleArgs, RuleArgs, RuleResult,
 };
+use crate::checker::error::{Explain, Explanation};
 use crate::{ast::*, resolution::*};
 use indexmap::IndexSet;
 
@@ -30,16 +31,57 @@ pub fn resolution(rule_args: RuleArgs) -> RuleResult {
     greedy_resolution(conclusion, &premise_clauses, pool, false)
         .map(|_| ())
         .or_else(|greedy_error| {
+            tracing::trace!(error = %greedy_error, "greedy resolution failed, trying RUP");
             if rup_resolution(conclusion, premises) {
                 Ok(())
             } else {
                 // If RUP resolution also fails, we return the error originally returned by the greedy
                 // algorithm
-                Err(greedy_error.into())
+                let explanation = explain_greedy_resolution(&greedy_error, conclusion, premises);
+                Err(greedy_error).explain(|| explanation)
             }
         })
 }
 
+fn explain_greedy_resolution(
+    error: &ResolutionError,
+    conclusion: &[Rc<Term>],
+    premises: &[Premise],
+) -> Explanation {
+    let mut explanation = Explanation::new(
+        "the conclusion is not the result of resolving the premises, and it can't be derived from \
+        them by unit propagation",
+    );
+    let term = match error {
+        ResolutionError::RemainingPivot(t) => {
+            explanation.reasons.push(format!(
+                "term '{}' is not in the conclusion, so it must be eliminated, but no premise has \
+                its negation as a pivot",
+                t
+            ));
+            t
+        }
+        ResolutionError::ExtraTermInConclusion(t) => {
+            explanation.reasons.push(format!(
+                "term '{}' is in the conclusion, but in no premise",
+                t
+            ));
+            t
+        }
+        _ => return explanation,
+    };
+    explanation = explanation.with_terms("conclusion", conclusion);
+
+    // We show the premises in which the term appears, with any number of leading negations
+    let (_, inner) = term.remove_all_negations();
+    for p in premises {
+        if p.clause.iter().any(|t| t.remove_all_negations().1 == inner) {
+            explanation = explanation.with_terms(format!("premise '{}'", p.id), p.clause);
+        }
+    }
+    explanation
+}
+
 fn rup_resolution(conclusion: &[Rc<Term>], premises: &[Premise]) -> bool {
     let mut clauses: Vec<IndexSet<(bool, &Rc<Term>)>> = premises
         .iter()
@@ -88,17 +130,33 @@ pub fn resolution_with_args(
 ) -> RuleResult {
     let resolution_result = apply_generic_resolution::<IndexSet<_>>(premises, args, pool)?;
 
-    let conclusion: IndexSet<_> = conclusion.iter().map(Rc::remove_all_negations).collect();
+    let conclusion_set: IndexSet<_> = conclusion.iter().map(Rc::remove_all_negations).collect();
 
-    if let Some(extra) = conclusion.difference(&resolution_result).next() {
-        let extra = literal_to_term(pool, *extra);
-        return Err(ResolutionError::ExtraTermInConclusion(extra).into());
-    }
-    if let Some(missing) = resolution_result.difference(&conclusion).next() {
-        let missing = literal_to_term(pool, *missing);
-        return Err(ResolutionError::MissingTermInConclusion(missing).into());
-    }
-    Ok(())
+    let error = if let Some(extra) = conclusion_set.difference(&resolution_result).next() {
+        ResolutionError::ExtraTermInConclusion(literal_to_term(pool, *extra))
+    } else if let Some(missing) = resolution_result.difference(&conclusion_set).next() {
+        ResolutionError::MissingTermInConclusion(literal_to_term(pool, *missing))
+    } else {
+        return Ok(());
+    };
+    let result = literals_to_terms(pool, resolution_result);
+    Err(error).explain(|| explain_result_mismatch(conclusion, &result))
+}
+
+fn literals_to_terms<'a>(
+    pool: &mut dyn TermPool,
+    literals: impl IntoIterator<Item = Literal<'a>>,
+) -> Vec<Rc<Term>> {
+    literals
+        .into_iter()
+        .map(|l| literal_to_term(pool, l))
+        .collect()
+}
+
+fn explain_result_mismatch(conclusion: &[Rc<Term>], result: &[Rc<Term>]) -> Explanation {
+    Explanation::new("the conclusion differs from the result of resolving the premises")
+        .with_terms("conclusion", conclusion)
+        .with_terms("result of resolution", result)
 }
 
 pub fn strict_resolution(
@@ -110,7 +168,7 @@ pub fn strict_resolution(
 
     let resolution_result = apply_generic_resolution::<Vec<_>>(premises, args, pool)?;
 
-    match conclusion.len().cmp(&resolution_result.len()) {
+    let result = match conclusion.len().cmp(&resolution_result.len()) {
         Ordering::Less => {
             let missing = literal_to_term(pool, resolution_result[conclusion.len()]);
             Err(ResolutionError::MissingTermInConclusion(missing).into())
@@ -119,15 +177,17 @@ pub fn strict_resolution(
             let extra = conclusion[resolution_result.len()].clone();
             Err(ResolutionError::ExtraTermInConclusion(extra).into())
         }
-        Ordering::Equal => {
-            for (t, u) in resolution_result.into_iter().zip(conclusion) {
-                if t != u.remove_all_negations() {
-                    assert_eq(&literal_to_term(pool, t), u)?;
-                }
-            }
-            Ok(())
-        }
+        Ordering::Equal => resolution_result
+            .iter()
+            .zip(conclusion)
+            .filter(|(t, u)| **t != u.remove_all_negations())
+            .try_for_each(|(t, u)| assert_eq(&literal_to_term(pool, *t), u)),
+    };
+    if result.is_err() {
+        let result_terms = literals_to_terms(pool, resolution_result);
+        return result.explain(|| explain_result_mismatch(conclusion, &result_terms));
     }
+    Ok(())
 }
 
 fn apply_generic_resolution<'a, C: ClauseCollection<'a>>(
@@ -161,8 +221,19 @@ fn apply_generic_resolution<'a, C: ClauseCollection<'a>>(
         .map(Rc::remove_all_negations)
         .collect();
 
-    for (premise, (pivot, polarity)) in premises[1..].iter().zip(args) {
-        binary_resolution(pool, &mut current, premise.clause, pivot, polarity)?;
+    for (i, (premise, (pivot, polarity))) in premises[1..].iter().zip(args).enumerate() {
+        // For explanations, we describe the clause on the left of each binary resolution step
+        let current_name = || {
+            if i == 0 {
+                format!("left premise '{}'", premises[0].id)
+            } else {
+                format!(
+                    "clause resolved from premises '{}' to '{}'",
+                    premises[0].id, premises[i].id
+                )
+            }
+        };
+        binary_resolution(pool, &mut current, premise, pivot, polarity, current_name)?;
     }
 
     Ok(current)
@@ -171,10 +242,11 @@ fn apply_generic_resolution<'a, C: ClauseCollection<'a>>(
 fn binary_resolution<'a, C: ClauseCollection<'a>>(
     pool: &mut dyn TermPool,
     current: &mut C,
-    next: &'a [Rc<Term>],
+    next: &Premise<'a>,
     pivot: Literal<'a>,
     is_pivot_in_current: bool,
-) -> Result<(), ResolutionError> {
+    current_name: impl FnOnce() -> String,
+) -> RuleResult {
     let negated_pivot = (pivot.0 + 1, pivot.1);
     let (pivot_in_current, pivot_in_next) = if is_pivot_in_current {
         (pivot, negated_pivot)
@@ -183,11 +255,15 @@ fn binary_resolution<'a, C: ClauseCollection<'a>>(
     };
     if !current.remove_term(&pivot_in_current) {
         let p = literal_to_term(pool, pivot_in_current);
-        return Err(ResolutionError::PivotNotFound(p));
+        let candidates = literals_to_terms(pool, current.literals());
+        let name = current_name();
+        let reason = format!("pivot '{}' not found in {}", p, name);
+        return Err(ResolutionError::PivotNotFound(p))
+            .explain(|| Explanation::new(reason).with_terms(name, &candidates));
     }
 
     let mut found = false;
-    for t in next {
+    for t in next.clause {
         let t = t.remove_all_negations();
         if !found && t == pivot_in_next {
             found = true;
@@ -197,12 +273,15 @@ fn binary_resolution<'a, C: ClauseCollection<'a>>(
     }
     if !found {
         let p = literal_to_term(pool, pivot_in_next);
-        return Err(ResolutionError::PivotNotFound(p));
+        let name = format!("right premise '{}'", next.id);
+        let reason = format!("pivot '{}' not found in {}", p, name);
+        return Err(ResolutionError::PivotNotFound(p))
+            .explain(|| Explanation::new(reason).with_terms(name, next.clause));
     }
     Ok(())
 }
 
-pub fn tautology(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn tautology(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 1)?;
     assert_is_bool_constant(&conclusion[0], true)?;
@@ -219,18 +298,25 @@ pub fn tautology(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult
     Err(ResolutionError::TautologyFailed.into())
 }
 
-pub fn contraction(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn contraction(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
 
     let premise_set: IndexSet<_> = premises[0].clause.iter().collect();
     let conclusion_set: IndexSet<_> = conclusion.iter().collect();
-    if let Some(&t) = premise_set.difference(&conclusion_set).next() {
+    let result = if let Some(&t) = premise_set.difference(&conclusion_set).next() {
         Err(CheckerError::ContractionMissingTerm(t.clone()))
     } else if let Some(&t) = conclusion_set.difference(&premise_set).next() {
         Err(CheckerError::ContractionExtraTerm(t.clone()))
     } else {
         Ok(())
-    }
+    };
+    result.explain(|| {
+        Explanation::new(
+            "the conclusion must have the same terms as the premise, without duplicates",
+        )
+        .with_terms("terms in premise", premise_set)
+        .with_terms("terms in conclusion", conclusion_set)
+    })
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 use super::{
-    assert_clause_len, assert_eq, assert_is_bool_constant, CheckerError, EqualityError, RuleArgs,
-    RuleResult,
+    assert_clause_len, assert_eq, assert_is_bool_constant, declarative, CheckerError,
+    EqualityError, ReadOnlyRuleArgs, RuleArgs, RuleResult,
 };
 use crate::{ast::*, utils::DedupIterator};
 use indexmap::{IndexMap, IndexSet};
@@ -38,6 +38,18 @@ macro_rules! simplify {
     };
 }
 
+/// A macro to define "simplify" rules whose transformations are given declaratively, in the
+/// `simplify.rules` file.
+macro_rules! declarative_simplify_rules {
+    ($($name:ident),* $(,)?) => {$(
+        pub fn $name(args: RuleArgs) -> RuleResult {
+            generic_simplify_rule(args.conclusion, args.pool, |term, pool| {
+                declarative::rewrite(stringify!($name), term, pool)
+            })
+        }
+    )*};
+}
+
 fn generic_simplify_rule(
     conclusion: &[Rc<Term>],
     pool: &mut dyn TermPool,
@@ -351,48 +363,10 @@ pub fn equiv_simplify(args: RuleArgs) -> RuleResult {
     })
 }
 
-pub fn bool_simplify(args: RuleArgs) -> RuleResult {
-    generic_simplify_rule(args.conclusion, args.pool, |term, pool| {
-        simplify!(term {
-            // ¬(phi_1 -> phi_2) => (phi_1 ^ ¬phi_2)
-            (not (=> phi_1 phi_2)): (phi_1, phi_2) => {
-                build_term!(pool, (and {phi_1.clone()} (not {phi_2.clone()})))
-            },
-
-            // ¬(phi_1 v phi_2) => (¬phi_1 ^ ¬phi_2)
-            (not (or phi_1 phi_2)): (phi_1, phi_2) => {
-                build_term!(pool, (and (not {phi_1.clone()}) (not {phi_2.clone()})))
-            },
-
-            // ¬(phi_1 ^ phi_2) => (¬phi_1 v ¬phi_2)
-            (not (and phi_1 phi_2)): (phi_1, phi_2) => {
-                build_term!(pool, (or (not {phi_1.clone()}) (not {phi_2.clone()})))
-            },
-
-            // (phi_1 -> (phi_2 -> phi_3)) => ((phi_1 ^ phi_2) -> phi_3)
-            (=> phi_1 (=> phi_2 phi_3)): (phi_1, (phi_2, phi_3)) => {
-                build_term!(pool, (=> (and {phi_1.clone()} {phi_2.clone()}) {phi_3.clone()}))
-            },
-
-            // ((phi_1 -> phi_2) -> phi_2) => (phi_1 v phi_2)
-            (=> (=> phi_1 phi_2) phi_3): ((phi_1, phi_2), phi_3) if phi_2 == phi_3 => {
-                build_term!(pool, (or {phi_1.clone()} {phi_2.clone()}))
-            },
-
-            // (phi_1 ^ (phi_1 -> phi_2)) => (phi_1 ^ phi_2)
-            (and phi_1 (=> phi_2 phi_3)): (phi_1, (phi_2, phi_3)) if phi_1 == phi_2 => {
-                build_term!(pool, (and {phi_1.clone()} {phi_3.clone()}))
-            },
+// The transformations of these rules are given in the `simplify.rules` file
+declarative_simplify_rules!(bool_simplify);
 
-            // ((phi_1 -> phi_2) ^ phi_1) => (phi_1 ^ phi_2)
-            (and (=> phi_1 phi_2) phi_3): ((phi_1, phi_2), phi_3) if phi_1 == phi_3 => {
-                build_term!(pool, (and {phi_1.clone()} {phi_2.clone()}))
-            },
-        })
-    })
-}
-
-pub fn qnt_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn qnt_simplify(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let (left, right) = match_term_err!((= l r) = &conclusion[0])?;
     let (_, _, inner) = left.as_quant_err()?;
@@ -404,7 +378,56 @@ pub fn qnt_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn div_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+/// Evaluates a ground arithmetic term, that is, a term built only from numerical constants and the
+/// arithmetic operators. The `div` and `mod` operators follow the SMT-LIB semantics, in which
+/// integer division is Euclidean: the remainder is always non-negative. Returns `None` if the term
+/// is not a ground arithmetic term, or if it contains a division by zero.
+fn evaluate_arithmetic(term: &Rc<Term>) -> Option<Rational> {
+    if let Some(n) = term.as_number() {
+        return Some(n);
+    }
+    let Term::Op(op, args) = term.as_ref() else {
+        return None;
+    };
+    let mut values = args.iter().map(evaluate_arithmetic);
+    Some(match (op, args.len()) {
+        (Operator::Sub, 1) => -values.next()??,
+        (Operator::Sub, _) => {
+            let first = values.next()??;
+            values.try_fold(first, |acc, v| Some(acc - v?))?
+        }
+        (Operator::Add, _) => values.try_fold(Rational::new(), |acc, v| Some(acc + v?))?,
+        (Operator::Mult, _) => values.try_fold(Rational::from(1), |acc, v| Some(acc * v?))?,
+        (Operator::RealDiv, _) => {
+            let first = values.next()??;
+            values.try_fold(first, |acc, v| {
+                let v = v?;
+                (!v.is_zero()).then(|| acc / v)
+            })?
+        }
+        (Operator::IntDiv | Operator::Mod, 2) => {
+            let [n, d] = [values.next()??, values.next()??];
+            if !n.is_integer() || !d.is_integer() || d.is_zero() {
+                return None;
+            }
+            let [n, d] = [n, d].map(|n| n.into_numer_denom().0);
+            let (q, r) = n.div_rem_euc(d);
+            Rational::from(if *op == Operator::IntDiv { q } else { r })
+        }
+        (Operator::Abs, 1) => values.next()??.abs(),
+        (Operator::ToReal | Operator::ToInt, 1) => {
+            let value = values.next()??;
+            if *op == Operator::ToInt {
+                value.floor()
+            } else {
+                value
+            }
+        }
+        _ => return None,
+    })
+}
+
+pub fn div_simplify(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let (left, right) = match_term_err!((= l r) = &conclusion[0])?;
 
@@ -418,6 +441,10 @@ pub fn div_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
         return Err(CheckerError::ExpectedNumber(Rational::new(), right.clone()));
     }
 
+    if let Some((numer, denom)) = match_term!((mod n d) = left) {
+        return mod_simplify(numer, denom, right);
+    }
+
     let ((numer, denom), is_int_div) = match match_term!((div n d) = left) {
         Some(v) => (v, true),
         None => (match_term_err!((/ n d) = left)?, false),
@@ -432,11 +459,13 @@ pub fn div_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     } else if denom.as_number().is_some_and(|n| n == 1) {
         assert_eq(right, numer)
     } else {
-        let denom = denom.as_signed_number_err()?;
+        let denom = evaluate_arithmetic(denom)
+            .ok_or_else(|| CheckerError::ExpectedAnyNumber(denom.clone()))?;
         if denom.is_zero() {
             return Err(CheckerError::DivOrModByZero);
         }
-        let numer = numer.as_signed_number_err()?;
+        let numer = evaluate_arithmetic(numer)
+            .ok_or_else(|| CheckerError::ExpectedAnyNumber(numer.clone()))?;
         let expected = if is_int_div {
             assert!(numer.is_integer() && denom.is_integer()); // This is guaranteed by the Alethe typing rules
             let [numer, denom] = [numer, denom].map(|n| n.into_numer_denom().0);
@@ -452,6 +481,33 @@ pub fn div_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     }
 }
 
+/// Checks the simplification of a `(mod n d)` term into `result`. This is valid if `d` is 1, if `n`
+/// and `d` are the same term, in which case the result is 0, or if both `n` and `d` are ground
+/// arithmetic terms.
+fn mod_simplify(numer: &Rc<Term>, denom: &Rc<Term>, result: &Rc<Term>) -> RuleResult {
+    let expected = if numer == denom || denom.as_number().is_some_and(|n| n == 1) {
+        Rational::new()
+    } else {
+        let denom = evaluate_arithmetic(denom)
+            .ok_or_else(|| CheckerError::ExpectedAnyNumber(denom.clone()))?;
+        if denom.is_zero() {
+            return Err(CheckerError::DivOrModByZero);
+        }
+        let numer = evaluate_arithmetic(numer)
+            .ok_or_else(|| CheckerError::ExpectedAnyNumber(numer.clone()))?;
+        if !numer.is_integer() || !denom.is_integer() {
+            return Err(CheckerError::ExpectedAnyInteger(result.clone()));
+        }
+        let [numer, denom] = [numer, denom].map(|n| n.into_numer_denom().0);
+        Rational::from(numer.div_rem_euc(denom).1)
+    };
+    rassert!(
+        result.as_signed_number_err()? == expected,
+        CheckerError::ExpectedNumber(expected, result.clone())
+    );
+    Ok(())
+}
+
 /// Used for both the `sum_simplify` and `prod_simplify` rules, depending on `rule_kind`.
 /// `rule_kind` has to be either `Operator::Add` or `Operator::Mult`.
 fn generic_sum_prod_simplify_rule(
@@ -562,7 +618,7 @@ pub fn prod_simplify(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult
     generic_sum_prod_simplify_rule(pool, first, second, Operator::Mult)
 }
 
-pub fn minus_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn minus_simplify(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     // Despite being separate rules in the documentation, this rule is used to do the job of both
     // the `minus_simplify` and the `unary_minus_simplify` rules
     fn try_unary_minus_simplify(t: &Rc<Term>, u: &Rc<Term>) -> bool {
@@ -588,7 +644,7 @@ pub fn minus_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
             );
             return Ok(());
         }
-        match (t_1.as_signed_number(), t_2.as_signed_number()) {
+        match (evaluate_arithmetic(t_1), evaluate_arithmetic(t_2)) {
             (_, Some(z)) if z == 0 => assert_eq(u, t_1),
             (Some(z), _) if z == 0 => assert_eq(match_term_err!((-t) = u)?, t_2),
             (Some(t_1), Some(t_2)) => {
@@ -641,9 +697,9 @@ pub fn comp_simplify(args: RuleArgs) -> RuleResult {
         simplify!(term {
             (< t_1 t_2): (t_1, t_2) => {
                 if let (Some(t_1), Some(t_2)) =
-                    (t_1.as_signed_number(), t_2.as_signed_number())
+                    (evaluate_arithmetic(t_1), evaluate_arithmetic(t_2))
                 {
-                    // t_1 < t_2 => phi, where t_1 and t_2 are numerical constants
+                    // t_1 < t_2 => phi, where t_1 and t_2 are ground arithmetic terms
                     pool.bool_constant(t_1 < t_2)
                 } else if t_1 == t_2 {
                     // t < t => false
@@ -655,9 +711,9 @@ pub fn comp_simplify(args: RuleArgs) -> RuleResult {
             },
             (<= t_1 t_2): (t_1, t_2) => {
                 if let (Some(t_1), Some(t_2)) =
-                    (t_1.as_signed_number(), t_2.as_signed_number())
+                    (evaluate_arithmetic(t_1), evaluate_arithmetic(t_2))
                 {
-                    // t_1 <= t_2 => phi, where t_1 and t_2 are numerical constants
+                    // t_1 <= t_2 => phi, where t_1 and t_2 are ground arithmetic terms
                     pool.bool_constant(t_1 <= t_2)
                 } else if t_1 == t_2 {
                     // t <= t => true
@@ -676,7 +732,7 @@ pub fn comp_simplify(args: RuleArgs) -> RuleResult {
     })
 }
 
-fn apply_ac_simp(
+pub fn apply_ac_simp(
     pool: &mut dyn TermPool,
     cache: &mut IndexMap<Rc<Term>, Rc<Term>>,
     term: &Rc<Term>,
@@ -717,10 +773,27 @@ fn apply_ac_simp(
                 .collect();
             Term::App(func.clone(), args)
         }
+        Term::ParamOp { op, op_args, args } => {
+            let args = args
+                .iter()
+                .map(|term| apply_ac_simp(pool, cache, term))
+                .collect();
+            Term::ParamOp {
+                op: *op,
+                op_args: op_args.clone(),
+                args,
+            }
+        }
         Term::Binder(q, bindings, inner) => {
             Term::Binder(*q, bindings.clone(), apply_ac_simp(pool, cache, inner))
         }
-        Term::Let(binding, inner) => Term::Let(binding.clone(), apply_ac_simp(pool, cache, inner)),
+        Term::Let(bindings, inner) => {
+            let bindings = bindings
+                .iter()
+                .map(|(name, value)| (name.clone(), apply_ac_simp(pool, cache, value)))
+                .collect();
+            Term::Let(BindingList(bindings), apply_ac_simp(pool, cache, inner))
+        }
         _ => return term.clone(),
     };
     let result = pool.add(result);
@@ -1176,6 +1249,17 @@ mod tests {
             }
             "Left and right terms don't match" {
                 "(step t1 (cl (= (forall ((x Int)) false) true)) :rule qnt_simplify)": false,
+                "(step t1 (cl (= (exists ((x Int)) true) false)) :rule qnt_simplify)": false,
+            }
+            "Left term is not a quantifier" {
+                "(step t1 (cl (= false false)) :rule qnt_simplify)": false,
+                "(step t1 (cl (= (not (forall ((x Int)) false)) true)) :rule qnt_simplify)": false,
+            }
+            "Invalid conclusions" {
+                "(step t1 (cl) :rule qnt_simplify)": false,
+                "(step t1 (cl (forall ((x Int)) false)) :rule qnt_simplify)": false,
+                "(step t1 (cl (= (forall ((x Int)) false) false) (= true true))
+                    :rule qnt_simplify)": false,
             }
         }
     }
@@ -1218,6 +1302,25 @@ mod tests {
                 "(step t1 (cl (= (div 8 (- 3)) (- 3))) :rule div_simplify)": false,
                 "(step t1 (cl (= (div (- 8) (- 3)) 2)) :rule div_simplify)": false,
             }
+            "Ground arguments" {
+                "(step t1 (cl (= (div (abs (- 7)) 2) 3)) :rule div_simplify)": true,
+                "(step t1 (cl (= (div (+ 5 2) (- 3 5)) (- 3))) :rule div_simplify)": true,
+                "(step t1 (cl (= (div 7 (mod 4 2)) 0)) :rule div_simplify)": false,
+                "(step t1 (cl (= (div n (+ 1 1)) 0)) :rule div_simplify)": false,
+            }
+            "Modulo" {
+                "(step t1 (cl (= (mod n 1) 0)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod n n) 0)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod 8 3) 2)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod (- 7) 2) 1)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod 7 (- 2)) 1)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod (- 8) (- 3)) 1)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod (div 17 2) (abs (- 3))) 2)) :rule div_simplify)": true,
+
+                "(step t1 (cl (= (mod (- 7) 2) (- 1))) :rule div_simplify)": false,
+                "(step t1 (cl (= (mod 3 0) 0)) :rule div_simplify)": false,
+                "(step t1 (cl (= (mod n 2) 0)) :rule div_simplify)": false,
+            }
         }
     }
 
@@ -1317,6 +1420,19 @@ mod tests {
                 "(step t1 (cl (= (- 0) 0)) :rule minus_simplify)": true,
                 "(step t1 (cl (= 0.0 (- 0.0))) :rule minus_simplify)": true,
             }
+            "Using the \"unary_minus_simplify\" rule name" {
+                "(step t1 (cl (= (- (- x)) x)) :rule unary_minus_simplify)": true,
+                "(step t1 (cl (= (- x 0.0) x)) :rule unary_minus_simplify)": true,
+                "(step t1 (cl (= (- (- x)) (- x))) :rule unary_minus_simplify)": false,
+            }
+            "Integer division, modulo and absolute value" {
+                "(step t1 (cl (= (- (div 7 2) (mod 7 2)) 2)) :rule minus_simplify)": true,
+                "(step t1 (cl (= (- (div (- 7) 2) 1) (- 5))) :rule minus_simplify)": true,
+                "(step t1 (cl (= (- (abs (- 3)) (mod (- 7) 2)) 2)) :rule minus_simplify)": true,
+                "(step t1 (cl (= (- a (mod 4 2)) a)) :rule minus_simplify)": true,
+                "(step t1 (cl (= (- (div (- 7) 2) 1) (- 4))) :rule minus_simplify)": false,
+                "(step t1 (cl (= (- (div 7 0) 1) 0)) :rule minus_simplify)": false,
+            }
         }
     }
 
@@ -1406,6 +1522,14 @@ mod tests {
                 "(step t1 (cl (= (>= a a) true)) :rule comp_simplify)": true,
                 "(step t1 (cl (= (>= 5.0 8.0) false)) :rule comp_simplify)": true,
             }
+            "Integer division, modulo and absolute value" {
+                "(step t1 (cl (= (< (div (- 7) 2) (- 3)) true)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (<= (mod (- 7) 2) 0) false)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (>= (abs (- 5)) (div 10 2)) true)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (>= (mod 7 (- 2)) (abs (- 1))) true)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (< (div (- 7) 2) (- 3)) false)) :rule comp_simplify)": false,
+                "(step t1 (cl (= (< (div 1 0) 1) true)) :rule comp_simplify)": false,
+            }
         }
     }
 
@@ -1417,6 +1541,9 @@ mod tests {
                 (declare-fun q () Bool)
                 (declare-fun r () Bool)
                 (declare-fun s () Bool)
+                (declare-fun f (Bool) Bool)
+                (declare-fun x () (_ BitVec 4))
+                (declare-fun y () (_ BitVec 4))
             ",
             "Simple working examples" {
                 "(step t1 (cl (= (and (and p q) (and r s)) (and p q r s))) :rule ac_simp)": true,
@@ -1441,6 +1568,25 @@ mod tests {
                 "(step t1 (cl (= (or p p q r s) (or p q r s))) :rule ac_simp)": true,
                 "(step t1 (cl (= (and (and p q) (and q r)) (and p q r))) :rule ac_simp)": true,
                 "(step t1 (cl (= (and (and p q) (and q r)) (and p q q r))) :rule ac_simp)": false,
+                "(step t1 (cl (= (or p (or p p)) p)) :rule ac_simp)": true,
+            }
+            "Nested in other terms" {
+                "(step t1 (cl (= (f (and (and p q) r)) (f (and p q r)))) :rule ac_simp)": true,
+                "(step t1 (cl (= (f (and (and p q) r)) (f (and (and p q) r)))) :rule ac_simp)": false,
+
+                "(step t1 (cl (= ((_ extract 0 0) (ite (or (or p q) r) x y))
+                    ((_ extract 0 0) (ite (or p q r) x y)))) :rule ac_simp)": true,
+
+                "(step t1 (cl (= (let ((a (and (and p q) r))) (or a (or s a)))
+                    (let ((a (and p q r))) (or a s)))) :rule ac_simp)": true,
+
+                "(step t1 (cl (= (let ((a (and (and p q) r))) a)
+                    (let ((a (and (and p q) r))) a))) :rule ac_simp)": false,
+            }
+            "Invalid conclusions" {
+                "(step t1 (cl) :rule ac_simp)": false,
+                "(step t1 (cl (= p p) (= q q)) :rule ac_simp)": false,
+                "(step t1 (cl (and (and p q) r)) :rule ac_simp)": false,
             }
         }
     }
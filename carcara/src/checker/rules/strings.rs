@@ -1,13 +1,18 @@
 use super::{
     assert_clause_len, assert_eq, assert_num_args, assert_num_premises, assert_polyeq_expected,
-    get_premise_term, RuleArgs, RuleResult,
+    get_premise_term, ReadOnlyRuleArgs, RuleArgs, RuleResult,
 };
 use crate::{
     ast::*,
     checker::{error::CheckerError, rules::assert_polyeq},
 };
+use rug::Rational;
 use std::{cmp, time::Duration};
 
+/// The number of code points in the SMT-LIB string theory. The code of every character of length
+/// one is smaller than this.
+const NUM_CODE_POINTS: i32 = 196_608;
+
 /// A function that takes an `Rc<Term>` and returns a vector corresponding to
 /// the flat form of that term.
 ///
@@ -340,9 +345,8 @@ fn singleton_elim(pool: &mut dyn TermPool, r_list: Vec<Rc<Term>>) -> Rc<Term> {
 /// It takes an `Rc<Term>` and recursively match over the regular expression operators whose length
 /// can be inferred. It throws an error if the term length cannot be evaluated, i.e., if the length
 /// of the term itself or one of its arguments cannot be inferred.
-fn str_fixed_len_re(pool: &mut dyn TermPool, r: Rc<Term>) -> Result<usize, CheckerError> {
+fn str_fixed_len_re(r: Rc<Term>) -> Result<usize, CheckerError> {
     fn has_same_length(
-        pool: &mut dyn TermPool,
         args: &[Rc<Term>],
         r: Rc<Term>,
         ignore: Operator,
@@ -351,7 +355,7 @@ fn str_fixed_len_re(pool: &mut dyn TermPool, r: Rc<Term>) -> Result<usize, Check
         let mut iter = args
             .iter()
             .filter(|a| !should_ignore(a))
-            .map(|a| str_fixed_len_re(pool, a.clone()));
+            .map(|a| str_fixed_len_re(a.clone()));
         let Some(first) = iter.next() else {
             return Err(CheckerError::LengthCannotBeEvaluated(r.clone()));
         };
@@ -367,7 +371,7 @@ fn str_fixed_len_re(pool: &mut dyn TermPool, r: Rc<Term>) -> Result<usize, Check
 
     match r.as_ref() {
         Term::Op(Operator::ReConcat, args) => {
-            let mut lengths = args.iter().map(|a| str_fixed_len_re(pool, a.clone()));
+            let mut lengths = args.iter().map(|a| str_fixed_len_re(a.clone()));
             lengths.try_fold(0, |acc, x| Ok(acc + x?))
         }
         Term::Op(Operator::ReAllChar, _) => Ok(1),
@@ -379,16 +383,23 @@ fn str_fixed_len_re(pool: &mut dyn TermPool, r: Rc<Term>) -> Result<usize, Check
                 _ => Err(CheckerError::LengthCannotBeEvaluated(r.clone())),
             }
         }
-        Term::Op(Operator::ReUnion, args) => {
-            has_same_length(pool, args, r.clone(), Operator::ReNone)
-        }
+        Term::Op(Operator::ReUnion, args) => has_same_length(args, r.clone(), Operator::ReNone),
         Term::Op(Operator::ReIntersection, args) => {
-            has_same_length(pool, args, r.clone(), Operator::ReAll)
+            has_same_length(args, r.clone(), Operator::ReAll)
         }
         _ => Err(CheckerError::LengthCannotBeEvaluated(r.clone())),
     }
 }
 
+/// Checks that a term is the integer constant `value`, which may be written as a negation.
+fn assert_int_constant(term: &Rc<Term>, value: i32) -> RuleResult {
+    rassert!(
+        term.as_signed_number() == Some(Rational::from(value)),
+        CheckerError::ExpectedNumber(Rational::from(value), term.clone())
+    );
+    Ok(())
+}
+
 pub fn concat_eq(
     RuleArgs {
         premises,
@@ -981,7 +992,9 @@ pub fn string_decompose(
     assert_polyeq(&conclusion[0], &expanded, polyeq_time)
 }
 
-pub fn string_length_pos(RuleArgs { args, conclusion, polyeq_time, .. }: RuleArgs) -> RuleResult {
+pub fn string_length_pos(
+    RuleArgs { args, conclusion, polyeq_time, .. }: ReadOnlyRuleArgs,
+) -> RuleResult {
     assert_num_args(args, 1)?;
     assert_clause_len(conclusion, 1)?;
 
@@ -1006,7 +1019,7 @@ pub fn string_length_pos(RuleArgs { args, conclusion, polyeq_time, .. }: RuleArg
 pub fn string_length_non_empty(
     RuleArgs {
         premises, conclusion, polyeq_time, ..
-    }: RuleArgs,
+    }: ReadOnlyRuleArgs,
 ) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 1)?;
@@ -1025,10 +1038,70 @@ pub fn string_length_non_empty(
     Ok(())
 }
 
+pub fn string_code_inj(RuleArgs { conclusion, polyeq_time, .. }: ReadOnlyRuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+
+    let ((t_1, minus_one), (t_2, s_1), (t_3, s_2)) = match_term_err!(
+        (or
+            (= (strtocode t) m)
+            (not (= (strtocode t) (strtocode s)))
+            (= t s)
+        ) = &conclusion[0]
+    )?;
+
+    assert_int_constant(minus_one, -1)?;
+    assert_polyeq(t_1, t_2, polyeq_time)?;
+    assert_polyeq(t_1, t_3, polyeq_time)?;
+    assert_polyeq(s_1, s_2, polyeq_time)?;
+
+    Ok(())
+}
+
+pub fn string_eager_reduction(
+    RuleArgs { args, conclusion, polyeq_time, .. }: ReadOnlyRuleArgs,
+) -> RuleResult {
+    assert_num_args(args, 1)?;
+    assert_clause_len(conclusion, 1)?;
+
+    let t = &args[0];
+    match t.as_op() {
+        Some((Operator::StrToCode, [s])) => {
+            let ((s_conc, one), ((c_1, zero), (c_2, max)), (c_3, minus_one)) = match_term_err!(
+                (ite
+                    (= (strlen s) n)
+                    (and (>= c z) (< c m))
+                    (= c k)
+                ) = &conclusion[0]
+            )?;
+            assert_polyeq(s_conc, s, polyeq_time)?;
+            for c in [c_1, c_2, c_3] {
+                assert_polyeq(c, t, polyeq_time)?;
+            }
+            assert_int_constant(one, 1)?;
+            assert_int_constant(zero, 0)?;
+            assert_int_constant(max, NUM_CODE_POINTS)?;
+            assert_int_constant(minus_one, -1)
+        }
+        Some((Operator::IndexOf, [s, _, _])) => {
+            let ((i_1, minus_one), (i_2, s_conc)) = match_term_err!(
+                (and (>= i m) (<= i (strlen s))) = &conclusion[0]
+            )?;
+            assert_polyeq(i_1, t, polyeq_time)?;
+            assert_polyeq(i_2, t, polyeq_time)?;
+            assert_polyeq(s_conc, s, polyeq_time)?;
+            assert_int_constant(minus_one, -1)
+        }
+        _ => Err(CheckerError::TermOfWrongForm(
+            "(str.to_code ...) or (str.indexof ...)",
+            t.clone(),
+        )),
+    }
+}
+
 pub fn re_inter(
     RuleArgs {
         premises, conclusion, polyeq_time, ..
-    }: RuleArgs,
+    }: ReadOnlyRuleArgs,
 ) -> RuleResult {
     assert_num_premises(premises, 2)?;
     assert_clause_len(conclusion, 1)?;
@@ -1160,7 +1233,7 @@ pub fn re_unfold_neg_concat_fixed_prefix(
 
     let expanded = if let Term::Op(Operator::ReConcat, args) = r.as_ref() {
         if let [r_1, r_2 @ ..] = &args[..] {
-            let n = Term::new_int(str_fixed_len_re(pool, r_1.clone())?);
+            let n = Term::new_int(str_fixed_len_re(r_1.clone())?);
             let n = pool.add(n);
             let pref = build_skolem_prefix(pool, s.clone(), n.clone());
             let suff = build_skolem_suffix_rem(pool, s.clone(), n.clone());
@@ -1201,7 +1274,7 @@ pub fn re_unfold_neg_concat_fixed_suffix(
         args_rev.reverse();
 
         if let [r_1, r_2 @ ..] = &args_rev[..] {
-            let n = Term::new_int(str_fixed_len_re(pool, r_1.clone())?);
+            let n = Term::new_int(str_fixed_len_re(r_1.clone())?);
             let n = pool.add(n);
             let suff = build_skolem_suffix(pool, s.clone(), n.clone());
             let size = build_term!(pool, (- (strlen {s.clone()}) {n.clone()}));
@@ -2692,4 +2765,47 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn string_code_inj() {
+        test_cases! {
+            definitions = "
+                (declare-fun a () String)
+                (declare-fun b () String)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (or (= (str.to_code a) (- 1)) (not (= (str.to_code a) (str.to_code b))) (= a b))) :rule string_code_inj)": true,
+                r#"(step t1 (cl (or (= (str.to_code "c") (- 1)) (not (= (str.to_code "c") (str.to_code b))) (= "c" b))) :rule string_code_inj)"#: true,
+            }
+            "Wrong terms" {
+                "(step t1 (cl (or (= (str.to_code a) 0) (not (= (str.to_code a) (str.to_code b))) (= a b))) :rule string_code_inj)": false,
+                "(step t1 (cl (or (= (str.to_code b) (- 1)) (not (= (str.to_code a) (str.to_code b))) (= a b))) :rule string_code_inj)": false,
+                "(step t1 (cl (or (= (str.to_code a) (- 1)) (not (= (str.to_code a) (str.to_code b))) (= a a))) :rule string_code_inj)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn string_eager_reduction() {
+        test_cases! {
+            definitions = "
+                (declare-fun a () String)
+                (declare-fun b () String)
+                (declare-fun n () Int)
+            ",
+            "Using str.to_code" {
+                "(step t1 (cl (ite (= (str.len a) 1) (and (>= (str.to_code a) 0) (< (str.to_code a) 196608)) (= (str.to_code a) (- 1)))) :rule string_eager_reduction :args ((str.to_code a)))": true,
+                "(step t1 (cl (ite (= (str.len a) 1) (and (>= (str.to_code a) 0) (< (str.to_code a) 256)) (= (str.to_code a) (- 1)))) :rule string_eager_reduction :args ((str.to_code a)))": false,
+                "(step t1 (cl (ite (= (str.len b) 1) (and (>= (str.to_code a) 0) (< (str.to_code a) 196608)) (= (str.to_code a) (- 1)))) :rule string_eager_reduction :args ((str.to_code a)))": false,
+            }
+            "Using str.indexof" {
+                "(step t1 (cl (and (>= (str.indexof a b n) (- 1)) (<= (str.indexof a b n) (str.len a)))) :rule string_eager_reduction :args ((str.indexof a b n)))": true,
+                "(step t1 (cl (and (>= (str.indexof a b n) 0) (<= (str.indexof a b n) (str.len a)))) :rule string_eager_reduction :args ((str.indexof a b n)))": false,
+                "(step t1 (cl (and (>= (str.indexof a b n) (- 1)) (<= (str.indexof a b n) (str.len b)))) :rule string_eager_reduction :args ((str.indexof a b n)))": false,
+            }
+            "Unsupported term" {
+                "(step t1 (cl (>= (str.len a) 0)) :rule string_eager_reduction :args ((str.len a)))": false,
+            }
+        }
+    }
 }
@@ -387,9 +387,19 @@ fn generic_skolemization_rule(
     }
 
     let context = context.last().unwrap();
-    let args = context.as_ref().unwrap().args.iter();
+    let args = &context.as_ref().unwrap().args;
+
+    // The context may only assign the skolemized variables
+    if let Some((name, _)) = args
+        .iter()
+        .filter_map(AnchorArg::as_assign)
+        .find(|(name, _)| !bindings.iter().any(|b| &b.0 == *name))
+    {
+        return Err(SubproofError::BindUnexpectedVarArgument(name.clone()).into());
+    }
 
     let substitution: HashMap<Rc<Term>, Rc<Term>> = args
+        .iter()
         .filter_map(AnchorArg::as_assign)
         .map(|(k, v)| {
             let var = Term::new_var(k, pool.sort(v));
@@ -757,6 +767,41 @@ mod tests {
                        (choice ((y Int)) (= (choice ((x Int)) (exists ((y Int)) (= x y))) y)))
                 )) :rule sko_ex)": true,
             }
+            "Nested quantifiers" {
+                "(anchor :step t1 :args (
+                    (:= (x Int) (choice ((x Int)) (forall ((y Int)) (=> (p y) (q x)))))
+                ))
+                (step t1.t1 (cl (=
+                    (forall ((y Int)) (=> (p y) (q x)))
+                    (forall ((y Int)) (=> (p y)
+                        (q (choice ((x Int)) (forall ((y Int)) (=> (p y) (q x)))))))
+                )) :rule hole)
+                (step t1 (cl (=
+                    (exists ((x Int)) (forall ((y Int)) (=> (p y) (q x))))
+                    (forall ((y Int)) (=> (p y)
+                        (q (choice ((x Int)) (forall ((y Int)) (=> (p y) (q x)))))))
+                )) :rule sko_ex)": true,
+            }
+            "Wrong choice term" {
+                "(anchor :step t1 :args ((:= (x Int) (choice ((x Int)) (q x)))))
+                (step t1.t1 (cl (= (p x) (p (choice ((x Int)) (q x))))) :rule hole)
+                (step t1 (cl (= (exists ((x Int)) (p x)) (p (choice ((x Int)) (q x)))))
+                    :rule sko_ex)": false,
+
+                "(anchor :step t1 :args ((:= (x Int) (choice ((y Int)) (p y)))))
+                (step t1.t1 (cl (= (p x) (p (choice ((y Int)) (p y))))) :rule hole)
+                (step t1 (cl (= (exists ((x Int)) (p x)) (p (choice ((y Int)) (p y)))))
+                    :rule sko_ex)": true,
+            }
+            "Context assigns variables that are not skolemized" {
+                "(anchor :step t1 :args (
+                    (:= (x Int) (choice ((x Int)) (p x)))
+                    (:= (z Int) 0)
+                ))
+                (step t1.t1 (cl (= (p x) (p (choice ((x Int)) (p x))))) :rule hole)
+                (step t1 (cl (= (exists ((x Int)) (p x)) (p (choice ((x Int)) (p x)))))
+                    :rule sko_ex)": false,
+            }
         }
     }
 
@@ -1,10 +1,10 @@
 use super::{
     assert_clause_len, assert_eq, assert_num_args, assert_num_premises, assert_polyeq,
-    get_premise_term, CheckerError, RuleArgs, RuleResult,
+    get_premise_term, CheckerError, ReadOnlyRuleArgs, RuleArgs, RuleResult,
 };
 use crate::{ast::*, checker::rules::assert_operation_len};
 
-pub fn r#true(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn r#true(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     if !conclusion[0].is_bool_true() {
         return Err(CheckerError::ExpectedBoolConstant(
@@ -15,7 +15,7 @@ pub fn r#true(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn r#false(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn r#false(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let t = conclusion[0].remove_negation_err()?;
     if !t.is_bool_false() {
@@ -24,14 +24,14 @@ pub fn r#false(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn not_not(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn not_not(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 2)?;
 
     let p = match_term_err!((not (not (not p))) = &conclusion[0])?;
     assert_eq(p, &conclusion[1])
 }
 
-pub fn and_pos(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
+pub fn and_pos(RuleArgs { conclusion, args, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 2)?;
     assert_num_args(args, 1)?;
 
@@ -45,7 +45,7 @@ pub fn and_pos(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
     assert_eq(&conclusion[1], &and_contents[i])
 }
 
-pub fn and_neg(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn and_neg(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 2..)?;
 
     let and_contents = match_term_err!((and ...) = &conclusion[0])?;
@@ -58,7 +58,7 @@ pub fn and_neg(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn or_pos(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn or_pos(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 2..)?;
 
     let or_contents = match_term_err!((not (or ...)) = &conclusion[0])?;
@@ -70,7 +70,7 @@ pub fn or_pos(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn or_neg(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
+pub fn or_neg(RuleArgs { conclusion, args, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 2)?;
     assert_num_args(args, 1)?;
 
@@ -85,110 +85,110 @@ pub fn or_neg(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
     assert_eq(other, &or_contents[i])
 }
 
-pub fn xor_pos1(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn xor_pos1(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2) = match_term_err!((not (xor phi_1 phi_2)) = &conclusion[0])?;
     assert_eq(phi_1, &conclusion[1])?;
     assert_eq(phi_2, &conclusion[2])
 }
 
-pub fn xor_pos2(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn xor_pos2(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2) = match_term_err!((not (xor phi_1 phi_2)) = &conclusion[0])?;
     assert_eq(phi_1, conclusion[1].remove_negation_err()?)?;
     assert_eq(phi_2, conclusion[2].remove_negation_err()?)
 }
 
-pub fn xor_neg1(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn xor_neg1(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2) = match_term_err!((xor phi_1 phi_2) = &conclusion[0])?;
     assert_eq(phi_1, &conclusion[1])?;
     assert_eq(phi_2, conclusion[2].remove_negation_err()?)
 }
 
-pub fn xor_neg2(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn xor_neg2(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2) = match_term_err!((xor phi_1 phi_2) = &conclusion[0])?;
     assert_eq(phi_1, conclusion[1].remove_negation_err()?)?;
     assert_eq(phi_2, &conclusion[2])
 }
 
-pub fn implies_pos(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn implies_pos(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2) = match_term_err!((not (=> phi_1 phi_2)) = &conclusion[0])?;
     assert_eq(phi_1, conclusion[1].remove_negation_err()?)?;
     assert_eq(phi_2, &conclusion[2])
 }
 
-pub fn implies_neg1(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn implies_neg1(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 2)?;
     let (phi_1, _) = match_term_err!((=> phi_1 phi_2) = &conclusion[0])?;
     assert_eq(phi_1, &conclusion[1])
 }
 
-pub fn implies_neg2(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn implies_neg2(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 2)?;
     let (_, phi_2) = match_term_err!((=> phi_1 phi_2) = &conclusion[0])?;
     assert_eq(phi_2, conclusion[1].remove_negation_err()?)
 }
 
-pub fn equiv_pos1(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn equiv_pos1(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2) = match_term_err!((not (= phi_1 phi_2)) = &conclusion[0])?;
     assert_eq(phi_1, &conclusion[1])?;
     assert_eq(phi_2, conclusion[2].remove_negation_err()?)
 }
 
-pub fn equiv_pos2(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn equiv_pos2(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2) = match_term_err!((not (= phi_1 phi_2)) = &conclusion[0])?;
     assert_eq(phi_1, conclusion[1].remove_negation_err()?)?;
     assert_eq(phi_2, &conclusion[2])
 }
 
-pub fn equiv_neg1(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn equiv_neg1(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2) = match_term_err!((= phi_1 phi_2) = &conclusion[0])?;
     assert_eq(phi_1, conclusion[1].remove_negation_err()?)?;
     assert_eq(phi_2, conclusion[2].remove_negation_err()?)
 }
 
-pub fn equiv_neg2(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn equiv_neg2(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2) = match_term_err!((= phi_1 phi_2) = &conclusion[0])?;
     assert_eq(phi_1, &conclusion[1])?;
     assert_eq(phi_2, &conclusion[2])
 }
 
-pub fn ite_pos1(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn ite_pos1(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, _, phi_3) = match_term_err!((not (ite phi_1 phi_2 phi_3)) = &conclusion[0])?;
     assert_eq(phi_1, &conclusion[1])?;
     assert_eq(phi_3, &conclusion[2])
 }
 
-pub fn ite_pos2(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn ite_pos2(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2, _) = match_term_err!((not (ite phi_1 phi_2 phi_3)) = &conclusion[0])?;
     assert_eq(phi_1, conclusion[1].remove_negation_err()?)?;
     assert_eq(phi_2, &conclusion[2])
 }
 
-pub fn ite_neg1(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn ite_neg1(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, _, phi_3) = match_term_err!((ite phi_1 phi_2 phi_3) = &conclusion[0])?;
     assert_eq(phi_1, &conclusion[1])?;
     assert_eq(phi_3, conclusion[2].remove_negation_err()?)
 }
 
-pub fn ite_neg2(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn ite_neg2(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
     let (phi_1, phi_2, _) = match_term_err!((ite phi_1 phi_2 phi_3) = &conclusion[0])?;
     assert_eq(phi_1, conclusion[1].remove_negation_err()?)?;
     assert_eq(phi_2, conclusion[2].remove_negation_err()?)
 }
 
-pub fn equiv1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn equiv1(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
     let premise_term = get_premise_term(&premises[0])?;
@@ -197,7 +197,7 @@ pub fn equiv1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, &conclusion[1])
 }
 
-pub fn equiv2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn equiv2(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
     let premise_term = get_premise_term(&premises[0])?;
@@ -206,7 +206,7 @@ pub fn equiv2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, conclusion[1].remove_negation_err()?)
 }
 
-pub fn not_equiv1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_equiv1(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
     let premise_term = get_premise_term(&premises[0])?;
@@ -215,7 +215,7 @@ pub fn not_equiv1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult
     assert_eq(phi_2, &conclusion[1])
 }
 
-pub fn not_equiv2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_equiv2(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
     let premise_term = get_premise_term(&premises[0])?;
@@ -224,7 +224,7 @@ pub fn not_equiv2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult
     assert_eq(phi_2, conclusion[1].remove_negation_err()?)
 }
 
-pub fn ite1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn ite1(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
     let premise_term = get_premise_term(&premises[0])?;
@@ -233,7 +233,7 @@ pub fn ite1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_3, &conclusion[1])
 }
 
-pub fn ite2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn ite2(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
     let premise_term = get_premise_term(&premises[0])?;
@@ -242,7 +242,7 @@ pub fn ite2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, &conclusion[1])
 }
 
-pub fn not_ite1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_ite1(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
     let premise_term = get_premise_term(&premises[0])?;
@@ -251,7 +251,7 @@ pub fn not_ite1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_3, conclusion[1].remove_negation_err()?)
 }
 
-pub fn not_ite2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_ite2(RuleArgs { conclusion, premises, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
     let premise_term = get_premise_term(&premises[0])?;
@@ -260,7 +260,7 @@ pub fn not_ite2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, conclusion[1].remove_negation_err()?)
 }
 
-pub fn ite_intro(RuleArgs { conclusion, polyeq_time, .. }: RuleArgs) -> RuleResult {
+pub fn ite_intro(RuleArgs { conclusion, polyeq_time, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let (root_term, right_side) = match_term_err!((= t u) = &conclusion[0])?;
@@ -317,7 +317,7 @@ pub fn ite_intro(RuleArgs { conclusion, polyeq_time, .. }: RuleArgs) -> RuleResu
     Ok(())
 }
 
-pub fn connective_def(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn connective_def(RuleArgs { conclusion, .. }: ReadOnlyRuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let (first, second) = match_term_err!((= f s) = &conclusion[0])?;
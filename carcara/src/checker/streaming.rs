@@ -0,0 +1,330 @@
+//! Checking of proofs as they are parsed, without keeping all of their commands in memory.
+//!
+//! Streaming checking is done in two passes over the proof, both of which use
+//! [`Parser::parse_proof_streaming`](crate::parser::Parser::parse_proof_streaming). In the first
+//! pass, [`LastUses`] records, for each root-level command, the last command that uses it as a
+//! premise. In the second pass, [`StreamingChecker`] checks each command as it is parsed, and
+//! drops every command whose consumers were all checked. Periodically, the term pool is garbage
+//! collected, freeing the terms that are no longer needed. For long linear proofs, this bounds
+//! the memory used during checking.
+//!
+//! If the proof can only be read once, for example when it is read from stdin,
+//! [`check_streaming_single_pass`](crate::check_streaming_single_pass) copies it to a temporary
+//! file during the first pass, and reads the copy in the second pass.
+
+use super::*;
+
+/// The minimum number of terms in the pool before the first garbage collection is done.
+const MIN_GARBAGE_COLLECTION_THRESHOLD: usize = 1 << 16;
+
+/// Collects the garbage in the pool if it has grown enough since the last collection. To amortize
+/// the cost of collections, the pool is only collected after it has doubled in size.
+fn maybe_collect_garbage(pool: &mut PrimitivePool, next_collection: &mut usize) {
+    if pool.len() >= *next_collection {
+        pool.collect_garbage();
+        *next_collection = std::cmp::max(2 * pool.len(), MIN_GARBAGE_COLLECTION_THRESHOLD);
+    }
+}
+
+/// Calls `f` on the index of each root-level command referenced by `command`, including
+/// references from steps inside subproofs.
+fn for_each_root_premise(command: &ProofCommand, f: &mut impl FnMut(usize)) {
+    match command {
+        ProofCommand::Assume { .. } => (),
+        ProofCommand::Step(s) => {
+            for &(depth, i) in s.premises.iter().chain(&s.discharge) {
+                if depth == 0 {
+                    f(i);
+                }
+            }
+        }
+        ProofCommand::Subproof(s) => {
+            for c in &s.commands {
+                for_each_root_premise(c, f);
+            }
+        }
+    }
+}
+
+/// Records, for each root-level command in a proof, the index of the last root-level command that
+/// uses it as a premise.
+#[derive(Debug, Default)]
+pub struct LastUses {
+    last_uses: Vec<Option<usize>>,
+    next_collection: usize,
+
+    /// Whether the proof has any `exists_elim` steps, whose witnesses must be checked to be fresh.
+    uses_witnesses: bool,
+}
+
+impl LastUses {
+    pub fn new() -> Self {
+        Self {
+            last_uses: Vec::new(),
+            next_collection: MIN_GARBAGE_COLLECTION_THRESHOLD,
+            uses_witnesses: false,
+        }
+    }
+
+    /// Records the premises of the next root-level command. Since the command is dropped after
+    /// this, the pool may also be garbage collected.
+    pub fn add_command(&mut self, pool: &mut PrimitivePool, command: ProofCommand) {
+        let index = self.last_uses.len();
+        for_each_root_premise(&command, &mut |i| self.last_uses[i] = Some(index));
+        self.last_uses.push(None);
+        self.uses_witnesses |=
+            witnesses::uses_witnesses(ProofIter::starting_at(std::slice::from_ref(&command), 0));
+
+        drop(command);
+        maybe_collect_garbage(pool, &mut self.next_collection);
+    }
+}
+
+/// A proof checker that checks root-level commands one at a time, as they are parsed, dropping
+/// each command once all the commands that use it were checked.
+pub struct StreamingChecker<'p> {
+    problem: &'p Problem,
+    config: Config,
+    context: ContextStack,
+    logic: Option<Logic>,
+    reached_empty_clause: bool,
+    is_holey: bool,
+    last_uses: LastUses,
+
+    /// If the proof has `exists_elim` steps, this records the symbols seen so far, to check that
+    /// their witnesses are fresh.
+    witnesses: Option<witnesses::WitnessChecker>,
+
+    /// The root-level commands checked so far. Commands that are no longer needed are replaced by
+    /// a placeholder `assume` command, so the indices of the remaining commands are preserved.
+    commands: Vec<ProofCommand>,
+}
+
+impl<'p> StreamingChecker<'p> {
+    /// Constructs a new `StreamingChecker`, using the last uses collected in a previous pass over
+    /// the proof.
+    pub fn new(problem: &'p Problem, config: Config, last_uses: LastUses) -> Self {
+        Self {
+            logic: Logic::from_prelude(&config, &problem.prelude),
+            problem,
+            config,
+            context: ContextStack::new(),
+            reached_empty_clause: false,
+            is_holey: false,
+            witnesses: last_uses
+                .uses_witnesses
+                .then(|| witnesses::WitnessChecker::new(problem)),
+            last_uses,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Checks the next root-level command of the proof.
+    pub fn check_command(
+        &mut self,
+        pool: &mut PrimitivePool,
+        command: ProofCommand,
+    ) -> CarcaraResult<()> {
+        let index = self.commands.len();
+        let mut premises = Vec::new();
+        for_each_root_premise(&command, &mut |i| premises.push(i));
+        self.commands.push(command);
+        self.config
+            .check_rule_filters(ProofIter::starting_at(&self.commands, index))?;
+        if let Some(witnesses) = &mut self.witnesses {
+            witnesses.check_commands(ProofIter::starting_at(&self.commands, index))?;
+        }
+
+        let mut checker = ProofChecker {
+            pool,
+            config: std::mem::take(&mut self.config),
+            context: std::mem::take(&mut self.context),
+            logic: self.logic.take(),
+            reached_empty_clause: self.reached_empty_clause,
+            is_holey: self.is_holey,
+            leniencies: None,
+            obligations: None,
+        };
+        let iter = ProofIter::starting_at(&self.commands, index);
+        let result = checker.check_commands(
+            self.problem,
+            iter,
+            None::<&mut CheckerStatistics<OnlineBenchmarkResults>>,
+        );
+        self.config = checker.config;
+        self.context = checker.context;
+        self.logic = checker.logic;
+        self.reached_empty_clause = checker.reached_empty_clause;
+        self.is_holey = checker.is_holey;
+        let pool = checker.pool;
+        result?;
+
+        // A command can be dropped if this is the last command that uses it, or if it is not used
+        // by any command
+        let last_uses = &self.last_uses.last_uses;
+        let placeholder = ProofCommand::Assume {
+            id: String::new(),
+            term: pool.bool_false(),
+        };
+        for i in premises.into_iter().chain(std::iter::once(index)) {
+            let last_use = last_uses.get(i).copied().flatten();
+            if last_use.map_or(true, |last| last <= index) {
+                self.commands[i] = placeholder.clone();
+            }
+        }
+
+        maybe_collect_garbage(pool, &mut self.last_uses.next_collection);
+        Ok(())
+    }
+
+    /// Finishes checking the proof. Returns `true` if the proof contains holes, or an error if the
+    /// proof does not reach the empty clause (or the expected conclusion, if one was given).
+    pub fn finish(self) -> CarcaraResult<bool> {
+        if self.reached_empty_clause {
+            Ok(self.is_holey)
+        } else {
+            Err(self.config.missing_conclusion_error())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{self, Parser};
+
+    const PROBLEM: &str = "
+        (declare-const p Bool)
+        (declare-const q Bool)
+        (assert (and p q))
+        (assert (not p))
+    ";
+
+    const PROOF: &str = "
+        (assume h1 (and p q))
+        (assume h2 (not p))
+        (step t1 (cl (= (and q p) (and q p))) :rule refl)
+        (step t2 (cl p) :rule and :premises (h1) :args (0))
+        (step t3 (cl) :rule resolution :premises (t2 h2))
+    ";
+
+    /// Checks `PROOF` with a `StreamingChecker`. Returns the number of terms in the pool after
+    /// parsing the problem, and, for each command, the ids of the commands that are kept after it
+    /// is checked, and the number of terms in the pool at that point. All terms are counted after
+    /// collecting garbage.
+    fn check() -> (usize, Vec<(String, usize)>) {
+        let mut pool = PrimitivePool::new();
+        let mut parser = Parser::new(&mut pool, parser::Config::new(), PROBLEM.as_bytes()).unwrap();
+        let problem = parser.parse_problem().unwrap();
+
+        parser.reset(PROOF.as_bytes()).unwrap();
+        let mut last_uses = LastUses::new();
+        parser
+            .parse_proof_streaming(|pool, command| {
+                last_uses.add_command(pool, command);
+                Ok(())
+            })
+            .unwrap();
+
+        parser.reset(PROOF.as_bytes()).unwrap();
+        let mut checker = StreamingChecker::new(&problem, Config::new(), last_uses);
+        let mut steps = Vec::new();
+        parser
+            .parse_proof_streaming(|pool, command| {
+                checker.check_command(pool, command)?;
+                pool.collect_garbage();
+                let ids: Vec<_> = checker.commands.iter().map(ProofCommand::id).collect();
+                steps.push((ids.join(" "), pool.len()));
+                Ok(())
+            })
+            .unwrap();
+        assert!(!checker.finish().unwrap());
+
+        let mut pool = PrimitivePool::new();
+        let mut parser = Parser::new(&mut pool, parser::Config::new(), PROBLEM.as_bytes()).unwrap();
+        let _problem = parser.parse_problem().unwrap();
+        drop(parser);
+        pool.collect_garbage();
+        (pool.len(), steps)
+    }
+
+    #[test]
+    fn test_dropped_commands() {
+        let (n, steps) = check();
+        let expected = [
+            ("h1", n),
+            ("h1 h2", n),
+            // `t1` is not used by any command, so it is dropped as soon as it is checked, and the
+            // terms in its conclusion are freed. Only `false`, used by the placeholders, is added
+            ("h1 h2 ", n + 1),
+            // `t2` is kept until `t3` is checked, together with its argument `0` and its sort
+            (" h2  t2", n + 3),
+            ("    ", n + 1),
+        ];
+        let expected: Vec<_> = expected.iter().map(|&(s, n)| (s.to_owned(), n)).collect();
+        assert_eq!(steps, expected);
+    }
+
+    #[test]
+    fn test_streaming_matches_check() {
+        let cases = [
+            (PROOF, Ok(false)),
+            (
+                "(assume h1 (and p q))
+                (step t2 (cl p) :rule hole)
+                (step t3 (cl) :rule resolution :premises (t2 h1))",
+                Err(()),
+            ),
+            (
+                "(assume h1 (and p q))
+                (assume h2 (not p))
+                (step t2 (cl p) :rule hole)
+                (step t3 (cl) :rule resolution :premises (t2 h2))",
+                Ok(true),
+            ),
+            ("(assume h1 (and p q))", Err(())),
+        ];
+        for (proof, expected) in cases {
+            let check = crate::check(
+                PROBLEM.as_bytes(),
+                proof.as_bytes(),
+                parser::Config::new(),
+                Config::new(),
+                false,
+            );
+            let streaming = crate::check_streaming(
+                PROBLEM.as_bytes(),
+                || Ok(proof.as_bytes()),
+                parser::Config::new(),
+                Config::new(),
+            );
+            let single_pass = crate::check_streaming_single_pass(
+                PROBLEM.as_bytes(),
+                proof.as_bytes(),
+                parser::Config::new(),
+                Config::new(),
+            );
+            for result in [check, streaming, single_pass] {
+                assert_eq!(result.map_err(|_| ()), expected, "{}", proof);
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_pass_copy() {
+        use std::{fs, io};
+
+        let copy = crate::TempFile::new("test");
+        let mut tee = crate::Tee {
+            input: PROOF.as_bytes(),
+            copy: io::BufWriter::new(fs::File::create(&copy.0).unwrap()),
+        };
+        io::copy(&mut tee, &mut io::sink()).unwrap();
+        assert_eq!(fs::read_to_string(&copy.0).unwrap(), PROOF);
+
+        // The copy is removed once it is no longer needed
+        let path = copy.0.clone();
+        drop(copy);
+        assert!(!path.exists());
+    }
+}
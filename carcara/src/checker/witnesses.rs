@@ -0,0 +1,234 @@
+//! Freshness checking for the witnesses introduced by `exists_elim` steps.
+//!
+//! An `exists_elim` step concludes `φ[c/x]` from `(exists ((x S)) φ)`, where the witness `c` is a
+//! constant. This is only sound if nothing else is known about `c`, so each witness must be a
+//! symbol that appears neither in the problem premises nor in any command that comes before the
+//! step that introduces it. In particular, the same symbol can't be used as a witness twice.
+//!
+//! Since this depends on the whole proof, it can't be checked by the rule itself, and is instead
+//! checked by a separate pass over the proof commands.
+
+use super::error::QuantifierError;
+use crate::{ast::*, CarcaraResult, Error};
+use std::collections::HashSet;
+
+/// Checks that the witnesses of every `exists_elim` step in the proof are fresh. If the proof has
+/// no such steps, this does nothing.
+pub(super) fn check_witnesses(problem: &Problem, proof: &Proof) -> CarcaraResult<()> {
+    if uses_witnesses(proof.iter()) {
+        WitnessChecker::new(problem).check_commands(proof.iter())?;
+    }
+    Ok(())
+}
+
+/// Returns `true` if any step in the given commands uses the `exists_elim` rule.
+pub(super) fn uses_witnesses(mut commands: ProofIter) -> bool {
+    commands.any(|c| matches!(c, ProofCommand::Step(s) if s.rule == "exists_elim"))
+}
+
+/// Records the symbols that appear in the problem premises and in the commands checked so far.
+#[derive(Debug, Default)]
+pub(super) struct WitnessChecker {
+    symbols: HashSet<String>,
+    visited: HashSet<Rc<Term>>,
+}
+
+impl WitnessChecker {
+    pub fn new(problem: &Problem) -> Self {
+        let mut result = Self::default();
+        for premise in &problem.premises {
+            result.add_term(premise);
+        }
+        result
+    }
+
+    /// Checks the witnesses of every `exists_elim` step in `commands`, in order. The commands must
+    /// follow the ones given in previous calls.
+    pub fn check_commands(&mut self, commands: ProofIter) -> CarcaraResult<()> {
+        for command in commands {
+            match command {
+                ProofCommand::Assume { term, .. } => self.add_term(term),
+                ProofCommand::Step(step) => {
+                    if step.rule == "exists_elim" {
+                        if let Some(w) = step.args.iter().find(|w| self.occurs(w)) {
+                            return Err(Error::Checker {
+                                inner: QuantifierError::WitnessIsNotFresh(w.clone()).into(),
+                                rule: step.rule.clone(),
+                                step: step.id.clone(),
+                                location: None,
+                            });
+                        }
+                    }
+                    for term in step.clause.iter().chain(&step.args) {
+                        self.add_term(term);
+                    }
+                }
+                ProofCommand::Subproof(s) => {
+                    for arg in &s.args {
+                        let ((name, _), value) = match arg {
+                            AnchorArg::Variable(var) => (var, None),
+                            AnchorArg::Assign(var, value) => (var, Some(value)),
+                        };
+                        self.symbols.insert(name.clone());
+                        if let Some(value) = value {
+                            self.add_term(value);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn occurs(&self, witness: &Rc<Term>) -> bool {
+        match witness.as_ref() {
+            Term::Var(name, _) => self.symbols.contains(name),
+            _ => false,
+        }
+    }
+
+    /// Records every symbol that appears in `term`, including the names of bound variables.
+    fn add_term(&mut self, term: &Rc<Term>) {
+        let mut stack = vec![term.clone()];
+        while let Some(term) = stack.pop() {
+            if !self.visited.insert(term.clone()) {
+                continue;
+            }
+            match term.as_ref() {
+                Term::Const(_) | Term::Sort(_) => (),
+                Term::Var(name, _) => {
+                    self.symbols.insert(name.clone());
+                }
+                Term::App(f, args) => {
+                    stack.push(f.clone());
+                    stack.extend(args.iter().cloned());
+                }
+                Term::Op(_, args) => stack.extend(args.iter().cloned()),
+                Term::Binder(_, bindings, inner) | Term::Let(bindings, inner) => {
+                    for (name, value) in bindings {
+                        self.symbols.insert(name.clone());
+                        stack.push(value.clone());
+                    }
+                    stack.push(inner.clone());
+                }
+                Term::ParamOp { op_args, args, .. } => {
+                    stack.extend(op_args.iter().chain(args).cloned());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        checker::{
+            self,
+            error::{CheckerError, QuantifierError},
+        },
+        parser, Error,
+    };
+
+    const PROBLEM: &str = "
+        (declare-fun p (Int) Bool)
+        (declare-fun d () Int)
+        (assert (exists ((x Int)) (p x)))
+        (assert (forall ((x Int)) (not (p x))))
+    ";
+
+    fn run(problem: &str, proof: &str) -> Result<bool, Error> {
+        crate::check(
+            problem.as_bytes(),
+            proof.as_bytes(),
+            parser::Config::new(),
+            checker::Config::new(),
+            false,
+        )
+    }
+
+    fn run_streaming(problem: &str, proof: &str) -> Result<bool, Error> {
+        crate::check_streaming(
+            problem.as_bytes(),
+            || Ok(proof.as_bytes()),
+            parser::Config::new(),
+            checker::Config::new(),
+        )
+    }
+
+    fn is_not_fresh(result: Result<bool, Error>) -> bool {
+        matches!(
+            result,
+            Err(Error::Checker {
+                inner: CheckerError::Quant(QuantifierError::WitnessIsNotFresh(_)),
+                ..
+            })
+        )
+    }
+
+    #[test]
+    fn test_witness_in_problem() {
+        // This problem is satisfiable, so the proof must be rejected. The witness `c` is not fresh,
+        // since it is constrained by the second premise
+        let problem = "
+            (declare-fun p (Int) Bool)
+            (declare-fun c () Int)
+            (assert (exists ((x Int)) (p x)))
+            (assert (not (p c)))
+        ";
+        let proof = "
+            (assume h1 (exists ((x Int)) (p x)))
+            (assume h2 (not (p c)))
+            (step t1 (cl (p c)) :rule exists_elim :premises (h1) :args (c))
+            (step t2 (cl) :rule resolution :premises (t1 h2))
+        ";
+        assert!(is_not_fresh(run(problem, proof)));
+        assert!(is_not_fresh(run_streaming(problem, proof)));
+    }
+
+    #[test]
+    fn test_witness_order() {
+        let proof = "
+            (assume h1 (exists ((x Int)) (p x)))
+            (assume h2 (forall ((x Int)) (not (p x))))
+            (step t1 (cl (p d)) :rule exists_elim :premises (h1) :args (d))
+            (step t2 (cl (or (not (forall ((x Int)) (not (p x)))) (not (p d))))
+                :rule forall_inst :args (d))
+            (step t3 (cl (not (forall ((x Int)) (not (p x)))) (not (p d))) :rule or :premises (t2))
+            (step t4 (cl) :rule resolution :premises (t3 h2 t1))
+        ";
+        assert_eq!(run(PROBLEM, proof).ok(), Some(false));
+        assert_eq!(run_streaming(PROBLEM, proof).ok(), Some(false));
+
+        // If `d` is used before the `exists_elim` step, it is no longer fresh
+        let proof = "
+            (assume h1 (exists ((x Int)) (p x)))
+            (assume h2 (forall ((x Int)) (not (p x))))
+            (step t2 (cl (or (not (forall ((x Int)) (not (p x)))) (not (p d))))
+                :rule forall_inst :args (d))
+            (step t1 (cl (p d)) :rule exists_elim :premises (h1) :args (d))
+            (step t3 (cl (not (forall ((x Int)) (not (p x)))) (not (p d))) :rule or :premises (t2))
+            (step t4 (cl) :rule resolution :premises (t3 h2 t1))
+        ";
+        assert!(is_not_fresh(run(PROBLEM, proof)));
+        assert!(is_not_fresh(run_streaming(PROBLEM, proof)));
+    }
+
+    #[test]
+    fn test_repeated_witness() {
+        let problem = "
+            (declare-fun p (Int) Bool)
+            (declare-fun d () Int)
+            (assert (exists ((x Int)) (p x)))
+            (assert (exists ((x Int)) (not (p x))))
+        ";
+        let proof = "
+            (assume h1 (exists ((x Int)) (p x)))
+            (assume h2 (exists ((x Int)) (not (p x))))
+            (step t1 (cl (p d)) :rule exists_elim :premises (h1) :args (d))
+            (step t2 (cl (not (p d))) :rule exists_elim :premises (h2) :args (d))
+            (step t3 (cl) :rule resolution :premises (t1 t2))
+        ";
+        assert!(is_not_fresh(run(problem, proof)));
+        assert!(is_not_fresh(run_streaming(problem, proof)));
+    }
+}
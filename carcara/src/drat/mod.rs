@@ -0,0 +1,357 @@
+//! A checker for DRAT and LRAT proofs of unsatisfiability of propositional formulas, given in the
+//! DIMACS CNF format. Only the textual formats of DRAT and LRAT are supported.
+//!
+//! DRAT proofs are checked forwards: each added clause must be a reverse unit propagation (RUP)
+//! consequence of the current clause database, or a resolution asymmetric tautology (RAT) on its
+//! first literal. LRAT proofs are checked by following the unit propagation hints given for each
+//! clause. Hints for RAT steps in LRAT proofs are not supported.
+
+mod parser;
+
+pub use parser::{parse_cnf, parse_drat, parse_lrat};
+
+use indexmap::IndexMap;
+use std::{collections::HashMap, io};
+use thiserror::Error;
+
+/// A clause, represented by its literals. Variables are positive integers, and a negative literal
+/// is represented by the negation of its variable.
+pub type Clause = Vec<i32>;
+
+/// The format of a propositional proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofFormat {
+    Drat,
+    Lrat,
+}
+
+/// A step in a DRAT proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DratStep {
+    Add(Clause),
+    Delete(Clause),
+}
+
+/// A step in a LRAT proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LratStep {
+    /// Adds a clause with the given id, which is derived by unit propagation from the clauses
+    /// given as hints, in order.
+    Add {
+        id: u64,
+        clause: Clause,
+        hints: Vec<i64>,
+    },
+
+    /// Deletes the clauses with the given ids.
+    Delete(Vec<u64>),
+}
+
+#[derive(Debug, Error)]
+pub enum DratError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("syntax error on line {line}: {message}")]
+    Syntax { line: usize, message: String },
+
+    #[error("lemma {0} is not implied by the clause database: {}", display_clause(.1))]
+    InvalidLemma(usize, Clause),
+
+    #[error("clause {0} is not defined")]
+    UndefinedClause(u64),
+
+    #[error("clause {0} was defined more than once")]
+    RepeatedClauseId(u64),
+
+    #[error("hint {1} of clause {0} is not a unit clause")]
+    HintIsNotUnit(u64, u64),
+
+    #[error("hints of clause {0} don't lead to a conflict")]
+    NoConflict(u64),
+
+    #[error("RAT hints are not supported, found in clause {0}")]
+    RatHint(u64),
+
+    #[error("proof does not derive the empty clause")]
+    DoesNotReachEmptyClause,
+}
+
+pub type DratResult<T> = Result<T, DratError>;
+
+fn display_clause(clause: &[i32]) -> String {
+    let literals: Vec<_> = clause.iter().map(ToString::to_string).collect();
+    format!("[{}]", literals.join(" "))
+}
+
+/// A partial assignment of truth values to variables.
+#[derive(Debug, Default)]
+struct Assignment(HashMap<u32, bool>);
+
+impl Assignment {
+    fn value(&self, literal: i32) -> Option<bool> {
+        let value = self.0.get(&literal.unsigned_abs())?;
+        Some(*value == (literal > 0))
+    }
+
+    /// Makes the literal true. Returns `false` if it was already false.
+    fn assign(&mut self, literal: i32) -> bool {
+        *self.0.entry(literal.unsigned_abs()).or_insert(literal > 0) == (literal > 0)
+    }
+}
+
+/// The status of a clause under an assignment.
+enum ClauseStatus {
+    Satisfied,
+    Falsified,
+    Unit(i32),
+    Unresolved,
+}
+
+fn clause_status(clause: &[i32], assignment: &Assignment) -> ClauseStatus {
+    let mut unassigned = None;
+    let mut num_unassigned = 0;
+    for &l in clause {
+        match assignment.value(l) {
+            Some(true) => return ClauseStatus::Satisfied,
+            Some(false) => (),
+            None => {
+                unassigned = Some(l);
+                num_unassigned += 1;
+            }
+        }
+    }
+    match (num_unassigned, unassigned) {
+        (0, _) => ClauseStatus::Falsified,
+        (1, Some(l)) => ClauseStatus::Unit(l),
+        _ => ClauseStatus::Unresolved,
+    }
+}
+
+/// The clause database used when checking DRAT proofs. Clauses are indexed by their sorted
+/// literals, so they can be deleted, and each clause may appear more than once.
+#[derive(Debug, Default)]
+struct ClauseDatabase(IndexMap<Clause, usize>);
+
+impl ClauseDatabase {
+    fn key(clause: &[i32]) -> Clause {
+        let mut key = clause.to_vec();
+        key.sort_unstable();
+        key.dedup();
+        key
+    }
+
+    fn add(&mut self, clause: &[i32]) {
+        *self.0.entry(Self::key(clause)).or_default() += 1;
+    }
+
+    fn delete(&mut self, clause: &[i32]) {
+        let key = Self::key(clause);
+        match self.0.get_mut(&key) {
+            Some(1) => {
+                self.0.shift_remove(&key);
+            }
+            Some(n) => *n -= 1,
+            None => tracing::debug!(
+                clause = %display_clause(clause),
+                "ignoring deletion of clause that is not in the database"
+            ),
+        }
+    }
+
+    /// Returns `true` if unit propagation from the negation of the clause leads to a conflict.
+    fn is_rup(&self, clause: &[i32]) -> bool {
+        let mut assignment = Assignment::default();
+        for &l in clause {
+            if !assignment.assign(-l) {
+                // The clause is a tautology
+                return true;
+            }
+        }
+        loop {
+            let mut changed = false;
+            for c in self.0.keys() {
+                match clause_status(c, &assignment) {
+                    ClauseStatus::Falsified => return true,
+                    ClauseStatus::Unit(l) => {
+                        assignment.assign(l);
+                        changed = true;
+                    }
+                    ClauseStatus::Satisfied | ClauseStatus::Unresolved => (),
+                }
+            }
+            if !changed {
+                return false;
+            }
+        }
+    }
+
+    /// Returns `true` if the clause is a resolution asymmetric tautology on its first literal.
+    fn is_rat(&self, clause: &[i32]) -> bool {
+        let Some(&pivot) = clause.first() else {
+            return false;
+        };
+        self.0.keys().filter(|c| c.contains(&-pivot)).all(|c| {
+            let resolvent: Vec<_> = clause
+                .iter()
+                .chain(c.iter().filter(|&&l| l != -pivot))
+                .copied()
+                .collect();
+            self.is_rup(&resolvent)
+        })
+    }
+}
+
+/// Checks a DRAT proof that the formula is unsatisfiable.
+pub fn check_drat(formula: &[Clause], proof: &[DratStep]) -> DratResult<()> {
+    let mut database = ClauseDatabase::default();
+    for clause in formula {
+        if clause.is_empty() {
+            return Ok(());
+        }
+        database.add(clause);
+    }
+    let mut num_lemmas = 0;
+    for step in proof {
+        match step {
+            DratStep::Add(clause) => {
+                num_lemmas += 1;
+                if !database.is_rup(clause) && !database.is_rat(clause) {
+                    return Err(DratError::InvalidLemma(num_lemmas, clause.clone()));
+                }
+                if clause.is_empty() {
+                    return Ok(());
+                }
+                database.add(clause);
+            }
+            DratStep::Delete(clause) => database.delete(clause),
+        }
+    }
+    Err(DratError::DoesNotReachEmptyClause)
+}
+
+/// Checks a LRAT proof that the formula is unsatisfiable. The clauses in the formula are given the
+/// ids `1` to `n`, in order.
+pub fn check_lrat(formula: &[Clause], proof: &[LratStep]) -> DratResult<()> {
+    let mut database: HashMap<u64, &Clause> = (1..).zip(formula).collect();
+    if formula.iter().any(Vec::is_empty) {
+        return Ok(());
+    }
+    for step in proof {
+        let (id, clause, hints) = match step {
+            LratStep::Add { id, clause, hints } => (*id, clause, hints),
+            LratStep::Delete(ids) => {
+                for id in ids {
+                    database.remove(id);
+                }
+                continue;
+            }
+        };
+
+        let mut assignment = Assignment::default();
+        let is_tautology = !clause.iter().all(|&l| assignment.assign(-l));
+        let mut found_conflict = is_tautology;
+        for &hint in hints {
+            if found_conflict {
+                break;
+            }
+            let hint = u64::try_from(hint).map_err(|_| DratError::RatHint(id))?;
+            let hint_clause = database
+                .get(&hint)
+                .ok_or(DratError::UndefinedClause(hint))?;
+            match clause_status(hint_clause, &assignment) {
+                ClauseStatus::Falsified => found_conflict = true,
+                ClauseStatus::Unit(l) => {
+                    assignment.assign(l);
+                }
+                ClauseStatus::Satisfied | ClauseStatus::Unresolved => {
+                    return Err(DratError::HintIsNotUnit(id, hint))
+                }
+            }
+        }
+        if !found_conflict {
+            return Err(DratError::NoConflict(id));
+        }
+        if clause.is_empty() {
+            return Ok(());
+        }
+        if database.insert(id, clause).is_some() {
+            return Err(DratError::RepeatedClauseId(id));
+        }
+    }
+    Err(DratError::DoesNotReachEmptyClause)
+}
+
+/// Parses and checks a propositional proof of unsatisfiability of the given DIMACS CNF formula.
+pub fn check<T: io::BufRead>(problem: T, proof: T, format: ProofFormat) -> DratResult<()> {
+    let formula = parse_cnf(problem)?;
+    match format {
+        ProofFormat::Drat => check_drat(&formula, &parse_drat(proof)?),
+        ProofFormat::Lrat => check_lrat(&formula, &parse_lrat(proof)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The formula `(x1 | x2) & (x1 | -x2) & (-x1 | x2) & (-x1 | -x2)`, which is unsatisfiable
+    const FORMULA: &str = "p cnf 2 4\n1 2 0\n1 -2 0\n-1 2 0\n-1 -2 0\n";
+
+    fn check_str(proof: &str, format: ProofFormat) -> DratResult<()> {
+        check(FORMULA.as_bytes(), proof.as_bytes(), format)
+    }
+
+    #[test]
+    fn test_drat() {
+        assert!(check_str("1 0\n0\n", ProofFormat::Drat).is_ok());
+        assert!(check_str("1 0\nd 1 2 0\nd 1 -2 0\n0\n", ProofFormat::Drat).is_ok());
+
+        // `3` is a fresh variable, so the unit clause `3` is RAT on it, but not RUP
+        assert!(check_str("3 0\n1 0\n0\n", ProofFormat::Drat).is_ok());
+
+        assert!(matches!(
+            check_str("1 0\nd 1 0\n0\n", ProofFormat::Drat),
+            Err(DratError::InvalidLemma(2, _))
+        ));
+        assert!(matches!(
+            check_str("1 0\n", ProofFormat::Drat),
+            Err(DratError::DoesNotReachEmptyClause)
+        ));
+        assert!(matches!(
+            check(
+                "p cnf 2 1\n1 2 0".as_bytes(),
+                "0\n".as_bytes(),
+                ProofFormat::Drat
+            ),
+            Err(DratError::InvalidLemma(1, _))
+        ));
+    }
+
+    #[test]
+    fn test_lrat() {
+        assert!(check_str("5 1 0 1 2 0\n6 0 5 3 4 0\n", ProofFormat::Lrat).is_ok());
+        assert!(check_str("5 1 0 1 2 0\n5 d 1 2 0\n6 0 5 3 4 0\n", ProofFormat::Lrat).is_ok());
+
+        assert!(matches!(
+            check_str("5 1 0 1 0\n", ProofFormat::Lrat),
+            Err(DratError::NoConflict(5))
+        ));
+        assert!(matches!(
+            check_str("5 1 0 3 0\n", ProofFormat::Lrat),
+            Err(DratError::HintIsNotUnit(5, 3))
+        ));
+        assert!(matches!(
+            check_str("5 1 0 1 2 0\n5 d 5 0\n6 0 5 3 4 0\n", ProofFormat::Lrat),
+            Err(DratError::UndefinedClause(5))
+        ));
+        assert!(matches!(
+            check_str("5 1 0 -1 0\n", ProofFormat::Lrat),
+            Err(DratError::RatHint(5))
+        ));
+        assert!(matches!(
+            check_str("5 1 0 1 2 0\n", ProofFormat::Lrat),
+            Err(DratError::DoesNotReachEmptyClause)
+        ));
+    }
+}
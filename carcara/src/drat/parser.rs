@@ -0,0 +1,165 @@
+//! Parsers for DIMACS CNF formulas, and for DRAT and LRAT proofs in the textual format.
+
+use super::{Clause, DratError, DratResult, DratStep, LratStep};
+use std::io::BufRead;
+
+/// Splits the input into tokens, together with the line where each of them appears. Lines starting
+/// with `c` are comments.
+fn tokens<T: BufRead>(input: T) -> DratResult<Vec<(usize, String)>> {
+    let mut result = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim_start().starts_with('c') {
+            continue;
+        }
+        result.extend(line.split_whitespace().map(|t| (i + 1, t.to_owned())));
+    }
+    Ok(result)
+}
+
+fn parse_int<N: std::str::FromStr>((line, token): &(usize, String)) -> DratResult<N> {
+    token.parse().map_err(|_| DratError::Syntax {
+        line: *line,
+        message: format!("expected integer, got '{}'", token),
+    })
+}
+
+/// Reads integers until a terminating `0`.
+fn read_until_zero<'a, N, I>(iter: &mut I, last_line: usize) -> DratResult<Vec<N>>
+where
+    N: std::str::FromStr + Default + PartialEq,
+    I: Iterator<Item = &'a (usize, String)>,
+{
+    let mut result = Vec::new();
+    loop {
+        let Some(token) = iter.next() else {
+            return Err(DratError::Syntax {
+                line: last_line,
+                message: "missing terminating '0'".to_owned(),
+            });
+        };
+        let n = parse_int(token)?;
+        if n == N::default() {
+            return Ok(result);
+        }
+        result.push(n);
+    }
+}
+
+/// Parses a formula in the DIMACS CNF format.
+pub fn parse_cnf<T: BufRead>(input: T) -> DratResult<Vec<Clause>> {
+    let tokens = tokens(input)?;
+    let last_line = tokens.last().map_or(0, |(l, _)| *l);
+    let mut iter = tokens.iter().peekable();
+    match iter.next() {
+        Some((_, p)) if p == "p" => {
+            let format = iter.next();
+            if format.map(|(_, f)| f.as_str()) != Some("cnf") {
+                let line = format.map_or(last_line, |(l, _)| *l);
+                let message = "expected 'p cnf <variables> <clauses>' header".to_owned();
+                return Err(DratError::Syntax { line, message });
+            }
+            for _ in 0..2 {
+                let token = iter.next().ok_or(DratError::Syntax {
+                    line: last_line,
+                    message: "incomplete header".to_owned(),
+                })?;
+                parse_int::<usize>(token)?;
+            }
+        }
+        Some((line, _)) => {
+            let message = "expected 'p cnf <variables> <clauses>' header".to_owned();
+            return Err(DratError::Syntax { line: *line, message });
+        }
+        None => return Ok(Vec::new()),
+    }
+    let mut clauses = Vec::new();
+    while iter.peek().is_some() {
+        clauses.push(read_until_zero(&mut iter, last_line)?);
+    }
+    Ok(clauses)
+}
+
+/// Parses a DRAT proof, in the textual format.
+pub fn parse_drat<T: BufRead>(input: T) -> DratResult<Vec<DratStep>> {
+    let tokens = tokens(input)?;
+    let last_line = tokens.last().map_or(0, |(l, _)| *l);
+    let mut iter = tokens.iter().peekable();
+    let mut steps = Vec::new();
+    while let Some((_, token)) = iter.peek() {
+        let step = if token == "d" {
+            iter.next();
+            DratStep::Delete(read_until_zero(&mut iter, last_line)?)
+        } else {
+            DratStep::Add(read_until_zero(&mut iter, last_line)?)
+        };
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+/// Parses an LRAT proof, in the textual format.
+pub fn parse_lrat<T: BufRead>(input: T) -> DratResult<Vec<LratStep>> {
+    let tokens = tokens(input)?;
+    let last_line = tokens.last().map_or(0, |(l, _)| *l);
+    let mut iter = tokens.iter().peekable();
+    let mut steps = Vec::new();
+    while let Some(token) = iter.next() {
+        let id: u64 = parse_int(token)?;
+        let step = if iter.peek().is_some_and(|(_, t)| t == "d") {
+            iter.next();
+            LratStep::Delete(read_until_zero(&mut iter, last_line)?)
+        } else {
+            let clause = read_until_zero(&mut iter, last_line)?;
+            let hints = read_until_zero(&mut iter, last_line)?;
+            LratStep::Add { id, clause, hints }
+        };
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsers() {
+        let cnf = "c a comment\np cnf 2 3\n1 2 0\n-1 0 -2\n0\n";
+        assert_eq!(
+            parse_cnf(cnf.as_bytes()).unwrap(),
+            vec![vec![1, 2], vec![-1], vec![-2]]
+        );
+        assert!(parse_cnf("1 2 0".as_bytes()).is_err());
+        assert!(parse_cnf("p cnf 2 1\n1 2".as_bytes()).is_err());
+
+        let drat = "1 0\nd 1 2 0\n0\n";
+        assert_eq!(
+            parse_drat(drat.as_bytes()).unwrap(),
+            vec![
+                DratStep::Add(vec![1]),
+                DratStep::Delete(vec![1, 2]),
+                DratStep::Add(Vec::new())
+            ]
+        );
+
+        let lrat = "4 1 0 1 2 0\n4 d 1 2 0\n5 0 4 3 0\n";
+        assert_eq!(
+            parse_lrat(lrat.as_bytes()).unwrap(),
+            vec![
+                LratStep::Add {
+                    id: 4,
+                    clause: vec![1],
+                    hints: vec![1, 2]
+                },
+                LratStep::Delete(vec![1, 2]),
+                LratStep::Add {
+                    id: 5,
+                    clause: Vec::new(),
+                    hints: vec![4, 3]
+                },
+            ]
+        );
+        assert!(parse_lrat("4 1 0 1 x 0".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use super::IdHelper;
+use crate::{ast::*, checker::error::CheckerError};
+
+/// Returns the terms `t` and `u` of an inequality `(<= t u)` or `(>= u t)`.
+fn match_less_eq(term: &Rc<Term>) -> Result<(&Rc<Term>, &Rc<Term>), CheckerError> {
+    if let Some((u, t)) = match_term!((>= u t) = term) {
+        return Ok((t, u));
+    }
+    match_term_err!((<= t u) = term)
+}
+
+/// Given a premise stating that `t <= u`, returns a step concluding exactly `(<= t u)`. If the
+/// premise is written as `(>= u t)`, this is derived with a `la_generic` step.
+fn normalize_inequality(
+    pool: &mut PrimitivePool,
+    premise: &Rc<ProofNode>,
+    (t, u): (&Rc<Term>, &Rc<Term>),
+    ids: &mut IdHelper,
+    depth: usize,
+) -> Rc<ProofNode> {
+    let target = build_term!(pool, (<= {t.clone()} {u.clone()}));
+    let term = &premise.clause()[0];
+    if *term == target {
+        return premise.clone();
+    }
+
+    let one = pool.add(Term::new_int(1));
+    let la_generic_step = Rc::new(ProofNode::Step(StepNode {
+        id: ids.next_id(),
+        depth,
+        clause: vec![build_term!(pool, (not {term.clone()})), target.clone()],
+        rule: "la_generic".to_owned(),
+        args: vec![one.clone(), one],
+        ..Default::default()
+    }));
+    Rc::new(ProofNode::Step(StepNode {
+        id: ids.next_id(),
+        depth,
+        clause: vec![target],
+        rule: "resolution".to_owned(),
+        premises: vec![premise.clone(), la_generic_step],
+        args: vec![term.clone(), pool.bool_true()],
+        ..Default::default()
+    }))
+}
+
+/// Elaborates a `la_antisymmetry` step into a `la_disequality` step, which is resolved with the
+/// two inequalities. Premises written with `>=` are first rewritten into `<=` using `la_generic`.
+pub fn la_antisymmetry(
+    pool: &mut PrimitivePool,
+    _: &mut ContextStack,
+    step: &StepNode,
+) -> Result<Rc<ProofNode>, CheckerError> {
+    assert_eq!(step.clause.len(), 1);
+    assert_eq!(step.premises.len(), 2);
+
+    let (t, u) = match_term_err!((= t u) = &step.clause[0])?;
+    let mut ids = IdHelper::new(&step.id);
+    let depth = step.depth;
+
+    // The first premise may state either `t <= u` or `u <= t`
+    let (first, second) = if match_less_eq(&step.premises[0].clause()[0])?.0 == t {
+        (&step.premises[0], &step.premises[1])
+    } else {
+        (&step.premises[1], &step.premises[0])
+    };
+    let t_le_u = normalize_inequality(pool, first, (t, u), &mut ids, depth);
+    let u_le_t = normalize_inequality(pool, second, (u, t), &mut ids, depth);
+    let (t_le_u_term, u_le_t_term) = (t_le_u.clause()[0].clone(), u_le_t.clause()[0].clone());
+
+    let disequality = build_term!(
+        pool,
+        (or {step.clause[0].clone()} (not {t_le_u_term.clone()}) (not {u_le_t_term.clone()}))
+    );
+    let la_disequality_step = Rc::new(ProofNode::Step(StepNode {
+        id: ids.next_id(),
+        depth,
+        clause: vec![disequality],
+        rule: "la_disequality".to_owned(),
+        ..Default::default()
+    }));
+    let or_step = Rc::new(ProofNode::Step(StepNode {
+        id: ids.next_id(),
+        depth,
+        clause: vec![
+            step.clause[0].clone(),
+            build_term!(pool, (not {t_le_u_term.clone()})),
+            build_term!(pool, (not {u_le_t_term.clone()})),
+        ],
+        rule: "or".to_owned(),
+        premises: vec![la_disequality_step],
+        ..Default::default()
+    }));
+
+    let false_term = pool.bool_false();
+    Ok(Rc::new(ProofNode::Step(StepNode {
+        id: step.id.clone(),
+        depth,
+        clause: step.clause.clone(),
+        rule: "resolution".to_owned(),
+        premises: vec![or_step, t_le_u, u_le_t],
+        args: vec![t_le_u_term, false_term.clone(), u_le_t_term, false_term],
+        ..Default::default()
+    })))
+}
@@ -0,0 +1,96 @@
+use crate::{ast::*, checker};
+use rug::{Integer, Rational};
+
+/// Returns the integer `n` as a term. Negative numbers are written as `(- n)`, since the SMT-LIB
+/// standard has no negative numerals.
+fn integer_term(pool: &mut PrimitivePool, n: Integer) -> Rc<Term> {
+    if n < 0 {
+        let abs = pool.add(Term::new_int(-n));
+        pool.add(Term::Op(Operator::Sub, vec![abs]))
+    } else {
+        pool.add(Term::new_int(n))
+    }
+}
+
+/// Returns `true` if the term is an integer numeral, possibly negated.
+pub fn is_integer_coefficient(term: &Rc<Term>) -> bool {
+    term.as_signed_integer().is_some()
+}
+
+/// Rewrites the arguments of a `la_generic` step so that all coefficients are integers, by
+/// multiplying them by the least common multiple of their denominators. The new step is checked
+/// before being returned, and if it is not valid the original step is kept instead.
+pub fn integer_coefficients(pool: &mut PrimitivePool, step: &StepNode) -> Option<Rc<ProofNode>> {
+    if step.args.iter().all(is_integer_coefficient) {
+        return None;
+    }
+    let coefficients: Vec<Rational> = step
+        .args
+        .iter()
+        .map(|a| a.as_fraction())
+        .collect::<Option<_>>()?;
+
+    let factor = coefficients
+        .iter()
+        .fold(Integer::from(1), |acc, c| acc.lcm(c.denom()));
+    let args: Vec<_> = coefficients
+        .into_iter()
+        .map(|c| {
+            let (numer, denom) = (c * &factor).into_numer_denom();
+            debug_assert_eq!(denom, 1);
+            integer_term(pool, numer)
+        })
+        .collect();
+
+    let config = checker::Config::new();
+    checker::check_step(pool, &config, &[], &step.rule, &args, &step.clause).ok()?;
+
+    Some(Rc::new(ProofNode::Step(StepNode { args, ..step.clone() })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::tests::parse_terms;
+
+    #[test]
+    fn test_integer_coefficients() {
+        let mut pool = PrimitivePool::new();
+        let [a, b, half, third, one, two, minus_two] = parse_terms(
+            &mut pool,
+            "(declare-fun x () Real)",
+            [
+                "(< x 1.0)",
+                "(> x 0.0)",
+                "(/ 1.0 2.0)",
+                "(/ 1.0 3.0)",
+                "1",
+                "2",
+                "(- 2)",
+            ],
+        );
+        let step = StepNode {
+            id: "t1".to_owned(),
+            clause: vec![a, b],
+            rule: "la_generic".to_owned(),
+            args: vec![half.clone(), half],
+            ..Default::default()
+        };
+        let elaborated = integer_coefficients(&mut pool, &step).unwrap();
+        let ProofNode::Step(elaborated) = elaborated.as_ref() else {
+            panic!("expected a step");
+        };
+        assert_eq!(elaborated.args, [one.clone(), one]);
+
+        // If the rewritten step is not valid, the original step is kept
+        let invalid = StepNode {
+            args: vec![third, minus_two.clone()],
+            ..step.clone()
+        };
+        assert!(integer_coefficients(&mut pool, &invalid).is_none());
+
+        // Coefficients that are already integers are left untouched
+        let step = StepNode { args: vec![two, minus_two], ..step };
+        assert!(integer_coefficients(&mut pool, &step).is_none());
+    }
+}
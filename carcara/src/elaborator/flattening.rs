@@ -0,0 +1,137 @@
+use super::*;
+use crate::checker::{self, apply_ac_simp};
+use indexmap::IndexMap;
+
+/// Elaborates a clausification step whose premise has nested `and` or `or` terms that the producer
+/// considered flattened. For example, an `or` step that concludes `(cl a b c)` from the premise
+/// `(or a (or b c))`. In that case, the premise is first flattened with an `ac_simp` step, and the
+/// original step is changed to use the flattened premise instead. Steps that are already valid are
+/// left unchanged.
+pub fn flattening(pool: &mut PrimitivePool, step: &StepNode) -> Rc<ProofNode> {
+    let unchanged = || Rc::new(ProofNode::Step(step.clone()));
+
+    let [premise] = step.premises.as_slice() else {
+        return unchanged();
+    };
+    let [original] = premise.clause() else {
+        return unchanged();
+    };
+    let flattened = apply_ac_simp(pool, &mut IndexMap::new(), original);
+    if flattened == *original {
+        return unchanged();
+    }
+
+    let config = checker::Config::new();
+    let is_valid = |pool: &mut PrimitivePool, premise: &Rc<Term>| {
+        let premise = std::slice::from_ref(premise);
+        checker::check_step(
+            pool,
+            &config,
+            &[premise],
+            &step.rule,
+            &step.args,
+            &step.clause,
+        )
+        .is_ok()
+    };
+    if is_valid(pool, original) || !is_valid(pool, &flattened) {
+        return unchanged();
+    }
+
+    let mut ids = IdHelper::new(&step.id);
+    let depth = step.depth;
+    let ac_simp_step = Rc::new(ProofNode::Step(StepNode {
+        id: ids.next_id(),
+        depth,
+        clause: vec![build_term!(pool, (= {original.clone()} {flattened.clone()}))],
+        rule: "ac_simp".to_owned(),
+        ..Default::default()
+    }));
+    let equiv1_step = Rc::new(ProofNode::Step(StepNode {
+        id: ids.next_id(),
+        depth,
+        clause: vec![
+            build_term!(pool, (not {original.clone()})),
+            flattened.clone(),
+        ],
+        rule: "equiv1".to_owned(),
+        premises: vec![ac_simp_step],
+        ..Default::default()
+    }));
+    let flattened_premise = Rc::new(ProofNode::Step(StepNode {
+        id: ids.next_id(),
+        depth,
+        clause: vec![flattened],
+        rule: "resolution".to_owned(),
+        premises: vec![premise.clone(), equiv1_step],
+        args: vec![original.clone(), pool.bool_true()],
+        ..Default::default()
+    }));
+
+    Rc::new(ProofNode::Step(StepNode {
+        premises: vec![flattened_premise],
+        ..step.clone()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{checker::ProofChecker, parser};
+
+    #[test]
+    fn test_flattening() {
+        let problem = "
+            (declare-fun a () Bool)
+            (declare-fun b () Bool)
+            (declare-fun c () Bool)
+            (assert (or a (or b c)))
+            (assert (and (not a) (and (not b) (not c))))
+        ";
+        let proof = "
+            (assume h1 (or a (or b c)))
+            (assume h2 (and (not a) (and (not b) (not c))))
+            (step t1 (cl a b c) :rule or :premises (h1))
+            (step t2 (cl (not a)) :rule and :premises (h2) :args (0))
+            (step t3 (cl (not b)) :rule and :premises (h2) :args (1))
+            (step t4 (cl (not c)) :rule and :premises (h2) :args (2))
+            (step t5 (cl) :rule resolution :premises (t1 t2 t3 t4))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        assert!(ProofChecker::new(&mut pool, checker::Config::new())
+            .check(&problem, &proof)
+            .is_err());
+
+        let node = ProofNode::from_commands(proof.commands);
+        let config = Config {
+            lia_solver: None,
+            nla_solver: None,
+            uncrowd_rotation: false,
+            hole_solver: None,
+            solver_limits: Default::default(),
+            solver_cache: None,
+            limits: Default::default(),
+            trace: false,
+            target: Default::default(),
+        };
+        let elaborated = Elaborator::new(&mut pool, &problem, config)
+            .elaborate(&node, vec![ElaborationStep::Flattening])
+            .unwrap();
+        let elaborated = Proof {
+            constant_definitions: proof.constant_definitions,
+            commands: elaborated.into_commands(),
+        };
+        // `t2` is valid without flattening, so only the other steps are elaborated
+        let num_ac_simp_steps = elaborated
+            .iter()
+            .filter(|c| matches!(c, ProofCommand::Step(s) if s.rule == "ac_simp"))
+            .count();
+        assert_eq!(num_ac_simp_steps, 3);
+
+        ProofChecker::new(&mut pool, checker::Config::new())
+            .check(&problem, &elaborated)
+            .unwrap();
+    }
+}
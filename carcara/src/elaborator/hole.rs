@@ -1,61 +1,15 @@
 use super::*;
 use crate::{checker, parser, CarcaraResult};
-use std::{
-    io::{self, BufRead, Write},
-    process::{Command, Stdio},
-};
-use thiserror::Error;
-
-#[derive(Debug, Error)]
-pub enum HoleError {
-    #[error("failed to spawn solver process")]
-    FailedSpawnSolver(io::Error),
-
-    #[error("failed to write to solver stdin")]
-    FailedWriteToSolverStdin(io::Error),
-
-    #[error("error while waiting for solver to exit")]
-    FailedWaitForSolver(io::Error),
-
-    #[error("solver gave invalid output")]
-    SolverGaveInvalidOutput,
-
-    #[error("solver output not unsat")]
-    OutputNotUnsat,
-
-    #[error("solver timed out when solving problem")]
-    SolverTimeout,
-
-    #[error(
-        "solver returned non-zero exit code: {}",
-        if let Some(i) = .0 { format!("{}", i) } else { "none".to_owned() }
-    )]
-    NonZeroExitCode(Option<i32>),
-
-    #[error("error in inner proof: {0}")]
-    InnerProofError(Box<crate::Error>),
-}
 
 fn get_problem_string(
     pool: &mut PrimitivePool,
+    solver: &dyn ExternalSolver,
     prelude: &ProblemPrelude,
     conclusion: &[Rc<Term>],
 ) -> String {
-    use std::fmt::Write;
-
-    let mut problem = String::new();
-    writeln!(&mut problem, "(set-option :produce-proofs true)").unwrap();
-    write!(&mut problem, "{}", prelude).unwrap();
-
     let mut bytes = Vec::new();
     printer::write_lia_smt_instance(pool, prelude, &mut bytes, conclusion, false).unwrap();
-    write!(&mut problem, "{}", String::from_utf8(bytes).unwrap()).unwrap();
-
-    writeln!(&mut problem, "(check-sat)").unwrap();
-    writeln!(&mut problem, "(get-proof)").unwrap();
-    writeln!(&mut problem, "(exit)").unwrap();
-
-    problem
+    solver.problem_string(prelude, &String::from_utf8(bytes).unwrap())
 }
 
 pub fn hole(elaborator: &mut Elaborator, step: &StepNode) -> Option<Rc<ProofNode>> {
@@ -68,16 +22,25 @@ pub fn hole(elaborator: &mut Elaborator, step: &StepNode) -> Option<Rc<ProofNode
     } else {
         prelude
     };
-    let problem = get_problem_string(elaborator.pool, &prelude, &step.clause);
-    let options = elaborator.config.hole_options.as_ref().unwrap();
-    let commands = match get_solver_proof(elaborator.pool, problem.clone(), options) {
+    let solver = elaborator.config.hole_solver.clone().unwrap();
+    let problem = get_problem_string(elaborator.pool, solver.as_ref(), &prelude, &step.clause);
+    let commands = match get_solver_proof(
+        elaborator.pool,
+        solver.as_ref(),
+        problem,
+        &elaborator.config,
+    ) {
         Ok((c, false)) => c,
         Ok((_, true)) => {
-            log::warn!("failed to elaborate `all_simplify` step: solver proof contains holes");
+            tracing::warn!("failed to elaborate `all_simplify` step: solver proof contains holes");
             return None;
         }
         Err(e) => {
-            log::warn!("failed to elaborate `all_simplify` step: {}", e);
+            tracing::warn!(
+                "failed to elaborate `all_simplify` step with {}: {}",
+                solver.name(),
+                e
+            );
             return None;
         }
     };
@@ -93,50 +56,17 @@ pub fn hole(elaborator: &mut Elaborator, step: &StepNode) -> Option<Rc<ProofNode
 
 fn get_solver_proof(
     pool: &mut PrimitivePool,
+    solver: &dyn ExternalSolver,
     problem: String,
-    options: &HoleOptions,
-) -> Result<(Vec<ProofCommand>, bool), HoleError> {
-    let mut process = Command::new(options.solver.as_ref())
-        .args(options.arguments.iter().map(AsRef::as_ref))
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(HoleError::FailedSpawnSolver)?;
-
-    process
-        .stdin
-        .take()
-        .expect("failed to open solver stdin")
-        .write_all(problem.as_bytes())
-        .map_err(HoleError::FailedWriteToSolverStdin)?;
-
-    let output = process
-        .wait_with_output()
-        .map_err(HoleError::FailedWaitForSolver)?;
-
-    if !output.status.success() {
-        if let Ok(s) = std::str::from_utf8(&output.stderr) {
-            if s.contains("interrupted by timeout.") {
-                return Err(HoleError::SolverTimeout);
-            }
-        }
-        return Err(HoleError::NonZeroExitCode(output.status.code()));
-    }
-
-    let mut proof = output.stdout.as_slice();
-    let mut first_line = String::new();
-
-    proof
-        .read_line(&mut first_line)
-        .map_err(|_| HoleError::SolverGaveInvalidOutput)?;
-
-    if first_line.trim_end() != "unsat" {
-        return Err(HoleError::OutputNotUnsat);
-    }
-
-    parse_and_check_solver_proof(pool, problem.as_bytes(), proof)
-        .map_err(|e| HoleError::InnerProofError(Box::new(e)))
+    config: &Config,
+) -> Result<(Vec<ProofCommand>, bool), SolverError> {
+    solver::get_checked_proof(
+        solver,
+        &problem,
+        config.solver_limits,
+        config.solver_cache.as_ref(),
+        |proof| parse_and_check_solver_proof(pool, problem.as_bytes(), proof),
+    )
 }
 
 fn parse_and_check_solver_proof(
@@ -147,9 +77,17 @@ fn parse_and_check_solver_proof(
     let config = parser::Config {
         apply_function_defs: false,
         expand_lets: true,
-        allow_int_real_subtyping: true,
+        int_real_subtyping: parser::IntRealSubtyping::Implicit,
         strict: false,
         parse_hole_args: false,
+        shadowed_binders: parser::ShadowedBinders::Allow,
+        limits: SizeLimits::new(),
+        track_locations: false,
+        alpha_canonical_binders: false,
+        fresh_name_scheme: pool.fresh_name_scheme(),
+        check_sorts: false,
+        alethe_version: None,
+        dialect: parser::Dialect::Alethe,
     };
 
     let (problem, proof) = parser::parse_instance_with_pool(problem, proof, config, pool)?;
@@ -160,22 +98,26 @@ fn parse_and_check_solver_proof(
 }
 
 fn increase_subproof_depth(proof: &Rc<ProofNode>, delta: usize, prefix: &str) -> Rc<ProofNode> {
-    mutate(proof, |_, node| {
-        let node = match node.as_ref().clone() {
-            ProofNode::Assume { id, depth, term } => ProofNode::Assume {
-                id: format!("{}.{}", prefix, id),
-                depth: depth + delta,
-                term,
-            },
-            ProofNode::Step(mut s) => {
-                s.id = format!("{}.{}", prefix, s.id);
-                s.depth += delta;
-                ProofNode::Step(s)
-            }
-            ProofNode::Subproof(_) => unreachable!(),
-        };
-        Rc::new(node)
-    })
+    mutate(
+        proof,
+        |_, node| {
+            let node = match node.as_ref().clone() {
+                ProofNode::Assume { id, depth, term } => ProofNode::Assume {
+                    id: format!("{}.{}", prefix, id),
+                    depth: depth + delta,
+                    term,
+                },
+                ProofNode::Step(mut s) => {
+                    s.id = format!("{}.{}", prefix, s.id);
+                    s.depth += delta;
+                    ProofNode::Step(s)
+                }
+                ProofNode::Subproof(_) => unreachable!(),
+            };
+            Rc::new(node)
+        },
+        None,
+    )
 }
 
 fn insert_solver_proof(
@@ -1,70 +1,50 @@
 use super::*;
 use crate::{checker, parser, CarcaraResult};
-use std::{
-    io::{self, BufRead, Write},
-    process::{Command, Stdio},
-};
-use thiserror::Error;
-
-#[derive(Debug, Error)]
-pub enum LiaGenericError {
-    #[error("failed to spawn solver process")]
-    FailedSpawnSolver(io::Error),
-
-    #[error("failed to write to solver stdin")]
-    FailedWriteToSolverStdin(io::Error),
-
-    #[error("error while waiting for solver to exit")]
-    FailedWaitForSolver(io::Error),
-
-    #[error("solver gave invalid output")]
-    SolverGaveInvalidOutput,
-
-    #[error("solver output not unsat")]
-    OutputNotUnsat,
-
-    #[error("solver timed out when solving problem")]
-    SolverTimeout,
-
-    #[error(
-        "solver returned non-zero exit code: {}",
-        if let Some(i) = .0 { format!("{}", i) } else { "none".to_owned() }
-    )]
-    NonZeroExitCode(Option<i32>),
-
-    #[error("error in inner proof: {0}")]
-    InnerProofError(Box<crate::Error>),
-}
 
 fn get_problem_string(
     pool: &mut PrimitivePool,
+    solver: &dyn ExternalSolver,
     prelude: &ProblemPrelude,
     conclusion: &[Rc<Term>],
 ) -> String {
-    use std::fmt::Write;
-
-    let mut problem = String::new();
-    writeln!(&mut problem, "(set-option :produce-proofs true)").unwrap();
-    write!(&mut problem, "{}", prelude).unwrap();
-
     let mut bytes = Vec::new();
     printer::write_lia_smt_instance(pool, prelude, &mut bytes, conclusion, true).unwrap();
-    write!(&mut problem, "{}", String::from_utf8(bytes).unwrap()).unwrap();
+    solver.problem_string(prelude, &String::from_utf8(bytes).unwrap())
+}
 
-    writeln!(&mut problem, "(check-sat)").unwrap();
-    writeln!(&mut problem, "(get-proof)").unwrap();
-    writeln!(&mut problem, "(exit)").unwrap();
+pub fn lia_generic(elaborator: &mut Elaborator, step: &StepNode) -> Option<Rc<ProofNode>> {
+    let solver = elaborator.config.lia_solver.clone().unwrap();
+    elaborate_with_solver(elaborator, step, solver.as_ref())
+}
 
-    problem
+/// Elaborates a `nla_generic` step. Like `lia_generic` steps, these are tautological clauses, but of
+/// nonlinear arithmetic, so they are elaborated in the same way, using the solver in
+/// [`Config::nla_solver`].
+pub fn nla_generic(elaborator: &mut Elaborator, step: &StepNode) -> Option<Rc<ProofNode>> {
+    let solver = elaborator.config.nla_solver.clone().unwrap();
+    elaborate_with_solver(elaborator, step, solver.as_ref())
 }
 
-pub fn lia_generic(elaborator: &mut Elaborator, step: &StepNode) -> Option<Rc<ProofNode>> {
-    let problem = get_problem_string(elaborator.pool, &elaborator.problem.prelude, &step.clause);
-    let options = elaborator.config.lia_options.as_ref().unwrap();
-    let commands = match get_solver_proof(elaborator.pool, problem, options) {
+fn elaborate_with_solver(
+    elaborator: &mut Elaborator,
+    step: &StepNode,
+    solver: &dyn ExternalSolver,
+) -> Option<Rc<ProofNode>> {
+    let problem = get_problem_string(
+        elaborator.pool,
+        solver,
+        &elaborator.problem.prelude,
+        &step.clause,
+    );
+    let commands = match get_solver_proof(elaborator.pool, solver, problem, &elaborator.config) {
         Ok(c) => c,
         Err(e) => {
-            log::warn!("failed to elaborate `lia_generic` step: {}", e);
+            tracing::warn!(
+                "failed to elaborate `{}` step with {}: {}",
+                step.rule,
+                solver.name(),
+                e
+            );
             return None;
         }
     };
@@ -80,50 +60,17 @@ pub fn lia_generic(elaborator: &mut Elaborator, step: &StepNode) -> Option<Rc<Pr
 
 fn get_solver_proof(
     pool: &mut PrimitivePool,
+    solver: &dyn ExternalSolver,
     problem: String,
-    options: &LiaGenericOptions,
-) -> Result<Vec<ProofCommand>, LiaGenericError> {
-    let mut process = Command::new(options.solver.as_ref())
-        .args(options.arguments.iter().map(AsRef::as_ref))
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(LiaGenericError::FailedSpawnSolver)?;
-
-    process
-        .stdin
-        .take()
-        .expect("failed to open solver stdin")
-        .write_all(problem.as_bytes())
-        .map_err(LiaGenericError::FailedWriteToSolverStdin)?;
-
-    let output = process
-        .wait_with_output()
-        .map_err(LiaGenericError::FailedWaitForSolver)?;
-
-    if !output.status.success() {
-        if let Ok(s) = std::str::from_utf8(&output.stderr) {
-            if s.contains("interrupted by timeout.") {
-                return Err(LiaGenericError::SolverTimeout);
-            }
-        }
-        return Err(LiaGenericError::NonZeroExitCode(output.status.code()));
-    }
-
-    let mut proof = output.stdout.as_slice();
-    let mut first_line = String::new();
-
-    proof
-        .read_line(&mut first_line)
-        .map_err(|_| LiaGenericError::SolverGaveInvalidOutput)?;
-
-    if first_line.trim_end() != "unsat" {
-        return Err(LiaGenericError::OutputNotUnsat);
-    }
-
-    parse_and_check_solver_proof(pool, problem.as_bytes(), proof)
-        .map_err(|e| LiaGenericError::InnerProofError(Box::new(e)))
+    config: &Config,
+) -> Result<Vec<ProofCommand>, SolverError> {
+    solver::get_checked_proof(
+        solver,
+        &problem,
+        config.solver_limits,
+        config.solver_cache.as_ref(),
+        |proof| parse_and_check_solver_proof(pool, problem.as_bytes(), proof),
+    )
 }
 
 fn parse_and_check_solver_proof(
@@ -134,9 +81,17 @@ fn parse_and_check_solver_proof(
     let config = parser::Config {
         apply_function_defs: false,
         expand_lets: true,
-        allow_int_real_subtyping: true,
+        int_real_subtyping: parser::IntRealSubtyping::Implicit,
         strict: false,
         parse_hole_args: false,
+        shadowed_binders: parser::ShadowedBinders::Allow,
+        limits: SizeLimits::new(),
+        track_locations: false,
+        alpha_canonical_binders: false,
+        fresh_name_scheme: pool.fresh_name_scheme(),
+        check_sorts: false,
+        alethe_version: None,
+        dialect: parser::Dialect::Alethe,
     };
     let (problem, proof) = parser::parse_instance_with_pool(problem, proof, config, pool)?;
 
@@ -146,22 +101,26 @@ fn parse_and_check_solver_proof(
 }
 
 fn increase_subproof_depth(proof: &Rc<ProofNode>, delta: usize, prefix: &str) -> Rc<ProofNode> {
-    mutate(proof, |_, node| {
-        let node = match node.as_ref().clone() {
-            ProofNode::Assume { id, depth, term } => ProofNode::Assume {
-                id: format!("{}.{}", prefix, id),
-                depth: depth + delta,
-                term,
-            },
-            ProofNode::Step(mut s) => {
-                s.id = format!("{}.{}", prefix, s.id);
-                s.depth += delta;
-                ProofNode::Step(s)
-            }
-            ProofNode::Subproof(_) => unreachable!(),
-        };
-        Rc::new(node)
-    })
+    mutate(
+        proof,
+        |_, node| {
+            let node = match node.as_ref().clone() {
+                ProofNode::Assume { id, depth, term } => ProofNode::Assume {
+                    id: format!("{}.{}", prefix, id),
+                    depth: depth + delta,
+                    term,
+                },
+                ProofNode::Step(mut s) => {
+                    s.id = format!("{}.{}", prefix, s.id);
+                    s.depth += delta;
+                    ProofNode::Step(s)
+                }
+                ProofNode::Subproof(_) => unreachable!(),
+            };
+            Rc::new(node)
+        },
+        None,
+    )
 }
 
 fn insert_solver_proof(
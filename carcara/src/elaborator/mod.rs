@@ -1,18 +1,33 @@
+mod antisymmetry;
+mod coefficients;
+mod flattening;
+#[cfg(feature = "external-solver")]
 mod hole;
+#[cfg(feature = "external-solver")]
 mod lia_generic;
 mod polyeq;
 mod reflexivity;
 mod reordering;
 mod resolution;
+mod rewrite;
+mod skolemization;
+mod solver;
 mod transitivity;
 mod uncrowding;
 
-use crate::{ast::*, CheckerError};
+use crate::{
+    ast::*,
+    limits::{SizeLimitError, SizeLimits},
+    trace_map::TraceRecorder,
+    utils::Instant,
+    CheckerError,
+};
 use indexmap::IndexSet;
 use polyeq::PolyeqElaborator;
 use std::{
     collections::{HashMap, HashSet},
-    time::{Duration, Instant},
+    sync::Arc,
+    time::Duration,
 };
 
 #[derive(Debug, Clone)]
@@ -20,62 +35,131 @@ pub struct Config {
     /// If `Some`, enables the elaboration of `lia_generic` steps using an external solver. When
     /// checking a proof, this means calling the solver to solve the linear integer arithmetic
     /// problem, checking the proof, and discarding it. When elaborating, the proof will instead be
-    /// inserted in the place of the `lia_generic` step. See [`LiaGenericOptions`] for more details.
-    pub lia_options: Option<LiaGenericOptions>,
+    /// inserted in the place of the `lia_generic` step. See [`ExternalSolver`] for more details.
+    ///
+    /// This is ignored if Carcara is built without the `external-solver` feature.
+    pub lia_solver: Option<Arc<dyn ExternalSolver>>,
+
+    /// If `Some`, enables the elaboration of `nla_generic` steps using an external solver, in the
+    /// same way as `lia_solver`. The solver must support nonlinear arithmetic.
+    pub nla_solver: Option<Arc<dyn ExternalSolver>>,
 
     /// Enables an optimization that reorders premises when uncrowding resolution steps, in order to
     /// further minimize the number of `contraction` steps added.
     pub uncrowd_rotation: bool,
 
-    pub hole_options: Option<HoleOptions>,
+    /// If `Some`, enables the elaboration of `all_simplify` and `rare_rewrite` steps using an
+    /// external solver. Like `lia_solver`, this is ignored if Carcara is built without the
+    /// `external-solver` feature.
+    pub hole_solver: Option<Arc<dyn ExternalSolver>>,
+
+    /// The time and memory limits enforced on each call to the external solvers in `lia_solver`
+    /// and `hole_solver`.
+    pub solver_limits: SolverLimits,
+
+    /// If `Some`, the proofs produced by the external solvers are stored in this cache, and reused
+    /// when the same problem is given to a solver again. See [`SolverCache`] for more details.
+    pub solver_cache: Option<SolverCache>,
+
+    /// The maximum number of proof commands and term nodes allowed. These are checked after each
+    /// step of the elaboration pipeline, and an error is returned if any of them is exceeded.
+    pub limits: SizeLimits,
+
+    /// If `true`, the elaborator keeps a [`TraceMap`] that maps each command in the original proof
+    /// to the commands that replaced it in the elaborated proof.
+    pub trace: bool,
+
+    /// The tool that will consume the elaborated proof. See [`ElaborationTarget`] for more
+    /// details.
+    pub target: ElaborationTarget,
 }
 
+pub use crate::trace_map::TraceMap;
+#[cfg(feature = "serde")]
+pub use crate::trace_map::TraceMapError;
+
 #[derive(Debug, Clone, Copy)]
 pub enum ElaborationStep {
+    Skolemization,
     Polyeq,
+    Flattening,
     LiaGeneric,
+    NlaGeneric,
     Local,
     Uncrowd,
     Reordering,
     Hole,
+    IntegerCoefficients,
 }
 
-/// The options that control how `lia_generic` steps are elaborated using an external solver.
-#[derive(Debug, Clone)]
-pub struct LiaGenericOptions {
-    /// The external solver path. The solver should be a binary that can read SMT-LIB from stdin and
-    /// output an Alethe proof to stdout.
-    pub solver: Box<str>,
+/// The tool that will consume an elaborated proof. Some tools that replay Alethe proofs only
+/// support a subset of the format, and each target determines which constraints the elaborated
+/// proof must satisfy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ElaborationTarget {
+    /// No constraints besides the ones of the Alethe format itself.
+    #[default]
+    Alethe,
+
+    /// The reconstruction of Isabelle's `smt` method. Proofs elaborated for this target contain no
+    /// implicit reordering of clauses, all `resolution` steps have explicit pivots, and all
+    /// `la_generic` steps have integer coefficients.
+    Isabelle,
+}
 
-    /// The arguments to pass to the solver.
-    pub arguments: Vec<Box<str>>,
+impl ElaborationTarget {
+    /// Returns the elaboration pipeline that produces proofs suitable for this target.
+    pub fn pipeline(self) -> Vec<ElaborationStep> {
+        use ElaborationStep::*;
+        let mut pipeline = vec![
+            Skolemization,
+            Polyeq,
+            Flattening,
+            LiaGeneric,
+            NlaGeneric,
+            Local,
+            Uncrowd,
+            Reordering,
+        ];
+        if self == ElaborationTarget::Isabelle {
+            pipeline.push(IntegerCoefficients);
+        }
+        pipeline
+    }
 }
 
-/// The options that control how `hole` steps are elaborated using an external solver.
-#[derive(Debug, Clone)]
-pub struct HoleOptions {
-    /// The external solver path. The solver should be a binary that can read SMT-LIB from stdin and
-    /// output an Alethe proof to stdout.
-    pub solver: Box<str>,
+pub use coefficients::is_integer_coefficient;
 
-    /// The arguments to pass to the solver.
-    pub arguments: Vec<Box<str>>,
-}
+pub use solver::{Cvc5, ExternalSolver, SolverCache, SolverError, SolverLimits, VeriT, Z3};
 
 pub struct Elaborator<'e> {
     pool: &'e mut PrimitivePool,
     problem: &'e Problem,
     config: Config,
+    trace: Option<TraceMap>,
 }
 
 impl<'e> Elaborator<'e> {
     pub fn new(pool: &'e mut PrimitivePool, problem: &'e Problem, config: Config) -> Self {
-        Self { pool, problem, config }
+        Self { pool, problem, config, trace: None }
     }
 
-    pub fn elaborate_with_default_pipeline(&mut self, root: &Rc<ProofNode>) -> Rc<ProofNode> {
-        use ElaborationStep::*;
-        let pipeline = vec![Polyeq, LiaGeneric, Local, Uncrowd, Reordering];
+    /// Returns the trace map of the last elaboration, if tracing is enabled in the config.
+    pub fn take_trace(&mut self) -> Option<TraceMap> {
+        self.trace.take()
+    }
+
+    /// Returns the renamings of bound variables done during the last elaboration, as pairs of the
+    /// original and the new variable name.
+    pub fn take_rename_log(&mut self) -> Vec<(String, String)> {
+        self.pool.take_rename_log()
+    }
+
+    pub fn elaborate_with_default_pipeline(
+        &mut self,
+        root: &Rc<ProofNode>,
+    ) -> Result<Rc<ProofNode>, SizeLimitError> {
+        let pipeline = self.config.target.pipeline();
         self.elaborate(root, pipeline)
     }
 
@@ -83,63 +167,139 @@ impl<'e> Elaborator<'e> {
         &mut self,
         root: &Rc<ProofNode>,
         pipeline: Vec<ElaborationStep>,
-    ) -> Rc<ProofNode> {
-        self.elaborate_with_stats(root, pipeline).0
+    ) -> Result<Rc<ProofNode>, SizeLimitError> {
+        Ok(self.elaborate_with_stats(root, pipeline)?.0)
     }
 
     pub fn elaborate_with_stats(
         &mut self,
         root: &Rc<ProofNode>,
         pipeline: Vec<ElaborationStep>,
-    ) -> (Rc<ProofNode>, Vec<Duration>) {
+    ) -> Result<(Rc<ProofNode>, Vec<Duration>), SizeLimitError> {
         let mut durations = Vec::new();
         let mut current = root.clone();
+        self.trace = self.config.trace.then(|| TraceMap::identity(root));
+        self.pool.start_rename_log();
         for step in pipeline {
+            let _span = tracing::debug_span!("pass", ?step).entered();
             let time = Instant::now();
+            let mut recorder = self.config.trace.then(TraceRecorder::new);
             current = match step {
-                ElaborationStep::Polyeq => self.elaborate_polyeq(&current),
-                ElaborationStep::LiaGeneric if self.config.lia_options.is_some() => {
-                    mutate(&current, |_, node| match node.as_ref() {
-                        ProofNode::Step(s) if s.rule == "lia_generic" => {
+                ElaborationStep::Skolemization => {
+                    skolemization::insert_choice_terms(self.pool, &current, recorder.as_mut())
+                }
+                ElaborationStep::Polyeq => self.elaborate_polyeq(&current, recorder.as_mut()),
+                ElaborationStep::Flattening => mutate(
+                    &current,
+                    |_, node| match node.as_ref() {
+                        ProofNode::Step(s)
+                            if matches!(s.rule.as_str(), "or" | "and" | "not_or" | "not_and") =>
+                        {
+                            flattening::flattening(self.pool, s)
+                        }
+                        _ => node.clone(),
+                    },
+                    recorder.as_mut(),
+                ),
+                ElaborationStep::LiaGeneric => mutate(
+                    &current,
+                    |_, node| match node.as_ref() {
+                        // Steps that include Farkas coefficients are checked just like `la_generic`
+                        // steps, so there is no need to call the solver
+                        ProofNode::Step(s) if s.rule == "lia_generic" && !s.args.is_empty() => {
+                            Rc::new(ProofNode::Step(StepNode {
+                                rule: "la_generic".to_owned(),
+                                ..s.clone()
+                            }))
+                        }
+                        #[cfg(feature = "external-solver")]
+                        ProofNode::Step(s)
+                            if s.rule == "lia_generic" && self.config.lia_solver.is_some() =>
+                        {
                             lia_generic::lia_generic(self, s).unwrap_or_else(|| node.clone())
                         }
                         _ => node.clone(),
-                    })
-                }
-                ElaborationStep::LiaGeneric => current.clone(),
-                ElaborationStep::Local => self.elaborate_local(&current),
-                ElaborationStep::Uncrowd => mutate(&current, |_, node| match node.as_ref() {
-                    ProofNode::Step(s)
-                        if (s.rule == "resolution" || s.rule == "th_resolution")
-                            && !s.args.is_empty() =>
-                    {
-                        uncrowding::uncrowd_resolution(self.pool, s, self.config.uncrowd_rotation)
-                    }
-                    _ => node.clone(),
-                }),
-                ElaborationStep::Reordering => reordering::remove_reorderings(&current),
-                ElaborationStep::Hole => {
-                    if self.config.hole_options.is_none() {
-                        current.clone()
-                    } else {
-                        mutate(&current, |_, node| match node.as_ref() {
-                            ProofNode::Step(s)
-                                if (s.rule == "all_simplify" || s.rule == "rare_rewrite") =>
-                            {
-                                hole::hole(self, s).unwrap_or_else(|| node.clone())
-                            }
-                            _ => node.clone(),
-                        })
-                    }
+                    },
+                    recorder.as_mut(),
+                ),
+                #[cfg(feature = "external-solver")]
+                ElaborationStep::NlaGeneric if self.config.nla_solver.is_some() => mutate(
+                    &current,
+                    |_, node| match node.as_ref() {
+                        ProofNode::Step(s) if s.rule == "nla_generic" => {
+                            lia_generic::nla_generic(self, s).unwrap_or_else(|| node.clone())
+                        }
+                        _ => node.clone(),
+                    },
+                    recorder.as_mut(),
+                ),
+                ElaborationStep::NlaGeneric => current.clone(),
+                ElaborationStep::Local => self.elaborate_local(&current, recorder.as_mut()),
+                ElaborationStep::Uncrowd => mutate(
+                    &current,
+                    |_, node| match node.as_ref() {
+                        ProofNode::Step(s)
+                            if (s.rule == "resolution" || s.rule == "th_resolution")
+                                && !s.args.is_empty() =>
+                        {
+                            uncrowding::uncrowd_resolution(
+                                self.pool,
+                                s,
+                                self.config.uncrowd_rotation,
+                            )
+                        }
+                        _ => node.clone(),
+                    },
+                    recorder.as_mut(),
+                ),
+                ElaborationStep::Reordering => {
+                    reordering::remove_reorderings(&current, recorder.as_mut())
                 }
+                #[cfg(feature = "external-solver")]
+                ElaborationStep::Hole if self.config.hole_solver.is_some() => mutate(
+                    &current,
+                    |_, node| match node.as_ref() {
+                        ProofNode::Step(s)
+                            if (s.rule == "all_simplify" || s.rule == "rare_rewrite") =>
+                        {
+                            hole::hole(self, s).unwrap_or_else(|| node.clone())
+                        }
+                        _ => node.clone(),
+                    },
+                    recorder.as_mut(),
+                ),
+                ElaborationStep::Hole => current.clone(),
+                ElaborationStep::IntegerCoefficients => mutate(
+                    &current,
+                    |_, node| match node.as_ref() {
+                        ProofNode::Step(s) if s.rule == "la_generic" => {
+                            coefficients::integer_coefficients(self.pool, s)
+                                .unwrap_or_else(|| node.clone())
+                        }
+                        _ => node.clone(),
+                    },
+                    recorder.as_mut(),
+                ),
             };
             durations.push(time.elapsed());
+            tracing::debug!(elapsed = ?time.elapsed(), "pass finished");
+            if let (Some(trace), Some(recorder)) = (&mut self.trace, recorder) {
+                // Steps that are skipped leave the recorder empty, and don't change the proof
+                if let Some(stage) = recorder.finish() {
+                    trace.compose(&stage);
+                }
+            }
+            self.config.limits.check_proof(self.pool, &current)?;
         }
-        (current, durations)
+        Ok((current, durations))
     }
 
-    fn elaborate_polyeq(&mut self, root: &Rc<ProofNode>) -> Rc<ProofNode> {
-        mutate(root, |context, node| {
+    fn elaborate_polyeq(
+        &mut self,
+        root: &Rc<ProofNode>,
+        recorder: Option<&mut TraceRecorder>,
+    ) -> Rc<ProofNode> {
+        let mutate_func = |context: &mut ContextStack, node: &Rc<ProofNode>| {
             match node.as_ref() {
                 ProofNode::Assume { id, depth, term }
                     if context.is_empty() && !self.problem.premises.contains(term) =>
@@ -151,20 +311,27 @@ impl<'e> Elaborator<'e> {
                 }
                 _ => node.clone(),
             }
-        })
+        };
+        mutate(root, mutate_func, recorder)
     }
 
-    fn elaborate_local(&mut self, root: &Rc<ProofNode>) -> Rc<ProofNode> {
+    fn elaborate_local(
+        &mut self,
+        root: &Rc<ProofNode>,
+        recorder: Option<&mut TraceRecorder>,
+    ) -> Rc<ProofNode> {
         fn get_elaboration_function(rule: &str) -> Option<ElaborationFunc> {
             Some(match rule {
                 "eq_transitive" => transitivity::eq_transitive,
                 "trans" => transitivity::trans,
                 "resolution" | "th_resolution" => resolution::resolution,
+                "rewrite" => rewrite::rewrite,
+                "la_antisymmetry" => antisymmetry::la_antisymmetry,
                 _ => return None,
             })
         }
 
-        mutate(root, |context, node| {
+        let mutate_func = |context: &mut ContextStack, node: &Rc<ProofNode>| {
             match node.as_ref() {
                 ProofNode::Step(s) => {
                     if let Some(func) = get_elaboration_function(&s.rule) {
@@ -175,7 +342,8 @@ impl<'e> Elaborator<'e> {
                 ProofNode::Assume { .. } => (),
             }
             node.clone()
-        })
+        };
+        mutate(root, mutate_func, recorder)
     }
 
     fn elaborate_assume(&mut self, id: &str, depth: usize, term: &Rc<Term>) -> Rc<ProofNode> {
@@ -229,6 +397,26 @@ impl<'e> Elaborator<'e> {
     }
 }
 
+/// Elaborates an already checked proof using the default pipeline for the configured target, and
+/// returns the elaborated proof. The proof is not checked again, neither before nor after
+/// elaboration, so this should only be called with proofs that are known to be valid.
+///
+/// The problem is needed because some elaboration steps depend on the problem premises.
+pub fn elaborate(
+    pool: &mut PrimitivePool,
+    problem: &Problem,
+    proof: Proof,
+    config: Config,
+) -> Result<Proof, SizeLimitError> {
+    let node = ProofNode::from_commands(proof.commands);
+    let elaborated =
+        Elaborator::new(pool, problem, config).elaborate_with_default_pipeline(&node)?;
+    Ok(Proof {
+        commands: elaborated.into_commands(),
+        ..proof
+    })
+}
+
 pub fn add_refl_step(
     pool: &mut dyn TermPool,
     a: Rc<Term>,
@@ -251,9 +439,30 @@ pub fn add_refl_step(
 type ElaborationFunc =
     fn(&mut PrimitivePool, &mut ContextStack, &StepNode) -> Result<Rc<ProofNode>, CheckerError>;
 
-fn mutate<F>(root: &Rc<ProofNode>, mut mutate_func: F) -> Rc<ProofNode>
+/// Rebuilds the proof bottom-up, calling `mutate_func` on each command after its premises were
+/// rebuilt. If a `recorder` is given, the replacement of each command is recorded in it.
+fn mutate<F>(
+    root: &Rc<ProofNode>,
+    mutate_func: F,
+    recorder: Option<&mut TraceRecorder>,
+) -> Rc<ProofNode>
+where
+    F: FnMut(&mut ContextStack, &Rc<ProofNode>) -> Rc<ProofNode>,
+{
+    mutate_with_args(root, mutate_func, <[AnchorArg]>::to_vec, recorder)
+}
+
+/// Like [`mutate`], but also calls `mutate_args` on the arguments of each subproof, to build the
+/// arguments of the rebuilt subproof.
+fn mutate_with_args<F, G>(
+    root: &Rc<ProofNode>,
+    mut mutate_func: F,
+    mut mutate_args: G,
+    mut recorder: Option<&mut TraceRecorder>,
+) -> Rc<ProofNode>
 where
     F: FnMut(&mut ContextStack, &Rc<ProofNode>) -> Rc<ProofNode>,
+    G: FnMut(&[AnchorArg]) -> Vec<AnchorArg>,
 {
     let mut cache: HashMap<&Rc<ProofNode>, Rc<ProofNode>> = HashMap::new();
     let mut did_outbound: HashSet<&Rc<ProofNode>> = HashSet::new();
@@ -322,7 +531,7 @@ where
                     outbound_premises_stack.pop().unwrap().into_iter().collect();
                 Rc::new(ProofNode::Subproof(SubproofNode {
                     last_step: cache[&s.last_step].clone(),
-                    args: s.args.clone(),
+                    args: mutate_args(&s.args),
                     outbound_premises,
                 }))
             }
@@ -331,6 +540,9 @@ where
             .last_mut()
             .unwrap()
             .extend(mutated.get_outbound_premises());
+        if let Some(recorder) = recorder.as_deref_mut() {
+            recorder.record(node, &mutated);
+        }
         cache.insert(node, mutated);
     }
     assert!(outbound_premises_stack.len() == 1 && outbound_premises_stack[0].is_empty());
@@ -370,3 +582,153 @@ impl IdHelper {
         self.stack.pop();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{checker, parser};
+
+    #[test]
+    fn test_elaborate() {
+        let problem = "
+            (declare-fun a () Bool)
+            (declare-fun b () Bool)
+            (assert (or a b))
+            (assert (and (not a) (not b)))
+        ";
+        let proof = "
+            (assume h1 (or a b))
+            (assume h2 (and (not a) (not b)))
+            (step t1 (cl a b) :rule or :premises (h1))
+            (step t2 (cl (not a)) :rule and :premises (h2) :args (0))
+            (step t3 (cl (not b)) :rule and :premises (h2) :args (1))
+            (step t4 (cl) :rule th_resolution :premises (t1 t2 t3))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let config = Config {
+            lia_solver: None,
+            nla_solver: None,
+            uncrowd_rotation: false,
+            hole_solver: None,
+            solver_limits: Default::default(),
+            solver_cache: None,
+            limits: Default::default(),
+            trace: false,
+            target: Default::default(),
+        };
+        let elaborated = elaborate(&mut pool, &problem, proof, config).unwrap();
+        assert!(elaborated
+            .iter()
+            .all(|c| !matches!(c, ProofCommand::Step(s) if s.rule == "th_resolution")));
+
+        let checker_config = checker::Config::new().elaborated(true);
+        checker::ProofChecker::new(&mut pool, checker_config)
+            .check(&problem, &elaborated)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_lia_generic_with_coefficients() {
+        let problem = "
+            (declare-fun m () Int)
+            (assert (> m 0))
+            (assert (<= m 0))
+        ";
+        let proof = "
+            (assume h1 (> m 0))
+            (assume h2 (<= m 0))
+            (step t3 (cl (not (> m 0)) (not (<= m 0))) :rule lia_generic :args (1 1))
+            (step t4 (cl) :rule resolution :premises (t3 h1 h2))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let is_holey = checker::ProofChecker::new(&mut pool, checker::Config::new())
+            .check(&problem, &proof)
+            .unwrap();
+        assert!(!is_holey);
+
+        let config = Config {
+            lia_solver: None,
+            nla_solver: None,
+            uncrowd_rotation: false,
+            hole_solver: None,
+            solver_limits: Default::default(),
+            solver_cache: None,
+            limits: Default::default(),
+            trace: false,
+            target: Default::default(),
+        };
+        let elaborated = elaborate(&mut pool, &problem, proof, config).unwrap();
+        assert!(elaborated
+            .iter()
+            .any(|c| matches!(c, ProofCommand::Step(s) if s.rule == "la_generic")));
+    }
+
+    #[test]
+    fn test_trace_map() {
+        let problem: &[u8] = b"
+            (declare-const a Int)
+            (declare-const b Int)
+            (declare-const c Int)
+            (assert (= a b))
+            (assert (= b c))
+            (assert (not (= a c)))
+        ";
+        let proof: &[u8] = b"
+            (assume h1 (= a b))
+            (assume h2 (= b c))
+            (assume h3 (not (= a c)))
+            (step t4 (cl (not (= a b)) (not (= b c)) (= a c)) :rule eq_transitive)
+            (step t5 (cl (= a c) (not (= b c)) (not (= a b))) :rule reordering :premises (t4))
+            (step t6 (cl) :rule resolution :premises (t5 h1 h2 h3))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem, proof, parser::Config::new()).unwrap();
+        let node = ProofNode::from_commands(proof.commands);
+        let config = Config {
+            lia_solver: None,
+            nla_solver: None,
+            uncrowd_rotation: false,
+            hole_solver: None,
+            solver_limits: Default::default(),
+            solver_cache: None,
+            limits: Default::default(),
+            trace: true,
+            target: Default::default(),
+        };
+        let mut elaborator = Elaborator::new(&mut pool, &problem, config);
+        let elaborated = elaborator
+            .elaborate(
+                &node,
+                vec![ElaborationStep::Local, ElaborationStep::Reordering],
+            )
+            .unwrap();
+        let trace = elaborator.take_trace().unwrap();
+
+        // Every command in the elaborated proof must come from some original command
+        let mut new_ids = Vec::new();
+        elaborated.traverse(|n| {
+            if !n.is_subproof() {
+                new_ids.push(n.id().to_owned());
+            }
+        });
+        for id in &new_ids {
+            assert!(!trace.predecessors(id).is_empty(), "{}", id);
+        }
+
+        assert_eq!(
+            Some(&IndexSet::from(["h1".to_owned()])),
+            trace.successors("h1")
+        );
+        assert!(trace.successors("t6").unwrap().contains("t6"));
+        assert_eq!(
+            Some(&IndexSet::from(["t4".to_owned()])),
+            trace.successors("t5")
+        );
+        assert!(trace.successors("t4").unwrap().contains("t4"));
+        assert!(trace.successors("t99").is_none());
+    }
+}
@@ -82,7 +82,7 @@ impl<'a> PolyeqElaborator<'a> {
             // use these terms.
             (Term::Binder(b @ (Binder::Choice | Binder::Lambda), _, _), _)
             | (_, Term::Binder(b @ (Binder::Choice | Binder::Lambda), _, _)) => {
-                log::error!("Trying to elaborate polyequality between `{b}` terms");
+                tracing::error!("Trying to elaborate polyequality between `{b}` terms");
                 panic!()
             }
             (Term::Binder(a_q, a_bindings, a_inner), Term::Binder(b_q, b_bindings, b_inner)) => {
@@ -1,36 +1,43 @@
 use super::*;
 use crate::utils::DedupIterator;
 
-pub fn remove_reorderings(root: &Rc<ProofNode>) -> Rc<ProofNode> {
+pub fn remove_reorderings(
+    root: &Rc<ProofNode>,
+    recorder: Option<&mut TraceRecorder>,
+) -> Rc<ProofNode> {
     let mut modified = HashSet::new();
 
-    mutate(root, |_, node| {
-        let Some(step) = node.as_step() else {
-            return node.clone();
-        };
-
-        if step.rule == "reordering" {
-            // Since the premises are changed before the mutation function is called, we have to
-            // insert the new node in the modified set
-            let new = Rc::new(step.premises[0].as_ref().clone());
-            modified.insert(new.clone());
-            return new;
-        }
-
-        // If the rule is order-sensitive, and any premise was modified, we recompute the conclusion
-        if let Some(recompute) = get_recomputation_func(&step.rule) {
-            if step.premises.iter().any(|p| modified.contains(p)) {
-                let new = Rc::new(ProofNode::Step(StepNode {
-                    clause: recompute(step),
-                    ..step.clone()
-                }));
+    mutate(
+        root,
+        |_, node| {
+            let Some(step) = node.as_step() else {
+                return node.clone();
+            };
+
+            if step.rule == "reordering" {
+                // Since the premises are changed before the mutation function is called, we have to
+                // insert the new node in the modified set
+                let new = Rc::new(step.premises[0].as_ref().clone());
                 modified.insert(new.clone());
                 return new;
             }
-        }
 
-        node.clone()
-    })
+            // If the rule is order-sensitive, and any premise was modified, we recompute the conclusion
+            if let Some(recompute) = get_recomputation_func(&step.rule) {
+                if step.premises.iter().any(|p| modified.contains(p)) {
+                    let new = Rc::new(ProofNode::Step(StepNode {
+                        clause: recompute(step),
+                        ..step.clone()
+                    }));
+                    modified.insert(new.clone());
+                    return new;
+                }
+            }
+
+            node.clone()
+        },
+        recorder,
+    )
 }
 
 type RecomputationFunc = fn(&StepNode) -> Vec<Rc<Term>>;
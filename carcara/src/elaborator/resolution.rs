@@ -1,5 +1,10 @@
 use super::*;
-use crate::{ast::*, checker::error::CheckerError, resolution::*, utils::DedupIterator};
+use crate::{
+    ast::*,
+    checker::{self, error::CheckerError},
+    resolution::*,
+    utils::DedupIterator,
+};
 
 pub fn resolution(
     pool: &mut PrimitivePool,
@@ -47,6 +52,25 @@ pub fn resolution(
         }
     }
 
+    // If the solver already gave the pivots as arguments, we use them instead of searching for the
+    // pivots again. The search may find a different set of pivots, which may not be what the solver
+    // intended. The pivots must be valid, since otherwise the checker would have rejected the step
+    if !step.args.is_empty() {
+        let premise_clauses: Vec<_> = step.premises.iter().map(|p| p.clause()).collect();
+        checker::check_step(
+            pool,
+            &checker::Config::new().elaborated(true),
+            &premise_clauses,
+            "resolution",
+            &step.args,
+            &step.clause,
+        )?;
+        return Ok(Rc::new(ProofNode::Step(StepNode {
+            rule: "resolution".to_owned(),
+            ..step.clone()
+        })));
+    }
+
     let mut premises: Vec<_> = step.premises.iter().dedup().cloned().collect();
     let premise_clauses: Vec<_> = premises.iter().map(|p| p.clause()).collect();
 
@@ -140,3 +164,52 @@ pub fn resolution(
         Ok(Rc::new(ProofNode::Step(resolution_step)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn elaborate_last_step(problem: &str, proof: &str) -> Result<Rc<ProofNode>, CheckerError> {
+        let (_, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let root = ProofNode::from_commands(proof.commands);
+        let ProofNode::Step(step) = root.as_ref() else {
+            panic!("expected a step");
+        };
+        resolution(&mut pool, &mut ContextStack::new(), step)
+    }
+
+    #[test]
+    fn test_hinted_pivots() {
+        let problem = "
+            (declare-const p Bool)
+            (declare-const q Bool)
+            (declare-const r Bool)
+        ";
+        let proof = "
+            (step t1 (cl p q) :rule hole)
+            (step t2 (cl (not p) r) :rule hole)
+            (step t3 (cl (not q)) :rule hole)
+            (step t4 (cl r) :rule th_resolution :premises (t1 t2 t3) :args (p true q true))
+        ";
+        let elaborated = elaborate_last_step(problem, proof).unwrap();
+        let ProofNode::Step(step) = elaborated.as_ref() else {
+            panic!("expected a step");
+        };
+        assert_eq!(step.rule, "resolution");
+        assert_eq!(step.premises.len(), 3);
+        let args: Vec<_> = step.args.iter().map(ToString::to_string).collect();
+        assert_eq!(args, ["p", "true", "q", "true"]);
+
+        // The pivots are not searched for if invalid hints are given
+        let proof = "
+            (step t1 (cl p q) :rule hole)
+            (step t2 (cl (not p) r) :rule hole)
+            (step t3 (cl (not q)) :rule hole)
+            (step t4 (cl r) :rule resolution :premises (t1 t2 t3) :args (q true p true))
+        ";
+        assert!(elaborate_last_step(problem, proof).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use super::*;
+
+/// Returns the child of a term at the given index, if the term is an application or an operation.
+fn get_child(term: &Rc<Term>, i: usize) -> Result<&Rc<Term>, CheckerError> {
+    let args = match term.as_ref() {
+        Term::App(_, args) | Term::Op(_, args) | Term::ParamOp { args, .. } => args.as_slice(),
+        _ => &[],
+    };
+    args.get(i)
+        .ok_or_else(|| CheckerError::NoIthChildInTerm(i, term.clone()))
+}
+
+/// Elaborates a `rewrite` step into a chain of `cong` steps, one for each index in the position
+/// given as argument. The innermost `cong` step uses the premise of the `rewrite` step directly.
+pub fn rewrite(
+    pool: &mut PrimitivePool,
+    _: &mut ContextStack,
+    step: &StepNode,
+) -> Result<Rc<ProofNode>, CheckerError> {
+    assert_eq!(step.clause.len(), 1);
+    assert_eq!(step.premises.len(), 1);
+
+    let (t, u) = match_term_err!((= t u) = &step.clause[0])?;
+    let path = step
+        .args
+        .iter()
+        .map(Rc::as_usize_err)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // If the position is empty, the rewrite is justified by the premise directly, possibly after
+    // flipping it
+    let premise = &step.premises[0];
+    if path.is_empty() {
+        let node = if premise.clause() == step.clause.as_slice() {
+            premise.clone()
+        } else {
+            transitivity::add_symm_step(pool, premise, step.id.clone())
+        };
+        return Ok(node);
+    }
+
+    let mut subterms = vec![(t.clone(), u.clone())];
+    for &i in &path {
+        let (t, u) = subterms.last().unwrap();
+        let next = (get_child(t, i)?.clone(), get_child(u, i)?.clone());
+        subterms.push(next);
+    }
+
+    // The subterms at the end of the path are justified by the premise, so we build the `cong`
+    // steps from the innermost position to the outermost one. The outermost step concludes the
+    // original clause, so it keeps the original id
+    let mut ids = IdHelper::new(&step.id);
+    let mut current = premise.clone();
+    for (i, (t, u)) in subterms.into_iter().enumerate().take(path.len()).rev() {
+        let id = if i == 0 {
+            step.id.clone()
+        } else {
+            ids.next_id()
+        };
+        current = Rc::new(ProofNode::Step(StepNode {
+            id,
+            depth: step.depth,
+            clause: vec![build_term!(pool, (= {t} {u}))],
+            rule: "cong".to_owned(),
+            premises: vec![current],
+            ..Default::default()
+        }));
+    }
+    Ok(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast, checker, elaborator, parser};
+
+    #[test]
+    fn test_elaborate_rewrite() {
+        let problem: &[u8] = b"
+            (declare-const a Int)
+            (declare-const b Int)
+            (declare-const c Int)
+            (declare-const p Bool)
+            (assert (= b a))
+        ";
+        let proof: &[u8] = b"
+            (assume h1 (= b a))
+            (step t2 (cl (= (and p (< (+ c a) 0)) (and p (< (+ c b) 0))))
+                :rule rewrite :premises (h1) :args (1 0 1))
+            (step t3 (cl (= a b)) :rule rewrite :premises (h1))
+            (step t4 (cl) :rule hole :premises (t2 t3))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem, proof, parser::Config::new()).unwrap();
+        let node = ast::ProofNode::from_commands(proof.commands);
+        let elaborated = elaborator::Elaborator::new(
+            &mut pool,
+            &problem,
+            elaborator::Config {
+                lia_solver: None,
+                nla_solver: None,
+                uncrowd_rotation: false,
+                hole_solver: None,
+                solver_limits: Default::default(),
+                solver_cache: None,
+                limits: Default::default(),
+                trace: false,
+                target: Default::default(),
+            },
+        )
+        .elaborate(&node, vec![elaborator::ElaborationStep::Local])
+        .unwrap();
+        let elaborated = ast::Proof {
+            constant_definitions: Vec::new(),
+            commands: elaborated.into_commands(),
+        };
+
+        assert!(elaborated.iter().all(|c| match c {
+            ast::ProofCommand::Step(s) => s.rule != "rewrite",
+            _ => true,
+        }));
+        let config = checker::Config::new().elaborated(true);
+        checker::ProofChecker::new(&mut pool, config)
+            .check(&problem, &elaborated)
+            .unwrap();
+    }
+}
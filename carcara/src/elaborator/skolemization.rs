@@ -0,0 +1,202 @@
+use super::*;
+use indexmap::IndexMap;
+use std::cell::RefCell;
+
+/// Replaces the Skolem constants used in `sko_ex` and `sko_forall` steps by the `choice` terms they
+/// stand for. Some solvers introduce a fresh constant in the subproof context, like `(:= (x Int)
+/// sk)`, instead of the `choice` term required by the Alethe format. In that case, every occurrence
+/// of the constant in the proof is replaced by the expected `choice` term, which is built from the
+/// quantified formula in the same way as the checker does.
+pub fn insert_choice_terms(
+    pool: &mut PrimitivePool,
+    root: &Rc<ProofNode>,
+    recorder: Option<&mut TraceRecorder>,
+) -> Rc<ProofNode> {
+    let skolems = collect_skolems(pool, root);
+    if skolems.is_empty() {
+        return root.clone();
+    }
+
+    // The Skolem constants are variables of the same sort as their `choice` terms, so this can't
+    // fail
+    let substitution = Substitution::new(pool, skolems).unwrap();
+
+    // Both closures need to access the pool and the substitution
+    let state = RefCell::new((pool, substitution));
+    let apply = |term: &Rc<Term>| {
+        let (pool, substitution) = &mut *state.borrow_mut();
+        substitution.apply(*pool, term)
+    };
+
+    mutate_with_args(
+        root,
+        |_, node| match node.as_ref() {
+            ProofNode::Assume { id, depth, term } => Rc::new(ProofNode::Assume {
+                id: id.clone(),
+                depth: *depth,
+                term: apply(term),
+            }),
+            ProofNode::Step(s) => {
+                let clause: Vec<_> = s.clause.iter().map(apply).collect();
+                let args: Vec<_> = s.args.iter().map(apply).collect();
+                if clause == s.clause && args == s.args {
+                    return node.clone();
+                }
+                Rc::new(ProofNode::Step(StepNode { clause, args, ..s.clone() }))
+            }
+            ProofNode::Subproof(_) => node.clone(),
+        },
+        |args| {
+            args.iter()
+                .map(|arg| match arg {
+                    AnchorArg::Assign(var, value) => AnchorArg::Assign(var.clone(), apply(value)),
+                    AnchorArg::Variable(_) => arg.clone(),
+                })
+                .collect()
+        },
+        recorder,
+    )
+}
+
+/// Finds the Skolem constants introduced in the contexts of `sko_ex` and `sko_forall` subproofs,
+/// and returns a map from each of them to the `choice` term it should be replaced by.
+fn collect_skolems(pool: &mut PrimitivePool, root: &Rc<ProofNode>) -> IndexMap<Rc<Term>, Rc<Term>> {
+    let mut skolems: IndexMap<Rc<Term>, Rc<Term>> = IndexMap::new();
+    root.traverse(|node| {
+        let ProofNode::Subproof(subproof) = node.as_ref() else {
+            return;
+        };
+        let ProofNode::Step(last_step) = subproof.last_step.as_ref() else {
+            return;
+        };
+        let rule_type = match last_step.rule.as_str() {
+            "sko_ex" => Binder::Exists,
+            "sko_forall" => Binder::Forall,
+            _ => return,
+        };
+        let Some((left, _)) = last_step
+            .clause
+            .first()
+            .and_then(|c| match_term!((= l r) = c))
+        else {
+            return;
+        };
+        let Some((quant, bindings, phi)) = left.as_quant() else {
+            return;
+        };
+        if quant != rule_type {
+            return;
+        }
+
+        // The formula may contain Skolem constants from earlier subproofs, which must also be
+        // replaced in the `choice` terms
+        let mut current_phi = if skolems.is_empty() {
+            phi.clone()
+        } else {
+            Substitution::new(pool, skolems.clone())
+                .unwrap()
+                .apply(pool, phi)
+        };
+
+        for (i, x) in bindings.iter().enumerate() {
+            let Some(value) = subproof
+                .args
+                .iter()
+                .filter_map(AnchorArg::as_assign)
+                .find_map(|(name, value)| (*name == x.0).then(|| value.clone()))
+            else {
+                return;
+            };
+            let x_term = pool.add(Term::from(x.clone()));
+
+            let choice = if matches!(value.as_ref(), Term::Binder(Binder::Choice, _, _)) {
+                value
+            } else if value.as_var().is_some_and(|name| name != x.0)
+                && pool.sort(&value) == pool.sort(&x_term)
+            {
+                let mut inner = current_phi.clone();
+                if i < bindings.len() - 1 {
+                    inner = pool.add(Term::Binder(
+                        rule_type,
+                        BindingList(bindings.0[i + 1..].to_vec()),
+                        inner,
+                    ));
+                }
+                if rule_type == Binder::Forall {
+                    inner = build_term!(pool, (not { inner }));
+                }
+                let binding_list = BindingList(vec![x.clone()]);
+                let choice = pool.add(Term::Binder(Binder::Choice, binding_list, inner));
+                skolems.insert(value, choice.clone());
+                choice
+            } else {
+                return;
+            };
+
+            let mut s = Substitution::single(pool, x_term, choice).unwrap();
+            current_phi = s.apply(pool, &current_phi);
+        }
+    });
+    skolems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{checker, parser};
+
+    #[test]
+    fn test_insert_choice_terms() {
+        let problem = "
+            (declare-fun p (Int) Bool)
+            (declare-fun q (Int Int) Bool)
+            (declare-const sk1 Int)
+            (declare-const sk2 Int)
+            (declare-const sk3 Int)
+        ";
+        let proof = "
+            (anchor :step t1 :args ((:= (x Int) sk1)))
+            (step t1.t1 (cl (= x sk1)) :rule refl)
+            (step t1.t2 (cl (= (p x) (p sk1))) :rule cong :premises (t1.t1))
+            (step t1 (cl (= (exists ((x Int)) (p x)) (p sk1))) :rule sko_ex)
+            (anchor :step t2 :args ((:= (x Int) sk2) (:= (y Int) sk3)))
+            (step t2.t1 (cl (= x sk2)) :rule refl)
+            (step t2.t2 (cl (= y sk3)) :rule refl)
+            (step t2.t3 (cl (= (q x y) (q sk2 sk3))) :rule cong :premises (t2.t1 t2.t2))
+            (step t2 (cl (= (forall ((x Int) (y Int)) (q x y)) (q sk2 sk3))) :rule sko_forall)
+            (step t3 (cl) :rule hole :premises (t1 t2))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let result =
+            checker::ProofChecker::new(&mut pool, checker::Config::new()).check(&problem, &proof);
+        assert!(matches!(result, Err(crate::Error::Checker { ref step, .. }) if step == "t1"));
+
+        let node = ProofNode::from_commands(proof.commands);
+        let elaborated = insert_choice_terms(&mut pool, &node, None);
+        let elaborated = Proof {
+            constant_definitions: proof.constant_definitions,
+            commands: elaborated.into_commands(),
+        };
+
+        checker::ProofChecker::new(&mut pool, checker::Config::new())
+            .check(&problem, &elaborated)
+            .unwrap();
+
+        let ProofCommand::Subproof(s) = &elaborated.commands[0] else {
+            panic!("expected a subproof");
+        };
+        assert_eq!(
+            s.args[0].as_assign().unwrap().1.to_string(),
+            "(choice ((x Int)) (p x))"
+        );
+        let ProofCommand::Subproof(s) = &elaborated.commands[1] else {
+            panic!("expected a subproof");
+        };
+        assert_eq!(
+            s.args[1].as_assign().unwrap().1.to_string(),
+            "(choice ((y Int)) (not (q (choice ((x Int)) (not (forall ((y Int)) (q x y)))) y)))"
+        );
+    }
+}
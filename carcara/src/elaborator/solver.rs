@@ -0,0 +1,643 @@
+//! The interface to the external SMT solvers used to elaborate `lia_generic` and `hole` steps.
+
+use crate::{ast::ProblemPrelude, CarcaraResult};
+use std::{
+    fmt, fs,
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+    process::{self, Child, Command, ExitStatus, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SolverError {
+    #[error("failed to spawn solver process")]
+    FailedSpawnSolver(io::Error),
+
+    #[error("failed to write to solver stdin")]
+    FailedWriteToSolverStdin(io::Error),
+
+    #[error("error while waiting for solver to exit")]
+    FailedWaitForSolver(io::Error),
+
+    #[error("solver gave invalid output")]
+    SolverGaveInvalidOutput,
+
+    #[error("solver output not unsat")]
+    OutputNotUnsat,
+
+    #[error("solver timed out when solving problem")]
+    SolverTimeout,
+
+    #[error(
+        "solver returned non-zero exit code: {}",
+        if let Some(i) = .0 { format!("{}", i) } else { "none".to_owned() }
+    )]
+    NonZeroExitCode(Option<i32>),
+
+    #[error("{0} does not produce proofs in the Alethe format")]
+    NoAletheProof(&'static str),
+
+    #[error("error in inner proof: {0}")]
+    InnerProofError(Box<crate::Error>),
+}
+
+/// An external SMT solver that can be called to prove a problem. Each solver has its own command
+/// line interface and output quirks, so implementations are responsible for building the solver
+/// command, writing the problem in a way the solver accepts, and extracting the Alethe proof from
+/// its output.
+pub trait ExternalSolver: fmt::Debug + Send + Sync {
+    /// The name of the solver, used in error and log messages.
+    fn name(&self) -> &'static str;
+
+    /// Builds the command that starts the solver. The problem will be written to its stdin.
+    fn command(&self) -> Command;
+
+    /// Returns the complete problem that will be given to the solver, given the prelude and the
+    /// assertions of the problem, already printed as SMT-LIB.
+    fn problem_string(&self, prelude: &ProblemPrelude, assertions: &str) -> String {
+        format!(
+            "(set-option :produce-proofs true)\n{}{}(check-sat)\n(get-proof)\n(exit)\n",
+            prelude, assertions
+        )
+    }
+
+    /// Extracts the Alethe proof from the output of the solver. Returns an error if the solver
+    /// failed, or if its answer was not `unsat`.
+    fn extract_proof<'a>(&self, output: &'a Output) -> Result<&'a [u8], SolverError> {
+        if !output.status.success() {
+            return Err(SolverError::NonZeroExitCode(output.status.code()));
+        }
+        expect_unsat(&output.stdout)
+    }
+
+    /// Calls the solver on the given problem, and returns the proof it produced. If the solver
+    /// doesn't finish within the time limit in `limits`, it is killed and
+    /// [`SolverError::SolverTimeout`] is returned.
+    fn get_proof(&self, problem: &str, limits: SolverLimits) -> Result<Vec<u8>, SolverError> {
+        let output = self.run(problem, limits)?;
+        self.extract_proof(&output).map(<[u8]>::to_vec)
+    }
+
+    /// Runs the solver on the given problem, and returns its output without interpreting it. The
+    /// problem is written to the solver stdin while its output is read, so the solver never blocks
+    /// on a full pipe, and the time limit in `limits` also covers writing the problem. If the solver
+    /// exits before reading the whole problem, its output is returned as usual.
+    fn run(&self, problem: &str, limits: SolverLimits) -> Result<Output, SolverError> {
+        let mut command = self.command();
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        limits.apply(&mut command);
+        let mut process = command.spawn().map_err(SolverError::FailedSpawnSolver)?;
+        let deadline = limits.timeout.map(|t| Instant::now() + t);
+
+        let stdin = write_in_background(process.stdin.take(), problem.as_bytes().to_vec());
+        let stdout = read_in_background(process.stdout.take());
+        let stderr = read_in_background(process.stderr.take());
+        let status = wait_until(&mut process, deadline)?;
+
+        let written = stdin.join().unwrap_or(Ok(()));
+        let output = Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        };
+        // A broken pipe only means that the solver exited without reading the whole problem, so
+        // its output and exit status are still what decide the result
+        match written {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                Err(SolverError::FailedWriteToSolverStdin(e))
+            }
+            _ => Ok(output),
+        }
+    }
+}
+
+/// The resource limits enforced on external solver calls. These are enforced by Carcara itself,
+/// and are independent of any limits given to the solver in its arguments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolverLimits {
+    /// The wall-clock time limit for each solver call. When it is reached, the solver is killed,
+    /// along with any processes it spawned.
+    pub timeout: Option<Duration>,
+
+    /// The maximum size of the address space of the solver process, in bytes. When the solver
+    /// exceeds it, memory allocations fail, and the solver usually exits with an error. This is
+    /// only enforced on Unix systems.
+    pub memory: Option<u64>,
+}
+
+impl SolverLimits {
+    fn apply(&self, command: &mut Command) {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+
+            // We spawn the solver in a new process group, so that, in case of a timeout, we can
+            // kill it together with any processes it spawned
+            if self.timeout.is_some() {
+                command.process_group(0);
+            }
+            if let Some(bytes) = self.memory {
+                let limit = libc::rlimit { rlim_cur: bytes, rlim_max: bytes };
+                // SAFETY: The closure runs in the child process, after `fork`. It only calls
+                // `setrlimit`, which is async-signal-safe, and doesn't allocate
+                unsafe {
+                    command.pre_exec(move || {
+                        if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                            return Err(io::Error::last_os_error());
+                        }
+                        Ok(())
+                    });
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = command;
+    }
+}
+
+/// How often we check if the solver has finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Waits for the solver to exit. If it is still running at the deadline, or if waiting fails, the
+/// solver is killed, together with any processes it spawned, and an error is returned.
+fn wait_until(process: &mut Child, deadline: Option<Instant>) -> Result<ExitStatus, SolverError> {
+    let result = loop {
+        match process.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if deadline.is_some_and(|d| Instant::now() >= d) => {
+                break SolverError::SolverTimeout;
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => break SolverError::FailedWaitForSolver(e),
+        }
+    };
+    kill_process_group(process);
+    let _ = process.wait();
+    Err(result)
+}
+
+fn write_in_background<W: Write + Send + 'static>(
+    pipe: Option<W>,
+    input: Vec<u8>,
+) -> thread::JoinHandle<io::Result<()>> {
+    thread::spawn(move || match pipe {
+        // The pipe is closed when it is dropped, at the end of the thread
+        Some(mut pipe) => pipe.write_all(&input),
+        None => Ok(()),
+    })
+}
+
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+#[cfg(unix)]
+fn kill_process_group(process: &mut Child) {
+    // Since the solver was spawned in a new process group, the group id is the solver pid
+    let group = -(process.id() as libc::pid_t);
+
+    // SAFETY: `kill` has no memory safety requirements
+    unsafe {
+        libc::kill(group, libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(process: &mut Child) {
+    let _ = process.kill();
+}
+
+/// Checks that the first line of the solver output is `unsat`, and returns the rest of it.
+fn expect_unsat(mut stdout: &[u8]) -> Result<&[u8], SolverError> {
+    let mut first_line = String::new();
+    stdout
+        .read_line(&mut first_line)
+        .map_err(|_| SolverError::SolverGaveInvalidOutput)?;
+
+    if first_line.trim_end() != "unsat" {
+        return Err(SolverError::OutputNotUnsat);
+    }
+    Ok(stdout)
+}
+
+/// Returns `true` if the first line of the solver output is the given answer.
+fn first_line_is(mut stdout: &[u8], answer: &str) -> bool {
+    let mut first_line = String::new();
+    let _ = stdout.read_line(&mut first_line);
+    first_line.trim() == answer
+}
+
+fn command_with_args(path: &str, arguments: &[Box<str>]) -> Command {
+    let mut command = Command::new(path);
+    command.args(arguments.iter().map(AsRef::as_ref));
+    command
+}
+
+/// The cvc5 SMT solver.
+#[derive(Debug, Clone)]
+pub struct Cvc5 {
+    /// The path to the solver binary.
+    pub path: Box<str>,
+
+    /// The arguments to pass to the solver.
+    pub arguments: Vec<Box<str>>,
+}
+
+impl Cvc5 {
+    /// The default arguments, which make cvc5 read SMT-LIB from stdin and print an Alethe proof
+    /// with explicit resolution pivots.
+    pub const DEFAULT_ARGUMENTS: &'static str = "--tlimit=10000 --lang=smt2 \
+        --proof-format-mode=alethe --proof-granularity=theory-rewrite --proof-alethe-res-pivots";
+
+    pub fn new(path: &str) -> Self {
+        Self::with_arguments(path, Self::DEFAULT_ARGUMENTS)
+    }
+
+    /// Creates a solver with the given arguments, separated by whitespace.
+    pub fn with_arguments(path: &str, arguments: &str) -> Self {
+        Self {
+            path: path.into(),
+            arguments: arguments.split_whitespace().map(Into::into).collect(),
+        }
+    }
+}
+
+impl ExternalSolver for Cvc5 {
+    fn name(&self) -> &'static str {
+        "cvc5"
+    }
+
+    fn command(&self) -> Command {
+        command_with_args(&self.path, &self.arguments)
+    }
+
+    fn extract_proof<'a>(&self, output: &'a Output) -> Result<&'a [u8], SolverError> {
+        // When the time limit given by `--tlimit` is reached, cvc5 exits with a non-zero code and
+        // reports the timeout in stderr
+        if !output.status.success() {
+            if let Ok(s) = std::str::from_utf8(&output.stderr) {
+                if s.contains("interrupted by timeout.") {
+                    return Err(SolverError::SolverTimeout);
+                }
+            }
+            return Err(SolverError::NonZeroExitCode(output.status.code()));
+        }
+        expect_unsat(&output.stdout)
+    }
+}
+
+/// The veriT SMT solver.
+#[derive(Debug, Clone)]
+pub struct VeriT {
+    /// The path to the solver binary.
+    pub path: Box<str>,
+
+    /// The arguments to pass to the solver.
+    pub arguments: Vec<Box<str>>,
+}
+
+impl VeriT {
+    /// The default arguments, which make veriT print a pruned proof to stdout, without the banner
+    /// and the `success` responses that would otherwise precede the `unsat` answer.
+    pub const DEFAULT_ARGUMENTS: &'static str =
+        "--disable-banner --disable-print-success --proof-prune --proof-merge --proof=";
+
+    pub fn new(path: &str) -> Self {
+        Self::with_arguments(path, Self::DEFAULT_ARGUMENTS)
+    }
+
+    /// Creates a solver with the given arguments, separated by whitespace.
+    pub fn with_arguments(path: &str, arguments: &str) -> Self {
+        Self {
+            path: path.into(),
+            arguments: arguments.split_whitespace().map(Into::into).collect(),
+        }
+    }
+}
+
+impl ExternalSolver for VeriT {
+    fn name(&self) -> &'static str {
+        "veriT"
+    }
+
+    fn command(&self) -> Command {
+        command_with_args(&self.path, &self.arguments)
+    }
+
+    fn extract_proof<'a>(&self, output: &'a Output) -> Result<&'a [u8], SolverError> {
+        // When its time limit is reached, veriT answers `unknown` instead of failing
+        if !output.status.success() {
+            return Err(SolverError::NonZeroExitCode(output.status.code()));
+        }
+        if first_line_is(&output.stdout, "unknown") {
+            return Err(SolverError::SolverTimeout);
+        }
+        expect_unsat(&output.stdout)
+    }
+}
+
+/// The Z3 SMT solver. Z3 does not produce proofs in the Alethe format, so it can only be used to
+/// confirm that a problem is unsatisfiable. Calling [`ExternalSolver::get_proof`] with it will
+/// always fail, either because the problem is not `unsat`, or with
+/// [`SolverError::NoAletheProof`].
+#[derive(Debug, Clone)]
+pub struct Z3 {
+    /// The path to the solver binary.
+    pub path: Box<str>,
+
+    /// The arguments to pass to the solver.
+    pub arguments: Vec<Box<str>>,
+}
+
+impl Z3 {
+    /// The default arguments, which make Z3 read SMT-LIB from stdin, with a timeout of 10 seconds.
+    pub const DEFAULT_ARGUMENTS: &'static str = "-in -smt2 -T:10";
+
+    pub fn new(path: &str) -> Self {
+        Self::with_arguments(path, Self::DEFAULT_ARGUMENTS)
+    }
+
+    /// Creates a solver with the given arguments, separated by whitespace.
+    pub fn with_arguments(path: &str, arguments: &str) -> Self {
+        Self {
+            path: path.into(),
+            arguments: arguments.split_whitespace().map(Into::into).collect(),
+        }
+    }
+}
+
+impl ExternalSolver for Z3 {
+    fn name(&self) -> &'static str {
+        "Z3"
+    }
+
+    fn command(&self) -> Command {
+        command_with_args(&self.path, &self.arguments)
+    }
+
+    fn problem_string(&self, prelude: &ProblemPrelude, assertions: &str) -> String {
+        // Z3 uses `:proof` instead of `:produce-proofs`, and its proofs are of no use to us, so we
+        // don't ask for them
+        format!("{}{}(check-sat)\n(exit)\n", prelude, assertions)
+    }
+
+    fn extract_proof<'a>(&self, output: &'a Output) -> Result<&'a [u8], SolverError> {
+        // When the time limit given by `-T` is reached, Z3 prints `timeout` and exits with a
+        // non-zero code
+        if first_line_is(&output.stdout, "timeout") {
+            return Err(SolverError::SolverTimeout);
+        }
+        if !output.status.success() {
+            return Err(SolverError::NonZeroExitCode(output.status.code()));
+        }
+        expect_unsat(&output.stdout)?;
+        Err(SolverError::NoAletheProof(self.name()))
+    }
+}
+
+/// An on-disk cache of solver proofs, indexed by the problems given to the solver. This avoids
+/// calling the solver again for the same problem, e.g. when elaborating many proofs that contain
+/// the same `lia_generic` step.
+///
+/// Each entry is stored as two files in the cache directory, named after a hash of the problem: a
+/// `.smt2` file with the problem, and an `.alethe` file with the proof. Since the full problem is
+/// stored, hash collisions are detected and treated as cache misses. Proofs taken from the cache
+/// are checked just like the ones produced by the solver.
+#[derive(Debug, Clone)]
+pub struct SolverCache {
+    dir: PathBuf,
+}
+
+impl SolverCache {
+    /// Creates a cache that stores its entries in the given directory. The directory is created
+    /// when the first entry is inserted, if it doesn't exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn entry_path(&self, problem: &str, extension: &str) -> PathBuf {
+        // We use FNV-1a instead of `DefaultHasher`, since its output must be the same across
+        // executions and Rust versions
+        let hash = problem
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        self.dir.join(format!("{:016x}.{}", hash, extension))
+    }
+
+    /// Returns the cached proof for the given problem, if there is one.
+    pub fn get(&self, problem: &str) -> Option<Vec<u8>> {
+        let cached_problem = fs::read(self.entry_path(problem, "smt2")).ok()?;
+        if cached_problem != problem.as_bytes() {
+            return None;
+        }
+        fs::read(self.entry_path(problem, "alethe")).ok()
+    }
+
+    /// Stores the proof for the given problem, replacing any existing entry with the same hash.
+    pub fn insert(&self, problem: &str, proof: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        // Many Carcara processes may share the same cache, so the files are first written to a
+        // temporary path and then renamed, to make sure other processes never read partial
+        // entries. The proof is written first, so that an entry is only visible once it is complete
+        write_atomically(&self.entry_path(problem, "alethe"), proof)?;
+        write_atomically(&self.entry_path(problem, "smt2"), problem.as_bytes())
+    }
+}
+
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".tmp.{}", process::id()));
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
+
+/// Gets a proof for the given problem, either from the cache or by calling the solver, and parses
+/// and checks it using `check`. If a cached proof fails to check, it is discarded and the solver is
+/// called instead. Proofs given by the solver are only added to the cache if they are valid.
+pub(super) fn get_checked_proof<T>(
+    solver: &dyn ExternalSolver,
+    problem: &str,
+    limits: SolverLimits,
+    cache: Option<&SolverCache>,
+    mut check: impl FnMut(&[u8]) -> CarcaraResult<T>,
+) -> Result<T, SolverError> {
+    if let Some(proof) = cache.and_then(|c| c.get(problem)) {
+        match check(&proof) {
+            Ok(result) => return Ok(result),
+            Err(e) => tracing::warn!("discarding invalid proof from solver cache: {}", e),
+        }
+    }
+
+    let proof = solver.get_proof(problem, limits)?;
+    let result = check(&proof).map_err(|e| SolverError::InnerProofError(Box::new(e)))?;
+    if let Some(cache) = cache {
+        if let Err(e) = cache.insert(problem, &proof) {
+            tracing::warn!("failed to write to solver cache: {}", e);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn output(code: i32, stdout: &str, stderr: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(code << 8),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_extract_proof() {
+        let proof = "(step t1 (cl) :rule hole)\n";
+        let unsat = output(0, &format!("unsat\n{}", proof), "");
+
+        let cvc5 = Cvc5::new("cvc5");
+        assert_eq!(cvc5.extract_proof(&unsat).unwrap(), proof.as_bytes());
+        let timeout = output(1, "", "cvc5 interrupted by timeout.");
+        assert!(matches!(
+            cvc5.extract_proof(&timeout),
+            Err(SolverError::SolverTimeout)
+        ));
+        assert!(matches!(
+            cvc5.extract_proof(&output(0, "sat\n", "")),
+            Err(SolverError::OutputNotUnsat)
+        ));
+
+        let verit = VeriT::new("veriT");
+        assert_eq!(verit.extract_proof(&unsat).unwrap(), proof.as_bytes());
+        assert!(matches!(
+            verit.extract_proof(&output(0, "unknown\n", "")),
+            Err(SolverError::SolverTimeout)
+        ));
+
+        let z3 = Z3::new("z3");
+        assert!(matches!(
+            z3.extract_proof(&unsat),
+            Err(SolverError::NoAletheProof("Z3"))
+        ));
+        assert!(matches!(
+            z3.extract_proof(&output(1, "timeout\n", "")),
+            Err(SolverError::SolverTimeout)
+        ));
+    }
+
+    /// A "solver" that runs a shell script, ignoring the problem.
+    #[derive(Debug)]
+    struct Script(&'static str);
+
+    impl ExternalSolver for Script {
+        fn name(&self) -> &'static str {
+            "script"
+        }
+
+        fn command(&self) -> Command {
+            let mut command = Command::new("sh");
+            command.args(["-c", self.0]);
+            command
+        }
+    }
+
+    #[test]
+    fn test_solver_limits() {
+        let limits = SolverLimits {
+            timeout: Some(Duration::from_millis(200)),
+            memory: None,
+        };
+        // The problem is larger than the pipe buffer, so writing it blocks until the solver reads it
+        let large_problem = "(assert true)\n".repeat(100_000);
+        let proof = Script("cat > /dev/null; echo unsat; echo proof")
+            .get_proof(&large_problem, limits)
+            .unwrap();
+        assert_eq!(proof, b"proof\n");
+
+        // A solver that exits without reading the problem gives its exit status
+        let result = Script("exit 3").get_proof(&large_problem, limits);
+        assert!(matches!(result, Err(SolverError::NonZeroExitCode(Some(3)))));
+        let proof = Script("echo unsat; echo proof")
+            .get_proof(&large_problem, limits)
+            .unwrap();
+        assert_eq!(proof, b"proof\n");
+
+        // The time limit also covers writing the problem
+        let start = Instant::now();
+        let result = Script("sleep 10").get_proof(&large_problem, limits);
+        assert!(matches!(result, Err(SolverError::SolverTimeout)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // The solver is killed, together with the processes it spawned
+        let start = Instant::now();
+        let result = Script("sleep 10 & sleep 10; echo unsat").get_proof("", limits);
+        assert!(matches!(result, Err(SolverError::SolverTimeout)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // Large outputs don't block the solver while we wait for it
+        let proof = Script("echo unsat; head -c 1000000 /dev/zero")
+            .get_proof("", limits)
+            .unwrap();
+        assert_eq!(proof.len(), 1_000_000);
+
+        let limits = SolverLimits {
+            timeout: None,
+            memory: Some(64 * 1024 * 1024),
+        };
+        let proof = Script("echo unsat; ulimit -v")
+            .get_proof("", limits)
+            .unwrap();
+        assert_eq!(proof, b"65536\n");
+    }
+
+    #[test]
+    fn test_solver_cache() {
+        let dir = std::env::temp_dir().join(format!("carcara-solver-cache-{}", process::id()));
+        let cache = SolverCache::new(&dir);
+        let limits = SolverLimits::default();
+        let check = |proof: &[u8]| match proof {
+            b"invalid\n" => Err(crate::Error::DoesNotReachEmptyClause),
+            _ => Ok(proof.to_vec()),
+        };
+
+        // A miss calls the solver, and stores its proof
+        let solver = Script("echo unsat; echo proof");
+        let proof = get_checked_proof(&solver, "a", limits, Some(&cache), check).unwrap();
+        assert_eq!(proof, b"proof\n");
+        assert_eq!(cache.get("a").unwrap(), b"proof\n");
+        assert!(cache.get("b").is_none());
+
+        // A hit doesn't call the solver
+        let failing = Script("exit 1");
+        let proof = get_checked_proof(&failing, "a", limits, Some(&cache), check).unwrap();
+        assert_eq!(proof, b"proof\n");
+
+        // Invalid proofs are not stored, and invalid cached proofs are discarded
+        let invalid = Script("echo unsat; echo invalid");
+        assert!(get_checked_proof(&invalid, "b", limits, Some(&cache), check).is_err());
+        assert!(cache.get("b").is_none());
+        cache.insert("b", b"invalid\n").unwrap();
+        let proof = get_checked_proof(&solver, "b", limits, Some(&cache), check).unwrap();
+        assert_eq!(proof, b"proof\n");
+        assert_eq!(cache.get("b").unwrap(), b"proof\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,11 @@
 use super::IdHelper;
-use crate::{ast::*, checker::error::CheckerError};
+use crate::{ast::*, checker::error::CheckerError, utils::EqualityGraph};
 
-fn add_symm_step(pool: &mut PrimitivePool, node: &Rc<ProofNode>, id: String) -> Rc<ProofNode> {
+pub(super) fn add_symm_step(
+    pool: &mut PrimitivePool,
+    node: &Rc<ProofNode>,
+    id: String,
+) -> Rc<ProofNode> {
     assert_eq!(node.clause().len(), 1);
     let (a, b) = match_term!((= a b) = node.clause()[0]).unwrap();
     let clause = vec![build_term!(pool, (= {b.clone()} {a.clone()}))];
@@ -19,48 +23,43 @@ fn add_symm_step(pool: &mut PrimitivePool, node: &Rc<ProofNode>, id: String) ->
 
 /// Similar to `find_chain`, but reorders a premises vector to match the found chain. In `trans`,
 /// this is used to reorder the step premises vector; in `eq_transitive`, it is used to reorder the
-/// clause. This returns a boolean indicating whether any reordering was needed, a `usize`
-/// indicating how many premises are needed to prove the conclusion, and a vector of indices of the
-/// premise equalities that need to be flipped.
-fn find_and_trace_chain<'a, T>(
-    mut conclusion: (&'a Rc<Term>, &'a Rc<Term>),
+/// clause. The premises that form the chain are moved to the start of the vector, in order. This
+/// returns a boolean indicating whether any reordering was needed, a `usize` indicating how many
+/// premises are needed to prove the conclusion, and a vector of indices of the premise equalities
+/// that need to be flipped.
+fn find_and_trace_chain<'a, T: Clone>(
+    conclusion: (&'a Rc<Term>, &'a Rc<Term>),
     premise_equalities: &mut [(&'a Rc<Term>, &'a Rc<Term>)],
     premises: &mut [T],
 ) -> Result<(bool, usize, Vec<usize>), CheckerError> {
-    let mut reordered = false;
-    let mut should_flip = Vec::with_capacity(premise_equalities.len());
-    let mut i = 0;
-    loop {
-        if conclusion.0 == conclusion.1 {
-            return Ok((reordered, i, should_flip));
-        }
+    let (a, b) = conclusion;
+    let chain = EqualityGraph::new(premise_equalities)
+        .find_chain(a, b)
+        .ok_or_else(|| CheckerError::BrokenTransitivityChain(a.clone(), b.clone()))?;
 
-        let (found_index, next_link) = premise_equalities[i..]
-            .iter()
-            .enumerate()
-            .find_map(|(j, &(t, u))| {
-                if t == conclusion.0 {
-                    Some((j + i, u))
-                } else if u == conclusion.0 {
-                    should_flip.push(i);
-                    Some((j + i, t))
-                } else {
-                    None
-                }
-            })
-            .ok_or_else(|| {
-                let (a, b) = conclusion;
-                CheckerError::BrokenTransitivityChain(a.clone(), b.clone())
-            })?;
+    // The new order has the premises in the chain first, followed by the remaining premises in
+    // their original order
+    let mut is_in_chain = vec![false; premises.len()];
+    let mut order: Vec<_> = chain.iter().map(|&(i, _)| i).collect();
+    for &i in &order {
+        is_in_chain[i] = true;
+    }
+    order.extend((0..premises.len()).filter(|&i| !is_in_chain[i]));
 
-        if found_index != i {
-            premise_equalities.swap(i, found_index);
-            premises.swap(i, found_index);
-            reordered = true;
-        }
-        conclusion = (next_link, conclusion.1);
-        i += 1;
+    let reordered = order.iter().enumerate().any(|(i, &j)| i != j);
+    if reordered {
+        let new_equalities: Vec<_> = order.iter().map(|&i| premise_equalities[i]).collect();
+        premise_equalities.copy_from_slice(&new_equalities);
+        let new_premises: Vec<_> = order.iter().map(|&i| premises[i].clone()).collect();
+        premises.clone_from_slice(&new_premises);
     }
+
+    let should_flip = chain
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &(_, is_flipped))| is_flipped.then_some(i))
+        .collect();
+    Ok((reordered, chain.len(), should_flip))
 }
 
 pub fn trans(
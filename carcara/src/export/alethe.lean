@@ -0,0 +1,76 @@
+/-!
+A small embedding of Alethe proofs in Lean 4, used by the proofs exported by Carcara. A clause is
+the disjunction of its literals, and each Alethe step is reconstructed either by applying one of
+the lemmas below to its premises, or by one of the tactics below, after clearing every hypothesis
+that is not a premise of the step.
+-/
+
+namespace Alethe
+
+open Classical
+
+/-- An Alethe clause, as the disjunction of its literals. The empty clause is `False`. -/
+def Clause : List Prop → Prop
+  | [] => False
+  | l :: ls => l ∨ Clause ls
+
+theorem assume {p : Prop} (h : p) : Clause [p] := Or.inl h
+
+theorem «true» : Clause [True] := Or.inl trivial
+
+theorem «false» : Clause [¬False] := Or.inl id
+
+theorem equiv_pos1 {a b : Prop} : Clause [¬(a ↔ b), a, ¬b] := by
+  simp only [Clause]; tauto
+
+theorem equiv_pos2 {a b : Prop} : Clause [¬(a ↔ b), ¬a, b] := by
+  simp only [Clause]; tauto
+
+theorem equiv_neg1 {a b : Prop} : Clause [a ↔ b, ¬a, ¬b] := by
+  simp only [Clause]; tauto
+
+theorem equiv_neg2 {a b : Prop} : Clause [a ↔ b, a, b] := by
+  simp only [Clause]; tauto
+
+theorem implies_pos {a b : Prop} : Clause [¬(a → b), ¬a, b] := by
+  simp only [Clause]; tauto
+
+theorem implies_neg1 {a b : Prop} : Clause [a → b, a] := by
+  simp only [Clause]; tauto
+
+theorem implies_neg2 {a b : Prop} : Clause [a → b, ¬b] := by
+  simp only [Clause]; tauto
+
+theorem equiv1 {a b : Prop} (h : Clause [a ↔ b]) : Clause [¬a, b] := by
+  simp only [Clause] at *; tauto
+
+theorem equiv2 {a b : Prop} (h : Clause [a ↔ b]) : Clause [a, ¬b] := by
+  simp only [Clause] at *; tauto
+
+theorem not_equiv1 {a b : Prop} (h : Clause [¬(a ↔ b)]) : Clause [a, b] := by
+  simp only [Clause] at *; tauto
+
+theorem not_equiv2 {a b : Prop} (h : Clause [¬(a ↔ b)]) : Clause [¬a, ¬b] := by
+  simp only [Clause] at *; tauto
+
+theorem implies {a b : Prop} (h : Clause [a → b]) : Clause [¬a, b] := by
+  simp only [Clause] at *; tauto
+
+theorem not_implies1 {a b : Prop} (h : Clause [¬(a → b)]) : Clause [a] := by
+  simp only [Clause] at *; tauto
+
+theorem not_implies2 {a b : Prop} (h : Clause [¬(a → b)]) : Clause [¬b] := by
+  simp only [Clause] at *; tauto
+
+/-- Proves a step whose conclusion follows propositionally from its premises. -/
+macro "alethe_taut" : tactic => `(tactic| (simp only [Clause] at *; tauto))
+
+/-- Proves a step whose conclusion follows from its premises by the theory of equality. -/
+macro "alethe_eq" : tactic =>
+  `(tactic| (simp only [Clause] at *; by_contra h; push_neg at h; casesm* _ ∧ _; cc))
+
+/-- Proves a step whose conclusion follows from its premises by linear arithmetic. -/
+macro "alethe_arith" : tactic =>
+  `(tactic| (simp only [Clause] at *; by_contra h; push_neg at h; casesm* _ ∧ _; linarith))
+
+end Alethe
@@ -0,0 +1,494 @@
+//! An exporter of Alethe proofs into Lean 4 files.
+//!
+//! The exported file contains a small embedding of Alethe in Lean (see `alethe.lean`), followed by
+//! a theorem stating that the problem premises imply `False`. All sorts and function symbols
+//! declared in the problem are parameters of this theorem, and its proof has one `have` for each
+//! proof command. Each step is justified by the Lean lemma or tactic associated with its rule, and
+//! rules without one are rejected. The exported proof depends on Mathlib for its tactics.
+//!
+//! Only the boolean, equality and arithmetic fragments are supported, and the proof must not
+//! contain subproofs, so this is meant to be used on elaborated proofs.
+
+use super::{ExportError, ExportResult};
+use crate::ast::*;
+
+/// The Alethe embedding, which is included at the start of every exported file.
+const EMBEDDING: &str = include_str!("alethe.lean");
+
+/// How a step with a given rule is reconstructed in Lean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Justification {
+    /// The step is the application of the lemma in the embedding with the same name as the rule
+    /// to the step premises.
+    Lemma,
+
+    /// The step is proved by this tactic, after clearing every hypothesis except its premises.
+    Tactic(&'static str),
+}
+
+fn justification(rule: &str) -> Option<Justification> {
+    use Justification::*;
+
+    Some(match rule {
+        "true" | "false" | "equiv_pos1" | "equiv_pos2" | "equiv_neg1" | "equiv_neg2"
+        | "implies_pos" | "implies_neg1" | "implies_neg2" | "equiv1" | "equiv2" | "not_equiv1"
+        | "not_equiv2" | "implies" | "not_implies1" | "not_implies2" => Lemma,
+        "not_not" | "and_pos" | "and_neg" | "or_pos" | "or_neg" | "xor_pos1" | "xor_pos2"
+        | "xor_neg1" | "xor_neg2" | "ite_pos1" | "ite_pos2" | "ite_neg1" | "ite_neg2" | "ite1"
+        | "ite2" | "not_ite1" | "not_ite2" | "connective_def" | "resolution"
+        | "strict_resolution" | "tautology" | "contraction" | "reordering" | "weakening"
+        | "and" | "not_or" | "or" | "not_and" | "xor1" | "xor2" | "not_xor1" | "not_xor2"
+        | "nary_elim" | "and_simplify" | "or_simplify" | "not_simplify" | "implies_simplify"
+        | "equiv_simplify" | "bool_simplify" | "ac_simp" => Tactic("alethe_taut"),
+        "eq_reflexive" | "eq_transitive" | "eq_congruent" | "eq_congruent_pred" | "refl"
+        | "trans" | "cong" | "symm" | "not_symm" | "eq_symmetric" | "distinct_elim" => {
+            Tactic("alethe_eq")
+        }
+        "la_rw_eq" | "la_generic" | "la_disequality" | "la_totality" | "la_tautology"
+        | "la_antisymmetry" | "la_mult_pos" | "la_mult_neg" | "lia_generic" => {
+            Tactic("alethe_arith")
+        }
+        _ => return None,
+    })
+}
+
+/// Returns `true` if the symbol is a valid Lean identifier as it is, without needing to be quoted.
+fn is_plain_identifier(symbol: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "at",
+        "by",
+        "do",
+        "else",
+        "end",
+        "exists",
+        "forall",
+        "from",
+        "fun",
+        "have",
+        "if",
+        "import",
+        "in",
+        "let",
+        "match",
+        "namespace",
+        "open",
+        "Prop",
+        "section",
+        "show",
+        "Sort",
+        "then",
+        "theorem",
+        "Type",
+        "variable",
+        "where",
+        "with",
+    ];
+    let mut chars = symbol.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&symbol)
+}
+
+fn identifier(symbol: &str) -> String {
+    if is_plain_identifier(symbol) {
+        symbol.to_owned()
+    } else {
+        format!("«{}»", symbol)
+    }
+}
+
+/// The name of the `have` that proves the command with the given index.
+fn command_name(index: usize) -> String {
+    format!("_c{}", index)
+}
+
+/// The name of the theorem hypothesis that corresponds to the premise with the given index.
+fn premise_name(index: usize) -> String {
+    format!("_p{}", index)
+}
+
+struct LeanPrinter<'a> {
+    pool: &'a dyn TermPool,
+    out: String,
+}
+
+impl LeanPrinter<'_> {
+    fn write_sort(&mut self, sort: &Rc<Term>) -> ExportResult<()> {
+        match sort.as_sort() {
+            Some(Sort::Bool) => self.out.push_str("Prop"),
+            Some(Sort::Int) => self.out.push_str("Int"),
+            Some(Sort::Real) => self.out.push_str("Real"),
+            Some(Sort::Atom(name, args)) if args.is_empty() => self.out.push_str(&identifier(name)),
+            Some(Sort::Atom(name, args)) => {
+                self.out.push('(');
+                self.out.push_str(&identifier(name));
+                for a in args {
+                    self.out.push(' ');
+                    self.write_sort(a)?;
+                }
+                self.out.push(')');
+            }
+            Some(Sort::Function(sorts)) => {
+                self.out.push('(');
+                for (i, s) in sorts.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(" → ");
+                    }
+                    self.write_sort(s)?;
+                }
+                self.out.push(')');
+            }
+            _ => return Err(ExportError::UnsupportedSort(sort.to_string())),
+        }
+        Ok(())
+    }
+
+    fn write_binding_list(&mut self, bindings: &BindingList) -> ExportResult<()> {
+        for (i, (name, sort)) in bindings.iter().enumerate() {
+            if i > 0 {
+                self.out.push(' ');
+            }
+            self.out.push('(');
+            self.out.push_str(&identifier(name));
+            self.out.push_str(" : ");
+            self.write_sort(sort)?;
+            self.out.push(')');
+        }
+        Ok(())
+    }
+
+    /// Writes the arguments separated by the given infix operator, surrounded by parentheses.
+    fn write_infix(&mut self, op: &str, args: &[Rc<Term>]) -> ExportResult<()> {
+        self.out.push('(');
+        for (i, a) in args.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(&format!(" {} ", op));
+            }
+            self.write_term(a)?;
+        }
+        self.out.push(')');
+        Ok(())
+    }
+
+    /// Writes a chain of binary relations, like `(= a b c)`, as a conjunction of the relation
+    /// between each pair of adjacent arguments.
+    fn write_chain(&mut self, op: &str, args: &[Rc<Term>]) -> ExportResult<()> {
+        if args.len() == 2 {
+            return self.write_infix(op, args);
+        }
+        self.out.push('(');
+        for (i, pair) in args.windows(2).enumerate() {
+            if i > 0 {
+                self.out.push_str(" ∧ ");
+            }
+            self.write_infix(op, pair)?;
+        }
+        self.out.push(')');
+        Ok(())
+    }
+
+    fn write_op(&mut self, term: &Rc<Term>, op: Operator, args: &[Rc<Term>]) -> ExportResult<()> {
+        let is_bool = |p: &dyn TermPool, t: &Rc<Term>| p.sort(t).as_sort() == Some(&Sort::Bool);
+        match (op, args) {
+            (Operator::True, []) => self.out.push_str("True"),
+            (Operator::False, []) => self.out.push_str("False"),
+            (Operator::Not, [a]) => {
+                self.out.push_str("(¬");
+                self.write_term(a)?;
+                self.out.push(')');
+            }
+            (Operator::And | Operator::Or, [a]) => self.write_term(a)?,
+            (Operator::And, []) => self.out.push_str("True"),
+            (Operator::Or, []) => self.out.push_str("False"),
+            (Operator::And, _) => self.write_infix("∧", args)?,
+            (Operator::Or, _) => self.write_infix("∨", args)?,
+            (Operator::Implies, [_, _, ..]) => self.write_infix("→", args)?,
+            (Operator::Xor, [first, rest @ ..]) if !rest.is_empty() => {
+                // `xor` is left-associative
+                self.out.push_str(&"(Xor' ".repeat(rest.len()));
+                self.write_term(first)?;
+                for a in rest {
+                    self.out.push(' ');
+                    self.write_term(a)?;
+                    self.out.push(')');
+                }
+            }
+            (Operator::Equals, [a, _, ..]) if is_bool(self.pool, a) => {
+                self.write_chain("↔", args)?;
+            }
+            (Operator::Equals, [_, _, ..]) => self.write_chain("=", args)?,
+            (Operator::Distinct, [_, _, ..]) => {
+                let pairs: Vec<_> = (0..args.len())
+                    .flat_map(|i| (i + 1..args.len()).map(move |j| (i, j)))
+                    .collect();
+                self.out.push('(');
+                for (k, (i, j)) in pairs.into_iter().enumerate() {
+                    if k > 0 {
+                        self.out.push_str(" ∧ ");
+                    }
+                    self.write_infix("≠", &[args[i].clone(), args[j].clone()])?;
+                }
+                self.out.push(')');
+            }
+            (Operator::Ite, [c, t, e]) => {
+                self.out.push_str("(if ");
+                self.write_term(c)?;
+                self.out.push_str(" then ");
+                self.write_term(t)?;
+                self.out.push_str(" else ");
+                self.write_term(e)?;
+                self.out.push(')');
+            }
+            (Operator::Add, [_, _, ..]) => self.write_infix("+", args)?,
+            (Operator::Sub, [a]) => {
+                self.out.push_str("(-");
+                self.write_term(a)?;
+                self.out.push(')');
+            }
+            (Operator::Sub, _) => self.write_infix("-", args)?,
+            (Operator::Mult, [_, _, ..]) => self.write_infix("*", args)?,
+            (Operator::IntDiv | Operator::RealDiv, [_, _, ..]) => self.write_infix("/", args)?,
+            (Operator::Mod, [_, _]) => self.write_infix("%", args)?,
+            (Operator::Abs, [a]) => {
+                self.out.push_str("(abs ");
+                self.write_term(a)?;
+                self.out.push(')');
+            }
+            (Operator::LessThan, [_, _, ..]) => self.write_chain("<", args)?,
+            (Operator::GreaterThan, [_, _, ..]) => self.write_chain(">", args)?,
+            (Operator::LessEq, [_, _, ..]) => self.write_chain("≤", args)?,
+            (Operator::GreaterEq, [_, _, ..]) => self.write_chain("≥", args)?,
+            (Operator::ToReal, [a]) => {
+                self.out.push_str("((");
+                self.write_term(a)?;
+                self.out.push_str(" : Int) : Real)");
+            }
+            (Operator::ToInt, [a]) => {
+                self.out.push_str("(Int.floor ");
+                self.write_term(a)?;
+                self.out.push(')');
+            }
+            (Operator::IsInt, [a]) => {
+                self.out.push_str("(((Int.floor ");
+                self.write_term(a)?;
+                self.out.push_str(" : Int) : Real) = ");
+                self.write_term(a)?;
+                self.out.push(')');
+            }
+            _ => return Err(ExportError::UnsupportedTerm(term.to_string())),
+        }
+        Ok(())
+    }
+
+    fn write_term(&mut self, term: &Rc<Term>) -> ExportResult<()> {
+        match term.as_ref() {
+            Term::Const(Constant::Integer(i)) => self.out.push_str(&format!("({} : Int)", i)),
+            Term::Const(Constant::Real(r)) if *r.denom() == 1 => {
+                self.out.push_str(&format!("({} : Real)", r.numer()));
+            }
+            Term::Const(Constant::Real(r)) => {
+                self.out
+                    .push_str(&format!("(({} : Real) / {})", r.numer(), r.denom()));
+            }
+            Term::Var(name, _) => self.out.push_str(&identifier(name)),
+            Term::App(f, args) => {
+                self.out.push('(');
+                self.write_term(f)?;
+                for a in args {
+                    self.out.push(' ');
+                    self.write_term(a)?;
+                }
+                self.out.push(')');
+            }
+            Term::Op(op, args) => self.write_op(term, *op, args)?,
+            Term::Binder(binder, bindings, body) => {
+                let (open, separator) = match binder {
+                    Binder::Forall => ("(∀ ", ", "),
+                    Binder::Exists => ("(∃ ", ", "),
+                    Binder::Lambda => ("(fun ", " => "),
+                    Binder::Choice => ("(Classical.epsilon fun ", " => "),
+                };
+                self.out.push_str(open);
+                self.write_binding_list(bindings)?;
+                self.out.push_str(separator);
+                self.write_term(body)?;
+                self.out.push(')');
+            }
+            Term::Let(bindings, body) => {
+                self.out.push('(');
+                for (name, value) in bindings {
+                    self.out.push_str(&format!("let {} := ", identifier(name)));
+                    self.write_term(value)?;
+                    self.out.push_str("; ");
+                }
+                self.write_term(body)?;
+                self.out.push(')');
+            }
+            _ => return Err(ExportError::UnsupportedTerm(term.to_string())),
+        }
+        Ok(())
+    }
+
+    fn write_clause(&mut self, clause: &[Rc<Term>]) -> ExportResult<()> {
+        self.out.push_str("Clause [");
+        for (i, t) in clause.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.write_term(t)?;
+        }
+        self.out.push(']');
+        Ok(())
+    }
+}
+
+/// Exports a checked proof of the problem into a Lean 4 file, which is returned as a string. See
+/// the module documentation for the fragment of Alethe that is supported.
+pub fn export_proof(problem: &Problem, proof: &Proof, pool: &dyn TermPool) -> ExportResult<String> {
+    if let Some(datatype) = problem.prelude.datatypes().first() {
+        return Err(ExportError::UnsupportedDatatype(datatype.name.clone()));
+    }
+
+    let mut p = LeanPrinter { pool, out: String::new() };
+    p.out.push_str("import Mathlib.Tactic\n\n");
+    p.out.push_str(EMBEDDING);
+    p.out.push_str("\nopen Classical Alethe\n\ntheorem unsat");
+
+    for (name, arity) in &problem.prelude.sort_declarations {
+        let name = identifier(name);
+        p.out
+            .push_str(&format!(" {{{} : Type{}}}", name, " → Type".repeat(*arity)));
+        if *arity == 0 {
+            p.out.push_str(&format!(" [Nonempty {}]", name));
+        }
+    }
+    for (name, sort) in &problem.prelude.function_declarations {
+        p.out.push_str(&format!("\n    ({} : ", identifier(name)));
+        p.write_sort(sort)?;
+        p.out.push(')');
+    }
+    for (i, premise) in problem.premises.iter().enumerate() {
+        p.out.push_str(&format!("\n    ({} : ", premise_name(i)));
+        p.write_term(premise)?;
+        p.out.push(')');
+    }
+    p.out.push_str(" :\n    False := by\n");
+
+    for (i, command) in proof.commands.iter().enumerate() {
+        p.out.push_str(&format!("  have {} : ", command_name(i)));
+        p.write_clause(command.clause())?;
+        p.out.push_str(" := ");
+        match command {
+            ProofCommand::Assume { term, .. } => match problem.premises.get_index_of(term) {
+                Some(j) => p.out.push_str(&format!("assume {}", premise_name(j))),
+                None => p.out.push_str("assume (by assumption)"),
+            },
+            ProofCommand::Step(step) => {
+                // Since there are no subproofs, all premises are in the outermost depth
+                let premises: Vec<_> = step
+                    .premises
+                    .iter()
+                    .map(|&(_, j)| command_name(j))
+                    .collect();
+                match justification(&step.rule) {
+                    Some(Justification::Lemma) => {
+                        p.out.push_str("Alethe.");
+                        p.out.push_str(&identifier(&step.rule));
+                        for premise in premises {
+                            p.out.push(' ');
+                            p.out.push_str(&premise);
+                        }
+                    }
+                    Some(Justification::Tactic(tactic)) => {
+                        p.out.push_str("by clear * -");
+                        for premise in premises {
+                            p.out.push(' ');
+                            p.out.push_str(&premise);
+                        }
+                        p.out.push_str("; ");
+                        p.out.push_str(tactic);
+                    }
+                    None => return Err(ExportError::UnsupportedRule(step.rule.clone())),
+                }
+            }
+            ProofCommand::Subproof(_) => {
+                return Err(ExportError::UnsupportedSubproof(command.id().to_owned()))
+            }
+        }
+        p.out.push('\n');
+    }
+    if !proof.commands.is_empty() {
+        p.out.push_str(&format!(
+            "  exact {}\n",
+            command_name(proof.commands.len() - 1)
+        ));
+    }
+    Ok(p.out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_instance, Config};
+
+    fn export(problem: &str, proof: &str) -> ExportResult<String> {
+        let (problem, proof, pool) =
+            parse_instance(problem.as_bytes(), proof.as_bytes(), Config::new()).unwrap();
+        export_proof(&problem, &proof, &pool)
+    }
+
+    #[test]
+    fn test_lean_export() {
+        let problem = "
+            (declare-sort U 0)
+            (declare-fun f (U) U)
+            (declare-fun a () U)
+            (declare-fun p () Bool)
+            (declare-fun x () Real)
+            (assert (= p (not (= (f a) a))))
+            (assert (and p (< x 2.5)))
+            (assert (= (f a) a))
+        ";
+        let proof = "
+            (assume h1 (= p (not (= (f a) a))))
+            (assume h2 (and p (< x 2.5)))
+            (assume h3 (= (f a) a))
+            (step t4 (cl (not p) (not (= (f a) a))) :rule equiv1 :premises (h1))
+            (step t5 (cl p) :rule and :premises (h2) :args (0))
+            (step t6 (cl (= x x)) :rule refl)
+            (step t7 (cl) :rule resolution :premises (t4 t5 h3))
+        ";
+        let exported = export(problem, proof).unwrap();
+        assert!(exported.starts_with("import Mathlib.Tactic\n"));
+        let expected = "
+theorem unsat {U : Type} [Nonempty U]
+    (f : (U → U))
+    (a : U)
+    (p : Prop)
+    (x : Real)
+    (_p0 : (p ↔ (¬((f a) = a))))
+    (_p1 : (p ∧ (x < ((5 : Real) / 2))))
+    (_p2 : ((f a) = a)) :
+    False := by
+  have _c0 : Clause [(p ↔ (¬((f a) = a)))] := assume _p0
+  have _c1 : Clause [(p ∧ (x < ((5 : Real) / 2)))] := assume _p1
+  have _c2 : Clause [((f a) = a)] := assume _p2
+  have _c3 : Clause [(¬p), (¬((f a) = a))] := Alethe.equiv1 _c0
+  have _c4 : Clause [p] := by clear * - _c1; alethe_taut
+  have _c5 : Clause [(x = x)] := by clear * -; alethe_eq
+  have _c6 : Clause [] := by clear * - _c3 _c4 _c2; alethe_taut
+  exact _c6
+";
+        assert!(exported.ends_with(expected));
+
+        for proof in [
+            "(step t1 (cl) :rule hole)",
+            "(step t1 (cl (= \"a\" \"a\")) :rule refl)",
+            "(anchor :step t2) (assume t2.a0 p) (step t2 (cl (not p) p) :rule subproof)",
+        ] {
+            assert!(export(problem, proof).is_err());
+        }
+    }
+}
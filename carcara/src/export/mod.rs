@@ -0,0 +1,27 @@
+//! Exporters of checked Alethe proofs into the languages of proof assistants, so that the proofs
+//! can be rechecked by a trusted kernel.
+
+pub mod lean;
+pub mod smtcoq;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("unsupported sort: '{0}'")]
+    UnsupportedSort(String),
+
+    #[error("unsupported term: '{0}'")]
+    UnsupportedTerm(String),
+
+    #[error("unsupported rule '{0}'")]
+    UnsupportedRule(String),
+
+    #[error("subproofs are not supported, found subproof ending in step '{0}'")]
+    UnsupportedSubproof(String),
+
+    #[error("datatype declarations are not supported, found datatype '{0}'")]
+    UnsupportedDatatype(String),
+}
+
+pub type ExportResult<T> = Result<T, ExportError>;
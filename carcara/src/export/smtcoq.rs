@@ -0,0 +1,235 @@
+//! An exporter of Alethe proofs into certificates that can be replayed by SMTCoq.
+//!
+//! SMTCoq reads the proofs produced by veriT, which are in the Alethe format, so the certificate is
+//! the proof itself, printed without term sharing. However, SMTCoq only knows a subset of the
+//! Alethe rules, and only supports the boolean, equality, linear integer arithmetic, bit-vector
+//! and array fragments. It also computes the pivots of resolution steps by itself, so these are
+//! removed from the certificate. Proofs that use other rules or sorts are rejected, so this is
+//! meant to be used on elaborated proofs.
+
+use super::{ExportError, ExportResult};
+use crate::ast::*;
+
+/// The rules that SMTCoq can replay.
+const SUPPORTED_RULES: &[&str] = &[
+    "true",
+    "false",
+    "not_not",
+    "and_pos",
+    "and_neg",
+    "or_pos",
+    "or_neg",
+    "xor_pos1",
+    "xor_pos2",
+    "xor_neg1",
+    "xor_neg2",
+    "implies_pos",
+    "implies_neg1",
+    "implies_neg2",
+    "equiv_pos1",
+    "equiv_pos2",
+    "equiv_neg1",
+    "equiv_neg2",
+    "ite_pos1",
+    "ite_pos2",
+    "ite_neg1",
+    "ite_neg2",
+    "eq_reflexive",
+    "eq_transitive",
+    "eq_congruent",
+    "eq_congruent_pred",
+    "distinct_elim",
+    "la_rw_eq",
+    "la_generic",
+    "la_disequality",
+    "la_totality",
+    "la_tautology",
+    "lia_generic",
+    "resolution",
+    "th_resolution",
+    "and",
+    "not_or",
+    "or",
+    "not_and",
+    "xor1",
+    "xor2",
+    "not_xor1",
+    "not_xor2",
+    "implies",
+    "not_implies1",
+    "not_implies2",
+    "equiv1",
+    "equiv2",
+    "not_equiv1",
+    "not_equiv2",
+    "ite1",
+    "ite2",
+    "not_ite1",
+    "not_ite2",
+    "ite_intro",
+    "contraction",
+    "connective_def",
+    "refl",
+    "trans",
+    "cong",
+    "symm",
+    "bool_simplify",
+    "and_simplify",
+    "or_simplify",
+    "not_simplify",
+    "implies_simplify",
+    "equiv_simplify",
+    "ite_simplify",
+    "eq_simplify",
+    "qnt_cnf",
+    "forall_inst",
+    "qnt_join",
+    "qnt_rm_unused",
+    "bind",
+    "sko_ex",
+    "sko_forall",
+    "subproof",
+    "weakening",
+];
+
+/// The rules that can end a subproof in a certificate.
+const SUPPORTED_SUBPROOF_RULES: &[&str] = &["subproof", "bind", "sko_ex", "sko_forall"];
+
+fn check_sort(sort: &Rc<Term>) -> ExportResult<()> {
+    let unsupported = || Err(ExportError::UnsupportedSort(sort.to_string()));
+    match sort.as_sort() {
+        Some(Sort::Function(sorts)) => sorts.iter().try_for_each(check_sort),
+        Some(Sort::Array(x, y)) => {
+            check_sort(x)?;
+            check_sort(y)
+        }
+        Some(Sort::Atom(_, args)) if args.is_empty() => Ok(()),
+        Some(Sort::Bool | Sort::Int | Sort::BitVec(_)) => Ok(()),
+        _ => unsupported(),
+    }
+}
+
+fn check_commands(commands: &[ProofCommand]) -> ExportResult<()> {
+    for command in commands {
+        match command {
+            ProofCommand::Assume { .. } => (),
+            ProofCommand::Step(s) => {
+                if !SUPPORTED_RULES.contains(&s.rule.as_str()) {
+                    return Err(ExportError::UnsupportedRule(s.rule.clone()));
+                }
+            }
+            ProofCommand::Subproof(s) => {
+                let last = s.commands.last();
+                match last {
+                    Some(ProofCommand::Step(end))
+                        if SUPPORTED_SUBPROOF_RULES.contains(&end.rule.as_str()) => {}
+                    _ => return Err(ExportError::UnsupportedSubproof(command.id().to_owned())),
+                }
+                check_commands(&s.commands)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Removes the pivots of resolution steps, which SMTCoq computes by itself.
+fn remove_pivots(commands: &[ProofCommand]) -> Vec<ProofCommand> {
+    commands
+        .iter()
+        .map(|c| match c {
+            ProofCommand::Step(s) if s.rule == "resolution" || s.rule == "th_resolution" => {
+                ProofCommand::Step(ProofStep { args: Vec::new(), ..s.clone() })
+            }
+            ProofCommand::Subproof(s) => ProofCommand::Subproof(Subproof {
+                commands: remove_pivots(&s.commands),
+                ..s.clone()
+            }),
+            _ => c.clone(),
+        })
+        .collect()
+}
+
+/// Exports a checked proof of the problem into an SMTCoq certificate, which is returned as a
+/// string. See the module documentation for the fragment of Alethe that is supported.
+pub fn export_proof(
+    problem: &Problem,
+    proof: &Proof,
+    pool: &mut PrimitivePool,
+) -> ExportResult<String> {
+    if let Some(datatype) = problem.prelude.datatypes().first() {
+        return Err(ExportError::UnsupportedDatatype(datatype.name.clone()));
+    }
+    for (_, sort) in &problem.prelude.function_declarations {
+        check_sort(sort)?;
+    }
+    check_commands(&proof.commands)?;
+
+    let certificate = Proof {
+        constant_definitions: proof.constant_definitions.clone(),
+        commands: remove_pivots(&proof.commands),
+    };
+    let mut out = Vec::new();
+    write_proof(
+        pool,
+        &problem.prelude,
+        &certificate,
+        &mut out,
+        &PrintOptions::new(),
+    )
+    .expect("writing to a `Vec` should never fail");
+    Ok(String::from_utf8(out).expect("the printer should only produce valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn export(problem: &str, proof: &str) -> ExportResult<String> {
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        export_proof(&problem, &proof, &mut pool)
+    }
+
+    #[test]
+    fn test_smtcoq_export() {
+        let problem = "
+            (declare-const p Bool)
+            (declare-const q Bool)
+            (assert (or p q))
+            (assert (not p))
+            (assert (not q))
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (not p))
+            (assume h3 (not q))
+            (step t4 (cl p q) :rule or :premises (h1))
+            (step t5 (cl) :rule resolution :premises (t4 h2 h3) :args (p true q true))
+        ";
+        let expected = "\
+            (assume h1 (or p q))\n\
+            (assume h2 (not p))\n\
+            (assume h3 (not q))\n\
+            (step t4 (cl p q) :rule or :premises (h1))\n\
+            (step t5 (cl) :rule resolution :premises (t4 h2 h3))\n";
+        assert_eq!(export(problem, proof).unwrap(), expected);
+
+        let proof = "
+            (assume h1 (or p q))
+            (step t2 (cl p q) :rule hole :premises (h1))
+        ";
+        assert!(matches!(
+            export(problem, proof),
+            Err(ExportError::UnsupportedRule(r)) if r == "hole",
+        ));
+
+        let problem = "(declare-const x Real) (assert (< x 0.0))";
+        let proof = "(assume h1 (< x 0.0))";
+        assert!(matches!(
+            export(problem, proof),
+            Err(ExportError::UnsupportedSort(s)) if s == "Real",
+        ));
+    }
+}
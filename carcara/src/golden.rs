@@ -0,0 +1,234 @@
+//! A harness for golden-file tests, which compare the output of proof transformations, such as
+//! elaboration, against stored proofs.
+//!
+//! The comparison is structural and ignores command ids, so renumbering the steps of a proof does
+//! not cause spurious failures. If the environment variable `CARCARA_UPDATE_GOLDEN` is set, the
+//! golden files are overwritten with the actual outputs instead of being compared against them.
+
+use crate::{ast::*, parser};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// The environment variable that enables the update mode.
+pub const UPDATE_ENV_VAR: &str = "CARCARA_UPDATE_GOLDEN";
+
+#[derive(Debug, Error)]
+pub enum GoldenError {
+    #[error(transparent)]
+    Carcara(#[from] crate::Error),
+
+    #[error("golden file '{}' does not exist, set {} to create it", .0.display(), UPDATE_ENV_VAR)]
+    Missing(PathBuf),
+
+    #[error("output differs from golden file '{}' {}", .0.display(), .1)]
+    Mismatch(PathBuf, ProofMismatch),
+}
+
+impl From<io::Error> for GoldenError {
+    fn from(e: io::Error) -> Self {
+        Self::Carcara(crate::Error::Io(e))
+    }
+}
+
+/// A difference between two proofs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofMismatch {
+    /// The position of the command where the proofs differ. Each element is the (one-based) index
+    /// of a command, starting from the outermost proof and descending into subproofs.
+    pub location: Vec<usize>,
+
+    pub message: String,
+}
+
+impl fmt::Display for ProofMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location: Vec<_> = self.location.iter().map(usize::to_string).collect();
+        write!(f, "at command {}: {}", location.join("."), self.message)
+    }
+}
+
+/// Returns `true` if the update mode was requested, by setting the `CARCARA_UPDATE_GOLDEN`
+/// environment variable to a value other than `0`.
+pub fn update_requested() -> bool {
+    std::env::var_os(UPDATE_ENV_VAR).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Compares a proof against the golden proof stored in `golden_path`, which is parsed using the
+/// given problem prelude. In update mode, the golden file is instead overwritten with the given
+/// proof.
+pub fn check_golden(
+    pool: &mut PrimitivePool,
+    prelude: &ProblemPrelude,
+    actual: &Proof,
+    golden_path: &Path,
+) -> Result<(), GoldenError> {
+    if update_requested() {
+        let mut file = io::BufWriter::new(fs::File::create(golden_path)?);
+        write_proof(pool, prelude, actual, &mut file, &PrintOptions::new())?;
+        return Ok(());
+    }
+    if !golden_path.exists() {
+        return Err(GoldenError::Missing(golden_path.to_owned()));
+    }
+
+    let problem = prelude.to_string();
+    let golden = fs::read_to_string(golden_path)?;
+    let (_, expected, _) =
+        parser::parse_instance(problem.as_bytes(), golden.as_bytes(), parser::Config::new())?;
+    compare_proofs(&expected, actual)
+        .map_err(|mismatch| GoldenError::Mismatch(golden_path.to_owned(), mismatch))
+}
+
+/// Compares two proofs structurally, ignoring the ids of their commands. The proofs may come from
+/// different term pools, so terms are compared by their textual representation.
+pub fn compare_proofs(expected: &Proof, actual: &Proof) -> Result<(), ProofMismatch> {
+    compare_commands(&expected.commands, &actual.commands, &mut Vec::new())
+}
+
+fn compare_commands(
+    expected: &[ProofCommand],
+    actual: &[ProofCommand],
+    location: &mut Vec<usize>,
+) -> Result<(), ProofMismatch> {
+    let mismatch = |location: &[usize], message: String| ProofMismatch {
+        location: location.to_vec(),
+        message,
+    };
+
+    for (i, (e, a)) in expected.iter().zip(actual).enumerate() {
+        location.push(i + 1);
+        match (e, a) {
+            (ProofCommand::Assume { term: e, .. }, ProofCommand::Assume { term: a, .. }) => {
+                compare_terms(
+                    "assumed term",
+                    std::slice::from_ref(e),
+                    std::slice::from_ref(a),
+                )
+                .map_err(|m| mismatch(location, m))?;
+            }
+            (ProofCommand::Step(e), ProofCommand::Step(a)) => {
+                if e.rule != a.rule {
+                    let message = format!("expected rule '{}', got '{}'", e.rule, a.rule);
+                    return Err(mismatch(location, message));
+                }
+                compare_terms("clause", &e.clause, &a.clause)
+                    .and_then(|()| compare_terms("arguments", &e.args, &a.args))
+                    .map_err(|m| mismatch(location, m))?;
+                if e.premises != a.premises {
+                    let message =
+                        format!("expected premises {:?}, got {:?}", e.premises, a.premises);
+                    return Err(mismatch(location, message));
+                }
+                if e.discharge != a.discharge {
+                    let message = format!(
+                        "expected discharge {:?}, got {:?}",
+                        e.discharge, a.discharge
+                    );
+                    return Err(mismatch(location, message));
+                }
+            }
+            (ProofCommand::Subproof(e), ProofCommand::Subproof(a)) => {
+                let e_args: Vec<_> = e.args.iter().map(anchor_arg_to_string).collect();
+                let a_args: Vec<_> = a.args.iter().map(anchor_arg_to_string).collect();
+                if e_args != a_args {
+                    let message = format!(
+                        "expected anchor arguments ({}), got ({})",
+                        e_args.join(" "),
+                        a_args.join(" "),
+                    );
+                    return Err(mismatch(location, message));
+                }
+                compare_commands(&e.commands, &a.commands, location)?;
+            }
+            _ => {
+                let message = format!("expected {}, got {}", command_kind(e), command_kind(a));
+                return Err(mismatch(location, message));
+            }
+        }
+        location.pop();
+    }
+
+    if expected.len() != actual.len() {
+        location.push(expected.len().min(actual.len()) + 1);
+        let message = format!("expected {} commands, got {}", expected.len(), actual.len());
+        return Err(mismatch(location, message));
+    }
+    Ok(())
+}
+
+fn compare_terms(what: &str, expected: &[Rc<Term>], actual: &[Rc<Term>]) -> Result<(), String> {
+    let expected: Vec<_> = expected.iter().map(ToString::to_string).collect();
+    let actual: Vec<_> = actual.iter().map(ToString::to_string).collect();
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(format!(
+            "expected {} ({}), got ({})",
+            what,
+            expected.join(" "),
+            actual.join(" ")
+        ))
+    }
+}
+
+fn anchor_arg_to_string(arg: &AnchorArg) -> String {
+    match arg {
+        AnchorArg::Variable((name, sort)) => format!("({} {})", name, sort),
+        AnchorArg::Assign((name, sort), value) => format!("(:= ({} {}) {})", name, sort, value),
+    }
+}
+
+fn command_kind(command: &ProofCommand) -> &'static str {
+    match command {
+        ProofCommand::Assume { .. } => "an assume",
+        ProofCommand::Step(_) => "a step",
+        ProofCommand::Subproof(_) => "a subproof",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(proof: &str) -> Proof {
+        let definitions = "(declare-fun p () Bool) (declare-fun q () Bool)";
+        let (_, proof, _) = parser::parse_instance(
+            definitions.as_bytes(),
+            proof.as_bytes(),
+            parser::Config::new(),
+        )
+        .unwrap();
+        proof
+    }
+
+    #[test]
+    fn test_compare_proofs() {
+        let original = parse(
+            "(assume h1 p) (assume h2 (not p))
+            (step t3 (cl) :rule resolution :premises (h1 h2))",
+        );
+        let renamed = parse(
+            "(assume a p) (assume b (not p))
+            (step c (cl) :rule resolution :premises (a b))",
+        );
+        assert_eq!(compare_proofs(&original, &renamed), Ok(()));
+
+        let different = parse(
+            "(assume h1 p) (assume h2 (not q))
+            (step t3 (cl) :rule resolution :premises (h1 h2))",
+        );
+        let mismatch = compare_proofs(&original, &different).unwrap_err();
+        assert_eq!(mismatch.location, [2]);
+
+        let longer = parse(
+            "(assume h1 p) (assume h2 (not p))
+            (step t3 (cl) :rule resolution :premises (h1 h2))
+            (step t4 (cl (not false)) :rule false)",
+        );
+        let mismatch = compare_proofs(&original, &longer).unwrap_err();
+        assert_eq!(mismatch.location, [4]);
+    }
+}
@@ -0,0 +1,87 @@
+//! Reporting of the size of the term pool and of the proof after each stage of the pipeline, used
+//! to attribute memory usage to the stage that caused it.
+
+use crate::ast::{PrimitivePool, Proof};
+use std::fmt;
+
+/// A stage of the checking pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Parse,
+    Check,
+    Elaborate,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Stage::Parse => "parse",
+            Stage::Check => "check",
+            Stage::Elaborate => "elaborate",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The sizes measured after a stage of the pipeline finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageReport {
+    pub stage: Stage,
+
+    /// The number of terms interned in the term pool.
+    pub num_terms: usize,
+
+    /// The number of commands in the proof, including the commands inside subproofs.
+    pub num_commands: usize,
+
+    /// The resident memory of the process, in bytes, or `None` if it can't be measured in this
+    /// platform.
+    pub resident_memory: Option<usize>,
+}
+
+impl StageReport {
+    /// Measures the sizes of the given pool and proof, as well as the current resident memory.
+    pub fn measure(stage: Stage, pool: &PrimitivePool, proof: &Proof) -> Self {
+        Self {
+            stage,
+            num_terms: pool.len(),
+            num_commands: proof.iter().filter(|c| !c.is_subproof()).count(),
+            resident_memory: resident_memory(),
+        }
+    }
+}
+
+impl fmt::Display for StageReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "after {}: {} terms, {} commands",
+            self.stage, self.num_terms, self.num_commands
+        )?;
+        if let Some(bytes) = self.resident_memory {
+            write!(f, ", {:.1} MiB resident", bytes as f64 / (1024.0 * 1024.0))?;
+        }
+        Ok(())
+    }
+}
+
+/// A listener that is notified when each stage of the pipeline finishes. This is implemented for
+/// any closure that receives a `&StageReport`.
+pub trait PipelineListener {
+    fn stage_finished(&mut self, report: &StageReport);
+}
+
+impl<F: FnMut(&StageReport)> PipelineListener for F {
+    fn stage_finished(&mut self, report: &StageReport) {
+        self(report);
+    }
+}
+
+/// Returns the resident memory of the current process, in bytes. This is only supported on Linux,
+/// where it is read from `/proc/self/status`.
+pub fn resident_memory() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kilobytes: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
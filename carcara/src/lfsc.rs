@@ -0,0 +1,457 @@
+//! A front-end for proofs in the LFSC format, as produced by older versions of cvc5 and CVC4. The
+//! LFSC proof is translated into an Alethe proof, which can then be checked by the usual checker.
+//!
+//! Only the propositional core of the CVC4 signatures is translated into proper Alethe steps:
+//!
+//! - Declarations `(% x (term S) ...)` are skipped, since the symbols are taken from the problem
+//! - Assumptions `(% h (th_holds F) ...)` become `assume` commands
+//! - Atoms introduced by `decl_atom` are replaced by their formulas in clauses
+//! - `R` and `Q` become `resolution` steps, with the pivot given as an argument
+//! - `satlem` and `satlem_simplify` bind the lemma proved by their first proof argument
+//! - `contra` and `clausify_false` derive the empty clause
+//!
+//! Any other rule is translated into a `hole` step, as long as its conclusion is given by a type
+//! ascription `(: (holds C) ...)` or `(: (th_holds F) ...)`. Otherwise, an error is returned.
+
+use crate::{
+    ast::*,
+    utils::{read_sexprs, SExpr},
+    CarcaraResult,
+};
+use indexmap::IndexSet;
+use rug::{Integer, Rational};
+use std::{collections::HashMap, io::BufRead, str::FromStr};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LfscError {
+    #[error("syntax error: {0}")]
+    Syntax(String),
+
+    #[error("expected a single proof, of the form `(check <proof>)`")]
+    ExpectedCheck,
+
+    #[error("identifier '{0}' is not defined")]
+    UndefinedIden(String),
+
+    #[error("unsupported term: '{0}'")]
+    UnsupportedTerm(String),
+
+    #[error("unsupported rule '{0}', and its conclusion is not given by a type ascription")]
+    UnsupportedRule(String),
+
+    #[error("malformed proof: '{0}'")]
+    MalformedProof(String),
+}
+
+type LfscResult<T> = Result<T, LfscError>;
+
+/// Parses an LFSC proof of the given problem, and translates it into an Alethe proof. The symbols
+/// used in the proof must be declared in the problem.
+pub fn parse_proof<T: BufRead>(
+    mut input: T,
+    problem: &Problem,
+    pool: &mut dyn TermPool,
+) -> CarcaraResult<Proof> {
+    let mut src = String::new();
+    input.read_to_string(&mut src)?;
+    let proof = match read_sexprs(&src).map_err(LfscError::Syntax)?.as_slice() {
+        [SExpr::List(list)] => match list.as_slice() {
+            [SExpr::Atom(check), proof] if check == "check" => proof.clone(),
+            _ => return Err(LfscError::ExpectedCheck.into()),
+        },
+        _ => return Err(LfscError::ExpectedCheck.into()),
+    };
+
+    let symbols = problem
+        .prelude
+        .function_declarations
+        .iter()
+        .map(|(name, sort)| {
+            let var = pool.add(Term::new_var(name, sort.clone()));
+            (name.clone(), var)
+        })
+        .collect();
+    let mut translator = Translator {
+        pool,
+        symbols,
+        atoms: HashMap::new(),
+        proofs: HashMap::new(),
+        commands: Vec::new(),
+    };
+    translator.proof(&proof)?;
+    Ok(Proof {
+        constant_definitions: Vec::new(),
+        commands: translator.commands,
+    })
+}
+
+struct Translator<'a> {
+    pool: &'a mut dyn TermPool,
+
+    /// The functions and constants declared in the problem.
+    symbols: HashMap<String, Rc<Term>>,
+
+    /// The formulas of the atoms introduced by `decl_atom`.
+    atoms: HashMap<String, Rc<Term>>,
+
+    /// The index of the command that concludes each named assumption or lemma.
+    proofs: HashMap<String, usize>,
+
+    commands: Vec<ProofCommand>,
+}
+
+fn malformed(e: &SExpr) -> LfscError {
+    LfscError::MalformedProof(e.to_string())
+}
+
+/// If the expression is a lambda abstraction `(\ x body)`, returns `x` and `body`.
+fn match_lambda(e: &SExpr) -> LfscResult<(&str, &SExpr)> {
+    match e {
+        SExpr::List(list) => match list.as_slice() {
+            [SExpr::Atom(lambda), SExpr::Atom(var), body] if lambda == "\\" => Ok((var, body)),
+            _ => Err(malformed(e)),
+        },
+        SExpr::Atom(_) => Err(malformed(e)),
+    }
+}
+
+/// Parses a numeral, which may be negated with `~`, as in `(~ 5)`.
+fn parse_number<T: FromStr + std::ops::Neg<Output = T>>(e: &SExpr) -> LfscResult<T> {
+    let err = || LfscError::UnsupportedTerm(e.to_string());
+    match e {
+        SExpr::Atom(a) => a.parse().map_err(|_| err()),
+        SExpr::List(list) => match list.as_slice() {
+            [SExpr::Atom(neg), inner] if neg == "~" => parse_number(inner).map(|n: T| -n),
+            _ => Err(err()),
+        },
+    }
+}
+
+impl Translator<'_> {
+    fn push_step(
+        &mut self,
+        clause: Vec<Rc<Term>>,
+        rule: &str,
+        premises: &[usize],
+        args: Vec<Rc<Term>>,
+    ) -> usize {
+        let index = self.commands.len();
+        self.commands.push(ProofCommand::Step(ProofStep {
+            id: format!("t{}", index + 1),
+            clause,
+            rule: rule.to_owned(),
+            premises: premises.iter().map(|&i| (0, i)).collect(),
+            args,
+            discharge: Vec::new(),
+        }));
+        index
+    }
+
+    fn term(&mut self, e: &SExpr) -> LfscResult<Rc<Term>> {
+        let list = match e {
+            SExpr::Atom(a) if a == "true" => return Ok(self.pool.bool_true()),
+            SExpr::Atom(a) if a == "false" => return Ok(self.pool.bool_false()),
+            SExpr::Atom(a) => {
+                return self
+                    .symbols
+                    .get(a)
+                    .cloned()
+                    .ok_or_else(|| LfscError::UndefinedIden(a.clone()))
+            }
+            SExpr::List(list) => list.as_slice(),
+        };
+        let unsupported = || LfscError::UnsupportedTerm(e.to_string());
+        let [SExpr::Atom(head), args @ ..] = list else {
+            return Err(unsupported());
+        };
+
+        let term = match (head.as_str(), args) {
+            ("a_int", [n]) => Term::new_int(parse_number::<Integer>(n)?),
+            ("a_real", [r]) => Term::new_real(parse_number::<Rational>(r)?),
+            ("p_app", [t]) => return self.term(t),
+            ("=", [_, a, b]) => Term::Op(Operator::Equals, vec![self.term(a)?, self.term(b)?]),
+            ("ite", [_, c, a, b]) | ("ifte", [c, a, b]) => {
+                let args = vec![self.term(c)?, self.term(a)?, self.term(b)?];
+                Term::Op(Operator::Ite, args)
+            }
+            ("apply", [_, _, _, _]) => {
+                // Function applications are curried, so `(f a b)` is represented as
+                // `(apply _ _ (apply _ _ f a) b)`
+                let mut args = Vec::new();
+                let mut current = e;
+                while let SExpr::List(list) = current {
+                    match list.as_slice() {
+                        [SExpr::Atom(apply), _, _, f, x] if apply == "apply" => {
+                            args.push(x);
+                            current = f;
+                        }
+                        _ => break,
+                    }
+                }
+                let function = self.term(current)?;
+                let args = args
+                    .into_iter()
+                    .rev()
+                    .map(|a| self.term(a))
+                    .collect::<Result<_, _>>()?;
+                Term::App(function, args)
+            }
+            (op, args) => {
+                let op = match op.strip_suffix("_Int").or(op.strip_suffix("_Real")) {
+                    Some("+") => Operator::Add,
+                    Some("-" | "u-") => Operator::Sub,
+                    Some("*") => Operator::Mult,
+                    Some("/") => Operator::RealDiv,
+                    Some("<") => Operator::LessThan,
+                    Some("<=") => Operator::LessEq,
+                    Some(">") => Operator::GreaterThan,
+                    Some(">=") => Operator::GreaterEq,
+                    Some(_) => return Err(unsupported()),
+                    None => match op {
+                        "not" => Operator::Not,
+                        "and" => Operator::And,
+                        "or" => Operator::Or,
+                        "impl" => Operator::Implies,
+                        "iff" => Operator::Equals,
+                        "xor" => Operator::Xor,
+                        _ => return Err(unsupported()),
+                    },
+                };
+                let args = args
+                    .iter()
+                    .map(|a| self.term(a))
+                    .collect::<Result<_, _>>()?;
+                Term::Op(op, args)
+            }
+        };
+        Ok(self.pool.add(term))
+    }
+
+    /// Translates the type of a proof, `(holds C)` or `(th_holds F)`, into an Alethe clause.
+    fn clause_of_type(&mut self, ty: &SExpr) -> LfscResult<Vec<Rc<Term>>> {
+        let SExpr::List(list) = ty else {
+            return Err(malformed(ty));
+        };
+        match list.as_slice() {
+            [SExpr::Atom(head), f] if head == "th_holds" => Ok(vec![self.term(f)?]),
+            [SExpr::Atom(head), clause] if head == "holds" => {
+                let mut clause = clause;
+                let mut result = Vec::new();
+                loop {
+                    match clause {
+                        SExpr::Atom(a) if a == "cln" => return Ok(result),
+                        SExpr::List(list) => match list.as_slice() {
+                            [SExpr::Atom(clc), literal, rest] if clc == "clc" => {
+                                result.push(self.literal(literal)?);
+                                clause = rest;
+                            }
+                            _ => return Err(malformed(ty)),
+                        },
+                        SExpr::Atom(_) => return Err(malformed(ty)),
+                    }
+                }
+            }
+            _ => Err(malformed(ty)),
+        }
+    }
+
+    /// Translates a literal `(pos v)` or `(neg v)`, where `v` is an atom.
+    fn literal(&mut self, e: &SExpr) -> LfscResult<Rc<Term>> {
+        let (polarity, atom) = match e {
+            SExpr::List(list) => match list.as_slice() {
+                [SExpr::Atom(p), SExpr::Atom(v)] if p == "pos" || p == "neg" => (p == "pos", v),
+                _ => return Err(malformed(e)),
+            },
+            SExpr::Atom(_) => return Err(malformed(e)),
+        };
+        let atom = self.atom(atom)?;
+        Ok(if polarity {
+            atom
+        } else {
+            build_term!(self.pool, (not { atom }))
+        })
+    }
+
+    fn atom(&self, name: &str) -> LfscResult<Rc<Term>> {
+        self.atoms
+            .get(name)
+            .cloned()
+            .ok_or_else(|| LfscError::UndefinedIden(name.to_owned()))
+    }
+
+    fn clause(&self, index: usize) -> &[Rc<Term>] {
+        self.commands[index].clause()
+    }
+
+    /// Translates a proof term, returning the index of the command that concludes it.
+    fn proof(&mut self, e: &SExpr) -> LfscResult<usize> {
+        let list = match e {
+            SExpr::Atom(name) => {
+                return self
+                    .proofs
+                    .get(name)
+                    .copied()
+                    .ok_or_else(|| LfscError::UndefinedIden(name.clone()))
+            }
+            SExpr::List(list) => list.as_slice(),
+        };
+        let [SExpr::Atom(head), args @ ..] = list else {
+            return Err(malformed(e));
+        };
+        match (head.as_str(), args) {
+            ("%", [SExpr::Atom(name), ty, body]) => {
+                match ty {
+                    SExpr::List(l) if matches!(l.first(), Some(SExpr::Atom(a)) if a == "term") => {}
+                    _ => {
+                        let [term]: [_; 1] = self
+                            .clause_of_type(ty)?
+                            .try_into()
+                            .map_err(|_| malformed(ty))?;
+                        self.proofs.insert(name.clone(), self.commands.len());
+                        self.commands
+                            .push(ProofCommand::Assume { id: name.clone(), term });
+                    }
+                }
+                self.proof(body)
+            }
+            (":", [ty, proof]) => match self.proof(proof) {
+                Err(LfscError::UnsupportedRule(rule)) => {
+                    tracing::debug!(rule, "translating unsupported LFSC rule as a hole");
+                    let clause = self.clause_of_type(ty)?;
+                    Ok(self.push_step(clause, "hole", &[], Vec::new()))
+                }
+                result => result,
+            },
+            ("decl_atom", [f, lambda]) => {
+                let formula = self.term(f)?;
+                let (var, inner) = match_lambda(lambda)?;
+                let (_, body) = match_lambda(inner)?;
+                self.atoms.insert(var.to_owned(), formula);
+                self.proof(body)
+            }
+            ("satlem" | "satlem_simplify", [.., lemma, lambda]) => {
+                let index = self.proof(lemma)?;
+                let (name, body) = match_lambda(lambda)?;
+                self.proofs.insert(name.to_owned(), index);
+                self.proof(body)
+            }
+            ("R" | "Q", [_, _, _, left, right, SExpr::Atom(var)]) => {
+                let (left, right) = (self.proof(left)?, self.proof(right)?);
+                let atom = self.atom(var)?;
+                let negated = build_term!(self.pool, (not {atom.clone()}));
+
+                // In `R`, the atom appears positively in the left clause, while in `Q` it appears
+                // negatively
+                let polarity = head == "R";
+                let (left_pivot, right_pivot) = if polarity {
+                    (&atom, &negated)
+                } else {
+                    (&negated, &atom)
+                };
+                let clause: IndexSet<_> = (self.clause(left).iter())
+                    .filter(|&l| l != left_pivot)
+                    .chain(self.clause(right).iter().filter(|&l| l != right_pivot))
+                    .cloned()
+                    .collect();
+                let args = vec![atom, self.pool.bool_constant(polarity)];
+                Ok(self.push_step(
+                    clause.into_iter().collect(),
+                    "resolution",
+                    &[left, right],
+                    args,
+                ))
+            }
+            ("contra", [_, p, not_p]) => {
+                let (p, not_p) = (self.proof(p)?, self.proof(not_p)?);
+                let [term] = self.clause(p) else {
+                    return Err(malformed(e));
+                };
+                let args = vec![term.clone(), self.pool.bool_true()];
+                Ok(self.push_step(Vec::new(), "resolution", &[p, not_p], args))
+            }
+            ("clausify_false", [p]) => {
+                let p = self.proof(p)?;
+                let false_term = self.pool.bool_false();
+                let not_false = build_term!(self.pool, (not {false_term.clone()}));
+                let false_step = self.push_step(vec![not_false], "false", &[], Vec::new());
+                let args = vec![false_term, self.pool.bool_true()];
+                Ok(self.push_step(Vec::new(), "resolution", &[p, false_step], args))
+            }
+            (rule, _) => Err(LfscError::UnsupportedRule(rule.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::pool::PrimitivePool, checker, parser};
+
+    /// Translates and checks an LFSC proof, returning the rules used in the translated proof.
+    fn check(problem: &str, proof: &str) -> CarcaraResult<Vec<String>> {
+        let mut pool = PrimitivePool::new();
+        let problem = parser::Parser::new(&mut pool, parser::Config::new(), problem.as_bytes())?
+            .parse_problem()?;
+        let proof = parse_proof(proof.as_bytes(), &problem, &mut pool)?;
+        checker::ProofChecker::new(&mut pool, checker::Config::new()).check(&problem, &proof)?;
+        let rules = proof.commands.iter().filter_map(|c| match c {
+            ProofCommand::Step(s) => Some(s.rule.clone()),
+            _ => None,
+        });
+        Ok(rules.collect())
+    }
+
+    #[test]
+    fn test_lfsc() {
+        let problem = "
+            (declare-fun p () Bool)
+            (declare-fun a () Int)
+            (declare-fun f (Int) Int)
+            (assert (or p (= (f a) 1)))
+            (assert (not p))
+            (assert (not (= (f a) 1)))
+        ";
+        let proof = "
+            (check
+            (% a (term Int)
+            (% A0 (th_holds (or (p_app p) (= Int (apply _ _ f a) (a_int 1))))
+            (% A1 (th_holds (not (p_app p)))
+            (% A2 (th_holds (not (= Int (apply _ _ f a) (a_int 1))))
+            (: (holds cln)
+            (decl_atom (p_app p) (\\ v1 (\\ a1
+            (decl_atom (= Int (apply _ _ f a) (a_int 1)) (\\ v2 (\\ a2
+            (satlem_simplify _ _ _
+                (: (holds (clc (pos v1) (clc (pos v2) cln))) (or_elim _ _ A0))
+                (\\ L1
+            (satlem_simplify _ _ _
+                (: (holds (clc (neg v1) cln)) (asf _ _ _ a1 A1))
+                (\\ L2
+            (satlem_simplify _ _ _
+                (: (holds (clc (neg v2) cln)) (asf _ _ _ a2 A2))
+                (\\ L3
+            (satlem_simplify _ _ _ (Q _ _ _ L3 (R _ _ _ L1 L2 v1) v2) (\\ empty empty))
+            ))))))))))))))))))
+        ";
+        // Rules that aren't supported become holes
+        let rules = check(problem, proof).unwrap();
+        assert_eq!(rules, ["hole", "hole", "hole", "resolution", "resolution"]);
+
+        let problem = "(declare-fun p () Bool) (assert p) (assert (not p))";
+        let proof = "
+            (check (% A1 (th_holds (not (p_app p))) (% A2 (th_holds (p_app p))
+            (: (holds cln) (contra _ A2 A1)))))
+        ";
+        assert_eq!(check(problem, proof).unwrap(), ["resolution"]);
+
+        for invalid in [
+            "(% A1 (th_holds (p_app p)) (: (holds cln) A1))",
+            "(check (% A1 (th_holds (p_app q)) A1))",
+            "(check (% A1 (th_holds (p_app p)) (unknown_rule A1)))",
+            "(check (% A1 (th_holds (p_app p)) (contra _ A1",
+            "(check (% A1 (th_holds (p_app p)) A1))",
+        ] {
+            assert!(check(problem, invalid).is_err(), "{}", invalid);
+        }
+    }
+}
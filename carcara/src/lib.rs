@@ -38,17 +38,45 @@
 pub mod ast;
 pub mod benchmarking;
 pub mod checker;
+pub mod drat;
+#[cfg(feature = "elaborator")]
 pub mod elaborator;
+pub mod export;
+pub mod golden;
+pub mod instrumentation;
+pub mod lfsc;
+pub mod limits;
+pub mod lint;
 pub mod parser;
 mod resolution;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod slicer;
+pub mod statistics;
+pub mod term_trace;
+pub mod trace_map;
+pub mod transformation;
+#[cfg(feature = "external-solver")]
+pub mod unsat_core;
 mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(all(
+    target_arch = "wasm32",
+    target_os = "unknown",
+    feature = "external-solver"
+))]
+compile_error!("the `external-solver` feature is not supported on `wasm32-unknown-unknown`");
 
 use crate::benchmarking::{CollectResults, OnlineBenchmarkResults, RunMeasurement};
 use checker::{error::CheckerError, CheckerStatistics};
+use instrumentation::{PipelineListener, Stage, StageReport};
 use parser::{ParserError, Position};
-use std::io;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+use std::{fs, io, path::Path};
 use thiserror::Error;
+use utils::Instant;
 
 pub type CarcaraResult<T> = Result<T, Error>;
 
@@ -61,6 +89,21 @@ fn wrap_parser_error_message(e: &ParserError, pos: &Position) -> String {
     }
 }
 
+fn format_checker_error_location(location: Option<&parser::CommandLocation>) -> String {
+    match location {
+        Some(l) => format!(" (on line {}, column {})", l.span.start.0, l.span.start.1),
+        None => String::new(),
+    }
+}
+
+fn format_rejected_steps(steps: &[(String, String)]) -> String {
+    let steps: Vec<_> = steps
+        .iter()
+        .map(|(step, rule)| format!("'{}' ({})", step, rule))
+        .collect();
+    steps.join(", ")
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("IO error: {0}")]
@@ -69,17 +112,73 @@ pub enum Error {
     #[error("{}", wrap_parser_error_message(.0, .1))]
     Parser(ParserError, Position),
 
-    #[error("checking failed on step '{step}' with rule '{rule}': {inner}")]
+    #[error(
+        "checking failed on step '{step}' with rule '{rule}'{}: {inner}",
+        format_checker_error_location(location.as_deref()),
+    )]
     Checker {
         inner: CheckerError,
         rule: String,
         step: String,
+
+        /// The location of the failing step in the proof file, if locations were tracked by the
+        /// parser. See [`parser::Config::track_locations`].
+        location: Option<Box<parser::CommandLocation>>,
     },
 
     // While this is a kind of checking error, it does not happen in a specific step like all other
     // checker errors, so we model it as a different variant
     #[error("checker error: proof does not conclude empty clause")]
     DoesNotReachEmptyClause,
+
+    #[error("checker error: proof does not reach the expected conclusion")]
+    DoesNotReachExpectedConclusion,
+
+    /// The proof has steps whose rules are not accepted by the checker configuration. Each entry
+    /// holds the id of an offending step and its rule.
+    #[error(
+        "checker error: proof uses rules that are not allowed: {}",
+        format_rejected_steps(.0)
+    )]
+    RejectedRules(Vec<(String, String)>),
+
+    #[error("size limit exceeded: {0}")]
+    SizeLimit(#[from] limits::SizeLimitError),
+
+    #[error("LFSC error: {0}")]
+    Lfsc(#[from] lfsc::LfscError),
+
+    #[error("DRAT error: {0}")]
+    Drat(#[from] drat::DratError),
+
+    #[error("export error: {0}")]
+    Export(#[from] export::ExportError),
+}
+
+impl Error {
+    /// If this is a checker error, attaches to it the location of the failing step, if it is in the
+    /// source map.
+    pub fn with_location(self, source_map: &parser::SourceMap) -> Self {
+        match self {
+            Error::Checker { inner, rule, step, location: None } => {
+                let location = source_map.get(&step).cloned().map(Box::new);
+                Error::Checker { inner, rule, step, location }
+            }
+            other => other,
+        }
+    }
+}
+
+/// Notifies the listener, if there is one, that a stage of the pipeline finished.
+fn report_stage(
+    listener: &mut Option<&mut dyn PipelineListener>,
+    stage: Stage,
+    pool: &ast::PrimitivePool,
+    proof: &ast::Proof,
+) {
+    if let Some(listener) = listener {
+        listener.stage_finished(&StageReport::measure(stage, pool, proof));
+    }
 }
 
 pub fn check<T: io::BufRead>(
@@ -88,18 +187,40 @@ pub fn check<T: io::BufRead>(
     parser_config: parser::Config,
     checker_config: checker::Config,
     collect_stats: bool,
+) -> Result<bool, Error> {
+    check_with_listener(
+        problem,
+        proof,
+        parser_config,
+        checker_config,
+        collect_stats,
+        None,
+    )
+}
+
+/// Similar to [`check`], but notifies the listener after each stage of the pipeline finishes.
+pub fn check_with_listener<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+    collect_stats: bool,
+    mut listener: Option<&mut dyn PipelineListener>,
 ) -> Result<bool, Error> {
     let mut run_measures: RunMeasurement = RunMeasurement::default();
 
     // Parsing
     let total = Instant::now();
-    let (problem, proof, mut pool) = parser::parse_instance(problem, proof, parser_config)?;
+    let (problem, proof, mut pool, source_map) =
+        parser::parse_instance_with_locations(problem, proof, parser_config)?;
     run_measures.parsing = total.elapsed();
+    run_measures.term_nodes = pool.len();
+    report_stage(&mut listener, Stage::Parse, &pool, &proof);
 
     // Checking
     let checking = Instant::now();
     let mut checker = checker::ProofChecker::new(&mut pool, checker_config);
-    if collect_stats {
+    let result = if collect_stats {
         let mut checker_stats = CheckerStatistics {
             file_name: "this",
             polyeq_time: Duration::ZERO,
@@ -124,6 +245,7 @@ pub fn check<T: io::BufRead>(
                 assume: checker_stats.assume_time,
                 assume_core: checker_stats.assume_core_time,
                 elaboration_pipeline: Vec::new(),
+                term_nodes: run_measures.term_nodes,
             },
         );
         // Print the statistics
@@ -132,9 +254,154 @@ pub fn check<T: io::BufRead>(
         res
     } else {
         checker.check(&problem, &proof)
+    };
+    report_stage(&mut listener, Stage::Check, &pool, &proof);
+    result.map_err(|e| e.with_location(&source_map))
+}
+
+/// Checks a proof that is expected to conclude the given clause, instead of the empty clause. The
+/// expected conclusion is read as a clause of the form `(cl <term>*)`, after the problem and proof
+/// are parsed. A warning is logged if some commands are not needed to derive the conclusion.
+pub fn check_with_expected_conclusion<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    expected_conclusion: T,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+) -> Result<bool, Error> {
+    let mut pool = ast::PrimitivePool::new();
+    let mut parser = parser::Parser::new(&mut pool, parser_config, problem)?;
+    let problem = parser.parse_problem()?;
+    parser.reset(proof)?;
+    let proof = parser.parse_proof()?;
+    parser.reset(expected_conclusion)?;
+    let expected = parser.parse_clause()?;
+
+    let analysis = checker::roots::analyze_roots(&proof, &expected);
+    if analysis.root.is_some() && !analysis.is_connected() {
+        tracing::warn!(
+            "{} commands are not needed to derive the conclusion, in {} disconnected components",
+            analysis.disconnected.len(),
+            analysis.disconnected_roots.len(),
+        );
     }
+
+    let checker_config = checker_config.expected_conclusion(Some(expected));
+    checker::ProofChecker::new(&mut pool, checker_config).check(&problem, &proof)
+}
+
+/// Checks a proof against a problem given as a list of named hypotheses, instead of a problem file.
+/// The symbols used in the hypotheses and in the proof are declared in `declarations`, which is
+/// parsed like a problem file. The hypotheses are the premises that `assume` commands are matched
+/// against.
+pub fn check_with_hypotheses<T: io::BufRead>(
+    declarations: T,
+    hypotheses: &[parser::NamedHypothesis],
+    proof: T,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+) -> Result<bool, Error> {
+    let (problem, proof, mut pool) =
+        parser::parse_instance_with_hypotheses(declarations, hypotheses, proof, parser_config)?;
+    checker::ProofChecker::new(&mut pool, checker_config).check(&problem, &proof)
 }
 
+/// Checks a proof that uses symbols introduced by a satisfiability-preserving preprocessing step,
+/// like the fresh variables of definitional CNF. These symbols are defined in `definitions`, as a
+/// list of `define-fun` commands, and each definition is checked to be a valid extension of the
+/// problem. The proof may then assume the equality between each symbol and its definition.
+pub fn check_with_definitions<T: io::BufRead>(
+    problem: T,
+    definitions: T,
+    proof: T,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+) -> Result<bool, Error> {
+    let (problem, proof, mut pool) =
+        parser::parse_instance_with_definitions(problem, definitions, proof, parser_config)?;
+    checker::ProofChecker::new(&mut pool, checker_config).check(&problem, &proof)
+}
+
+/// Checks a proof in the LFSC format, by first translating it into an Alethe proof. See the
+/// [`lfsc`] module for the subset of LFSC that is supported.
+pub fn check_lfsc<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+) -> Result<bool, Error> {
+    let mut pool = ast::PrimitivePool::new();
+    let problem = parser::Parser::new(&mut pool, parser_config, problem)?.parse_problem()?;
+    let proof = lfsc::parse_proof(proof, &problem, &mut pool)?;
+    checker::ProofChecker::new(&mut pool, checker_config).check(&problem, &proof)
+}
+
+/// Checks a DRAT or LRAT proof of unsatisfiability of a propositional problem, given in the DIMACS
+/// CNF format.
+pub fn check_drat<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    format: drat::ProofFormat,
+) -> Result<bool, Error> {
+    drat::check(problem, proof, format)?;
+    Ok(false)
+}
+
+/// Checks a proof in the most permissive mode, and reports every step that is only valid because
+/// of a leniency of the checker, such as implicit reordering of equalities or unknown rules.
+pub fn check_leniencies<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+) -> Result<(bool, checker::leniency::LeniencyReport), Error> {
+    let (problem, proof, mut pool) = parser::parse_instance(problem, proof, parser_config)?;
+    checker::ProofChecker::new(&mut pool, checker_config).check_leniencies(&problem, &proof)
+}
+
+/// Checks a proof, periodically saving the progress to a checkpoint file. If the checkpoint file
+/// already exists, checking resumes from it, skipping the commands that were already checked. Once
+/// checking finishes, the checkpoint file is removed.
+pub fn check_resumable<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+    checkpoint_path: &Path,
+    interval: Duration,
+) -> Result<bool, Error> {
+    use checker::checkpoint::Checkpoint;
+
+    let (problem, proof, mut pool) = parser::parse_instance(problem, proof, parser_config)?;
+    let resume = match Checkpoint::load(checkpoint_path) {
+        Ok(c) => Some(c),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(c) = &resume {
+        tracing::info!(
+            "resuming from checkpoint after {} commands",
+            c.checked_commands
+        );
+    }
+
+    let result = checker::ProofChecker::new(&mut pool, checker_config).check_resumable(
+        &problem,
+        &proof,
+        resume.as_ref(),
+        interval,
+        |c| c.save(checkpoint_path),
+    );
+    if !matches!(result, Err(Error::Io(_))) {
+        match fs::remove_file(checkpoint_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => (),
+        }
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn check_parallel<T: io::BufRead>(
     problem: T,
     proof: T,
@@ -143,6 +410,7 @@ pub fn check_parallel<T: io::BufRead>(
     collect_stats: bool,
     num_threads: usize,
     stack_size: usize,
+    cost_model: &checker::CostModel,
 ) -> Result<bool, Error> {
     use crate::checker::Scheduler;
     use std::sync::Arc;
@@ -152,10 +420,11 @@ pub fn check_parallel<T: io::BufRead>(
     let total = Instant::now();
     let (problem, proof, pool) = parser::parse_instance(problem, proof, parser_config)?;
     run_measures.parsing = total.elapsed();
+    run_measures.term_nodes = pool.len();
 
     // Checking
     let checking = Instant::now();
-    let (scheduler, schedule_context_usage) = Scheduler::new(num_threads, &proof);
+    let (scheduler, schedule_context_usage) = Scheduler::new(num_threads, &proof, cost_model);
     run_measures.scheduling = checking.elapsed();
     let mut checker = checker::ParallelProofChecker::new(
         Arc::new(pool),
@@ -190,6 +459,7 @@ pub fn check_parallel<T: io::BufRead>(
                 assume: checker_stats.assume_time,
                 assume_core: checker_stats.assume_core_time,
                 elaboration_pipeline: Vec::new(),
+                term_nodes: run_measures.term_nodes,
             },
         );
         // Print the statistics
@@ -201,6 +471,125 @@ pub fn check_parallel<T: io::BufRead>(
     }
 }
 
+/// Checks a proof without keeping all of its commands in memory, freeing the commands and terms
+/// that are no longer needed as the proof is checked. This is useful for very long proofs, where
+/// keeping the whole proof in memory may be prohibitive.
+///
+/// Since the proof is read twice, this receives a function that opens the proof, instead of the
+/// proof itself.
+pub fn check_streaming<T: io::BufRead>(
+    problem: T,
+    mut open_proof: impl FnMut() -> io::Result<T>,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+) -> Result<bool, Error> {
+    let mut pool = ast::PrimitivePool::new();
+    let mut parser = parser::Parser::new(&mut pool, parser_config, problem)?;
+    let problem = parser.parse_problem()?;
+
+    // In the first pass, we only record where each command is last used
+    parser.reset(open_proof()?)?;
+    let mut last_uses = checker::LastUses::new();
+    parser.parse_proof_streaming(|pool, command| {
+        last_uses.add_command(pool, command);
+        Ok(())
+    })?;
+
+    parser.reset(open_proof()?)?;
+    let mut checker = checker::StreamingChecker::new(&problem, checker_config, last_uses);
+    parser.parse_proof_streaming(|pool, command| checker.check_command(pool, command))?;
+    checker.finish()
+}
+
+/// Similar to [`check_streaming`], but reads the proof only once. This can be used when the proof
+/// can't be reopened, for example when reading from stdin.
+///
+/// While the first pass records where each command is last used, the proof is copied to a
+/// temporary file, from which it is read in the second pass. This way, every command is still
+/// dropped as soon as it is no longer used.
+pub fn check_streaming_single_pass<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+) -> Result<bool, Error> {
+    let copy = TempFile::new("proof");
+    let tee = Tee {
+        input: proof,
+        copy: io::BufWriter::new(fs::File::create(&copy.0)?),
+    };
+
+    let mut pool = ast::PrimitivePool::new();
+    let problem: Box<dyn io::BufRead + '_> = Box::new(problem);
+    let mut parser = parser::Parser::new(&mut pool, parser_config, problem)?;
+    let problem = parser.parse_problem()?;
+
+    parser.reset(Box::new(io::BufReader::new(tee)))?;
+    let mut last_uses = checker::LastUses::new();
+    parser.parse_proof_streaming(|pool, command| {
+        last_uses.add_command(pool, command);
+        Ok(())
+    })?;
+
+    parser.reset(Box::new(io::BufReader::new(fs::File::open(&copy.0)?)))?;
+    let mut checker = checker::StreamingChecker::new(&problem, checker_config, last_uses);
+    parser.parse_proof_streaming(|pool, command| checker.check_command(pool, command))?;
+    checker.finish()
+}
+
+/// A reader that copies everything it reads to a writer. The writer is flushed once the end of the
+/// input is reached.
+struct Tee<R, W: io::Write> {
+    input: R,
+    copy: W,
+}
+
+impl<R: io::Read, W: io::Write> io::Read for Tee<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.input.read(buf)?;
+        if n == 0 {
+            self.copy.flush()?;
+        } else {
+            self.copy.write_all(&buf[..n])?;
+        }
+        Ok(n)
+    }
+}
+
+/// A path to a new file in the temporary directory, which is removed when this is dropped.
+struct TempFile(std::path::PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let file_name = format!("carcara-{}-{}-{}", name, std::process::id(), n);
+        Self(std::env::temp_dir().join(file_name))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(feature = "elaborator")]
+/// The result of checking and elaborating a proof: whether the proof is holey, the problem, the
+/// elaborated proof, the term pool, the trace map, if tracing was enabled, and the renamings of
+/// bound variables done during elaboration.
+pub type ElaborationResult = (
+    bool,
+    ast::Problem,
+    ast::Proof,
+    ast::PrimitivePool,
+    Option<elaborator::TraceMap>,
+    Vec<(String, String)>,
+);
+
+#[cfg(feature = "elaborator")]
 pub fn check_and_elaborate<T: io::BufRead>(
     problem: T,
     proof: T,
@@ -209,18 +598,49 @@ pub fn check_and_elaborate<T: io::BufRead>(
     elaborator_config: elaborator::Config,
     pipeline: Vec<elaborator::ElaborationStep>,
     collect_stats: bool,
-) -> Result<(bool, ast::Problem, ast::Proof, ast::PrimitivePool), Error> {
+) -> Result<ElaborationResult, Error> {
+    check_and_elaborate_with_listener(
+        problem,
+        proof,
+        parser_config,
+        checker_config,
+        elaborator_config,
+        pipeline,
+        collect_stats,
+        None,
+    )
+}
+
+#[cfg(feature = "elaborator")]
+/// Similar to [`check_and_elaborate`], but notifies the listener after each stage of the pipeline
+/// finishes.
+#[allow(clippy::too_many_arguments)]
+pub fn check_and_elaborate_with_listener<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+    elaborator_config: elaborator::Config,
+    pipeline: Vec<elaborator::ElaborationStep>,
+    collect_stats: bool,
+    mut listener: Option<&mut dyn PipelineListener>,
+) -> Result<ElaborationResult, Error> {
     let mut run: RunMeasurement = RunMeasurement::default();
 
     // Parsing
     let total = Instant::now();
-    let (problem, proof, mut pool) = parser::parse_instance(problem, proof, parser_config)?;
+    let (problem, proof, mut pool, source_map) =
+        parser::parse_instance_with_locations(problem, proof, parser_config)?;
     run.parsing = total.elapsed();
+    run.term_nodes = pool.len();
+    report_stage(&mut listener, Stage::Parse, &pool, &proof);
 
     let mut stats = OnlineBenchmarkResults::new();
 
     // Checking
     let checking = Instant::now();
+    let target = elaborator_config.target;
+    let target_checker_config = checker_config.clone();
     let mut checker = checker::ProofChecker::new(&mut pool, checker_config);
     let checking_result = if collect_stats {
         let mut checker_stats = CheckerStatistics {
@@ -241,19 +661,30 @@ pub fn check_and_elaborate<T: io::BufRead>(
         res
     } else {
         checker.check(&problem, &proof)
-    }?;
+    }
+    .map_err(|e| e.with_location(&source_map))?;
+    report_stage(&mut listener, Stage::Check, &pool, &proof);
 
     // Elaborating
     let elaboration = Instant::now();
 
     let node = ast::ProofNode::from_commands(proof.commands);
-    let (elaborated, pipeline_durations) =
-        elaborator::Elaborator::new(&mut pool, &problem, elaborator_config)
-            .elaborate_with_stats(&node, pipeline);
+    let mut elaborator = elaborator::Elaborator::new(&mut pool, &problem, elaborator_config);
+    let (elaborated, pipeline_durations) = elaborator.elaborate_with_stats(&node, pipeline)?;
+    let trace = elaborator.take_trace();
+    let renamings = elaborator.take_rename_log();
     let elaborated = ast::Proof {
         commands: elaborated.into_commands(),
         ..proof
     };
+    report_stage(&mut listener, Stage::Elaborate, &pool, &elaborated);
+    check_elaboration_target(
+        &mut pool,
+        &problem,
+        &elaborated,
+        target_checker_config,
+        target,
+    )?;
 
     if collect_stats {
         run.elaboration = elaboration.elapsed();
@@ -265,9 +696,52 @@ pub fn check_and_elaborate<T: io::BufRead>(
         stats.print(false);
     }
 
-    Ok((checking_result, problem, elaborated, pool))
+    Ok((checking_result, problem, elaborated, pool, trace, renamings))
+}
+
+#[cfg(feature = "elaborator")]
+/// Checks that an elaborated proof satisfies the constraints of the given elaboration target. See
+/// [`elaborator::ElaborationTarget`] for more details.
+fn check_elaboration_target(
+    pool: &mut ast::PrimitivePool,
+    problem: &ast::Problem,
+    proof: &ast::Proof,
+    checker_config: checker::Config,
+    target: elaborator::ElaborationTarget,
+) -> Result<(), Error> {
+    if target == elaborator::ElaborationTarget::Alethe {
+        return Ok(());
+    }
+
+    for command in proof.iter() {
+        let ast::ProofCommand::Step(step) = command else {
+            continue;
+        };
+        if step.rule != "la_generic" {
+            continue;
+        }
+        if let Some(arg) = step
+            .args
+            .iter()
+            .find(|a| !elaborator::is_integer_coefficient(a))
+        {
+            return Err(Error::Checker {
+                inner: CheckerError::ExpectedAnyInteger(arg.clone()),
+                rule: step.rule.clone(),
+                step: step.id.clone(),
+                location: None,
+            });
+        }
+    }
+
+    // Checking the proof again in elaborated mode ensures that there is no implicit reordering,
+    // and that all resolution steps have explicit pivots
+    let config = checker_config.elaborated(true);
+    checker::ProofChecker::new(pool, config).check(problem, proof)?;
+    Ok(())
 }
 
+#[cfg(feature = "smt-printer")]
 pub fn generate_lia_smt_instances<T: io::BufRead>(
     problem: T,
     proof: T,
@@ -283,7 +757,7 @@ pub fn generate_lia_smt_instances<T: io::BufRead>(
         if let ast::ProofCommand::Step(step) = command {
             if step.rule == "lia_generic" {
                 if iter.depth() > 0 {
-                    log::error!(
+                    tracing::error!(
                         "generating SMT instance for step inside subproof is not supported"
                     );
                     continue;
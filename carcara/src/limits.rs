@@ -0,0 +1,157 @@
+//! Limits on the size of proofs, used to reject inputs that are too large before they exhaust the
+//! available memory.
+
+use crate::ast::{PrimitivePool, ProofNode, Rc};
+use thiserror::Error;
+
+/// An error caused by a proof exceeding one of the configured [`SizeLimits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum SizeLimitError {
+    #[error("proof has more than {0} commands")]
+    TooManyCommands(usize),
+
+    #[error("term pool has more than {0} terms")]
+    TooManyTerms(usize),
+}
+
+/// The maximum sizes allowed for a proof. A limit of `None` means that there is no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeLimits {
+    /// The maximum number of proof commands, including the commands inside subproofs.
+    pub max_commands: Option<usize>,
+
+    /// The maximum number of distinct terms stored in the term pool. Since terms are hash consed,
+    /// this is the number of term nodes needed to represent every term in the problem and proof.
+    pub max_term_nodes: Option<usize>,
+}
+
+impl SizeLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_commands(mut self, value: Option<usize>) -> Self {
+        self.max_commands = value;
+        self
+    }
+
+    pub fn max_term_nodes(mut self, value: Option<usize>) -> Self {
+        self.max_term_nodes = value;
+        self
+    }
+
+    /// Returns an error if `num_commands` exceeds the maximum number of commands.
+    pub fn check_commands(&self, num_commands: usize) -> Result<(), SizeLimitError> {
+        match self.max_commands {
+            Some(max) if num_commands > max => Err(SizeLimitError::TooManyCommands(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns an error if the number of terms in the pool exceeds the maximum number of term
+    /// nodes.
+    pub fn check_pool(&self, pool: &PrimitivePool) -> Result<(), SizeLimitError> {
+        match self.max_term_nodes {
+            Some(max) if pool.len() > max => Err(SizeLimitError::TooManyTerms(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks both limits against a proof, represented as a `ProofNode`, and the pool that holds
+    /// its terms.
+    pub fn check_proof(
+        &self,
+        pool: &PrimitivePool,
+        root: &Rc<ProofNode>,
+    ) -> Result<(), SizeLimitError> {
+        if self.max_commands.is_some() {
+            let mut num_commands = 0;
+            root.traverse(|node| {
+                if !node.is_subproof() {
+                    num_commands += 1;
+                }
+            });
+            self.check_commands(num_commands)?;
+        }
+        self.check_pool(pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::ProofCommand, parser};
+
+    #[test]
+    fn test_size_limits() {
+        let problem: &[u8] = b"
+            (declare-const p Bool)
+            (assert (not (or p (not p))))
+        ";
+        let proof: &[u8] = b"
+            (assume h1 (not (or p (not p))))
+            (anchor :step t3)
+            (assume t3.h1 p)
+            (step t3 (cl (not p) p) :rule subproof)
+            (step t4 (cl (or p (not p))) :rule hole :premises (t3))
+            (step t5 (cl) :rule resolution :premises (h1 t4))
+        ";
+        let parse = |limits: SizeLimits| {
+            let config = parser::Config { limits, ..parser::Config::new() };
+            parser::parse_instance(problem, proof, config)
+        };
+
+        let (_, proof, pool) = parse(SizeLimits::new()).unwrap();
+        let num_terms = pool.len();
+        assert!(parse(SizeLimits::new().max_commands(Some(5))).is_ok());
+        assert!(parse(SizeLimits::new().max_term_nodes(Some(num_terms))).is_ok());
+        assert!(matches!(
+            parse(SizeLimits::new().max_commands(Some(4))),
+            Err(crate::Error::Parser(
+                parser::ParserError::SizeLimit(SizeLimitError::TooManyCommands(4)),
+                _
+            ))
+        ));
+        assert!(matches!(
+            parse(SizeLimits::new().max_term_nodes(Some(num_terms - 1))),
+            Err(crate::Error::Parser(
+                parser::ParserError::SizeLimit(SizeLimitError::TooManyTerms(_)),
+                _
+            ))
+        ));
+
+        // The limit is checked while parsing each term, so the error is found in the middle of the
+        // command, while expanding the `let`
+        let problem: &[u8] = b"(declare-const p Bool)
+            (assert (let ((a (and p p)))
+                (or a
+                    (not a)
+                    (and a (not p))
+                    (or a (not (not p))))))
+        ";
+        let config = parser::Config {
+            expand_lets: true,
+            limits: SizeLimits::new().max_term_nodes(Some(4)),
+            ..parser::Config::new()
+        };
+        match parser::parse_instance(problem, b"", config) {
+            Err(crate::Error::Parser(parser::ParserError::SizeLimit(_), (line, _))) => {
+                assert!(line < 6);
+            }
+            other => panic!("{:?}", other.map(|_| ())),
+        }
+
+        let node = ProofNode::from_commands(proof.commands.clone());
+        let num_commands = proof
+            .iter()
+            .filter(|c| !matches!(c, ProofCommand::Subproof(_)))
+            .count();
+        let limits = SizeLimits::new().max_commands(Some(num_commands));
+        assert_eq!(Ok(()), limits.check_proof(&pool, &node));
+        let limits = SizeLimits::new().max_commands(Some(num_commands - 1));
+        assert_eq!(
+            Err(SizeLimitError::TooManyCommands(num_commands - 1)),
+            limits.check_proof(&pool, &node)
+        );
+    }
+}
@@ -0,0 +1,418 @@
+//! A linter for proofs, which detects structural and stylistic issues. These issues don't make a
+//! proof invalid, but usually indicate that it could be smaller or simpler. Each issue is detected
+//! by a named lint, that can be allowed, reported as a warning, or denied.
+
+use crate::{
+    ast::*,
+    checker::{metadata, roots},
+};
+use std::{collections::HashMap, fmt, str::FromStr};
+
+/// A check done by the linter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Lint {
+    /// A step that is not used, directly or indirectly, to derive the conclusion of the proof, or
+    /// of the subproof it is in.
+    UnusedStep,
+
+    /// A step whose conclusion is identical to the conclusion of an earlier command in the same
+    /// subproof.
+    DuplicateStep,
+
+    /// A step that can be trivially simplified together with its premise, like two consecutive
+    /// `symm` steps, or a `not_not` step that only removes one of several nested double negations.
+    SimplifiableChain,
+
+    /// A step with no premises, whose rule requires at least one premise.
+    MissingPremises,
+
+    /// A `resolution` or `th_resolution` step with more premises than a configured limit.
+    LongResolution,
+}
+
+impl Lint {
+    /// All the lints, in the order in which they are described.
+    pub const ALL: [Lint; 5] = [
+        Lint::UnusedStep,
+        Lint::DuplicateStep,
+        Lint::SimplifiableChain,
+        Lint::MissingPremises,
+        Lint::LongResolution,
+    ];
+
+    /// The name of the lint, used to allow or deny it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedStep => "unused-step",
+            Lint::DuplicateStep => "duplicate-step",
+            Lint::SimplifiableChain => "simplifiable-chain",
+            Lint::MissingPremises => "missing-premises",
+            Lint::LongResolution => "long-resolution",
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Lint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Lint::ALL
+            .into_iter()
+            .find(|l| l.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Lint::ALL.iter().map(|l| l.name()).collect();
+                format!(
+                    "unknown lint '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// How an issue detected by a lint is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum LintLevel {
+    /// The issue is not reported.
+    Allow,
+
+    /// The issue is reported as a warning.
+    #[default]
+    Warn,
+
+    /// The issue is reported as an error.
+    Deny,
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warning",
+            LintLevel::Deny => "error",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    /// The level of each lint. Lints that are not in this map are reported as warnings.
+    pub levels: HashMap<Lint, LintLevel>,
+
+    /// The maximum number of premises a `resolution` or `th_resolution` step can have before the
+    /// `long-resolution` lint is triggered.
+    pub max_resolution_premises: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            levels: HashMap::new(),
+            max_resolution_premises: 64,
+        }
+    }
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lint_level(mut self, lint: Lint, level: LintLevel) -> Self {
+        self.levels.insert(lint, level);
+        self
+    }
+
+    pub fn max_resolution_premises(mut self, value: usize) -> Self {
+        self.max_resolution_premises = value;
+        self
+    }
+
+    /// Returns the level of the given lint.
+    pub fn level_of(&self, lint: Lint) -> LintLevel {
+        self.levels.get(&lint).copied().unwrap_or_default()
+    }
+}
+
+/// An issue found by the linter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LintDiagnostic {
+    pub lint: Lint,
+    pub level: LintLevel,
+
+    /// The id of the step in which the issue was found.
+    pub step: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}: {}",
+            self.level, self.lint, self.step, self.message
+        )
+    }
+}
+
+/// Lints a proof, returning the issues found by the lints that are not allowed, in the order of
+/// the steps in which they were found.
+pub fn lint_proof(proof: &Proof, config: &LintConfig) -> Vec<LintDiagnostic> {
+    let mut linter = Linter {
+        config,
+        scopes: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let root = roots::analyze_roots(proof, &[])
+        .root
+        .or_else(|| proof.commands.len().checked_sub(1));
+    linter.lint_commands(&proof.commands, root);
+    linter.diagnostics
+}
+
+/// Collects the indices of the commands in the subproof at the given depth that are used as
+/// premises by a command, including those used by the commands inside it, if it is a subproof.
+fn premises_at_depth(command: &ProofCommand, depth: usize, result: &mut Vec<usize>) {
+    match command {
+        ProofCommand::Assume { .. } => (),
+        ProofCommand::Step(s) => {
+            let all = s.premises.iter().chain(&s.discharge);
+            result.extend(all.filter(|(d, _)| *d == depth).map(|&(_, i)| i));
+        }
+        ProofCommand::Subproof(s) => {
+            for c in &s.commands {
+                premises_at_depth(c, depth, result);
+            }
+        }
+    }
+}
+
+/// Rules for which two consecutive steps can be merged into a single step.
+const MERGEABLE_RULES: &[&str] = &["contraction", "reordering", "weakening"];
+
+/// Rules for which two consecutive steps cancel each other out.
+const SELF_INVERSE_RULES: &[&str] = &["symm", "not_symm"];
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+
+    /// The commands of each enclosing subproof, used to find the premises of a step.
+    scopes: Vec<&'a [ProofCommand]>,
+    diagnostics: Vec<LintDiagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, lint: Lint, step: &str, message: String) {
+        let level = self.config.level_of(lint);
+        if level != LintLevel::Allow {
+            self.diagnostics.push(LintDiagnostic {
+                lint,
+                level,
+                step: step.to_owned(),
+                message,
+            });
+        }
+    }
+
+    /// Lints the commands of the proof or of a subproof. `root` is the index of the command that
+    /// concludes it, if any.
+    fn lint_commands(&mut self, commands: &'a [ProofCommand], root: Option<usize>) {
+        let depth = self.scopes.len();
+        self.scopes.push(commands);
+
+        let mut is_used = vec![root.is_none(); commands.len()];
+        let mut stack: Vec<_> = root.into_iter().collect();
+        let mut premises = Vec::new();
+        while let Some(i) = stack.pop() {
+            if is_used[i] {
+                continue;
+            }
+            is_used[i] = true;
+            premises.clear();
+            premises_at_depth(&commands[i], depth, &mut premises);
+            stack.extend(&premises);
+        }
+
+        let mut clauses: HashMap<&[Rc<Term>], &str> = HashMap::new();
+        for (i, command) in commands.iter().enumerate() {
+            if let ProofCommand::Subproof(s) = command {
+                self.lint_commands(&s.commands, s.commands.len().checked_sub(1));
+            }
+            if let ProofCommand::Step(s) = command {
+                self.lint_step(s);
+            }
+            if command.is_assume() {
+                clauses.entry(command.clause()).or_insert(command.id());
+                continue;
+            }
+            if !is_used[i] {
+                let message = if depth == 0 {
+                    "step is not used to derive the conclusion of the proof"
+                } else {
+                    "step is not used to derive the conclusion of the subproof"
+                };
+                self.report(Lint::UnusedStep, command.id(), message.to_owned());
+            }
+            match clauses.get(command.clause()) {
+                Some(first) => {
+                    let message = format!("step concludes the same clause as `{}`", first);
+                    self.report(Lint::DuplicateStep, command.id(), message);
+                }
+                None => {
+                    clauses.insert(command.clause(), command.id());
+                }
+            }
+        }
+        self.scopes.pop();
+    }
+
+    fn lint_step(&mut self, step: &ProofStep) {
+        let rule = step.rule.as_str();
+        if let [(depth, i)] = step.premises.as_slice() {
+            if let ProofCommand::Step(premise) = &self.scopes[*depth][*i] {
+                if premise.rule == rule && MERGEABLE_RULES.contains(&rule) {
+                    let message = format!(
+                        "`{}` step applied to the conclusion of another `{}` step, `{}`; they can \
+                        be merged into a single step",
+                        rule, rule, premise.id
+                    );
+                    self.report(Lint::SimplifiableChain, &step.id, message);
+                } else if premise.rule == rule && SELF_INVERSE_RULES.contains(&rule) {
+                    let message = format!(
+                        "`{}` step applied to the conclusion of another `{}` step, `{}`; the two \
+                        steps cancel each other out",
+                        rule, rule, premise.id
+                    );
+                    self.report(Lint::SimplifiableChain, &step.id, message);
+                }
+            }
+        }
+        if rule == "not_not" {
+            if let [_, p] = step.clause.as_slice() {
+                if match_term!((not (not q)) = p).is_some() {
+                    let message = "`not_not` step concludes a term that is itself a double \
+                        negation, which needs another `not_not` step to be removed"
+                        .to_owned();
+                    self.report(Lint::SimplifiableChain, &step.id, message);
+                }
+            }
+        }
+
+        if step.premises.is_empty() {
+            let premises = metadata::rule_metadata(rule).map(|r| r.premises);
+            if let Some(
+                count @ (metadata::PremiseCount::Exactly(n) | metadata::PremiseCount::AtLeast(n)),
+            ) = premises
+            {
+                if n > 0 {
+                    let message =
+                        format!("step has no premises, but `{}` requires {}", rule, count);
+                    self.report(Lint::MissingPremises, &step.id, message);
+                }
+            }
+        }
+
+        let max = self.config.max_resolution_premises;
+        if (rule == "resolution" || rule == "th_resolution") && step.premises.len() > max {
+            let message = format!(
+                "resolution step has {} premises, more than the limit of {}",
+                step.premises.len(),
+                max
+            );
+            self.report(Lint::LongResolution, &step.id, message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn run_lints(proof: &str, config: &LintConfig) -> Vec<(Lint, String)> {
+        let definitions = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (declare-fun a () Int)
+            (declare-fun b () Int)
+        ";
+        let (_, proof, _) = parser::parse_instance(
+            definitions.as_bytes(),
+            proof.as_bytes(),
+            parser::Config::new(),
+        )
+        .unwrap();
+        lint_proof(&proof, config)
+            .into_iter()
+            .map(|d| (d.lint, d.step))
+            .collect()
+    }
+
+    #[test]
+    fn test_lints() {
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (assume h3 (= a b))
+            (step t1 (cl p) :rule reordering :premises (h1))
+            (step t2 (cl p) :rule reordering :premises (t1))
+            (step t3 (cl (not (not (not (not (not p))))) (not (not p))) :rule not_not)
+            (step t4 (cl q) :rule and)
+            (step t5 (cl (= b a)) :rule symm :premises (h3))
+            (step t6 (cl (= a b)) :rule symm :premises (t5))
+            (anchor :step t7)
+            (assume t7.a0 p)
+            (step t7.t1 (cl q) :rule hole)
+            (step t7 (cl (not p) p) :rule subproof :discharge (t7.a0))
+            (step t8 (cl) :rule resolution :premises (t2 h2))
+        ";
+        let expected = [
+            (Lint::DuplicateStep, "t1"),
+            (Lint::SimplifiableChain, "t2"),
+            (Lint::DuplicateStep, "t2"),
+            (Lint::SimplifiableChain, "t3"),
+            (Lint::UnusedStep, "t3"),
+            (Lint::MissingPremises, "t4"),
+            (Lint::UnusedStep, "t4"),
+            (Lint::UnusedStep, "t5"),
+            (Lint::SimplifiableChain, "t6"),
+            (Lint::UnusedStep, "t6"),
+            (Lint::DuplicateStep, "t6"),
+            (Lint::UnusedStep, "t7.t1"),
+            (Lint::UnusedStep, "t7"),
+        ];
+        let expected: Vec<_> = expected.iter().map(|&(l, s)| (l, s.to_owned())).collect();
+        assert_eq!(run_lints(proof, &LintConfig::new()), expected);
+
+        let config = LintConfig::new()
+            .lint_level(Lint::UnusedStep, LintLevel::Allow)
+            .lint_level(Lint::DuplicateStep, LintLevel::Allow)
+            .lint_level(Lint::SimplifiableChain, LintLevel::Allow)
+            .lint_level(Lint::MissingPremises, LintLevel::Deny)
+            .max_resolution_premises(1);
+        let diagnostics = run_lints(proof, &config);
+        let expected = [
+            (Lint::MissingPremises, "t4".to_owned()),
+            (Lint::LongResolution, "t8".to_owned()),
+        ];
+        assert_eq!(diagnostics, expected);
+    }
+
+    #[test]
+    fn test_lint_names() {
+        for lint in Lint::ALL {
+            assert_eq!(lint.name().parse(), Ok(lint));
+        }
+        assert!("unused".parse::<Lint>().is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Normalization of proofs printed by specific solvers, whose output differs from the Alethe
+//! specification in some rule names and conventions. Each root-level command is normalized as soon
+//! as it is parsed, so the checker and every other consumer of the proof only see spec-compliant
+//! commands.
+
+use super::{Config, IntRealSubtyping};
+use crate::ast::*;
+
+/// The dialect of the Alethe format a proof is written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// The proof follows the Alethe specification, and is not changed.
+    #[default]
+    Alethe,
+
+    /// The proof was printed by veriT, and may use:
+    /// - the names given to some rules by older veriT releases, like `tmp_AC_simp` for `ac_simp`
+    /// - `subproof` steps without a `:discharge` attribute, which implicitly discharge every
+    ///   `assume` command in their subproof
+    Verit,
+
+    /// The proof was printed by cvc5, and may use:
+    /// - arithmetic operations that mix `Int` and `Real` terms, which are parsed as if
+    ///   [`IntRealSubtyping::ToReal`] was enabled, unless the parser is configured to accept them
+    ///   as they are, with [`IntRealSubtyping::Implicit`]
+    /// - pivots of `resolution` and `th_resolution` steps given with the polarity before the
+    ///   pivot, like `(true p false q)` instead of `(p true q false)`
+    /// - `rare_rewrite` steps, which are considered holes if no RARE rules are given to the checker
+    ///   (see [`checker::Config::dialect`](crate::checker::Config::dialect))
+    Cvc5,
+}
+
+/// The old names veriT used for some rules, and the names the Alethe specification uses for them.
+const VERIT_RULE_NAMES: &[(&str, &str)] = &[
+    ("tmp_AC_simp", "ac_simp"),
+    ("tmp_bfun_elim", "bfun_elim"),
+    ("tmp_distinct_elim", "distinct_elim"),
+    ("tmp_nary_elim", "nary_elim"),
+];
+
+impl Dialect {
+    /// Returns the rules that proofs in this dialect may use even if the checker can't check
+    /// them. These should be considered holes instead of unknown rules.
+    pub fn allowed_rules(self) -> &'static [&'static str] {
+        match self {
+            Dialect::Alethe | Dialect::Verit => &[],
+            Dialect::Cvc5 => &["rare_rewrite"],
+        }
+    }
+
+    /// Adjusts the parser configuration to the conventions of this dialect.
+    pub(super) fn adjust_config(self, mut config: Config) -> Config {
+        if self == Dialect::Cvc5 && config.int_real_subtyping == IntRealSubtyping::Strict {
+            config.int_real_subtyping = IntRealSubtyping::ToReal;
+        }
+        config
+    }
+
+    /// Normalizes a root-level proof command, including the commands in it, if it is a subproof.
+    pub(super) fn normalize(self, command: &mut ProofCommand) {
+        if self != Dialect::Alethe {
+            self.normalize_command(command, 0);
+        }
+    }
+
+    fn normalize_command(self, command: &mut ProofCommand, depth: usize) {
+        match command {
+            ProofCommand::Assume { .. } => (),
+            ProofCommand::Step(step) => self.normalize_step(step),
+            ProofCommand::Subproof(subproof) => {
+                for inner in &mut subproof.commands {
+                    self.normalize_command(inner, depth + 1);
+                }
+                if self == Dialect::Verit {
+                    add_implicit_discharge(subproof, depth + 1);
+                }
+            }
+        }
+    }
+
+    fn normalize_step(self, step: &mut ProofStep) {
+        match self {
+            Dialect::Alethe => (),
+            Dialect::Verit => {
+                let renamed = VERIT_RULE_NAMES.iter().find(|(old, _)| *old == step.rule);
+                if let Some((_, new)) = renamed {
+                    step.rule = (*new).to_owned();
+                }
+            }
+            Dialect::Cvc5 => {
+                if matches!(step.rule.as_str(), "resolution" | "th_resolution") {
+                    swap_polarity_first_pivots(&mut step.args);
+                }
+            }
+        }
+    }
+}
+
+/// If the step that ends a subproof is a `subproof` step without a `:discharge` attribute, makes it
+/// discharge every `assume` command in the subproof, whose commands are at the given depth.
+fn add_implicit_discharge(subproof: &mut Subproof, depth: usize) {
+    let assumes: Vec<_> = subproof
+        .commands
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| matches!(c, ProofCommand::Assume { .. }))
+        .map(|(i, _)| (depth, i))
+        .collect();
+    if let Some(ProofCommand::Step(end_step)) = subproof.commands.last_mut() {
+        if end_step.rule == "subproof" && end_step.discharge.is_empty() {
+            end_step.discharge = assumes;
+        }
+    }
+}
+
+/// If the arguments of a resolution step are pairs with the polarity before the pivot, swaps each
+/// pair, so the pivot comes first. Arguments where every term is a boolean constant are ambiguous,
+/// and are left unchanged.
+fn swap_polarity_first_pivots(args: &mut [Rc<Term>]) {
+    let is_polarity = |t: &Rc<Term>| t.is_bool_true() || t.is_bool_false();
+    let is_polarity_first = args.len() % 2 == 0
+        && args.chunks(2).all(|pair| is_polarity(&pair[0]))
+        && !args.chunks(2).all(|pair| is_polarity(&pair[1]));
+    if is_polarity_first {
+        for pair in args.chunks_mut(2) {
+            pair.swap(0, 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_instance, Config};
+
+    /// Parses the proof in the given dialect, and returns the first step and the step that ends
+    /// the subproof.
+    fn parse(dialect: Dialect) -> (ProofStep, ProofStep) {
+        let problem = "(declare-const a Bool) (declare-const b Bool)";
+        let proof = "
+            (step t1 (cl (= (and a (and b a)) (and a b a))) :rule tmp_AC_simp)
+            (anchor :step t2)
+            (assume t2.h1 a)
+            (assume t2.h2 b)
+            (step t2.t3 (cl (and a b)) :rule and_intro :premises (t2.h1 t2.h2))
+            (step t2 (cl (not a) (not b) (and a b)) :rule subproof)
+        ";
+        let config = Config { dialect, ..Config::new() };
+        let (_, proof, _) = parse_instance(problem.as_bytes(), proof.as_bytes(), config).unwrap();
+        match proof.commands.as_slice() {
+            [ProofCommand::Step(first), ProofCommand::Subproof(s)] => match s.commands.last() {
+                Some(ProofCommand::Step(last)) => (first.clone(), last.clone()),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_verit_dialect() {
+        let (first, last) = parse(Dialect::Alethe);
+        assert_eq!(first.rule, "tmp_AC_simp");
+        assert!(last.discharge.is_empty());
+
+        let (first, last) = parse(Dialect::Verit);
+        assert_eq!(first.rule, "ac_simp");
+        assert_eq!(last.discharge, [(1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_cvc5_dialect() {
+        use crate::checker::{self, ProofChecker};
+
+        // A hand-written proof that uses each of the conventions listed in `Dialect::Cvc5`: it
+        // mixes `Int` and `Real` terms, gives the polarity of each resolution pivot before the
+        // pivot, and uses `rare_rewrite` steps. It was not produced by cvc5
+        let problem = "
+            (declare-const b Bool)
+            (declare-const x Int)
+            (assert b)
+            (assert (and (not b) (> (+ x 0.5) 1.0)))
+        ";
+        let proof = r#"
+            (assume a0 b)
+            (assume a1 (and (not b) (> (+ x 0.5) 1.0)))
+            (step t1 (cl (not b)) :rule and :premises (a1) :args (0))
+            (step t2 (cl (= (not (not b)) b)) :rule rare_rewrite :args ("bool-double-not-elim" b))
+            (step t3 (cl) :rule resolution :premises (a0 t1) :args (true b))
+        "#;
+        let parse = |dialect| {
+            let config = Config { dialect, ..Config::new() };
+            parse_instance(problem.as_bytes(), proof.as_bytes(), config)
+        };
+        assert!(parse(Dialect::Alethe).is_err());
+
+        let (problem, proof, mut pool) = parse(Dialect::Cvc5).unwrap();
+        let ProofCommand::Step(step) = &proof.commands[4] else {
+            unreachable!();
+        };
+        assert_eq!(step.args[0].to_string(), "b");
+        assert!(step.args[1].is_bool_true());
+
+        let config = || checker::Config::new().elaborated(true);
+        assert!(ProofChecker::new(&mut pool, config())
+            .check(&problem, &proof)
+            .is_err());
+        let is_holey = ProofChecker::new(&mut pool, config().dialect(Dialect::Cvc5))
+            .check(&problem, &proof)
+            .unwrap();
+        assert!(is_holey);
+    }
+}
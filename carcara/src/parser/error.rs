@@ -2,7 +2,8 @@
 
 use crate::{
     ast::{Constant, PrimitivePool, Rc, Sort, Term, TermPool},
-    parser::Token,
+    limits::SizeLimitError,
+    parser::{Position, Token},
     utils::Range,
 };
 use rug::Integer;
@@ -61,10 +62,30 @@ pub enum ParserError {
     #[error("sort error: {0}")]
     SortError(#[from] SortError),
 
+    /// The problem or proof exceeded one of the configured size limits.
+    #[error("{0}")]
+    SizeLimit(#[from] SizeLimitError),
+
     /// Expected BvSort
     #[error("expected bitvector sort, got '{0}'")]
     ExpectedBvSort(Sort),
 
+    /// A datatype with sort parameters was declared.
+    #[error("parametric datatypes are not supported: '{0}'")]
+    ParametricDatatype(String),
+
+    /// The constructor in a datatype tester is not a datatype constructor.
+    #[error("'{0}' is not a datatype constructor")]
+    NotAConstructor(String),
+
+    /// A bound variable shadows a variable bound by an enclosing binder term.
+    #[error("bound variable '{0}' shadows another bound variable")]
+    ShadowedBinder(String),
+
+    /// Expected a floating-point sort.
+    #[error("expected floating-point sort, got '{0}'")]
+    ExpectedFpSort(Sort),
+
     // Expected Constant::Integer, got other Term
     #[error("expected integer constant, got '{0}'")]
     ExpectedIntegerConstant(Rc<Term>),
@@ -77,6 +98,14 @@ pub enum ParserError {
     #[error("identifier '{0}' is not defined")]
     UndefinedIden(String),
 
+    /// The parser encountered a term sharing reference that was not defined.
+    #[error("shared term '#{0}' is not defined")]
+    UndefinedSharedTerm(u64),
+
+    /// A term sharing reference has an index that is too large.
+    #[error("invalid term sharing reference: '#{0}'")]
+    InvalidSharingReference(String),
+
     /// The parser encountered a sort that was not defined.
     #[error("sort '{0}' is not defined")]
     UndefinedSort(String),
@@ -100,6 +129,14 @@ pub enum ParserError {
     #[error("step id '{0}' was repeated")]
     RepeatedStepId(String),
 
+    /// The same name was given to more than one inline hypothesis.
+    #[error("hypothesis name '{0}' was repeated")]
+    RepeatedHypothesisName(String),
+
+    /// A symbol introduced by a definition that extends the problem was already declared.
+    #[error("'{0}' is already declared, and cannot be introduced by a definition")]
+    DefinitionIsNotFresh(String),
+
     /// The number given as the arity in a `declare-sort` command is too large. This only happens
     /// if the number is too big to fit in a `usize`, so it almost never happens.
     #[error("{0} is not a valid sort arity")]
@@ -117,6 +154,11 @@ pub enum ParserError {
     #[error("subproof '{0}' was not closed")]
     UnclosedSubproof(String),
 
+    /// A `set-info` command or the parser configuration named an unknown version of the Alethe
+    /// format.
+    #[error("unknown Alethe version: '{0}'")]
+    UnknownAletheVersion(String),
+
     /// The parser encountered an unknown indexed operator.
     #[error("not a valid indexed operator: '{0}'")]
     InvalidIndexedOp(String),
@@ -124,6 +166,39 @@ pub enum ParserError {
     /// The parser encountered an unknown qualified operator.
     #[error("not a valid qualified operator: '{0}'")]
     InvalidQualifiedOp(String),
+
+    /// The sort checker found an ill-sorted term. See [`SortChecker`](super::SortChecker).
+    #[error("ill-sorted term '{0}': {1}")]
+    IllSortedTerm(Rc<Term>, Box<ParserError>),
+
+    /// The sort checker found an error in a proof command.
+    #[error("in command '{0}': {1}")]
+    IllSortedCommand(String, Box<ParserError>),
+}
+
+/// An error found while parsing a proof in recovery mode. See [`Parser::parse_proof_with_recovery`].
+///
+/// [`Parser::parse_proof_with_recovery`]: super::Parser::parse_proof_with_recovery
+#[derive(Debug)]
+pub struct ParserDiagnostic {
+    pub error: ParserError,
+
+    /// The line and column where the error was found.
+    pub position: Position,
+
+    /// The byte offset in the input where the error was found, if it could be determined.
+    pub offset: Option<usize>,
+}
+
+impl fmt::Display for ParserDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (line, column) = self.position;
+        write!(f, "line {}, column {}", line, column)?;
+        if let Some(offset) = self.offset {
+            write!(f, " (byte {})", offset)?;
+        }
+        write!(f, ": {}", self.error)
+    }
 }
 
 /// Returns an error if the length of `sequence` is not in the `expected` range.
@@ -155,6 +230,15 @@ where
     Ok(())
 }
 
+/// Returns an error if the sorts are not all the same floating-point sort.
+pub fn assert_fp_sorts(sorts: &[&Sort]) -> Result<(), ParserError> {
+    if let Some(s) = sorts.iter().find(|s| !matches!(s, Sort::FloatingPoint(..))) {
+        return Err(ParserError::ExpectedFpSort((*s).clone()));
+    }
+    SortError::assert_all_eq(sorts)?;
+    Ok(())
+}
+
 /// An error in sort checking.
 #[derive(Debug, Error)]
 pub struct SortError {
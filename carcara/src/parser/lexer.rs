@@ -38,6 +38,13 @@ pub enum Token {
     /// A string literal.
     String(String),
 
+    /// A term sharing definition, of the form `#n:`, as emitted by veriT. This precedes the term
+    /// that is being named.
+    SharedTermDefinition(u64),
+
+    /// A term sharing reference, of the form `#n`, as emitted by veriT.
+    SharedTermReference(u64),
+
     /// A reserved word.
     ReservedWord(Reserved),
 
@@ -96,6 +103,12 @@ pub enum Reserved {
     /// The `declare-sort` reserved word.
     DeclareSort,
 
+    /// The `declare-datatype` reserved word.
+    DeclareDatatype,
+
+    /// The `declare-datatypes` reserved word.
+    DeclareDatatypes,
+
     /// The `define-fun` reserved word.
     DefineFun,
 
@@ -135,6 +148,8 @@ impl_str_conversion_traits!(Reserved {
     DeclareFun: "declare-fun",
     DeclareConst: "declare-const",
     DeclareSort: "declare-sort",
+    DeclareDatatype: "declare-datatype",
+    DeclareDatatypes: "declare-datatypes",
     DefineFun: "define-fun",
     DefineFunRec: "define-fun-rec",
     DefineFunsRec: "define-funs-rec",
@@ -147,12 +162,29 @@ impl_str_conversion_traits!(Reserved {
 /// Represents a position (line and column numbers) in the source input.
 pub type Position = (usize, usize);
 
+/// A line of the input, stored so positions in it can be converted into byte offsets.
+struct LineText {
+    number: usize,
+    offset: usize,
+    text: String,
+}
+
 /// A lexer for the SMT-LIB and Alethe formats.
 pub struct Lexer<R> {
     input: R,
     current_line: Option<std::vec::IntoIter<char>>,
     current_char: Option<char>,
     position: Position,
+
+    /// The text of the current line, and its byte offset in the input.
+    current_line_text: LineText,
+
+    /// The number of bytes read from the input so far.
+    bytes_read: usize,
+
+    /// If `Some`, the lines that were already read are stored here, so that positions in them can
+    /// still be converted into byte offsets. See [`Lexer::record_lines`].
+    previous_lines: Option<Vec<LineText>>,
 }
 
 impl<R: BufRead> Lexer<R> {
@@ -162,23 +194,65 @@ impl<R: BufRead> Lexer<R> {
     pub fn new(mut input: R) -> io::Result<Self> {
         let mut buf = String::new();
         let read = input.read_line(&mut buf)?;
-        if read == 0 {
-            Ok(Lexer {
-                input,
-                current_line: None,
-                current_char: None,
-                position: (0, 0),
-            })
+        let (current_line, current_char, position) = if read == 0 {
+            (None, None, (0, 0))
         } else {
             let mut line = buf.chars().collect::<Vec<_>>().into_iter();
             let current_char = line.next();
-            Ok(Lexer {
-                input,
-                current_line: Some(line),
-                current_char,
-                position: (1, 1),
-            })
-        }
+            (Some(line), current_char, (1, 1))
+        };
+        Ok(Lexer {
+            input,
+            current_line,
+            current_char,
+            position,
+            current_line_text: LineText {
+                number: position.0,
+                offset: 0,
+                text: buf,
+            },
+            bytes_read: read,
+            previous_lines: None,
+        })
+    }
+
+    /// Starts or stops storing the lines that are read. Starting discards any lines that were
+    /// stored before, except for the current line, which is always available.
+    ///
+    /// While this is enabled, [`Lexer::offset_of`] can convert positions in any of the stored lines.
+    /// Otherwise, only positions in the current line can be converted.
+    pub fn record_lines(&mut self, value: bool) {
+        self.previous_lines = value.then(Vec::new);
+    }
+
+    /// Returns the byte offset in the input that corresponds to a position, or `None` if the
+    /// position is not in the current line or in a stored line.
+    pub fn offset_of(&self, (line, column): Position) -> Option<usize> {
+        let line_text = if line == self.current_line_text.number {
+            &self.current_line_text
+        } else {
+            self.previous_lines
+                .as_ref()?
+                .iter()
+                .find(|l| l.number == line)?
+        };
+
+        // Columns are counted in characters, starting at 1
+        let in_line: usize = (line_text.text.chars())
+            .take(column.saturating_sub(1))
+            .map(char::len_utf8)
+            .sum();
+        Some(line_text.offset + in_line)
+    }
+
+    /// Returns the position of the current character, which is right after the last token read.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Skips the current character. This can be used to recover from an error in the lexer.
+    pub fn skip_char(&mut self) -> io::Result<()> {
+        self.next_char().map(|_| ())
     }
 
     /// Advances the lexer by one character, and returns the previous `current_char`.
@@ -211,6 +285,17 @@ impl<R: BufRead> Lexer<R> {
             self.current_line = Some(line);
             self.position.0 += 1;
             self.position.1 = 0;
+
+            let new_line = LineText {
+                number: self.position.0,
+                offset: self.bytes_read,
+                text: buf,
+            };
+            let old_line = std::mem::replace(&mut self.current_line_text, new_line);
+            if let Some(lines) = &mut self.previous_lines {
+                lines.push(old_line);
+            }
+            self.bytes_read += read;
         }
         Ok(())
     }
@@ -272,7 +357,7 @@ impl<R: BufRead> Lexer<R> {
             Some('"') => self.read_string(),
             Some('|') => self.read_quoted_symbol(),
             Some(':') => self.read_keyword(),
-            Some('#') => self.read_bitvector(),
+            Some('#') => self.read_bitvector_or_sharing(),
             Some('-') => {
                 // If we encounter the '-' character, the token can either be a GMP-style numerical
                 // literal (e.g. '-5'), or a symbol that starts with '-' (e.g. the '-' operator
@@ -333,12 +418,26 @@ impl<R: BufRead> Lexer<R> {
         Ok(Token::Keyword(symbol))
     }
 
-    /// Reads a binary or hexadecimal bitvector literal, e.g. `#b0110` or `#x01Ab`.
+    /// Reads a binary or hexadecimal bitvector literal, e.g. `#b0110` or `#x01Ab`, or a term sharing
+    /// definition or reference, e.g. `#12:` or `#12`.
     ///
-    /// Returns an error if any character other than `b` or `x` is encountered after the `#`, or if
-    /// no digits are provided.
-    fn read_bitvector(&mut self) -> CarcaraResult<Token> {
+    /// Returns an error if any character other than `b`, `x` or a digit is encountered after the
+    /// `#`, or if no digits are provided.
+    fn read_bitvector_or_sharing(&mut self) -> CarcaraResult<Token> {
         self.next_char()?; // Consume `#`
+        if self.current_char.as_ref().is_some_and(char::is_ascii_digit) {
+            let position = self.position;
+            let digits = self.read_chars_while(|c| c.is_ascii_digit())?;
+            let n = digits.parse().map_err(|_| {
+                Error::Parser(ParserError::InvalidSharingReference(digits), position)
+            })?;
+            return if self.current_char == Some(':') {
+                self.next_char()?;
+                Ok(Token::SharedTermDefinition(n))
+            } else {
+                Ok(Token::SharedTermReference(n))
+            };
+        }
         let (base, bits_per_char) = match self.next_char()? {
             Some('b') => (2, 1),
             Some('x') => (16, 4),
@@ -642,6 +741,27 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_sharing_references() {
+        let input = "#1:(f #1) #23 #4:a";
+        let expected = vec![
+            Token::SharedTermDefinition(1),
+            Token::OpenParen,
+            Token::Symbol("f".into()),
+            Token::SharedTermReference(1),
+            Token::CloseParen,
+            Token::SharedTermReference(23),
+            Token::SharedTermDefinition(4),
+            Token::Symbol("a".into()),
+        ];
+        assert_eq!(expected, lex_all(input));
+
+        assert!(matches!(
+            lex_one("#99999999999999999999"),
+            Err(Error::Parser(ParserError::InvalidSharingReference(_), _)),
+        ));
+    }
+
     #[test]
     fn test_strings() {
         let input = r#" "string" "escaped quote: """ """" """""" "\u0061" "\u{0061}" "#;
@@ -1,25 +1,33 @@
 //! A parser for the Alethe proof format.
 
+mod dialect;
 mod error;
 mod lexer;
+mod rare;
+mod sort_checker;
+mod source_map;
 pub(crate) mod tests;
 
 use std::iter::Iterator;
 
-pub use error::{ParserError, SortError};
+pub use dialect::Dialect;
+pub use error::{ParserDiagnostic, ParserError, SortError};
 pub use lexer::{Lexer, Position, Reserved, Token};
+pub use rare::parse_rare_rules;
+pub use sort_checker::SortChecker;
+pub use source_map::{CommandLocation, SourceMap, Span};
 
 use crate::{
     ast::*,
+    limits::SizeLimits,
     utils::{HashCache, HashMapStack},
     CarcaraResult, Error,
 };
 use error::assert_num_args;
 use indexmap::{IndexMap, IndexSet};
 use rug::{Integer, Rational};
-use std::{io::BufRead, str::FromStr};
-
-use self::error::assert_indexed_op_args_value;
+use sort_checker::{check_app, check_indexed_op, check_op, check_qualified_op, check_tester};
+use std::{fmt, io::BufRead, str::FromStr};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Config {
@@ -35,20 +43,59 @@ pub struct Config {
     /// value.
     pub expand_lets: bool,
 
-    /// If `true`, this relaxes the type checking rules in Carcara to allow `Int`-`Real` subtyping.
-    /// That is, terms of sort `Int` will be allowed in arithmetic operations where a `Real` term
-    /// was expected. Note that this only applies to predefined operators --- passing an `Int` term
-    /// to a function that expects a `Real` will still be an error.
-    pub allow_int_real_subtyping: bool,
+    /// How terms of sort `Int` are handled in arithmetic operations where a `Real` term was
+    /// expected. Note that this only applies to predefined operators --- passing an `Int` term to
+    /// a function that expects a `Real` is always an error.
+    pub int_real_subtyping: IntRealSubtyping,
 
     /// Enables "strict" parsing. If `true`:
     /// - Unary `and`, `or` and `xor` terms are not allowed
-    /// - Anchor arguments using the old syntax (i.e., `(:= <symbol> <term>)`) are not allowed
+    /// - Anchor arguments using the old syntax (i.e., `(:= <symbol> <term>)`) are not allowed,
+    ///   unless the proof is in version 1.0 of the Alethe format
     pub strict: bool,
 
     /// If `true`, the parser will parse arguments to the `hole` rule, expecting them to be valid
     /// terms.
     pub parse_hole_args: bool,
+
+    /// How bound variables that shadow a variable bound by an enclosing binder term are handled.
+    /// Repeated variables in the same binding list are also considered shadowed.
+    pub shadowed_binders: ShadowedBinders,
+
+    /// The maximum number of proof commands and term nodes allowed. These are checked after each
+    /// command is parsed, and an error is returned if any of them is exceeded.
+    pub limits: SizeLimits,
+
+    /// If `true`, the parser records the location of each `assume` and `step` command in the proof,
+    /// and of the terms in them, in a [`SourceMap`]. These locations are attached to checker errors,
+    /// so they can point to the failing step in the proof file.
+    pub track_locations: bool,
+
+    /// If `true`, the term pool adds binder terms modulo alpha-equivalence, so terms that only
+    /// differ in the names of their bound variables share the same allocation. The parsed terms may
+    /// then use different names for their bound variables than the ones in the input. See
+    /// [`PrimitivePool::enable_alpha_canonical_binders`].
+    pub alpha_canonical_binders: bool,
+
+    /// How bound variables are renamed when they need a fresh name, for example to avoid capture
+    /// when expanding `let` terms or applying substitutions. If this is
+    /// [`FreshNameScheme::Counter`], the names of all variables in the input are reserved, so the
+    /// generated names never clash with any symbol in the problem or proof.
+    pub fresh_name_scheme: FreshNameScheme,
+
+    /// If `true`, every premise and proof command is checked by a [`SortChecker`] after it is
+    /// parsed. This is always done in "strict" parsing mode.
+    pub check_sorts: bool,
+
+    /// The version of the Alethe format the proof is written in, which determines which syntax is
+    /// accepted for some proof constructs. If this is `None`, the version is taken from a
+    /// `(set-info :alethe-version <version>)` command in the problem or proof, if there is one.
+    /// Otherwise, the syntax of all versions is accepted, unless "strict" parsing is enabled.
+    pub alethe_version: Option<AletheVersion>,
+
+    /// The dialect of the Alethe format the proof is written in. Proofs in a solver-specific
+    /// dialect are normalized into spec-compliant proofs while they are parsed. See [`Dialect`].
+    pub dialect: Dialect,
 }
 
 impl Config {
@@ -57,6 +104,84 @@ impl Config {
     }
 }
 
+/// How the parser handles terms of sort `Int` in arithmetic operations that mix `Int` and `Real`
+/// terms, like `(+ x 1.5)` where `x` has sort `Int`, or in divisions, which expect `Real` terms.
+/// Comparisons between `Int` and `Real` terms, like `(< x 1.5)`, are always allowed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntRealSubtyping {
+    /// Mixing the sorts is an error, as in SMT-LIB. All arguments of `+`, `-` and `*` must have the
+    /// same sort, and the arguments of `/` must have sort `Real`.
+    #[default]
+    Strict,
+
+    /// `Int` terms are accepted where a `Real` term was expected, and are kept as they are. This is
+    /// how veriT prints its proofs.
+    Implicit,
+
+    /// `Int` terms used where a `Real` term was expected are coerced while parsing, by wrapping
+    /// them in `to_real`, as cvc5 does in its proofs. Integer constants are converted into real
+    /// constants instead. This also applies to comparisons and equalities. The resulting terms
+    /// follow the strict rules.
+    ToReal,
+}
+
+/// How the parser handles bound variables that shadow other bound variables, like the inner `x` in
+/// `(forall ((x Int)) (exists ((x Int)) (= x 0)))`. Variables introduced by `let` terms or by
+/// subproof anchors are not considered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShadowedBinders {
+    /// Shadowed variables are allowed, and follow the usual scoping rules.
+    #[default]
+    Allow,
+
+    /// Shadowed variables are allowed, but a warning is logged for each of them.
+    Warn,
+
+    /// Shadowed variables are rejected with an error.
+    Reject,
+
+    /// Shadowing variables are renamed to a fresh name, so that no variable in the parsed terms is
+    /// shadowed. This happens while parsing, so all rules see the renamed terms.
+    Rename,
+}
+
+/// A version of the Alethe proof format. The versions differ in the syntax of some proof
+/// constructs, and the parser converts the older syntax into the current one, so the checker sees
+/// the same proof commands regardless of the version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AletheVersion {
+    /// The version printed by older releases of veriT. Assignments, both in `anchor` arguments and
+    /// in step arguments (e.g., in the `forall_inst` rule), have the form `(:= <symbol> <term>)`.
+    /// In step arguments, they are read as just the assigned term, since the checker only looks
+    /// at their position.
+    V1_0,
+
+    /// The current version. Assignments in `anchor` arguments must have the form
+    /// `(:= (<symbol> <sort>) <term>)`, and step arguments must be plain terms.
+    V1_1,
+}
+
+impl fmt::Display for AletheVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AletheVersion::V1_0 => write!(f, "1.0"),
+            AletheVersion::V1_1 => write!(f, "1.1"),
+        }
+    }
+}
+
+impl FromStr for AletheVersion {
+    type Err = ParserError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" | "1.0" => Ok(AletheVersion::V1_0),
+            "1.1" => Ok(AletheVersion::V1_1),
+            _ => Err(ParserError::UnknownAletheVersion(s.to_owned())),
+        }
+    }
+}
+
 /// Parses an SMT problem instance (in the SMT-LIB format) and its associated proof (in the Alethe
 /// format).
 ///
@@ -85,6 +210,111 @@ pub fn parse_instance_with_pool<T: BufRead>(
     Ok((problem, proof))
 }
 
+/// Similar to [`parse_instance`], but also returns the locations of the proof commands. These are
+/// only recorded if `config.track_locations` is `true`. Otherwise, the returned source map is empty.
+pub fn parse_instance_with_locations<T: BufRead>(
+    problem: T,
+    proof: T,
+    config: Config,
+) -> CarcaraResult<(Problem, Proof, PrimitivePool, SourceMap)> {
+    let mut pool = PrimitivePool::new();
+    let mut parser = Parser::new(&mut pool, config, problem)?;
+    let problem = parser.parse_problem()?;
+    parser.reset(proof)?;
+    let proof = parser.parse_proof()?;
+    let source_map = parser.take_source_map().unwrap_or_default();
+    Ok((problem, proof, pool, source_map))
+}
+
+/// Parses an SMT problem instance and its associated proof, recovering from syntax errors in the
+/// proof. Errors in the problem still stop the parser. See [`Parser::parse_proof_with_recovery`].
+pub fn parse_instance_with_recovery<T: BufRead>(
+    problem: T,
+    proof: T,
+    config: Config,
+) -> CarcaraResult<(Problem, Proof, PrimitivePool, Vec<ParserDiagnostic>)> {
+    let mut pool = PrimitivePool::new();
+    let mut parser = Parser::new(&mut pool, config, problem)?;
+    let problem = parser.parse_problem()?;
+    parser.reset(proof)?;
+    let (proof, diagnostics) = parser.parse_proof_with_recovery()?;
+    Ok((problem, proof, pool, diagnostics))
+}
+
+/// A hypothesis given inline instead of in a problem file, in the form `<name>:<term>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedHypothesis {
+    pub name: String,
+    pub term: String,
+}
+
+impl FromStr for NamedHypothesis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((name, term)) if !name.trim().is_empty() && !term.trim().is_empty() => Ok(Self {
+                name: name.trim().to_owned(),
+                term: term.to_owned(),
+            }),
+            _ => Err(format!(
+                "expected hypothesis of the form '<name>:<term>', got '{}'",
+                s
+            )),
+        }
+    }
+}
+
+/// Parses a proof whose premises are given as a list of named hypotheses, instead of the `assert`
+/// commands of a problem file. The symbols used by the hypotheses and the proof are declared in
+/// `declarations`, which is parsed like a problem file, and may be empty. Any premises it asserts
+/// are kept, together with the hypotheses.
+pub fn parse_instance_with_hypotheses<'a, T: BufRead + 'a>(
+    declarations: T,
+    hypotheses: &[NamedHypothesis],
+    proof: T,
+    config: Config,
+) -> CarcaraResult<(Problem, Proof, PrimitivePool)> {
+    let mut pool = PrimitivePool::new();
+    let (problem, proof) = {
+        let declarations: Box<dyn BufRead + 'a> = Box::new(declarations);
+        let mut parser = Parser::new(&mut pool, config, declarations)?;
+        let mut problem = parser.parse_problem()?;
+        let mut names = IndexSet::new();
+        for h in hypotheses {
+            if !names.insert(&h.name) {
+                let err = ParserError::RepeatedHypothesisName(h.name.clone());
+                return Err(Error::Parser(err, parser.current_position));
+            }
+            parser.reset(Box::new(h.term.as_bytes()))?;
+            let term = parser.parse_term()?;
+            parser.expect_token(Token::Eof)?;
+            problem.premises.insert(term);
+        }
+        parser.reset(Box::new(proof))?;
+        (problem, parser.parse_proof()?)
+    };
+    Ok((problem, proof, pool))
+}
+
+/// Parses a problem, a list of definitions that extend it with fresh symbols, and a proof that may
+/// use those symbols. See [`Parser::parse_definitions`] for the format of the definitions.
+pub fn parse_instance_with_definitions<T: BufRead>(
+    problem: T,
+    definitions: T,
+    proof: T,
+    config: Config,
+) -> CarcaraResult<(Problem, Proof, PrimitivePool)> {
+    let mut pool = PrimitivePool::new();
+    let mut parser = Parser::new(&mut pool, config, problem)?;
+    let problem = parser.parse_problem()?;
+    parser.reset(definitions)?;
+    let problem = parser.parse_definitions(problem)?;
+    parser.reset(proof)?;
+    let proof = parser.parse_proof()?;
+    Ok((problem, proof, pool))
+}
+
 /// A function definition, from a `define-fun` command.
 struct FunctionDef {
     params: Vec<SortedVar>,
@@ -126,6 +356,9 @@ struct SortDef {
     body: Rc<Term>,
 }
 
+/// The values of the variables bound in a single scope, used when expanding `let` terms.
+type LetValues = IndexMap<HashCache<String>, Rc<Term>>;
+
 /// The state of the parser.
 ///
 /// This holds all the function, constant or sort declarations and definitions, as well as the term
@@ -137,6 +370,22 @@ struct ParserState {
     sort_declarations: HashMapStack<String, usize>,
     sort_defs: IndexMap<String, SortDef>,
     step_ids: HashMapStack<HashCache<String>, usize>,
+
+    /// When `let` terms are expanded, this holds the values that variables bound by the enclosing
+    /// `let` terms should be replaced with, together with the depth of the symbol table scope in
+    /// which they were bound. Binder variables that had to be renamed to avoid capture are also
+    /// recorded here.
+    let_values: Vec<(usize, LetValues)>,
+
+    /// The terms named by term sharing definitions (`#n:`), which may be referenced later in the
+    /// proof by `#n`.
+    shared_terms: IndexMap<u64, Rc<Term>>,
+
+    /// The names of the variables bound by the enclosing binder terms, used to detect shadowing.
+    binder_names: Vec<String>,
+
+    /// The names of the declared datatype constructors.
+    constructors: IndexSet<String>,
 }
 
 /// A parser for the Alethe proof format.
@@ -149,6 +398,52 @@ pub struct Parser<'a, R> {
     state: ParserState,
     is_real_only_logic: bool,
     problem: Option<Problem>,
+
+    /// The number of parentheses opened by the tokens consumed so far that were not yet closed.
+    /// This is used to find the next top-level command when recovering from errors.
+    paren_depth: usize,
+
+    /// Whether the last token consumed was an opening parenthesis.
+    after_open_paren: bool,
+
+    /// The position right after the current token.
+    current_end: Position,
+
+    /// The location of the last token consumed.
+    previous_span: Span,
+
+    /// If locations are being tracked, the locations of the proof commands parsed so far.
+    source_map: Option<SourceMap>,
+
+    /// If sort checking is enabled, the checker used on each premise and proof command.
+    sort_checker: Option<SortChecker>,
+
+    /// The Alethe version declared by a `set-info` command, if any. This is kept when the parser is
+    /// reset, so a version declared in the problem also applies to the proof.
+    declared_version: Option<AletheVersion>,
+}
+
+/// The state of the parser while parsing the commands of a proof.
+struct ProofParsingState {
+    /// To avoid stack overflows in proofs with many nested subproofs, we parse the subproofs
+    /// iteratively, instead of recursively. Therefore, we need to manually keep a stack.
+    ///
+    /// Each frame of the stack stores the subproof that is being constructed, and the id of the
+    /// step that will end it. The first frame of the stack represents the root proof, so every
+    /// field except for the subproof commands is irrelevant.
+    stack: Vec<(Subproof, String)>,
+
+    next_subproof_context_id: usize,
+
+    /// Since the root-level commands are not stored, we only keep track of how many there are.
+    num_root_commands: usize,
+
+    /// The total number of `assume` and `step` commands, including the ones inside subproofs.
+    num_commands: usize,
+
+    finished_assumes: bool,
+
+    constant_definitions: Vec<(String, Rc<Term>)>,
 }
 
 impl<'a, R: BufRead> Parser<'a, R> {
@@ -156,17 +451,30 @@ impl<'a, R: BufRead> Parser<'a, R> {
     ///
     /// This operation can fail if there is an IO or lexer error on the first token.
     pub fn new(pool: &'a mut PrimitivePool, config: Config, input: R) -> CarcaraResult<Self> {
+        let config = config.dialect.adjust_config(config);
+        if config.alpha_canonical_binders {
+            pool.enable_alpha_canonical_binders();
+        }
+        pool.set_fresh_name_scheme(config.fresh_name_scheme);
         let mut lexer = Lexer::new(input)?;
         let (current_token, current_position) = lexer.next_token()?;
+        let current_end = lexer.position();
         Ok(Parser {
             pool,
             config,
             lexer,
             current_token,
             current_position,
+            current_end,
+            previous_span: Span::default(),
+            source_map: config.track_locations.then(SourceMap::default),
+            sort_checker: (config.check_sorts || config.strict).then(|| SortChecker::new(config)),
             state: ParserState::default(),
             is_real_only_logic: false,
             problem: None,
+            paren_depth: 0,
+            after_open_paren: false,
+            declared_version: None,
         })
     }
 
@@ -178,9 +486,19 @@ impl<'a, R: BufRead> Parser<'a, R> {
         self.lexer = lexer;
         self.current_token = current_token;
         self.current_position = current_position;
+        self.current_end = self.lexer.position();
+        self.previous_span = Span::default();
+        self.paren_depth = 0;
+        self.after_open_paren = false;
         Ok(())
     }
 
+    /// Returns the locations of the proof commands parsed so far, if locations are being tracked,
+    /// and starts a new empty source map.
+    pub fn take_source_map(&mut self) -> Option<SourceMap> {
+        self.source_map.as_mut().map(std::mem::take)
+    }
+
     /// Advances the parser one token, and returns the previous `current_token`.
     fn next_token(&mut self) -> CarcaraResult<(Token, Position)> {
         use std::mem::replace;
@@ -188,11 +506,22 @@ impl<'a, R: BufRead> Parser<'a, R> {
         let (new_token, new_position) = self.lexer.next_token()?;
         let old_token = replace(&mut self.current_token, new_token);
         let old_position = replace(&mut self.current_position, new_position);
+        let old_end = replace(&mut self.current_end, self.lexer.position());
+        self.previous_span = Span { start: old_position, end: old_end };
+        self.after_open_paren = old_token == Token::OpenParen;
+        match old_token {
+            Token::OpenParen => self.paren_depth += 1,
+            Token::CloseParen => self.paren_depth = self.paren_depth.saturating_sub(1),
+            _ => (),
+        }
         Ok((old_token, old_position))
     }
 
     /// Inserts a `SortedVar` into the parser symbol table.
     fn insert_sorted_var(&mut self, (symbol, sort): SortedVar) {
+        if self.config.fresh_name_scheme == FreshNameScheme::Counter {
+            self.pool.reserve_name(symbol.clone());
+        }
         self.state.symbol_table.insert(HashCache::new(symbol), sort);
     }
 
@@ -209,13 +538,95 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// Constructs and sort checks a variable term.
     fn make_var(&mut self, iden: String) -> Result<Rc<Term>, ParserError> {
         let cached = HashCache::new(iden);
-        let sort = match self.state.symbol_table.get(&cached) {
-            Some(s) => s.clone(),
+        let (depth, sort) = match self.state.symbol_table.get_with_depth(&cached) {
+            Some((depth, s)) => (depth, s.clone()),
             None => return Err(ParserError::UndefinedIden(cached.unwrap())),
         };
+
+        // If the variable was bound by a `let` term that is being expanded, we replace it with its
+        // value. We only consider values bound in the same scope as the variable, so variables that
+        // shadow a `let` variable are not replaced
+        let value = self
+            .state
+            .let_values
+            .iter()
+            .rev()
+            .take_while(|(d, _)| *d >= depth)
+            .find(|(d, _)| *d == depth)
+            .and_then(|(_, values)| values.get(&cached));
+        if let Some(value) = value {
+            return Ok(value.clone());
+        }
         Ok(self.pool.add(Term::Var(cached.unwrap(), sort)))
     }
 
+    /// Pops the last scope from the symbol table, along with any `let` values bound in it.
+    fn pop_symbol_table_scope(&mut self) {
+        self.state.symbol_table.pop_scope();
+        let height = self.state.symbol_table.height();
+        while matches!(self.state.let_values.last(), Some((d, _)) if *d >= height) {
+            self.state.let_values.pop();
+        }
+    }
+
+    /// Inserts a variable bound by a binder term into the symbol table. If the variable would
+    /// capture a free variable in the value of an enclosing `let` term that is being expanded, it
+    /// is renamed by adding `'`s to its name, and the renaming is recorded in the `let` values.
+    fn insert_bound_var(
+        &mut self,
+        (symbol, sort): SortedVar,
+        should_be_renamed: &mut IndexSet<String>,
+    ) -> SortedVar {
+        self.insert_sorted_var((symbol.clone(), sort.clone()));
+        if !should_be_renamed.contains(&symbol) {
+            return (symbol, sort);
+        }
+
+        let symbol_table = &self.state.symbol_table;
+        let new_name = self.pool.fresh_name(&symbol, &|name| {
+            should_be_renamed.contains(name)
+                || symbol_table.get(&HashCache::new(name.to_owned())).is_some()
+        });
+        should_be_renamed.insert(new_name.clone());
+
+        let depth = self.state.symbol_table.height() - 1;
+        let new_var = self.pool.add(Term::new_var(new_name.clone(), sort.clone()));
+        match self.state.let_values.last_mut() {
+            Some((d, values)) if *d == depth => {
+                values.insert(HashCache::new(symbol), new_var);
+            }
+            _ => {
+                let values = IndexMap::from([(HashCache::new(symbol), new_var)]);
+                self.state.let_values.push((depth, values));
+            }
+        }
+        (new_name, sort)
+    }
+
+    /// Handles a bound variable that may shadow a variable bound by an enclosing binder term,
+    /// according to the `shadowed_binders` option. If the variable should be renamed, it is added
+    /// to `should_be_renamed`.
+    fn check_shadowing(
+        &self,
+        name: &str,
+        should_be_renamed: &mut IndexSet<String>,
+    ) -> Result<(), ParserError> {
+        if !self.state.binder_names.iter().any(|n| n == name) {
+            return Ok(());
+        }
+        match self.config.shadowed_binders {
+            ShadowedBinders::Allow => (),
+            ShadowedBinders::Warn => {
+                tracing::warn!("bound variable '{}' shadows another bound variable", name);
+            }
+            ShadowedBinders::Reject => return Err(ParserError::ShadowedBinder(name.to_owned())),
+            ShadowedBinders::Rename => {
+                should_be_renamed.insert(name.to_owned());
+            }
+        }
+        Ok(())
+    }
+
     /// Return whether we should interpret integer constants as `Real`s.
     ///
     /// If we are working with a logic that contains reals but does not contain integers, and if we
@@ -224,258 +635,73 @@ impl<'a, R: BufRead> Parser<'a, R> {
         self.is_real_only_logic && self.problem.is_some()
     }
 
+    /// If sort checking is enabled, checks a premise of the problem with the [`SortChecker`].
+    fn sort_check_premise(&mut self, term: &Rc<Term>, position: Position) -> CarcaraResult<()> {
+        match &mut self.sort_checker {
+            Some(checker) => checker
+                .check_formula(self.pool, term)
+                .map_err(|e| Error::Parser(e, position)),
+            None => Ok(()),
+        }
+    }
+
     /// Constructs and sort checks an operation term.
     fn make_op(&mut self, op: Operator, args: Vec<Rc<Term>>) -> Result<Rc<Term>, ParserError> {
-        let sorts: Vec<_> = args.iter().map(|t| self.pool.sort(t)).collect();
-        let sorts: Vec<_> = sorts.iter().map(|s| s.as_sort().unwrap()).collect();
-        match op {
-            Operator::True | Operator::False => assert_num_args(&args, 0)?,
-            Operator::Not => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Bool, sorts[0])?;
-            }
-            Operator::Implies => {
-                assert_num_args(&args, 2..)?;
-                for s in sorts {
-                    SortError::assert_eq(&Sort::Bool, s)?;
-                }
-            }
-            Operator::Or | Operator::And | Operator::Xor => {
-                // If we are not in "strict" parsing mode, we allow these operators to be called
-                // with just one argument
-                assert_num_args(&args, if self.config.strict { 2.. } else { 1.. })?;
-                for s in sorts {
-                    SortError::assert_eq(&Sort::Bool, s)?;
-                }
-            }
-            Operator::Equals | Operator::Distinct => {
-                assert_num_args(&args, 2..)?;
-                SortError::assert_all_eq(&sorts)?;
-            }
-            Operator::Ite => {
-                assert_num_args(&args, 3)?;
-                SortError::assert_eq(&Sort::Bool, sorts[0])?;
-                SortError::assert_eq(sorts[1], sorts[2])?;
-            }
-            Operator::Add | Operator::Sub | Operator::Mult => {
-                // The `-` operator, in particular, can be called with only one argument, in which
-                // case it means negation instead of subtraction
-                if op == Operator::Sub {
-                    assert_num_args(&args, 1..)?;
-                } else {
-                    assert_num_args(&args, 2..)?;
-                }
-
-                // All the arguments must be either Int or Real. Also, if we are not allowing
-                // Int/Real subtyping, all arguments must have the same sort
-                if self.config.allow_int_real_subtyping {
-                    for s in sorts {
-                        SortError::assert_one_of(&[Sort::Int, Sort::Real], s)?;
-                    }
-                } else {
-                    SortError::assert_one_of(&[Sort::Int, Sort::Real], sorts[0])?;
-                    SortError::assert_all_eq(&sorts)?;
-                }
-            }
-            Operator::IntDiv => {
-                assert_num_args(&args, 2..)?;
-                SortError::assert_eq(&Sort::Int, sorts[0])?;
-                SortError::assert_all_eq(&sorts)?;
-            }
-            Operator::RealDiv => {
-                assert_num_args(&args, 2..)?;
-
-                // Normally, the `/` operator may only receive Real arguments, but if we are
-                // allowing Int/Real subtyping, it may also receive Ints
-                if self.config.allow_int_real_subtyping {
-                    for s in sorts {
-                        SortError::assert_one_of(&[Sort::Int, Sort::Real], s)?;
-                    }
-                } else {
-                    SortError::assert_eq(&Sort::Real, sorts[0])?;
-                    SortError::assert_all_eq(&sorts)?;
-                }
-
+        let args = if self.config.int_real_subtyping == IntRealSubtyping::ToReal {
+            // A division between integer constants may be a rational literal, in which case the
+            // constants must not be coerced
+            if op == Operator::RealDiv && args.len() == 2 {
                 if let Some(r) = self.interpret_div_as_real_lit(&args[0], &args[1]) {
                     return Ok(r);
                 }
             }
-            Operator::Mod => {
-                assert_num_args(&args, 2)?;
-                SortError::assert_eq(&Sort::Int, sorts[0])?;
-                SortError::assert_eq(&Sort::Int, sorts[1])?;
-            }
-            Operator::Abs => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Int, sorts[0])?;
-            }
-            Operator::LessThan | Operator::GreaterThan | Operator::LessEq | Operator::GreaterEq => {
-                assert_num_args(&args, 2..)?;
-                // All the arguments must be either Int or Real sorted, but they don't need to all
-                // have the same sort
-                for s in sorts {
-                    SortError::assert_one_of(&[Sort::Int, Sort::Real], s)?;
-                }
-            }
-            Operator::ToReal => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Int, sorts[0])?;
-            }
-            Operator::ToInt | Operator::IsInt => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Real, sorts[0])?;
-            }
-            Operator::Select => {
-                assert_num_args(&args, 2)?;
-                SortError::assert_array_sort(self.pool, Some(sorts[1]), None, sorts[0])?;
-            }
-            Operator::Store => {
-                assert_num_args(&args, 3)?;
-                SortError::assert_array_sort(self.pool, Some(sorts[1]), Some(sorts[2]), sorts[0])?;
-            }
-            Operator::StrConcat => {
-                assert_num_args(&args, 2..)?;
-                for s in sorts {
-                    SortError::assert_eq(&Sort::String, s)?;
-                }
-            }
-            Operator::StrLen | Operator::StrIsDigit | Operator::StrToCode | Operator::StrToInt => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::String, sorts[0])?;
-            }
-            Operator::StrLessThan
-            | Operator::StrLessEq
-            | Operator::PrefixOf
-            | Operator::SuffixOf
-            | Operator::Contains
-            | Operator::ReRange => {
-                assert_num_args(&args, 2)?;
-                SortError::assert_eq(&Sort::String, sorts[0])?;
-                SortError::assert_eq(&Sort::String, sorts[1])?;
-            }
-            Operator::CharAt => {
-                assert_num_args(&args, 2)?;
-                SortError::assert_eq(&Sort::String, sorts[0])?;
-                SortError::assert_eq(&Sort::Int, sorts[1])?;
-            }
-            Operator::Substring => {
-                assert_num_args(&args, 3)?;
-                SortError::assert_eq(&Sort::String, sorts[0])?;
-                SortError::assert_eq(&Sort::Int, sorts[1])?;
-                SortError::assert_eq(&Sort::Int, sorts[2])?;
-            }
-            Operator::IndexOf => {
-                assert_num_args(&args, 3)?;
-                SortError::assert_eq(&Sort::String, sorts[0])?;
-                SortError::assert_eq(&Sort::String, sorts[1])?;
-                SortError::assert_eq(&Sort::Int, sorts[2])?;
-            }
-            Operator::Replace | Operator::ReplaceAll => {
-                assert_num_args(&args, 3)?;
-                SortError::assert_eq(&Sort::String, sorts[0])?;
-                SortError::assert_eq(&Sort::String, sorts[1])?;
-                SortError::assert_eq(&Sort::String, sorts[2])?;
-            }
-            Operator::StrFromCode | Operator::StrFromInt => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Int, sorts[0])?;
-            }
-            Operator::StrToRe => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::String, sorts[0])?;
-            }
-            Operator::StrInRe => {
-                assert_num_args(&args, 2)?;
-                SortError::assert_eq(&Sort::String, sorts[0])?;
-                SortError::assert_eq(&Sort::RegLan, sorts[1])?;
-            }
-            Operator::ReNone | Operator::ReAll | Operator::ReAllChar => {
-                assert_num_args(&args, 0)?;
-            }
-            Operator::ReConcat
-            | Operator::ReUnion
-            | Operator::ReIntersection
-            | Operator::ReDiff => {
-                assert_num_args(&args, 2..)?;
-                for s in sorts {
-                    SortError::assert_eq(&Sort::RegLan, s)?;
-                }
-            }
-            Operator::ReKleeneClosure
-            | Operator::ReComplement
-            | Operator::ReKleeneCross
-            | Operator::ReOption => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::RegLan, sorts[0])?;
-            }
-            Operator::ReplaceRe | Operator::ReplaceReAll => {
-                assert_num_args(&args, 3)?;
-                SortError::assert_eq(&Sort::String, sorts[0])?;
-                SortError::assert_eq(&Sort::RegLan, sorts[1])?;
-                SortError::assert_eq(&Sort::String, sorts[2])?;
+            self.insert_to_real(op, args)
+        } else {
+            args
+        };
+        check_op(self.pool, &self.config, op, &args)?;
+        if op == Operator::RealDiv {
+            if let Some(r) = self.interpret_div_as_real_lit(&args[0], &args[1]) {
+                return Ok(r);
             }
-            Operator::BvNot | Operator::BvNeg => {
-                assert_num_args(&args, 1)?;
-                for s in sorts {
-                    if !matches!(s, Sort::BitVec(_)) {
-                        return Err(ParserError::ExpectedBvSort(s.clone()));
-                    }
+        }
+        Ok(self.pool.add(Term::Op(op, args)))
+    }
+
+    /// Coerces the `Int` arguments of an arithmetic operation, comparison or equality to `Real`,
+    /// if the operation expects `Real` arguments. That is the case for divisions, and for the
+    /// other operations if any of their arguments has sort `Real`.
+    fn insert_to_real(&mut self, op: Operator, args: Vec<Rc<Term>>) -> Vec<Rc<Term>> {
+        let is_real = |p: &PrimitivePool, t: &Rc<Term>| p.sort(t).as_sort() == Some(&Sort::Real);
+        let expects_real = match op {
+            Operator::RealDiv => true,
+            Operator::Add
+            | Operator::Sub
+            | Operator::Mult
+            | Operator::LessThan
+            | Operator::GreaterThan
+            | Operator::LessEq
+            | Operator::GreaterEq
+            | Operator::Equals
+            | Operator::Distinct => args.iter().any(|a| is_real(self.pool, a)),
+            _ => false,
+        };
+        if !expects_real {
+            return args;
+        }
+        args.into_iter()
+            .map(|arg| {
+                if self.pool.sort(&arg).as_sort() != Some(&Sort::Int) {
+                    return arg;
                 }
-            }
-            Operator::BvBbTerm => {
-                assert_num_args(&args, 1..)?;
-                SortError::assert_eq(&Sort::Bool, sorts[0])?;
-                SortError::assert_all_eq(&sorts)?;
-            }
-            Operator::BvConcat => {
-                assert_num_args(&args, 2..)?;
-                for s in sorts {
-                    if !matches!(s, Sort::BitVec(_)) {
-                        return Err(ParserError::ExpectedBvSort(s.clone()));
+                match arg.as_ref() {
+                    Term::Const(Constant::Integer(i)) => {
+                        self.pool.add(Term::new_real(Rational::from(i)))
                     }
+                    _ => self.pool.add(Term::Op(Operator::ToReal, vec![arg])),
                 }
-            }
-            Operator::BvAdd
-            | Operator::BvMul
-            | Operator::BvAnd
-            | Operator::BvOr
-            | Operator::BvXor => {
-                assert_num_args(&args, 2..)?;
-                if !matches!(sorts[0], Sort::BitVec(_)) {
-                    return Err(ParserError::ExpectedBvSort(sorts[0].clone()));
-                }
-                SortError::assert_all_eq(&sorts)?;
-            }
-            Operator::BvUDiv
-            | Operator::BvURem
-            | Operator::BvShl
-            | Operator::BvLShr
-            | Operator::BvULt
-            | Operator::BvNAnd
-            | Operator::BvNOr
-            | Operator::BvXNor
-            | Operator::BvComp
-            | Operator::BvSub
-            | Operator::BvSDiv
-            | Operator::BvSRem
-            | Operator::BvSMod
-            | Operator::BvAShr
-            | Operator::BvULe
-            | Operator::BvUGt
-            | Operator::BvUGe
-            | Operator::BvSLt
-            | Operator::BvSLe
-            | Operator::BvSGt
-            | Operator::BvSGe => {
-                assert_num_args(&args, 2)?;
-                if !matches!(sorts[0], Sort::BitVec(_)) {
-                    return Err(ParserError::ExpectedBvSort(sorts[0].clone()));
-                }
-                SortError::assert_all_eq(&sorts)?;
-            }
-            Operator::RareList => SortError::assert_all_eq(&sorts)?,
-        }
-        Ok(self.pool.add(Term::Op(op, args)))
+            })
+            .collect()
     }
 
     fn interpret_div_as_real_lit(&mut self, a: &Rc<Term>, b: &Rc<Term>) -> Option<Rc<Term>> {
@@ -505,23 +731,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
         function: Rc<Term>,
         args: Vec<Rc<Term>>,
     ) -> Result<Rc<Term>, ParserError> {
-        let sort = self.pool.sort(&function);
-        let sorts = {
-            let function_sort = sort.as_sort().unwrap();
-            if let Sort::Function(sorts) = function_sort {
-                sorts
-            } else {
-                // Function does not have function sort
-                return Err(ParserError::NotAFunction(function_sort.clone()));
-            }
-        };
-        assert_num_args(&args, sorts.len() - 1)?;
-        for i in 0..args.len() {
-            SortError::assert_eq(
-                sorts[i].as_sort().unwrap(),
-                self.pool.sort(&args[i]).as_sort().unwrap(),
-            )?;
-        }
+        check_app(self.pool, &function, &args)?;
         Ok(self.pool.add(Term::App(function, args)))
     }
 
@@ -628,6 +838,8 @@ impl<'a, R: BufRead> Parser<'a, R> {
                 | Token::Decimal(_)
                 | Token::Bitvector { .. }
                 | Token::String(_)
+                | Token::SharedTermDefinition(_)
+                | Token::SharedTermReference(_)
                 | Token::ReservedWord(_) => {
                     self.next_token()?;
                 }
@@ -667,6 +879,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
                 Token::ReservedWord(Reserved::DeclareFun) => {
                     let (name, sort) = self.parse_declare_fun()?;
                     self.insert_sorted_var((name.clone(), sort.clone()));
+                    self.pool.reserve_name(name.clone());
                     self.prelude().function_declarations.push((name, sort));
                 }
                 Token::ReservedWord(Reserved::DeclareConst) => {
@@ -674,6 +887,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
                     let sort = self.parse_sort()?;
                     self.expect_token(Token::CloseParen)?;
                     self.insert_sorted_var((name.clone(), sort.clone()));
+                    self.pool.reserve_name(name.clone());
                     self.prelude().function_declarations.push((name, sort));
                 }
                 Token::ReservedWord(Reserved::DeclareSort) => {
@@ -685,6 +899,12 @@ impl<'a, R: BufRead> Parser<'a, R> {
                     // argument which is a string terminal representing the sort name.
                     self.state.sort_declarations.insert(name, arity);
                 }
+                Token::ReservedWord(Reserved::DeclareDatatype) => {
+                    self.parse_declare_datatypes(true)?;
+                }
+                Token::ReservedWord(Reserved::DeclareDatatypes) => {
+                    self.parse_declare_datatypes(false)?;
+                }
                 Token::ReservedWord(Reserved::DefineFun) => {
                     let (name, func_def) = self.parse_define_fun()?;
 
@@ -693,39 +913,34 @@ impl<'a, R: BufRead> Parser<'a, R> {
                     } else {
                         // If `self.apply_function_defs` is false, we instead add the function name
                         // to the symbol table, and add a new premise that defines the function
-                        let lambda_term = if func_def.params.is_empty() {
-                            func_def.body
-                        } else {
-                            self.pool.add(Term::Binder(
-                                Binder::Lambda,
-                                BindingList(func_def.params),
-                                func_def.body,
-                            ))
-                        };
-                        let sort = self.pool.sort(&lambda_term);
-                        let var = (name, sort);
-                        self.insert_sorted_var(var.clone());
-                        let var_term = self.pool.add(var.into());
-                        let assertion_term = self
-                            .pool
-                            .add(Term::Op(Operator::Equals, vec![var_term, lambda_term]));
-                        self.premises().insert(assertion_term);
+                        self.add_function_def_premise(name, func_def);
                     }
                 }
                 Token::ReservedWord(Reserved::DefineFunRec) => self.parse_define_fun_rec(false)?,
                 Token::ReservedWord(Reserved::DefineFunsRec) => self.parse_define_fun_rec(true)?,
                 Token::ReservedWord(Reserved::DefineSort) => {
                     let (name, def) = self.parse_define_sort()?;
+                    self.prelude().sort_definitions.push(SortDefinition {
+                        name: name.clone(),
+                        params: def.params.clone(),
+                        body: def.body.clone(),
+                    });
                     self.state.sort_defs.insert(name, def);
                 }
                 Token::ReservedWord(Reserved::Assert) => {
+                    let position = self.current_position;
                     let term = self.parse_term()?;
+                    self.sort_check_premise(&term, position)?;
                     self.expect_token(Token::CloseParen)?;
                     self.premises().insert(term);
                 }
                 Token::ReservedWord(Reserved::CheckSatAssuming) => {
+                    let position = self.current_position;
                     self.expect_token(Token::OpenParen)?;
                     let terms = self.parse_sequence(Self::parse_term, true)?;
+                    for term in &terms {
+                        self.sort_check_premise(term, position)?;
+                    }
                     self.expect_token(Token::CloseParen)?;
                     self.premises().extend(terms);
                 }
@@ -742,12 +957,70 @@ impl<'a, R: BufRead> Parser<'a, R> {
                         (logic.contains("LRA") || logic.contains("NRA") || logic.contains("RDL"))
                             && !logic.contains('I');
                 }
+                Token::Symbol(s) if s == "set-info" => self.parse_set_info()?,
                 _ => {
                     // If the command is not one of the commands we care about, we just ignore it.
                     // We do that by reading tokens until the command parenthesis is closed
                     self.ignore_until_close_parens()?;
                 }
             }
+            self.check_pool_size()?;
+        }
+        Ok(self.problem.take().unwrap())
+    }
+
+    /// Adds the function name to the symbol table, and a premise that defines the function as equal
+    /// to its body (or to a lambda term, if it has parameters).
+    fn add_function_def_premise(&mut self, name: String, func_def: FunctionDef) {
+        let lambda_term = if func_def.params.is_empty() {
+            func_def.body
+        } else {
+            self.pool.add(Term::Binder(
+                Binder::Lambda,
+                BindingList(func_def.params),
+                func_def.body,
+            ))
+        };
+        let sort = self.pool.sort(&lambda_term);
+        self.pool.reserve_name(name.clone());
+        let var = (name, sort);
+        self.insert_sorted_var(var.clone());
+        let var_term = self.pool.add(var.into());
+        let assertion_term = self
+            .pool
+            .add(Term::Op(Operator::Equals, vec![var_term, lambda_term]));
+        self.premises().insert(assertion_term);
+    }
+
+    /// Parses a list of `define-fun` commands that extend the problem with fresh symbols, like the
+    /// variables introduced by definitional CNF. Each defined symbol must not be declared or defined
+    /// in the problem or in an earlier definition, and its body may only use symbols that were
+    /// already declared, so the definitions preserve the satisfiability of the problem. For each
+    /// definition, the symbol is declared and a premise equating it to its body is added to
+    /// `problem`, regardless of the `apply_function_defs` option.
+    pub fn parse_definitions(&mut self, problem: Problem) -> CarcaraResult<Problem> {
+        self.problem = Some(problem);
+        while self.current_token != Token::Eof {
+            self.expect_token(Token::OpenParen)?;
+            match self.next_token()? {
+                (Token::ReservedWord(Reserved::DefineFun), _) => (),
+                (other, pos) => {
+                    return Err(Error::Parser(ParserError::UnexpectedToken(other), pos))
+                }
+            }
+            let position = self.current_position;
+            let (name, func_def) = self.parse_define_fun()?;
+            let cached = HashCache::new(name);
+            let is_declared = self.state.symbol_table.get(&cached).is_some()
+                || self.state.function_defs.contains_key(cached.as_ref())
+                || self.state.constructors.contains(cached.as_ref());
+            let name = cached.unwrap();
+            if is_declared {
+                let err = ParserError::DefinitionIsNotFresh(name);
+                return Err(Error::Parser(err, position));
+            }
+            self.add_function_def_premise(name, func_def);
+            self.check_pool_size()?;
         }
         Ok(self.problem.take().unwrap())
     }
@@ -756,19 +1029,73 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// should already be in the parser state. Note that the `premises` field in the proof will not
     /// be set.
     pub fn parse_proof(&mut self) -> CarcaraResult<Proof> {
-        // To avoid stack overflows in proofs with many nested subproofs, we parse the subproofs
-        // iteratively, instead of recursively. Therefore, we need to manually keep a stack.
-        //
-        // Each frame of the stack stores the subproof that is being constructed, and the id of the
-        // step that will end it. The first frame of the stack represents the root proof, so every
-        // field except for the subproof commands is irrelevant.
-        let mut stack: Vec<(Subproof, String)> = vec![(Subproof::default(), String::new())];
+        let mut commands = Vec::new();
+        let constant_definitions = self.parse_proof_streaming(|_, command| {
+            commands.push(command);
+            Ok(())
+        })?;
+        Ok(Proof { constant_definitions, commands })
+    }
+
+    /// Parses a proof in the Alethe format, calling `on_command` for each root-level command as
+    /// soon as it is parsed, instead of collecting all commands in a vector. Subproofs are passed
+    /// to `on_command` as a single command, once their last step is parsed. The callback also
+    /// receives the parser's term pool. Returns the constants defined in the proof.
+    ///
+    /// This allows the proof to be processed without keeping all of its commands in memory.
+    pub fn parse_proof_streaming<F>(
+        &mut self,
+        on_command: F,
+    ) -> CarcaraResult<Vec<(String, Rc<Term>)>>
+    where
+        F: FnMut(&mut PrimitivePool, ProofCommand) -> CarcaraResult<()>,
+    {
+        self.parse_proof_commands(on_command, None)
+    }
 
-        let mut next_subproof_context_id = 0;
+    /// Parses a proof in the Alethe format like [`Parser::parse_proof`], but recovers from syntax
+    /// errors instead of stopping at the first one. When a command can't be parsed, the error is
+    /// recorded and the parser skips to the next top-level command. Returns the proof containing
+    /// all commands that were parsed successfully, and the errors that were found, in order.
+    ///
+    /// Commands that reference a command that could not be parsed will also result in errors. If a
+    /// command has unbalanced parentheses, the parser resumes at the next token sequence that
+    /// starts a proof command, like `(step`. Errors that are not syntax errors, like IO errors or
+    /// exceeded size limits, still stop the parser.
+    pub fn parse_proof_with_recovery(&mut self) -> CarcaraResult<(Proof, Vec<ParserDiagnostic>)> {
+        let mut commands = Vec::new();
+        let mut diagnostics = Vec::new();
+        let constant_definitions = self.parse_proof_commands(
+            |_, command| {
+                commands.push(command);
+                Ok(())
+            },
+            Some(&mut diagnostics),
+        )?;
+        Ok((Proof { constant_definitions, commands }, diagnostics))
+    }
 
-        let mut finished_assumes = false;
+    /// Parses the commands of a proof, calling `on_command` for each root-level command. If
+    /// `diagnostics` is `Some`, syntax errors are recorded in it instead of being returned.
+    fn parse_proof_commands<F>(
+        &mut self,
+        mut on_command: F,
+        diagnostics: Option<&mut Vec<ParserDiagnostic>>,
+    ) -> CarcaraResult<Vec<(String, Rc<Term>)>>
+    where
+        F: FnMut(&mut PrimitivePool, ProofCommand) -> CarcaraResult<()>,
+    {
+        let mut state = ProofParsingState {
+            stack: vec![(Subproof::default(), String::new())],
+            next_subproof_context_id: 0,
+            num_root_commands: 0,
+            num_commands: 0,
+            finished_assumes: false,
+            constant_definitions: Vec::new(),
+        };
 
-        let mut constant_definitions = Vec::new();
+        // Step ids are local to each proof
+        self.state.step_ids = HashMapStack::new();
 
         // Some solvers print the satisfiability result (unsat) together with the proof. To save the
         // user from having to remove this, we consume this first "unsat" token if it exists
@@ -776,114 +1103,277 @@ impl<'a, R: BufRead> Parser<'a, R> {
             self.next_token()?;
         }
 
-        while self.current_token != Token::Eof {
-            self.expect_token(Token::OpenParen)?;
-            let (token, position) = self.next_token()?;
-            let (id, command) = match token {
-                Token::ReservedWord(Reserved::Assume) => {
-                    let (id, term) = self.parse_assume_command()?;
-                    if stack.len() == 1 && finished_assumes {
-                        log::warn!("`assume` command '{}' appears after `step` commands", &id);
-                    }
-                    (id.clone(), ProofCommand::Assume { id, term })
-                }
-                Token::ReservedWord(Reserved::Step) => {
-                    finished_assumes = true;
-                    let step = self.parse_step_command()?;
-                    (step.id.clone(), ProofCommand::Step(step))
+        let Some(diagnostics) = diagnostics else {
+            while self.current_token != Token::Eof {
+                self.expect_token(Token::OpenParen)?;
+                self.parse_proof_command(&mut state, &mut on_command)?;
+            }
+            self.check_subproofs_closed(&state)?;
+            return Ok(state.constant_definitions);
+        };
+
+        // In recovery mode, the opening parenthesis of the next command may have already been
+        // consumed while skipping a malformed command
+        let mut consumed_paren = false;
+        while consumed_paren || self.current_token != Token::Eof {
+            self.lexer.record_lines(true);
+            let symbol_table_height = self.state.symbol_table.height();
+            let result = if consumed_paren {
+                self.parse_proof_command(&mut state, &mut on_command)
+            } else {
+                self.expect_token(Token::OpenParen)
+                    .and_then(|_| self.parse_proof_command(&mut state, &mut on_command))
+            };
+            match result {
+                Ok(()) => consumed_paren = false,
+                Err(Error::Parser(ParserError::SizeLimit(e), pos)) => {
+                    return Err(Error::Parser(ParserError::SizeLimit(e), pos));
                 }
-                Token::ReservedWord(Reserved::DefineFun) => {
-                    let (name, func_def) = self.parse_define_fun()?;
-                    if func_def.params.is_empty() {
-                        constant_definitions.push((name.clone(), func_def.body.clone()));
+                Err(Error::Parser(error, position)) => {
+                    self.push_diagnostic(diagnostics, error, position)?;
+
+                    // Any scopes that were opened by the malformed command must be closed
+                    while self.state.symbol_table.height() > symbol_table_height {
+                        self.state.symbol_table.pop_scope();
                     }
-                    self.state.function_defs.insert(name, func_def);
-                    continue;
+                    self.state.let_values.clear();
+                    self.state.binder_names.clear();
+                    consumed_paren = self.skip_to_next_command(diagnostics)?;
                 }
-                Token::ReservedWord(Reserved::Anchor) => {
-                    let (end_step_id, args) = self.parse_anchor_command()?;
-
-                    // When we encounter an `anchor` command, we push a new scope into the step ids
-                    // symbol table, a fresh commands vector into the commands stack for the
-                    // subproof to fill, and the `anchor` data (end step and arguments) into their
-                    // respective stacks. All of this will be popped off at the end of the subproof.
-                    // We don't need to push a new scope into the symbol table because
-                    // `Parser::parse_anchor_command` already does that for us
-                    self.state.step_ids.push_scope();
-                    let subproof = Subproof {
-                        commands: Vec::new(),
-                        args,
-                        context_id: next_subproof_context_id,
-                    };
-                    stack.push((subproof, end_step_id));
-                    next_subproof_context_id += 1;
-                    continue;
+                Err(e) => return Err(e),
+            }
+        }
+        self.lexer.record_lines(false);
+
+        if let Err(Error::Parser(error, position)) = self.check_subproofs_closed(&state) {
+            self.push_diagnostic(diagnostics, error, position)?;
+        }
+        Ok(state.constant_definitions)
+    }
+
+    /// Returns an error if there is a subproof that was not closed at the end of the proof.
+    fn check_subproofs_closed(&self, state: &ProofParsingState) -> CarcaraResult<()> {
+        // If there is more than one layer in the stack, we are inside a subproof that should be
+        // closed before the outer proof is finished
+        if let [_, .., (_, end_step_id)] = state.stack.as_slice() {
+            return Err(Error::Parser(
+                ParserError::UnclosedSubproof(end_step_id.clone()),
+                self.current_position,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Records an error in `diagnostics`. If the error happened in the lexer, this also makes sure
+    /// the lexer skips the offending character, so the same error isn't found again.
+    fn push_diagnostic(
+        &mut self,
+        diagnostics: &mut Vec<ParserDiagnostic>,
+        error: ParserError,
+        position: Position,
+    ) -> CarcaraResult<()> {
+        let offset = self.lexer.offset_of(position);
+        if matches!(error, ParserError::UnexpectedChar(_)) {
+            self.lexer.skip_char()?;
+        }
+        diagnostics.push(ParserDiagnostic { error, position, offset });
+        Ok(())
+    }
+
+    /// Consumes tokens until the start of the next top-level command, recording any lexer errors
+    /// found along the way. A command starts either with an opening parenthesis that is not nested
+    /// in any other, or with an opening parenthesis followed by a proof command keyword. In the
+    /// second case, the parenthesis is consumed, and this returns `true`.
+    fn skip_to_next_command(
+        &mut self,
+        diagnostics: &mut Vec<ParserDiagnostic>,
+    ) -> CarcaraResult<bool> {
+        use Reserved::*;
+
+        loop {
+            match &self.current_token {
+                Token::Eof => return Ok(false),
+                Token::OpenParen if self.paren_depth == 0 => return Ok(false),
+                Token::ReservedWord(Assume | Step | Anchor | DefineFun)
+                    if self.paren_depth > 0 && self.after_open_paren =>
+                {
+                    self.paren_depth = 1;
+                    return Ok(true);
                 }
-                _ => {
-                    return Err(Error::Parser(ParserError::UnexpectedToken(token), position));
+                _ => (),
+            }
+            match self.next_token() {
+                Ok(_) => (),
+                Err(Error::Parser(error, position)) => {
+                    self.push_diagnostic(diagnostics, error, position)?;
                 }
-            };
-            let id = HashCache::new(id);
-            if self.state.step_ids.get(&id).is_some() {
-                return Err(Error::Parser(
-                    ParserError::RepeatedStepId(id.unwrap()),
-                    position,
-                ));
+                Err(e) => return Err(e),
             }
+        }
+    }
 
-            let (top_subproof, top_end_step) = stack.last_mut().unwrap();
-            top_subproof.commands.push(command);
-            if top_end_step == id.as_ref() {
-                // If this is the last step in a subproof, we need to pop all the subproof data off
-                // of the stacks and build the subproof command with it
-                self.state.symbol_table.pop_scope();
-                self.state.step_ids.pop_scope();
-                let (subproof, _) = stack.pop().unwrap();
-
-                // The subproof must contain at least two commands: the end step and the previous
-                // command it implicitly references
-                if subproof.commands.len() < 2 {
-                    return Err(Error::Parser(
-                        ParserError::EmptySubproof(id.unwrap()),
-                        position,
-                    ));
+    /// Parses a single proof command. This method assumes that the opening parenthesis of the
+    /// command was already consumed.
+    fn parse_proof_command<F>(
+        &mut self,
+        state: &mut ProofParsingState,
+        on_command: &mut F,
+    ) -> CarcaraResult<()>
+    where
+        F: FnMut(&mut PrimitivePool, ProofCommand) -> CarcaraResult<()>,
+    {
+        let start = self.previous_span.start;
+        let mut location = CommandLocation::default();
+        let (token, position) = self.next_token()?;
+        let (id, command) = match token {
+            Token::ReservedWord(Reserved::Assume) => {
+                state.num_commands += 1;
+                self.check_command_count(state.num_commands, position)?;
+                let (id, term) = self.parse_assume_command(&mut location)?;
+                if state.stack.len() == 1 && state.finished_assumes {
+                    tracing::warn!("`assume` command '{}' appears after `step` commands", &id);
                 }
-
-                // We also need to make sure that the last command is in fact a `step`
-                if !subproof.commands.last().unwrap().is_step() {
-                    return Err(Error::Parser(
-                        ParserError::LastSubproofStepIsNotStep(id.unwrap()),
-                        position,
-                    ));
+                (id.clone(), ProofCommand::Assume { id, term })
+            }
+            Token::ReservedWord(Reserved::Step) => {
+                state.finished_assumes = true;
+                state.num_commands += 1;
+                self.check_command_count(state.num_commands, position)?;
+                let step = self.parse_step_command(&mut location)?;
+                (step.id.clone(), ProofCommand::Step(step))
+            }
+            Token::ReservedWord(Reserved::DefineFun) => {
+                let (name, func_def) = self.parse_define_fun()?;
+                if func_def.params.is_empty() {
+                    state
+                        .constant_definitions
+                        .push((name.clone(), func_def.body.clone()));
+                }
+                self.state.function_defs.insert(name, func_def);
+                return Ok(());
+            }
+            Token::ReservedWord(Reserved::Anchor) => {
+                let (end_step_id, args) = self.parse_anchor_command()?;
+                if let Some(checker) = &mut self.sort_checker {
+                    checker
+                        .check_anchor_args(self.pool, &args)
+                        .map_err(|e| Error::Parser(e, position))?;
                 }
 
-                let (outer, _) = stack.last_mut().unwrap();
-                outer.commands.push(ProofCommand::Subproof(subproof));
+                // When we encounter an `anchor` command, we push a new scope into the step ids
+                // symbol table, a fresh commands vector into the commands stack for the subproof
+                // to fill, and the `anchor` data (end step and arguments) into their respective
+                // stacks. All of this will be popped off at the end of the subproof. We don't need
+                // to push a new scope into the symbol table because `Parser::parse_anchor_command`
+                // already does that for us
+                self.state.step_ids.push_scope();
+                let subproof = Subproof {
+                    commands: Vec::new(),
+                    args,
+                    context_id: state.next_subproof_context_id,
+                };
+                state.stack.push((subproof, end_step_id));
+                state.next_subproof_context_id += 1;
+                return Ok(());
             }
-            let index = stack.last().unwrap().0.commands.len() - 1;
-            self.state.step_ids.insert(id, index);
+            Token::Symbol(s) if s == "set-info" => return self.parse_set_info(),
+            _ => {
+                return Err(Error::Parser(ParserError::UnexpectedToken(token), position));
+            }
+        };
+        self.check_pool_size()?;
+        if let Some(checker) = &mut self.sort_checker {
+            checker
+                .check_command(self.pool, &command)
+                .map_err(|e| Error::Parser(e, position))?;
         }
-        let commands = match stack.len() {
-            0 => unreachable!(),
-            1 => stack.pop().unwrap().0.commands,
 
-            // If there is more than one layer in the stack, we are inside a subproof that should be
-            // closed before the outer proof is finished
-            _ => {
+        let id = HashCache::new(id);
+        if self.state.step_ids.get(&id).is_some() {
+            return Err(Error::Parser(
+                ParserError::RepeatedStepId(id.unwrap()),
+                position,
+            ));
+        }
+
+        if let Some(source_map) = &mut self.source_map {
+            location.span = Span { start, end: self.previous_span.end };
+            source_map.insert(id.as_ref().clone(), location);
+        }
+
+        let (top_subproof, top_end_step) = state.stack.last_mut().unwrap();
+        let command = if top_end_step == id.as_ref() {
+            top_subproof.commands.push(command);
+            // If this is the last step in a subproof, we need to pop all the subproof data off of
+            // the stacks and build the subproof command with it
+            self.state.symbol_table.pop_scope();
+            self.state.step_ids.pop_scope();
+            let (subproof, _) = state.stack.pop().unwrap();
+
+            // The subproof must contain at least two commands: the end step and the previous
+            // command it implicitly references
+            if subproof.commands.len() < 2 {
                 return Err(Error::Parser(
-                    ParserError::UnclosedSubproof(stack.pop().unwrap().1),
-                    self.current_position,
-                ))
+                    ParserError::EmptySubproof(id.unwrap()),
+                    position,
+                ));
+            }
+
+            // We also need to make sure that the last command is in fact a `step`
+            if !subproof.commands.last().unwrap().is_step() {
+                return Err(Error::Parser(
+                    ParserError::LastSubproofStepIsNotStep(id.unwrap()),
+                    position,
+                ));
             }
+
+            ProofCommand::Subproof(subproof)
+        } else {
+            command
         };
-        Ok(Proof { constant_definitions, commands })
+
+        let index = if state.stack.len() == 1 {
+            let mut command = command;
+            self.config.dialect.normalize(&mut command);
+            on_command(self.pool, command)?;
+            state.num_root_commands += 1;
+            state.num_root_commands - 1
+        } else {
+            let commands = &mut state.stack.last_mut().unwrap().0.commands;
+            commands.push(command);
+            commands.len() - 1
+        };
+        self.state.step_ids.insert(id, index);
+        Ok(())
+    }
+
+    /// Returns an error if the number of proof commands parsed so far exceeds the configured limit.
+    fn check_command_count(&self, num_commands: usize, position: Position) -> CarcaraResult<()> {
+        self.config
+            .limits
+            .check_commands(num_commands)
+            .map_err(|e| Error::Parser(e.into(), position))
+    }
+
+    /// Returns an error if the number of terms in the pool exceeds the configured limit.
+    fn check_pool_size(&self) -> CarcaraResult<()> {
+        self.config
+            .limits
+            .check_pool(self.pool)
+            .map_err(|e| Error::Parser(e.into(), self.current_position))
     }
 
     /// Parses an `assume` proof command. This method assumes that the `(` and `assume` tokens were
     /// already consumed.
-    fn parse_assume_command(&mut self) -> CarcaraResult<(String, Rc<Term>)> {
+    fn parse_assume_command(
+        &mut self,
+        location: &mut CommandLocation,
+    ) -> CarcaraResult<(String, Rc<Term>)> {
         let id = self.expect_symbol()?;
+        location.id = self.previous_span;
+        let start = self.current_position;
         let term = self.parse_term_expecting_sort(&Sort::Bool)?;
+        self.push_span(&mut location.clause, start);
         self.ignore_remaining_attributes()?;
         self.expect_token(Token::CloseParen)?;
         Ok((id, term))
@@ -891,9 +1381,20 @@ impl<'a, R: BufRead> Parser<'a, R> {
 
     /// Parses a `step` proof command. This method assumes that the `(` and `step` tokens were
     /// already consumed.
-    fn parse_step_command(&mut self) -> CarcaraResult<ProofStep> {
+    fn parse_step_command(&mut self, location: &mut CommandLocation) -> CarcaraResult<ProofStep> {
         let id = self.expect_symbol()?;
-        let clause = self.parse_clause()?;
+        location.id = self.previous_span;
+        self.expect_token(Token::OpenParen)?;
+        self.expect_token(Token::ReservedWord(Reserved::Cl))?;
+        let clause = self.parse_sequence(
+            |p| {
+                let start = p.current_position;
+                let term = p.parse_term_expecting_sort(&Sort::Bool)?;
+                p.push_span(&mut location.clause, start);
+                Ok(term)
+            },
+            false,
+        )?;
         self.expect_token(Token::Keyword("rule".into()))?;
         let rule = match self.next_token()? {
             (Token::Symbol(s), _) => s,
@@ -906,7 +1407,15 @@ impl<'a, R: BufRead> Parser<'a, R> {
         let premises = if self.current_token == Token::Keyword("premises".into()) {
             self.next_token()?;
             self.expect_token(Token::OpenParen)?;
-            self.parse_sequence(Self::parse_step_premise, true)?
+            self.parse_sequence(
+                |p| {
+                    let start = p.current_position;
+                    let premise = p.parse_step_premise()?;
+                    p.push_span(&mut location.premises, start);
+                    Ok(premise)
+                },
+                true,
+            )?
         } else {
             Vec::new()
         };
@@ -922,7 +1431,15 @@ impl<'a, R: BufRead> Parser<'a, R> {
                 self.ignore_until_close_parens()?;
                 Vec::new()
             } else {
-                self.parse_sequence(Self::parse_term, true)?
+                self.parse_sequence(
+                    |p| {
+                        let start = p.current_position;
+                        let term = p.parse_step_arg()?;
+                        p.push_span(&mut location.args, start);
+                        Ok(term)
+                    },
+                    true,
+                )?
             }
         } else {
             Vec::new()
@@ -933,7 +1450,15 @@ impl<'a, R: BufRead> Parser<'a, R> {
         let discharge = if self.current_token == Token::Keyword("discharge".into()) {
             self.next_token()?;
             self.expect_token(Token::OpenParen)?;
-            self.parse_sequence(|p| p.parse_discharge_premise(&id), true)?
+            self.parse_sequence(
+                |p| {
+                    let start = p.current_position;
+                    let premise = p.parse_discharge_premise(&id)?;
+                    p.push_span(&mut location.discharge, start);
+                    Ok(premise)
+                },
+                true,
+            )?
         } else {
             Vec::new()
         };
@@ -951,6 +1476,14 @@ impl<'a, R: BufRead> Parser<'a, R> {
         })
     }
 
+    /// If locations are being tracked, pushes to `spans` the span from `start` to the end of the
+    /// last token consumed.
+    fn push_span(&self, spans: &mut Vec<Span>, start: Position) {
+        if self.source_map.is_some() {
+            spans.push(Span { start, end: self.previous_span.end });
+        }
+    }
+
     /// Parses a premise for a `step` command. This already converts it into the depth and command
     /// index used to reference commands in the AST.
     fn parse_step_premise(&mut self) -> CarcaraResult<(usize, usize)> {
@@ -1019,18 +1552,20 @@ impl<'a, R: BufRead> Parser<'a, R> {
             // parsing the two versions of assign-style anchor arguments:
             // - the old version, without the sort hint: `(:= <symbol> <term>)`
             // - and the new version, with the sort hint: `(:= (<symbol> <sort>) <term>)`
-            // However, if "strict" parsing is enabled, we only allow the new version
-            let (var, value, sort) =
-                if !self.config.strict && matches!(self.current_token, Token::Symbol(_)) {
-                    let var = self.expect_symbol()?;
-                    let value = self.parse_term()?;
-                    let sort = self.pool.sort(&value);
-                    (var, value, sort)
-                } else {
-                    let (var, sort) = self.parse_sorted_var()?;
-                    let value = self.parse_term_expecting_sort(sort.as_sort().unwrap())?;
-                    (var, value, sort)
-                };
+            // However, if "strict" parsing is enabled, or the proof is in version 1.1 of the format,
+            // we only allow the new version
+            let (var, value, sort) = if self.allows_old_assignments()
+                && matches!(self.current_token, Token::Symbol(_))
+            {
+                let var = self.expect_symbol()?;
+                let value = self.parse_term()?;
+                let sort = self.pool.sort(&value);
+                (var, value, sort)
+            } else {
+                let (var, sort) = self.parse_sorted_var()?;
+                let value = self.parse_term_expecting_sort(sort.as_sort().unwrap())?;
+                (var, value, sort)
+            };
             self.insert_sorted_var((var.clone(), sort.clone()));
             self.expect_token(Token::CloseParen)?;
             AnchorArg::Assign((var, sort), value)
@@ -1043,6 +1578,66 @@ impl<'a, R: BufRead> Parser<'a, R> {
         })
     }
 
+    /// Parses an argument of a `step` command. In version 1.0 of the Alethe format, arguments may
+    /// also be assignments of the form `(:= <symbol> <term>)`, in which case only the assigned term
+    /// is returned.
+    fn parse_step_arg(&mut self) -> CarcaraResult<Rc<Term>> {
+        if self.current_token != Token::OpenParen || !self.allows_old_assignments() {
+            return self.parse_term();
+        }
+        self.next_token()?;
+        if self.current_token != Token::Keyword("=".into()) {
+            return self.parse_application();
+        }
+        self.next_token()?;
+        self.expect_symbol()?;
+        let value = self.parse_term()?;
+        self.expect_token(Token::CloseParen)?;
+        Ok(value)
+    }
+
+    /// Returns the version of the Alethe format that the proof is parsed with, if it is known.
+    pub fn alethe_version(&self) -> Option<AletheVersion> {
+        self.config.alethe_version.or(self.declared_version)
+    }
+
+    /// Returns `true` if assignments using the syntax of version 1.0 of the Alethe format are
+    /// allowed, in `anchor` and `step` arguments.
+    fn allows_old_assignments(&self) -> bool {
+        match self.alethe_version() {
+            Some(version) => version == AletheVersion::V1_0,
+            None => !self.config.strict,
+        }
+    }
+
+    /// Parses a `set-info` command. The `:alethe-version` attribute sets the version of the Alethe
+    /// format, unless it was given in the parser configuration, and any other attributes are
+    /// ignored. This method assumes that the `(` and `set-info` tokens were already consumed.
+    fn parse_set_info(&mut self) -> CarcaraResult<()> {
+        if self.current_token != Token::Keyword("alethe-version".into()) {
+            return self.ignore_until_close_parens();
+        }
+        self.next_token()?;
+        let (token, position) = self.next_token()?;
+        let version = match token {
+            Token::Symbol(s) | Token::String(s) => s.parse(),
+            Token::Numeral(n) => n.to_string().parse(),
+            // Decimals are read as exact fractions, so `1.1` becomes 11/10
+            Token::Decimal(r) => match (r.numer().to_u32(), r.denom().to_u32()) {
+                (Some(1), Some(1)) => Ok(AletheVersion::V1_0),
+                (Some(11), Some(10)) => Ok(AletheVersion::V1_1),
+                _ => Err(ParserError::UnknownAletheVersion(r.to_f64().to_string())),
+            },
+            other => Err(ParserError::UnexpectedToken(other)),
+        };
+        let version = version.map_err(|e| Error::Parser(e, position))?;
+        self.expect_token(Token::CloseParen)?;
+        if self.declared_version.is_none() {
+            self.declared_version = Some(version);
+        }
+        Ok(())
+    }
+
     /// Parses a `declare-fun` proof command. Returns the function name and a term representing its
     /// sort. This method assumes that the `(` and `declare-fun` tokens were already consumed.
     fn parse_declare_fun(&mut self) -> CarcaraResult<(String, Rc<Term>)> {
@@ -1075,6 +1670,162 @@ impl<'a, R: BufRead> Parser<'a, R> {
         Ok((name, arity))
     }
 
+    /// Parses a `declare-datatype` command, if `single` is `true`, or a `declare-datatypes`
+    /// command otherwise. The datatype sorts are declared first, so the datatypes may be mutually
+    /// recursive. Then, the constructors and selectors are declared as functions, and the datatypes
+    /// are added to the problem prelude. This method assumes that the `(` and `declare-datatype` or
+    /// `declare-datatypes` tokens were already consumed.
+    fn parse_declare_datatypes(&mut self, single: bool) -> CarcaraResult<()> {
+        let names = if single {
+            vec![self.expect_symbol()?]
+        } else {
+            self.expect_token(Token::OpenParen)?;
+            self.parse_sequence(
+                |p| {
+                    p.expect_token(Token::OpenParen)?;
+                    let pos = p.current_position;
+                    let name = p.expect_symbol()?;
+                    if p.expect_numeral()? != 0 {
+                        return Err(Error::Parser(ParserError::ParametricDatatype(name), pos));
+                    }
+                    p.expect_token(Token::CloseParen)?;
+                    Ok(name)
+                },
+                true,
+            )?
+        };
+        for name in &names {
+            self.state.sort_declarations.insert(name.clone(), 0);
+        }
+
+        let constructor_lists = if single {
+            vec![self.parse_datatype_dec(&names[0])?]
+        } else {
+            self.expect_token(Token::OpenParen)?;
+            let mut i = 0;
+            self.parse_sequence(
+                |p| {
+                    let name = names.get(i).map_or("", String::as_str);
+                    i += 1;
+                    p.parse_datatype_dec(name)
+                },
+                true,
+            )?
+        };
+        self.expect_token(Token::CloseParen)?;
+        if constructor_lists.len() != names.len() {
+            return Err(Error::Parser(
+                ParserError::WrongNumberOfArgs(names.len().into(), constructor_lists.len()),
+                self.current_position,
+            ));
+        }
+
+        for (name, constructors) in names.into_iter().zip(constructor_lists) {
+            let sort = self
+                .pool
+                .add(Term::Sort(Sort::Atom(name.clone(), Vec::new())));
+            let mut datatype = DatatypeDef {
+                name,
+                sort: sort.clone(),
+                constructors: Vec::new(),
+            };
+            for (constructor_name, fields) in constructors {
+                let mut selectors = Vec::new();
+                let mut field_sorts = Vec::new();
+                for (selector_name, field_sort) in fields {
+                    let selector_sort = Sort::Function(vec![sort.clone(), field_sort.clone()]);
+                    let selector_sort = self.pool.add(Term::Sort(selector_sort));
+                    self.insert_sorted_var((selector_name.clone(), selector_sort.clone()));
+                    self.pool.reserve_name(selector_name.clone());
+                    selectors.push(self.pool.add(Term::new_var(selector_name, selector_sort)));
+                    field_sorts.push(field_sort);
+                }
+                let constructor_sort = if field_sorts.is_empty() {
+                    sort.clone()
+                } else {
+                    field_sorts.push(sort.clone());
+                    self.pool.add(Term::Sort(Sort::Function(field_sorts)))
+                };
+                self.insert_sorted_var((constructor_name.clone(), constructor_sort.clone()));
+                self.pool.reserve_name(constructor_name.clone());
+                self.state.constructors.insert(constructor_name.clone());
+                let constructor = self
+                    .pool
+                    .add(Term::new_var(constructor_name, constructor_sort));
+                datatype
+                    .constructors
+                    .push(ConstructorDef { constructor, selectors });
+            }
+            self.prelude().datatype_declarations.push(datatype);
+        }
+        Ok(())
+    }
+
+    /// Parses the declaration of a datatype's constructors, of the form
+    /// `((<symbol> (<symbol> <sort>)*)+)`. Returns each constructor name, together with the names
+    /// and sorts of its selectors.
+    #[allow(clippy::type_complexity)]
+    fn parse_datatype_dec(
+        &mut self,
+        name: &str,
+    ) -> CarcaraResult<Vec<(String, Vec<(String, Rc<Term>)>)>> {
+        let pos = self.current_position;
+        self.expect_token(Token::OpenParen)?;
+        if self.current_token == Token::Symbol("par".to_owned()) {
+            return Err(Error::Parser(
+                ParserError::ParametricDatatype(name.to_owned()),
+                pos,
+            ));
+        }
+        self.parse_sequence(
+            |p| {
+                p.expect_token(Token::OpenParen)?;
+                let constructor = p.expect_symbol()?;
+                let selectors = p.parse_sequence(
+                    |p| {
+                        p.expect_token(Token::OpenParen)?;
+                        let selector = p.expect_symbol()?;
+                        let sort = p.parse_sort()?;
+                        p.expect_token(Token::CloseParen)?;
+                        Ok((selector, sort))
+                    },
+                    false,
+                )?;
+                Ok((constructor, selectors))
+            },
+            true,
+        )
+    }
+
+    /// Parses a datatype tester application, of the form `((_ is <constructor>) <term>)`. This
+    /// method assumes that the first two `(` tokens and the `_` token were already consumed.
+    fn parse_tester(&mut self, head_pos: Position) -> CarcaraResult<Rc<Term>> {
+        self.expect_symbol()?; // The `is` symbol
+        let constructor = self.expect_symbol()?;
+        self.expect_token(Token::CloseParen)?;
+        let args = self.parse_sequence(Self::parse_term, true)?;
+        self.make_tester(constructor, args)
+            .map_err(|err| Error::Parser(err, head_pos))
+    }
+
+    /// Constructs and sort checks a datatype tester application.
+    fn make_tester(
+        &mut self,
+        constructor: String,
+        args: Vec<Rc<Term>>,
+    ) -> Result<Rc<Term>, ParserError> {
+        if !self.state.constructors.contains(&constructor) {
+            return Err(ParserError::NotAConstructor(constructor));
+        }
+        let constructor = self.make_var(constructor)?;
+        check_tester(self.pool, &constructor, &args)?;
+        Ok(self.pool.add(Term::ParamOp {
+            op: ParamOperator::DtTester,
+            op_args: vec![constructor],
+            args,
+        }))
+    }
+
     /// Parses a function declaration, of the form `(<symbol> (<sorted var>*) <sort>)`. If the
     /// parameter `consume_parens` is `false`, the opening and closing parentheses are not consumed
     fn parse_function_dec(
@@ -1142,7 +1893,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
     }
 
     /// Parses a `define-fun-rec`/`define-funs-rec` command. Inserts the function names into the
-    /// symbol table, and adds the appropriate premises. This method assumes the `(` and
+    /// symbol table and the problem prelude, and adds the appropriate premises. This method assumes the `(` and
     /// `define-fun-rec`/`define-funs-rec` tokens were already consumed.
     fn parse_define_fun_rec(&mut self, is_multiple: bool) -> CarcaraResult<()> {
         let declarations = if is_multiple {
@@ -1161,7 +1912,14 @@ impl<'a, R: BufRead> Parser<'a, R> {
                 Sort::Function(param_sorts)
             };
             let sort = self.pool.add(Term::Sort(sort));
-            self.insert_sorted_var((name.clone(), sort));
+            self.insert_sorted_var((name.clone(), sort.clone()));
+            self.pool.reserve_name(name.clone());
+
+            // Recursive functions are kept uninterpreted, so they must be declared in the prelude
+            // for the problem to be printed correctly
+            self.prelude()
+                .function_declarations
+                .push((name.clone(), sort));
         }
 
         if is_multiple {
@@ -1207,7 +1965,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
     }
 
     /// Parses a clause of the form `(cl <term>*)`.
-    fn parse_clause(&mut self) -> CarcaraResult<Vec<Rc<Term>>> {
+    pub fn parse_clause(&mut self) -> CarcaraResult<Vec<Rc<Term>>> {
         self.expect_token(Token::OpenParen)?;
         self.expect_token(Token::ReservedWord(Reserved::Cl))?;
         self.parse_sequence(|p| p.parse_term_expecting_sort(&Sort::Bool), false)
@@ -1224,6 +1982,16 @@ impl<'a, R: BufRead> Parser<'a, R> {
 
     /// Parses a term.
     pub fn parse_term(&mut self) -> CarcaraResult<Rc<Term>> {
+        let term = self.parse_term_unchecked()?;
+
+        // The pool size is checked after every term, instead of only after every command, so a
+        // single command can't exhaust the memory, for example when expanding many nested `let`s
+        self.check_pool_size()?;
+        Ok(term)
+    }
+
+    /// Parses a term, without checking the size limit of the pool.
+    fn parse_term_unchecked(&mut self) -> CarcaraResult<Rc<Term>> {
         let term = match self.next_token()? {
             (Token::Bitvector { value, width }, _) => Term::new_bv(value, width),
             (Token::Numeral(n), _) if self.interpret_ints_as_reals() => Term::new_real(n),
@@ -1245,6 +2013,19 @@ impl<'a, R: BufRead> Parser<'a, R> {
                 };
             }
             (Token::OpenParen, _) => return self.parse_application(),
+            (Token::SharedTermDefinition(n), _) => {
+                let term = self.parse_term()?;
+                self.state.shared_terms.insert(n, term.clone());
+                return Ok(term);
+            }
+            (Token::SharedTermReference(n), pos) => {
+                return self
+                    .state
+                    .shared_terms
+                    .get(&n)
+                    .cloned()
+                    .ok_or(Error::Parser(ParserError::UndefinedSharedTerm(n), pos));
+            }
             (other, pos) => {
                 return Err(Error::Parser(ParserError::UnexpectedToken(other), pos));
             }
@@ -1278,27 +2059,40 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// already consumed.
     fn parse_binder(&mut self, binder: Binder) -> CarcaraResult<Rc<Term>> {
         self.expect_token(Token::OpenParen)?;
+
+        // If we are inside `let` terms that are being expanded, the bound variables must not
+        // capture any of the free variables in the values of these `let`s
+        let mut should_be_renamed = IndexSet::new();
+        for (_, values) in &self.state.let_values {
+            for value in values.values() {
+                let free_vars = self.pool.free_vars(value);
+                should_be_renamed.extend(free_vars.iter().map(|v| v.as_var().unwrap().to_owned()));
+            }
+        }
+
         self.state.symbol_table.push_scope();
+        let num_binder_names = self.state.binder_names.len();
+        let mut parse_bound_var = |p: &mut Self| -> CarcaraResult<SortedVar> {
+            let pos = p.current_position;
+            let var = p.parse_sorted_var()?;
+            p.check_shadowing(&var.0, &mut should_be_renamed)
+                .map_err(|err| Error::Parser(err, pos))?;
+            p.state.binder_names.push(var.0.clone());
+            Ok(p.insert_bound_var(var, &mut should_be_renamed))
+        };
         let bindings = if binder == Binder::Choice {
-            let var = self.parse_sorted_var()?;
-            self.insert_sorted_var(var.clone());
+            let var = parse_bound_var(self)?;
             self.expect_token(Token::CloseParen)?;
             BindingList(vec![var])
         } else {
-            BindingList(self.parse_sequence(
-                |p| {
-                    let var = p.parse_sorted_var()?;
-                    p.insert_sorted_var(var.clone());
-                    Ok(var)
-                },
-                true,
-            )?)
+            BindingList(self.parse_sequence(parse_bound_var, true)?)
         };
         let term = match binder {
             Binder::Lambda => self.parse_term()?,
             _ => self.parse_term_expecting_sort(&Sort::Bool)?,
         };
-        self.state.symbol_table.pop_scope();
+        self.state.binder_names.truncate(num_binder_names);
+        self.pop_symbol_table_scope();
         self.expect_token(Token::CloseParen)?;
         Ok(self.pool.add(Term::Binder(binder, bindings, term)))
     }
@@ -1327,25 +2121,25 @@ impl<'a, R: BufRead> Parser<'a, R> {
             self.insert_sorted_var((name.clone(), sort));
         }
 
+        // If `let`s are expanded, we record the values of the bound variables, and replace each
+        // occurrence of them while parsing the inner term. Since the values themselves were parsed
+        // in the same way, nested `let`s are all expanded in a single pass
+        if self.config.expand_lets {
+            let depth = self.state.symbol_table.height() - 1;
+            let values = bindings
+                .iter()
+                .map(|(name, value)| (HashCache::new(name.clone()), value.clone()))
+                .collect();
+            self.state.let_values.push((depth, values));
+        }
+
         let inner = self.parse_term()?;
         self.expect_token(Token::CloseParen)?;
 
-        self.state.symbol_table.pop_scope();
+        self.pop_symbol_table_scope();
 
         if self.config.expand_lets {
-            let substitution = bindings
-                .into_iter()
-                .map(|(name, value)| {
-                    let var = Term::new_var(name, self.pool.sort(&value));
-                    (self.pool.add(var), value)
-                })
-                .collect();
-
-            let result = Substitution::new(self.pool, substitution)
-                .unwrap()
-                .apply(self.pool, &inner);
-
-            Ok(result)
+            Ok(inner)
         } else {
             Ok(self.pool.add(Term::Let(BindingList(bindings), inner)))
         }
@@ -1460,75 +2254,11 @@ impl<'a, R: BufRead> Parser<'a, R> {
         op_args: Vec<Constant>,
         args: Vec<Rc<Term>>,
     ) -> Result<Rc<Term>, ParserError> {
-        let sorts: Vec<_> = args.iter().map(|t| self.pool.sort(t)).collect();
-        let sorts: Vec<_> = sorts.iter().map(|s| s.as_sort().unwrap()).collect();
-        match &op {
-            ParamOperator::BvConst => {
-                assert_num_args(&op_args, 2)?;
-                assert_num_args(&args, 0)?;
-                let value = op_args[0].as_integer().unwrap();
-                let width = op_args[1].as_integer().unwrap();
-                assert_indexed_op_args_value(&[op_args[0].clone()], 0..)?;
-                assert_indexed_op_args_value(&[op_args[1].clone()], 1..)?;
-                return Ok(self.pool.add(Term::Const(Constant::BitVec(value, width))));
-            }
-            ParamOperator::BvExtract => {
-                /*
-                ((_ extract i j) (_ BitVec m) (_ BitVec n))
-
-                where
-                - i, j, m, n are numerals
-                - m > i ≥ j ≥ 0,
-                - n = i - j + 1
-                 */
-                assert_num_args(&op_args, 2)?;
-                assert_num_args(&args, 1)?;
-                if !matches!(sorts[0], Sort::BitVec(_)) {
-                    return Err(ParserError::ExpectedBvSort(sorts[0].clone()));
-                }
-                for arg in &op_args {
-                    SortError::assert_eq(&Sort::Int, &arg.sort())?;
-                }
-                assert_indexed_op_args_value(&op_args, 0..)?;
-                let i = op_args[0].as_integer().unwrap();
-                let j = op_args[1].as_integer().unwrap();
-                let Sort::BitVec(m) = sorts[0].clone() else {
-                    unreachable!()
-                };
-                if !(m > i && i >= j && j >= Integer::ZERO) {
-                    return Err(ParserError::InvalidExtractArgs(
-                        i.to_usize().unwrap(),
-                        j.to_usize().unwrap(),
-                        m.to_usize().unwrap(),
-                    ));
-                }
-            }
-            ParamOperator::BvBitOf | ParamOperator::ZeroExtend | ParamOperator::SignExtend => {
-                assert_num_args(&op_args, 1)?;
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Int, &op_args[0].sort())?;
-                if !matches!(sorts[0], Sort::BitVec(_)) {
-                    return Err(ParserError::ExpectedBvSort(sorts[0].clone()));
-                }
-                assert_indexed_op_args_value(&op_args, 0..)?;
-            }
-            ParamOperator::RePower => {
-                assert_num_args(&op_args, 1)?;
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Int, &op_args[0].sort())?;
-                SortError::assert_eq(&Sort::RegLan, sorts[0])?;
-                assert_indexed_op_args_value(&op_args, 0..)?;
-            }
-            ParamOperator::ReLoop => {
-                assert_num_args(&op_args, 2)?;
-                assert_num_args(&args, 1)?;
-                for arg in &op_args {
-                    SortError::assert_eq(&Sort::Int, &arg.sort())?;
-                }
-                SortError::assert_eq(&Sort::RegLan, sorts[0])?;
-                assert_indexed_op_args_value(&op_args, 0..)?;
-            }
-            ParamOperator::ArrayConst => return Err(ParserError::InvalidIndexedOp(op.to_string())),
+        check_indexed_op(self.pool, op, &op_args, &args)?;
+        if op == ParamOperator::BvConst {
+            let value = op_args[0].as_integer().unwrap();
+            let width = op_args[1].as_integer().unwrap();
+            return Ok(self.pool.add(Term::Const(Constant::BitVec(value, width))));
         }
         let op_args = op_args
             .into_iter()
@@ -1544,20 +2274,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
         op_sort: Rc<Term>,
         args: Vec<Rc<Term>>,
     ) -> Result<Rc<Term>, ParserError> {
-        let sorts: Vec<_> = args.iter().map(|t| self.pool.sort(t)).collect();
-        let sorts: Vec<_> = sorts.iter().map(|s| s.as_sort().unwrap()).collect();
-        match &op {
-            ParamOperator::ArrayConst => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_array_sort(
-                    self.pool,
-                    None,
-                    Some(sorts[0]),
-                    op_sort.as_sort().unwrap(),
-                )?;
-            }
-            _ => return Err(ParserError::InvalidQualifiedOp(op.to_string())),
-        }
+        check_qualified_op(self.pool, op, &op_sort, &args)?;
         let op_args = vec![op_sort];
         Ok(self.pool.add(Term::ParamOp { op, op_args, args }))
     }
@@ -1601,7 +2318,8 @@ impl<'a, R: BufRead> Parser<'a, R> {
             Token::Symbol(s) if Operator::from_str(s).is_ok() => {
                 let operator = Operator::from_str(s).unwrap();
                 self.next_token()?;
-                let args = self.parse_sequence(Self::parse_term, true)?;
+                // Empty RARE lists are used to instantiate list parameters with no terms
+                let args = self.parse_sequence(Self::parse_term, operator != Operator::RareList)?;
                 self.make_op(operator, args)
                     .map_err(|err| Error::Parser(err, head_pos))
             }
@@ -1616,9 +2334,12 @@ impl<'a, R: BufRead> Parser<'a, R> {
             }
             Token::OpenParen => {
                 self.next_token()?;
-                match self.current_token {
+                match &self.current_token {
                     Token::ReservedWord(Reserved::Underscore) => {
                         self.next_token()?;
+                        if matches!(&self.current_token, Token::Symbol(s) if s == "is") {
+                            return self.parse_tester(head_pos);
+                        }
                         let (op, op_args) = self.parse_indexed_operator()?;
                         let args = self.parse_sequence(Self::parse_term, true)?;
                         self.make_indexed_op(op, op_args, args)
@@ -1650,7 +2371,10 @@ impl<'a, R: BufRead> Parser<'a, R> {
 
     fn make_sort(&mut self, name: String, args: Vec<Rc<Term>>) -> Result<Rc<Term>, ParserError> {
         let sort = match name.as_str() {
-            "Bool" | "Int" | "Real" | "String" | "RegLan" if !args.is_empty() => {
+            "Bool" | "Int" | "Real" | "String" | "RegLan" | "RoundingMode" | "Float16"
+            | "Float32" | "Float64" | "Float128"
+                if !args.is_empty() =>
+            {
                 Err(ParserError::WrongNumberOfArgs(0.into(), args.len()))
             }
             "Bool" => Ok(Sort::Bool),
@@ -1658,6 +2382,11 @@ impl<'a, R: BufRead> Parser<'a, R> {
             "Real" => Ok(Sort::Real),
             "String" => Ok(Sort::String),
             "RegLan" => Ok(Sort::RegLan),
+            "RoundingMode" => Ok(Sort::RoundingMode),
+            "Float16" => Ok(Sort::FloatingPoint(5.into(), 11.into())),
+            "Float32" => Ok(Sort::FloatingPoint(8.into(), 24.into())),
+            "Float64" => Ok(Sort::FloatingPoint(11.into(), 53.into())),
+            "Float128" => Ok(Sort::FloatingPoint(15.into(), 113.into())),
             "Array" => match args.as_slice() {
                 [x, y] => Ok(Sort::Array(x.clone(), y.clone())),
                 _ => Err(ParserError::WrongNumberOfArgs(2.into(), args.len())),
@@ -1711,6 +2440,16 @@ impl<'a, R: BufRead> Parser<'a, R> {
                     Err(ParserError::ExpectedIntegerConstant(args[0].clone()))
                 }
             }
+            "FloatingPoint" => {
+                if args.len() != 2 {
+                    return Err(ParserError::WrongNumberOfArgs(2.into(), args.len()));
+                }
+                match (args[0].as_integer(), args[1].as_integer()) {
+                    (Some(e), Some(s)) => Ok(self.pool.add(Term::Sort(Sort::FloatingPoint(e, s)))),
+                    (None, _) => Err(ParserError::ExpectedIntegerConstant(args[0].clone())),
+                    (_, None) => Err(ParserError::ExpectedIntegerConstant(args[1].clone())),
+                }
+            }
             _ => Err(ParserError::UndefinedSort(name)),
         }
     }
@@ -0,0 +1,180 @@
+//! A parser for files of RARE rule definitions, like the ones used by cvc5 to justify
+//! `rare_rewrite` steps. Each rule is given by one of these commands:
+//!
+//! ```text
+//! (define-rule <name> (<param>*) [(def <binding>+)] <lhs> <rhs>)
+//! (define-rule* <name> (<param>*) [(def <binding>+)] <lhs> <rhs> [<context>])
+//! (define-cond-rule <name> (<param>*) [(def <binding>+)] <condition> <lhs> <rhs>)
+//! ```
+//!
+//! Each parameter has the form `(<symbol> <sort> [:list])`. Polymorphic sorts, written as `?` or
+//! `?<name>`, are parsed as uninterpreted sorts, so rules can only use parameters of these sorts in
+//! operations that accept arguments of any sort, like `=` or `ite`. The `def` bindings are local
+//! definitions, that are replaced by their values in the rest of the rule. The context of
+//! `define-rule*` rules is ignored, since each application of a rule is checked on its own.
+
+use super::*;
+use crate::checker::rare::{RareParam, RareRule, RareRules};
+
+/// Parses a file of RARE rule definitions into a rule database.
+pub fn parse_rare_rules<T: BufRead>(input: T, config: Config) -> CarcaraResult<RareRules> {
+    let mut pool = PrimitivePool::new();
+    let mut parser = Parser::new(&mut pool, config, input)?;
+    let mut rules = RareRules::new();
+    while parser.current_token != Token::Eof {
+        rules.insert(parser.parse_rare_rule()?);
+    }
+    Ok(rules)
+}
+
+impl<R: BufRead> Parser<'_, R> {
+    fn parse_rare_rule(&mut self) -> CarcaraResult<RareRule> {
+        self.expect_token(Token::OpenParen)?;
+        let (command, position) = self.next_token()?;
+        let (has_condition, has_context) = match &command {
+            Token::Symbol(s) if s == "define-rule" => (false, false),
+            Token::Symbol(s) if s == "define-rule*" => (false, true),
+            Token::Symbol(s) if s == "define-cond-rule" => (true, false),
+            _ => {
+                return Err(Error::Parser(
+                    ParserError::UnexpectedToken(command),
+                    position,
+                ))
+            }
+        };
+        let name = self.expect_symbol()?;
+
+        self.state.symbol_table.push_scope();
+        self.expect_token(Token::OpenParen)?;
+        let params = self.parse_sequence(Self::parse_rare_param, false)?;
+
+        // The `def` bindings and the first term of the rule both start with `(`, so we can only
+        // tell them apart after reading the next token
+        let mut num_scopes = 1;
+        let mut first_term = None;
+        if self.current_token == Token::OpenParen {
+            self.next_token()?;
+            if self.current_token == Token::Symbol("def".to_owned()) {
+                self.next_token()?;
+                num_scopes += self.parse_rare_defs()?;
+            } else {
+                first_term = Some(self.parse_application()?);
+            }
+        }
+        let mut next_term = |p: &mut Self| first_term.take().map_or_else(|| p.parse_term(), Ok);
+        let conditions = if has_condition {
+            vec![next_term(self)?]
+        } else {
+            Vec::new()
+        };
+        let lhs = next_term(self)?;
+        let rhs = next_term(self)?;
+        if has_context
+            && self.current_token != Token::CloseParen
+            && self.next_token()?.0 == Token::OpenParen
+        {
+            self.read_until_close_parens()?;
+        }
+        self.expect_token(Token::CloseParen)?;
+
+        for _ in 0..num_scopes {
+            self.pop_symbol_table_scope();
+        }
+        let params = params
+            .into_iter()
+            .map(|((name, _), is_list)| RareParam { name, is_list })
+            .collect();
+        Ok(RareRule { name, params, conditions, lhs, rhs })
+    }
+
+    /// Parses a rule parameter, of the form `(<symbol> <sort> [:list])`, and adds it to the symbol
+    /// table.
+    fn parse_rare_param(&mut self) -> CarcaraResult<(SortedVar, bool)> {
+        self.expect_token(Token::OpenParen)?;
+        let symbol = self.expect_symbol()?;
+        let sort = match &self.current_token {
+            Token::Symbol(s) if s.starts_with('?') => {
+                let sort = Sort::Atom(s.clone(), Vec::new());
+                self.next_token()?;
+                self.pool.add(Term::Sort(sort))
+            }
+            _ => self.parse_sort()?,
+        };
+        let is_list = self.current_token == Token::Keyword("list".to_owned());
+        if is_list {
+            self.next_token()?;
+        }
+        self.expect_token(Token::CloseParen)?;
+        let var = (symbol, sort);
+        self.insert_sorted_var(var.clone());
+        Ok((var, is_list))
+    }
+
+    /// Parses the bindings of a `def`, after the `def` symbol. Each binding is added in a new
+    /// scope, so later bindings can refer to earlier ones. Returns the number of scopes added.
+    fn parse_rare_defs(&mut self) -> CarcaraResult<usize> {
+        let mut num_scopes = 0;
+        while self.current_token != Token::CloseParen {
+            self.expect_token(Token::OpenParen)?;
+            let name = self.expect_symbol()?;
+            let value = self.parse_term()?;
+            self.expect_token(Token::CloseParen)?;
+
+            self.state.symbol_table.push_scope();
+            num_scopes += 1;
+            let sort = self.pool.sort(&value);
+            self.insert_sorted_var((name.clone(), sort));
+            let depth = self.state.symbol_table.height() - 1;
+            let values = [(HashCache::new(name), value)].into_iter().collect();
+            self.state.let_values.push((depth, values));
+        }
+        self.next_token()?; // Consume `)` token
+        Ok(num_scopes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rare_rules() {
+        let input = "
+            (define-rule bool-double-not-elim ((t Bool)) (not (not t)) t)
+            (define-rule* bool-and-true ((xs Bool :list) (ys Bool :list))
+                (and xs true ys)
+                (and xs ys))
+            (define-cond-rule ite-neg-branch ((c Bool) (x ?) (y ?))
+                (= x y)
+                (ite c x y)
+                x)
+            (define-rule str-len-concat ((s String) (t String))
+                (def (u (str.++ s t)) (v (str.len u)))
+                v
+                (+ (str.len s) (str.len t)))
+        ";
+        let rules = parse_rare_rules(input.as_bytes(), Config::new()).unwrap();
+        assert_eq!(rules.len(), 4);
+
+        let rule = rules.get("bool-and-true").unwrap();
+        assert!(rule.params.iter().all(|p| p.is_list));
+        assert_eq!(rule.lhs.to_string(), "(and xs true ys)");
+
+        let rule = rules.get("ite-neg-branch").unwrap();
+        assert_eq!(rule.conditions.len(), 1);
+        assert_eq!(rule.conditions[0].to_string(), "(= x y)");
+
+        // The `def` bindings are replaced by their values
+        let rule = rules.get("str-len-concat").unwrap();
+        assert_eq!(rule.lhs.to_string(), "(str.len (str.++ s t))");
+
+        for invalid in [
+            "(define-rule r ((t Bool)) t)",
+            "(define-rule r ((t Bool)) (not u) t)",
+            "(define-cond-rule r ((t Bool)) (not t) t)",
+            "(define-lemma r ((t Bool)) (not (not t)) t)",
+        ] {
+            assert!(parse_rare_rules(invalid.as_bytes(), Config::new()).is_err());
+        }
+    }
+}
@@ -0,0 +1,684 @@
+//! Sort checking of terms and proofs.
+//!
+//! The functions in this module check that the arguments of an operation or application have the
+//! expected number and sorts. They are used by the parser when building terms, and by the
+//! [`SortChecker`], which checks terms that were already built.
+
+use super::{
+    error::{assert_fp_sorts, assert_indexed_op_args_value, assert_num_args},
+    Config, IntRealSubtyping, ParserError, SortError,
+};
+use crate::ast::{
+    visit::{visit_term, BoundVars, TermVisitor},
+    *,
+};
+use indexmap::IndexSet;
+use rug::Integer;
+
+/// A sort checking pass over terms that were already built, like the terms of a parsed problem and
+/// proof, or of an elaborated proof.
+///
+/// This checks the number and sorts of the arguments of every operation and application, and that
+/// the bodies of quantifiers and `choice` terms have sort `Bool`. In proofs, it also checks that
+/// every assumption and every term in a step clause has sort `Bool`, and that the values in anchor
+/// assignments have the sorts of the assigned variables. The rules are the same ones used by the
+/// parser with the given configuration, so, for example, `Int`/`Real` subtyping is only allowed if
+/// the configuration enables it.
+///
+/// The checker remembers the terms that were already checked, so each distinct term is only
+/// checked once, even across many calls.
+#[derive(Debug, Default)]
+pub struct SortChecker {
+    config: Config,
+    checked: IndexSet<Rc<Term>>,
+}
+
+impl SortChecker {
+    pub fn new(config: Config) -> Self {
+        Self { config, checked: IndexSet::new() }
+    }
+
+    /// Checks a term and all of its subterms. If some subterm is ill-sorted, the error holds the
+    /// innermost such subterm.
+    pub fn check_term(
+        &mut self,
+        pool: &mut PrimitivePool,
+        term: &Rc<Term>,
+    ) -> Result<(), ParserError> {
+        struct Visitor<'a> {
+            checker: &'a mut SortChecker,
+            pool: &'a mut PrimitivePool,
+            error: Option<ParserError>,
+        }
+
+        impl TermVisitor for Visitor<'_> {
+            fn pre_visit(&mut self, term: &Rc<Term>, _: &BoundVars) -> bool {
+                self.error.is_none() && !self.checker.checked.contains(term)
+            }
+
+            fn post_visit(&mut self, term: &Rc<Term>, _: &BoundVars) {
+                if self.error.is_some() || self.checker.checked.contains(term) {
+                    return;
+                }
+                match self.checker.check_node(self.pool, term) {
+                    Ok(()) => {
+                        self.checker.checked.insert(term.clone());
+                    }
+                    Err(e) => self.error = Some(ParserError::IllSortedTerm(term.clone(), e.into())),
+                }
+            }
+        }
+
+        let mut visitor = Visitor { checker: self, pool, error: None };
+        visit_term(&mut visitor, term);
+        visitor.error.map_or(Ok(()), Err)
+    }
+
+    /// Checks a term that must have sort `Bool`, like a premise of the problem, and all of its
+    /// subterms.
+    pub fn check_formula(
+        &mut self,
+        pool: &mut PrimitivePool,
+        term: &Rc<Term>,
+    ) -> Result<(), ParserError> {
+        self.check_term(pool, term)?;
+        SortError::assert_eq(&Sort::Bool, pool.sort(term).as_sort().unwrap())
+            .map_err(|e| ParserError::IllSortedTerm(term.clone(), Box::new(e.into())))
+    }
+
+    /// Checks the arguments of an `anchor` command.
+    pub fn check_anchor_args(
+        &mut self,
+        pool: &mut PrimitivePool,
+        args: &[AnchorArg],
+    ) -> Result<(), ParserError> {
+        for arg in args {
+            if let AnchorArg::Assign((_, sort), value) = arg {
+                self.check_term(pool, value)?;
+                SortError::assert_eq(sort.as_sort().unwrap(), pool.sort(value).as_sort().unwrap())
+                    .map_err(|e| ParserError::IllSortedTerm(value.clone(), Box::new(e.into())))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a proof command. For a subproof, this checks the arguments of its anchor, and all of
+    /// its commands. The error holds the id of the command in which it was found.
+    pub fn check_command(
+        &mut self,
+        pool: &mut PrimitivePool,
+        command: &ProofCommand,
+    ) -> Result<(), ParserError> {
+        let result = match command {
+            ProofCommand::Assume { term, .. } => self.check_formula(pool, term),
+            ProofCommand::Step(step) => step
+                .clause
+                .iter()
+                .try_for_each(|t| self.check_formula(pool, t))
+                .and_then(|()| step.args.iter().try_for_each(|a| self.check_term(pool, a))),
+            ProofCommand::Subproof(subproof) => {
+                self.check_anchor_args(pool, &subproof.args)?;
+                return subproof
+                    .commands
+                    .iter()
+                    .try_for_each(|c| self.check_command(pool, c));
+            }
+        };
+        result.map_err(|e| ParserError::IllSortedCommand(command.id().to_owned(), Box::new(e)))
+    }
+
+    /// Checks the premises of a problem.
+    pub fn check_problem(
+        &mut self,
+        pool: &mut PrimitivePool,
+        problem: &Problem,
+    ) -> Result<(), ParserError> {
+        problem
+            .premises
+            .iter()
+            .try_for_each(|p| self.check_formula(pool, p))
+    }
+
+    /// Checks all the commands of a proof, and the values of the constants it defines.
+    pub fn check_proof(
+        &mut self,
+        pool: &mut PrimitivePool,
+        proof: &Proof,
+    ) -> Result<(), ParserError> {
+        for (_, value) in &proof.constant_definitions {
+            self.check_term(pool, value)?;
+        }
+        proof
+            .commands
+            .iter()
+            .try_for_each(|c| self.check_command(pool, c))
+    }
+
+    /// Checks a single term, assuming its subterms were already checked.
+    fn check_node(&self, pool: &mut PrimitivePool, term: &Rc<Term>) -> Result<(), ParserError> {
+        match term.as_ref() {
+            Term::Op(op, args) => check_op(pool, &self.config, *op, args),
+            Term::App(function, args) => check_app(pool, function, args),
+            Term::ParamOp {
+                op: ParamOperator::ArrayConst,
+                op_args,
+                args,
+            } => {
+                assert_num_args(op_args, 1)?;
+                check_qualified_op(pool, ParamOperator::ArrayConst, &op_args[0], args)
+            }
+            Term::ParamOp {
+                op: ParamOperator::DtTester,
+                op_args,
+                args,
+            } => {
+                assert_num_args(op_args, 1)?;
+                check_tester(pool, &op_args[0], args)
+            }
+            Term::ParamOp { op, op_args, args } => {
+                let constants: Option<Vec<_>> = op_args
+                    .iter()
+                    .map(|arg| match arg.as_ref() {
+                        Term::Const(c) => Some(c.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                let Some(constants) = constants else {
+                    return Err(ParserError::InvalidIndexedOp(op.to_string()));
+                };
+                check_indexed_op(pool, *op, &constants, args)
+            }
+            Term::Binder(Binder::Lambda, _, _) => Ok(()),
+            Term::Binder(_, _, inner) => {
+                SortError::assert_eq(&Sort::Bool, pool.sort(inner).as_sort().unwrap())?;
+                Ok(())
+            }
+            Term::Const(_) | Term::Var(..) | Term::Sort(_) | Term::Let(..) => Ok(()),
+        }
+    }
+}
+
+/// Sort checks an operation term, given its arguments.
+pub(super) fn check_op(
+    pool: &mut PrimitivePool,
+    config: &Config,
+    op: Operator,
+    args: &[Rc<Term>],
+) -> Result<(), ParserError> {
+    let sorts: Vec<_> = args.iter().map(|t| pool.sort(t)).collect();
+    let sorts: Vec<_> = sorts.iter().map(|s| s.as_sort().unwrap()).collect();
+    match op {
+        Operator::True | Operator::False => assert_num_args(args, 0)?,
+        Operator::Not => {
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::Bool, sorts[0])?;
+        }
+        Operator::Implies => {
+            assert_num_args(args, 2..)?;
+            for s in sorts {
+                SortError::assert_eq(&Sort::Bool, s)?;
+            }
+        }
+        Operator::Or | Operator::And | Operator::Xor => {
+            // If we are not in "strict" parsing mode, we allow these operators to be called
+            // with just one argument
+            assert_num_args(args, if config.strict { 2.. } else { 1.. })?;
+            for s in sorts {
+                SortError::assert_eq(&Sort::Bool, s)?;
+            }
+        }
+        Operator::Equals | Operator::Distinct => {
+            assert_num_args(args, 2..)?;
+            SortError::assert_all_eq(&sorts)?;
+        }
+        Operator::Ite => {
+            assert_num_args(args, 3)?;
+            SortError::assert_eq(&Sort::Bool, sorts[0])?;
+            SortError::assert_eq(sorts[1], sorts[2])?;
+        }
+        Operator::Add | Operator::Sub | Operator::Mult => {
+            // The `-` operator, in particular, can be called with only one argument, in which
+            // case it means negation instead of subtraction
+            if op == Operator::Sub {
+                assert_num_args(args, 1..)?;
+            } else {
+                assert_num_args(args, 2..)?;
+            }
+
+            // All the arguments must be either Int or Real. Also, unless `Int` terms are
+            // implicitly accepted as `Real` terms, all arguments must have the same sort
+            if config.int_real_subtyping == IntRealSubtyping::Implicit {
+                for s in sorts {
+                    SortError::assert_one_of(&[Sort::Int, Sort::Real], s)?;
+                }
+            } else {
+                SortError::assert_one_of(&[Sort::Int, Sort::Real], sorts[0])?;
+                SortError::assert_all_eq(&sorts)?;
+            }
+        }
+        Operator::IntDiv => {
+            assert_num_args(args, 2..)?;
+            SortError::assert_eq(&Sort::Int, sorts[0])?;
+            SortError::assert_all_eq(&sorts)?;
+        }
+        Operator::RealDiv => {
+            assert_num_args(args, 2..)?;
+
+            // Normally, the `/` operator may only receive Real arguments, but if `Int` terms are
+            // implicitly accepted as `Real` terms, it may also receive Ints
+            if config.int_real_subtyping == IntRealSubtyping::Implicit {
+                for s in sorts {
+                    SortError::assert_one_of(&[Sort::Int, Sort::Real], s)?;
+                }
+            } else {
+                SortError::assert_eq(&Sort::Real, sorts[0])?;
+                SortError::assert_all_eq(&sorts)?;
+            }
+        }
+        Operator::Mod => {
+            assert_num_args(args, 2)?;
+            SortError::assert_eq(&Sort::Int, sorts[0])?;
+            SortError::assert_eq(&Sort::Int, sorts[1])?;
+        }
+        Operator::Abs => {
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::Int, sorts[0])?;
+        }
+        Operator::LessThan | Operator::GreaterThan | Operator::LessEq | Operator::GreaterEq => {
+            assert_num_args(args, 2..)?;
+            // All the arguments must be either Int or Real sorted, but they don't need to all
+            // have the same sort
+            for s in sorts {
+                SortError::assert_one_of(&[Sort::Int, Sort::Real], s)?;
+            }
+        }
+        Operator::ToReal => {
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::Int, sorts[0])?;
+        }
+        Operator::ToInt | Operator::IsInt => {
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::Real, sorts[0])?;
+        }
+        Operator::Select => {
+            assert_num_args(args, 2)?;
+            SortError::assert_array_sort(pool, Some(sorts[1]), None, sorts[0])?;
+        }
+        Operator::Store => {
+            assert_num_args(args, 3)?;
+            SortError::assert_array_sort(pool, Some(sorts[1]), Some(sorts[2]), sorts[0])?;
+        }
+        Operator::StrConcat => {
+            assert_num_args(args, 2..)?;
+            for s in sorts {
+                SortError::assert_eq(&Sort::String, s)?;
+            }
+        }
+        Operator::StrLen | Operator::StrIsDigit | Operator::StrToCode | Operator::StrToInt => {
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::String, sorts[0])?;
+        }
+        Operator::StrLessThan
+        | Operator::StrLessEq
+        | Operator::PrefixOf
+        | Operator::SuffixOf
+        | Operator::Contains
+        | Operator::ReRange => {
+            assert_num_args(args, 2)?;
+            SortError::assert_eq(&Sort::String, sorts[0])?;
+            SortError::assert_eq(&Sort::String, sorts[1])?;
+        }
+        Operator::CharAt => {
+            assert_num_args(args, 2)?;
+            SortError::assert_eq(&Sort::String, sorts[0])?;
+            SortError::assert_eq(&Sort::Int, sorts[1])?;
+        }
+        Operator::Substring => {
+            assert_num_args(args, 3)?;
+            SortError::assert_eq(&Sort::String, sorts[0])?;
+            SortError::assert_eq(&Sort::Int, sorts[1])?;
+            SortError::assert_eq(&Sort::Int, sorts[2])?;
+        }
+        Operator::IndexOf => {
+            assert_num_args(args, 3)?;
+            SortError::assert_eq(&Sort::String, sorts[0])?;
+            SortError::assert_eq(&Sort::String, sorts[1])?;
+            SortError::assert_eq(&Sort::Int, sorts[2])?;
+        }
+        Operator::Replace | Operator::ReplaceAll => {
+            assert_num_args(args, 3)?;
+            SortError::assert_eq(&Sort::String, sorts[0])?;
+            SortError::assert_eq(&Sort::String, sorts[1])?;
+            SortError::assert_eq(&Sort::String, sorts[2])?;
+        }
+        Operator::StrFromCode | Operator::StrFromInt => {
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::Int, sorts[0])?;
+        }
+        Operator::StrToRe => {
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::String, sorts[0])?;
+        }
+        Operator::StrInRe => {
+            assert_num_args(args, 2)?;
+            SortError::assert_eq(&Sort::String, sorts[0])?;
+            SortError::assert_eq(&Sort::RegLan, sorts[1])?;
+        }
+        Operator::ReNone | Operator::ReAll | Operator::ReAllChar => {
+            assert_num_args(args, 0)?;
+        }
+        Operator::ReConcat | Operator::ReUnion | Operator::ReIntersection | Operator::ReDiff => {
+            assert_num_args(args, 2..)?;
+            for s in sorts {
+                SortError::assert_eq(&Sort::RegLan, s)?;
+            }
+        }
+        Operator::ReKleeneClosure
+        | Operator::ReComplement
+        | Operator::ReKleeneCross
+        | Operator::ReOption => {
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::RegLan, sorts[0])?;
+        }
+        Operator::ReplaceRe | Operator::ReplaceReAll => {
+            assert_num_args(args, 3)?;
+            SortError::assert_eq(&Sort::String, sorts[0])?;
+            SortError::assert_eq(&Sort::RegLan, sorts[1])?;
+            SortError::assert_eq(&Sort::String, sorts[2])?;
+        }
+        Operator::BvNot | Operator::BvNeg => {
+            assert_num_args(args, 1)?;
+            for s in sorts {
+                if !matches!(s, Sort::BitVec(_)) {
+                    return Err(ParserError::ExpectedBvSort(s.clone()));
+                }
+            }
+        }
+        Operator::BvBbTerm => {
+            assert_num_args(args, 1..)?;
+            SortError::assert_eq(&Sort::Bool, sorts[0])?;
+            SortError::assert_all_eq(&sorts)?;
+        }
+        Operator::BvConcat => {
+            assert_num_args(args, 2..)?;
+            for s in sorts {
+                if !matches!(s, Sort::BitVec(_)) {
+                    return Err(ParserError::ExpectedBvSort(s.clone()));
+                }
+            }
+        }
+        Operator::BvAdd | Operator::BvMul | Operator::BvAnd | Operator::BvOr | Operator::BvXor => {
+            assert_num_args(args, 2..)?;
+            if !matches!(sorts[0], Sort::BitVec(_)) {
+                return Err(ParserError::ExpectedBvSort(sorts[0].clone()));
+            }
+            SortError::assert_all_eq(&sorts)?;
+        }
+        Operator::BvUDiv
+        | Operator::BvURem
+        | Operator::BvShl
+        | Operator::BvLShr
+        | Operator::BvULt
+        | Operator::BvNAnd
+        | Operator::BvNOr
+        | Operator::BvXNor
+        | Operator::BvComp
+        | Operator::BvSub
+        | Operator::BvSDiv
+        | Operator::BvSRem
+        | Operator::BvSMod
+        | Operator::BvAShr
+        | Operator::BvULe
+        | Operator::BvUGt
+        | Operator::BvUGe
+        | Operator::BvSLt
+        | Operator::BvSLe
+        | Operator::BvSGt
+        | Operator::BvSGe => {
+            assert_num_args(args, 2)?;
+            if !matches!(sorts[0], Sort::BitVec(_)) {
+                return Err(ParserError::ExpectedBvSort(sorts[0].clone()));
+            }
+            SortError::assert_all_eq(&sorts)?;
+        }
+        Operator::RareList => SortError::assert_all_eq(&sorts)?,
+        Operator::RoundNearestTiesToEven
+        | Operator::RoundNearestTiesToAway
+        | Operator::RoundTowardPositive
+        | Operator::RoundTowardNegative
+        | Operator::RoundTowardZero => assert_num_args(args, 0)?,
+        Operator::Fp => {
+            assert_num_args(args, 3)?;
+            SortError::assert_eq(&Sort::BitVec(Integer::ONE.into()), sorts[0])?;
+            for s in &sorts[1..] {
+                if !matches!(s, Sort::BitVec(_)) {
+                    return Err(ParserError::ExpectedBvSort((*s).clone()));
+                }
+            }
+        }
+        Operator::FpAbs
+        | Operator::FpNeg
+        | Operator::FpIsNormal
+        | Operator::FpIsSubnormal
+        | Operator::FpIsZero
+        | Operator::FpIsInfinite
+        | Operator::FpIsNaN
+        | Operator::FpIsNegative
+        | Operator::FpIsPositive
+        | Operator::FpToReal => {
+            assert_num_args(args, 1)?;
+            assert_fp_sorts(&sorts)?;
+        }
+        Operator::FpRem | Operator::FpMin | Operator::FpMax => {
+            assert_num_args(args, 2)?;
+            assert_fp_sorts(&sorts)?;
+        }
+        Operator::FpLeq | Operator::FpLt | Operator::FpGeq | Operator::FpGt | Operator::FpEq => {
+            assert_num_args(args, 2..)?;
+            assert_fp_sorts(&sorts)?;
+        }
+        Operator::FpSqrt | Operator::FpRoundToIntegral => {
+            assert_num_args(args, 2)?;
+            SortError::assert_eq(&Sort::RoundingMode, sorts[0])?;
+            assert_fp_sorts(&sorts[1..])?;
+        }
+        Operator::FpAdd | Operator::FpSub | Operator::FpMul | Operator::FpDiv => {
+            assert_num_args(args, 3)?;
+            SortError::assert_eq(&Sort::RoundingMode, sorts[0])?;
+            assert_fp_sorts(&sorts[1..])?;
+        }
+        Operator::FpFma => {
+            assert_num_args(args, 4)?;
+            SortError::assert_eq(&Sort::RoundingMode, sorts[0])?;
+            assert_fp_sorts(&sorts[1..])?;
+        }
+    }
+    Ok(())
+}
+
+/// Sort checks an application term, given its function and arguments.
+pub(super) fn check_app(
+    pool: &mut PrimitivePool,
+    function: &Rc<Term>,
+    args: &[Rc<Term>],
+) -> Result<(), ParserError> {
+    let sort = pool.sort(function);
+    let sorts = {
+        let function_sort = sort.as_sort().unwrap();
+        if let Sort::Function(sorts) = function_sort {
+            sorts
+        } else {
+            // Function does not have function sort
+            return Err(ParserError::NotAFunction(function_sort.clone()));
+        }
+    };
+    assert_num_args(args, sorts.len() - 1)?;
+    for i in 0..args.len() {
+        SortError::assert_eq(
+            sorts[i].as_sort().unwrap(),
+            pool.sort(&args[i]).as_sort().unwrap(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Sort checks an indexed operation term, given its indices and arguments.
+pub(super) fn check_indexed_op(
+    pool: &mut PrimitivePool,
+    op: ParamOperator,
+    op_args: &[Constant],
+    args: &[Rc<Term>],
+) -> Result<(), ParserError> {
+    let sorts: Vec<_> = args.iter().map(|t| pool.sort(t)).collect();
+    let sorts: Vec<_> = sorts.iter().map(|s| s.as_sort().unwrap()).collect();
+    match op {
+        ParamOperator::BvConst => {
+            assert_num_args(op_args, 2)?;
+            assert_num_args(args, 0)?;
+            assert_indexed_op_args_value(&op_args[..1], 0..)?;
+            assert_indexed_op_args_value(&op_args[1..], 1..)?;
+        }
+        ParamOperator::BvExtract => {
+            /*
+            ((_ extract i j) (_ BitVec m) (_ BitVec n))
+
+            where
+            - i, j, m, n are numerals
+            - m > i ≥ j ≥ 0,
+            - n = i - j + 1
+             */
+            assert_num_args(op_args, 2)?;
+            assert_num_args(args, 1)?;
+            if !matches!(sorts[0], Sort::BitVec(_)) {
+                return Err(ParserError::ExpectedBvSort(sorts[0].clone()));
+            }
+            for arg in op_args {
+                SortError::assert_eq(&Sort::Int, &arg.sort())?;
+            }
+            assert_indexed_op_args_value(op_args, 0..)?;
+            let i = op_args[0].as_integer().unwrap();
+            let j = op_args[1].as_integer().unwrap();
+            let Sort::BitVec(m) = sorts[0].clone() else {
+                unreachable!()
+            };
+            if !(m > i && i >= j && j >= Integer::ZERO) {
+                return Err(ParserError::InvalidExtractArgs(
+                    i.to_usize().unwrap(),
+                    j.to_usize().unwrap(),
+                    m.to_usize().unwrap(),
+                ));
+            }
+        }
+        ParamOperator::BvBitOf | ParamOperator::ZeroExtend | ParamOperator::SignExtend => {
+            assert_num_args(op_args, 1)?;
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::Int, &op_args[0].sort())?;
+            if !matches!(sorts[0], Sort::BitVec(_)) {
+                return Err(ParserError::ExpectedBvSort(sorts[0].clone()));
+            }
+            assert_indexed_op_args_value(op_args, 0..)?;
+        }
+        ParamOperator::RePower => {
+            assert_num_args(op_args, 1)?;
+            assert_num_args(args, 1)?;
+            SortError::assert_eq(&Sort::Int, &op_args[0].sort())?;
+            SortError::assert_eq(&Sort::RegLan, sorts[0])?;
+            assert_indexed_op_args_value(op_args, 0..)?;
+        }
+        ParamOperator::ReLoop => {
+            assert_num_args(op_args, 2)?;
+            assert_num_args(args, 1)?;
+            for arg in op_args {
+                SortError::assert_eq(&Sort::Int, &arg.sort())?;
+            }
+            SortError::assert_eq(&Sort::RegLan, sorts[0])?;
+            assert_indexed_op_args_value(op_args, 0..)?;
+        }
+        ParamOperator::FpPlusZero
+        | ParamOperator::FpMinusZero
+        | ParamOperator::FpPlusInfinity
+        | ParamOperator::FpMinusInfinity
+        | ParamOperator::FpNaN => {
+            assert_num_args(op_args, 2)?;
+            assert_num_args(args, 0)?;
+            assert_indexed_op_args_value(op_args, 2..)?;
+        }
+        ParamOperator::FpToFp => {
+            /*
+            ((_ to_fp eb sb) (_ BitVec m) (_ FloatingPoint eb sb)), where m = eb + sb
+            ((_ to_fp eb sb) RoundingMode (_ FloatingPoint mb nb) (_ FloatingPoint eb sb))
+            ((_ to_fp eb sb) RoundingMode Real (_ FloatingPoint eb sb))
+            ((_ to_fp eb sb) RoundingMode (_ BitVec m) (_ FloatingPoint eb sb))
+             */
+            assert_num_args(op_args, 2)?;
+            assert_num_args(args, 1..3)?;
+            assert_indexed_op_args_value(op_args, 2..)?;
+            if let [s] = sorts.as_slice() {
+                let width = op_args[0].as_integer().unwrap() + op_args[1].as_integer().unwrap();
+                SortError::assert_eq(&Sort::BitVec(width), s)?;
+            } else {
+                SortError::assert_eq(&Sort::RoundingMode, sorts[0])?;
+                if !matches!(
+                    sorts[1],
+                    Sort::FloatingPoint(..) | Sort::Real | Sort::BitVec(_)
+                ) {
+                    return Err(ParserError::ExpectedFpSort(sorts[1].clone()));
+                }
+            }
+        }
+        ParamOperator::FpToFpUnsigned => {
+            assert_num_args(op_args, 2)?;
+            assert_num_args(args, 2)?;
+            assert_indexed_op_args_value(op_args, 2..)?;
+            SortError::assert_eq(&Sort::RoundingMode, sorts[0])?;
+            if !matches!(sorts[1], Sort::BitVec(_)) {
+                return Err(ParserError::ExpectedBvSort(sorts[1].clone()));
+            }
+        }
+        ParamOperator::FpToUbv | ParamOperator::FpToSbv => {
+            assert_num_args(op_args, 1)?;
+            assert_num_args(args, 2)?;
+            assert_indexed_op_args_value(op_args, 1..)?;
+            SortError::assert_eq(&Sort::RoundingMode, sorts[0])?;
+            assert_fp_sorts(&sorts[1..])?;
+        }
+        ParamOperator::ArrayConst | ParamOperator::DtTester => {
+            return Err(ParserError::InvalidIndexedOp(op.to_string()))
+        }
+    }
+    Ok(())
+}
+
+/// Sort checks a qualified operation term, like `((as const (Array Int Int)) 0)`.
+pub(super) fn check_qualified_op(
+    pool: &mut PrimitivePool,
+    op: ParamOperator,
+    op_sort: &Rc<Term>,
+    args: &[Rc<Term>],
+) -> Result<(), ParserError> {
+    let sorts: Vec<_> = args.iter().map(|t| pool.sort(t)).collect();
+    let sorts: Vec<_> = sorts.iter().map(|s| s.as_sort().unwrap()).collect();
+    match op {
+        ParamOperator::ArrayConst => {
+            assert_num_args(args, 1)?;
+            SortError::assert_array_sort(pool, None, Some(sorts[0]), op_sort.as_sort().unwrap())?;
+        }
+        _ => return Err(ParserError::InvalidQualifiedOp(op.to_string())),
+    }
+    Ok(())
+}
+
+/// Sort checks a datatype tester application, given the tested constructor and the arguments.
+pub(super) fn check_tester(
+    pool: &mut PrimitivePool,
+    constructor: &Rc<Term>,
+    args: &[Rc<Term>],
+) -> Result<(), ParserError> {
+    assert_num_args(args, 1)?;
+    let datatype_sort = match pool.sort(constructor).as_sort().unwrap() {
+        Sort::Function(sorts) => sorts.last().unwrap().as_sort().unwrap().clone(),
+        other => other.clone(),
+    };
+    SortError::assert_eq(&datatype_sort, pool.sort(&args[0]).as_sort().unwrap())?;
+    Ok(())
+}
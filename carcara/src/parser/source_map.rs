@@ -0,0 +1,78 @@
+//! The locations of proof commands in the proof file, recorded by the parser when
+//! [`Config::track_locations`](super::Config::track_locations) is enabled.
+
+use super::Position;
+use std::collections::HashMap;
+
+/// A range in the source input, from `start` (inclusive) to `end` (exclusive).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// Returns `true` if the given position is in this span.
+    pub fn contains(&self, position: Position) -> bool {
+        self.start <= position && position < self.end
+    }
+}
+
+/// The location of a proof command, and of the terms in it. Only the top-level terms of the command
+/// are tracked, since terms are shared in the term pool, and the same subterm may come from many
+/// places in the input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandLocation {
+    /// The whole command, including its parentheses.
+    pub span: Span,
+
+    /// The command id.
+    pub id: Span,
+
+    /// Each term in the conclusion clause of a `step`, or the term of an `assume`.
+    pub clause: Vec<Span>,
+
+    /// Each term in the arguments of a `step`.
+    pub args: Vec<Span>,
+
+    /// Each command id in the premises of a `step`.
+    pub premises: Vec<Span>,
+
+    /// Each command id in the `:discharge` attribute of a `step`.
+    pub discharge: Vec<Span>,
+}
+
+/// The locations of the `assume` and `step` commands in a proof, indexed by their ids.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    commands: HashMap<String, CommandLocation>,
+}
+
+impl SourceMap {
+    /// Returns the location of the command with the given id. If more than one command has this id,
+    /// which can happen in different subproofs, the location of the last one is returned.
+    pub fn get(&self, id: &str) -> Option<&CommandLocation> {
+        self.commands.get(id)
+    }
+
+    /// Returns the id and location of the command whose span contains the given position, if there
+    /// is one.
+    pub fn command_at(&self, position: Position) -> Option<(&str, &CommandLocation)> {
+        self.commands
+            .iter()
+            .find(|(_, location)| location.span.contains(position))
+            .map(|(id, location)| (id.as_str(), location))
+    }
+
+    pub(super) fn insert(&mut self, id: String, location: CommandLocation) {
+        self.commands.insert(id, location);
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
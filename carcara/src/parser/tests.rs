@@ -11,9 +11,20 @@ const TEST_CONFIG: Config = Config {
     // Some tests need function definitions to be applied
     apply_function_defs: true,
     expand_lets: false,
-    allow_int_real_subtyping: false,
+    int_real_subtyping: IntRealSubtyping::Strict,
     strict: false,
     parse_hole_args: false,
+    shadowed_binders: ShadowedBinders::Allow,
+    limits: SizeLimits {
+        max_commands: None,
+        max_term_nodes: None,
+    },
+    track_locations: false,
+    alpha_canonical_binders: false,
+    fresh_name_scheme: FreshNameScheme::Primes,
+    check_sorts: false,
+    alethe_version: None,
+    dialect: Dialect::Alethe,
 };
 
 pub fn parse_terms<const N: usize>(
@@ -143,6 +154,58 @@ fn test_arithmetic_ops() {
     ));
 }
 
+#[test]
+fn test_int_real_subtyping() {
+    fn parse_with(int_real_subtyping: IntRealSubtyping, input: &str) -> CarcaraResult<String> {
+        let mut pool = PrimitivePool::new();
+        let config = Config { int_real_subtyping, ..TEST_CONFIG };
+        let definitions = "(declare-fun x () Int) (declare-fun y () Real)";
+        let mut parser = Parser::new(&mut pool, config, definitions.as_bytes())?;
+        parser.parse_problem()?;
+        parser.reset(input.as_bytes())?;
+        let term = parser.parse_term()?;
+
+        // Terms built with explicit coercions must pass the strict sort checks
+        if int_real_subtyping == IntRealSubtyping::ToReal {
+            SortChecker::new(TEST_CONFIG)
+                .check_term(&mut pool, &term)
+                .unwrap();
+        }
+        Ok(term.to_string())
+    }
+
+    for input in ["(+ x y)", "(* 2 y)", "(/ x 2)"] {
+        assert!(parse_with(IntRealSubtyping::Strict, input).is_err());
+        assert_eq!(
+            parse_with(IntRealSubtyping::Implicit, input).unwrap(),
+            input
+        );
+    }
+
+    let cases = [
+        ("(+ x y)", "(+ (to_real x) y)"),
+        ("(* 2 y)", "(* 2.0 y)"),
+        ("(/ x 2)", "(/ (to_real x) 2.0)"),
+        ("(< x y 1)", "(< (to_real x) y 1.0)"),
+        ("(= y x)", "(= y (to_real x))"),
+        ("(+ x 1)", "(+ x 1)"),
+        ("(< x 1)", "(< x 1)"),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(
+            parse_with(IntRealSubtyping::ToReal, input).unwrap(),
+            expected
+        );
+    }
+
+    // Divisions between integer constants are still rational literals
+    let half = parse_with(IntRealSubtyping::ToReal, "(/ 1 2)").unwrap();
+    assert_eq!(
+        half,
+        parse_with(IntRealSubtyping::Implicit, "(/ 1 2)").unwrap()
+    );
+}
+
 #[test]
 fn test_logic_ops() {
     let mut p = PrimitivePool::new();
@@ -344,6 +407,46 @@ fn test_let_terms() {
     ));
 }
 
+#[test]
+fn test_expanded_let_terms() {
+    let mut p = PrimitivePool::new();
+    let definitions = "(declare-fun f (Int Int) Int) (declare-const x Int)";
+    let config = Config { expand_lets: true, ..TEST_CONFIG };
+    let cases = [
+        ("(let ((y 1)) (f y y))", "(f 1 1)"),
+        ("(let ((y 1)) (let ((z (f y x))) (f z y)))", "(f (f 1 x) 1)"),
+        // `let` bindings are simultaneous
+        ("(let ((x 1)) (let ((x 2) (y x)) (f x y)))", "(f 2 1)"),
+        // Inner binders shadow `let` variables, and vice versa
+        (
+            "(let ((y 1)) (forall ((y Int)) (= y x)))",
+            "(forall ((y Int)) (= y x))",
+        ),
+        (
+            "(forall ((y Int)) (let ((y 1)) (= y x)))",
+            "(forall ((y Int)) (= 1 x))",
+        ),
+        // Binders are renamed to avoid capturing free variables in `let` values
+        (
+            "(let ((y x)) (forall ((x Int)) (= x y)))",
+            "(forall ((x' Int)) (= x' x))",
+        ),
+        (
+            "(let ((y x)) (forall ((x Int) (x' Int)) (let ((z x')) (exists ((x Int)) (= z y)))))",
+            "(forall ((x' Int) (x'' Int)) (exists ((x''' Int)) (= x'' x)))",
+        ),
+    ];
+    for (input, expected) in cases {
+        let mut parser = Parser::new(&mut p, config, definitions.as_bytes()).unwrap();
+        parser.parse_problem().unwrap();
+        parser.reset(input.as_bytes()).unwrap();
+        let got = parser.parse_term().unwrap();
+
+        let [expected] = parse_terms(&mut p, definitions, [expected]);
+        assert_eq!(expected, got, "{}", input);
+    }
+}
+
 #[test]
 fn test_lambda_terms() {
     let mut p = PrimitivePool::new();
@@ -479,7 +582,13 @@ fn test_define_fun() {
 fn test_define_fun_rec() {
     fn run_test(pool: &mut PrimitivePool, problem: &str, expected_premises: &[&str]) {
         let mut parser = Parser::new(pool, TEST_CONFIG, problem.as_bytes()).expect(ERROR_MESSAGE);
-        let got = parser.parse_problem().expect(ERROR_MESSAGE).premises;
+        let problem = parser.parse_problem().expect(ERROR_MESSAGE);
+
+        // The defined functions are declared in the prelude
+        let declared = &problem.prelude.function_declarations;
+        assert_eq!(expected_premises.len(), declared.len());
+
+        let got = problem.premises;
         assert_eq!(expected_premises.len(), got.len());
         for p in expected_premises {
             parser.reset(p.as_bytes()).expect(ERROR_MESSAGE);
@@ -544,6 +653,30 @@ fn test_define_sort() {
         "(exists ((a (Array String (Array Int (Array Int (Array Int Bool)))))) false)",
     );
     assert_eq!(expected, got);
+
+    // Aliases can refer to parametric sorts, and are recorded in the prelude
+    let problem = "
+        (declare-sort Pair 2)
+        (define-sort Set (T) (Array T Bool))
+        (define-sort Relation (T) (Set (Pair T T)))
+        (declare-const r (Relation Int))
+        (declare-const s (Array (Pair Int Int) Bool))
+    ";
+    let mut parser = Parser::new(&mut p, TEST_CONFIG, problem.as_bytes()).expect(ERROR_MESSAGE);
+    let prelude = parser.parse_problem().expect(ERROR_MESSAGE).prelude;
+    assert_eq!(prelude.sort_declarations(), [("Pair".to_owned(), 2)]);
+    let names: Vec<_> = prelude.sort_definitions().iter().map(|d| &d.name).collect();
+    assert_eq!(names, ["Set", "Relation"]);
+    let [r, s] = parse_terms(&mut p, problem, ["r", "s"]);
+    assert_eq!(p.sort(&r), p.sort(&s));
+
+    // Parametric sorts and aliases must be applied to the right number of arguments
+    let problem = "(declare-sort Pair 2) (define-sort Set (T) (Array T Bool))";
+    for sort in ["(Pair Int)", "(Set Int Int)", "Set"] {
+        let input = format!("{} (declare-const x {})", problem, sort);
+        let mut parser = Parser::new(&mut p, TEST_CONFIG, input.as_bytes()).expect(ERROR_MESSAGE);
+        assert!(parser.parse_problem().is_err());
+    }
 }
 
 #[test]
@@ -573,6 +706,112 @@ fn test_assume() {
     );
 }
 
+#[test]
+fn test_named_hypotheses() {
+    let hypotheses: Vec<NamedHypothesis> = ["h1:(> x 0)", "h2: (= x (f x))"]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    assert_eq!(hypotheses[1].name, "h2");
+    assert!("(> x 0)".parse::<NamedHypothesis>().is_err());
+    assert!("h1:".parse::<NamedHypothesis>().is_err());
+
+    let declarations = "(declare-fun x () Int) (declare-fun f (Int) Int)";
+    let proof = "(assume a1 (> x 0)) (assume a2 (= x (f x)))";
+    let (problem, proof, p) = parse_instance_with_hypotheses(
+        declarations.as_bytes(),
+        &hypotheses,
+        proof.as_bytes(),
+        Config::new(),
+    )
+    .unwrap();
+    assert_eq!(problem.premises.len(), 2);
+    let ProofCommand::Assume { term, .. } = &proof.commands[0] else {
+        panic!()
+    };
+    assert_eq!(problem.premises[0], *term);
+    assert_eq!(p.sort(term).as_sort(), Some(&Sort::Bool));
+
+    for hypotheses in [
+        &["h1:(> x 0) x"][..],
+        &["h1:(> x 0)", "h1:(< x 0)"],
+        &["h1:(> y 0)"],
+    ] {
+        let hypotheses: Vec<NamedHypothesis> =
+            hypotheses.iter().map(|s| s.parse().unwrap()).collect();
+        let result = parse_instance_with_hypotheses(
+            declarations.as_bytes(),
+            &hypotheses,
+            "".as_bytes(),
+            Config::new(),
+        );
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn test_definitions() {
+    let problem = "(declare-fun a () Bool) (declare-fun b () Bool) (assert (or a b))";
+    let definitions = "(define-fun t1 () Bool (and a b)) (define-fun t2 () Bool (or t1 a))";
+    let proof = "(assume h1 (= t2 (or t1 a)))";
+    let (parsed_problem, proof, p) = parse_instance_with_definitions(
+        problem.as_bytes(),
+        definitions.as_bytes(),
+        proof.as_bytes(),
+        Config::new(),
+    )
+    .unwrap();
+    assert_eq!(parsed_problem.premises.len(), 3);
+    let ProofCommand::Assume { term, .. } = &proof.commands[0] else {
+        panic!()
+    };
+    assert_eq!(parsed_problem.premises[2], *term);
+    assert_eq!(p.sort(term).as_sort(), Some(&Sort::Bool));
+
+    for definitions in [
+        "(define-fun a () Bool (not b))",
+        "(define-fun t1 () Bool a) (define-fun t1 () Bool b)",
+        "(define-fun t1 () Bool (not t1))",
+        "(assert (not a))",
+    ] {
+        let result = parse_instance_with_definitions(
+            problem.as_bytes(),
+            definitions.as_bytes(),
+            "".as_bytes(),
+            Config::new(),
+        );
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn test_sharing_references() {
+    let mut p = PrimitivePool::new();
+    let input = "
+        (assume h1 #1:(or #2:(not true) false))
+        (step t2 (cl #2 false) :rule or :premises (h1))
+        (step t3 (cl (not #1) #2) :rule rule-name)
+    ";
+    let proof = parse_proof(&mut p, input);
+    let disjunction = parse_term(&mut p, "(or (not true) false)");
+    let negation = parse_term(&mut p, "(not true)");
+    assert_eq!(
+        proof.commands[0].clause(),
+        std::slice::from_ref(&disjunction)
+    );
+    assert_eq!(
+        proof.commands[1].clause(),
+        [negation.clone(), p.bool_false()]
+    );
+    let not_disjunction = p.add(Term::Op(Operator::Not, vec![disjunction]));
+    assert_eq!(proof.commands[2].clause(), [not_disjunction, negation]);
+
+    assert!(matches!(
+        parse_term_err("(and #1 true)"),
+        Error::Parser(ParserError::UndefinedSharedTerm(1), _),
+    ));
+}
+
 #[test]
 fn test_step() {
     let mut p = PrimitivePool::new();
@@ -644,6 +883,69 @@ fn test_step() {
     );
 }
 
+#[test]
+fn test_alethe_versions() {
+    fn parse(config: Config, input: &str) -> CarcaraResult<Proof> {
+        let mut pool = PrimitivePool::new();
+        Parser::new(&mut pool, config, input.as_bytes()).and_then(|mut p| p.parse_proof())
+    }
+    let with_version = |v| Config {
+        alethe_version: Some(v),
+        ..TEST_CONFIG
+    };
+    let strict = Config { strict: true, ..TEST_CONFIG };
+    let old_anchor = "
+        (anchor :step t1 :args ((:= x 1)))
+        (step t1.t1 (cl (= x 1)) :rule rule-name)
+        (step t1 (cl) :rule rule-name)
+    ";
+    let old_args = "(step t1 (cl) :rule rule-name :args ((:= x 1) (+ 1 2)))";
+
+    // Without a version, the old syntax is accepted unless parsing is strict
+    assert!(parse(TEST_CONFIG, old_anchor).is_ok());
+    assert!(parse(strict, old_anchor).is_err());
+    assert!(parse(with_version(AletheVersion::V1_0), old_anchor).is_ok());
+    assert!(parse(with_version(AletheVersion::V1_1), old_anchor).is_err());
+
+    // Assignments in step arguments are replaced by the assigned term
+    let mut pool = PrimitivePool::new();
+    let [one, sum] = parse_terms(&mut pool, "", ["1", "(+ 1 2)"]);
+    let proof = Parser::new(&mut pool, TEST_CONFIG, old_args.as_bytes())
+        .and_then(|mut p| p.parse_proof())
+        .unwrap();
+    match &proof.commands[0] {
+        ProofCommand::Step(step) => assert_eq!(step.args, [one, sum]),
+        _ => panic!(),
+    }
+    assert!(parse(with_version(AletheVersion::V1_1), old_args).is_err());
+
+    // The version can be declared in the proof, but the configuration takes precedence
+    let v1_0 = format!("(set-info :alethe-version 1.0) {}", old_anchor);
+    let v1_1 = format!("(set-info :alethe-version \"1.1\") {}", old_anchor);
+    assert!(parse(strict, &v1_0).is_ok());
+    assert!(parse(TEST_CONFIG, &v1_1).is_err());
+    assert!(parse(with_version(AletheVersion::V1_0), &v1_1).is_ok());
+    let ignored = format!("(set-info :source |veriT|) {}", old_anchor);
+    assert!(parse(TEST_CONFIG, &ignored).is_ok());
+    assert!(matches!(
+        parse(TEST_CONFIG, "(set-info :alethe-version 2.0)"),
+        Err(Error::Parser(ParserError::UnknownAletheVersion(v), _)) if v == "2",
+    ));
+
+    // A version declared in the problem also applies to the proof
+    let mut pool = PrimitivePool::new();
+    let mut parser = Parser::new(
+        &mut pool,
+        TEST_CONFIG,
+        "(set-info :alethe-version 1.1)".as_bytes(),
+    )
+    .unwrap();
+    parser.parse_problem().unwrap();
+    assert_eq!(parser.alethe_version(), Some(AletheVersion::V1_1));
+    parser.reset(old_anchor.as_bytes()).unwrap();
+    assert!(parser.parse_proof().is_err());
+}
+
 #[test]
 fn test_premises_in_subproofs() {
     let mut p = PrimitivePool::new();
@@ -697,6 +999,108 @@ fn test_premises_in_subproofs() {
     );
 }
 
+#[test]
+fn test_error_recovery() {
+    let mut p = PrimitivePool::new();
+    let input = "(assume h1 true)
+(assume h2 (not true) [)
+(step t3 (cl) :rule rule-name :premises (h1 h2))
+(step t4 (cl) :rule rule-name :premises (h1)
+(step t5 (cl false) :rule rule-name)
+foo
+(anchor :step t6)
+(step t6.t1 (cl) :rule rule-name :premises (h1))
+";
+    let mut parser = Parser::new(&mut p, TEST_CONFIG, input.as_bytes()).unwrap();
+    let (proof, diagnostics) = parser.parse_proof_with_recovery().unwrap();
+
+    let ids: Vec<_> = proof.iter().map(ProofCommand::id).collect();
+    assert_eq!(ids, ["h1", "t5"]);
+
+    let errors: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (&d.error, d.position, d.offset))
+        .collect();
+    assert!(matches!(
+        errors.as_slice(),
+        [
+            (ParserError::UnexpectedChar('['), (2, 23), Some(39)),
+            (ParserError::UndefinedStepId(h2), (3, 45), Some(86)),
+            (ParserError::UnexpectedToken(Token::OpenParen), (5, 1), Some(136)),
+            (ParserError::UnexpectedToken(Token::Symbol(foo)), (6, 1), Some(173)),
+            (ParserError::UnclosedSubproof(t6), (8, 49), Some(243)),
+        ] if h2 == "h2" && foo == "foo" && t6 == "t6"
+    ));
+
+    // Without recovery, parsing stops at the first error
+    let mut parser = Parser::new(&mut p, TEST_CONFIG, input.as_bytes()).unwrap();
+    assert!(matches!(
+        parser.parse_proof(),
+        Err(Error::Parser(ParserError::UnexpectedChar('['), (2, 23)))
+    ));
+}
+
+#[test]
+fn test_source_locations() {
+    let mut p = PrimitivePool::new();
+    let input = "(assume h1 (= 1 1))
+(anchor :step t2)
+  (step t2.t1 (cl (= 2 2)
+     (= 3 3)) :rule rule-name :args (1 (+ 1 2)))
+  (step t2 (cl) :rule rule-name :premises (h1 t2.t1))
+";
+    let config = Config {
+        track_locations: true,
+        ..TEST_CONFIG
+    };
+    let mut parser = Parser::new(&mut p, config, input.as_bytes()).unwrap();
+    parser.parse_proof().unwrap();
+    let source_map = parser.take_source_map().unwrap();
+    let span = |start, end| Span { start, end };
+
+    assert_eq!(source_map.len(), 3);
+    assert_eq!(
+        source_map.get("h1"),
+        Some(&CommandLocation {
+            span: span((1, 1), (1, 20)),
+            id: span((1, 9), (1, 11)),
+            clause: vec![span((1, 12), (1, 19))],
+            args: Vec::new(),
+            premises: Vec::new(),
+            discharge: Vec::new(),
+        })
+    );
+    assert_eq!(
+        source_map.get("t2.t1"),
+        Some(&CommandLocation {
+            span: span((3, 3), (4, 49)),
+            id: span((3, 9), (3, 14)),
+            clause: vec![span((3, 19), (3, 26)), span((4, 6), (4, 13))],
+            args: vec![span((4, 38), (4, 39)), span((4, 40), (4, 47))],
+            premises: Vec::new(),
+            discharge: Vec::new(),
+        })
+    );
+    let t2 = source_map.get("t2").unwrap();
+    assert_eq!(t2.span, span((5, 3), (5, 54)));
+    assert_eq!(
+        t2.premises,
+        vec![span((5, 44), (5, 46)), span((5, 47), (5, 52))]
+    );
+
+    assert_eq!(
+        source_map.command_at((4, 10)).map(|(id, _)| id),
+        Some("t2.t1")
+    );
+    assert_eq!(source_map.command_at((5, 3)).map(|(id, _)| id), Some("t2"));
+    assert!(source_map.command_at((2, 5)).is_none());
+
+    // Locations are not recorded by default
+    let mut parser = Parser::new(&mut p, TEST_CONFIG, input.as_bytes()).unwrap();
+    parser.parse_proof().unwrap();
+    assert!(parser.take_source_map().is_none());
+}
+
 #[test]
 fn test_bitvectors() {
     let mut p = PrimitivePool::new();
@@ -784,3 +1188,221 @@ fn test_qualified_operators() {
         Error::Parser(ParserError::InvalidQualifiedOp(_), _),
     ));
 }
+
+#[test]
+fn test_floating_point() {
+    let mut p = PrimitivePool::new();
+    let definitions = "
+        (declare-fun x () Float32)
+        (declare-fun y () (_ FloatingPoint 8 24))
+        (declare-fun r () RoundingMode)
+        (declare-fun h () (_ FloatingPoint 5 11))
+    ";
+    let [x, y, lit, sum, nan, conv, to_bv, is_nan] = parse_terms(
+        &mut p,
+        definitions,
+        [
+            "x",
+            "y",
+            "(fp #b0 #b10000000 #b00000000000000000000000)",
+            "(fp.add RNE x y)",
+            "(_ NaN 8 24)",
+            "((_ to_fp 8 24) roundTowardZero h)",
+            "((_ fp.to_sbv 32) r x)",
+            "(fp.isNaN (fp.fma r x y (_ +zero 8 24)))",
+        ],
+    );
+    let float32 = Sort::FloatingPoint(8.into(), 24.into());
+    for t in [&x, &y, &lit, &sum, &nan, &conv] {
+        assert_eq!(p.sort(t).as_sort(), Some(&float32));
+    }
+    assert_eq!(p.sort(&to_bv).as_sort(), Some(&Sort::BitVec(32.into())));
+    assert_eq!(p.sort(&is_nan).as_sort(), Some(&Sort::Bool));
+
+    // The long and short names of rounding modes denote the same operator
+    let [rne, long_rne] = parse_terms(&mut p, "", ["RNE", "roundNearestTiesToEven"]);
+    assert_eq!(rne, long_rne);
+    assert_eq!(rne.to_string(), "RNE");
+
+    assert!(matches!(
+        parse_term_err("(fp.abs 1.0)"),
+        Error::Parser(ParserError::ExpectedFpSort(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("(fp.add RNE (_ +oo 8 24) (_ -oo 11 53))"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("(fp.sqrt (_ +oo 8 24) (_ -oo 8 24))"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+}
+
+#[test]
+fn test_shadowed_binders() {
+    fn parse_with(shadowed_binders: ShadowedBinders, input: &str) -> CarcaraResult<Rc<Term>> {
+        let mut pool = PrimitivePool::new();
+        let config = Config { shadowed_binders, ..TEST_CONFIG };
+        let definitions = "(declare-fun x' () Int)";
+        let mut parser = Parser::new(&mut pool, config, definitions.as_bytes())?;
+        parser.parse_problem()?;
+        parser.reset(input.as_bytes())?;
+        parser.parse_term()
+    }
+
+    let shadowed = "(forall ((x Int)) (exists ((x Int)) (= x x')))";
+    let repeated = "(forall ((x Int) (x Int)) (= x 0))";
+    let not_shadowed = "(and (forall ((x Int)) (= x 0)) (exists ((x Int)) (= x 0)))";
+    for mode in [ShadowedBinders::Allow, ShadowedBinders::Warn] {
+        for input in [shadowed, repeated, not_shadowed] {
+            assert!(parse_with(mode, input).is_ok());
+        }
+    }
+
+    for input in [shadowed, repeated] {
+        assert!(matches!(
+            parse_with(ShadowedBinders::Reject, input),
+            Err(Error::Parser(ParserError::ShadowedBinder(x), _)) if x == "x",
+        ));
+    }
+    assert!(parse_with(ShadowedBinders::Reject, not_shadowed).is_ok());
+
+    // The renamed variable must not capture the declared constant `x'`
+    let renamed = parse_with(ShadowedBinders::Rename, shadowed).unwrap();
+    assert_eq!(
+        renamed.to_string(),
+        "(forall ((x Int)) (exists ((x'' Int)) (= x'' x')))"
+    );
+    let renamed = parse_with(ShadowedBinders::Rename, repeated).unwrap();
+    assert_eq!(
+        renamed.to_string(),
+        "(forall ((x Int) (x'' Int)) (= x'' 0))"
+    );
+    let renamed = parse_with(ShadowedBinders::Rename, not_shadowed).unwrap();
+    assert_eq!(renamed.to_string(), not_shadowed);
+}
+
+#[test]
+fn test_datatypes() {
+    fn parse_problem(input: &str) -> CarcaraResult<Problem> {
+        let mut pool = PrimitivePool::new();
+        Parser::new(&mut pool, TEST_CONFIG, input.as_bytes())?.parse_problem()
+    }
+
+    let mut p = PrimitivePool::new();
+    let definitions = "
+        (declare-datatype Color ((red) (green) (blue)))
+        (declare-datatypes ((List 0) (Tree 0)) (
+            ((nil) (cons (head Int) (tail List)))
+            ((leaf (value Int)) (node (children List)))
+        ))
+        (declare-fun xs () List)
+    ";
+    let [red, cons, head, is_cons] = parse_terms(
+        &mut p,
+        definitions,
+        ["red", "(cons 1 xs)", "(head xs)", "((_ is cons) (tail xs))"],
+    );
+    let list = Sort::Atom("List".to_owned(), Vec::new());
+    assert_eq!(
+        p.sort(&red).as_sort(),
+        Some(&Sort::Atom("Color".to_owned(), Vec::new()))
+    );
+    assert_eq!(p.sort(&cons).as_sort(), Some(&list));
+    assert_eq!(p.sort(&head).as_sort(), Some(&Sort::Int));
+    assert_eq!(p.sort(&is_cons).as_sort(), Some(&Sort::Bool));
+    assert_eq!(is_cons.to_string(), "((_ is cons) (tail xs))");
+
+    let problem = parse_problem(definitions).unwrap();
+    let datatypes = problem.prelude.datatypes();
+    assert_eq!(datatypes.len(), 3);
+    assert_eq!(datatypes[1].name, "List");
+    assert_eq!(datatypes[1].constructors[1].selectors.len(), 2);
+
+    // The printed prelude declares all datatypes in a single command, and can be parsed back
+    let printed = problem.prelude.to_string();
+    assert!(parse_problem(&printed).is_ok());
+
+    assert!(matches!(
+        parse_problem("(declare-datatypes ((P 1)) ((par (T) ((p (first T))))))"),
+        Err(Error::Parser(ParserError::ParametricDatatype(_), _)),
+    ));
+    assert!(matches!(
+        parse_problem("(declare-datatype D ((c))) (declare-fun f () D) (assert ((_ is f) f))"),
+        Err(Error::Parser(ParserError::NotAConstructor(_), _)),
+    ));
+    assert!(matches!(
+        parse_problem("(declare-datatype D ((c (s Int)))) (assert ((_ is c) 1))"),
+        Err(Error::Parser(ParserError::SortError(_), _)),
+    ));
+}
+
+#[test]
+fn test_sort_checker() {
+    fn parse_problem(check_sorts: bool, input: &str) -> CarcaraResult<Problem> {
+        let mut pool = PrimitivePool::new();
+        let config = Config { check_sorts, ..TEST_CONFIG };
+        Parser::new(&mut pool, config, input.as_bytes())?.parse_problem()
+    }
+
+    // Premises are only required to be `Bool` when sort checking is enabled
+    let non_bool_premise = "(declare-fun x () Int) (assert (+ x 1))";
+    assert!(parse_problem(false, non_bool_premise).is_ok());
+    assert!(matches!(
+        parse_problem(true, non_bool_premise),
+        Err(Error::Parser(ParserError::IllSortedTerm(_, _), pos)) if pos == (1, 32),
+    ));
+    let strict = Config { strict: true, ..TEST_CONFIG };
+    let mut pool = PrimitivePool::new();
+    let result = Parser::new(&mut pool, strict, non_bool_premise.as_bytes())
+        .and_then(|mut p| p.parse_problem());
+    assert!(result.is_err());
+    assert!(parse_problem(true, "(declare-fun p () Bool) (assert (and p (not p)))").is_ok());
+
+    // Terms built without the parser are checked too, and the error points to the innermost
+    // ill-sorted subterm
+    let mut pool = PrimitivePool::new();
+    let [x, p] = parse_terms(
+        &mut pool,
+        "(declare-fun x () Int) (declare-fun p () Bool)",
+        ["x", "p"],
+    );
+    let mut checker = SortChecker::new(TEST_CONFIG);
+    let bad_eq = pool.add(Term::Op(Operator::Equals, vec![x.clone(), p.clone()]));
+    let bad_ite = pool.add(Term::Op(
+        Operator::Ite,
+        vec![p.clone(), x.clone(), p.clone()],
+    ));
+    let unary_not = pool.add(Term::Op(Operator::Not, vec![p.clone(), p.clone()]));
+    let outer = pool.add(Term::Op(Operator::Or, vec![p.clone(), bad_eq.clone()]));
+    for bad in [&bad_eq, &bad_ite, &unary_not] {
+        assert!(checker.check_term(&mut pool, bad).is_err());
+    }
+    assert!(matches!(
+        checker.check_term(&mut pool, &outer),
+        Err(ParserError::IllSortedTerm(t, _)) if t == bad_eq,
+    ));
+    let good = pool.add(Term::Op(
+        Operator::Ite,
+        vec![p.clone(), x.clone(), x.clone()],
+    ));
+    assert!(checker.check_term(&mut pool, &good).is_ok());
+    assert!(checker.check_formula(&mut pool, &good).is_err());
+
+    // Errors in proofs also report the command where they were found
+    let proof = Proof {
+        constant_definitions: Vec::new(),
+        commands: vec![ProofCommand::Step(ProofStep {
+            id: "t1".to_owned(),
+            clause: vec![p, x],
+            rule: "hole".to_owned(),
+            premises: Vec::new(),
+            args: Vec::new(),
+            discharge: Vec::new(),
+        })],
+    };
+    assert!(matches!(
+        checker.check_proof(&mut pool, &proof),
+        Err(ParserError::IllSortedCommand(id, _)) if id == "t1",
+    ));
+}
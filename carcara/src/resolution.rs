@@ -3,6 +3,11 @@ use indexmap::{map::Entry, IndexMap, IndexSet};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", content = "data")
+)]
 pub enum ResolutionError {
     #[error("couldn't find tautology in clause")]
     TautologyFailed,
@@ -27,6 +32,8 @@ pub trait ClauseCollection<'a>: FromIterator<Literal<'a>> {
     fn insert_term(&mut self, item: Literal<'a>);
 
     fn remove_term(&mut self, item: &Literal<'a>) -> bool;
+
+    fn literals(&self) -> Vec<Literal<'a>>;
 }
 
 impl<'a> ClauseCollection<'a> for Vec<Literal<'a>> {
@@ -42,6 +49,10 @@ impl<'a> ClauseCollection<'a> for Vec<Literal<'a>> {
             false
         }
     }
+
+    fn literals(&self) -> Vec<Literal<'a>> {
+        self.clone()
+    }
 }
 
 impl<'a> ClauseCollection<'a> for IndexSet<Literal<'a>> {
@@ -52,6 +63,10 @@ impl<'a> ClauseCollection<'a> for IndexSet<Literal<'a>> {
     fn remove_term(&mut self, item: &Literal<'a>) -> bool {
         self.remove(item)
     }
+
+    fn literals(&self) -> Vec<Literal<'a>> {
+        self.iter().copied().collect()
+    }
 }
 
 /// Transformas a `Literal` into an `Rc<Term>`, by undoing the transformation done by
@@ -64,6 +79,8 @@ pub fn literal_to_term(pool: &mut dyn TermPool, (n, term): Literal) -> Rc<Term>
     term
 }
 
+// The trace is only used when elaborating
+#[cfg_attr(not(feature = "elaborator"), allow(dead_code))]
 pub struct ResolutionTrace {
     pub not_not_added: bool,
     pub pivot_trace: Vec<(Rc<Term>, bool)>,
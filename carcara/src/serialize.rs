@@ -0,0 +1,38 @@
+//! `Serialize` implementations for the types that appear in checking results and errors. Terms,
+//! operators and binding lists are serialized as strings, in SMT-LIB syntax.
+
+use crate::{
+    ast::{Binder, BindingList, Operator, ParamOperator, Rc, Term},
+    utils::Range,
+};
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// Serializes a value as a string, using its `Display` implementation. This can be used with the
+/// `serialize_with` attribute for fields of foreign types.
+pub fn with_display<T: fmt::Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+macro_rules! impl_serialize_with_display {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl Serialize for $t {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    with_display(self, serializer)
+                }
+            }
+        )*
+    };
+}
+
+impl_serialize_with_display!(Rc<Term>, Operator, ParamOperator, Binder, BindingList);
+
+impl<T: fmt::Display + PartialEq> Serialize for Range<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        with_display(self, serializer)
+    }
+}
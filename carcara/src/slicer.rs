@@ -0,0 +1,207 @@
+//! Extraction of the commands needed to derive a given proof step, as a standalone proof.
+
+use crate::ast::*;
+use std::collections::VecDeque;
+
+/// The result of slicing a proof.
+#[derive(Debug, Clone)]
+pub struct Slice {
+    /// The sliced proof.
+    pub proof: Proof,
+
+    /// The terms assumed at the outermost level of the sliced proof. For the sliced proof to be
+    /// checked on its own, these must be the premises of the problem.
+    pub assumptions: Vec<Rc<Term>>,
+}
+
+/// Extracts from a proof the minimal set of commands needed to derive the outermost command with
+/// id `root_id`. Returns `None` if there is no such command.
+///
+/// If `max_distance` is given, commands that are further than that from the root, in number of
+/// premise links, are not derived. Instead, their conclusions are assumed: a unit clause is
+/// replaced by an `assume` command, and any other clause is replaced by an `assume` command of its
+/// disjunction, followed by an `or` step. Commands that conclude the empty clause are never
+/// assumed.
+pub fn slice(
+    pool: &mut dyn TermPool,
+    proof: &Proof,
+    root_id: &str,
+    max_distance: Option<usize>,
+) -> Option<Slice> {
+    let commands = &proof.commands;
+    let root = commands.iter().position(|c| c.id() == root_id)?;
+
+    let is_cut = |distance: usize, command: &ProofCommand| {
+        max_distance.is_some_and(|max| distance > max)
+            && !command.is_assume()
+            && !command.clause().is_empty()
+    };
+
+    // We find the distance from the root to each command it depends on, using a breadth-first
+    // search. Commands that are cut are included, but we don't visit their premises
+    let mut distances: Vec<Option<usize>> = vec![None; commands.len()];
+    distances[root] = Some(0);
+    let mut queue = VecDeque::from([root]);
+    let mut premises = Vec::new();
+    while let Some(i) = queue.pop_front() {
+        let distance = distances[i].unwrap();
+        if is_cut(distance, &commands[i]) {
+            continue;
+        }
+        premises.clear();
+        outermost_premises(&commands[i], &mut premises);
+        for &p in &premises {
+            if distances[p].is_none() {
+                distances[p] = Some(distance + 1);
+                queue.push_back(p);
+            }
+        }
+    }
+
+    let mut new_commands = Vec::new();
+    let mut new_indices: Vec<Option<usize>> = vec![None; commands.len()];
+    let mut assumptions = Vec::new();
+    for (i, command) in commands.iter().enumerate() {
+        let Some(distance) = distances[i] else {
+            continue;
+        };
+        if is_cut(distance, command) {
+            let clause = command.clause();
+            if let [term] = clause {
+                assumptions.push(term.clone());
+                new_commands.push(ProofCommand::Assume {
+                    id: command.id().to_owned(),
+                    term: term.clone(),
+                });
+            } else {
+                let disjunction = pool.add(Term::Op(Operator::Or, clause.to_vec()));
+                assumptions.push(disjunction.clone());
+                new_commands.push(ProofCommand::Assume {
+                    id: format!("{}.h", command.id()),
+                    term: disjunction,
+                });
+                new_commands.push(ProofCommand::Step(ProofStep {
+                    id: command.id().to_owned(),
+                    clause: clause.to_vec(),
+                    rule: "or".to_owned(),
+                    premises: vec![(0, new_commands.len() - 1)],
+                    args: Vec::new(),
+                    discharge: Vec::new(),
+                }));
+            }
+        } else {
+            if let ProofCommand::Assume { term, .. } = command {
+                assumptions.push(term.clone());
+            }
+            new_commands.push(remap_premises(command, &new_indices));
+        }
+        new_indices[i] = Some(new_commands.len() - 1);
+    }
+
+    Some(Slice {
+        proof: Proof {
+            constant_definitions: proof.constant_definitions.clone(),
+            commands: new_commands,
+        },
+        assumptions,
+    })
+}
+
+/// Collects the indices of the outermost commands used as premises by a command, including those
+/// used by the commands inside it, if it is a subproof.
+pub(crate) fn outermost_premises(command: &ProofCommand, result: &mut Vec<usize>) {
+    match command {
+        ProofCommand::Assume { .. } => (),
+        ProofCommand::Step(s) => {
+            let all = s.premises.iter().chain(&s.discharge);
+            result.extend(all.filter(|(depth, _)| *depth == 0).map(|&(_, i)| i));
+        }
+        ProofCommand::Subproof(s) => {
+            for c in &s.commands {
+                outermost_premises(c, result);
+            }
+        }
+    }
+}
+
+/// Rewrites the references to outermost commands in a command, according to the new indices of
+/// those commands.
+fn remap_premises(command: &ProofCommand, new_indices: &[Option<usize>]) -> ProofCommand {
+    let remap = |&(depth, i): &(usize, usize)| {
+        if depth == 0 {
+            (0, new_indices[i].unwrap())
+        } else {
+            (depth, i)
+        }
+    };
+    match command {
+        ProofCommand::Assume { .. } => command.clone(),
+        ProofCommand::Step(s) => ProofCommand::Step(ProofStep {
+            premises: s.premises.iter().map(remap).collect(),
+            discharge: s.discharge.iter().map(remap).collect(),
+            ..s.clone()
+        }),
+        ProofCommand::Subproof(s) => ProofCommand::Subproof(Subproof {
+            commands: s
+                .commands
+                .iter()
+                .map(|c| remap_premises(c, new_indices))
+                .collect(),
+            args: s.args.clone(),
+            context_id: s.context_id,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn ids(proof: &Proof) -> Vec<&str> {
+        proof.iter().map(ProofCommand::id).collect()
+    }
+
+    #[test]
+    fn test_slice() {
+        let definitions = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (declare-fun r () Bool)
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (not p))
+            (assume h3 r)
+            (step t4 (cl p q) :rule or :premises (h1))
+            (step t5 (cl q) :rule resolution :premises (t4 h2))
+            (step t6 (cl q r) :rule weakening :premises (t5))
+            (step t7 (cl r) :rule weakening :premises (h3))
+        ";
+        let (_, proof, mut pool) = parser::parse_instance(
+            definitions.as_bytes(),
+            proof.as_bytes(),
+            parser::Config::new(),
+        )
+        .unwrap();
+
+        let sliced = slice(&mut pool, &proof, "t6", None).unwrap();
+        assert_eq!(ids(&sliced.proof), ["h1", "h2", "t4", "t5", "t6"]);
+        assert_eq!(sliced.assumptions.len(), 2);
+
+        // With a maximum distance of 1, `t4` is replaced by an assumption of its disjunction, and
+        // `h1` is no longer needed
+        let sliced = slice(&mut pool, &proof, "t6", Some(1)).unwrap();
+        assert_eq!(ids(&sliced.proof), ["h2", "t4.h", "t4", "t5", "t6"]);
+        let ProofCommand::Step(t5) = &sliced.proof.commands[3] else {
+            panic!()
+        };
+        assert_eq!(t5.premises, [(0, 2), (0, 0)]);
+
+        let sliced = slice(&mut pool, &proof, "t6", Some(0)).unwrap();
+        assert_eq!(ids(&sliced.proof), ["t5", "t6"]);
+        assert!(sliced.proof.commands[0].is_assume());
+
+        assert!(slice(&mut pool, &proof, "t8", None).is_none());
+    }
+}
@@ -0,0 +1,255 @@
+//! Structural statistics about a proof and its terms, such as the number of steps that use each
+//! rule and how much the terms in the proof are shared.
+
+use crate::ast::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ProofStatistics {
+    /// The number of `assume` commands, including the ones inside subproofs.
+    pub assumes: usize,
+
+    /// The number of `step` commands, including the ones inside subproofs.
+    pub steps: usize,
+
+    /// The number of subproofs.
+    pub subproofs: usize,
+
+    /// The number of steps that use each rule.
+    pub steps_per_rule: BTreeMap<String, usize>,
+
+    /// For each clause size, the number of commands whose conclusion has that size.
+    pub clause_sizes: BTreeMap<usize, usize>,
+
+    /// The length of the longest chain of premises in the proof, counting both ends. A command that
+    /// uses a subproof as premise extends the longest chain that ends inside that subproof.
+    pub dag_depth: usize,
+
+    /// The maximum depth of a term in the proof, where constants and variables have depth 1.
+    pub max_term_depth: usize,
+
+    /// The number of terms in the term pool, including sorts and terms that were created while
+    /// parsing but are not used in the proof.
+    pub pool_size: usize,
+
+    /// The number of distinct term nodes used in the proof.
+    pub dag_size: usize,
+
+    /// The number of term nodes used in the proof if no subterm was shared. This saturates at
+    /// `u64::MAX`.
+    pub tree_size: u64,
+
+    /// The ratio between `tree_size` and `dag_size`.
+    pub sharing_factor: f64,
+}
+
+fn children(term: &Term) -> Vec<&Rc<Term>> {
+    match term {
+        Term::Const(_) | Term::Var(..) | Term::Sort(_) => Vec::new(),
+        Term::App(f, args) => std::iter::once(f).chain(args).collect(),
+        Term::Op(_, args) => args.iter().collect(),
+        Term::ParamOp { op_args, args, .. } => op_args.iter().chain(args).collect(),
+        Term::Binder(_, _, inner) => vec![inner],
+        Term::Let(bindings, inner) => bindings
+            .iter()
+            .map(|(_, value)| value)
+            .chain(std::iter::once(inner))
+            .collect(),
+    }
+}
+
+/// Computes, and caches, the depth and tree size of terms.
+#[derive(Default)]
+struct TermSizes {
+    cache: HashMap<Rc<Term>, (usize, u64)>,
+}
+
+impl TermSizes {
+    fn visit(&mut self, term: &Rc<Term>) -> (usize, u64) {
+        if let Some(&result) = self.cache.get(term) {
+            return result;
+        }
+        let mut depth = 0;
+        let mut size: u64 = 1;
+        for child in children(term) {
+            let (d, s) = self.visit(child);
+            depth = depth.max(d);
+            size = size.saturating_add(s);
+        }
+        let result = (depth + 1, size);
+        self.cache.insert(term.clone(), result);
+        result
+    }
+}
+
+struct Collector {
+    stats: ProofStatistics,
+    terms: TermSizes,
+    tree_size: u64,
+}
+
+impl Collector {
+    fn add_terms(&mut self, terms: &[Rc<Term>]) {
+        for t in terms {
+            let (depth, size) = self.terms.visit(t);
+            self.stats.max_term_depth = self.stats.max_term_depth.max(depth);
+            self.tree_size = self.tree_size.saturating_add(size);
+        }
+    }
+
+    /// Visits the commands in a scope, and returns the length of the longest premise chain that
+    /// ends in any of them. `scopes` holds, for each enclosing scope, the length of the longest
+    /// chain that ends in each of its commands.
+    fn visit_commands(&mut self, commands: &[ProofCommand], scopes: &mut Vec<Vec<usize>>) -> usize {
+        let depth = scopes.len();
+        scopes.push(Vec::with_capacity(commands.len()));
+        for command in commands {
+            let chain = match command {
+                ProofCommand::Assume { term, .. } => {
+                    self.stats.assumes += 1;
+                    self.add_terms(std::slice::from_ref(term));
+                    1
+                }
+                ProofCommand::Step(s) => {
+                    self.stats.steps += 1;
+                    *self.stats.steps_per_rule.entry(s.rule.clone()).or_default() += 1;
+                    self.add_terms(&s.clause);
+                    self.add_terms(&s.args);
+                    let premises = s.premises.iter().chain(&s.discharge);
+                    1 + premises.map(|&(d, i)| scopes[d][i]).max().unwrap_or(0)
+                }
+                ProofCommand::Subproof(s) => {
+                    self.stats.subproofs += 1;
+                    self.visit_commands(&s.commands, scopes)
+                }
+            };
+            if !command.is_subproof() {
+                let size = command.clause().len();
+                *self.stats.clause_sizes.entry(size).or_default() += 1;
+            }
+            self.stats.dag_depth = self.stats.dag_depth.max(chain);
+            scopes[depth].push(chain);
+        }
+        scopes.pop().unwrap().into_iter().max().unwrap_or(0)
+    }
+}
+
+/// Collects statistics about a proof, whose terms are stored in `pool`.
+pub fn collect_statistics(pool: &PrimitivePool, proof: &Proof) -> ProofStatistics {
+    let mut collector = Collector {
+        stats: ProofStatistics::default(),
+        terms: TermSizes::default(),
+        tree_size: 0,
+    };
+    collector.visit_commands(&proof.commands, &mut Vec::new());
+
+    let mut stats = collector.stats;
+    stats.pool_size = pool.len();
+    stats.dag_size = collector.terms.cache.len();
+    stats.tree_size = collector.tree_size;
+    stats.sharing_factor = if stats.dag_size == 0 {
+        1.0
+    } else {
+        stats.tree_size as f64 / stats.dag_size as f64
+    };
+    stats
+}
+
+impl ProofStatistics {
+    /// Writes the statistics in CSV format, with one metric per row. The rule and clause size
+    /// metrics are written as `rule.<name>` and `clause_size.<size>`, respectively.
+    pub fn write_csv(&self, dest: &mut dyn std::io::Write) -> std::io::Result<()> {
+        writeln!(dest, "metric,value")?;
+        writeln!(dest, "assumes,{}", self.assumes)?;
+        writeln!(dest, "steps,{}", self.steps)?;
+        writeln!(dest, "subproofs,{}", self.subproofs)?;
+        writeln!(dest, "dag_depth,{}", self.dag_depth)?;
+        writeln!(dest, "max_term_depth,{}", self.max_term_depth)?;
+        writeln!(dest, "pool_size,{}", self.pool_size)?;
+        writeln!(dest, "dag_size,{}", self.dag_size)?;
+        writeln!(dest, "tree_size,{}", self.tree_size)?;
+        writeln!(dest, "sharing_factor,{}", self.sharing_factor)?;
+        for (rule, count) in &self.steps_per_rule {
+            writeln!(dest, "rule.{},{}", rule, count)?;
+        }
+        for (size, count) in &self.clause_sizes {
+            writeln!(dest, "clause_size.{},{}", size, count)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProofStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "assumes: {}", self.assumes)?;
+        writeln!(f, "steps: {}", self.steps)?;
+        writeln!(f, "subproofs: {}", self.subproofs)?;
+        writeln!(f, "proof DAG depth: {}", self.dag_depth)?;
+        writeln!(f, "max term depth: {}", self.max_term_depth)?;
+        writeln!(f, "term pool size: {}", self.pool_size)?;
+        writeln!(f, "term DAG size: {}", self.dag_size)?;
+        writeln!(f, "term tree size: {}", self.tree_size)?;
+        writeln!(f, "sharing factor: {:.2}", self.sharing_factor)?;
+        writeln!(f, "steps per rule:")?;
+        for (rule, count) in &self.steps_per_rule {
+            writeln!(f, "    {}: {}", rule, count)?;
+        }
+        writeln!(f, "clause sizes:")?;
+        for (size, count) in &self.clause_sizes {
+            writeln!(f, "    {}: {}", size, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_statistics() {
+        let problem = "
+            (declare-const p Bool)
+            (declare-const q Bool)
+            (assert (or p q))
+            (assert (not p))
+            (assert (not q))
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (not p))
+            (assume h3 (not q))
+            (step t4 (cl p q) :rule or :premises (h1))
+            (anchor :step t5)
+                (assume t5.a0 p)
+                (step t5.t1 (cl p) :rule resolution :premises (t4 h3 t5.a0))
+                (step t5 (cl (not p) p) :rule subproof :discharge (t5.a0))
+            (step t6 (cl) :rule resolution :premises (t4 h2 h3))
+        ";
+        let (_, proof, pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+        let stats = collect_statistics(&pool, &proof);
+
+        assert_eq!(stats.assumes, 4);
+        assert_eq!(stats.steps, 4);
+        assert_eq!(stats.subproofs, 1);
+        assert_eq!(stats.steps_per_rule["resolution"], 2);
+        assert_eq!(stats.steps_per_rule["subproof"], 1);
+        assert_eq!(stats.clause_sizes[&1], 5);
+        assert_eq!(stats.clause_sizes[&2], 2);
+
+        // h1 -> t4 -> t5.t1
+        assert_eq!(stats.dag_depth, 3);
+        assert_eq!(stats.max_term_depth, 2);
+
+        // The distinct terms are `p`, `q`, `(or p q)`, `(not p)` and `(not q)`
+        assert_eq!(stats.dag_size, 5);
+        assert!(stats.sharing_factor > 1.0);
+    }
+}
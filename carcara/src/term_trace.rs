@@ -0,0 +1,329 @@
+//! Low-level traces of the term operations done while checking a proof, meant to be replayed by an
+//! independent verifier, such as the `carcara-trace-checker` crate.
+//!
+//! A trace records three kinds of operations:
+//!
+//! - term constructions, in which terms are added to the term pool;
+//! - equality checks modulo reordering of equalities (`polyeq`) or alpha-equivalence, together
+//!   with their result;
+//! - applications of capture-avoiding substitutions, together with their result.
+//!
+//! Since the term pool implements hash consing, the checker compares terms by pointer. This is only
+//! sound if no two structurally identical terms are constructed separately, which the verifier
+//! checks by replaying the constructions. The remaining operations are computed again by the
+//! verifier, and compared to the results recorded in the trace. The logic of each rule is not
+//! recorded, so it is still part of the trusted code base.
+//!
+//! # Format
+//!
+//! A trace is a text file with one operation per line. The first line is the header
+//! `carcara-term-trace 1`. Each term is identified by a number, and is defined by a `t` line
+//! before it is used. Terms are assigned consecutive numbers, starting at zero. Names and string
+//! constants are written between double quotes, with `\"`, `\\` and `\n` as escape sequences.
+//!
+//! ```text
+//! t <id> int <value>
+//! t <id> real <numerator> <denominator>
+//! t <id> str <string>
+//! t <id> bv <value> <width>
+//! t <id> var <name> <sort>
+//! t <id> app <function> <arg>...
+//! t <id> op <operator> <arg>...
+//! t <id> pop <operator> <number of op args> <op arg>... <arg>...
+//! t <id> sort <head> <arg>...
+//! t <id> bind <binder> <number of bindings> (<name> <sort>)... <body>
+//! t <id> let <number of bindings> (<name> <value>)... <body>
+//! e <polyeq|alpha> <a> <b> <0|1>
+//! s <term> <result> <number of mappings> (<variable> <value>)...
+//! ```
+//!
+//! The head of a sort is one of `Function`, `Bool`, `Int`, `Real`, `String`, `RegLan`, `Array`,
+//! `RoundingMode`, `RareList`, `Type`, `Atom:<name>`, `BitVec:<width>` or
+//! `FloatingPoint:<exponent width>:<significand width>`.
+//!
+//! Only operations done in the thread that calls [`record`] are recorded, so the parallel checker
+//! can't be traced. Comparisons done with a custom [`Polyeq`](crate::ast::Polyeq), like modulo
+//! n-ary expansion, are not recorded either.
+
+use crate::ast::{Constant, Rc, Sort, Term};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+/// The kind of an equality check recorded in a trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqualityKind {
+    /// Equality modulo reordering of equalities.
+    Polyeq,
+
+    /// Alpha-equivalence, also modulo reordering of equalities.
+    Alpha,
+}
+
+struct Recorder {
+    writer: Box<dyn Write>,
+    ids: HashMap<Rc<Term>, usize>,
+
+    /// The first error that happened while writing the trace. After an error, nothing else is
+    /// written.
+    error: Option<io::Error>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Removes the recorder of the current thread when dropped, even if the recorded function panics.
+struct RecorderGuard;
+
+impl Drop for RecorderGuard {
+    fn drop(&mut self) {
+        RECORDER.with(|r| r.borrow_mut().take());
+    }
+}
+
+/// Runs `f`, recording a trace of the term operations it does in the current thread to `writer`.
+/// Returns the result of `f`, or an error if the trace could not be written.
+///
+/// # Panics
+///
+/// This panics if a trace is already being recorded in the current thread.
+pub fn record<T>(writer: impl Write + 'static, f: impl FnOnce() -> T) -> io::Result<T> {
+    RECORDER.with(|r| {
+        let mut r = r.borrow_mut();
+        assert!(r.is_none(), "a term trace is already being recorded");
+        let mut recorder = Recorder {
+            writer: Box::new(writer),
+            ids: HashMap::new(),
+            error: None,
+        };
+        recorder.write_line("carcara-term-trace 1".to_owned());
+        *r = Some(recorder);
+    });
+    let guard = RecorderGuard;
+    let result = f();
+
+    let recorder = RECORDER.with(|r| r.borrow_mut().take()).unwrap();
+    drop(guard);
+    match recorder.error {
+        Some(e) => Err(e),
+        None => {
+            let mut writer = recorder.writer;
+            writer.flush()?;
+            Ok(result)
+        }
+    }
+}
+
+/// Calls `f` with the recorder of the current thread, if a trace is being recorded.
+fn with_recorder(f: impl FnOnce(&mut Recorder)) {
+    RECORDER.with(|r| {
+        // If the recorder is already borrowed, this was called while recording another operation,
+        // which can't happen, since recording an operation doesn't do any term operations
+        if let Ok(mut r) = r.try_borrow_mut() {
+            if let Some(recorder) = r.as_mut() {
+                f(recorder);
+            }
+        }
+    });
+}
+
+/// Records that `term` was added to the term pool.
+pub(crate) fn construction(term: &Rc<Term>) {
+    with_recorder(|r| {
+        r.define(term);
+    });
+}
+
+/// Records an equality check between `a` and `b`, and its result.
+pub(crate) fn equality(kind: EqualityKind, a: &Rc<Term>, b: &Rc<Term>, result: bool) {
+    with_recorder(|r| {
+        let (a, b) = (r.define(a), r.define(b));
+        let kind = match kind {
+            EqualityKind::Polyeq => "polyeq",
+            EqualityKind::Alpha => "alpha",
+        };
+        r.write_line(format!("e {} {} {} {}", kind, a, b, result as u8));
+    });
+}
+
+/// Records that applying the substitution given by `mappings` to `term` resulted in `result`.
+pub(crate) fn substitution<'a>(
+    mappings: impl ExactSizeIterator<Item = (&'a Rc<Term>, &'a Rc<Term>)>,
+    term: &Rc<Term>,
+    result: &Rc<Term>,
+) {
+    with_recorder(|r| {
+        let mut line = format!(
+            "s {} {} {}",
+            r.define(term),
+            r.define(result),
+            mappings.len()
+        );
+        for (x, t) in mappings {
+            write!(line, " {} {}", r.define(x), r.define(t)).unwrap();
+        }
+        r.write_line(line);
+    });
+}
+
+impl Recorder {
+    fn write_line(&mut self, line: String) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.writer, "{}", line) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Returns the id of `term`, writing its definition, and the definitions of its subterms, if
+    /// they were not written yet.
+    fn define(&mut self, term: &Rc<Term>) -> usize {
+        if let Some(&id) = self.ids.get(term) {
+            return id;
+        }
+        let node = match term.as_ref() {
+            Term::Const(Constant::Integer(i)) => format!("int {}", i),
+            Term::Const(Constant::Real(r)) => format!("real {} {}", r.numer(), r.denom()),
+            Term::Const(Constant::String(s)) => format!("str {}", quote(s)),
+            Term::Const(Constant::BitVec(v, w)) => format!("bv {} {}", v, w),
+            Term::Var(name, sort) => {
+                let sort = self.define(sort);
+                format!("var {} {}", quote(name), sort)
+            }
+            Term::App(f, args) => {
+                let f = self.define(f);
+                format!("app {}{}", f, self.define_all(args))
+            }
+            Term::Op(op, args) => format!("op {}{}", op, self.define_all(args)),
+            Term::ParamOp { op, op_args, args } => {
+                let op_args_ids = self.define_all(op_args);
+                let args_ids = self.define_all(args);
+                format!("pop {} {}{}{}", op, op_args.len(), op_args_ids, args_ids)
+            }
+            Term::Sort(sort) => {
+                let (head, args) = sort_head(sort);
+                format!("sort {}{}", head, self.define_all(args))
+            }
+            Term::Binder(binder, bindings, body) => {
+                let mut node = format!("bind {} {}", binder, bindings.len());
+                for (name, sort) in bindings {
+                    write!(node, " {} {}", quote(name), self.define(sort)).unwrap();
+                }
+                write!(node, " {}", self.define(body)).unwrap();
+                node
+            }
+            Term::Let(bindings, body) => {
+                let mut node = format!("let {}", bindings.len());
+                for (name, value) in bindings {
+                    write!(node, " {} {}", quote(name), self.define(value)).unwrap();
+                }
+                write!(node, " {}", self.define(body)).unwrap();
+                node
+            }
+        };
+        let id = self.ids.len();
+        self.ids.insert(term.clone(), id);
+        self.write_line(format!("t {} {}", id, node));
+        id
+    }
+
+    /// Defines each of the given terms, and returns their ids, each preceded by a space.
+    fn define_all<'a>(&mut self, terms: impl IntoIterator<Item = &'a Rc<Term>>) -> String {
+        terms
+            .into_iter()
+            .map(|t| format!(" {}", self.define(t)))
+            .collect()
+    }
+}
+
+/// Returns the head of a sort, as written in a trace, and its arguments.
+fn sort_head(sort: &Sort) -> (String, Vec<&Rc<Term>>) {
+    let head = match sort {
+        Sort::Function(args) => return ("Function".to_owned(), args.iter().collect()),
+        Sort::Atom(name, args) => return (format!("Atom:{}", quote(name)), args.iter().collect()),
+        Sort::Array(x, y) => return ("Array".to_owned(), vec![x, y]),
+        Sort::BitVec(w) => format!("BitVec:{}", w),
+        Sort::FloatingPoint(e, s) => format!("FloatingPoint:{}:{}", e, s),
+        Sort::Bool => "Bool".to_owned(),
+        Sort::Int => "Int".to_owned(),
+        Sort::Real => "Real".to_owned(),
+        Sort::String => "String".to_owned(),
+        Sort::RegLan => "RegLan".to_owned(),
+        Sort::RoundingMode => "RoundingMode".to_owned(),
+        Sort::RareList => "RareList".to_owned(),
+        Sort::Type => "Type".to_owned(),
+    };
+    (head, Vec::new())
+}
+
+/// Writes a string between double quotes, escaping double quotes, backslashes and newlines.
+fn quote(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{polyeq, Operator, PrimitivePool, TermPool};
+    use std::time::Duration;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::rc::Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let buffer = SharedBuffer::default();
+        let mut pool = PrimitivePool::new();
+        let result = record(buffer.clone(), || {
+            let int = pool.add(Term::Sort(Sort::Int));
+            let x = pool.add(Term::new_var("x \"y\"", int));
+            let one = pool.add(Term::new_int(1));
+            let a = pool.add(Term::Op(Operator::Equals, vec![x.clone(), one.clone()]));
+            let b = pool.add(Term::Op(Operator::Equals, vec![one, x]));
+            polyeq(&a, &b, &mut Duration::default())
+        })
+        .unwrap();
+        assert!(result);
+
+        let expected = [
+            "carcara-term-trace 1",
+            "t 0 sort Int",
+            r#"t 1 var "x \"y\"" 0"#,
+            "t 2 int 1",
+            "t 3 op = 1 2",
+            "t 4 op = 2 1",
+            "e polyeq 3 4 1",
+        ];
+        let trace = String::from_utf8(buffer.0.take()).unwrap();
+        assert_eq!(trace, expected.join("\n") + "\n");
+
+        // Nothing is recorded after `record` returns
+        pool.add(Term::new_int(2));
+        assert!(buffer.0.borrow().is_empty());
+    }
+}
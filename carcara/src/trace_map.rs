@@ -0,0 +1,221 @@
+//! Traceability between the steps of an original proof and the steps of a transformed proof.
+
+use crate::ast::*;
+use indexmap::{IndexMap, IndexSet};
+use std::collections::HashSet;
+#[cfg(feature = "serde")]
+use std::io;
+#[cfg(feature = "serde")]
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Error)]
+pub enum TraceMapError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid trace map: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// A mapping between the step ids of an original proof and the step ids of a proof obtained by
+/// transforming it, for example by elaborating it.
+///
+/// Each command in the original proof is mapped to the commands that replaced it. A command that
+/// was not changed is mapped to itself, and a command that was removed from the proof is mapped
+/// to no commands. Since a command can be replaced by several new commands, and several original
+/// commands can be replaced by the same command, the mapping is many-to-many.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceMap {
+    forward: IndexMap<String, IndexSet<String>>,
+}
+
+impl TraceMap {
+    /// Constructs a trace map in which every command in the given proof is mapped to itself.
+    pub fn identity(root: &Rc<ProofNode>) -> Self {
+        let mut forward = IndexMap::new();
+        root.traverse(|node| {
+            if !node.is_subproof() {
+                forward.insert(node.id().to_owned(), IndexSet::from([node.id().to_owned()]));
+            }
+        });
+        Self { forward }
+    }
+
+    /// Constructs a trace map in which every command in the given list, including the commands in
+    /// its subproofs, is mapped to itself. Unlike [`TraceMap::identity`], this also includes the
+    /// commands that are not used to derive the conclusion of the proof.
+    pub(crate) fn identity_of_commands(commands: &[ProofCommand]) -> Self {
+        let forward = ProofIter::starting_at(commands, 0)
+            .filter(|c| !c.is_subproof())
+            .map(|c| (c.id().to_owned(), IndexSet::from([c.id().to_owned()])))
+            .collect();
+        Self { forward }
+    }
+
+    /// Records that the command with id `old` was replaced by the command with id `new`.
+    pub(crate) fn insert(&mut self, old: &str, new: &str) {
+        let entry = self.forward.entry(old.to_owned()).or_default();
+        entry.insert(new.to_owned());
+    }
+
+    /// Returns the ids of the commands that replaced the original command with the given id, or
+    /// `None` if there was no such command in the original proof.
+    pub fn successors(&self, id: &str) -> Option<&IndexSet<String>> {
+        self.forward.get(id)
+    }
+
+    /// Returns the ids of the original commands that were replaced by the command with the given
+    /// id. If the command was not in the transformed proof, this is empty.
+    pub fn predecessors(&self, id: &str) -> Vec<&str> {
+        self.forward
+            .iter()
+            .filter(|(_, new)| new.contains(id))
+            .map(|(old, _)| old.as_str())
+            .collect()
+    }
+
+    /// Composes this trace map with the trace map of a later transformation, so that it maps the
+    /// commands of the original proof to the commands of the proof after both transformations.
+    pub fn compose(&mut self, next: &TraceMap) {
+        for new in self.forward.values_mut() {
+            *new = new
+                .iter()
+                .flat_map(|id| next.forward.get(id).into_iter().flatten())
+                .cloned()
+                .collect();
+        }
+    }
+
+    /// Records that the command with id `old` was replaced by the command `new`, along with all
+    /// the commands it transitively depends on, except those in `existing`. The command `new` is
+    /// recorded even if it is in `existing`, which happens when the original command was replaced
+    /// by a command that was already in the proof.
+    fn record(&mut self, old: &str, new: &Rc<ProofNode>, existing: &HashSet<Rc<ProofNode>>) {
+        let entry = self.forward.entry(old.to_owned()).or_default();
+        let mut seen = HashSet::new();
+        let mut todo = vec![new];
+        while let Some(node) = todo.pop() {
+            if (node != new && existing.contains(node)) || !seen.insert(node) {
+                continue;
+            }
+            match node.as_ref() {
+                ProofNode::Assume { id, .. } => {
+                    entry.insert(id.clone());
+                }
+                ProofNode::Step(s) => {
+                    entry.insert(s.id.clone());
+                    todo.extend(
+                        s.premises
+                            .iter()
+                            .chain(&s.discharge)
+                            .chain(&s.previous_step),
+                    );
+                }
+                ProofNode::Subproof(s) => {
+                    todo.push(&s.last_step);
+                    todo.extend(&s.outbound_premises);
+                }
+            }
+        }
+    }
+
+    /// Writes the trace map as a JSON object. The object has two fields: `forward`, which maps
+    /// each original command to the commands that replaced it, and `backward`, which maps each
+    /// new command to the original commands it replaced.
+    #[cfg(feature = "serde")]
+    pub fn write_json(&self, dest: &mut dyn io::Write) -> io::Result<()> {
+        let mut backward: IndexMap<&str, Vec<&str>> = IndexMap::new();
+        for (old, new) in &self.forward {
+            for id in new {
+                backward.entry(id).or_default().push(old);
+            }
+        }
+        let json = TraceMapJson { forward: &self.forward, backward };
+        serde_json::to_writer_pretty(&mut *dest, &json)?;
+        writeln!(dest)
+    }
+
+    /// Reads a trace map from a JSON object, in the format written by [`TraceMap::write_json`].
+    /// Only the `forward` field is used, since the `backward` field can be computed from it.
+    #[cfg(feature = "serde")]
+    pub fn read_json(src: &mut dyn io::Read) -> Result<Self, TraceMapError> {
+        #[derive(serde::Deserialize)]
+        struct ForwardOnly {
+            forward: IndexMap<String, IndexSet<String>>,
+        }
+
+        let ForwardOnly { forward } = serde_json::from_reader(src)?;
+        Ok(Self { forward })
+    }
+}
+
+/// The JSON representation of a trace map, as written by [`TraceMap::write_json`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct TraceMapJson<'a> {
+    forward: &'a IndexMap<String, IndexSet<String>>,
+    backward: IndexMap<&'a str, Vec<&'a str>>,
+}
+
+/// Records, for a single pass over the proof, which commands replaced each original command.
+pub(crate) struct TraceRecorder {
+    map: TraceMap,
+    existing: HashSet<Rc<ProofNode>>,
+}
+
+impl TraceRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            map: TraceMap::default(),
+            existing: HashSet::new(),
+        }
+    }
+
+    /// Records that the `original` node was replaced by `mutated`. This must be called for each
+    /// node, in the order they are mutated, so every node that `mutated` depends on but were not
+    /// introduced by this replacement are already known.
+    pub(crate) fn record(&mut self, original: &Rc<ProofNode>, mutated: &Rc<ProofNode>) {
+        if !original.is_subproof() {
+            self.map.record(original.id(), mutated, &self.existing);
+        }
+        self.existing.insert(mutated.clone());
+    }
+
+    /// Returns the recorded trace map, or `None` if nothing was recorded.
+    pub(crate) fn finish(self) -> Option<TraceMap> {
+        (!self.map.forward.is_empty()).then_some(self.map)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json() {
+        let mut trace = TraceMap::default();
+        trace.insert("h1", "h1");
+        trace.insert("t2", "t2");
+        trace.insert("t2", "t2.lowered");
+        trace.insert("t3", "t2");
+        trace.forward.insert("t4".to_owned(), IndexSet::new());
+        trace.insert("t\u{e9}\"5", "t\u{e9}\"5");
+
+        let mut json = Vec::new();
+        trace.write_json(&mut json).unwrap();
+        let read = TraceMap::read_json(&mut json.as_slice()).unwrap();
+        assert_eq!(trace, read);
+        assert_eq!(read.predecessors("t2"), ["t2", "t3"]);
+
+        // Escape sequences are decoded, and the `backward` field is ignored
+        let json = r#"{"forward": {"t\u00e91": ["t\u00e91"], "t2": []}, "backward": {}}"#;
+        let read = TraceMap::read_json(&mut json.as_bytes()).unwrap();
+        assert!(read.successors("t\u{e9}1").unwrap().contains("t\u{e9}1"));
+        assert!(read.successors("t2").unwrap().is_empty());
+
+        for invalid in ["", "{", "{\"forward\": {\"t1\": [t1]}}", "{} x", "{}"] {
+            assert!(TraceMap::read_json(&mut invalid.as_bytes()).is_err());
+        }
+    }
+}
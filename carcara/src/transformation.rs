@@ -0,0 +1,262 @@
+//! A common interface for the passes that transform proofs, such as the elaboration steps and the
+//! compression algorithms, so that they can be freely composed into pipelines.
+
+use crate::{
+    ast::*,
+    checker::compression::{self, CompressionAlgorithm, CompressionError},
+    limits::SizeLimitError,
+    utils::Instant,
+};
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TransformationError {
+    #[error("{0}")]
+    SizeLimit(#[from] SizeLimitError),
+
+    #[error("{0}")]
+    Compression(#[from] CompressionError),
+}
+
+pub type TransformationResult<T> = Result<T, TransformationError>;
+
+/// A summary of what a transformation did to a proof.
+#[derive(Debug, Clone)]
+pub struct TransformationReport {
+    /// The name of the transformation.
+    pub name: String,
+
+    /// The time spent running the transformation.
+    pub duration: Duration,
+
+    /// The number of proof commands, not counting subproofs, before the transformation.
+    pub commands_before: usize,
+
+    /// The number of proof commands, not counting subproofs, after the transformation.
+    pub commands_after: usize,
+}
+
+/// A pass that takes a proof and produces a new, equivalent proof.
+pub trait ProofTransformation {
+    /// The name of the transformation, used in its report.
+    fn name(&self) -> String;
+
+    /// Transforms the proof whose root is `root`.
+    fn transform(
+        &mut self,
+        pool: &mut PrimitivePool,
+        problem: &Problem,
+        root: &Rc<ProofNode>,
+    ) -> TransformationResult<Rc<ProofNode>>;
+
+    /// Transforms the proof whose root is `root`, and returns the new proof together with a report
+    /// of the transformation.
+    fn apply(
+        &mut self,
+        pool: &mut PrimitivePool,
+        problem: &Problem,
+        root: &Rc<ProofNode>,
+    ) -> TransformationResult<(Rc<ProofNode>, TransformationReport)> {
+        let _span = tracing::debug_span!("transformation", name = %self.name()).entered();
+        let commands_before = count_commands(root);
+        let time = Instant::now();
+        let result = self.transform(pool, problem, root)?;
+        let report = TransformationReport {
+            name: self.name(),
+            duration: time.elapsed(),
+            commands_before,
+            commands_after: count_commands(&result),
+        };
+        Ok((result, report))
+    }
+}
+
+fn count_commands(root: &Rc<ProofNode>) -> usize {
+    let mut count = 0;
+    root.traverse(|node| {
+        if !node.is_subproof() {
+            count += 1;
+        }
+    });
+    count
+}
+
+impl ProofTransformation for CompressionAlgorithm {
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn transform(
+        &mut self,
+        pool: &mut PrimitivePool,
+        _: &Problem,
+        root: &Rc<ProofNode>,
+    ) -> TransformationResult<Rc<ProofNode>> {
+        let proof = Proof {
+            constant_definitions: Vec::new(),
+            commands: root.into_commands(),
+        };
+        let compressed = compression::compress_proof_with(&proof, pool, &[*self])?;
+        Ok(ProofNode::from_commands(compressed.commands))
+    }
+}
+
+/// A single step of the elaboration pipeline, run with the given elaborator configuration.
+#[cfg(feature = "elaborator")]
+#[derive(Debug, Clone)]
+pub struct ElaborationPass {
+    pub step: crate::elaborator::ElaborationStep,
+    pub config: crate::elaborator::Config,
+}
+
+#[cfg(feature = "elaborator")]
+impl ProofTransformation for ElaborationPass {
+    fn name(&self) -> String {
+        format!("{:?}", self.step)
+    }
+
+    fn transform(
+        &mut self,
+        pool: &mut PrimitivePool,
+        problem: &Problem,
+        root: &Rc<ProofNode>,
+    ) -> TransformationResult<Rc<ProofNode>> {
+        let mut elaborator = crate::elaborator::Elaborator::new(pool, problem, self.config.clone());
+        Ok(elaborator.elaborate(root, vec![self.step])?)
+    }
+}
+
+/// A sequence of transformations that are applied in order.
+#[derive(Default)]
+pub struct Pipeline {
+    transformations: Vec<Box<dyn ProofTransformation>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a transformation to the end of the pipeline.
+    pub fn then(mut self, transformation: impl ProofTransformation + 'static) -> Self {
+        self.push(Box::new(transformation));
+        self
+    }
+
+    /// Adds a transformation to the end of the pipeline.
+    pub fn push(&mut self, transformation: Box<dyn ProofTransformation>) {
+        self.transformations.push(transformation);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transformations.is_empty()
+    }
+
+    /// Runs the pipeline on a proof, returning the transformed proof and a report for each
+    /// transformation, in order.
+    pub fn run(
+        &mut self,
+        pool: &mut PrimitivePool,
+        problem: &Problem,
+        proof: &Proof,
+    ) -> TransformationResult<(Proof, Vec<TransformationReport>)> {
+        let root = ProofNode::from_commands(proof.commands.clone());
+        let (root, reports) = self.run_on_node(pool, problem, &root)?;
+        let proof = Proof {
+            constant_definitions: proof.constant_definitions.clone(),
+            commands: root.into_commands(),
+        };
+        Ok((proof, reports))
+    }
+
+    fn run_on_node(
+        &mut self,
+        pool: &mut PrimitivePool,
+        problem: &Problem,
+        root: &Rc<ProofNode>,
+    ) -> TransformationResult<(Rc<ProofNode>, Vec<TransformationReport>)> {
+        let mut current = root.clone();
+        let mut reports = Vec::with_capacity(self.transformations.len());
+        for t in &mut self.transformations {
+            let (result, report) = t.apply(pool, problem, &current)?;
+            current = result;
+            reports.push(report);
+        }
+        Ok((current, reports))
+    }
+}
+
+/// A pipeline is itself a transformation, so pipelines can be nested.
+impl ProofTransformation for Pipeline {
+    fn name(&self) -> String {
+        let names: Vec<_> = self.transformations.iter().map(|t| t.name()).collect();
+        format!("Pipeline({})", names.join(", "))
+    }
+
+    fn transform(
+        &mut self,
+        pool: &mut PrimitivePool,
+        problem: &Problem,
+        root: &Rc<ProofNode>,
+    ) -> TransformationResult<Rc<ProofNode>> {
+        Ok(self.run_on_node(pool, problem, root)?.0)
+    }
+}
+
+#[cfg(all(test, feature = "elaborator"))]
+mod tests {
+    use super::*;
+    use crate::{checker, elaborator, parser};
+
+    #[test]
+    fn test_pipeline() {
+        let problem = "
+            (declare-const p Bool)
+            (declare-const q Bool)
+            (assert (or p q))
+            (assert (or p (not q)))
+            (assert (not p))
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (or p (not q)))
+            (assume h3 (not p))
+            (step t4 (cl p q) :rule or :premises (h1))
+            (step t5 (cl q) :rule resolution :premises (t4 h3))
+            (step t6 (cl p (not q)) :rule or :premises (h2))
+            (step t7 (cl (not q)) :rule resolution :premises (t6 h3))
+            (step t8 (cl) :rule resolution :premises (t5 t7))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser::Config::new())
+                .unwrap();
+
+        let config = elaborator::Config {
+            lia_solver: None,
+            nla_solver: None,
+            uncrowd_rotation: false,
+            hole_solver: None,
+            solver_limits: Default::default(),
+            solver_cache: None,
+            limits: crate::limits::SizeLimits::new(),
+            trace: false,
+            target: Default::default(),
+        };
+        let elaborate = |step| ElaborationPass { step, config: config.clone() };
+        let mut pipeline = Pipeline::new()
+            .then(CompressionAlgorithm::LowerUnits)
+            .then(Pipeline::new().then(elaborate(elaborator::ElaborationStep::Local)));
+        let (transformed, reports) = pipeline.run(&mut pool, &problem, &proof).unwrap();
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].name, "LowerUnits");
+        assert_eq!(reports[0].commands_before, proof.commands.len());
+        assert_eq!(reports[1].name, "Pipeline(Local)");
+        assert_eq!(reports[1].commands_after, transformed.commands.len());
+
+        let checker_config = checker::Config::new().elaborated(true);
+        let mut checker = checker::ProofChecker::new(&mut pool, checker_config);
+        assert!(checker.check(&problem, &transformed).is_ok());
+    }
+}
@@ -0,0 +1,372 @@
+//! Extraction and minimization of unsatisfiable cores.
+//!
+//! A proof of unsatisfiability usually only depends on some of the problem premises. The premises
+//! that are (transitively) used to derive the empty clause form an unsatisfiable core of the
+//! problem. This core is not necessarily minimal, so this module also implements a deletion-based
+//! minimization procedure, that repeatedly calls an external SMT solver on subsets of the core.
+
+use crate::{
+    ast::{printer, Polyeq, PrimitivePool, Problem, ProblemPrelude, Proof, ProofNode, Rc, Term},
+    elaborator::{ExternalSolver, SolverCache, SolverError, SolverLimits},
+    limits::SizeLimits,
+    parser,
+};
+use indexmap::IndexSet;
+use std::{collections::HashMap, io::BufRead, sync::Arc};
+use thiserror::Error;
+
+/// The options for [`minimize_unsat_core`].
+#[derive(Debug, Clone)]
+pub struct MinimizationOptions {
+    /// The external solver that is called on subsets of the core. The solver should be an SMT
+    /// solver that is able to produce proofs in the Alethe format.
+    pub solver: Arc<dyn ExternalSolver>,
+
+    /// The time and memory limits enforced on each solver call. A subset for which the solver
+    /// times out is considered satisfiable.
+    pub limits: SolverLimits,
+
+    /// If `Some`, the proofs produced by the solver for each subset are stored in this cache, and
+    /// reused when the same subset is tried again, e.g. when minimizing the core of another proof
+    /// of the same problem. See [`SolverCache`] for more details.
+    pub cache: Option<SolverCache>,
+}
+
+#[derive(Debug, Error)]
+pub enum UnsatCoreError {
+    #[error(transparent)]
+    SolverError(#[from] SolverError),
+}
+
+/// Returns the problem premises that are used to derive the proof's conclusion, in the order in
+/// which they appear in the problem.
+///
+/// An `assume` command may not be syntactically equal to its corresponding premise, but only equal
+/// modulo reordering of equalities and n-ary operators. In that case, the original premise is
+/// returned.
+pub fn get_unsat_core(problem: &Problem, proof: &Proof) -> Vec<Rc<Term>> {
+    let root = ProofNode::from_commands(proof.commands.clone());
+    let mut used = vec![false; problem.premises.len()];
+    for node in root.get_assumptions() {
+        let (_, _, term) = node.as_assume().unwrap();
+        if let Some(i) = find_premise(&problem.premises, term) {
+            used[i] = true;
+        }
+    }
+    problem
+        .premises
+        .iter()
+        .zip(used)
+        .filter(|(_, is_used)| *is_used)
+        .map(|(p, _)| p.clone())
+        .collect()
+}
+
+fn find_premise(premises: &IndexSet<Rc<Term>>, term: &Rc<Term>) -> Option<usize> {
+    premises.get_index_of(term).or_else(|| {
+        premises.iter().position(|p| {
+            Polyeq::new()
+                .mod_reordering(true)
+                .mod_nary(true)
+                .eq(term, p)
+        })
+    })
+}
+
+/// Counts, for each premise in the unsat core, how many proof steps use it directly. This is used
+/// to guide the minimization, since premises that are used less often are more likely to be
+/// redundant.
+fn count_premise_uses(problem: &Problem, proof: &Proof, core: &[Rc<Term>]) -> Vec<usize> {
+    let root = ProofNode::from_commands(proof.commands.clone());
+    let mut counts = vec![0; core.len()];
+    root.traverse(|node| {
+        let Some(step) = node.as_step() else { return };
+        for premise in &step.premises {
+            if let Some((_, 0, term)) = premise.as_assume() {
+                let found = find_premise(&problem.premises, term)
+                    .and_then(|i| core.iter().position(|c| *c == problem.premises[i]));
+                if let Some(i) = found {
+                    counts[i] += 1;
+                }
+            }
+        }
+    });
+    counts
+}
+
+/// Minimizes the unsat core of a proof, by repeatedly calling an external solver on subsets of the
+/// core. The resulting core is locally minimal, that is, removing any single premise from it makes
+/// the problem satisfiable (or at least not provably unsatisfiable by the solver).
+///
+/// Premises that are used by fewer proof steps are tried first. Whenever the solver proves a subset
+/// unsatisfiable, the core of the proof it produces is used to shrink the current core even
+/// further. The solver results for each subset that is tried are cached, so no subset is sent to
+/// the solver more than once.
+pub fn minimize_unsat_core(
+    pool: &mut PrimitivePool,
+    problem: &Problem,
+    proof: &Proof,
+    options: &MinimizationOptions,
+) -> Result<Vec<Rc<Term>>, UnsatCoreError> {
+    let core = get_unsat_core(problem, proof);
+    let counts = count_premise_uses(problem, proof, &core);
+
+    let mut minimizer = CoreMinimizer {
+        pool,
+        prelude: &problem.prelude,
+        options,
+        core: &core,
+        cache: HashMap::new(),
+    };
+
+    let mut candidates: Vec<usize> = (0..core.len()).collect();
+    candidates.sort_by_key(|&i| counts[i]);
+
+    let mut current: Vec<usize> = (0..core.len()).collect();
+    for candidate in candidates {
+        if !current.contains(&candidate) {
+            continue;
+        }
+        let subset: Vec<usize> = current
+            .iter()
+            .copied()
+            .filter(|&i| i != candidate)
+            .collect();
+        if let Some(smaller) = minimizer.try_subset(subset)? {
+            current = smaller;
+        }
+    }
+
+    Ok(current.into_iter().map(|i| core[i].clone()).collect())
+}
+
+struct CoreMinimizer<'a> {
+    pool: &'a mut PrimitivePool,
+    prelude: &'a ProblemPrelude,
+    options: &'a MinimizationOptions,
+    core: &'a [Rc<Term>],
+
+    /// Maps each subset of the core that was already tried to its result. If the subset was shown
+    /// to be unsatisfiable, this holds the core of the proof produced by the solver, which is a
+    /// subset of the original subset.
+    cache: HashMap<Vec<usize>, Option<Vec<usize>>>,
+}
+
+impl<'a> CoreMinimizer<'a> {
+    fn try_subset(&mut self, subset: Vec<usize>) -> Result<Option<Vec<usize>>, UnsatCoreError> {
+        if let Some(result) = self.cache.get(&subset) {
+            return Ok(result.clone());
+        }
+
+        let terms: Vec<_> = subset.iter().map(|&i| self.core[i].clone()).collect();
+        let problem = get_problem_string(self.pool, self.prelude, self.options, &terms);
+        let result = match self.options.cache.as_ref().and_then(|c| c.get(&problem)) {
+            Some(proof) => match self.get_solver_core(&problem, &proof, &subset) {
+                Some(core) => Some(core),
+                None => self.run_solver(&problem, &subset)?,
+            },
+            None => self.run_solver(&problem, &subset)?,
+        };
+
+        self.cache.insert(subset, result.clone());
+        Ok(result)
+    }
+
+    /// Runs the solver on the given problem. If the solver finds the problem to be unsatisfiable,
+    /// this returns the core of the proof it produced, and stores the proof in the solver cache.
+    /// Otherwise (if the solver answers `sat` or `unknown`, or times out), this returns `None`.
+    fn run_solver(
+        &mut self,
+        problem: &str,
+        subset: &[usize],
+    ) -> Result<Option<Vec<usize>>, UnsatCoreError> {
+        let solver = self.options.solver.as_ref();
+        let output = match solver.run(problem, self.options.limits) {
+            Ok(output) => output,
+            Err(SolverError::SolverTimeout) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // When the problem is not unsatisfiable, the `get-proof` command will fail, and the solver
+        // may return a non-zero exit code, so we check the result before checking the exit code
+        let mut first_line = String::new();
+        let _ = output.stdout.as_slice().read_line(&mut first_line);
+        if matches!(first_line.trim_end(), "sat" | "unknown") {
+            return Ok(None);
+        }
+        let proof = match solver.extract_proof(&output) {
+            Ok(proof) => proof,
+            Err(SolverError::SolverTimeout) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let Some(core) = self.get_solver_core(problem, proof, subset) else {
+            return Ok(Some(subset.to_vec()));
+        };
+        if let Some(cache) = &self.options.cache {
+            if let Err(e) = cache.insert(problem, proof) {
+                tracing::warn!("failed to write to solver cache: {}", e);
+            }
+        }
+        Ok(Some(core))
+    }
+
+    /// Parses the proof given by the solver and returns its unsat core, as indices into the
+    /// original core. Returns `None` if the proof cannot be parsed, or if it uses premises that are
+    /// not in the given subset.
+    fn get_solver_core(
+        &mut self,
+        problem: &str,
+        proof: &[u8],
+        subset: &[usize],
+    ) -> Option<Vec<usize>> {
+        let config = parser::Config {
+            apply_function_defs: false,
+            expand_lets: true,
+            int_real_subtyping: parser::IntRealSubtyping::Implicit,
+            strict: false,
+            parse_hole_args: false,
+            shadowed_binders: parser::ShadowedBinders::Allow,
+            limits: SizeLimits::new(),
+            track_locations: false,
+            alpha_canonical_binders: false,
+            fresh_name_scheme: self.pool.fresh_name_scheme(),
+            check_sorts: false,
+            alethe_version: None,
+            dialect: parser::Dialect::Alethe,
+        };
+        let parsed = parser::parse_instance_with_pool(problem.as_bytes(), proof, config, self.pool);
+        let (problem, proof) = match parsed {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::warn!("failed to parse solver proof: {}", e);
+                return None;
+            }
+        };
+
+        let solver_core = get_unsat_core(&problem, &proof);
+        let result: Vec<usize> = subset
+            .iter()
+            .copied()
+            .filter(|&i| solver_core.contains(&self.core[i]))
+            .collect();
+
+        // If some premise in the solver core is not in the subset, the solver proof is not
+        // reliable, so we ignore it
+        (result.len() == solver_core.len()).then_some(result)
+    }
+}
+
+fn get_problem_string(
+    pool: &mut PrimitivePool,
+    prelude: &ProblemPrelude,
+    options: &MinimizationOptions,
+    premises: &[Rc<Term>],
+) -> String {
+    let mut bytes = Vec::new();
+    printer::write_smt_assertions(pool, prelude, &mut bytes, premises, false).unwrap();
+    options
+        .solver
+        .problem_string(prelude, &String::from_utf8(bytes).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_unsat_core() {
+        let problem: &[u8] = b"
+            (declare-const a Bool)
+            (declare-const b Bool)
+            (declare-const c Bool)
+            (assert a)
+            (assert (= b c))
+            (assert (not a))
+            (assert b)
+        ";
+        let proof: &[u8] = b"
+            (assume h1 a)
+            (assume h2 (= c b))
+            (assume h3 (not a))
+            (step t1 (cl) :rule resolution :premises (h1 h3))
+        ";
+        let (problem, proof, _) =
+            parser::parse_instance(problem, proof, parser::Config::new()).unwrap();
+        let core = get_unsat_core(&problem, &proof);
+        let expected = vec![problem.premises[0].clone(), problem.premises[2].clone()];
+        assert_eq!(expected, core);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_minimize_unsat_core() {
+        use std::process::{self, Command};
+
+        /// A "solver" that runs a shell script on the problem.
+        #[derive(Debug)]
+        struct Script(&'static str);
+
+        impl ExternalSolver for Script {
+            fn name(&self) -> &'static str {
+                "script"
+            }
+
+            fn command(&self) -> Command {
+                let mut command = Command::new("sh");
+                command.args(["-c", self.0]);
+                command
+            }
+        }
+
+        let problem: &[u8] = b"
+            (declare-const a Bool)
+            (declare-const b Bool)
+            (assert a)
+            (assert (not a))
+            (assert b)
+            (assert (not b))
+        ";
+        let proof: &[u8] = b"
+            (assume h1 a)
+            (assume h2 (not a))
+            (assume h3 b)
+            (assume h4 (not b))
+            (step t1 (cl) :rule hole :premises (h1 h2 h3 h4))
+        ";
+        let (problem, proof, mut pool) =
+            parser::parse_instance(problem, proof, parser::Config::new()).unwrap();
+
+        // This solver only finds the subsets that contain both `a` and `(not a)` to be unsat
+        let solver = Script(
+            r#"case "$(cat)" in
+                *"(assert a)"*"(assert (not a))"*)
+                    echo unsat
+                    echo "(assume h1 a)"
+                    echo "(assume h2 (not a))"
+                    echo "(step t1 (cl) :rule resolution :premises (h1 h2))" ;;
+                *) echo sat ;;
+            esac"#,
+        );
+        let dir = std::env::temp_dir().join(format!("carcara-unsat-core-{}", process::id()));
+        let mut options = MinimizationOptions {
+            solver: Arc::new(solver),
+            limits: SolverLimits::default(),
+            cache: Some(SolverCache::new(&dir)),
+        };
+        let expected = vec![problem.premises[0].clone(), problem.premises[1].clone()];
+        let core = minimize_unsat_core(&mut pool, &problem, &proof, &options).unwrap();
+        assert_eq!(expected, core);
+
+        // The proof found for the unsat subset is taken from the cache
+        options.solver = Arc::new(Script("cat > /dev/null; echo sat"));
+        let core = minimize_unsat_core(&mut pool, &problem, &proof, &options).unwrap();
+        assert_eq!(expected, core);
+
+        options.cache = None;
+        let core = minimize_unsat_core(&mut pool, &problem, &proof, &options).unwrap();
+        assert_eq!(problem.premises.iter().cloned().collect::<Vec<_>>(), core);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
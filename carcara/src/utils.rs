@@ -3,11 +3,19 @@ use indexmap::{IndexMap, IndexSet};
 use rug::Integer;
 use std::{
     borrow::Borrow,
+    cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
     ops,
 };
 
+// `std::time::Instant::now` panics on `wasm32-unknown-unknown`, so there we use the browser clock
+// instead. Everywhere else, `web_time::Instant` is not even compiled, and this is the `std` type
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use std::time::Instant;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub use web_time::Instant;
+
 /// Returns `true` if the character is a valid symbol character in the SMT-LIB and Alethe formats.
 pub fn is_symbol_character(ch: char) -> bool {
     match ch {
@@ -115,10 +123,6 @@ impl<K, V> HashMapStack<K, V> {
         self.scopes.len()
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.scopes.iter().all(IndexMap::is_empty)
-    }
-
     pub fn push_scope(&mut self) {
         self.scopes.push(IndexMap::new());
     }
@@ -232,6 +236,56 @@ impl From<ops::RangeTo<usize>> for Range {
     }
 }
 
+/// A simple s-expression, used to read the formats that don't need a full parser, like the
+/// declarative rule specs and LFSC proofs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SExpr {
+    Atom(String),
+    List(Vec<SExpr>),
+}
+
+impl fmt::Display for SExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SExpr::Atom(a) => write!(f, "{}", a),
+            SExpr::List(list) => {
+                write!(f, "(")?;
+                for (i, e) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", e)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Reads a sequence of s-expressions. Comments start with `;` and go until the end of the line.
+pub fn read_sexprs(src: &str) -> Result<Vec<SExpr>, String> {
+    let mut stack = vec![Vec::new()];
+    for line in src.lines() {
+        let line = line.split(';').next().unwrap();
+        let line = line.replace('(', " ( ").replace(')', " ) ");
+        for token in line.split_whitespace() {
+            match token {
+                "(" => stack.push(Vec::new()),
+                ")" => {
+                    let list = stack.pop().filter(|_| !stack.is_empty());
+                    let list = list.ok_or("unexpected ')'")?;
+                    stack.last_mut().unwrap().push(SExpr::List(list));
+                }
+                atom => stack.last_mut().unwrap().push(SExpr::Atom(atom.to_owned())),
+            }
+        }
+    }
+    match stack.len() {
+        1 => Ok(stack.pop().unwrap()),
+        _ => Err("unclosed '('".to_owned()),
+    }
+}
+
 /// Provides a pretty displayable name for a type. For example, the type name for `Rc<Term>` is
 /// "term".
 pub trait TypeName {
@@ -249,3 +303,131 @@ impl TypeName for Binder {
 impl TypeName for BindingList {
     const NAME: &'static str = "binding list";
 }
+
+/// A union-find structure over the terms of a set of equalities, used to find transitivity chains
+/// in linear time, regardless of the order and orientation of the equalities.
+///
+/// Every union that merges two classes records the equality that caused it. These equalities form
+/// a spanning forest of the equalities, in which the chain between two terms can be found.
+pub struct EqualityGraph<'a> {
+    terms: IndexSet<&'a Rc<Term>>,
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+
+    /// For each term, the neighbouring terms in the spanning forest, the index of the equality
+    /// that connects them, and whether that equality must be flipped to go from the term to its
+    /// neighbour.
+    forest: Vec<Vec<(usize, usize, bool)>>,
+}
+
+impl<'a> EqualityGraph<'a> {
+    pub fn new(equalities: &[(&'a Rc<Term>, &'a Rc<Term>)]) -> Self {
+        let mut graph = Self {
+            terms: IndexSet::new(),
+            parent: Vec::new(),
+            rank: Vec::new(),
+            forest: Vec::new(),
+        };
+        for (i, &(t, u)) in equalities.iter().enumerate() {
+            let (t, u) = (graph.insert(t), graph.insert(u));
+            let (t_root, u_root) = (graph.find(t), graph.find(u));
+            if t_root == u_root {
+                continue;
+            }
+            match graph.rank[t_root].cmp(&graph.rank[u_root]) {
+                Ordering::Less => graph.parent[t_root] = u_root,
+                Ordering::Greater => graph.parent[u_root] = t_root,
+                Ordering::Equal => {
+                    graph.parent[u_root] = t_root;
+                    graph.rank[t_root] += 1;
+                }
+            }
+            graph.forest[t].push((u, i, false));
+            graph.forest[u].push((t, i, true));
+        }
+        graph
+    }
+
+    fn insert(&mut self, term: &'a Rc<Term>) -> usize {
+        let (index, is_new) = self.terms.insert_full(term);
+        if is_new {
+            self.parent.push(index);
+            self.rank.push(0);
+            self.forest.push(Vec::new());
+        }
+        index
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    /// Returns `true` if the equalities imply that `a` and `b` are equal.
+    pub fn connects(&mut self, a: &Rc<Term>, b: &Rc<Term>) -> bool {
+        if a == b {
+            return true;
+        }
+        match (self.terms.get_index_of(a), self.terms.get_index_of(b)) {
+            (Some(a), Some(b)) => self.find(a) == self.find(b),
+            _ => false,
+        }
+    }
+
+    /// Returns the terms that the equalities imply are equal to `a`, including `a` itself.
+    pub fn class_of(&mut self, a: &'a Rc<Term>) -> Vec<&'a Rc<Term>> {
+        let Some(index) = self.terms.get_index_of(a) else {
+            return vec![a];
+        };
+        let root = self.find(index);
+        let mut class = Vec::new();
+        for i in 0..self.terms.len() {
+            if self.find(i) == root {
+                class.push(self.terms[i]);
+            }
+        }
+        class
+    }
+
+    /// Finds a chain of equalities from `a` to `b`. Each element of the returned vector is the
+    /// index of an equality, together with a boolean indicating whether the equality must be
+    /// flipped for the chain to be in order. Returns `None` if there is no such chain.
+    #[cfg(feature = "elaborator")]
+    pub fn find_chain(&self, a: &Rc<Term>, b: &Rc<Term>) -> Option<Vec<(usize, bool)>> {
+        if a == b {
+            return Some(Vec::new());
+        }
+        let start = self.terms.get_index_of(a)?;
+        let end = self.terms.get_index_of(b)?;
+
+        // Since the forest has no cycles, a simple search from `start` finds the only path to
+        // `end`, if it exists. For each visited term, we store the term and equality from which
+        // it was reached
+        let mut reached_from = vec![None; self.terms.len()];
+        let mut to_visit = vec![start];
+        while let Some(current) = to_visit.pop() {
+            if current == end {
+                break;
+            }
+            for &(next, equality, is_flipped) in &self.forest[current] {
+                if next != start && reached_from[next].is_none() {
+                    reached_from[next] = Some((current, equality, is_flipped));
+                    to_visit.push(next);
+                }
+            }
+        }
+
+        let mut chain = Vec::new();
+        let mut current = end;
+        while current != start {
+            let (previous, equality, is_flipped) = reached_from[current]?;
+            chain.push((equality, is_flipped));
+            current = previous;
+        }
+        chain.reverse();
+        Some(chain)
+    }
+}
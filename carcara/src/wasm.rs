@@ -0,0 +1,125 @@
+//! Bindings for running Carcara in a browser, through WebAssembly.
+//!
+//! This module is enabled by the `wasm` feature, and exposes a single JavaScript function,
+//! [`check_proof`]. To build Carcara for `wasm32-unknown-unknown`, the `external-solver` feature
+//! must be disabled, since processes can't be spawned there. Without an external solver,
+//! `lia_generic` steps are considered holes, as usual. Only the sequential checker can be used, as
+//! the parallel checker needs threads.
+
+use crate::{checker, parser, Error};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// The result of checking a proof, as returned to JavaScript by [`check_proof`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckResult {
+    /// Either `"valid"`, `"holey"` or `"invalid"`.
+    pub status: &'static str,
+
+    /// The error message, if the proof is invalid.
+    pub error: Option<String>,
+
+    /// The id of the step where checking failed, if the error happened in a specific step.
+    pub step: Option<String>,
+
+    /// The line and column where the error happened, if it is known.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl From<Result<bool, Error>> for CheckResult {
+    fn from(result: Result<bool, Error>) -> Self {
+        let error = match result {
+            Ok(is_holey) => {
+                return Self {
+                    status: if is_holey { "holey" } else { "valid" },
+                    error: None,
+                    step: None,
+                    line: None,
+                    column: None,
+                }
+            }
+            Err(e) => e,
+        };
+        let (step, position) = match &error {
+            Error::Parser(_, pos) => (None, Some(*pos)),
+            Error::Checker { step, location, .. } => {
+                (Some(step.clone()), location.as_ref().map(|l| l.span.start))
+            }
+            _ => (None, None),
+        };
+        Self {
+            status: "invalid",
+            error: Some(error.to_string()),
+            step,
+            line: position.map(|p| p.0),
+            column: position.map(|p| p.1),
+        }
+    }
+}
+
+/// Checks a proof, using the default parser and checker configurations. This is the same as
+/// [`check_proof`], but returns the result as a Rust value.
+pub fn check_proof_result(problem: &str, proof: &str) -> CheckResult {
+    let parser_config = parser::Config {
+        track_locations: true,
+        ..parser::Config::new()
+    };
+    let result = crate::check(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        parser_config,
+        checker::Config::new(),
+        false,
+    );
+    result.into()
+}
+
+/// Checks a proof, given the contents of the problem and proof files. Returns an object with the
+/// fields of [`CheckResult`], where the fields that are not present are `undefined`.
+#[wasm_bindgen]
+pub fn check_proof(problem: &str, proof: &str) -> JsValue {
+    // Serializing a `CheckResult` can't fail, since it only contains strings and numbers
+    serde_wasm_bindgen::to_value(&check_proof_result(problem, proof)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBLEM: &str = "
+        (declare-fun p () Bool)
+        (assert p)
+        (assert (not p))
+    ";
+
+    #[test]
+    fn test_check_proof_result() {
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (step t3 (cl) :rule resolution :premises (h1 h2))
+        ";
+        assert_eq!(check_proof_result(PROBLEM, proof).status, "valid");
+
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (step t3 (cl) :rule hole :premises (h1 h2))
+        ";
+        assert_eq!(check_proof_result(PROBLEM, proof).status, "holey");
+
+        let proof = "
+            (assume h1 p)
+            (step t2 (cl) :rule resolution :premises (h1 h1))
+        ";
+        let result = check_proof_result(PROBLEM, proof);
+        assert_eq!(result.status, "invalid");
+        assert_eq!(result.step.as_deref(), Some("t2"));
+        assert_eq!(result.line, Some(3));
+
+        let result = check_proof_result(PROBLEM, "(assume h1 q)");
+        assert_eq!(result.status, "invalid");
+        assert_eq!((result.line, result.column), (Some(1), Some(12)));
+    }
+}
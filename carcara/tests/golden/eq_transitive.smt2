@@ -0,0 +1,11 @@
+(declare-sort T 0)
+(declare-fun a () T)
+(declare-fun b () T)
+(declare-fun c () T)
+(declare-fun d () T)
+(declare-fun e () T)
+(assert (= a b))
+(assert (= b e))
+(assert (= c b))
+(assert (= d c))
+(assert (not (= a d)))
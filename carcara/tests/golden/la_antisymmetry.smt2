@@ -0,0 +1,5 @@
+(declare-fun x () Int)
+(declare-fun y () Int)
+(assert (<= x y))
+(assert (>= x y))
+(assert (not (= x y)))
@@ -0,0 +1,7 @@
+(declare-sort T 0)
+(declare-fun a () T)
+(declare-fun b () T)
+(declare-fun c () T)
+(assert (= a b))
+(assert (= c b))
+(assert (not (= a c)))
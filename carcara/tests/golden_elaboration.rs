@@ -0,0 +1,56 @@
+//! Compares the output of the elaborator against the golden proofs in `tests/golden`. Each
+//! instance is a problem `<name>.smt2` and a proof `<name>.smt2.alethe`, whose elaborated proof is
+//! stored in `<name>.elaborated.alethe`. To update the golden proofs, run this test with the
+//! `CARCARA_UPDATE_GOLDEN` environment variable set.
+
+use carcara::*;
+use std::{fs, path::Path};
+
+fn elaborate_and_compare(problem_path: &Path, proof_path: &Path, golden_path: &Path) {
+    let (problem, proof, mut pool) = parser::parse_instance(
+        fs::read_to_string(problem_path).unwrap().as_bytes(),
+        fs::read_to_string(proof_path).unwrap().as_bytes(),
+        parser::Config::new(),
+    )
+    .unwrap();
+
+    let config = elaborator::Config {
+        lia_solver: None,
+        nla_solver: None,
+        hole_solver: None,
+        solver_limits: Default::default(),
+        solver_cache: None,
+        uncrowd_rotation: true,
+        limits: Default::default(),
+        trace: false,
+        target: Default::default(),
+    };
+    let node = ast::ProofNode::from_commands(proof.commands.clone());
+    let elaborated = elaborator::Elaborator::new(&mut pool, &problem, config)
+        .elaborate_with_default_pipeline(&node)
+        .unwrap();
+    let elaborated = ast::Proof {
+        constant_definitions: proof.constant_definitions,
+        commands: elaborated.into_commands(),
+    };
+
+    if let Err(e) = golden::check_golden(&mut pool, &problem.prelude, &elaborated, golden_path) {
+        panic!("{}", e);
+    }
+}
+
+#[test]
+fn golden_elaboration() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut num_instances = 0;
+    for entry in fs::read_dir(&dir).unwrap() {
+        let problem_path = entry.unwrap().path();
+        if problem_path.extension().is_some_and(|ext| ext == "smt2") {
+            let proof_path = problem_path.with_extension("smt2.alethe");
+            let golden_path = problem_path.with_extension("elaborated.alethe");
+            elaborate_and_compare(&problem_path, &proof_path, &golden_path);
+            num_instances += 1;
+        }
+    }
+    assert!(num_instances > 0, "no golden instances found");
+}
@@ -0,0 +1,9 @@
+use carcara::checker::conformance;
+
+#[test]
+fn spec_conformance() {
+    let examples = conformance::parse_examples(include_str!("spec_examples.txt"))
+        .expect("failed to parse specification examples");
+    let report = conformance::run_examples(examples);
+    assert!(report.is_conformant(), "\n{}", report);
+}
@@ -18,7 +18,8 @@ fn run_parallel_checker_test(
         parser::Config::new(),
     )?;
 
-    let (scheduler, schedule_context_usage) = checker::Scheduler::new(num_threads, &proof);
+    let (scheduler, schedule_context_usage) =
+        checker::Scheduler::new(num_threads, &proof, &checker::CostModel::new());
     let mut checker = checker::ParallelProofChecker::new(
         Arc::new(pool),
         Config::new(),
@@ -41,20 +42,49 @@ fn run_test(problem_path: &Path, proof_path: &Path) -> CarcaraResult<()> {
         elaborated: false,
         ignore_unknown_rules: false,
         allowed_rules: ["all_simplify".to_owned(), "rare_rewrite".to_owned()].into(),
+        accepted_rules: None,
+        denied_rules: [].into(),
+        allow_int_real_subtyping: false,
+        only_steps: None,
+        only_rules: None,
+        expected_conclusion: None,
+        enforce_logic: true,
+        interpretations: checker::interpretations::Interpretations::new(),
+        rare_rules: checker::rare::RareRules::new(),
     };
 
     // First, we check the proof normally
     checker::ProofChecker::new(&mut pool, checker_config.clone()).check(&problem, &proof)?;
 
+    // We also check it using the streaming checker
+    check_streaming(
+        io::BufReader::new(fs::File::open(problem_path)?),
+        || Ok(io::BufReader::new(fs::File::open(proof_path)?)),
+        parser::Config::new(),
+        checker_config.clone(),
+    )?;
+    check_streaming_single_pass(
+        io::BufReader::new(fs::File::open(problem_path)?),
+        io::BufReader::new(fs::File::open(proof_path)?),
+        parser::Config::new(),
+        checker_config.clone(),
+    )?;
+
     // Then we elaborate it
     let config = elaborator::Config {
-        lia_options: None,
-        hole_options: None,
+        lia_solver: None,
+        nla_solver: None,
+        hole_solver: None,
+        solver_limits: Default::default(),
+        solver_cache: None,
         uncrowd_rotation: true,
+        limits: Default::default(),
+        trace: false,
+        target: Default::default(),
     };
     let node = ast::ProofNode::from_commands(proof.commands.clone());
     let elaborated_node = elaborator::Elaborator::new(&mut pool, &problem, config.clone())
-        .elaborate_with_default_pipeline(&node);
+        .elaborate_with_default_pipeline(&node)?;
     let elaborated = ast::Proof {
         constant_definitions: proof.constant_definitions.clone(),
         commands: elaborated_node.into_commands(),
@@ -66,7 +96,7 @@ fn run_test(problem_path: &Path, proof_path: &Path) -> CarcaraResult<()> {
     // Finally, we elaborate the already elaborated proof, to make sure the elaboration step is
     // idempotent
     let elaborated_twice = elaborator::Elaborator::new(&mut pool, &problem, config)
-        .elaborate_with_default_pipeline(&elaborated_node);
+        .elaborate_with_default_pipeline(&elaborated_node)?;
     assert!(
         elaborated.commands == elaborated_twice.into_commands(),
         "elaboration was not idempotent!"
@@ -96,7 +126,13 @@ fn test_file(proof_path: &str) {
             Error::Io(_) => "IO error".to_owned(),
             Error::Parser(_, (line, column)) => format!("parser error at {}:{}", line, column),
             Error::Checker { rule, step, .. } => format!("checker error at '{}' ({})", step, rule),
-            Error::DoesNotReachEmptyClause => format!("{}", e), // This one is already pretty short
+            Error::Lfsc(_) => "LFSC error".to_owned(),
+            Error::Drat(_) => "DRAT error".to_owned(),
+            Error::Export(_) => "export error".to_owned(),
+            Error::DoesNotReachEmptyClause
+            | Error::DoesNotReachExpectedConclusion
+            | Error::RejectedRules(_)
+            | Error::SizeLimit(_) => format!("{}", e), // This one is already pretty short
         };
         panic!(
             "\"{}\" returned error: {}",
@@ -44,6 +44,7 @@ fn run_job<T: CollectResults + Default + Send>(
         parser_config,
     )?;
     let parsing = parsing.elapsed();
+    let term_nodes = pool.len();
 
     let mut checker = checker::ProofChecker::new(&mut pool, checker_config);
 
@@ -57,7 +58,7 @@ fn run_job<T: CollectResults + Default + Send>(
         let node = ast::ProofNode::from_commands(proof.commands);
         let (elaborated, pipeline_durations) =
             elaborator::Elaborator::new(&mut pool, &problem, config)
-                .elaborate_with_stats(&node, pipeline);
+                .elaborate_with_stats(&node, pipeline)?;
         elaborated.into_commands();
         (elaboration.elapsed(), pipeline_durations)
     } else {
@@ -78,6 +79,7 @@ fn run_job<T: CollectResults + Default + Send>(
             assume: checker_stats.assume_time,
             assume_core: checker_stats.assume_core_time,
             elaboration_pipeline: pipeline_durations,
+            term_nodes,
         },
     );
     *results = checker_stats.results;
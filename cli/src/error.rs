@@ -6,6 +6,19 @@ pub enum CliError {
     CantInferProblemFile(PathBuf),
     InvalidSliceId(String),
     BothFilesStdin,
+    UnsatCoreError(carcara::unsat_core::UnsatCoreError),
+    CostModelError(carcara::checker::CostModelError),
+    TraceMapError(carcara::trace_map::TraceMapError),
+    UnknownTraceStep(String),
+    ConformanceError(carcara::checker::conformance::ConformanceError),
+    UnknownRule(String),
+    ManifestError(PathBuf, usize, String),
+    JsonManifestError(PathBuf, serde_json::Error),
+    BenchmarkSummaryError(carcara::benchmarking::BenchmarkSummaryError),
+    CompressionError(carcara::checker::compression::CompressionError),
+    TransformationError(carcara::transformation::TransformationError),
+    ParserErrors(usize),
+    LanguageServerError(String),
 }
 
 pub type CliResult<T> = Result<T, CliError>;
@@ -22,6 +35,54 @@ impl From<carcara::Error> for CliError {
     }
 }
 
+impl From<carcara::unsat_core::UnsatCoreError> for CliError {
+    fn from(e: carcara::unsat_core::UnsatCoreError) -> Self {
+        Self::UnsatCoreError(e)
+    }
+}
+
+impl From<carcara::checker::CostModelError> for CliError {
+    fn from(e: carcara::checker::CostModelError) -> Self {
+        Self::CostModelError(e)
+    }
+}
+
+impl From<carcara::trace_map::TraceMapError> for CliError {
+    fn from(e: carcara::trace_map::TraceMapError) -> Self {
+        Self::TraceMapError(e)
+    }
+}
+
+impl From<carcara::checker::conformance::ConformanceError> for CliError {
+    fn from(e: carcara::checker::conformance::ConformanceError) -> Self {
+        Self::ConformanceError(e)
+    }
+}
+
+impl From<carcara::benchmarking::BenchmarkSummaryError> for CliError {
+    fn from(e: carcara::benchmarking::BenchmarkSummaryError) -> Self {
+        Self::BenchmarkSummaryError(e)
+    }
+}
+
+impl From<carcara::checker::compression::CompressionError> for CliError {
+    fn from(e: carcara::checker::compression::CompressionError) -> Self {
+        Self::CompressionError(e)
+    }
+}
+
+impl From<carcara::transformation::TransformationError> for CliError {
+    fn from(e: carcara::transformation::TransformationError) -> Self {
+        Self::TransformationError(e)
+    }
+}
+
+impl From<lsp_server::ProtocolError> for CliError {
+    fn from(e: lsp_server::ProtocolError) -> Self {
+        Self::LanguageServerError(e.to_string())
+    }
+}
+
 impl fmt::Display for CliError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -31,6 +92,32 @@ impl fmt::Display for CliError {
             }
             CliError::BothFilesStdin => write!(f, "problem and proof files can't both be `-`"),
             CliError::InvalidSliceId(id) => write!(f, "invalid id for slice: {}", id),
+            CliError::UnsatCoreError(e) => write!(f, "failed to minimize unsat core: {}", e),
+            CliError::CostModelError(e) => write!(f, "failed to read cost model: {}", e),
+            CliError::TraceMapError(e) => write!(f, "failed to read trace map: {}", e),
+            CliError::UnknownTraceStep(id) => write!(f, "step '{}' is not in the trace map", id),
+            CliError::ConformanceError(e) => write!(f, "failed to read examples: {}", e),
+            CliError::UnknownRule(name) => write!(f, "unknown rule: '{}'", name),
+            CliError::ManifestError(path, line, message) => {
+                write!(
+                    f,
+                    "invalid manifest '{}' (line {}): {}",
+                    path.display(),
+                    line,
+                    message
+                )
+            }
+            CliError::JsonManifestError(path, e) => {
+                write!(f, "invalid manifest '{}': {}", path.display(), e)
+            }
+            CliError::BenchmarkSummaryError(e) => {
+                write!(f, "failed to read benchmark results: {}", e)
+            }
+            CliError::CompressionError(e) => write!(f, "failed to compress proof: {}", e),
+            CliError::TransformationError(e) => write!(f, "failed to transform proof: {}", e),
+            CliError::ParserErrors(1) => write!(f, "found 1 parser error"),
+            CliError::ParserErrors(n) => write!(f, "found {} parser errors", n),
+            CliError::LanguageServerError(e) => write!(f, "language server error: {}", e),
         }
     }
 }
@@ -1,5 +1,7 @@
 use ansi_term::{ANSIString, Color, Style};
 use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::{env, io};
+use tracing_subscriber::EnvFilter;
 
 pub struct Logger {
     colors_enabled: bool,
@@ -39,3 +41,24 @@ pub fn init(max_level: LevelFilter, colors_enabled: bool) {
     log::set_boxed_logger(Box::new(Logger { colors_enabled })).expect("couldn't set up logger");
     log::set_max_level(max_level);
 }
+
+/// The environment variable that enables the `tracing` diagnostics emitted by the library.
+const TRACING_ENV_VAR: &str = "CARCARA_LOG";
+
+/// If the `CARCARA_LOG` environment variable is set, installs a `tracing` subscriber that prints
+/// the library's events and spans matching its filter, for example
+/// `CARCARA_LOG=carcara::checker::rules::resolution=trace`. Otherwise, the library's events are
+/// printed by the `log` logger, according to the `--log` level.
+pub fn init_tracing(colors_enabled: bool) {
+    let Ok(directives) = env::var(TRACING_ENV_VAR) else {
+        return;
+    };
+    match EnvFilter::try_new(&directives) {
+        Ok(filter) => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(io::stderr)
+            .with_ansi(colors_enabled)
+            .init(),
+        Err(e) => log::warn!("invalid `{}` filter: {}", TRACING_ENV_VAR, e),
+    }
+}
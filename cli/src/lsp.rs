@@ -0,0 +1,570 @@
+//! A language server for Alethe proof files, started by the `lsp` command. It communicates with the
+//! editor through stdin and stdout, and provides:
+//!
+//! - diagnostics, from the parser and from the checker, whenever a proof file is opened or changed;
+//! - go-to-definition, from a premise of a step to the command it references;
+//! - hover information, showing the clause of a command and the sorts of its free symbols.
+//!
+//! The problem file for each proof is inferred from its path, in the same way as in the `check`
+//! command, and is read from disk every time the proof is checked.
+
+use crate::{error::CliError, path_args::infer_problem_path};
+use carcara::{
+    ast::{PrimitivePool, Proof, ProofCommand, TermPool, TermPoolView},
+    checker, parser,
+    parser::{CommandLocation, SourceMap, Span},
+    Error,
+};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as NotificationTrait, PublishDiagnostics,
+    },
+    request::{GotoDefinition, HoverRequest, Request as RequestTrait},
+    Diagnostic, DiagnosticSeverity, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind,
+    OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use std::{collections::HashMap, fmt::Write, fs};
+
+/// Runs the language server until the editor asks it to shut down.
+pub fn run(parser_config: parser::Config, checker_config: checker::Config) -> Result<(), CliError> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities).unwrap())?;
+
+    let mut server = Server {
+        connection: &connection,
+        parser_config,
+        checker_config,
+        documents: HashMap::new(),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                server.handle_request(request)?;
+            }
+            Message::Notification(notification) => server.handle_notification(notification)?,
+            Message::Response(_) => (),
+        }
+    }
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+/// An open proof file, and the result of parsing it. The parsed proof is kept so that requests can
+/// be answered without parsing the file again.
+struct Document {
+    text: String,
+    pool: PrimitivePool,
+    proof: Option<Proof>,
+    source_map: SourceMap,
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+    documents: HashMap<Url, Document>,
+}
+
+impl Server<'_> {
+    fn send(&self, message: impl Into<Message>) -> Result<(), CliError> {
+        self.connection
+            .sender
+            .send(message.into())
+            .map_err(|e| CliError::LanguageServerError(e.to_string()))
+    }
+
+    /// Answers a request. If its parameters are invalid, the error is logged and an error response
+    /// is sent, so the server keeps running. This only fails if the response can't be sent.
+    fn handle_request(&mut self, request: Request) -> Result<(), CliError> {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            HoverRequest::METHOD => request_params::<HoverRequest>(request)
+                .map(|params| serde_json::to_value(self.hover(params))),
+            GotoDefinition::METHOD => request_params::<GotoDefinition>(request)
+                .map(|params| serde_json::to_value(self.definition(params))),
+            method => {
+                let message = format!("unsupported request: {}", method);
+                let response = Response::new_err(id, ErrorCode::MethodNotFound as i32, message);
+                return self.send(response);
+            }
+        };
+        let response = match result {
+            Ok(Ok(value)) => Response::new_ok(id, value),
+            Ok(Err(e)) => {
+                log::error!("failed to serialize response: {}", e);
+                Response::new_err(id, ErrorCode::InternalError as i32, e.to_string())
+            }
+            Err(message) => {
+                log::error!("{}", message);
+                Response::new_err(id, ErrorCode::InvalidParams as i32, message)
+            }
+        };
+        self.send(response)
+    }
+
+    /// Handles a notification. Notifications with invalid parameters are logged and ignored, since
+    /// they can't be answered.
+    fn handle_notification(&mut self, notification: Notification) -> Result<(), CliError> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidOpenTextDocument>(notification) else {
+                    return Ok(());
+                };
+                let document = params.text_document;
+                self.update(document.uri, document.text)
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidChangeTextDocument>(notification)
+                else {
+                    return Ok(());
+                };
+
+                // Since we only support full document synchronization, the last change has the
+                // whole text of the document
+                match params.content_changes.into_iter().last() {
+                    Some(change) => self.update(params.text_document.uri, change.text),
+                    None => Ok(()),
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidCloseTextDocument>(notification) else {
+                    return Ok(());
+                };
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.publish_diagnostics(uri, Vec::new())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Parses and checks the new text of a document, and publishes the resulting diagnostics.
+    fn update(&mut self, uri: Url, text: String) -> Result<(), CliError> {
+        let mut document = Document {
+            text,
+            pool: PrimitivePool::new(),
+            proof: None,
+            source_map: SourceMap::default(),
+        };
+        let diagnostics = match read_problem(&uri) {
+            Ok(problem) => self.analyze(&mut document, &problem),
+            Err(message) => vec![diagnostic(Range::default(), message)],
+        };
+        self.documents.insert(uri.clone(), document);
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn publish_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> Result<(), CliError> {
+        let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+        self.send(Notification::new(
+            PublishDiagnostics::METHOD.to_owned(),
+            params,
+        ))
+    }
+
+    fn analyze(&self, document: &mut Document, problem: &str) -> Vec<Diagnostic> {
+        let config = parser::Config {
+            track_locations: true,
+            ..self.parser_config
+        };
+        let mut parser = match parser::Parser::new(&mut document.pool, config, problem.as_bytes()) {
+            Ok(p) => p,
+            Err(e) => {
+                return vec![diagnostic(
+                    Range::default(),
+                    format!("in problem file: {}", e),
+                )]
+            }
+        };
+        let problem = match parser.parse_problem() {
+            Ok(p) => p,
+            Err(e) => {
+                return vec![diagnostic(
+                    Range::default(),
+                    format!("in problem file: {}", e),
+                )]
+            }
+        };
+        let parsed = parser
+            .reset(document.text.as_bytes())
+            .and_then(|()| parser.parse_proof_with_recovery());
+        document.source_map = parser.take_source_map().unwrap_or_default();
+        let (proof, parser_errors) = match parsed {
+            Ok(result) => result,
+            Err(e) => return vec![error_diagnostic(&e)],
+        };
+
+        // If there are syntax errors, the proof is incomplete, so checking it would only produce
+        // misleading errors
+        let diagnostics = if parser_errors.is_empty() {
+            let mut checker =
+                checker::ProofChecker::new(&mut document.pool, self.checker_config.clone());
+            match checker.check(&problem, &proof) {
+                Ok(_) => Vec::new(),
+                Err(e) => vec![error_diagnostic(&e.with_location(&document.source_map))],
+            }
+        } else {
+            parser_errors
+                .into_iter()
+                .map(|e| diagnostic(point_range(e.position), e.error.to_string()))
+                .collect()
+        };
+        document.proof = Some(proof);
+        diagnostics
+    }
+
+    fn hover(&mut self, params: HoverParams) -> Option<Hover> {
+        let params = params.text_document_position_params;
+        let document = self.documents.get_mut(&params.text_document.uri)?;
+        let (id, location) = document
+            .source_map
+            .command_at(from_lsp_position(params.position))?;
+        let command = document
+            .proof
+            .as_ref()?
+            .iter()
+            .filter(|c| !c.is_subproof() && c.id() == id)
+            .last()?;
+
+        let mut value = String::new();
+        match command {
+            ProofCommand::Step(step) => {
+                writeln!(value, "**step** `{}` (rule `{}`)", step.id, step.rule).unwrap();
+            }
+            _ => writeln!(value, "**assume** `{}`", id).unwrap(),
+        }
+        value.push_str("```smt2\n(cl");
+        for term in command.clause() {
+            write!(value, "\n  {}", term).unwrap();
+        }
+        value.push_str(")\n```\n");
+
+        let mut symbols = Vec::new();
+        for term in command.clause() {
+            for var in document.pool.free_vars(term) {
+                if !symbols.contains(&var) {
+                    symbols.push(var);
+                }
+            }
+        }
+        for var in symbols {
+            let sort = document.pool.sort(&var);
+            writeln!(value, "- `{}`: `{}`", var, sort).unwrap();
+        }
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+            range: Some(to_lsp_range(location.span)),
+        })
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let params = params.text_document_position_params;
+        let uri = params.text_document.uri;
+        let document = self.documents.get(&uri)?;
+        let position = from_lsp_position(params.position);
+        let (id, location) = document.source_map.command_at(position)?;
+        let target = find_premise(document, id, location, position)?;
+        Some(GotoDefinitionResponse::Scalar(Location {
+            uri,
+            range: to_lsp_range(target.span),
+        }))
+    }
+}
+
+/// Extracts the parameters of a request, or returns the message of the error response that should
+/// be sent if they are invalid.
+fn request_params<R: RequestTrait>(request: Request) -> Result<R::Params, String> {
+    request
+        .extract(R::METHOD)
+        .map(|(_, params)| params)
+        .map_err(|e| format!("invalid parameters for '{}': {:?}", R::METHOD, e))
+}
+
+/// Extracts the parameters of a notification, logging an error if they are invalid.
+fn notification_params<N: NotificationTrait>(notification: Notification) -> Option<N::Params> {
+    notification
+        .extract(N::METHOD)
+        .map_err(|e| log::error!("invalid parameters for '{}': {:?}", N::METHOD, e))
+        .ok()
+}
+
+/// Returns the location of the command referenced by the premise or discharged command at the
+/// given position, in the step with location `location`.
+fn find_premise<'a>(
+    document: &'a Document,
+    step_id: &str,
+    location: &CommandLocation,
+    position: parser::Position,
+) -> Option<&'a CommandLocation> {
+    let source_map = &document.source_map;
+    if let Some(span) = location.premises.iter().find(|s| s.contains(position)) {
+        return source_map.get(span_text(&document.text, *span)?);
+    }
+
+    // Like the parser, we consider that discharged commands may be given by their id relative to
+    // the subproof
+    let span = location.discharge.iter().find(|s| s.contains(position))?;
+    let premise = span_text(&document.text, *span)?;
+    source_map
+        .get(&format!("{}.{}", step_id, premise))
+        .or_else(|| source_map.get(premise))
+}
+
+/// Reads the problem file for the proof file with the given URI.
+fn read_problem(uri: &Url) -> Result<String, String> {
+    let path = uri
+        .to_file_path()
+        .map_err(|()| format!("not a file: {}", uri))?;
+    let problem_path = infer_problem_path(path).map_err(|e| e.to_string())?;
+    fs::read_to_string(&problem_path).map_err(|e| {
+        format!(
+            "can't read problem file '{}': {}",
+            problem_path.display(),
+            e
+        )
+    })
+}
+
+/// Returns the text in a span, if it is contained in a single line.
+fn span_text(text: &str, span: Span) -> Option<&str> {
+    let ((start_line, start), (end_line, end)) = (span.start, span.end);
+    if start_line != end_line {
+        return None;
+    }
+    let line = text.lines().nth(start_line.checked_sub(1)?)?;
+    let byte_offset = |column: usize| {
+        line.char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(line.len()))
+            .nth(column.checked_sub(1)?)
+    };
+    line.get(byte_offset(start)?..byte_offset(end)?)
+}
+
+fn diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("carcara".to_owned()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+fn error_diagnostic(error: &Error) -> Diagnostic {
+    match error {
+        Error::Parser(e, position) => diagnostic(point_range(*position), e.to_string()),
+        Error::Checker { inner, rule, location, .. } => {
+            let range = location
+                .as_ref()
+                .map(|l| to_lsp_range(l.span))
+                .unwrap_or_default();
+            let mut message = format!("{} (rule '{}')", inner, rule);
+            if let Some(explanation) = inner.explanation() {
+                message = format!("{}\n{}", message, explanation.to_string().trim_end());
+            }
+            diagnostic(range, message)
+        }
+        other => diagnostic(Range::default(), other.to_string()),
+    }
+}
+
+// Carcara positions have one-based lines and columns, while LSP positions are zero-based. Columns
+// are counted in characters, and not in UTF-16 code units as in the LSP, but these are the same
+// for the ASCII characters that Alethe proofs are made of
+
+fn from_lsp_position(position: Position) -> parser::Position {
+    (position.line as usize + 1, position.character as usize + 1)
+}
+
+fn to_lsp_position((line, column): parser::Position) -> Position {
+    Position::new(
+        line.saturating_sub(1) as u32,
+        column.saturating_sub(1) as u32,
+    )
+}
+
+fn to_lsp_range(span: Span) -> Range {
+    Range::new(to_lsp_position(span.start), to_lsp_position(span.end))
+}
+
+/// Returns a range containing only the character at the given position.
+fn point_range(position: parser::Position) -> Range {
+    let start = to_lsp_position(position);
+    Range::new(start, Position::new(start.line, start.character + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBLEM: &str = "
+        (declare-const p Bool)
+        (declare-const q Bool)
+        (assert p)
+        (assert (not p))
+    ";
+
+    /// Parses and checks a proof of `PROBLEM`, and returns the resulting document and diagnostics.
+    fn analyze(proof: &str) -> (Document, Vec<Diagnostic>) {
+        let (connection, _client) = Connection::memory();
+        let server = Server {
+            connection: &connection,
+            parser_config: parser::Config::new(),
+            checker_config: checker::Config::new(),
+            documents: HashMap::new(),
+        };
+        let mut document = Document {
+            text: proof.to_owned(),
+            pool: PrimitivePool::new(),
+            proof: None,
+            source_map: SourceMap::default(),
+        };
+        let diagnostics = server.analyze(&mut document, PROBLEM);
+        (document, diagnostics)
+    }
+
+    /// Returns the position of the first character of the `n`-th occurrence of `pattern` in the
+    /// text, counting from zero.
+    fn position_of(text: &str, pattern: &str, n: usize) -> parser::Position {
+        let (offset, _) = text.match_indices(pattern).nth(n).unwrap();
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line = text[..offset].matches('\n').count() + 1;
+        (line, text[line_start..offset].chars().count() + 1)
+    }
+
+    #[test]
+    fn test_position_conversions() {
+        assert_eq!(from_lsp_position(Position::new(0, 0)), (1, 1));
+        assert_eq!(from_lsp_position(Position::new(4, 7)), (5, 8));
+        assert_eq!(to_lsp_position((5, 8)), Position::new(4, 7));
+        assert_eq!(to_lsp_position((0, 0)), Position::new(0, 0));
+
+        let span = Span { start: (1, 2), end: (3, 4) };
+        assert_eq!(
+            to_lsp_range(span),
+            Range::new(Position::new(0, 1), Position::new(2, 3))
+        );
+        assert_eq!(
+            point_range((2, 5)),
+            Range::new(Position::new(1, 4), Position::new(1, 5))
+        );
+    }
+
+    #[test]
+    fn test_span_text() {
+        let text = "(assume h1 p)\n(step t\u{e9}2 (cl) :rule hole)";
+        let span = |start, end| Span { start, end };
+        assert_eq!(span_text(text, span((1, 9), (1, 11))), Some("h1"));
+        assert_eq!(span_text(text, span((2, 7), (2, 10))), Some("t\u{e9}2"));
+        assert_eq!(span_text(text, span((2, 22), (2, 26))), Some("hole"));
+        assert_eq!(span_text(text, span((1, 9), (2, 3))), None);
+        assert_eq!(span_text(text, span((3, 1), (3, 2))), None);
+        assert_eq!(span_text(text, span((1, 9), (1, 50))), None);
+        assert_eq!(span_text(text, span((0, 1), (0, 2))), None);
+    }
+
+    #[test]
+    fn test_find_premise() {
+        let proof = "(assume h1 p)
+(assume h2 (not p))
+(anchor :step t3)
+(assume t3.h1 q)
+(step t3.t1 (cl q) :rule reordering :premises (t3.h1))
+(step t3 (cl (not q) q) :rule subproof :discharge (h1))
+(step t4 (cl) :rule resolution :premises (h1 h2))
+";
+        let (document, diagnostics) = analyze(proof);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let find = |pattern, n| {
+            let position = position_of(proof, pattern, n);
+            let (id, location) = document.source_map.command_at(position)?;
+            find_premise(&document, id, location, position).map(|l| l.span.start)
+        };
+
+        assert_eq!(find("h1 h2", 0), Some((1, 1)));
+        assert_eq!(find("h2))", 0), Some((2, 1)));
+        assert_eq!(find("t3.h1))", 0), Some((4, 1)));
+
+        // Discharged commands may be given by their id relative to the subproof
+        assert_eq!(find("h1))", 1), Some((4, 1)));
+
+        // Positions that are not on a premise have no definition
+        assert_eq!(find("resolution", 0), None);
+        assert_eq!(find("anchor", 0), None);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let proof = "(assume h1 p)
+(assume h2 (not p))
+(step t3 (cl) :rule resolution :premises (h1 h2))
+";
+        let (document, diagnostics) = analyze(proof);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert!(document.proof.is_some());
+
+        // A checker error is reported on the failing step
+        let proof = "(assume h1 p)\n(step t2 (cl q) :rule resolution :premises (h1))\n";
+        let (_, diagnostics) = analyze(proof);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(1, 0));
+        assert!(diagnostics[0].message.contains("rule 'resolution'"));
+
+        // Every syntax error is reported, and the proof is not checked
+        let proof = "(assume h1 p)\n(step t2 (cl q) :rule)\n(assume h3 (not p q))\n";
+        let (_, diagnostics) = analyze(proof);
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].range.start.line, 1);
+        assert_eq!(diagnostics[1].range.start.line, 2);
+        assert!(diagnostics.iter().all(|d| !d.message.contains("rule")));
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let (connection, client) = Connection::memory();
+        let mut server = Server {
+            connection: &connection,
+            parser_config: parser::Config::new(),
+            checker_config: checker::Config::new(),
+            documents: HashMap::new(),
+        };
+        let invalid = serde_json::json!({ "textDocument": 42 });
+
+        // Invalid requests are answered with an error, and the server keeps running
+        let request = Request::new(1.into(), HoverRequest::METHOD.to_owned(), invalid.clone());
+        server.handle_request(request).unwrap();
+        let Ok(Message::Response(response)) = client.receiver.try_recv() else {
+            panic!("expected response");
+        };
+        assert_eq!(response.id, 1.into());
+        assert_eq!(
+            response.error.unwrap().code,
+            ErrorCode::InvalidParams as i32
+        );
+
+        // Invalid notifications are ignored
+        let method = DidOpenTextDocument::METHOD.to_owned();
+        server
+            .handle_notification(Notification::new(method, invalid))
+            .unwrap();
+        assert!(client.receiver.try_recv().is_err());
+        assert!(server.documents.is_empty());
+    }
+}
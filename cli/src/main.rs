@@ -1,11 +1,22 @@
 mod benchmarking;
 mod error;
 mod logger;
+mod lsp;
+mod manifest;
 mod path_args;
+mod report;
+mod snippet;
 
 use carcara::{
-    ast, benchmarking::OnlineBenchmarkResults, check, check_and_elaborate, check_parallel, checker,
-    elaborator, generate_lia_smt_instances, parser,
+    ast,
+    benchmarking::{BenchmarkSummary, OnlineBenchmarkResults},
+    check, check_and_elaborate_with_listener, check_drat, check_leniencies, check_lfsc,
+    check_parallel, check_resumable, check_streaming, check_streaming_single_pass,
+    check_with_definitions, check_with_expected_conclusion, check_with_hypotheses,
+    check_with_listener, checker, drat, elaborator, export, generate_lia_smt_instances,
+    instrumentation::{PipelineListener, Stage, StageReport},
+    limits::SizeLimits,
+    lint, parser, slicer, statistics, trace_map, transformation, unsat_core, ElaborationResult,
 };
 use clap::{AppSettings, ArgEnum, Args, Parser, Subcommand};
 use const_format::{formatcp, str_index};
@@ -14,9 +25,10 @@ use git_version::git_version;
 use path_args::{get_instances_from_paths, infer_problem_path};
 use std::{
     fs::File,
-    io::{self, BufRead, IsTerminal},
+    io::{self, BufRead, IsTerminal, Write},
     path::Path,
-    sync::atomic,
+    sync::{atomic, Arc},
+    time::{Duration, Instant},
 };
 
 // `git describe --all` will try to find any ref (including tags) that describes the current commit.
@@ -59,6 +71,14 @@ struct Cli {
     /// Don't use sharing when printing terms.
     #[clap(global = true, short = 'v', long)]
     no_print_with_sharing: bool,
+
+    /// How terms that occur multiple times are shared when printing proofs.
+    #[clap(arg_enum, global = true, long, default_value = "named")]
+    sharing: SharingMode,
+
+    /// Only share terms that occur more than this number of times when printing proofs.
+    #[clap(global = true, long, default_value = "1")]
+    sharing_threshold: usize,
 }
 
 #[derive(Subcommand)]
@@ -78,8 +98,67 @@ enum Command {
     /// Given a step, takes a slice of a proof consisting of all its transitive premises.
     Slice(SliceCommandOptions),
 
+    /// Compresses the resolution parts of a proof and prints the compressed proof.
+    Compress(CompressCommandOptions),
+
+    /// Checks a proof, applies a pipeline of elaboration steps and compression algorithms to it in
+    /// the given order, and prints the transformed proof.
+    Transform(TransformCommandOptions),
+
     /// Generates the equivalent SMT instance for every `lia_generic` step in a proof.
     GenerateLiaProblems(ParseCommandOptions),
+
+    /// Prints the unsat core of a proof, that is, the problem premises used to reach the empty
+    /// clause.
+    UnsatCore(UnsatCoreCommandOptions),
+
+    /// Merges two proofs of the same problem into a single proof.
+    Merge(MergeCommandOptions),
+
+    /// Parses a proof and prints statistics about it, such as the number of steps per rule, the
+    /// distribution of clause sizes and how much its terms are shared.
+    Stats(StatsCommandOptions),
+
+    /// Parses a proof and reports structural and stylistic issues that don't make it invalid, like
+    /// unused or duplicated steps. Each issue is detected by a named lint: `unused-step`,
+    /// `duplicate-step`, `simplifiable-chain`, `missing-premises` or `long-resolution`. Exits with
+    /// status 1 if a denied lint is triggered.
+    Lint(LintCommandOptions),
+
+    /// Compares two proofs of the same problem, modulo renaming of steps and alpha-equivalence of
+    /// terms, and prints the steps that were removed (`-`), added (`+`) or changed (`~`). Exits
+    /// with status 1 if the proofs differ.
+    Diff(DiffCommandOptions),
+
+    /// Given a trace map written by `elaborate --trace-map` or `compress --trace-map`, prints what
+    /// happened to a step.
+    Trace(TraceCommandOptions),
+
+    /// Checks a file of examples from the Alethe specification against the rule implementations,
+    /// and prints a summary of the conformance gaps.
+    SpecConformance(SpecConformanceCommandOptions),
+
+    /// Checks a proof in the most permissive mode, and prints a report of every step that relies
+    /// on a leniency of the checker, such as implicit reordering or elided arguments.
+    Conformance(ConformanceCommandOptions),
+
+    /// Checks a proof, treating `hole` steps and steps with unknown rules as assumptions, and
+    /// prints the clauses of these steps as the proof obligations that remain to be discharged.
+    /// Exits with status 1 if there are any obligations.
+    Obligations(ObligationsCommandOptions),
+
+    /// Checks every instance listed in a manifest file, and reports the instances whose result
+    /// differs from the expected status.
+    CheckManifest(CheckManifestCommandOptions),
+
+    /// Prints the metadata of a rule, such as the number of premises and arguments it expects. If
+    /// no rule is given, lists all rules implemented by the checker.
+    ExplainRule(ExplainRuleCommandOptions),
+
+    /// Starts a language server for proof files, communicating through stdin and stdout. The
+    /// server reports parser and checker errors as diagnostics, and supports go-to-definition on
+    /// premises and hovering over steps.
+    Lsp(LspCommandOptions),
 }
 
 #[derive(Args)]
@@ -97,6 +176,11 @@ struct StatsOptions {
     /// Enables the gathering of performance statistics
     #[clap(long)]
     stats: bool,
+
+    /// Report the number of terms in the term pool, the number of proof commands and the resident
+    /// memory after each stage of the pipeline.
+    #[clap(long)]
+    stage_sizes: bool,
 }
 
 #[derive(Args)]
@@ -119,9 +203,16 @@ struct ParsingOptions {
     #[clap(long)]
     expand_let_bindings: bool,
 
-    /// Enables `Int`/`Real` subtyping in the parser. This allows terms of sort `Int` to be passed
-    /// to arithmetic operators that are expecting a term of sort `Real`.
-    #[clap(long)]
+    /// How to handle terms of sort `Int` passed to arithmetic operators that are expecting a term
+    /// of sort `Real`. With `strict`, this is an error. With `implicit`, the terms are accepted as
+    /// they are, as in veriT proofs. With `to-real`, they are wrapped in `to_real`, as in cvc5
+    /// proofs.
+    #[clap(arg_enum, long, default_value = "strict")]
+    int_real_subtyping: IntRealSubtyping,
+
+    // Note: the `--allow-int-real-subtyping` flag has been deprecated in favor of
+    // `--int-real-subtyping implicit`
+    #[clap(long, conflicts_with("int-real-subtyping"), hide = true)]
     allow_int_real_subtyping: bool,
 
     /// Enables strict parsing.
@@ -134,6 +225,56 @@ struct ParsingOptions {
     /// terms. In the future, this will be the default behaviour.
     #[clap(long)]
     parse_hole_args: bool,
+
+    /// How to handle bound variables that shadow a variable bound by an enclosing quantifier,
+    /// `choice` or `lambda` term. They can be allowed, allowed with a warning, rejected, or renamed
+    /// to a fresh name.
+    #[clap(arg_enum, long, default_value = "allow")]
+    shadowed_binders: ShadowedBinders,
+
+    /// The maximum number of proof commands allowed, including commands inside subproofs. If the
+    /// proof has more commands, or if elaboration produces a proof with more commands, Carcara
+    /// stops with an error.
+    #[clap(long)]
+    max_commands: Option<usize>,
+
+    /// The maximum number of distinct terms that can be created while parsing or elaborating. If
+    /// this is exceeded, Carcara stops with an error.
+    #[clap(long)]
+    max_term_nodes: Option<usize>,
+
+    /// Stores binder terms modulo alpha-equivalence, so quantifiers that only differ in the names
+    /// of their bound variables are stored only once. This can reduce memory usage, but may break
+    /// the checking of rules that refer to bound variables by name, like `bind` or `sko_ex`.
+    #[clap(long)]
+    alpha_canonical_binders: bool,
+
+    /// How to name bound variables that must be renamed to avoid capture. With `primes`, `'`s are
+    /// appended to the name, as in `x'`. With `counter`, a number from a global counter is
+    /// appended, as in `x!0`, and the names never clash with any symbol in the input.
+    #[clap(arg_enum, long, default_value = "primes")]
+    fresh_names: FreshNameScheme,
+
+    /// Checks the sorts of every premise and proof command after it is parsed, including the
+    /// arities and argument sorts of all operators. This is always done with `--strict-parsing`.
+    #[clap(long)]
+    check_sorts: bool,
+
+    /// The version of the Alethe format the proof is written in. Version 1.0, printed by older
+    /// releases of veriT, writes assignments as `(:= <symbol> <term>)`, in anchors and in step
+    /// arguments. With `auto`, the version is read from a `(set-info :alethe-version ...)` command,
+    /// and if there is none, both syntaxes are accepted, unless `--strict-parsing` is enabled.
+    #[clap(arg_enum, long, default_value = "auto")]
+    alethe_version: AletheVersion,
+
+    /// The solver-specific dialect the proof is written in. With `verit`, the old names of some
+    /// rules are replaced by their current names, and `subproof` steps without a `:discharge`
+    /// attribute discharge every assumption in their subproof. With `cvc5`, `Int` terms are
+    /// coerced with `to_real` where a `Real` term is expected (unless `--int-real-subtyping
+    /// implicit` is given), resolution pivots may be given after their polarity, and `rare_rewrite`
+    /// steps are considered holes if `--rare-rules` is not given.
+    #[clap(arg_enum, long, default_value = "alethe")]
+    dialect: Dialect,
 }
 
 impl From<ParsingOptions> for parser::Config {
@@ -141,13 +282,134 @@ impl From<ParsingOptions> for parser::Config {
         Self {
             apply_function_defs: val.apply_function_defs,
             expand_lets: val.expand_let_bindings,
-            allow_int_real_subtyping: val.allow_int_real_subtyping,
+            int_real_subtyping: if val.allow_int_real_subtyping {
+                parser::IntRealSubtyping::Implicit
+            } else {
+                val.int_real_subtyping.into()
+            },
             strict: val.strict,
             parse_hole_args: val.parse_hole_args,
+            shadowed_binders: val.shadowed_binders.into(),
+            limits: SizeLimits::new()
+                .max_commands(val.max_commands)
+                .max_term_nodes(val.max_term_nodes),
+            track_locations: false,
+            alpha_canonical_binders: val.alpha_canonical_binders,
+            fresh_name_scheme: val.fresh_names.into(),
+            check_sorts: val.check_sorts,
+            alethe_version: val.alethe_version.into(),
+            dialect: val.dialect.into(),
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum ShadowedBinders {
+    Allow,
+    Warn,
+    Reject,
+    Rename,
+}
+
+impl From<ShadowedBinders> for parser::ShadowedBinders {
+    fn from(val: ShadowedBinders) -> Self {
+        match val {
+            ShadowedBinders::Allow => Self::Allow,
+            ShadowedBinders::Warn => Self::Warn,
+            ShadowedBinders::Reject => Self::Reject,
+            ShadowedBinders::Rename => Self::Rename,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum IntRealSubtyping {
+    Strict,
+    Implicit,
+    ToReal,
+}
+
+impl From<IntRealSubtyping> for parser::IntRealSubtyping {
+    fn from(val: IntRealSubtyping) -> Self {
+        match val {
+            IntRealSubtyping::Strict => Self::Strict,
+            IntRealSubtyping::Implicit => Self::Implicit,
+            IntRealSubtyping::ToReal => Self::ToReal,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum AletheVersion {
+    #[clap(name = "1.0")]
+    V1_0,
+    #[clap(name = "1.1")]
+    V1_1,
+    Auto,
+}
+
+impl From<AletheVersion> for Option<parser::AletheVersion> {
+    fn from(val: AletheVersion) -> Self {
+        match val {
+            AletheVersion::V1_0 => Some(parser::AletheVersion::V1_0),
+            AletheVersion::V1_1 => Some(parser::AletheVersion::V1_1),
+            AletheVersion::Auto => None,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum Dialect {
+    Alethe,
+    Verit,
+    Cvc5,
+}
+
+impl From<Dialect> for parser::Dialect {
+    fn from(val: Dialect) -> Self {
+        match val {
+            Dialect::Alethe => Self::Alethe,
+            Dialect::Verit => Self::Verit,
+            Dialect::Cvc5 => Self::Cvc5,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum FreshNameScheme {
+    Primes,
+    Counter,
+}
+
+impl From<FreshNameScheme> for ast::FreshNameScheme {
+    fn from(val: FreshNameScheme) -> Self {
+        match val {
+            FreshNameScheme::Primes => Self::Primes,
+            FreshNameScheme::Counter => Self::Counter,
         }
     }
 }
 
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
+enum ProofFormat {
+    Alethe,
+    Lfsc,
+    Drat,
+    Lrat,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
+enum ExportTarget {
+    Lean,
+    Smtcoq,
+}
+
 #[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
 enum CheckGranularity {
     Normal,
@@ -168,6 +430,16 @@ struct CheckingOptions {
     #[clap(long, multiple = true, conflicts_with = "ignore-unknown-rules")]
     allowed_rules: Option<Vec<String>>,
 
+    /// Only accept proofs whose steps use one of the given rules. If any step uses a different
+    /// rule, checking fails with an error listing the offending steps.
+    #[clap(long, multiple = true)]
+    accepted_rules: Option<Vec<String>>,
+
+    /// Reject proofs with steps that use any of the given rules, like `hole` or `trust`. If any
+    /// step uses one of them, checking fails with an error listing the offending steps.
+    #[clap(long, multiple = true)]
+    denied_rules: Option<Vec<String>>,
+
     /// Enforce restrictions on the granularity of the proof.
     ///
     /// If this is "normal", the proof is checked normally, with no extra restrictions. If this
@@ -177,26 +449,107 @@ struct CheckingOptions {
     /// - the pivots for `resolution` steps must be given as arguments
     #[clap(arg_enum, long, default_value = "normal", verbatim_doc_comment)]
     check_granularity: CheckGranularity,
+
+    /// Allows `Int`/`Real` subtyping in the arguments of `forall_inst` steps. Arguments of sort
+    /// `Int` given to bindings of sort `Real` are coerced to `Real`.
+    #[clap(long)]
+    coerce_int_to_real: bool,
+
+    /// Only check the steps with the given ids. All other steps are trusted, and considered holes.
+    #[clap(long, multiple = true)]
+    only_steps: Option<Vec<String>>,
+
+    /// Only check the steps that use one of the given rules. All other steps are trusted, and
+    /// considered holes.
+    #[clap(long, multiple = true)]
+    only_rules: Option<Vec<String>>,
+
+    /// A file of RARE rule definitions, used to check `rare_rewrite` steps. If this is not given,
+    /// `rare_rewrite` is considered an unknown rule.
+    #[clap(long)]
+    rare_rules: Option<String>,
+
+    /// Reject steps whose rules use a theory that is not part of the problem's logic, like a
+    /// `la_generic` step in a `QF_UF` problem.
+    #[clap(long)]
+    enforce_logic: bool,
 }
 
-impl From<CheckingOptions> for checker::Config {
-    fn from(val: CheckingOptions) -> Self {
-        Self {
+impl TryFrom<CheckingOptions> for checker::Config {
+    type Error = CliError;
+
+    fn try_from(val: CheckingOptions) -> CliResult<Self> {
+        let rare_rules = match &val.rare_rules {
+            Some(path) => parser::parse_rare_rules(
+                io::BufReader::new(File::open(path)?),
+                parser::Config::new(),
+            )?,
+            None => checker::rare::RareRules::new(),
+        };
+        Ok(Self {
             elaborated: val.check_granularity == CheckGranularity::Elaborated,
             ignore_unknown_rules: val.ignore_unknown_rules,
             allowed_rules: val.allowed_rules.unwrap_or_default().into_iter().collect(),
-        }
+            accepted_rules: val.accepted_rules.map(|r| r.into_iter().collect()),
+            denied_rules: val.denied_rules.unwrap_or_default().into_iter().collect(),
+            allow_int_real_subtyping: val.coerce_int_to_real,
+            only_steps: val.only_steps.map(|s| s.into_iter().collect()),
+            only_rules: val.only_rules.map(|r| r.into_iter().collect()),
+            expected_conclusion: None,
+            enforce_logic: val.enforce_logic,
+            // Interpretations are Rust functions, so they can only be registered through the library
+            interpretations: checker::interpretations::Interpretations::new(),
+            rare_rules,
+        })
     }
 }
 
 #[derive(ArgEnum, Clone)]
 enum ElaborationStep {
+    Skolemization,
     Polyeq,
+    Flattening,
     LiaGeneric,
+    NlaGeneric,
     Local,
     Uncrowd,
     Reordering,
     Hole,
+    IntegerCoefficients,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum ElaborationTarget {
+    Alethe,
+    Isabelle,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum SolverKind {
+    Cvc5,
+    Verit,
+    Z3,
+}
+
+impl SolverKind {
+    /// Builds the solver with the given path. If `arguments` is `None`, the default arguments for
+    /// the solver are used.
+    fn build(self, path: &str, arguments: Option<&str>) -> Arc<dyn elaborator::ExternalSolver> {
+        match self {
+            SolverKind::Cvc5 => Arc::new(elaborator::Cvc5::with_arguments(
+                path,
+                arguments.unwrap_or(elaborator::Cvc5::DEFAULT_ARGUMENTS),
+            )),
+            SolverKind::Verit => Arc::new(elaborator::VeriT::with_arguments(
+                path,
+                arguments.unwrap_or(elaborator::VeriT::DEFAULT_ARGUMENTS),
+            )),
+            SolverKind::Z3 => Arc::new(elaborator::Z3::with_arguments(
+                path,
+                arguments.unwrap_or(elaborator::Z3::DEFAULT_ARGUMENTS),
+            )),
+        }
+    }
 }
 
 #[derive(Args, Clone)]
@@ -205,15 +558,32 @@ struct ElaborationOptions {
     #[clap(long)]
     lia_solver: Option<String>,
 
+    /// Which solver is given in `--lia-solver`. This determines the default arguments, and how the
+    /// solver output is interpreted.
+    #[clap(arg_enum, long, requires = "lia-solver", default_value = "cvc5")]
+    lia_solver_kind: SolverKind,
+
     /// The arguments to pass to the `lia_generic` solver. This should be a single string where
-    /// multiple arguments are separated by spaces.
-    #[clap(
-        long,
-        requires = "lia-solver",
-        allow_hyphen_values = true,
-        default_value = "--tlimit=10000 --lang=smt2 --proof-format-mode=alethe --proof-granularity=theory-rewrite --proof-alethe-res-pivots"
-    )]
-    lia_solver_args: String,
+    /// multiple arguments are separated by spaces. If not given, the default arguments for the
+    /// solver kind are used.
+    #[clap(long, requires = "lia-solver", allow_hyphen_values = true)]
+    lia_solver_args: Option<String>,
+
+    /// Elaborate `nla_generic` steps using the provided solver, which must support nonlinear
+    /// arithmetic.
+    #[clap(long)]
+    nla_solver: Option<String>,
+
+    /// Which solver is given in `--nla-solver`. This determines the default arguments, and how the
+    /// solver output is interpreted.
+    #[clap(arg_enum, long, requires = "nla-solver", default_value = "cvc5")]
+    nla_solver_kind: SolverKind,
+
+    /// The arguments to pass to the `nla_generic` solver. This should be a single string where
+    /// multiple arguments are separated by spaces. If not given, the default arguments for the
+    /// solver kind are used.
+    #[clap(long, requires = "nla-solver", allow_hyphen_values = true)]
+    nla_solver_args: Option<String>,
 
     /// When uncrowding resolutions steps, also reorder premises to further minimize the number of
     /// `contraction` steps added.
@@ -224,62 +594,106 @@ struct ElaborationOptions {
     #[clap(long)]
     hole_solver: Option<String>,
 
-    /// The arguments to pass to the `lia_generic` solver. This should be a single string where
-    /// multiple arguments are separated by spaces.
-    #[clap(
-        long,
-        requires = "hole-solver",
-        allow_hyphen_values = true,
-        default_value = "--disable-banner --disable-print-success --proof-prune --proof-merge --proof="
-    )]
-    hole_solver_args: String,
+    /// Which solver is given in `--hole-solver`. This determines the default arguments, and how the
+    /// solver output is interpreted.
+    #[clap(arg_enum, long, requires = "hole-solver", default_value = "verit")]
+    hole_solver_kind: SolverKind,
+
+    /// The arguments to pass to the `hole` solver. This should be a single string where multiple
+    /// arguments are separated by spaces. If not given, the default arguments for the solver kind
+    /// are used.
+    #[clap(long, requires = "hole-solver", allow_hyphen_values = true)]
+    hole_solver_args: Option<String>,
+
+    /// Kill the external solvers if they don't finish within this many seconds. This is enforced
+    /// by Carcara, independently of any time limit given in the solver arguments.
+    #[clap(long)]
+    solver_timeout: Option<u64>,
+
+    /// Limit the memory each external solver can allocate to this many megabytes. Only supported
+    /// on Unix systems.
+    #[clap(long)]
+    solver_memory_limit: Option<u64>,
+
+    /// Store the proofs produced by the external solvers in this directory, and reuse them when
+    /// the same problem is given to a solver again.
+    #[clap(long, value_name = "DIR")]
+    solver_cache: Option<String>,
 
     /// The pipeline of elaboration steps to use.
     #[clap(
         arg_enum,
         long,
         multiple = true,
-        default_values = &["polyeq", "lia-generic", "local", "uncrowd", "reordering", "hole"]
+        default_values = &["skolemization", "polyeq", "flattening", "lia-generic", "nla-generic", "local", "uncrowd", "reordering", "hole"]
     )]
     pipeline: Vec<ElaborationStep>,
+
+    /// Elaborate the proof into the shape expected by the given tool. When targeting Isabelle, the
+    /// `integer-coefficients` step is added to the end of the pipeline if it is not already
+    /// present, and the elaborated proof is checked to have no implicit reordering, explicit
+    /// resolution pivots, and integer `la_generic` coefficients.
+    #[clap(arg_enum, long, default_value = "alethe")]
+    elaborate_for: ElaborationTarget,
 }
 
 impl From<ElaborationOptions> for (elaborator::Config, Vec<elaborator::ElaborationStep>) {
     fn from(val: ElaborationOptions) -> Self {
-        let pipeline: Vec<_> = val
+        let mut pipeline: Vec<_> = val
             .pipeline
             .into_iter()
             .map(|s| match s {
+                ElaborationStep::Skolemization => elaborator::ElaborationStep::Skolemization,
                 ElaborationStep::Polyeq => elaborator::ElaborationStep::Polyeq,
+                ElaborationStep::Flattening => elaborator::ElaborationStep::Flattening,
                 ElaborationStep::LiaGeneric => elaborator::ElaborationStep::LiaGeneric,
+                ElaborationStep::NlaGeneric => elaborator::ElaborationStep::NlaGeneric,
                 ElaborationStep::Local => elaborator::ElaborationStep::Local,
                 ElaborationStep::Uncrowd => elaborator::ElaborationStep::Uncrowd,
                 ElaborationStep::Reordering => elaborator::ElaborationStep::Reordering,
                 ElaborationStep::Hole => elaborator::ElaborationStep::Hole,
+                ElaborationStep::IntegerCoefficients => {
+                    elaborator::ElaborationStep::IntegerCoefficients
+                }
             })
             .collect();
-        let lia_options = val.lia_solver.map(|solver| elaborator::LiaGenericOptions {
-            solver: solver.into(),
-            arguments: val
-                .lia_solver_args
-                .split_whitespace()
-                .map(Into::into)
-                .collect(),
+        let target = match val.elaborate_for {
+            ElaborationTarget::Alethe => elaborator::ElaborationTarget::Alethe,
+            ElaborationTarget::Isabelle => elaborator::ElaborationTarget::Isabelle,
+        };
+        if target == elaborator::ElaborationTarget::Isabelle
+            && !pipeline
+                .iter()
+                .any(|s| matches!(s, elaborator::ElaborationStep::IntegerCoefficients))
+        {
+            pipeline.push(elaborator::ElaborationStep::IntegerCoefficients);
+        }
+        let lia_solver = val.lia_solver.map(|path| {
+            val.lia_solver_kind
+                .build(&path, val.lia_solver_args.as_deref())
         });
-
-        let hole_options = val.hole_solver.map(|solver| elaborator::HoleOptions {
-            solver: solver.into(),
-            arguments: val
-                .hole_solver_args
-                .split_whitespace()
-                .map(Into::into)
-                .collect(),
+        let nla_solver = val.nla_solver.map(|path| {
+            val.nla_solver_kind
+                .build(&path, val.nla_solver_args.as_deref())
+        });
+        let hole_solver = val.hole_solver.map(|path| {
+            val.hole_solver_kind
+                .build(&path, val.hole_solver_args.as_deref())
         });
 
         let config = elaborator::Config {
-            lia_options,
+            lia_solver,
+            nla_solver,
             uncrowd_rotation: val.uncrowd_rotate,
-            hole_options,
+            hole_solver,
+            solver_limits: elaborator::SolverLimits {
+                timeout: val.solver_timeout.map(Duration::from_secs),
+                memory: val.solver_memory_limit.map(|mb| mb * 1024 * 1024),
+            },
+            solver_cache: val.solver_cache.map(elaborator::SolverCache::new),
+            limits: SizeLimits::new(),
+            trace: false,
+            target,
         };
         (config, pipeline)
     }
@@ -292,6 +706,11 @@ struct ParseCommandOptions {
 
     #[clap(flatten)]
     parsing: ParsingOptions,
+
+    /// Instead of stopping at the first syntax error in the proof, skips to the next command and
+    /// reports all errors at the end.
+    #[clap(long)]
+    recover_errors: bool,
 }
 
 #[derive(Args)]
@@ -319,6 +738,83 @@ struct CheckCommandOptions {
     })]
     num_threads: usize,
 
+    /// A file containing a custom cost model, used to balance the work between threads when
+    /// checking in parallel. Each line of the file should have the form `<rule> <weight>`. If this
+    /// is not given, a default cost model is used.
+    #[clap(long)]
+    cost_model: Option<String>,
+
+    /// Check the proof without keeping all of it in memory, freeing steps and terms that are no
+    /// longer needed. The proof file is read twice, unless it is read from stdin, in which case it
+    /// is read once and copied to a temporary file, which is read in the second pass.
+    #[clap(long, conflicts_with_all = &["num-threads", "stats"])]
+    streaming: bool,
+
+    /// The format in which the result is printed. With `json`, a JSON document containing the
+    /// status, the errors found and the time spent is printed to stdout.
+    #[clap(arg_enum, long, default_value = "text")]
+    output: OutputFormat,
+
+    /// If a step fails, print why its rule could not be applied, when the rule provides an
+    /// explanation. Explanations are always included in the JSON output.
+    #[clap(long)]
+    explain: bool,
+
+    /// The format of the proof file. LFSC proofs are translated into Alethe before checking, and
+    /// only the propositional core of the CVC4 signatures is supported. DRAT and LRAT proofs must
+    /// be in the textual format, and the problem must be a DIMACS CNF file.
+    #[clap(
+        arg_enum,
+        long,
+        default_value = "alethe",
+        conflicts_with_all = &["num-threads", "stats", "streaming", "expected-conclusion", "checkpoint", "hypotheses", "definitions"]
+    )]
+    format: ProofFormat,
+
+    /// Accept proofs that conclude the given clause, written as `(cl <term>*)`, instead of the
+    /// empty clause. A warning is printed if some steps are not needed to derive it.
+    #[clap(long, conflicts_with_all = &["num-threads", "stats", "streaming"])]
+    expected_conclusion: Option<String>,
+
+    /// Periodically save the checking progress to the given file. If the file already exists,
+    /// checking resumes from the progress saved in it. The file is removed once checking
+    /// finishes.
+    #[clap(
+        long,
+        conflicts_with_all = &["num-threads", "stats", "streaming", "expected-conclusion"]
+    )]
+    checkpoint: Option<String>,
+
+    /// The minimum time between two checkpoints, in seconds.
+    #[clap(long, requires = "checkpoint", default_value = "300")]
+    checkpoint_interval: u64,
+
+    /// Check the proof against the given hypothesis, of the form `<name>:<term>`, instead of the
+    /// assertions in a problem file. Can be given multiple times. If a problem file is given, it is
+    /// only used for its declarations, and is not inferred from the proof file.
+    #[clap(
+        long = "assume",
+        value_name = "NAME:TERM",
+        multiple_occurrences = true,
+        conflicts_with_all = &["num-threads", "stats", "streaming", "expected-conclusion", "checkpoint"]
+    )]
+    hypotheses: Vec<parser::NamedHypothesis>,
+
+    /// A file of `define-fun` commands that introduce the fresh symbols added by a
+    /// satisfiability-preserving preprocessing step, like definitional CNF. Each definition is
+    /// checked to be a valid extension of the problem, and the proof may assume it.
+    #[clap(
+        long,
+        conflicts_with_all = &["num-threads", "stats", "streaming", "expected-conclusion", "checkpoint", "hypotheses"]
+    )]
+    definitions: Option<String>,
+
+    /// Write a trace of the term constructions, equality checks and substitutions done while
+    /// checking to the given file. The trace can be verified independently with the
+    /// `carcara-trace-checker` binary.
+    #[clap(long, conflicts_with = "num-threads")]
+    term_trace: Option<String>,
+
     #[clap(flatten)]
     stats: StatsOptions,
 
@@ -340,6 +836,22 @@ struct ElaborateCommandOptions {
     #[clap(flatten)]
     elaboration: ElaborationOptions,
 
+    /// Write a JSON file mapping each step of the original proof to the steps that replaced it in
+    /// the elaborated proof, and vice versa.
+    #[clap(long)]
+    trace_map: Option<String>,
+
+    /// Write a file listing the bound variables that were renamed during elaboration, one per
+    /// line, as the original name followed by the new name.
+    #[clap(long)]
+    rename_log: Option<String>,
+
+    /// Instead of printing the elaborated proof, export it to the language of the given proof
+    /// assistant. For Lean, a Lean 4 file that depends on Mathlib is printed. For SMTCoq, a
+    /// certificate in the format of veriT proofs is printed.
+    #[clap(arg_enum, long)]
+    export: Option<ExportTarget>,
+
     #[clap(flatten)]
     stats: StatsOptions,
 }
@@ -375,12 +887,123 @@ struct BenchCommandOptions {
     #[clap(long = "dump-to-csv")]
     dump_to_csv: bool,
 
+    /// Save a summary of the results to a JSON file, which can later be passed to
+    /// `--assert-no-regression`.
+    #[clap(long)]
+    save_results: Option<String>,
+
+    /// Compare the results against a summary saved with `--save-results`, and fail if the total
+    /// time or the time of any rule regressed beyond the threshold with statistical confidence.
+    #[clap(long)]
+    assert_no_regression: Option<String>,
+
+    /// The maximum slowdown allowed by `--assert-no-regression`, as a percentage.
+    #[clap(long, default_value = "5%", value_parser = parse_percentage)]
+    threshold: f64,
+
     /// The proof files on which the benchmark will be run. If a directory is passed, the checker
     /// will recursively find all proof files in the directory. The problem files will be
-    /// inferred from the proof files.
+    /// inferred from the proof files. A manifest file (with extension `.csv` or `.json`) may also
+    /// be passed, in which case the instances it lists are used.
     files: Vec<String>,
 }
 
+#[derive(ArgEnum, Clone, Copy)]
+enum CompressionAlgorithm {
+    LowerUnits,
+    RecyclePivots,
+    RecyclePivotsWithIntersection,
+    Prune,
+}
+
+impl From<CompressionAlgorithm> for checker::compression::CompressionAlgorithm {
+    fn from(val: CompressionAlgorithm) -> Self {
+        match val {
+            CompressionAlgorithm::LowerUnits => Self::LowerUnits,
+            CompressionAlgorithm::RecyclePivots => Self::RecyclePivots,
+            CompressionAlgorithm::RecyclePivotsWithIntersection => {
+                Self::RecyclePivotsWithIntersection
+            }
+            CompressionAlgorithm::Prune => Self::Prune,
+        }
+    }
+}
+
+#[derive(Args)]
+struct CompressCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    #[clap(flatten)]
+    checking: CheckingOptions,
+
+    /// The compression algorithms to apply, in order.
+    #[clap(
+        arg_enum,
+        long,
+        alias = "compress",
+        multiple = true,
+        default_values = &["lower-units"]
+    )]
+    algorithms: Vec<CompressionAlgorithm>,
+
+    /// Check the compressed proof, and report the steps that fail instead of printing it.
+    #[clap(long)]
+    verify_compression: bool,
+
+    /// Write a JSON file mapping each step of the original proof to the steps that replaced it in
+    /// the compressed proof, and vice versa.
+    #[clap(long)]
+    trace_map: Option<String>,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum TransformationPass {
+    Skolemization,
+    Polyeq,
+    Flattening,
+    LiaGeneric,
+    NlaGeneric,
+    Local,
+    Uncrowd,
+    Reordering,
+    Hole,
+    IntegerCoefficients,
+    LowerUnits,
+    RecyclePivots,
+    RecyclePivotsWithIntersection,
+    Prune,
+}
+
+#[derive(Args)]
+struct TransformCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    #[clap(flatten)]
+    checking: CheckingOptions,
+
+    /// The transformations to apply, in order.
+    #[clap(arg_enum, long, multiple = true, required = true)]
+    passes: Vec<TransformationPass>,
+
+    /// When uncrowding resolutions steps, also reorder premises to further minimize the number of
+    /// `contraction` steps added.
+    #[clap(long)]
+    uncrowd_rotate: bool,
+
+    /// Print to stderr, for each transformation, the time it took and the number of commands
+    /// before and after it.
+    #[clap(long)]
+    report: bool,
+}
+
 #[derive(Args)]
 struct SliceCommandOptions {
     #[clap(flatten)]
@@ -389,26 +1012,253 @@ struct SliceCommandOptions {
     #[clap(flatten)]
     parsing: ParsingOptions,
 
+    /// The id of the step to slice from. The sliced proof contains only the commands needed to
+    /// derive this step.
     #[clap(long)]
     from: String,
 
+    /// The maximum distance, in number of premise links, from the sliced step to the commands
+    /// included in the slice. The conclusions of commands beyond this distance are assumed.
     #[clap(long, short = 'd')]
     max_distance: Option<usize>,
 
+    /// Write to this file a problem whose assertions are the assumptions of the sliced proof, so
+    /// that the slice can be checked on its own.
+    #[clap(long)]
+    problem_output: Option<String>,
+
     // To make slice more convenient to use, we accept (and ignore!) some options from the `check`
     // subcommand
     #[clap(short, long, hide = true)]
     ignore_unknown_rules: bool,
     #[clap(long, hide = true)]
     lia_solver: Option<String>,
+    #[clap(arg_enum, long, hide = true)]
+    lia_solver_kind: Option<SolverKind>,
     #[clap(long, allow_hyphen_values = true, hide = true)]
     lia_solver_args: Option<String>,
     #[clap(long, hide = true)]
+    nla_solver: Option<String>,
+    #[clap(arg_enum, long, hide = true)]
+    nla_solver_kind: Option<SolverKind>,
+    #[clap(long, allow_hyphen_values = true, hide = true)]
+    nla_solver_args: Option<String>,
+    #[clap(long, hide = true)]
     hole_solver: Option<String>,
+    #[clap(arg_enum, long, hide = true)]
+    hole_solver_kind: Option<SolverKind>,
     #[clap(long, allow_hyphen_values = true, hide = true)]
     hole_solver_args: Option<String>,
 }
 
+#[derive(Args)]
+struct UnsatCoreCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    /// Minimize the unsat core using the provided solver. The solver is repeatedly called on
+    /// subsets of the core, until a locally minimal core is found.
+    #[clap(long)]
+    minimize_with: Option<String>,
+
+    /// Which solver is given in `--minimize-with`. This determines the default arguments, and how
+    /// the solver output is interpreted.
+    #[clap(arg_enum, long, requires = "minimize-with", default_value = "cvc5")]
+    solver_kind: SolverKind,
+
+    /// The arguments to pass to the minimization solver. This should be a single string where
+    /// multiple arguments are separated by spaces. If not given, the default arguments for the
+    /// solver kind are used.
+    #[clap(long, requires = "minimize-with", allow_hyphen_values = true)]
+    solver_args: Option<String>,
+
+    /// Kill the solver if a single call doesn't finish within this many seconds. The subset of the
+    /// core given in that call is considered satisfiable.
+    #[clap(long, requires = "minimize-with")]
+    solver_timeout: Option<u64>,
+
+    /// Store the proofs produced by the solver in this directory, and reuse them when the same
+    /// subset of the core is tried again.
+    #[clap(long, requires = "minimize-with", value_name = "DIR")]
+    solver_cache: Option<String>,
+}
+
+#[derive(Args)]
+struct MergeCommandOptions {
+    /// The main proof file. The conclusion of the merged proof is the conclusion of this proof.
+    proof_file: String,
+
+    /// The proof file containing the additional steps, e.g. separately elaborated theory lemmas.
+    /// Steps in this proof that conclude a clause derived in the main proof will replace the
+    /// corresponding steps.
+    lemmas_file: String,
+
+    /// The original problem file. If this argument is not present, it will be inferred from the
+    /// main proof file.
+    problem_file: Option<String>,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
+enum StatsFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Args)]
+struct StatsCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    /// The format in which to print the statistics.
+    #[clap(arg_enum, long, default_value = "text")]
+    output: StatsFormat,
+}
+
+#[derive(Args)]
+struct LintCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    /// Don't report the issues found by the given lint. Can be given multiple times.
+    #[clap(short = 'A', long, value_name = "LINT", multiple_occurrences = true)]
+    allow: Vec<lint::Lint>,
+
+    /// Report the issues found by the given lint as warnings. This is the default for all lints.
+    /// Can be given multiple times.
+    #[clap(short = 'W', long, value_name = "LINT", multiple_occurrences = true)]
+    warn: Vec<lint::Lint>,
+
+    /// Report the issues found by the given lint as errors, and exit with status 1 if any is found.
+    /// Can be given multiple times.
+    #[clap(short = 'D', long, value_name = "LINT", multiple_occurrences = true)]
+    deny: Vec<lint::Lint>,
+
+    /// The maximum number of premises of a `resolution` or `th_resolution` step before the
+    /// `long-resolution` lint is triggered.
+    #[clap(long, default_value = "64")]
+    max_resolution_premises: usize,
+}
+
+#[derive(Args)]
+struct DiffCommandOptions {
+    /// The first proof file.
+    proof_a: String,
+
+    /// The second proof file.
+    proof_b: String,
+
+    /// The original problem file. If this argument is not present, it will be inferred from the
+    /// first proof file.
+    problem_file: Option<String>,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    /// Also print the steps that are the same in both proofs but have different ids (`=`).
+    #[clap(long)]
+    show_renamed: bool,
+}
+
+#[derive(Args)]
+struct TraceCommandOptions {
+    /// The trace map file.
+    #[clap(long)]
+    map: String,
+
+    /// The id of the step to look up. This can be either a step from the original proof or from
+    /// the transformed proof.
+    step: String,
+}
+
+#[derive(Args)]
+struct SpecConformanceCommandOptions {
+    /// The file containing the specification examples.
+    examples_file: String,
+}
+
+#[derive(Args)]
+struct ConformanceCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+}
+
+#[derive(Args)]
+struct ObligationsCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    #[clap(flatten)]
+    checking: CheckingOptions,
+
+    /// Write each obligation as a standalone SMT-LIB instance, in the file
+    /// `<proof file>.<step id>.smt2`. The instance asserts the step's premises and the negation of
+    /// its conclusion, so it is unsatisfiable if the obligation holds. Obligations inside subproofs are not written, since they may depend on the anchor.
+    #[clap(long)]
+    dump_smt: bool,
+}
+
+#[derive(Args)]
+struct CheckManifestCommandOptions {
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    #[clap(flatten)]
+    checking: CheckingOptions,
+
+    /// The manifest file, in CSV or JSON format. Each entry lists a problem file, a proof file and,
+    /// optionally, the expected status: `valid`, `holey`, `invalid` or `error`.
+    manifest: String,
+}
+
+#[derive(Args)]
+struct ExplainRuleCommandOptions {
+    /// The name of the rule.
+    rule: Option<String>,
+}
+
+#[derive(Args)]
+struct LspCommandOptions {
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    #[clap(flatten)]
+    checking: CheckingOptions,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum SharingMode {
+    Named,
+    DefineFun,
+}
+
+impl From<SharingMode> for ast::SharingMode {
+    fn from(val: SharingMode) -> Self {
+        match val {
+            SharingMode::Named => Self::Named,
+            SharingMode::DefineFun => Self::DefineFun,
+        }
+    }
+}
+
 #[derive(ArgEnum, Clone)]
 enum LogLevel {
     Off,
@@ -433,8 +1283,16 @@ fn main() {
     let colors_enabled = !cli.no_color && std::io::stderr().is_terminal();
 
     ast::USE_SHARING_IN_TERM_DISPLAY.store(!cli.no_print_with_sharing, atomic::Ordering::Relaxed);
+    let print_options = ast::PrintOptions::new()
+        .sharing(if cli.no_print_with_sharing {
+            ast::SharingMode::None
+        } else {
+            cli.sharing.into()
+        })
+        .sharing_threshold(cli.sharing_threshold);
 
     logger::init(cli.log_level.into(), colors_enabled);
+    logger::init_tracing(colors_enabled);
 
     if let Command::Check(CheckCommandOptions { checking, .. })
     | Command::Elaborate(ElaborateCommandOptions { checking, .. })
@@ -450,40 +1308,151 @@ fn main() {
 
     let result = match cli.command {
         Command::Parse(options) => parse_command(options).and_then(|(pb, pf, mut pool)| {
-            ast::print_proof(&mut pool, &pb.prelude, &pf, !cli.no_print_with_sharing)?;
+            ast::print_proof(&mut pool, &pb.prelude, &pf, &print_options)?;
             Ok(())
         }),
+        Command::Check(options) if options.output == OutputFormat::Json => {
+            let start = Instant::now();
+            let mut stages = Vec::new();
+            let result = check_command(options, &mut stages);
+            let timing = report::Timing::new(start.elapsed(), &stages);
+            let (status, errors) = match &result {
+                Ok(false) => (report::CheckStatus::Valid, Vec::new()),
+                Ok(true) => (report::CheckStatus::Holey, Vec::new()),
+                Err(e) => (
+                    report::CheckStatus::Invalid,
+                    vec![report::ErrorReport::new(e)],
+                ),
+            };
+            let report = report::CheckReport { status, errors, timing };
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            if result.is_err() {
+                std::process::exit(1);
+            }
+            return;
+        }
         Command::Check(options) => {
-            match check_command(options) {
+            let proof_file = options.input.proof_file.clone();
+            let explain = options.explain;
+            match check_command(options, &mut Vec::new()) {
                 Ok(false) => println!("valid"),
                 Ok(true) => println!("holey"),
                 Err(e) => {
                     log::error!("{}", e);
+                    if let CliError::CarcaraError(carcara::Error::Checker {
+                        inner,
+                        step,
+                        location,
+                        ..
+                    }) = &e
+                    {
+                        let message = inner.to_string();
+                        let location = location.as_deref();
+                        print_step_snippet(&proof_file, step, location, &message, colors_enabled);
+                        if let Some(explanation) = inner.explanation().filter(|_| explain) {
+                            eprint!("explanation:\n{}", explanation);
+                        }
+                    }
                     println!("invalid");
                     std::process::exit(1);
                 }
             }
             return;
         }
+        Command::Elaborate(options) if options.export.is_some() => {
+            let target = options.export.unwrap();
+            elaborate_command(options).and_then(|(_, pb, pf, mut pool, _, _)| {
+                let exported = match target {
+                    ExportTarget::Lean => export::lean::export_proof(&pb, &pf, &pool),
+                    ExportTarget::Smtcoq => export::smtcoq::export_proof(&pb, &pf, &mut pool),
+                };
+                print!("{}", exported.map_err(carcara::Error::from)?);
+                Ok(())
+            })
+        }
         Command::Elaborate(options) => {
-            elaborate_command(options).and_then(|(res, pb, pf, mut pool)| {
+            elaborate_command(options).and_then(|(res, pb, pf, mut pool, _, _)| {
                 if res {
                     println!("holey");
                 } else {
                     println!("valid");
                 }
-                ast::print_proof(&mut pool, &pb.prelude, &pf, !cli.no_print_with_sharing)?;
+                ast::print_proof(&mut pool, &pb.prelude, &pf, &print_options)?;
                 Ok(())
             })
         }
-        Command::Bench(options) => bench_command(options),
+        Command::Bench(options) => match bench_command(options) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => Err(e),
+        },
         Command::Slice(options) => slice_command(options).and_then(|(pb, pf, mut pool)| {
-            ast::print_proof(&mut pool, &pb.prelude, &pf, !cli.no_print_with_sharing)?;
+            ast::print_proof(&mut pool, &pb.prelude, &pf, &print_options)?;
+            Ok(())
+        }),
+        Command::Compress(options) => compress_command(options).and_then(|(pb, pf, mut pool)| {
+            ast::print_proof(&mut pool, &pb.prelude, &pf, &print_options)?;
+            Ok(())
+        }),
+        Command::Transform(options) => transform_command(options).and_then(|(pb, pf, mut pool)| {
+            ast::print_proof(&mut pool, &pb.prelude, &pf, &print_options)?;
             Ok(())
         }),
         Command::GenerateLiaProblems(options) => {
             generate_lia_problems_command(options, !cli.no_print_with_sharing)
         }
+        Command::Merge(options) => merge_command(options).and_then(|(pb, pf, mut pool)| {
+            ast::print_proof(&mut pool, &pb.prelude, &pf, &print_options)?;
+            Ok(())
+        }),
+        Command::Stats(options) => stats_command(options),
+        Command::Lint(options) => match lint_command(options) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => Err(e),
+        },
+        Command::Diff(options) => match diff_command(options) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => Err(e),
+        },
+        Command::Trace(options) => trace_command(options),
+        Command::SpecConformance(options) => match spec_conformance_command(options) {
+            Ok(conformant) => {
+                if !conformant {
+                    std::process::exit(1);
+                }
+                return;
+            }
+            Err(e) => Err(e),
+        },
+        Command::Conformance(options) => match conformance_command(options) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => Err(e),
+        },
+        Command::Obligations(options) => {
+            match obligations_command(options, !cli.no_print_with_sharing) {
+                Ok(true) => return,
+                Ok(false) => std::process::exit(1),
+                Err(e) => Err(e),
+            }
+        }
+        Command::CheckManifest(options) => match check_manifest_command(options) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => Err(e),
+        },
+        Command::ExplainRule(options) => explain_rule_command(options),
+        Command::Lsp(options) => options
+            .checking
+            .into_config(options.parsing.dialect)
+            .and_then(|checking| lsp::run(options.parsing.into(), checking)),
+        Command::UnsatCore(options) => unsat_core_command(options).map(|core| {
+            for term in core {
+                println!("{}", term);
+            }
+        }),
     };
     if let Err(e) = result {
         log::error!("{}", e);
@@ -491,6 +1460,28 @@ fn main() {
     }
 }
 
+/// Prints the source of a failing step to stderr. Nothing is printed if the proof was read from
+/// stdin, or if the step can't be found in the proof file.
+fn print_step_snippet(
+    proof_file: &str,
+    step: &str,
+    location: Option<&parser::CommandLocation>,
+    message: &str,
+    colors_enabled: bool,
+) {
+    if proof_file == "-" {
+        return;
+    }
+    let Ok(source) = std::fs::read_to_string(proof_file) else {
+        return;
+    };
+    if let Some(snippet) =
+        snippet::render_step_snippet(&source, proof_file, step, location, message, colors_enabled)
+    {
+        eprintln!("{}", snippet);
+    }
+}
+
 fn get_instance(options: &Input) -> CliResult<(Box<dyn BufRead>, Box<dyn BufRead>)> {
     fn reader_from_path<P: AsRef<Path>>(path: P) -> CliResult<Box<dyn BufRead>> {
         Ok(Box::new(io::BufReader::new(File::open(path)?)))
@@ -512,19 +1503,124 @@ fn parse_command(
     options: ParseCommandOptions,
 ) -> CliResult<(ast::Problem, ast::Proof, ast::PrimitivePool)> {
     let (problem, proof) = get_instance(&options.input)?;
+    if options.recover_errors {
+        let (problem, proof, pool, diagnostics) =
+            parser::parse_instance_with_recovery(problem, proof, options.parsing.into())?;
+        for d in &diagnostics {
+            log::error!("parser error: {}", d);
+        }
+        return if diagnostics.is_empty() {
+            Ok((problem, proof, pool))
+        } else {
+            Err(CliError::ParserErrors(diagnostics.len()))
+        };
+    }
     let result = parser::parse_instance(problem, proof, options.parsing.into())
         .map_err(carcara::Error::from)?;
     Ok(result)
 }
 
-fn check_command(options: CheckCommandOptions) -> CliResult<bool> {
+fn print_stage_report(report: &StageReport) {
+    eprintln!("{}", report);
+}
+
+/// Checks a proof. When checking with a single thread, the time at which each stage of the pipeline
+/// finished, relative to the start, is added to `stages`.
+impl CheckingOptions {
+    /// Builds the checker configuration for proofs in the given dialect.
+    fn into_config(self, dialect: Dialect) -> CliResult<checker::Config> {
+        let config: checker::Config = self.try_into()?;
+        Ok(config.dialect(dialect.into()))
+    }
+}
+
+fn check_command(
+    mut options: CheckCommandOptions,
+    stages: &mut Vec<(Stage, Duration)>,
+) -> CliResult<bool> {
+    if let Some(path) = options.term_trace.take() {
+        let writer = io::BufWriter::new(File::create(path)?);
+        return carcara::term_trace::record(writer, || check_command(options, stages))?;
+    }
+    let start = Instant::now();
+    let parser_config = parser::Config {
+        track_locations: true,
+        ..options.parsing.into()
+    };
+    let checker_config = options.checking.into_config(options.parsing.dialect)?;
+    if !options.hypotheses.is_empty() {
+        return check_hypotheses_command(
+            &options.input,
+            &options.hypotheses,
+            parser_config,
+            checker_config,
+        );
+    }
     let (problem, proof) = get_instance(&options.input)?;
-    let parser_config = options.parsing.into();
-    let checker_config = options.checking.into();
+    if let Some(path) = &options.definitions {
+        let definitions: Box<dyn BufRead> = Box::new(io::BufReader::new(File::open(path)?));
+        return check_with_definitions(problem, definitions, proof, parser_config, checker_config)
+            .map_err(Into::into);
+    }
+    match options.format {
+        ProofFormat::Alethe => (),
+        ProofFormat::Lfsc => {
+            return check_lfsc(problem, proof, parser_config, checker_config).map_err(Into::into)
+        }
+        ProofFormat::Drat => {
+            return check_drat(problem, proof, drat::ProofFormat::Drat).map_err(Into::into)
+        }
+        ProofFormat::Lrat => {
+            return check_drat(problem, proof, drat::ProofFormat::Lrat).map_err(Into::into)
+        }
+    }
     let collect_stats = options.stats.stats;
-    if options.num_threads == 1 {
-        check(problem, proof, parser_config, checker_config, collect_stats)
+    if options.stats.stage_sizes && (options.streaming || options.num_threads != 1) {
+        log::warn!("`--stage-sizes` is only supported when checking with a single thread");
+    }
+    if let Some(expected) = &options.expected_conclusion {
+        let expected: Box<dyn BufRead> = Box::new(io::Cursor::new(expected.clone().into_bytes()));
+        check_with_expected_conclusion(problem, proof, expected, parser_config, checker_config)
+    } else if let Some(path) = &options.checkpoint {
+        let interval = Duration::from_secs(options.checkpoint_interval);
+        check_resumable(
+            problem,
+            proof,
+            parser_config,
+            checker_config,
+            Path::new(path),
+            interval,
+        )
+    } else if options.streaming && options.input.proof_file == "-" {
+        check_streaming_single_pass(problem, proof, parser_config, checker_config)
+    } else if options.streaming {
+        let open_proof = || -> io::Result<Box<dyn BufRead>> {
+            Ok(Box::new(io::BufReader::new(File::open(
+                &options.input.proof_file,
+            )?)))
+        };
+        check_streaming(problem, open_proof, parser_config, checker_config)
+    } else if options.num_threads == 1 {
+        let stage_sizes = options.stats.stage_sizes;
+        let mut listener = |report: &StageReport| {
+            stages.push((report.stage, start.elapsed()));
+            if stage_sizes {
+                print_stage_report(report);
+            }
+        };
+        check_with_listener(
+            problem,
+            proof,
+            parser_config,
+            checker_config,
+            collect_stats,
+            Some(&mut listener),
+        )
     } else {
+        let cost_model = match &options.cost_model {
+            Some(path) => checker::CostModel::from_reader(File::open(path)?)?,
+            None => checker::CostModel::new(),
+        };
         check_parallel(
             problem,
             proof,
@@ -533,34 +1629,227 @@ fn check_command(options: CheckCommandOptions) -> CliResult<bool> {
             collect_stats,
             options.num_threads,
             options.stack.stack_size,
+            &cost_model,
         )
     }
     .map_err(Into::into)
 }
 
-fn elaborate_command(
-    options: ElaborateCommandOptions,
-) -> CliResult<(bool, ast::Problem, ast::Proof, ast::PrimitivePool)> {
+/// Checks a proof against inline hypotheses. If a problem file is given, its declarations are used.
+fn check_hypotheses_command(
+    input: &Input,
+    hypotheses: &[parser::NamedHypothesis],
+    parser_config: parser::Config,
+    checker_config: checker::Config,
+) -> CliResult<bool> {
+    let (declarations, proof): (Box<dyn BufRead>, Box<dyn BufRead>) = match input.problem_file {
+        Some(_) => get_instance(input)?,
+        None if input.proof_file == "-" => (Box::new(io::empty()), Box::new(io::stdin().lock())),
+        None => (
+            Box::new(io::empty()),
+            Box::new(io::BufReader::new(File::open(&input.proof_file)?)),
+        ),
+    };
+    check_with_hypotheses(
+        declarations,
+        hypotheses,
+        proof,
+        parser_config,
+        checker_config,
+    )
+    .map_err(Into::into)
+}
+
+fn elaborate_command(options: ElaborateCommandOptions) -> CliResult<ElaborationResult> {
     let (problem, proof) = get_instance(&options.input)?;
 
-    let (elab_config, pipeline) = options.elaboration.into();
-    check_and_elaborate(
+    let parser_config: parser::Config = options.parsing.into();
+    let (mut elab_config, pipeline): (elaborator::Config, _) = options.elaboration.into();
+    elab_config.limits = parser_config.limits;
+    elab_config.trace = options.trace_map.is_some();
+    let mut report = print_stage_report;
+    let listener = options
+        .stats
+        .stage_sizes
+        .then_some(&mut report as &mut dyn PipelineListener);
+    let result = check_and_elaborate_with_listener(
         problem,
         proof,
-        options.parsing.into(),
-        options.checking.into(),
+        parser_config,
+        options.checking.into_config(options.parsing.dialect)?,
         elab_config,
         pipeline,
         options.stats.stats,
+        listener,
+    )
+    .map_err(CliError::CarcaraError)?;
+
+    if let (Some(path), Some(trace)) = (&options.trace_map, &result.4) {
+        trace.write_json(&mut io::BufWriter::new(File::create(path)?))?;
+    }
+    if let Some(path) = &options.rename_log {
+        let mut file = io::BufWriter::new(File::create(path)?);
+        for (original, new) in &result.5 {
+            writeln!(file, "{} {}", original, new)?;
+        }
+    }
+    Ok(result)
+}
+
+fn spec_conformance_command(options: SpecConformanceCommandOptions) -> CliResult<bool> {
+    let input = std::fs::read_to_string(&options.examples_file)?;
+    let examples = checker::conformance::parse_examples(&input)?;
+    let report = checker::conformance::run_examples(examples);
+    print!("{}", report);
+    Ok(report.is_conformant())
+}
+
+/// Returns `true` if the proof does not rely on any leniency of the checker.
+fn conformance_command(options: ConformanceCommandOptions) -> CliResult<bool> {
+    let (problem, proof) = get_instance(&options.input)?;
+    let (is_holey, report) = check_leniencies(
+        problem,
+        proof,
+        options.parsing.into(),
+        checker::Config::new(),
+    )?;
+    println!("{}", if is_holey { "holey" } else { "valid" });
+    print!("{}", report);
+    Ok(report.is_strict())
+}
+
+/// Returns `true` if the proof leaves no proof obligations.
+fn obligations_command(options: ObligationsCommandOptions, use_sharing: bool) -> CliResult<bool> {
+    let (problem, proof) = get_instance(&options.input)?;
+    let (problem, proof, mut pool) = parser::parse_instance(problem, proof, options.parsing.into())
+        .map_err(carcara::Error::from)?;
+    let (is_holey, report) = checker::ProofChecker::new(
+        &mut pool,
+        options.checking.into_config(options.parsing.dialect)?,
     )
-    .map_err(CliError::CarcaraError)
+    .check_obligations(&problem, &proof)?;
+    println!("{}", if is_holey { "holey" } else { "valid" });
+    print!("{}", report);
+
+    if options.dump_smt {
+        for obligation in &report.obligations {
+            let Some(instance) = obligation.smt_instance(&mut pool, &problem.prelude, use_sharing)
+            else {
+                log::warn!("not writing obligation '{}' inside subproof", obligation.id);
+                continue;
+            };
+            let file_name = format!("{}.{}.smt2", options.input.proof_file, obligation.id);
+            File::create(file_name)?.write_all(instance.as_bytes())?;
+        }
+    }
+    Ok(report.is_empty())
+}
+
+/// Returns `true` if every instance with an expected status matches it.
+fn check_manifest_command(options: CheckManifestCommandOptions) -> CliResult<bool> {
+    let entries = manifest::read_manifest(Path::new(&options.manifest))?;
+    let parser_config: parser::Config = options.parsing.into();
+    let checker_config = options.checking.into_config(options.parsing.dialect)?;
+
+    let mut num_mismatches = 0;
+    for entry in &entries {
+        let open = |path| -> io::Result<_> { Ok(io::BufReader::new(File::open(path)?)) };
+        let result = match (open(&entry.problem), open(&entry.proof)) {
+            (Ok(problem), Ok(proof)) => {
+                check(problem, proof, parser_config, checker_config.clone(), false)
+            }
+            (Err(e), _) | (_, Err(e)) => Err(e.into()),
+        };
+        let status = manifest::Status::from_result(&result);
+        let proof = entry.proof.display();
+        match entry.expected {
+            Some(expected) if expected != status => {
+                num_mismatches += 1;
+                match result {
+                    Err(e) => println!("{}: expected {}, got {} ({})", proof, expected, status, e),
+                    Ok(_) => println!("{}: expected {}, got {}", proof, expected, status),
+                }
+            }
+            Some(_) => log::info!("{}: {}", proof, status),
+            None => println!("{}: {}", proof, status),
+        }
+    }
+    let num_expected = entries.iter().filter(|e| e.expected.is_some()).count();
+    println!(
+        "{}/{} instances match their expected status",
+        num_expected - num_mismatches,
+        num_expected
+    );
+    Ok(num_mismatches == 0)
 }
 
-fn bench_command(options: BenchCommandOptions) -> CliResult<()> {
+fn explain_rule_command(options: ExplainRuleCommandOptions) -> CliResult<()> {
+    use checker::metadata;
+
+    match options.rule {
+        Some(name) => {
+            let rule = metadata::rule_metadata(&name).ok_or(CliError::UnknownRule(name))?;
+            print!("{}", rule);
+        }
+        None => {
+            let mut rules = metadata::all_rules().iter().peekable();
+            while let Some(rule) = rules.next() {
+                println!("{}:", rule.section);
+                println!("  {}", rule.name);
+                while let Some(next) = rules.next_if(|r| r.section == rule.section) {
+                    println!("  {}", next.name);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn trace_command(options: TraceCommandOptions) -> CliResult<()> {
+    let map = trace_map::TraceMap::read_json(&mut File::open(&options.map)?)?;
+    let id = options.step.as_str();
+
+    let successors = map.successors(id);
+    let predecessors = map.predecessors(id);
+    if successors.is_none() && predecessors.is_empty() {
+        return Err(CliError::UnknownTraceStep(options.step));
+    }
+    match successors {
+        Some(new) if new.is_empty() => println!("{} was removed", id),
+        Some(new) if new.len() == 1 && new.contains(id) => println!("{} is still in the proof", id),
+        Some(new) => {
+            let new: Vec<_> = new.iter().map(String::as_str).collect();
+            println!("{} was replaced by: {}", id, new.join(", "));
+        }
+        None => (),
+    }
+    if !predecessors.is_empty() && predecessors != [id] {
+        println!("{} comes from: {}", id, predecessors.join(", "));
+    }
+    Ok(())
+}
+
+/// Parses a percentage such as `5%` or `2.5`, returning it as a fraction.
+fn parse_percentage(s: &str) -> Result<f64, String> {
+    let value: f64 = s
+        .strip_suffix('%')
+        .unwrap_or(s)
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid percentage: '{}'", s))?;
+    if value < 0.0 {
+        return Err(format!("percentage can't be negative: '{}'", s));
+    }
+    Ok(value / 100.0)
+}
+
+/// Runs the benchmark and prints the results. Returns `false` if `--assert-no-regression` was
+/// passed and the results regressed.
+fn bench_command(options: BenchCommandOptions) -> CliResult<bool> {
     let instances = get_instances_from_paths(options.files.iter().map(|s| s.as_str()))?;
     if instances.is_empty() {
         log::warn!("no files passed");
-        return Ok(());
+        return Ok(true);
     }
 
     log::info!(
@@ -575,12 +1864,12 @@ fn bench_command(options: BenchCommandOptions) -> CliResult<()> {
             options.num_runs,
             options.num_jobs,
             options.parsing.into(),
-            options.checking.into(),
+            options.checking.into_config(options.parsing.dialect)?,
             options.elaborate.then(|| options.elaboration.into()),
             &mut File::create("runs.csv")?,
             &mut File::create("steps.csv")?,
         )?;
-        return Ok(());
+        return Ok(true);
     }
 
     let results: OnlineBenchmarkResults = benchmarking::run_benchmark(
@@ -588,12 +1877,12 @@ fn bench_command(options: BenchCommandOptions) -> CliResult<()> {
         options.num_runs,
         options.num_jobs,
         options.parsing.into(),
-        options.checking.into(),
+        options.checking.into_config(options.parsing.dialect)?,
         options.elaborate.then(|| options.elaboration.into()),
     );
     if results.is_empty() {
         println!("no benchmark data collected");
-        return Ok(());
+        return Ok(true);
     }
 
     if results.had_error {
@@ -604,24 +1893,145 @@ fn bench_command(options: BenchCommandOptions) -> CliResult<()> {
         println!("valid");
     }
     results.print(options.sort_by_total);
-    Ok(())
+
+    let summary = BenchmarkSummary::from(&results);
+    if let Some(path) = &options.save_results {
+        summary.write_json(&mut File::create(path)?)?;
+    }
+    if let Some(path) = &options.assert_no_regression {
+        let old = BenchmarkSummary::read_json(&mut File::open(path)?)?;
+        let regressions = summary.find_regressions(&old, options.threshold);
+        if !regressions.is_empty() {
+            println!("regressions (threshold {}%):", options.threshold * 100.0);
+            for r in &regressions {
+                println!("    {}", r);
+            }
+            return Ok(false);
+        }
+        println!("no regressions");
+    }
+    Ok(true)
 }
 
 fn slice_command(
     options: SliceCommandOptions,
 ) -> CliResult<(ast::Problem, ast::Proof, ast::PrimitivePool)> {
     let (problem, proof) = get_instance(&options.input)?;
-    let (problem, proof, pool) = parser::parse_instance(problem, proof, options.parsing.into())
+    let (problem, proof, mut pool) = parser::parse_instance(problem, proof, options.parsing.into())
         .map_err(carcara::Error::from)?;
 
-    let node = ast::ProofNode::from_commands_with_root_id(proof.commands, &options.from)
+    let sliced = slicer::slice(&mut pool, &proof, &options.from, options.max_distance)
         .ok_or_else(|| CliError::InvalidSliceId(options.from))?;
-    let sliced = ast::Proof {
-        commands: node.into_commands(),
-        ..proof
+
+    if let Some(path) = &options.problem_output {
+        let mut file = io::BufWriter::new(File::create(path)?);
+        write!(file, "{}", problem.prelude)?;
+        ast::write_smt_assertions(
+            &mut pool,
+            &problem.prelude,
+            &mut file,
+            &sliced.assumptions,
+            false,
+        )?;
+        writeln!(file, "(check-sat)")?;
+    }
+
+    Ok((problem, sliced.proof, pool))
+}
+
+fn compress_command(
+    options: CompressCommandOptions,
+) -> CliResult<(ast::Problem, ast::Proof, ast::PrimitivePool)> {
+    let (problem, proof) = get_instance(&options.input)?;
+    let (problem, proof, mut pool) = parser::parse_instance(problem, proof, options.parsing.into())
+        .map_err(carcara::Error::from)?;
+
+    let config = checker::compression::Config::new()
+        .algorithms(options.algorithms.into_iter().map(Into::into).collect())
+        .verify(options.verify_compression)
+        .trace(options.trace_map.is_some());
+    let checker_config = options.checking.into_config(options.parsing.dialect)?;
+    let (compressed, trace) =
+        checker::compression::compress(&mut pool, &problem, &proof, &config, &checker_config)?;
+    if let (Some(path), Some(trace)) = (&options.trace_map, &trace) {
+        trace.write_json(&mut io::BufWriter::new(File::create(path)?))?;
+    }
+    Ok((problem, compressed, pool))
+}
+
+fn transform_command(
+    options: TransformCommandOptions,
+) -> CliResult<(ast::Problem, ast::Proof, ast::PrimitivePool)> {
+    use checker::compression::CompressionAlgorithm as Compression;
+    use elaborator::ElaborationStep as Elaboration;
+    use transformation::{ElaborationPass, Pipeline};
+
+    let (problem, proof) = get_instance(&options.input)?;
+    let (problem, proof, mut pool) = parser::parse_instance(problem, proof, options.parsing.into())
+        .map_err(carcara::Error::from)?;
+    checker::ProofChecker::new(
+        &mut pool,
+        options.checking.into_config(options.parsing.dialect)?,
+    )
+    .check(&problem, &proof)?;
+
+    let config = elaborator::Config {
+        lia_solver: None,
+        nla_solver: None,
+        uncrowd_rotation: options.uncrowd_rotate,
+        hole_solver: None,
+        solver_limits: Default::default(),
+        solver_cache: None,
+        limits: SizeLimits::new(),
+        trace: false,
+        target: elaborator::ElaborationTarget::Alethe,
     };
+    let mut pipeline = Pipeline::new();
+    for pass in options.passes {
+        let elaboration_step = match pass {
+            TransformationPass::Skolemization => Elaboration::Skolemization,
+            TransformationPass::Polyeq => Elaboration::Polyeq,
+            TransformationPass::Flattening => Elaboration::Flattening,
+            TransformationPass::LiaGeneric => Elaboration::LiaGeneric,
+            TransformationPass::NlaGeneric => Elaboration::NlaGeneric,
+            TransformationPass::Local => Elaboration::Local,
+            TransformationPass::Uncrowd => Elaboration::Uncrowd,
+            TransformationPass::Reordering => Elaboration::Reordering,
+            TransformationPass::Hole => Elaboration::Hole,
+            TransformationPass::IntegerCoefficients => Elaboration::IntegerCoefficients,
+            TransformationPass::LowerUnits => {
+                pipeline.push(Box::new(Compression::LowerUnits));
+                continue;
+            }
+            TransformationPass::RecyclePivots => {
+                pipeline.push(Box::new(Compression::RecyclePivots));
+                continue;
+            }
+            TransformationPass::RecyclePivotsWithIntersection => {
+                pipeline.push(Box::new(Compression::RecyclePivotsWithIntersection));
+                continue;
+            }
+            TransformationPass::Prune => {
+                pipeline.push(Box::new(Compression::Prune));
+                continue;
+            }
+        };
+        pipeline.push(Box::new(ElaborationPass {
+            step: elaboration_step,
+            config: config.clone(),
+        }));
+    }
 
-    Ok((problem, sliced, pool))
+    let (transformed, reports) = pipeline.run(&mut pool, &problem, &proof)?;
+    if options.report {
+        for r in reports {
+            eprintln!(
+                "{}: {:?}, {} -> {} commands",
+                r.name, r.duration, r.commands_before, r.commands_after
+            );
+        }
+    }
+    Ok((problem, transformed, pool))
 }
 
 fn generate_lia_problems_command(options: ParseCommandOptions, use_sharing: bool) -> CliResult<()> {
@@ -640,3 +2050,138 @@ fn generate_lia_problems_command(options: ParseCommandOptions, use_sharing: bool
 
     Ok(())
 }
+
+fn unsat_core_command(options: UnsatCoreCommandOptions) -> CliResult<Vec<ast::Rc<ast::Term>>> {
+    let (problem, proof) = get_instance(&options.input)?;
+    let (problem, proof, mut pool) = parser::parse_instance(problem, proof, options.parsing.into())
+        .map_err(carcara::Error::from)?;
+
+    match options.minimize_with {
+        Some(solver) => {
+            let minimization = unsat_core::MinimizationOptions {
+                solver: options
+                    .solver_kind
+                    .build(&solver, options.solver_args.as_deref()),
+                limits: elaborator::SolverLimits {
+                    timeout: options.solver_timeout.map(Duration::from_secs),
+                    memory: None,
+                },
+                cache: options.solver_cache.map(elaborator::SolverCache::new),
+            };
+            let core = unsat_core::minimize_unsat_core(&mut pool, &problem, &proof, &minimization)?;
+            Ok(core)
+        }
+        None => Ok(unsat_core::get_unsat_core(&problem, &proof)),
+    }
+}
+
+fn merge_command(
+    options: MergeCommandOptions,
+) -> CliResult<(ast::Problem, ast::Proof, ast::PrimitivePool)> {
+    // The problem file is parsed twice, so we have to infer its path beforehand
+    let problem_file = match options.problem_file {
+        Some(p) => p,
+        None => infer_problem_path(&options.proof_file)?
+            .to_string_lossy()
+            .into_owned(),
+    };
+    let main_input = Input {
+        proof_file: options.proof_file,
+        problem_file: Some(problem_file.clone()),
+    };
+    let lemmas_input = Input {
+        proof_file: options.lemmas_file,
+        problem_file: Some(problem_file),
+    };
+
+    let (problem, main) = get_instance(&main_input)?;
+    let (problem, main, mut pool) = parser::parse_instance(problem, main, options.parsing.into())
+        .map_err(carcara::Error::from)?;
+
+    // The lemmas proof is parsed using the same pool, so terms are shared between the two proofs
+    let (problem_reader, lemmas) = get_instance(&lemmas_input)?;
+    let (_, lemmas) =
+        parser::parse_instance_with_pool(problem_reader, lemmas, options.parsing.into(), &mut pool)
+            .map_err(carcara::Error::from)?;
+
+    let merged = ast::merge_proofs(&main, &lemmas);
+    Ok((problem, merged, pool))
+}
+
+fn stats_command(options: StatsCommandOptions) -> CliResult<()> {
+    let (problem, proof) = get_instance(&options.input)?;
+    let (_, proof, pool) = parser::parse_instance(problem, proof, options.parsing.into())
+        .map_err(carcara::Error::from)?;
+    let stats = statistics::collect_statistics(&pool, &proof);
+    match options.output {
+        StatsFormat::Text => print!("{}", stats),
+        StatsFormat::Csv => stats.write_csv(&mut io::stdout())?,
+        StatsFormat::Json => {
+            let json =
+                serde_json::to_string_pretty(&stats).expect("failed to serialize statistics");
+            println!("{}", json);
+        }
+    }
+    Ok(())
+}
+
+/// Lints a proof and prints the issues found. Returns `false` if a denied lint was triggered.
+fn lint_command(options: LintCommandOptions) -> CliResult<bool> {
+    let (problem, proof) = get_instance(&options.input)?;
+    let (_, proof, _) = parser::parse_instance(problem, proof, options.parsing.into())
+        .map_err(carcara::Error::from)?;
+
+    let levels = [
+        (&options.allow, lint::LintLevel::Allow),
+        (&options.warn, lint::LintLevel::Warn),
+        (&options.deny, lint::LintLevel::Deny),
+    ];
+    let mut config =
+        lint::LintConfig::new().max_resolution_premises(options.max_resolution_premises);
+    for (lints, level) in levels {
+        for &l in lints {
+            config = config.lint_level(l, level);
+        }
+    }
+
+    let diagnostics = lint::lint_proof(&proof, &config);
+    for d in &diagnostics {
+        println!("{}", d);
+    }
+    Ok(!diagnostics.iter().any(|d| d.level == lint::LintLevel::Deny))
+}
+
+fn diff_command(options: DiffCommandOptions) -> CliResult<bool> {
+    // The problem file is parsed twice, so we have to infer its path beforehand
+    let problem_file = match options.problem_file {
+        Some(p) => p,
+        None => infer_problem_path(&options.proof_a)?
+            .to_string_lossy()
+            .into_owned(),
+    };
+    let input_a = Input {
+        proof_file: options.proof_a,
+        problem_file: Some(problem_file.clone()),
+    };
+    let input_b = Input {
+        proof_file: options.proof_b,
+        problem_file: Some(problem_file),
+    };
+
+    let (problem, proof_a) = get_instance(&input_a)?;
+    let (_, proof_a, mut pool) = parser::parse_instance(problem, proof_a, options.parsing.into())
+        .map_err(carcara::Error::from)?;
+    let (problem, proof_b) = get_instance(&input_b)?;
+    let (_, proof_b) =
+        parser::parse_instance_with_pool(problem, proof_b, options.parsing.into(), &mut pool)
+            .map_err(carcara::Error::from)?;
+
+    let diff = ast::diff_proofs(&proof_a, &proof_b);
+    if options.show_renamed {
+        for (a, b) in &diff.renamed {
+            println!("= {} -> {}", a, b);
+        }
+    }
+    print!("{}", diff);
+    Ok(diff.is_empty())
+}
@@ -0,0 +1,257 @@
+//! Reading manifest files, that list problem and proof pairs, optionally with the expected result
+//! of checking each proof.
+//!
+//! A manifest can be either a CSV file or a JSON file, depending on its extension. In a CSV
+//! manifest, each line has the form `problem,proof[,expected]`. An optional header line starting
+//! with `problem` is skipped, as are empty lines and lines starting with `#`. Fields can't contain
+//! commas, since quoting is not supported. A JSON manifest is an array of objects with the fields
+//! `"problem"`, `"proof"` and, optionally, `"expected"`.
+//!
+//! In both formats, relative paths are resolved from the directory containing the manifest, and
+//! the problem path may be empty, in which case it is inferred from the proof path. The expected
+//! status must be one of `valid`, `holey`, `invalid` or `error`.
+
+use crate::{error::CliError, path_args::infer_problem_path};
+use serde::{de::Error as _, Deserialize, Deserializer};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// The result of checking a proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Valid,
+    Holey,
+    Invalid,
+
+    /// The instance could not be read or parsed.
+    Error,
+}
+
+impl Status {
+    pub fn from_result(result: &Result<bool, carcara::Error>) -> Self {
+        match result {
+            Ok(false) => Status::Valid,
+            Ok(true) => Status::Holey,
+            Err(carcara::Error::Io(_) | carcara::Error::Parser(..)) => Status::Error,
+            Err(_) => Status::Invalid,
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "valid" => Ok(Status::Valid),
+            "holey" => Ok(Status::Holey),
+            "invalid" => Ok(Status::Invalid),
+            "error" => Ok(Status::Error),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Status::Valid => "valid",
+            Status::Holey => "holey",
+            Status::Invalid => "invalid",
+            Status::Error => "error",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub problem: PathBuf,
+    pub proof: PathBuf,
+    pub expected: Option<Status>,
+}
+
+/// Returns `true` if the path has the extension of a manifest file.
+pub fn is_manifest(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("csv" | "json")
+    )
+}
+
+pub fn read_manifest(path: &Path) -> Result<Vec<ManifestEntry>, CliError> {
+    let contents = fs::read_to_string(path)?;
+    parse_manifest(path, &contents)
+}
+
+/// Parses the contents of the manifest file at the given path. The path is only used to decide the
+/// format of the manifest, and to resolve the relative paths in it.
+fn parse_manifest(path: &Path, contents: &str) -> Result<Vec<ManifestEntry>, CliError> {
+    let raw = if path.extension().and_then(|e| e.to_str()) == Some("json") {
+        serde_json::from_str(contents)
+            .map_err(|e| CliError::JsonManifestError(path.to_owned(), e))?
+    } else {
+        read_csv_manifest(contents)
+            .map_err(|(line, message)| CliError::ManifestError(path.to_owned(), line, message))?
+    };
+
+    let base = path.parent().unwrap_or(Path::new(""));
+    raw.into_iter()
+        .map(|RawEntry { problem, proof, expected }| {
+            let proof = base.join(proof);
+            let problem = match problem.filter(|p| !p.is_empty()) {
+                Some(problem) => base.join(problem),
+                None => infer_problem_path(&proof)?,
+            };
+            Ok(ManifestEntry { problem, proof, expected })
+        })
+        .collect()
+}
+
+/// A manifest entry whose paths were not yet resolved. If the problem path is missing or empty, it
+/// must be inferred from the proof path.
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEntry {
+    #[serde(default)]
+    problem: Option<String>,
+
+    #[serde(deserialize_with = "deserialize_proof_path")]
+    proof: String,
+
+    #[serde(default)]
+    expected: Option<Status>,
+}
+
+fn deserialize_proof_path<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let path = String::deserialize(deserializer)?;
+    if path.is_empty() {
+        return Err(D::Error::custom("missing proof path"));
+    }
+    Ok(path)
+}
+
+fn read_csv_manifest(input: &str) -> Result<Vec<RawEntry>, (usize, String)> {
+    let mut result = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (i == 0 && line.starts_with("problem")) {
+            continue;
+        }
+        let fields: Vec<_> = line.split(',').map(str::trim).collect();
+        let (problem, proof, expected) = match fields.as_slice() {
+            [problem, proof] | [problem, proof, ""] => (problem, proof, None),
+            [problem, proof, expected] => {
+                let status = expected.parse().map_err(|_| {
+                    let message = format!("invalid expected status '{}'", expected);
+                    (line_number, message)
+                })?;
+                (problem, proof, Some(status))
+            }
+            _ => return Err((line_number, "expected 2 or 3 fields".to_owned())),
+        };
+        if proof.is_empty() {
+            return Err((line_number, "missing proof path".to_owned()));
+        }
+        result.push(RawEntry {
+            problem: Some(problem.to_string()),
+            proof: proof.to_string(),
+            expected,
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(problem: &str, proof: &str, expected: Option<Status>) -> ManifestEntry {
+        ManifestEntry {
+            problem: PathBuf::from(problem),
+            proof: PathBuf::from(proof),
+            expected,
+        }
+    }
+
+    fn error_line(result: Result<Vec<ManifestEntry>, CliError>) -> usize {
+        match result {
+            Err(CliError::ManifestError(_, line, _)) => line,
+            Err(CliError::JsonManifestError(_, e)) => e.line(),
+            _ => panic!("expected manifest error"),
+        }
+    }
+
+    #[test]
+    fn test_csv_manifest() {
+        let path = Path::new("bench/manifest.csv");
+        let contents = "problem,proof,expected
+            # A comment
+
+            a.smt2, a.smt2.alethe, valid
+            ,b.smt2.alethe,
+            /abs/c.smt2,sub/c.alethe,holey
+        ";
+        let entries = parse_manifest(path, contents).unwrap();
+        assert_eq!(
+            entries,
+            [
+                entry("bench/a.smt2", "bench/a.smt2.alethe", Some(Status::Valid)),
+                entry("bench/b.smt2", "bench/b.smt2.alethe", None),
+                entry("/abs/c.smt2", "bench/sub/c.alethe", Some(Status::Holey)),
+            ]
+        );
+
+        let invalid = [
+            ("a.smt2,a.alethe,valid\nb.smt2,b.alethe,passed", 2),
+            ("\n\na.smt2,a.alethe,valid,extra", 3),
+            ("a.smt2", 1),
+            ("a.smt2,,valid", 1),
+        ];
+        for (contents, line) in invalid {
+            assert_eq!(error_line(parse_manifest(path, contents)), line);
+        }
+    }
+
+    #[test]
+    fn test_json_manifest() {
+        let path = Path::new("bench/manifest.json");
+        let contents = r#"[
+            {"problem": "a.smt2", "proof": "a.smt2.alethe", "expected": "invalid"},
+            {"problem": null, "proof": "b.smt2.alethe"},
+            {"proof": "\u00e9/c.smt2.alethe", "expected": null, "problem": ""}
+        ]"#;
+        let entries = parse_manifest(path, contents).unwrap();
+        assert_eq!(
+            entries,
+            [
+                entry("bench/a.smt2", "bench/a.smt2.alethe", Some(Status::Invalid)),
+                entry("bench/b.smt2", "bench/b.smt2.alethe", None),
+                entry("bench/\u{e9}/c.smt2", "bench/\u{e9}/c.smt2.alethe", None),
+            ]
+        );
+        assert!(parse_manifest(path, "[]").unwrap().is_empty());
+
+        let invalid = [
+            ("[\n{\"proof\": \"a.alethe\", \"expected\": \"passed\"}]", 2),
+            ("[\n{\"proof\": \"a.alethe\", \"result\": \"valid\"}]", 2),
+            ("[\n{\"problem\": \"a.smt2\"}\n]", 2),
+            ("[{\"proof\": \"\"}]", 1),
+            ("[{\"proof\": \"a.alethe\"}] []", 1),
+            ("{\"proof\": \"a.alethe\"}", 1),
+        ];
+        for (contents, line) in invalid {
+            assert_eq!(
+                error_line(parse_manifest(path, contents)),
+                line,
+                "{}",
+                contents
+            );
+        }
+    }
+}
@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use crate::error::CliError;
+use crate::{error::CliError, manifest};
 use std::{ffi::OsStr, fs, path::PathBuf};
 
 const SMT_FILE_EXTENSIONS: [&str; 3] = ["smt", "smt2", "smt_in"];
@@ -49,7 +49,10 @@ where
     let mut result = Vec::new();
     for p in paths {
         let file_type = fs::metadata(p)?.file_type();
-        if file_type.is_file() {
+        if file_type.is_file() && manifest::is_manifest(p.as_ref()) {
+            let entries = manifest::read_manifest(p.as_ref())?;
+            result.extend(entries.into_iter().map(|e| (e.problem, e.proof)));
+        } else if file_type.is_file() {
             let problem_file = infer_problem_path(p)?;
             result.push((problem_file, p.into()))
         } else {
@@ -0,0 +1,122 @@
+//! Machine-readable reports of checking results, printed with `--output json`.
+
+use crate::error::CliError;
+use carcara::{checker::error::Explanation, instrumentation::Stage};
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Valid,
+    Holey,
+    Invalid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckReport {
+    pub status: CheckStatus,
+    pub errors: Vec<ErrorReport>,
+    pub timing: Timing,
+}
+
+/// An error found while checking. For errors in a specific step, `step` and `rule` are present,
+/// and `details` holds the serialized checker error, with terms printed in SMT-LIB syntax. If the
+/// rule explained why it failed, the explanation is in `explanation`.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub step: Option<String>,
+    pub rule: Option<String>,
+    pub kind: String,
+    pub message: String,
+    pub details: Value,
+    pub explanation: Option<Explanation>,
+}
+
+/// The time spent in each stage, in seconds. The time of a stage is only present if it was
+/// measured separately, which is only the case when checking with a single thread.
+#[derive(Debug, Default, Serialize)]
+pub struct Timing {
+    pub parsing: Option<f64>,
+    pub checking: Option<f64>,
+    pub total: f64,
+}
+
+impl Timing {
+    /// Builds the timing from the total time and the times at which each stage finished, relative
+    /// to the start.
+    pub fn new(total: Duration, stages: &[(Stage, Duration)]) -> Self {
+        let finished = |stage| stages.iter().find(|(s, _)| *s == stage).map(|&(_, t)| t);
+        let parse = finished(Stage::Parse);
+        let check = finished(Stage::Check);
+        Self {
+            parsing: parse.map(|t| t.as_secs_f64()),
+            checking: parse.zip(check).map(|(p, c)| (c - p).as_secs_f64()),
+            total: total.as_secs_f64(),
+        }
+    }
+}
+
+impl ErrorReport {
+    pub fn new(error: &CliError) -> Self {
+        let message = error.to_string();
+        match error {
+            CliError::CarcaraError(carcara::Error::Checker { inner, rule, step, .. }) => {
+                let details = serde_json::to_value(inner.cause()).unwrap_or(Value::Null);
+                Self {
+                    step: Some(step.clone()),
+                    rule: Some(rule.clone()),
+                    kind: variant_path(&details),
+                    message: inner.to_string(),
+                    details,
+                    explanation: inner.explanation().cloned(),
+                }
+            }
+            CliError::CarcaraError(e) => {
+                let kind = match e {
+                    carcara::Error::Io(_) => "Io",
+                    carcara::Error::Parser(..) => "Parser",
+                    carcara::Error::Checker { .. } => unreachable!(),
+                    carcara::Error::DoesNotReachEmptyClause => "DoesNotReachEmptyClause",
+                    carcara::Error::DoesNotReachExpectedConclusion => {
+                        "DoesNotReachExpectedConclusion"
+                    }
+                    carcara::Error::RejectedRules(_) => "RejectedRules",
+                    carcara::Error::SizeLimit(_) => "SizeLimit",
+                    carcara::Error::Lfsc(_) => "Lfsc",
+                    carcara::Error::Drat(_) => "Drat",
+                    carcara::Error::Export(_) => "Export",
+                };
+                Self::without_step(kind, message)
+            }
+            _ => Self::without_step("Other", message),
+        }
+    }
+
+    fn without_step(kind: &str, message: String) -> Self {
+        Self {
+            step: None,
+            rule: None,
+            kind: kind.to_owned(),
+            message,
+            details: Value::Null,
+            explanation: None,
+        }
+    }
+}
+
+/// Returns the names of the enum variants in a serialized error, joined by "::". Each error is
+/// serialized with its variant name in the `kind` field, and errors that wrap other errors have the
+/// inner error in their `data` field.
+fn variant_path(mut value: &Value) -> String {
+    let mut path = Vec::new();
+    while let Some(Value::String(kind)) = value.get("kind") {
+        path.push(kind.as_str());
+        match value.get("data") {
+            Some(data) => value = data,
+            None => break,
+        }
+    }
+    path.join("::")
+}
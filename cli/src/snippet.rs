@@ -0,0 +1,328 @@
+use ansi_term::{Color, Style};
+use carcara::parser::{CommandLocation, Position, Span};
+use std::{fmt::Write, ops::Range};
+
+/// The maximum number of source lines shown for a single command.
+const MAX_SNIPPET_LINES: usize = 12;
+
+/// Renders the source of the proof command with the given id, in a format similar to the one used
+/// by `rustc`. If the error message contains a quoted term that appears in the command's source,
+/// that term is underlined. Otherwise, the command id is underlined. Returns `None` if the command
+/// could not be found in the source.
+///
+/// If the location of the command was recorded by the parser, it is used instead of searching for
+/// the command in the source, and a quoted term is preferably matched against the terms of the
+/// command's conclusion and arguments.
+pub fn render_step_snippet(
+    source: &str,
+    file_name: &str,
+    step_id: &str,
+    location: Option<&CommandLocation>,
+    message: &str,
+    colors_enabled: bool,
+) -> Option<String> {
+    let lines = line_ranges(source);
+    let (command, id, term_ranges) = match location {
+        Some(l) => {
+            let terms = (l.clause.iter().chain(&l.args))
+                .filter_map(|s| span_range(source, &lines, *s))
+                .collect();
+            (
+                span_range(source, &lines, l.span)?,
+                span_range(source, &lines, l.id)?,
+                terms,
+            )
+        }
+        None => {
+            let (command, id) = find_command(source, step_id)?;
+            (command, id, Vec::new())
+        }
+    };
+
+    // The terms in error messages are surrounded by single quotes. We try each of them, and
+    // underline the first one that appears in the command's source
+    let highlight = message
+        .split('\'')
+        .skip(1)
+        .step_by(2)
+        .filter(|s| !s.is_empty() && *s != step_id)
+        .find_map(|term| {
+            // The source of the term may be formatted differently, so we ignore whitespace
+            let exact = (term_ranges.iter()).find(|r| {
+                source[(*r).clone()]
+                    .split_whitespace()
+                    .eq(term.split_whitespace())
+            });
+            exact
+                .cloned()
+                .or_else(|| find_term(source, id.end..command.end, term))
+        })
+        .unwrap_or(id);
+
+    let (gutter_style, caret_style) = if colors_enabled {
+        (Color::Blue.bold(), Color::Red.bold())
+    } else {
+        (Style::new(), Style::new())
+    };
+
+    let first_line = lines.iter().position(|l| l.end >= command.start)?;
+    let last_line = lines.iter().position(|l| l.end >= command.end)?;
+    let highlight_line = lines.iter().position(|l| l.end >= highlight.start)?;
+    let column = highlight.start - lines[highlight_line].start + 1;
+
+    // If the command is too long, we only show the lines around the highlighted term
+    let shown_start = std::cmp::max(
+        first_line,
+        (highlight_line + 1).saturating_sub(MAX_SNIPPET_LINES / 2),
+    );
+    let shown_end = std::cmp::min(last_line, shown_start + MAX_SNIPPET_LINES - 1);
+
+    let width = (shown_end + 1).to_string().len();
+    let blank_gutter = gutter_style.paint(format!("{:width$} |", ""));
+    let mut result = String::new();
+    writeln!(
+        result,
+        "{}{} {}:{}:{}",
+        " ".repeat(width),
+        gutter_style.paint("-->"),
+        file_name,
+        highlight_line + 1,
+        column,
+    )
+    .unwrap();
+    writeln!(result, "{}", blank_gutter).unwrap();
+    if shown_start > first_line {
+        writeln!(result, "{}", gutter_style.paint("...")).unwrap();
+    }
+    for (i, line) in lines
+        .iter()
+        .enumerate()
+        .take(shown_end + 1)
+        .skip(shown_start)
+    {
+        let number = gutter_style.paint(format!("{:>width$} |", i + 1));
+        writeln!(result, "{} {}", number, &source[line.clone()]).unwrap();
+
+        let start = std::cmp::max(highlight.start, line.start);
+        let end = std::cmp::min(highlight.end, line.end);
+        if start < end {
+            let padding = source[line.start..start]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let carets = "^".repeat(source[start..end].chars().count());
+            writeln!(
+                result,
+                "{} {}{}",
+                blank_gutter,
+                padding,
+                caret_style.paint(carets)
+            )
+            .unwrap();
+        }
+    }
+    if shown_end < last_line {
+        writeln!(result, "{}", gutter_style.paint("...")).unwrap();
+    }
+    write!(result, "{}", blank_gutter).unwrap();
+    Some(result)
+}
+
+/// Finds the first occurrence of `term` in the given range of the source, making sure it is not
+/// part of a larger symbol.
+fn find_term(source: &str, range: Range<usize>, term: &str) -> Option<Range<usize>> {
+    let is_symbol_char =
+        |c: Option<char>| c.is_some_and(|c| !c.is_whitespace() && c != '(' && c != ')');
+    let mut start = range.start;
+    while let Some(i) = source[start..range.end].find(term) {
+        let found = start + i..start + i + term.len();
+        let before = source[..found.start].chars().next_back();
+        let after = source[found.end..].chars().next();
+        if !is_symbol_char(before) && !is_symbol_char(after) {
+            return Some(found);
+        }
+        start = found.start + 1;
+    }
+    None
+}
+
+/// Converts a span, given as line and column numbers, into a byte range of the source.
+fn span_range(source: &str, lines: &[Range<usize>], span: Span) -> Option<Range<usize>> {
+    let offset = |(line, column): Position| {
+        let line = lines.get(line.checked_sub(1)?)?;
+        let in_line: usize = source[line.clone()]
+            .chars()
+            .take(column.saturating_sub(1))
+            .map(char::len_utf8)
+            .sum();
+        Some(line.start + in_line)
+    };
+    Some(offset(span.start)?..offset(span.end)?)
+}
+
+/// Returns the byte range of each line in the source, not including the line terminator.
+fn line_ranges(source: &str) -> Vec<Range<usize>> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    for line in source.split('\n') {
+        let end = start + line.trim_end_matches('\r').len();
+        result.push(start..end);
+        start += line.len() + 1;
+    }
+    result
+}
+
+/// Finds the `step` or `assume` command with the given id. Returns the byte range of the
+/// whole command, and the byte range of its id.
+fn find_command(source: &str, id: &str) -> Option<(Range<usize>, Range<usize>)> {
+    let bytes = source.as_bytes();
+    let mut i = 0;
+    let mut depth = 0;
+    let mut command_start = None;
+    let mut id_range = None;
+    while i < bytes.len() {
+        match bytes[i] {
+            b';' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += 1;
+                }
+            }
+            b'|' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'|' {
+                    i += 1;
+                }
+            }
+            b'(' => {
+                if depth == 0 {
+                    if let Some(range) = command_id(source, i + 1) {
+                        if source[range.clone()].trim_matches('|') == id.trim_matches('|') {
+                            command_start = Some(i);
+                            id_range = Some(range);
+                        }
+                    }
+                }
+                depth += 1;
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    if let (Some(start), Some(id)) = (command_start, id_range.clone()) {
+                        return Some((start..i + 1, id));
+                    }
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Given the position right after the opening parenthesis of a root-level command, returns the
+/// byte range of the command id, if the command is a `step` or an `assume`. Note that `anchor`
+/// commands share their id with the step that closes the subproof, so they are not considered.
+fn command_id(source: &str, start: usize) -> Option<Range<usize>> {
+    let rest = &source[start..];
+    let keyword_len = rest.find(|c: char| c.is_whitespace())?;
+    if !matches!(&rest[..keyword_len], "step" | "assume") {
+        return None;
+    }
+    let after_keyword = &rest[keyword_len..];
+    let id_start = start + keyword_len + after_keyword.len() - after_keyword.trim_start().len();
+    let id_len = if source[id_start..].starts_with('|') {
+        source[id_start + 1..].find('|')? + 2
+    } else {
+        source[id_start..].find(|c: char| c.is_whitespace() || c == '(' || c == ')')?
+    };
+    Some(id_start..id_start + id_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the source of the command with the given id, and of its id.
+    fn find<'a>(source: &'a str, id: &str) -> Option<(&'a str, &'a str)> {
+        let (command, id) = find_command(source, id)?;
+        Some((&source[command], &source[id]))
+    }
+
+    #[test]
+    fn test_nested_parentheses() {
+        let source = "(assume h1 (and (or p (not q)) (=> r (and p q))))
+            (step t2 (cl (or p (not q))) :rule and :premises (h1) :args (0))";
+        assert_eq!(
+            find(source, "h1"),
+            Some(("(assume h1 (and (or p (not q)) (=> r (and p q))))", "h1"))
+        );
+        assert_eq!(
+            find(source, "t2"),
+            Some((
+                "(step t2 (cl (or p (not q))) :rule and :premises (h1) :args (0))",
+                "t2"
+            ))
+        );
+        assert_eq!(find(source, "t3"), None);
+    }
+
+    #[test]
+    fn test_strings() {
+        let source = r#"(step t1 (cl (= x ")(")) :rule hole)
+            ; (step t2 (cl) :rule hole)
+            (step t2 (cl (= y "a "" ) b")) :rule hole :premises (t1))"#;
+        assert_eq!(
+            find(source, "t1"),
+            Some((r#"(step t1 (cl (= x ")(")) :rule hole)"#, "t1"))
+        );
+        assert_eq!(
+            find(source, "t2"),
+            Some((
+                r#"(step t2 (cl (= y "a "" ) b")) :rule hole :premises (t1))"#,
+                "t2"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_quoted_symbols() {
+        let source = "(step |t 1| (cl (= |a)| b)) :rule hole)
+            (step t2 (cl (= |(| b)) :rule hole :premises (|t 1|))";
+        assert_eq!(
+            find(source, "|t 1|"),
+            Some(("(step |t 1| (cl (= |a)| b)) :rule hole)", "|t 1|"))
+        );
+        assert_eq!(
+            find(source, "t2"),
+            Some((
+                "(step t2 (cl (= |(| b)) :rule hole :premises (|t 1|))",
+                "t2"
+            ))
+        );
+
+        // The quoted term in the message is underlined, even if it contains parentheses
+        let snippet = render_step_snippet(
+            source,
+            "a.alethe",
+            "|t 1|",
+            None,
+            "term '|a)|' is wrong",
+            false,
+        )
+        .unwrap();
+        let expected = [
+            " --> a.alethe:1:20",
+            "  |",
+            "1 | (step |t 1| (cl (= |a)| b)) :rule hole)",
+            "  |                    ^^^^",
+            "  |",
+        ];
+        assert_eq!(snippet, expected.join("\n"));
+    }
+}
@@ -0,0 +1,247 @@
+//! Python bindings for Carcara, built with PyO3. These expose functions to parse, check and
+//! elaborate proofs, and to inspect the commands of a parsed proof. Terms are given to Python as
+//! strings, in SMT-LIB syntax.
+
+use carcara::{ast, checker, elaborator, parser};
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+create_exception!(
+    carcara_py,
+    CarcaraError,
+    PyException,
+    "Raised when a proof can't be parsed, checked or elaborated."
+);
+
+fn to_py_err(e: carcara::Error) -> PyErr {
+    CarcaraError::new_err(e.to_string())
+}
+
+/// A command in a parsed proof.
+#[pyclass(frozen, get_all)]
+#[derive(Debug, Clone)]
+struct Command {
+    /// Either `"assume"`, `"step"` or `"subproof"`.
+    kind: &'static str,
+
+    /// The command id. For subproofs, this is the id of the last step.
+    id: String,
+
+    /// The rule of a step, or of the last step of a subproof. This is `None` for `assume`
+    /// commands.
+    rule: Option<String>,
+
+    /// The conclusion clause. For `assume` commands, this has only the assumed term.
+    clause: Vec<String>,
+
+    /// The ids of the premises of a step.
+    premises: Vec<String>,
+
+    /// The arguments of a step, or the anchor arguments of a subproof.
+    args: Vec<String>,
+
+    /// The ids of the local premises discharged by a step.
+    discharge: Vec<String>,
+
+    /// The commands inside a subproof, including its last step.
+    commands: Vec<Command>,
+}
+
+#[pymethods]
+impl Command {
+    fn __repr__(&self) -> String {
+        match &self.rule {
+            Some(rule) => format!("<{} {}: {}>", self.kind, self.id, rule),
+            None => format!("<{} {}>", self.kind, self.id),
+        }
+    }
+}
+
+/// A parsed proof.
+#[pyclass(frozen, get_all)]
+#[derive(Debug, Clone)]
+struct Proof {
+    /// The root-level commands of the proof.
+    commands: Vec<Command>,
+}
+
+#[pymethods]
+impl Proof {
+    fn __len__(&self) -> usize {
+        self.commands.len()
+    }
+}
+
+/// Converts the commands of a proof, or of a subproof. The `stack` holds the commands of each
+/// enclosing subproof, and is used to find the ids of premises.
+fn convert_commands<'a>(
+    commands: &'a [ast::ProofCommand],
+    stack: &mut Vec<&'a [ast::ProofCommand]>,
+) -> Vec<Command> {
+    stack.push(commands);
+    let result = commands
+        .iter()
+        .map(|command| convert_command(command, stack))
+        .collect();
+    stack.pop();
+    result
+}
+
+fn convert_command<'a>(
+    command: &'a ast::ProofCommand,
+    stack: &mut Vec<&'a [ast::ProofCommand]>,
+) -> Command {
+    let terms = |terms: &[ast::Rc<ast::Term>]| terms.iter().map(ToString::to_string).collect();
+    let ids = |stack: &[&[ast::ProofCommand]], premises: &[(usize, usize)]| {
+        premises
+            .iter()
+            .map(|&(depth, i)| stack[depth][i].id().to_owned())
+            .collect()
+    };
+    let mut result = Command {
+        kind: "assume",
+        id: command.id().to_owned(),
+        rule: None,
+        clause: terms(command.clause()),
+        premises: Vec::new(),
+        args: Vec::new(),
+        discharge: Vec::new(),
+        commands: Vec::new(),
+    };
+    match command {
+        ast::ProofCommand::Assume { .. } => (),
+        ast::ProofCommand::Step(step) => {
+            result.kind = "step";
+            result.rule = Some(step.rule.clone());
+            result.premises = ids(stack, &step.premises);
+            result.args = terms(&step.args);
+            result.discharge = ids(stack, &step.discharge);
+        }
+        ast::ProofCommand::Subproof(subproof) => {
+            result.kind = "subproof";
+            if let Some(ast::ProofCommand::Step(step)) = subproof.commands.last() {
+                result.rule = Some(step.rule.clone());
+            }
+            result.args = subproof
+                .args
+                .iter()
+                .map(|arg| match arg {
+                    ast::AnchorArg::Variable((name, sort)) => format!("({} {})", name, sort),
+                    ast::AnchorArg::Assign((name, sort), value) => {
+                        format!("(:= ({} {}) {})", name, sort, value)
+                    }
+                })
+                .collect();
+            result.commands = convert_commands(&subproof.commands, stack);
+        }
+    }
+    result
+}
+
+fn parser_config(strict: bool) -> parser::Config {
+    parser::Config {
+        strict,
+        track_locations: true,
+        ..parser::Config::new()
+    }
+}
+
+/// Parses a proof, given the contents of the problem and proof files.
+#[pyfunction]
+#[pyo3(signature = (problem, proof, *, strict = false))]
+fn parse_proof(problem: &str, proof: &str, strict: bool) -> PyResult<Proof> {
+    let (_, proof, _) =
+        parser::parse_instance(problem.as_bytes(), proof.as_bytes(), parser_config(strict))
+            .map_err(to_py_err)?;
+    let commands = convert_commands(&proof.commands, &mut Vec::new());
+    Ok(Proof { commands })
+}
+
+/// Checks a proof, given the contents of the problem and proof files. Returns `"valid"` or
+/// `"holey"`, and raises a `CarcaraError` if the proof is invalid.
+#[pyfunction]
+#[pyo3(signature = (problem, proof, *, strict = false, ignore_unknown_rules = false))]
+fn check(
+    problem: &str,
+    proof: &str,
+    strict: bool,
+    ignore_unknown_rules: bool,
+) -> PyResult<&'static str> {
+    let checker_config = checker::Config::new().ignore_unknown_rules(ignore_unknown_rules);
+    let is_holey = carcara::check(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        parser_config(strict),
+        checker_config,
+        false,
+    )
+    .map_err(to_py_err)?;
+    Ok(if is_holey { "holey" } else { "valid" })
+}
+
+/// Checks and elaborates a proof, given the contents of the problem and proof files, using the
+/// same elaboration pipeline as the `elaborate` command of the CLI. Returns the elaborated proof,
+/// and raises a `CarcaraError` if the proof is invalid.
+#[pyfunction]
+#[pyo3(signature = (problem, proof, *, strict = false, use_sharing = false))]
+fn elaborate(problem: &str, proof: &str, strict: bool, use_sharing: bool) -> PyResult<String> {
+    use elaborator::ElaborationStep::*;
+
+    let elaborator_config = elaborator::Config {
+        lia_solver: None,
+        nla_solver: None,
+        uncrowd_rotation: false,
+        hole_solver: None,
+        solver_limits: elaborator::SolverLimits::default(),
+        solver_cache: None,
+        limits: carcara::limits::SizeLimits::new(),
+        trace: false,
+        target: elaborator::ElaborationTarget::Alethe,
+    };
+    let pipeline = vec![
+        Skolemization,
+        Polyeq,
+        Flattening,
+        LiaGeneric,
+        NlaGeneric,
+        Local,
+        Uncrowd,
+        Reordering,
+        Hole,
+    ];
+    let (_, problem, proof, mut pool, _, _) = carcara::check_and_elaborate(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        parser_config(strict),
+        checker::Config::new(),
+        elaborator_config,
+        pipeline,
+        false,
+    )
+    .map_err(to_py_err)?;
+
+    let sharing = if use_sharing {
+        ast::SharingMode::Named
+    } else {
+        ast::SharingMode::None
+    };
+    let mut output = Vec::new();
+    ast::write_proof(
+        &mut pool,
+        &problem.prelude,
+        &proof,
+        &mut output,
+        &ast::PrintOptions::new().sharing(sharing),
+    )?;
+    Ok(String::from_utf8(output).unwrap())
+}
+
+#[pymodule]
+fn carcara_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("CarcaraError", m.py().get_type::<CarcaraError>())?;
+    m.add_class::<Command>()?;
+    m.add_class::<Proof>()?;
+    m.add_function(wrap_pyfunction!(parse_proof, m)?)?;
+    m.add_function(wrap_pyfunction!(check, m)?)?;
+    m.add_function(wrap_pyfunction!(elaborate, m)?)?;
+    Ok(())
+}
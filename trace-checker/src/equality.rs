@@ -0,0 +1,274 @@
+use crate::term::{Node, TermId, TermTable};
+use std::collections::HashSet;
+
+/// Compares terms for equality, optionally modulo reordering of equalities and alpha-equivalence.
+/// This follows the definitions used by Carcara's `polyeq` and `alpha_equiv` functions.
+pub struct Comparator<'a> {
+    table: &'a TermTable,
+    mod_reordering: bool,
+    alpha_equiv: bool,
+
+    /// The pairs of variables bound by the enclosing binders in each term, from outermost to
+    /// innermost.
+    bound: Vec<(String, String)>,
+
+    /// Pairs of terms already found to be equal, outside of any binder.
+    cache: HashSet<(TermId, TermId)>,
+}
+
+impl<'a> Comparator<'a> {
+    pub fn new(table: &'a TermTable, mod_reordering: bool, alpha_equiv: bool) -> Self {
+        Self {
+            table,
+            mod_reordering,
+            alpha_equiv,
+            bound: Vec::new(),
+            cache: HashSet::new(),
+        }
+    }
+
+    pub fn eq(&mut self, a: TermId, b: TermId) -> bool {
+        // If every enclosing binder binds the same names in both terms, identical terms are
+        // always equal
+        if a == b && self.bound.iter().all(|(x, y)| x == y) {
+            return true;
+        }
+        if self.bound.is_empty() && self.cache.contains(&(a, b)) {
+            return true;
+        }
+        let result = self.eq_nodes(self.table.node(a), self.table.node(b));
+        if result && self.bound.is_empty() {
+            self.cache.insert((a, b));
+        }
+        result
+    }
+
+    fn eq_all(&mut self, a: &[TermId], b: &[TermId]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(&a, &b)| self.eq(a, b))
+    }
+
+    fn eq_nodes(&mut self, a: &Node, b: &Node) -> bool {
+        match (a, b) {
+            (Node::Var(a, a_sort), Node::Var(b, b_sort)) if self.alpha_equiv => {
+                let index = |name: &str, first: bool| {
+                    self.bound.iter().rposition(|(x, y)| {
+                        let bound_name = if first { x } else { y };
+                        bound_name == name
+                    })
+                };
+                let same_variable = match (index(a, true), index(b, false)) {
+                    (None, None) => a == b,
+                    (Some(i), Some(j)) => i == j,
+                    _ => false,
+                };
+                same_variable && self.eq(*a_sort, *b_sort)
+            }
+            (Node::App(f_a, args_a), Node::App(f_b, args_b)) => {
+                self.eq(*f_a, *f_b) && self.eq_all(args_a, args_b)
+            }
+            (Node::Op(op_a, args_a), Node::Op(op_b, args_b)) => {
+                if self.mod_reordering {
+                    if let ("=", [a_1, a_2], "=", [b_1, b_2]) = (
+                        op_a.as_str(),
+                        args_a.as_slice(),
+                        op_b.as_str(),
+                        args_b.as_slice(),
+                    ) {
+                        return (self.eq(*a_1, *b_1) && self.eq(*a_2, *b_2))
+                            || (self.eq(*a_1, *b_2) && self.eq(*a_2, *b_1));
+                    }
+                }
+                op_a == op_b && self.eq_all(args_a, args_b)
+            }
+            (Node::ParamOp(op_a, op_args_a, args_a), Node::ParamOp(op_b, op_args_b, args_b)) => {
+                op_a == op_b && op_args_a == op_args_b && self.eq_all(args_a, args_b)
+            }
+            (Node::Sort(head_a, args_a), Node::Sort(head_b, args_b)) => {
+                head_a == head_b && self.eq_all(args_a, args_b)
+            }
+            (Node::Binder(q_a, binds_a, a), Node::Binder(q_b, binds_b, b)) => {
+                q_a == q_b && self.eq_binders(binds_a, binds_b, *a, *b)
+            }
+            (Node::Let(binds_a, a), Node::Let(binds_b, b)) => {
+                self.eq_binders(binds_a, binds_b, *a, *b)
+            }
+            (Node::Real(n, d), Node::Op(op, args)) | (Node::Op(op, args), Node::Real(n, d))
+                if op == "/" =>
+            {
+                self.as_fraction(args)
+                    .is_some_and(|(p, q)| fraction_eq((n, d), (p, q)))
+            }
+            (Node::Int(i), Node::Op(op, args)) | (Node::Op(op, args), Node::Int(i))
+                if op == "-" && i.starts_with('-') && args.len() == 1 =>
+            {
+                matches!(self.table.node(args[0]), Node::Int(j) if *j == i[1..])
+            }
+            (Node::Real(n, d), Node::Op(op, args)) | (Node::Op(op, args), Node::Real(n, d))
+                if op == "-" && n.starts_with('-') && args.len() == 1 =>
+            {
+                let n = &n[1..];
+                match self.table.node(args[0]) {
+                    Node::Op(op, args) if op == "/" => self
+                        .as_fraction(args)
+                        .is_some_and(|(p, q)| fraction_eq((n, d), (p, q))),
+                    Node::Real(m, e) => m == n && e == d,
+                    _ => false,
+                }
+            }
+            // Constants and variables are only equal if they are identical
+            _ => a == b,
+        }
+    }
+
+    fn eq_binders(
+        &mut self,
+        binds_a: &[(String, TermId)],
+        binds_b: &[(String, TermId)],
+        a: TermId,
+        b: TermId,
+    ) -> bool {
+        if binds_a.len() != binds_b.len() {
+            return false;
+        }
+        // The sorts or values in the binding lists are compared outside of the scope of the binder
+        for ((_, x), (_, y)) in binds_a.iter().zip(binds_b) {
+            if !self.eq(*x, *y) {
+                return false;
+            }
+        }
+        if self.alpha_equiv {
+            let depth = self.bound.len();
+            let pairs = binds_a.iter().zip(binds_b);
+            self.bound
+                .extend(pairs.map(|((x, _), (y, _))| (x.clone(), y.clone())));
+            let result = self.eq(a, b);
+            self.bound.truncate(depth);
+            result
+        } else {
+            binds_a.iter().zip(binds_b).all(|((x, _), (y, _))| x == y) && self.eq(a, b)
+        }
+    }
+
+    /// If `args` are two real constants with integer values, returns their numerators.
+    fn as_fraction<'b>(&'b self, args: &[TermId]) -> Option<(&'b str, &'b str)> {
+        match args {
+            [p, q] => match (self.table.node(*p), self.table.node(*q)) {
+                (Node::Real(p, one_p), Node::Real(q, one_q)) if one_p == "1" && one_q == "1" => {
+                    Some((p, q))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Checks if `n / d` is equal to `p / q`, where `n / d` is in lowest terms, with `d` positive.
+fn fraction_eq((n, d): (&str, &str), (p, q): (&str, &str)) -> bool {
+    q != "0" && multiply(n, q) == multiply(p, d)
+}
+
+/// Multiplies two decimal integers in canonical form.
+fn multiply(a: &str, b: &str) -> String {
+    let (a_negative, a) = a.strip_prefix('-').map_or((false, a), |a| (true, a));
+    let (b_negative, b) = b.strip_prefix('-').map_or((false, b), |b| (true, b));
+
+    // The digits of the result, from least to most significant
+    let mut digits = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.bytes().rev().enumerate() {
+        let mut carry = 0;
+        for (j, y) in b.bytes().rev().enumerate() {
+            let value = digits[i + j] + u32::from(x - b'0') * u32::from(y - b'0') + carry;
+            digits[i + j] = value % 10;
+            carry = value / 10;
+        }
+        digits[i + b.len()] += carry;
+    }
+    while digits.len() > 1 && digits.last() == Some(&0) {
+        digits.pop();
+    }
+    let mut result = String::with_capacity(digits.len() + 1);
+    if a_negative != b_negative && digits != [0] {
+        result.push('-');
+    }
+    result.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|d| char::from_digit(*d, 10).unwrap()),
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiply() {
+        assert_eq!(multiply("0", "-5"), "0");
+        assert_eq!(multiply("12", "34"), "408");
+        assert_eq!(multiply("-99", "99"), "-9801");
+        assert_eq!(multiply("-7", "-6"), "42");
+        assert_eq!(
+            multiply("123456789012345678901234567890", "987654321"),
+            "121932631124828532112482853211126352690"
+        );
+    }
+
+    #[test]
+    fn test_fraction_eq() {
+        assert!(fraction_eq(("1", "2"), ("2", "4")));
+        assert!(fraction_eq(("-1", "3"), ("1", "-3")));
+        assert!(!fraction_eq(("1", "3"), ("1", "-3")));
+        assert!(!fraction_eq(("0", "1"), ("0", "0")));
+    }
+
+    #[test]
+    fn test_comparator() {
+        let mut table = TermTable::default();
+        let int = table.add(Node::Sort("Int".to_owned(), Vec::new()));
+        let var = |table: &mut TermTable, name: &str| table.add(Node::Var(name.to_owned(), int));
+        let (x, y, z) = (
+            var(&mut table, "x"),
+            var(&mut table, "y"),
+            var(&mut table, "z"),
+        );
+        let eq = |table: &mut TermTable, a, b| table.add(Node::Op("=".to_owned(), vec![a, b]));
+        let forall = |table: &mut TermTable, x: &str, body| {
+            table.add(Node::Binder(
+                "forall".to_owned(),
+                vec![(x.to_owned(), int)],
+                body,
+            ))
+        };
+
+        let x_eq_z = eq(&mut table, x, z);
+        let z_eq_x = eq(&mut table, z, x);
+        let y_eq_z = eq(&mut table, y, z);
+        let z_eq_y = eq(&mut table, z, y);
+        let a = forall(&mut table, "x", x_eq_z);
+        let b = forall(&mut table, "y", z_eq_y);
+        let c = forall(&mut table, "y", z_eq_x);
+
+        assert!(Comparator::new(&table, true, false).eq(x_eq_z, z_eq_x));
+        assert!(!Comparator::new(&table, false, false).eq(x_eq_z, z_eq_x));
+        assert!(!Comparator::new(&table, true, false).eq(a, b));
+        assert!(Comparator::new(&table, true, true).eq(a, b));
+        assert!(!Comparator::new(&table, false, true).eq(a, b));
+        assert!(!Comparator::new(&table, true, true).eq(a, c));
+        assert!(!Comparator::new(&table, true, true).eq(x_eq_z, y_eq_z));
+
+        let real = |table: &mut TermTable, n: &str, d: &str| {
+            table.add(Node::Real(n.to_owned(), d.to_owned()))
+        };
+        let half = real(&mut table, "1", "2");
+        let minus_half = real(&mut table, "-1", "2");
+        let (two, four) = (real(&mut table, "2", "1"), real(&mut table, "4", "1"));
+        let div = table.add(Node::Op("/".to_owned(), vec![two, four]));
+        let neg = table.add(Node::Op("-".to_owned(), vec![div]));
+        assert!(Comparator::new(&table, false, false).eq(half, div));
+        assert!(Comparator::new(&table, false, false).eq(neg, minus_half));
+        assert!(!Comparator::new(&table, false, false).eq(neg, half));
+    }
+}
@@ -0,0 +1,278 @@
+//! An independent verifier for the term traces produced by Carcara, using the `--term-trace`
+//! option of the `check` command. See the `carcara::term_trace` module for a description of the
+//! format.
+//!
+//! When checking a proof, Carcara relies on hash consing to compare terms by pointer, and on its
+//! implementations of equality modulo reordering of equalities, alpha-equivalence and
+//! capture-avoiding substitution. This crate replays a trace of these operations, and checks that:
+//!
+//! - no two structurally identical terms were constructed separately, so comparing terms by
+//!   pointer is the same as comparing them structurally;
+//! - every pair of terms that was considered equal by Carcara is in fact equal;
+//! - the result of every substitution is alpha-equivalent to the result of applying it again.
+//!
+//! Equality checks that failed in Carcara are not checked, since a wrong negative result can only
+//! make Carcara reject a proof. This crate has no dependencies, and is meant to be small enough to
+//! be audited on its own.
+
+mod equality;
+mod parser;
+mod substitution;
+mod term;
+
+use equality::Comparator;
+use parser::{EqualityKind, Line};
+use std::{collections::HashMap, fmt, io};
+use substitution::Substitution;
+use term::{TermId, TermTable};
+
+/// The first line of every trace.
+pub const HEADER: &str = "carcara-term-trace 1";
+
+/// Counts of the operations checked in a trace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// The number of terms defined in the trace.
+    pub terms: usize,
+
+    /// The number of checked equalities, that is, equality checks that succeeded in Carcara.
+    pub equalities: usize,
+
+    /// The number of equality checks that failed in Carcara, which are not checked.
+    pub skipped_equalities: usize,
+
+    /// The number of checked substitutions.
+    pub substitutions: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} terms, {} equalities ({} negative results skipped), {} substitutions",
+            self.terms, self.equalities, self.skipped_equalities, self.substitutions
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Io(io::Error),
+    MissingHeader,
+    Syntax(String),
+    UndefinedTerm(usize),
+    UnexpectedId { expected: usize, got: usize },
+    DuplicateTerm { id: usize, original: usize },
+    EqualityMismatch { a: usize, b: usize },
+    SubstitutionMismatch { term: usize, result: usize },
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::Io(e) => write!(f, "IO error: {}", e),
+            ErrorKind::MissingHeader => write!(f, "expected header `{}`", HEADER),
+            ErrorKind::Syntax(e) => write!(f, "syntax error: {}", e),
+            ErrorKind::UndefinedTerm(id) => write!(f, "term {} was not defined", id),
+            ErrorKind::UnexpectedId { expected, got } => {
+                write!(f, "expected term id {}, got {}", expected, got)
+            }
+            ErrorKind::DuplicateTerm { id, original } => {
+                write!(f, "term {} is identical to term {}", id, original)
+            }
+            ErrorKind::EqualityMismatch { a, b } => {
+                write!(
+                    f,
+                    "terms {} and {} were considered equal, but are not",
+                    a, b
+                )
+            }
+            ErrorKind::SubstitutionMismatch { term, result } => write!(
+                f,
+                "term {} is not the result of applying the substitution to term {}",
+                result, term
+            ),
+        }
+    }
+}
+
+/// An error found while verifying a trace, together with the line in which it was found.
+#[derive(Debug)]
+pub struct Error {
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Default)]
+struct Verifier {
+    table: TermTable,
+
+    /// The index in the term table of each term defined in the trace.
+    trace_ids: Vec<TermId>,
+
+    /// The id in the trace of each term in the table that was defined in the trace.
+    defined: HashMap<TermId, usize>,
+    summary: Summary,
+}
+
+impl Verifier {
+    fn line(&mut self, line: &str) -> Result<(), ErrorKind> {
+        match parser::parse_line(line, &self.trace_ids)? {
+            Line::Term(id, node) => {
+                let expected = self.trace_ids.len();
+                if id != expected {
+                    return Err(ErrorKind::UnexpectedId { expected, got: id });
+                }
+                if let Some(original) = self.table.lookup(&node).and_then(|t| self.defined.get(&t))
+                {
+                    return Err(ErrorKind::DuplicateTerm { id, original: *original });
+                }
+                let term = self.table.add(node);
+                self.trace_ids.push(term);
+                self.defined.insert(term, id);
+                self.summary.terms += 1;
+            }
+            Line::Equality { result: false, .. } => self.summary.skipped_equalities += 1,
+            Line::Equality { kind, a, b, result: true } => {
+                let alpha_equiv = kind == EqualityKind::Alpha;
+                if !Comparator::new(&self.table, true, alpha_equiv).eq(a, b) {
+                    let (a, b) = (self.defined[&a], self.defined[&b]);
+                    return Err(ErrorKind::EqualityMismatch { a, b });
+                }
+                self.summary.equalities += 1;
+            }
+            Line::Substitution { term, result, mappings } => {
+                let expected = Substitution::new(mappings).apply(&mut self.table, term);
+                if !Comparator::new(&self.table, false, true).eq(expected, result) {
+                    let (term, result) = (self.defined[&term], self.defined[&result]);
+                    return Err(ErrorKind::SubstitutionMismatch { term, result });
+                }
+                self.summary.substitutions += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Verifies a trace, returning a summary of the checked operations, or the first error found.
+pub fn verify(input: impl io::BufRead) -> Result<Summary, Error> {
+    let mut verifier = Verifier::default();
+    for (i, line) in input.lines().enumerate() {
+        let error = |kind| Error { line: i + 1, kind };
+        let line = line.map_err(|e| error(ErrorKind::Io(e)))?;
+        if i == 0 {
+            if line != HEADER {
+                return Err(error(ErrorKind::MissingHeader));
+            }
+            continue;
+        }
+        verifier.line(&line).map_err(error)?;
+    }
+    Ok(verifier.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(lines: &[&str]) -> Result<Summary, ErrorKind> {
+        let input = std::iter::once(HEADER)
+            .chain(lines.iter().copied())
+            .collect::<Vec<_>>()
+            .join("\n");
+        verify(input.as_bytes()).map_err(|e| e.kind)
+    }
+
+    const DEFINITIONS: &[&str] = &[
+        "t 0 sort Int",
+        r#"t 1 var "x" 0"#,
+        r#"t 2 var "y" 0"#,
+        r#"t 3 var "z" 0"#,
+        "t 4 op = 1 3",
+        "t 5 op = 3 1",
+        "t 6 op = 2 3",
+        r#"t 7 bind forall 1 "x" 0 4"#,
+        r#"t 8 bind forall 1 "y" 0 6"#,
+    ];
+
+    #[test]
+    fn test_valid() {
+        let lines = [
+            DEFINITIONS,
+            &["e polyeq 4 5 1", "e alpha 7 8 1", "e polyeq 7 8 0"],
+        ]
+        .concat();
+        let expected = Summary {
+            terms: 9,
+            equalities: 2,
+            skipped_equalities: 1,
+            substitutions: 0,
+        };
+        assert_eq!(run(&lines).unwrap(), expected);
+
+        // (= x z)[x -> z, z -> x] = (= z x)
+        let lines = [DEFINITIONS, &["s 4 5 2 1 3 3 1"]].concat();
+        assert_eq!(run(&lines).unwrap().substitutions, 1);
+
+        // (forall ((x Int)) (= x z))[z -> x] = (forall ((y Int)) (= y x)), after renaming
+        let lines = [
+            DEFINITIONS,
+            &[
+                "t 9 op = 2 1",
+                r#"t 10 bind forall 1 "y" 0 9"#,
+                "s 7 10 1 3 1",
+            ],
+        ]
+        .concat();
+        assert!(run(&lines).is_ok());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            verify("t 0 sort Int".as_bytes()).map_err(|e| e.kind),
+            Err(ErrorKind::MissingHeader)
+        ));
+        assert!(matches!(
+            run(&["t 0 sort Int", "t 2 sort Bool"]),
+            Err(ErrorKind::UnexpectedId { expected: 1, got: 2 })
+        ));
+        assert!(matches!(
+            run(&["t 0 sort Int", "t 1 sort Int"]),
+            Err(ErrorKind::DuplicateTerm { id: 1, original: 0 })
+        ));
+        assert!(matches!(
+            run(&["t 0 sort Int", "t 1 op - 2"]),
+            Err(ErrorKind::UndefinedTerm(2))
+        ));
+
+        let lines = [DEFINITIONS, &["e polyeq 7 8 1"]].concat();
+        assert!(matches!(
+            run(&lines),
+            Err(ErrorKind::EqualityMismatch { a: 7, b: 8 })
+        ));
+
+        // (forall ((x Int)) (= x z))[z -> x] is not (forall ((x Int)) (= x x))
+        let lines = [
+            DEFINITIONS,
+            &[
+                "t 9 op = 1 1",
+                r#"t 10 bind forall 1 "x" 0 9"#,
+                "s 7 10 1 3 1",
+            ],
+        ]
+        .concat();
+        assert!(matches!(
+            run(&lines),
+            Err(ErrorKind::SubstitutionMismatch { term: 7, result: 10 })
+        ));
+    }
+}
@@ -0,0 +1,33 @@
+use carcara_trace_checker::verify;
+use std::{env, fs::File, io, process::ExitCode};
+
+fn main() -> ExitCode {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let [path] = args.as_slice() else {
+        eprintln!("usage: carcara-trace-checker <trace file>");
+        eprintln!("if the file is `-`, the trace is read from stdin");
+        return ExitCode::from(2);
+    };
+
+    let result = if path == "-" {
+        verify(io::stdin().lock())
+    } else {
+        match File::open(path) {
+            Ok(f) => verify(io::BufReader::new(f)),
+            Err(e) => {
+                eprintln!("error: could not open `{}`: {}", path, e);
+                return ExitCode::from(2);
+            }
+        }
+    };
+    match result {
+        Ok(summary) => {
+            println!("valid: {}", summary);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("invalid: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{
+    term::{Node, TermId},
+    ErrorKind,
+};
+
+/// The kind of an equality check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqualityKind {
+    Polyeq,
+    Alpha,
+}
+
+/// An operation read from a trace. All term ids were already resolved to indices in the
+/// [`TermTable`](crate::term::TermTable).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Term(usize, Node),
+    Equality {
+        kind: EqualityKind,
+        a: TermId,
+        b: TermId,
+        result: bool,
+    },
+    Substitution {
+        term: TermId,
+        result: TermId,
+        mappings: Vec<(TermId, TermId)>,
+    },
+}
+
+/// Splits a line into tokens, separated by spaces. Spaces inside double quotes don't end a token,
+/// and quotes are kept in the tokens.
+fn tokenize(line: &str) -> Result<Vec<&str>, ErrorKind> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if in_quotes {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_quotes = false,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            ' ' => {
+                if let Some(s) = start.take() {
+                    tokens.push(&line[s..i]);
+                }
+            }
+            _ => {
+                start.get_or_insert(i);
+                in_quotes = c == '"';
+            }
+        }
+    }
+    if in_quotes {
+        return Err(ErrorKind::Syntax("unterminated string".to_owned()));
+    }
+    if let Some(s) = start {
+        tokens.push(&line[s..]);
+    }
+    Ok(tokens)
+}
+
+/// Reads a string between double quotes, replacing escape sequences.
+fn unquote(token: &str) -> Result<String, ErrorKind> {
+    let error = || ErrorKind::Syntax(format!("expected quoted string, got `{}`", token));
+    let inner = token
+        .strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .ok_or_else(error)?;
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('"') => result.push('"'),
+                Some('\\') => result.push('\\'),
+                Some('n') => result.push('\n'),
+                _ => return Err(error()),
+            },
+            '"' => return Err(error()),
+            c => result.push(c),
+        }
+    }
+    Ok(result)
+}
+
+/// Checks that a token is a decimal integer in canonical form, that is, with no leading zeros or
+/// plus sign, and no minus sign in zero.
+fn integer(token: &str, allow_negative: bool) -> Result<String, ErrorKind> {
+    let digits = match token.strip_prefix('-') {
+        Some(digits) if allow_negative && digits != "0" => digits,
+        _ => token,
+    };
+    let is_canonical = !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && (digits == "0" || !digits.starts_with('0'));
+    if is_canonical {
+        Ok(token.to_owned())
+    } else {
+        Err(ErrorKind::Syntax(format!("invalid number `{}`", token)))
+    }
+}
+
+struct LineParser<'a> {
+    tokens: std::vec::IntoIter<&'a str>,
+    trace_ids: &'a [TermId],
+}
+
+impl<'a> LineParser<'a> {
+    fn next(&mut self) -> Result<&'a str, ErrorKind> {
+        self.tokens
+            .next()
+            .ok_or_else(|| ErrorKind::Syntax("unexpected end of line".to_owned()))
+    }
+
+    fn number(&mut self) -> Result<usize, ErrorKind> {
+        let token = self.next()?;
+        token
+            .parse()
+            .map_err(|_| ErrorKind::Syntax(format!("expected number, got `{}`", token)))
+    }
+
+    fn term(&mut self) -> Result<TermId, ErrorKind> {
+        let id = self.number()?;
+        self.trace_ids
+            .get(id)
+            .copied()
+            .ok_or(ErrorKind::UndefinedTerm(id))
+    }
+
+    fn terms(&mut self, n: usize) -> Result<Vec<TermId>, ErrorKind> {
+        (0..n).map(|_| self.term()).collect()
+    }
+
+    fn remaining_terms(&mut self) -> Result<Vec<TermId>, ErrorKind> {
+        let n = self.tokens.len();
+        self.terms(n)
+    }
+
+    fn bindings(&mut self) -> Result<Vec<(String, TermId)>, ErrorKind> {
+        let n = self.number()?;
+        (0..n)
+            .map(|_| Ok((unquote(self.next()?)?, self.term()?)))
+            .collect()
+    }
+
+    fn end(&mut self) -> Result<(), ErrorKind> {
+        match self.tokens.next() {
+            Some(t) => Err(ErrorKind::Syntax(format!("unexpected token `{}`", t))),
+            None => Ok(()),
+        }
+    }
+
+    fn node(&mut self) -> Result<Node, ErrorKind> {
+        let node = match self.next()? {
+            "int" => Node::Int(integer(self.next()?, true)?),
+            "real" => Node::Real(integer(self.next()?, true)?, integer(self.next()?, false)?),
+            "str" => Node::Str(unquote(self.next()?)?),
+            "bv" => Node::BitVec(integer(self.next()?, false)?, integer(self.next()?, false)?),
+            "var" => Node::Var(unquote(self.next()?)?, self.term()?),
+            "app" => Node::App(self.term()?, self.remaining_terms()?),
+            "op" => Node::Op(self.next()?.to_owned(), self.remaining_terms()?),
+            "pop" => {
+                let op = self.next()?.to_owned();
+                let n = self.number()?;
+                let op_args = self.terms(n)?;
+                Node::ParamOp(op, op_args, self.remaining_terms()?)
+            }
+            "sort" => {
+                let head = self.next()?;
+                if let Some(name) = head.strip_prefix("Atom:") {
+                    unquote(name)?;
+                }
+                Node::Sort(head.to_owned(), self.remaining_terms()?)
+            }
+            "bind" => {
+                let binder = self.next()?.to_owned();
+                Node::Binder(binder, self.bindings()?, self.term()?)
+            }
+            "let" => Node::Let(self.bindings()?, self.term()?),
+            other => return Err(ErrorKind::Syntax(format!("unknown term kind `{}`", other))),
+        };
+        self.end()?;
+        Ok(node)
+    }
+}
+
+/// Parses a line of a trace, other than the header. `trace_ids` maps the ids of the terms already
+/// defined in the trace to their indices in the term table.
+pub fn parse_line(line: &str, trace_ids: &[TermId]) -> Result<Line, ErrorKind> {
+    let mut p = LineParser {
+        tokens: tokenize(line)?.into_iter(),
+        trace_ids,
+    };
+    let line = match p.next()? {
+        "t" => {
+            let id = p.number()?;
+            Line::Term(id, p.node()?)
+        }
+        "e" => {
+            let kind = match p.next()? {
+                "polyeq" => EqualityKind::Polyeq,
+                "alpha" => EqualityKind::Alpha,
+                other => {
+                    return Err(ErrorKind::Syntax(format!("unknown equality `{}`", other)));
+                }
+            };
+            let (a, b) = (p.term()?, p.term()?);
+            let result = match p.next()? {
+                "0" => false,
+                "1" => true,
+                other => return Err(ErrorKind::Syntax(format!("invalid result `{}`", other))),
+            };
+            p.end()?;
+            Line::Equality { kind, a, b, result }
+        }
+        "s" => {
+            let (term, result) = (p.term()?, p.term()?);
+            let n = p.number()?;
+            let mappings = (0..n)
+                .map(|_| Ok((p.term()?, p.term()?)))
+                .collect::<Result<_, ErrorKind>>()?;
+            p.end()?;
+            Line::Substitution { term, result, mappings }
+        }
+        other => return Err(ErrorKind::Syntax(format!("unknown operation `{}`", other))),
+    };
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("t 0  int 1").unwrap(), ["t", "0", "int", "1"]);
+        assert_eq!(
+            tokenize(r#"t 1 var "a b\" c" 0"#).unwrap(),
+            ["t", "1", "var", r#""a b\" c""#, "0"]
+        );
+        assert_eq!(
+            tokenize(r#"t 2 sort Atom:"my sort""#).unwrap(),
+            ["t", "2", "sort", r#"Atom:"my sort""#]
+        );
+        assert!(tokenize(r#"t 1 str "abc"#).is_err());
+    }
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(unquote(r#""a\"b\\c\nd""#).unwrap(), "a\"b\\c\nd");
+        assert!(unquote("abc").is_err());
+        assert!(unquote(r#""a"b""#).is_err());
+        assert!(unquote(r#""a\tb""#).is_err());
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let ids = [0, 1, 2];
+        assert_eq!(
+            parse_line("t 3 int -12", &ids).unwrap(),
+            Line::Term(3, Node::Int("-12".to_owned()))
+        );
+        assert_eq!(
+            parse_line(r#"t 3 bind forall 2 "x" 0 "y" 1 2"#, &ids).unwrap(),
+            Line::Term(
+                3,
+                Node::Binder(
+                    "forall".to_owned(),
+                    vec![("x".to_owned(), 0), ("y".to_owned(), 1)],
+                    2
+                )
+            )
+        );
+        assert_eq!(
+            parse_line("t 3 pop extract 2 0 1 2", &ids).unwrap(),
+            Line::Term(3, Node::ParamOp("extract".to_owned(), vec![0, 1], vec![2]))
+        );
+        assert_eq!(
+            parse_line("s 0 1 1 2 2", &ids).unwrap(),
+            Line::Substitution {
+                term: 0,
+                result: 1,
+                mappings: vec![(2, 2)]
+            }
+        );
+        assert!(matches!(
+            parse_line("e alpha 0 3 1", &ids),
+            Err(ErrorKind::UndefinedTerm(3))
+        ));
+        for invalid in [
+            "t 3 int 01",
+            "t 3 int -0",
+            "t 3 real 1 -2",
+            "e polyeq 0 1 2",
+            "s 0 1",
+        ] {
+            assert!(matches!(
+                parse_line(invalid, &ids),
+                Err(ErrorKind::Syntax(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::term::{Node, TermId, TermTable};
+use std::collections::{HashMap, HashSet};
+
+/// A capture-avoiding substitution, in which all mappings are applied simultaneously.
+///
+/// Like in Carcara, the sorts in the binding lists of binder terms and in variables, and the
+/// arguments of indexed operators, are not changed by the substitution.
+pub struct Substitution {
+    map: HashMap<TermId, TermId>,
+
+    /// The bound variables that were renamed to avoid capture, by name.
+    renaming: HashMap<String, String>,
+    cache: HashMap<TermId, TermId>,
+}
+
+impl Substitution {
+    pub fn new(mappings: impl IntoIterator<Item = (TermId, TermId)>) -> Self {
+        Self {
+            map: mappings.into_iter().collect(),
+            renaming: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn apply(&mut self, table: &mut TermTable, term: TermId) -> TermId {
+        if let Some(&t) = self.cache.get(&term) {
+            return t;
+        }
+        if let Some(&t) = self.map.get(&term) {
+            return t;
+        }
+        let result = match table.node(term).clone() {
+            Node::Int(_) | Node::Real(..) | Node::Str(_) | Node::BitVec(..) => term,
+            Node::Var(name, sort) => match self.renaming.get(&name) {
+                Some(new_name) => table.add(Node::Var(new_name.clone(), sort)),
+                None => term,
+            },
+            Node::App(f, args) => {
+                let f = self.apply(table, f);
+                let args = self.apply_all(table, &args);
+                table.add(Node::App(f, args))
+            }
+            Node::Op(op, args) => {
+                let args = self.apply_all(table, &args);
+                table.add(Node::Op(op, args))
+            }
+            Node::ParamOp(op, op_args, args) => {
+                let args = self.apply_all(table, &args);
+                table.add(Node::ParamOp(op, op_args, args))
+            }
+            Node::Sort(head, args) => {
+                let args = self.apply_all(table, &args);
+                table.add(Node::Sort(head, args))
+            }
+            Node::Binder(binder, bindings, body) => {
+                let (names, body) = self.apply_under_binder(table, &bindings, body);
+                let bindings = names.into_iter().zip(bindings.iter().map(|(_, s)| *s));
+                table.add(Node::Binder(binder, bindings.collect(), body))
+            }
+            Node::Let(bindings, body) => {
+                let values: Vec<_> = bindings
+                    .iter()
+                    .map(|(_, v)| self.apply(table, *v))
+                    .collect();
+                let (names, body) = self.apply_under_binder(table, &bindings, body);
+                table.add(Node::Let(names.into_iter().zip(values).collect(), body))
+            }
+        };
+        self.cache.insert(term, result);
+        result
+    }
+
+    fn apply_all(&mut self, table: &mut TermTable, terms: &[TermId]) -> Vec<TermId> {
+        terms.iter().map(|t| self.apply(table, *t)).collect()
+    }
+
+    /// Applies the substitution to the body of a binder term, renaming the bound variables that
+    /// would capture a free variable introduced by the substitution. Returns the new names of the
+    /// bound variables, and the new body.
+    fn apply_under_binder(
+        &self,
+        table: &mut TermTable,
+        bindings: &[(String, TermId)],
+        body: TermId,
+    ) -> (Vec<String>, TermId) {
+        let bound: HashSet<&String> = bindings.iter().map(|(name, _)| name).collect();
+
+        // Mappings whose domain mentions a bound variable don't apply inside the binder
+        let mut inner = Substitution::new(
+            self.map
+                .iter()
+                .filter(|(k, _)| table.free_names(**k).iter().all(|n| !bound.contains(n)))
+                .map(|(k, v)| (*k, *v)),
+        );
+        inner.renaming = (self.renaming.iter())
+            .filter(|(k, _)| !bound.contains(k))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        let mut capturing: HashSet<String> = inner.renaming.values().cloned().collect();
+        for value in inner.map.values() {
+            capturing.extend(table.free_names(*value).iter().cloned());
+        }
+        let mut taken = capturing.clone();
+        taken.extend(table.free_names(body).iter().cloned());
+        taken.extend(bound.iter().map(|name| (*name).clone()));
+
+        let names = bindings
+            .iter()
+            .map(|(name, _)| {
+                if !capturing.contains(name) {
+                    return name.clone();
+                }
+                let new_name = (0..)
+                    .map(|i| format!("{}@{}", name, i))
+                    .find(|n| !taken.contains(n))
+                    .unwrap();
+                taken.insert(new_name.clone());
+                inner.renaming.insert(name.clone(), new_name.clone());
+                new_name
+            })
+            .collect();
+        (names, inner.apply(table, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equality::Comparator;
+
+    #[test]
+    fn test_substitution() {
+        let mut table = TermTable::default();
+        let int = table.add(Node::Sort("Int".to_owned(), Vec::new()));
+        let x = table.add(Node::Var("x".to_owned(), int));
+        let y = table.add(Node::Var("y".to_owned(), int));
+        let plus = |table: &mut TermTable, a, b| table.add(Node::Op("+".to_owned(), vec![a, b]));
+        let forall = |table: &mut TermTable, x: &str, body| {
+            table.add(Node::Binder(
+                "forall".to_owned(),
+                vec![(x.to_owned(), int)],
+                body,
+            ))
+        };
+
+        // (+ x y)[x -> y] = (+ y y)
+        let x_plus_y = plus(&mut table, x, y);
+        let y_plus_y = plus(&mut table, y, y);
+        assert_eq!(
+            Substitution::new([(x, y)]).apply(&mut table, x_plus_y),
+            y_plus_y
+        );
+
+        // (forall ((x Int)) (+ x y))[x -> y] = (forall ((x Int)) (+ x y))
+        let term = forall(&mut table, "x", x_plus_y);
+        assert_eq!(Substitution::new([(x, y)]).apply(&mut table, term), term);
+
+        // (forall ((y Int)) (+ x y))[x -> y] = (forall ((y@0 Int)) (+ y y@0))
+        let term = forall(&mut table, "y", x_plus_y);
+        let result = Substitution::new([(x, y)]).apply(&mut table, term);
+        let y0 = table.add(Node::Var("y@0".to_owned(), int));
+        let y_plus_y0 = plus(&mut table, y, y0);
+        let expected = forall(&mut table, "y@0", y_plus_y0);
+        assert_eq!(result, expected);
+        let wrong = forall(&mut table, "y", y_plus_y);
+        assert!(!Comparator::new(&table, false, true).eq(result, wrong));
+
+        // The substitution is simultaneous: (+ x y)[x -> y, y -> x] = (+ y x)
+        let y_plus_x = plus(&mut table, y, x);
+        let mut swap = Substitution::new([(x, y), (y, x)]);
+        assert_eq!(swap.apply(&mut table, x_plus_y), y_plus_x);
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::{HashMap, HashSet};
+
+/// The index of a term in a [`TermTable`].
+pub type TermId = usize;
+
+/// A term node, whose children are given by their indices in the [`TermTable`].
+///
+/// Numbers are kept in the decimal representation used in the trace, which is canonical, so two
+/// nodes are structurally equal exactly when they are equal as Rust values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Node {
+    Int(String),
+    Real(String, String),
+    Str(String),
+    BitVec(String, String),
+    Var(String, TermId),
+    App(TermId, Vec<TermId>),
+    Op(String, Vec<TermId>),
+    ParamOp(String, Vec<TermId>, Vec<TermId>),
+    Sort(String, Vec<TermId>),
+    Binder(String, Vec<(String, TermId)>, TermId),
+    Let(Vec<(String, TermId)>, TermId),
+}
+
+impl Node {
+    /// Returns the children of the node. For binder terms, this includes the sorts of the bound
+    /// variables.
+    pub fn children(&self) -> Vec<TermId> {
+        match self {
+            Node::Int(_) | Node::Real(..) | Node::Str(_) | Node::BitVec(..) => Vec::new(),
+            Node::Var(_, sort) => vec![*sort],
+            Node::App(f, args) => std::iter::once(*f).chain(args.iter().copied()).collect(),
+            Node::Op(_, args) | Node::Sort(_, args) => args.clone(),
+            Node::ParamOp(_, op_args, args) => op_args.iter().chain(args).copied().collect(),
+            Node::Binder(_, bindings, body) | Node::Let(bindings, body) => bindings
+                .iter()
+                .map(|(_, t)| *t)
+                .chain(std::iter::once(*body))
+                .collect(),
+        }
+    }
+}
+
+/// A hash-consed table of terms. Adding the same node twice returns the same index.
+#[derive(Debug, Default)]
+pub struct TermTable {
+    nodes: Vec<Node>,
+    ids: HashMap<Node, TermId>,
+    free_names: HashMap<TermId, HashSet<String>>,
+}
+
+impl TermTable {
+    /// Adds a node to the table, and returns its index.
+    pub fn add(&mut self, node: Node) -> TermId {
+        if let Some(&id) = self.ids.get(&node) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(node.clone());
+        self.ids.insert(node, id);
+        id
+    }
+
+    /// Returns the index of a node, if it was already added to the table.
+    pub fn lookup(&self, node: &Node) -> Option<TermId> {
+        self.ids.get(node).copied()
+    }
+
+    pub fn node(&self, id: TermId) -> &Node {
+        &self.nodes[id]
+    }
+
+    /// Returns the names of the free variables of a term. Only the names of the variables are
+    /// considered, so two variables with the same name but different sorts are not distinguished.
+    pub fn free_names(&mut self, id: TermId) -> &HashSet<String> {
+        if !self.free_names.contains_key(&id) {
+            let names = match self.node(id).clone() {
+                Node::Var(name, _) => HashSet::from([name]),
+                Node::Binder(_, bindings, body) => {
+                    let mut names = self.free_names(body).clone();
+                    for (name, _) in &bindings {
+                        names.remove(name);
+                    }
+                    names
+                }
+                Node::Let(bindings, body) => {
+                    let mut names = self.free_names(body).clone();
+                    for (name, _) in &bindings {
+                        names.remove(name);
+                    }
+                    for (_, value) in &bindings {
+                        names.extend(self.free_names(*value).iter().cloned());
+                    }
+                    names
+                }
+                other => {
+                    let mut names = HashSet::new();
+                    for child in other.children() {
+                        names.extend(self.free_names(child).iter().cloned());
+                    }
+                    names
+                }
+            };
+            self.free_names.insert(id, names);
+        }
+        &self.free_names[&id]
+    }
+}
@@ -0,0 +1,59 @@
+//! Records the traces produced by Carcara while checking small proofs, and verifies them.
+
+use carcara::{
+    checker::{conformance, Config},
+    term_trace,
+};
+use std::{cell::RefCell, io, rc::Rc};
+
+/// A writer that appends to a buffer that can be read after the trace is recorded.
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn record_and_verify(definitions: &str, proof: &str) -> carcara_trace_checker::Summary {
+    let buffer = SharedBuffer::default();
+    term_trace::record(buffer.clone(), || {
+        // Invalid examples are also traced, so the result of checking is ignored
+        let _ = conformance::check_rule_example(definitions, proof, Config::new());
+    })
+    .unwrap();
+    let trace = buffer.0.borrow();
+    carcara_trace_checker::verify(trace.as_slice())
+        .unwrap_or_else(|e| panic!("invalid trace: {}\n{}", e, String::from_utf8_lossy(&trace)))
+}
+
+#[test]
+fn spec_examples() {
+    let examples =
+        conformance::parse_examples(include_str!("../../carcara/tests/spec_examples.txt"))
+            .expect("failed to parse specification examples");
+    for example in examples {
+        let summary = record_and_verify(&example.definitions, &example.proof);
+        assert!(summary.terms > 0, "{}", example.name);
+    }
+}
+
+#[test]
+fn capture_avoiding_substitution() {
+    let definitions = "
+        (declare-fun p (Int Int) Bool)
+        (declare-const y Int)
+    ";
+    let proof = "
+        (step t1 (cl (or (not (forall ((x Int)) (exists ((y Int)) (p x y))))
+            (exists ((z Int)) (p y z)))) :rule forall_inst :args (y))
+    ";
+    let summary = record_and_verify(definitions, proof);
+    assert!(summary.substitutions > 0);
+    assert!(summary.equalities > 0);
+}
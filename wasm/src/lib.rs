@@ -0,0 +1,8 @@
+//! The WebAssembly build of Carcara. This crate only re-exports the bindings in [`carcara::wasm`],
+//! and exists so they can be compiled into a `cdylib`, for example with:
+//!
+//! ```text
+//! wasm-pack build wasm --target web
+//! ```
+
+pub use carcara::wasm::*;